@@ -0,0 +1,64 @@
+//! Scoped transaction handle for Rust callers.
+
+use crate::{
+    api::{db::Database, errors::DbError},
+    execution::errors::ExecutionResult,
+    txn::transaction::TxnId,
+};
+
+/// An explicit transaction on a `Database`. Every statement run through it
+/// commits or rolls back together. Dropping the handle without calling
+/// `commit` rolls the transaction back.
+pub struct Transaction<'db> {
+    db: &'db mut Database,
+    finished: bool,
+}
+
+impl Database {
+    /// Begin an explicit transaction and return a handle to it.
+    pub fn transaction(&mut self) -> Result<Transaction<'_>, DbError> {
+        self.begin()?;
+        Ok(Transaction {
+            db: self,
+            finished: false,
+        })
+    }
+}
+
+impl Transaction<'_> {
+    pub fn id(&self) -> TxnId {
+        self.db.transaction_id().unwrap_or(TxnId::NONE)
+    }
+
+    pub fn execute(&mut self, sql: &str) -> Result<ExecutionResult, DbError> {
+        self.db.execute(sql)
+    }
+
+    pub fn savepoint(&mut self, name: &str) -> Result<(), DbError> {
+        self.db.savepoint(name).map(|_| ())
+    }
+
+    pub fn rollback_to(&mut self, name: &str) -> Result<(), DbError> {
+        self.db.rollback_to(name).map(|_| ())
+    }
+
+    pub fn commit(mut self) -> Result<(), DbError> {
+        self.finished = true;
+        self.db.commit().map(|_| ())
+    }
+
+    pub fn rollback(mut self) -> Result<(), DbError> {
+        self.finished = true;
+        self.db.rollback().map(|_| ())
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        // A statement may have ended the transaction itself (COMMIT or
+        // ROLLBACK run through `execute`).
+        if !self.finished && self.db.transaction_id().is_some() {
+            let _ = self.db.rollback();
+        }
+    }
+}
@@ -1,37 +1,257 @@
-use std::{path::PathBuf, sync::Mutex};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+};
 
 use crate::{
-    api::errors::DbError,
-    binder::bind_stmt::Binder,
-    catalog::catalog::Catalog,
+    api::errors::{DbError, TransactionAction, TransactionResult},
+    binder::{bind_stmt::Binder, bound::BoundStatement},
+    catalog::{catalog::Catalog, ids::TableId, persist::redo_lsn},
     execution::{
-        context::ExecutionContext,
+        context::{DEFAULT_WORK_MEM, ExecutionContext},
+        ddl::execute_ddl,
         engine::{execute_mutation, execute_query},
-        errors::ExecutionResult,
+        errors::{ExecutionError, ExecutionResult},
+    },
+    frontend::sql::{
+        ast::{Statement, TransactionStmt},
+        parser::Parser,
     },
-    frontend::sql::parser::Parser,
+    functions::{
+        aggregate::AggregateFunction,
+        errors::FunctionError,
+        registry::FunctionRegistry,
+        scalar::{FunctionResult, ScalarFunction, Signature, Volatility},
+    },
+    ir::plan::LogicalPlan,
     optimizer::optimize,
-    planner::logical::LogicalPlanner,
+    planner::{logical::LogicalPlanner, physical::resolve_columns},
     storage::{
         buffer::pool::{BufferPool, BufferPoolHandle},
-        pagemgr::file::FilePageManager,
+        manager::StorageManager,
+        pagemgr::{file::FilePageManager, manager::PageManager},
+    },
+    txn::{
+        errors::TransactionError,
+        lock_manager::{LockMode, LockTarget},
+        recovery::{Recovery, rollback, rollback_to},
+        transaction::{Transaction, TxnId},
+        wal::writer::{WalHandle, WalWriter},
     },
+    types::value::Value,
 };
 
+/// Engine state shared by every connection to one database.
+struct Shared {
+    /// Current schema. A statement works with the version it was bound
+    /// against; DDL installs a modified copy, and a statement that locks a
+    /// table only after that is bound again.
+    catalog: RwLock<Arc<Catalog>>,
+    /// Functions statements may call: the built-in ones and those the
+    /// application registered. Like the catalog, a registration installs a
+    /// modified copy.
+    functions: RwLock<Arc<FunctionRegistry>>,
+    /// Serializes schema changes and checkpoints, which both replace the
+    /// catalog.
+    ddl: Mutex<()>,
+    storage: StorageManager,
+    wal: WalHandle,
+}
+
+/// A connection to a database. Further connections to the same database
+/// are opened with `connect`; each has its own transaction and can be moved
+/// to its own thread.
 pub struct Database {
-    catalog: Catalog,
-    buffer_pool: BufferPoolHandle,
+    shared: Arc<Shared>,
+    /// Explicit transaction opened with BEGIN, if any. Without one each
+    /// statement commits on its own.
+    txn: Option<Transaction>,
+    /// Bytes a query operator may buffer before spilling to disk.
+    work_mem: usize,
 }
 
 impl Database {
     pub fn new(path: String) -> Result<Self, DbError> {
-        let pm = FilePageManager::open(&PathBuf::from(path))?;
+        let path = PathBuf::from(path);
+        let pm = FilePageManager::open(&path)?;
+        Self::with_page_manager(&path, Box::new(pm))
+    }
+
+    /// Open the database at `path` with pages served by `pm`. Crash tests
+    /// use this to inject faults into page writes.
+    ///
+    /// Recovery runs before the database is returned, so only the effects
+    /// of committed transactions are visible.
+    pub fn with_page_manager(path: &Path, pm: Box<dyn PageManager>) -> Result<Self, DbError> {
+        let wal = Arc::new(Mutex::new(WalWriter::open(&Self::wal_dir(path))?));
+
+        let mut pool = BufferPool::new(pm);
+        pool.attach_wal(wal.clone());
+        let buffer_pool = BufferPoolHandle::new(Mutex::new(pool));
+
+        let recovery = Recovery::analyze(&Self::wal_dir(path), redo_lsn(&buffer_pool)?)?;
+        recovery.redo(&buffer_pool)?;
+        let catalog = Catalog::load(&buffer_pool)?;
+        recovery.finish(&catalog, &buffer_pool, &wal)?;
+
+        let shared = Shared {
+            catalog: RwLock::new(Arc::new(catalog)),
+            functions: RwLock::new(Arc::new(FunctionRegistry::builtins().clone())),
+            ddl: Mutex::new(()),
+            storage: StorageManager::new(buffer_pool),
+            wal,
+        };
         Ok(Self {
-            catalog: Catalog::new(),
-            buffer_pool: BufferPoolHandle::new(Mutex::new(BufferPool::new(Box::new(pm)))),
+            shared: Arc::new(shared),
+            txn: None,
+            work_mem: DEFAULT_WORK_MEM,
         })
     }
 
+    /// Set how many bytes a query operator, such as the build side of a hash
+    /// join, may hold in memory before spilling to disk. Applies to this
+    /// connection only.
+    pub fn set_work_mem(&mut self, bytes: usize) {
+        self.work_mem = bytes;
+    }
+
+    /// Open another connection to this database. The database is
+    /// checkpointed and closed once the last connection is dropped.
+    pub fn connect(&self) -> Database {
+        Database {
+            shared: self.shared.clone(),
+            txn: None,
+            work_mem: DEFAULT_WORK_MEM,
+        }
+    }
+
+    /// Directory holding the write-ahead log of the database at `path`.
+    pub fn wal_dir(path: &Path) -> PathBuf {
+        let mut dir = path.as_os_str().to_owned();
+        dir.push("-wal");
+        PathBuf::from(dir)
+    }
+
+    pub fn catalog(&self) -> Arc<Catalog> {
+        self.shared.catalog()
+    }
+
+    /// Add a scalar function that statements on every connection to this
+    /// database may call, until it is closed. Like the built-in functions,
+    /// it may have several versions taking different arguments, and
+    /// returns NULL whenever an argument is NULL. A deterministic function
+    /// called on constants is called once, while the statement is planned.
+    pub fn register_scalar_fn(
+        &self,
+        name: &str,
+        signature: Signature,
+        volatility: Volatility,
+        body: impl Fn(&[Value]) -> FunctionResult + Send + Sync + 'static,
+    ) {
+        let function = ScalarFunction::new(name, signature, body).with_volatility(volatility);
+        self.shared
+            .register(|functions| functions.add_scalar(function));
+    }
+
+    /// Add an aggregate function of one argument that statements on every
+    /// connection to this database may call, until it is closed. Its state
+    /// over each group starts as `init` returns it, takes in every non-NULL
+    /// value with `update`, and becomes the result with `finalize`. `merge`
+    /// combines the states of two parts of one group; the hash aggregate
+    /// keeps a single state per group, so it does not call it yet.
+    pub fn register_aggregate_fn<S: Send + 'static>(
+        &self,
+        name: &str,
+        signature: Signature,
+        init: impl Fn() -> S + Send + Sync + 'static,
+        update: impl Fn(&mut S, &Value) -> Result<(), FunctionError> + Send + Sync + 'static,
+        merge: impl Fn(&mut S, S) -> Result<(), FunctionError> + Send + Sync + 'static,
+        finalize: impl Fn(S) -> FunctionResult + Send + Sync + 'static,
+    ) {
+        let function = AggregateFunction::new(name, signature, init, update, merge, finalize);
+        self.shared
+            .register(|functions| functions.add_aggregate(function));
+    }
+
+    /// Id of the explicit transaction in progress, if any.
+    pub fn transaction_id(&self) -> Option<TxnId> {
+        self.txn.as_ref().map(Transaction::id)
+    }
+
+    /// Open an explicit transaction. Statements run until `commit` or
+    /// `rollback` are applied atomically.
+    pub fn begin(&mut self) -> Result<TxnId, DbError> {
+        if let Some(txn) = &self.txn {
+            return Err(TransactionError::AlreadyActive(txn.id()).into());
+        }
+        let id = self.shared.wal.lock().unwrap().begin()?;
+        let snapshot = self.shared.storage.snapshot(id);
+        self.txn = Some(Transaction::new(id, snapshot));
+        Ok(id)
+    }
+
+    /// Commit the explicit transaction; it is durable once this returns.
+    pub fn commit(&mut self) -> Result<TxnId, DbError> {
+        let txn = self.txn.take().ok_or(TransactionError::NotActive)?.id();
+        self.shared.wal.lock().unwrap().commit(txn)?;
+        self.shared.finish(txn);
+        Ok(txn)
+    }
+
+    /// Undo everything the explicit transaction did and end it.
+    pub fn rollback(&mut self) -> Result<TxnId, DbError> {
+        let txn = self.txn.take().ok_or(TransactionError::NotActive)?.id();
+        self.shared.rollback(txn)?;
+        Ok(txn)
+    }
+
+    /// Mark the current point of the explicit transaction so that later
+    /// changes can be undone with `rollback_to`.
+    pub fn savepoint(&mut self, name: &str) -> Result<TxnId, DbError> {
+        let txn = self.txn.as_mut().ok_or(TransactionError::NotActive)?;
+        let lsn = self.shared.wal.lock().unwrap().txn_last_lsn(txn.id());
+        txn.add_savepoint(name, lsn);
+        Ok(txn.id())
+    }
+
+    /// Undo the changes made since savepoint `name`. The transaction and
+    /// the savepoint itself stay in place.
+    pub fn rollback_to(&mut self, name: &str) -> Result<TxnId, DbError> {
+        let txn = self.txn.as_mut().ok_or(TransactionError::NotActive)?;
+        let lsn = txn
+            .rewind_to(name)
+            .ok_or_else(|| TransactionError::UnknownSavepoint(name.to_string()))?;
+        let shared = &self.shared;
+        rollback_to(
+            txn.id(),
+            lsn,
+            &shared.catalog(),
+            shared.storage.buffer_pool(),
+            &shared.wal,
+        )?;
+        Ok(txn.id())
+    }
+
+    fn execute_transaction(&mut self, stmt: TransactionStmt) -> Result<ExecutionResult, DbError> {
+        let (action, txn_id) = match stmt {
+            TransactionStmt::Begin => (TransactionAction::Begin, self.begin()?),
+            TransactionStmt::Commit => (TransactionAction::Commit, self.commit()?),
+            TransactionStmt::Rollback => (TransactionAction::Rollback, self.rollback()?),
+            TransactionStmt::Savepoint(name) => {
+                let txn_id = self.savepoint(&name)?;
+                (TransactionAction::Savepoint(name), txn_id)
+            }
+            TransactionStmt::RollbackTo(name) => {
+                let txn_id = self.rollback_to(&name)?;
+                (TransactionAction::RollbackTo(name), txn_id)
+            }
+        };
+        Ok(ExecutionResult::Transaction(TransactionResult {
+            action,
+            txn_id,
+        }))
+    }
+
     /// Execute a SQL statement
     pub fn execute(&mut self, query: &str) -> Result<ExecutionResult, DbError> {
         //
@@ -42,40 +262,272 @@ impl Database {
         let mut result: Option<ExecutionResult> = None;
 
         for stmt in stmts {
-            // -------------------------
-            // 1. Bind
-            // -------------------------
-            let binder = Binder::new(&self.catalog);
-            let bound = binder.bind_statement(stmt)?;
-
-            // -------------------------
-            // 2. Plan
-            // -------------------------
-            let planner = LogicalPlanner::new();
-            let logical = planner.plan(bound)?;
-
-            // -------------------------
-            // 3. Optimize
-            // -------------------------
-            let optimized = optimize(&logical, &self.catalog)?;
-
-            // -------------------------
-            // 4. Execute
-            // -------------------------
-            let mut ctx = ExecutionContext::new(&self.catalog, &self.buffer_pool);
-
-            let exec_result = match optimized {
-                crate::ir::plan::LogicalPlan::Insert { .. }
-                | crate::ir::plan::LogicalPlan::Update { .. }
-                | crate::ir::plan::LogicalPlan::Delete { .. } => {
-                    execute_mutation(optimized, &mut ctx)?
+            // A statement whose catalog a schema change replaced before it
+            // locked its tables has been undone, and is bound again.
+            result = Some(loop {
+                match self.execute_statement(stmt.clone()) {
+                    Err(DbError::Execution(ExecutionError::SchemaChanged)) => continue,
+                    r => break r?,
                 }
+            });
+        }
+        result.ok_or(DbError::EmptyQuery)
+    }
+
+    fn execute_statement(&mut self, stmt: Statement) -> Result<ExecutionResult, DbError> {
+        // -------------------------
+        // 1. Bind
+        // -------------------------
+        let catalog = self.catalog();
+        let functions = self.shared.functions();
+        let binder = Binder::with_functions(&catalog, &functions);
+        let bound = binder.bind_statement(stmt)?;
+
+        if let BoundStatement::Transaction(stmt) = bound {
+            return self.execute_transaction(stmt);
+        }
+
+        // DDL bypasses planning and is applied to the catalog directly.
+        if matches!(
+            bound,
+            BoundStatement::CreateTable(_)
+                | BoundStatement::DropTable(_)
+                | BoundStatement::CreateIndex(_)
+                | BoundStatement::DropIndex(_)
+        ) {
+            if self.txn.is_some() {
+                return Err(TransactionError::DdlInTransaction.into());
+            }
+            return self.shared.execute_ddl(bound);
+        }
+
+        // -------------------------
+        // 2. Plan
+        // -------------------------
+        let planner = LogicalPlanner::new();
+        let logical = planner.plan(bound)?;
+
+        // -------------------------
+        // 3. Optimize
+        // -------------------------
+        let optimized = optimize(&logical, &catalog)?;
 
-                _ => execute_query(optimized, &mut ctx)?,
+        // -------------------------
+        // 4. Lower column references to row positions
+        // -------------------------
+        let physical = resolve_columns(optimized, &catalog)?;
+
+        // -------------------------
+        // 5. Execute
+        // -------------------------
+        let exec_result = match physical {
+            LogicalPlan::Insert { .. }
+            | LogicalPlan::Update { .. }
+            | LogicalPlan::Delete { .. } => self.execute_mutation(&catalog, physical),
+
+            _ => self.execute_query(&catalog, physical),
+        };
+
+        // A deadlock victim loses its whole transaction, so that the
+        // locks it holds are released.
+        if let Err(DbError::Deadlock(_)) = exec_result
+            && self.txn.is_some()
+        {
+            self.rollback()?;
+        }
+        exec_result
+    }
+
+    /// Run a query. Outside an explicit transaction its locks are owned by a
+    /// fresh id and released as soon as it finishes.
+    fn execute_query(
+        &mut self,
+        catalog: &Catalog,
+        plan: LogicalPlan,
+    ) -> Result<ExecutionResult, DbError> {
+        let storage = &self.shared.storage;
+        if let Some(txn) = &self.txn {
+            let mut ctx = ExecutionContext::new(catalog, storage, txn.id(), txn.snapshot().clone());
+            ctx.installed = Some(&self.shared.catalog);
+            ctx.work_mem = self.work_mem;
+            return Ok(execute_query(plan, &mut ctx)?);
+        }
+
+        let owner = self.shared.wal.lock().unwrap().allocate_txn_id();
+        let snapshot = storage.snapshot(TxnId::NONE);
+        let mut ctx = ExecutionContext::new(catalog, storage, owner, snapshot);
+        ctx.installed = Some(&self.shared.catalog);
+        ctx.work_mem = self.work_mem;
+        let result = execute_query(plan, &mut ctx);
+        storage.locks().unlock_all(owner);
+        Ok(result?)
+    }
+
+    /// Run INSERT/UPDATE/DELETE. Inside an explicit transaction a failing
+    /// statement is undone on its own and the transaction stays open;
+    /// otherwise the statement is its own transaction and is durable once
+    /// its commit record is forced.
+    fn execute_mutation(
+        &mut self,
+        catalog: &Catalog,
+        plan: LogicalPlan,
+    ) -> Result<ExecutionResult, DbError> {
+        let Shared { storage, wal, .. } = &*self.shared;
+        let bp = storage.buffer_pool();
+
+        if let Some(txn) = &self.txn {
+            let start = wal.lock().unwrap().txn_last_lsn(txn.id());
+            let snapshot = txn.snapshot().clone();
+            let mut ctx = ExecutionContext::new(catalog, storage, txn.id(), snapshot);
+            ctx.installed = Some(&self.shared.catalog);
+            return match execute_mutation(plan, &mut ctx) {
+                Ok(r) => Ok(r),
+                Err(e) => {
+                    rollback_to(txn.id(), start, catalog, bp, wal)?;
+                    Err(e.into())
+                }
             };
+        }
 
-            result = Some(exec_result);
+        let txn = wal.lock().unwrap().begin()?;
+        let snapshot = storage.snapshot(txn);
+        let mut ctx = ExecutionContext::new(catalog, storage, txn, snapshot);
+        ctx.installed = Some(&self.shared.catalog);
+        let result = match execute_mutation(plan, &mut ctx) {
+            Ok(r) => {
+                wal.lock().unwrap().commit(txn)?;
+                Ok(r)
+            }
+            Err(e) => {
+                rollback(txn, catalog, bp, wal)?;
+                Err(e.into())
+            }
+        };
+        drop(ctx);
+        self.shared.finish(txn);
+        result
+    }
+
+    /// Write all dirty pages to disk and fsync the database file.
+    pub fn flush(&self) -> Result<(), DbError> {
+        Ok(self.shared.storage.flush()?)
+    }
+
+    /// Flush every page, persist the catalog, log a checkpoint and drop log
+    /// segments that are no longer needed to recover.
+    pub fn checkpoint(&self) -> Result<(), DbError> {
+        self.shared.checkpoint()
+    }
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        // An explicit transaction that was never committed is discarded.
+        if self.txn.is_some() {
+            let _ = self.rollback();
         }
-        result.ok_or(DbError::EmptyQuery)
+    }
+}
+
+impl Shared {
+    fn catalog(&self) -> Arc<Catalog> {
+        self.catalog.read().unwrap().clone()
+    }
+
+    fn functions(&self) -> Arc<FunctionRegistry> {
+        self.functions.read().unwrap().clone()
+    }
+
+    /// Install a copy of the function registry changed by `add`. Statements
+    /// already bound keep the functions they found.
+    fn register(&self, add: impl FnOnce(&mut FunctionRegistry)) {
+        let mut functions = self.functions.write().unwrap();
+        add(Arc::make_mut(&mut functions));
+    }
+
+    /// Release what the finished transaction `txn` held on to.
+    fn finish(&self, txn: TxnId) {
+        self.storage.locks().unlock_all(txn);
+        self.storage.prune_versions();
+    }
+
+    fn rollback(&self, txn: TxnId) -> Result<(), DbError> {
+        let bp = self.storage.buffer_pool();
+        rollback(txn, &self.catalog(), bp, &self.wal)?;
+        self.finish(txn);
+        Ok(())
+    }
+
+    /// Run a schema change. Only one runs at a time; the table locks it
+    /// takes are owned by a fresh id and released once it is done.
+    fn execute_ddl(&self, bound: BoundStatement) -> Result<ExecutionResult, DbError> {
+        let _ddl = self.ddl.lock().unwrap();
+        let owner = self.wal.lock().unwrap().allocate_txn_id();
+        let result = self.apply_ddl(owner, bound);
+        self.storage.locks().unlock_all(owner);
+        result
+    }
+
+    /// Apply a schema change to a copy of the catalog and install the copy
+    /// once it is persisted. The table is locked first, so no running
+    /// transaction has it changed underneath it.
+    fn apply_ddl(&self, owner: TxnId, bound: BoundStatement) -> Result<ExecutionResult, DbError> {
+        let mut catalog = Catalog::clone(&self.catalog());
+        if let Some((table, mode)) = ddl_lock(&bound, &catalog) {
+            self.storage
+                .locks()
+                .lock(owner, LockTarget::Table(table), mode)?;
+        }
+
+        let mut freed = Vec::new();
+        let result = execute_ddl(bound, &mut catalog, &self.storage, &mut freed)?;
+        // DDL itself is not logged: it becomes durable with the catalog
+        // image the checkpoint writes. Dropped objects keep their pages
+        // until then, as the previous image still refers to them.
+        self.write_checkpoint(&mut catalog)?;
+        self.storage.free_pages(&freed)?;
+        *self.catalog.write().unwrap() = Arc::new(catalog);
+        Ok(result)
+    }
+
+    fn checkpoint(&self) -> Result<(), DbError> {
+        let _ddl = self.ddl.lock().unwrap();
+        let mut catalog = Catalog::clone(&self.catalog());
+        self.write_checkpoint(&mut catalog)?;
+        *self.catalog.write().unwrap() = Arc::new(catalog);
+        Ok(())
+    }
+
+    /// Persist `catalog` and log a checkpoint. The buffer pool stays locked
+    /// with no page latched for writing, so no page changes between the
+    /// flush and the checkpoint record that vouches for it.
+    fn write_checkpoint(&self, catalog: &mut Catalog) -> Result<(), DbError> {
+        let mut pool = BufferPool::lock_quiesced(self.storage.buffer_pool());
+        catalog.persist_locked(&mut pool)?;
+        let mut wal = self.wal.lock().unwrap();
+        let lsn = wal.checkpoint(Vec::new())?;
+        if wal.active_txns().is_empty() {
+            wal.truncate_before(lsn)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        let _ = self.checkpoint();
+    }
+}
+
+/// Table lock a schema change needs: building an index must not miss
+/// concurrent writes, and dropping must wait for every user.
+fn ddl_lock(bound: &BoundStatement, catalog: &Catalog) -> Option<(TableId, LockMode)> {
+    match bound {
+        BoundStatement::CreateIndex(s) => Some((s.table_id, LockMode::Shared)),
+        BoundStatement::DropTable(s) => Some((s.table_id, LockMode::Exclusive)),
+        BoundStatement::DropIndex(s) => catalog
+            .get_index_by_id(s.index_id)
+            .map(|entry| (entry.meta.table_id, LockMode::Exclusive)),
+        _ => None,
     }
 }
@@ -1,5 +1,6 @@
 use crate::{
     binder::errors::BindError,
+    catalog::errors::CatalogError,
     execution::{
         errors::{ExecutionError, ExecutionStats, TableMutationStats},
         executor::Row,
@@ -8,13 +9,17 @@ use crate::{
     optimizer::errors::OptimizerError,
     planner::errors::PlanError,
     storage::errors::StorageError,
-    types::schema::Schema,
+    txn::{
+        errors::{LockError, TransactionError},
+        transaction::TxnId,
+    },
+    types::schema::OutputSchema,
 };
 
 #[derive(Debug)]
 pub struct QueryResult {
-    /// Column names and types (post-projection)
-    pub schema: Schema,
+    /// Column names, types and sources (post-projection)
+    pub schema: OutputSchema,
 
     /// Materialized rows (phase 1)
     pub rows: Vec<Row>,
@@ -59,6 +64,22 @@ pub enum DefinitionAction {
     DropIndex,
 }
 
+#[derive(Debug)]
+pub struct TransactionResult {
+    pub action: TransactionAction,
+    /// Transaction the statement applied to.
+    pub txn_id: TxnId,
+}
+
+#[derive(Debug)]
+pub enum TransactionAction {
+    Begin,
+    Commit,
+    Rollback,
+    Savepoint(String),
+    RollbackTo(String),
+}
+
 #[derive(Debug)]
 pub enum DbError {
     Parse(ParseError),
@@ -67,6 +88,11 @@ pub enum DbError {
     Optimize(OptimizerError),
     Execution(ExecutionError),
     Storage(StorageError),
+    Catalog(CatalogError),
+    Transaction(TransactionError),
+    /// The statement was chosen as a deadlock victim and its transaction
+    /// was rolled back.
+    Deadlock(TxnId),
     EmptyQuery,
 }
 
@@ -79,6 +105,13 @@ impl std::fmt::Display for DbError {
             DbError::Optimize(e) => write!(f, "optimizer error: {e}"),
             DbError::Execution(e) => write!(f, "execution error: {e}"),
             DbError::Storage(e) => write!(f, "storage error: {e}"),
+            DbError::Catalog(e) => write!(f, "catalog error: {e}"),
+            DbError::Transaction(e) => write!(f, "transaction error: {e}"),
+            DbError::Deadlock(txn) => write!(
+                f,
+                "deadlock detected; transaction {} was rolled back",
+                txn.0
+            ),
             DbError::EmptyQuery => write!(f, "Empty Query String"),
         }
     }
@@ -112,7 +145,18 @@ impl From<OptimizerError> for DbError {
 
 impl From<ExecutionError> for DbError {
     fn from(e: ExecutionError) -> Self {
-        DbError::Execution(e)
+        match e {
+            ExecutionError::Lock(e) => e.into(),
+            e => DbError::Execution(e),
+        }
+    }
+}
+
+impl From<LockError> for DbError {
+    fn from(e: LockError) -> Self {
+        match e {
+            LockError::Deadlock(txn) => DbError::Deadlock(txn),
+        }
     }
 }
 
@@ -121,3 +165,15 @@ impl From<StorageError> for DbError {
         DbError::Storage(e)
     }
 }
+
+impl From<CatalogError> for DbError {
+    fn from(e: CatalogError) -> Self {
+        DbError::Catalog(e)
+    }
+}
+
+impl From<TransactionError> for DbError {
+    fn from(e: TransactionError) -> Self {
+        DbError::Transaction(e)
+    }
+}
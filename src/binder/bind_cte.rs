@@ -0,0 +1,241 @@
+//! Common table expression binding.
+//!
+//! A CTE is bound once, where it is defined, and read as if it were a
+//! table. Every reference to it in FROM gets column ids of its own, so a
+//! CTE can be joined with itself.
+
+use std::cell::Cell;
+
+use crate::binder::bind_expr::coerce_output;
+use crate::binder::bind_set_op::merge_columns;
+use crate::binder::bind_stmt::Binder;
+use crate::binder::bound::{BoundCte, BoundFrom, BoundRecursiveTerm};
+use crate::binder::errors::BindError;
+use crate::binder::scope::ColumnScope;
+use crate::catalog::ids::ColumnId;
+use crate::frontend::sql::ast::{Cte, Query, QueryBody, SetOperator, WithClause};
+use crate::ir::plan::FromId;
+use crate::types::datatype::DataType;
+use crate::types::schema::OutputColumn;
+
+/// A CTE visible under `name`.
+#[derive(Debug)]
+pub(crate) struct CteBinding {
+    name: String,
+    columns: Vec<OutputColumn>,
+    source: CteSource,
+}
+
+#[derive(Debug)]
+enum CteSource {
+    Query(Box<BoundCte>),
+    /// The recursive CTE being bound, as its recursive term sees it, and
+    /// whether the term read it.
+    WorkTable(usize, Cell<bool>),
+}
+
+impl<'a> Binder<'a> {
+    /// Bind the CTEs of `with` and make them visible, in order, so each
+    /// can read the ones before it.
+    pub(crate) fn bind_with(&self, with: WithClause) -> Result<(), BindError> {
+        let first = self.ctes.borrow().len();
+        for cte in with.ctes {
+            if self.ctes.borrow()[first..]
+                .iter()
+                .any(|c| c.name == cte.name)
+            {
+                return Err(BindError::DuplicateCte(cte.name));
+            }
+            let binding = match self.bind_recursive_cte(&cte, with.recursive)? {
+                Some(binding) => binding,
+                None => self.bind_cte(cte)?,
+            };
+            self.ctes.borrow_mut().push(binding);
+        }
+        Ok(())
+    }
+
+    fn bind_cte(&self, cte: Cte) -> Result<CteBinding, BindError> {
+        let query = self.bind_query(*cte.query, ColumnScope::new())?.0;
+        let columns = rename(query.output.clone(), cte.columns)?;
+        Ok(CteBinding {
+            name: cte.name,
+            columns,
+            source: CteSource::Query(Box::new(BoundCte {
+                query,
+                recursive: None,
+            })),
+        })
+    }
+
+    /// Bind `anchor UNION [ALL] term` where `term` reads the CTE, or return
+    /// `None` if `cte` is not of that form.
+    fn bind_recursive_cte(
+        &self,
+        cte: &Cte,
+        recursive: bool,
+    ) -> Result<Option<CteBinding>, BindError> {
+        let Query {
+            with: None,
+            body:
+                QueryBody::SetOp {
+                    op: SetOperator::Union,
+                    all,
+                    left,
+                    right,
+                },
+            order_by,
+            limit: None,
+            offset: None,
+        } = &*cte.query
+        else {
+            return Ok(None);
+        };
+        if !recursive || !order_by.is_empty() {
+            return Ok(None);
+        }
+
+        let mut query = self.bind_operand((**left).clone(), ColumnScope::new())?.0;
+        let mut columns = rename(query.output.clone(), cte.columns.clone())?;
+
+        // The recursive term reads the CTE through its work table, which
+        // may hold rows of either term.
+        let id = self.recursive_ctes.get();
+        self.recursive_ctes.set(id + 1);
+        let work_table = CteBinding {
+            name: cte.name.clone(),
+            columns: columns
+                .iter()
+                .map(|c| OutputColumn {
+                    nullable: true,
+                    ..c.clone()
+                })
+                .collect(),
+            source: CteSource::WorkTable(id, Cell::new(false)),
+        };
+        self.ctes.borrow_mut().push(work_table);
+        let term = self.bind_operand((**right).clone(), ColumnScope::new());
+        let work_table = self.ctes.borrow_mut().pop();
+        let mut term = term?.0;
+
+        // Without reading itself, it is a plain UNION.
+        let read = match work_table {
+            Some(CteBinding {
+                source: CteSource::WorkTable(_, read),
+                ..
+            }) => read.get(),
+            _ => false,
+        };
+        if !read {
+            return Ok(None);
+        }
+        if term.is_grouped() {
+            return Err(BindError::MisplacedAggregate("a recursive query"));
+        }
+        // The work table holds the rows of both terms in the types the
+        // recursive term read them as, so only its columns may be widened.
+        let read_as: Vec<DataType> = columns.iter().map(|c| c.data_type.clone()).collect();
+        merge_columns("UNION", &mut columns, &term.output)?;
+        for (i, (column, read_as)) in columns.iter().zip(read_as).enumerate() {
+            if read_as == DataType::Null {
+                coerce_output(&mut query, i, &column.data_type);
+            } else if column.data_type != read_as {
+                return Err(BindError::SetOpTypeMismatch {
+                    op: "UNION".into(),
+                    left: read_as,
+                    right: term.output[i].data_type.clone(),
+                });
+            }
+            coerce_output(&mut term, i, &column.data_type);
+        }
+
+        Ok(Some(CteBinding {
+            name: cte.name.clone(),
+            columns,
+            source: CteSource::Query(Box::new(BoundCte {
+                query,
+                recursive: Some(BoundRecursiveTerm {
+                    id,
+                    all: *all,
+                    query: term,
+                }),
+            })),
+        }))
+    }
+
+    /// Read the CTE called `name`, if one is visible, with its columns
+    /// qualified by `visible`.
+    pub(crate) fn bind_cte_ref(
+        &self,
+        name: &str,
+        visible: &str,
+        scope: &mut ColumnScope,
+    ) -> Option<BoundFrom> {
+        let ctes = self.ctes.borrow();
+        let cte = ctes.iter().rev().find(|c| c.name == name)?;
+
+        let (from, columns) = self.add_derived_columns(visible, &cte.columns, scope);
+        Some(match &cte.source {
+            CteSource::Query(query) => BoundFrom::Cte {
+                cte: query.clone(),
+                from,
+                columns,
+            },
+            CteSource::WorkTable(id, read) => {
+                read.set(true);
+                BoundFrom::WorkTable {
+                    id: *id,
+                    output: cte.columns.clone(),
+                    from,
+                    columns,
+                }
+            }
+        })
+    }
+
+    /// Make `output`, the columns of a query read like a table, visible in
+    /// `scope` qualified by `table`. Returns the FROM item and ids they are
+    /// read as.
+    pub(crate) fn add_derived_columns(
+        &self,
+        table: &str,
+        output: &[OutputColumn],
+        scope: &mut ColumnScope,
+    ) -> (FromId, Vec<ColumnId>) {
+        let from = self.next_from();
+        let columns = output
+            .iter()
+            .map(|column| {
+                let id = self.cte_column_id();
+                scope.add_cte_column(table, from, id, column);
+                id
+            })
+            .collect();
+        (from, columns)
+    }
+
+    /// Fresh id for a column of a query read like a table. These count
+    /// down from the top, so they never meet the ids the catalog hands out.
+    fn cte_column_id(&self) -> ColumnId {
+        let n = self.cte_columns.get();
+        self.cte_columns.set(n + 1);
+        ColumnId(u32::MAX - n)
+    }
+}
+
+/// `columns` named as the CTE's column list says, if it has one.
+fn rename(
+    mut columns: Vec<OutputColumn>,
+    names: Vec<String>,
+) -> Result<Vec<OutputColumn>, BindError> {
+    if names.is_empty() {
+        return Ok(columns);
+    }
+    if names.len() != columns.len() {
+        return Err(BindError::ColumnCountMismatch);
+    }
+    for (column, name) in columns.iter_mut().zip(names) {
+        column.name = name;
+    }
+    Ok(columns)
+}
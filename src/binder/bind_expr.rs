@@ -3,66 +3,721 @@
 //! Converts SQL AST expressions into BoundExpr:
 //! - resolves column names to ColumnId
 //! - performs type checking
+//! - binds subqueries, whose references to enclosing queries become
+//!   parameters
 //!
 //! This module MUST NOT depend on IR or execution.
 
-use crate::binder::bound::BoundExpr;
+use crate::binder::bind_stmt::Binder;
+use crate::binder::bound::{BoundExpr, BoundSelect, BoundSubqueryKind};
+use crate::binder::coerce::{self, castable, common_type, is_integer, is_numeric};
 use crate::binder::errors::BindError;
 use crate::binder::scope::ColumnScope;
-use crate::frontend::sql::ast::{BinaryOp as AstBinaryOp, Expr as SqlExpr, UnaryOp as AstUnaryOp};
+use crate::frontend::sql::ast::{
+    BinaryOp as AstBinaryOp, Expr as SqlExpr, Query, SqlType, UnaryOp as AstUnaryOp,
+};
 use crate::ir::expr::{BinaryOp as IrBinaryOp, UnaryOp as IrUnaryOp};
+use crate::ir::plan::AggregateFunc;
 use crate::types::datatype::DataType;
 use crate::types::value::Value;
 
-/// Entry point: bind a SQL expression into a BoundExpr.
-/// Returns the bound expression AND its inferred type.
-pub fn bind_expr(expr: &SqlExpr, scope: &ColumnScope) -> Result<(BoundExpr, DataType), BindError> {
-    match expr {
-        // ---------- column reference ----------
-        SqlExpr::Column { name, .. } => {
-            let (column_id, ty) = scope.resolve(name)?;
-            Ok((BoundExpr::Column { column_id }, ty))
-        }
-
-        // ---------- literal ----------
-        SqlExpr::Literal(v) => Ok((BoundExpr::Literal(v.clone()), literal_type(v))),
-
-        // ---------- NULL ----------
-        //SqlExpr::Null => Ok((BoundExpr::Null, DataType::Null)),
-
-        // ---------- unary ----------
-        SqlExpr::Unary { op, expr } => {
-            let (inner, inner_ty) = bind_expr(expr, scope)?;
-            let ir_op = lower_unary_op(*op);
-            let result_ty = infer_unary_type(ir_op, &inner_ty)?;
-            Ok((
-                BoundExpr::Unary {
-                    op: ir_op,
-                    expr: Box::new(inner),
-                },
-                result_ty,
-            ))
+impl<'a> Binder<'a> {
+    /// Entry point: bind a SQL expression into a BoundExpr.
+    /// Returns the bound expression AND its inferred type.
+    pub fn bind_expr(
+        &self,
+        expr: &SqlExpr,
+        scope: &ColumnScope,
+    ) -> Result<(BoundExpr, DataType), BindError> {
+        match expr {
+            // ---------- column reference ----------
+            SqlExpr::Column { table, name } => match scope.resolve(table.as_deref(), name) {
+                Ok((from, column_id, ty)) => Ok((BoundExpr::Column { from, column_id }, ty)),
+                // Inside a subquery, it may be a column of an enclosing query.
+                Err(BindError::UnknownColumn(_)) if scope.has_outer() => {
+                    let (param, from, column_id, ty) =
+                        scope.resolve_outer(table.as_deref(), name)?;
+                    let expr = BoundExpr::OuterColumn {
+                        from,
+                        column_id,
+                        param,
+                    };
+                    Ok((expr, ty))
+                }
+                Err(e) => Err(e),
+            },
+
+            // ---------- literal ----------
+            SqlExpr::Literal(v) => Ok((BoundExpr::Literal(v.clone()), literal_type(v))),
+
+            // ---------- NULL ----------
+            //SqlExpr::Null => Ok((BoundExpr::Null, DataType::Null)),
+
+            // ---------- unary ----------
+            SqlExpr::Unary { op, expr } => {
+                let (inner, inner_ty) = self.bind_expr(expr, scope)?;
+                let ir_op = lower_unary_op(*op);
+                let result_ty = infer_unary_type(ir_op, &inner_ty)?;
+                Ok((
+                    BoundExpr::Unary {
+                        op: ir_op,
+                        expr: Box::new(inner),
+                    },
+                    result_ty,
+                ))
+            }
+
+            // ---------- function call ----------
+            SqlExpr::Function {
+                name,
+                args,
+                distinct,
+            } => self.bind_function(name, args, *distinct, scope),
+
+            SqlExpr::Binary {
+                left,
+                op: AstBinaryOp::Concat,
+                right,
+            } => {
+                let args = vec![self.bind_expr(left, scope)?, self.bind_expr(right, scope)?];
+                self.bind_call("||", args)
+            }
+
+            SqlExpr::Binary { left, op, right } => {
+                let left = self.bind_expr(left, scope)?;
+                let right = self.bind_expr(right, scope)?;
+                bind_binary(lower_binary_op(*op), left, right)
+            }
+
+            SqlExpr::Cast { expr, ty } => {
+                let (inner, from) = self.bind_expr(expr, scope)?;
+                let to = data_type(ty);
+                if !castable(&from, &to) {
+                    return Err(BindError::InvalidCast { from, to });
+                }
+                Ok((coerce::widen(inner, &from, &to), to))
+            }
+
+            // ---------- subqueries ----------
+            SqlExpr::Subquery(select) => {
+                let (select, params) = self.bind_subquery(select, scope)?;
+                let ty = single_column(&select)?;
+                let expr = BoundExpr::Subquery {
+                    kind: BoundSubqueryKind::Scalar,
+                    select: Box::new(select),
+                    params,
+                };
+                Ok((expr, ty))
+            }
+
+            SqlExpr::Exists(select) => {
+                let (select, params) = self.bind_subquery(select, scope)?;
+                let expr = BoundExpr::Subquery {
+                    kind: BoundSubqueryKind::Exists,
+                    select: Box::new(select),
+                    params,
+                };
+                Ok((expr, DataType::Boolean))
+            }
+
+            SqlExpr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                let (lhs, lhs_ty) = self.bind_expr(expr, scope)?;
+                let (mut select, params) = self.bind_subquery(subquery, scope)?;
+                let ty = single_column(&select)?;
+                infer_binary_type(IrBinaryOp::Eq, &lhs_ty, &ty)?;
+
+                // Both sides are compared in the type that holds either.
+                let common = common_type(&lhs_ty, &ty).unwrap_or(ty);
+                let lhs = coerce::widen(lhs, &lhs_ty, &common);
+                coerce_output(&mut select, 0, &common);
+
+                let expr = BoundExpr::Subquery {
+                    kind: BoundSubqueryKind::In(Box::new(lhs)),
+                    select: Box::new(select),
+                    params,
+                };
+                Ok((negate(expr, *negated), DataType::Boolean))
+            }
+
+            SqlExpr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let value = self.bind_expr(expr, scope)?;
+                let list = list
+                    .iter()
+                    .map(|item| self.bind_expr(item, scope))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let (expr, list) = compare_with(value, list)?;
+                let expr = BoundExpr::InList {
+                    expr: Box::new(expr),
+                    list,
+                };
+                Ok((negate(expr, *negated), DataType::Boolean))
+            }
+
+            // `x BETWEEN a AND b` is `x >= a AND x <= b`.
+            SqlExpr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let value = self.bind_expr(expr, scope)?;
+                let low = bind_binary(IrBinaryOp::Gte, value.clone(), self.bind_expr(low, scope)?)?;
+                let high = bind_binary(IrBinaryOp::Lte, value, self.bind_expr(high, scope)?)?;
+                let (expr, ty) = bind_binary(IrBinaryOp::And, low, high)?;
+                Ok((negate(expr, *negated), ty))
+            }
+
+            SqlExpr::Like {
+                expr,
+                pattern,
+                escape,
+                case_insensitive,
+                negated,
+            } => {
+                let (expr, expr_ty) = self.bind_expr(expr, scope)?;
+                let (pattern, pattern_ty) = self.bind_expr(pattern, scope)?;
+                let text = |ty: &DataType| matches!(ty, DataType::Varchar { .. } | DataType::Null);
+                if !text(&expr_ty) || !text(&pattern_ty) {
+                    return Err(BindError::TypeMismatchBinary {
+                        op: if *case_insensitive { "ILIKE" } else { "LIKE" }.to_string(),
+                        left: expr_ty,
+                        right: pattern_ty,
+                    });
+                }
+                let expr = BoundExpr::Like {
+                    expr: Box::new(expr),
+                    pattern: Box::new(pattern),
+                    escape: like_escape(escape.as_deref())?,
+                    case_insensitive: *case_insensitive,
+                };
+                Ok((negate(expr, *negated), DataType::Boolean))
+            }
+
+            SqlExpr::IsNull { expr, negated } => {
+                let (expr, _) = self.bind_expr(expr, scope)?;
+                let op = match negated {
+                    true => IrUnaryOp::IsNotNull,
+                    false => IrUnaryOp::IsNull,
+                };
+                let expr = BoundExpr::Unary {
+                    op,
+                    expr: Box::new(expr),
+                };
+                Ok((expr, DataType::Boolean))
+            }
+
+            SqlExpr::IsDistinctFrom {
+                left,
+                right,
+                negated,
+            } => {
+                let op = match negated {
+                    true => IrBinaryOp::IsNotDistinctFrom,
+                    false => IrBinaryOp::IsDistinctFrom,
+                };
+                let left = self.bind_expr(left, scope)?;
+                let right = self.bind_expr(right, scope)?;
+                bind_binary(op, left, right)
+            }
+
+            SqlExpr::Case {
+                operand,
+                branches,
+                else_result,
+            } => self.bind_case(operand.as_deref(), branches, else_result.as_deref(), scope),
         }
+    }
+
+    /// Bind a CASE expression. The operand of a simple CASE is compared
+    /// with each WHEN value as by `=`; the conditions of a searched one are
+    /// booleans. All results are converted to the type that holds each.
+    fn bind_case(
+        &self,
+        operand: Option<&SqlExpr>,
+        branches: &[(SqlExpr, SqlExpr)],
+        else_result: Option<&SqlExpr>,
+        scope: &ColumnScope,
+    ) -> Result<(BoundExpr, DataType), BindError> {
+        let whens = branches
+            .iter()
+            .map(|(when, _)| self.bind_expr(when, scope))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (operand, whens) = match operand {
+            Some(operand) => {
+                let (operand, whens) = compare_with(self.bind_expr(operand, scope)?, whens)?;
+                (Some(Box::new(operand)), whens)
+            }
+            None => {
+                let whens = whens
+                    .into_iter()
+                    .map(|(when, ty)| match ty {
+                        DataType::Boolean | DataType::Null => Ok(when),
+                        found => Err(BindError::TypeMismatchUnary {
+                            op: "WHEN".to_string(),
+                            found,
+                        }),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                (None, whens)
+            }
+        };
+
+        let results = branches
+            .iter()
+            .map(|(_, then)| then)
+            .chain(else_result)
+            .map(|result| self.bind_expr(result, scope))
+            .collect::<Result<Vec<_>, _>>()?;
+        let ty = results.iter().try_fold(DataType::Null, |common, (_, ty)| {
+            common_type(&common, ty).ok_or_else(|| BindError::TypeMismatchBinary {
+                op: "CASE".to_string(),
+                left: common,
+                right: ty.clone(),
+            })
+        })?;
+        let mut results = results
+            .into_iter()
+            .map(|(result, result_ty)| coerce::widen(result, &result_ty, &ty));
+        let branches = whens.into_iter().zip(results.by_ref()).collect();
+        let else_result = results.next().map(Box::new);
 
-        SqlExpr::Binary { left, op, right } => {
-            let (l, l_ty) = bind_expr(left, scope)?;
-            let (r, r_ty) = bind_expr(right, scope)?;
-            let ir_op = lower_binary_op(*op);
-            let result_ty = infer_binary_type(ir_op, &l_ty, &r_ty)?;
+        let expr = BoundExpr::Case {
+            operand,
+            branches,
+            else_result,
+        };
+        Ok((expr, ty))
+    }
 
-            Ok((
-                BoundExpr::Binary {
-                    left: Box::new(l),
-                    op: ir_op,
-                    right: Box::new(r),
+    /// Bind a query nested in an expression bound in `scope`, along with
+    /// the values of enclosing queries it reads. Those the query one level
+    /// out owns are its plain columns; the rest it reads through
+    /// parameters of its own.
+    fn bind_subquery(
+        &self,
+        select: &Query,
+        scope: &ColumnScope,
+    ) -> Result<(BoundSelect, Vec<BoundExpr>), BindError> {
+        let (select, inner) = self.bind_query(select.clone(), ColumnScope::nested(scope))?;
+        let params = inner
+            .correlated()
+            .into_iter()
+            .map(|(from, column_id, depth)| match depth {
+                1 => BoundExpr::Column { from, column_id },
+                _ => BoundExpr::OuterColumn {
+                    from,
+                    column_id,
+                    param: scope.correlate(from, column_id, depth - 1),
                 },
-                result_ty,
-            ))
+            })
+            .collect();
+        Ok((select, params))
+    }
+
+    /// Bind a call to the function called `name`: one of the built-in
+    /// aggregates, or an aggregate or scalar function of the binder's
+    /// registry.
+    fn bind_function(
+        &self,
+        name: &str,
+        args: &[SqlExpr],
+        distinct: bool,
+        scope: &ColumnScope,
+    ) -> Result<(BoundExpr, DataType), BindError> {
+        if let Some(func) = aggregate_func(name) {
+            return self.bind_aggregate(func, name, args, distinct, scope);
+        }
+        if !self.functions.aggregate(name).is_empty() {
+            return self.bind_user_aggregate(name, args, distinct, scope);
+        }
+        if distinct {
+            return Err(BindError::InvalidFunctionCall {
+                function: name.to_ascii_uppercase(),
+                reason: "DISTINCT is only allowed in aggregate calls".to_string(),
+            });
+        }
+        let args = args
+            .iter()
+            .map(|arg| self.bind_expr(arg, scope))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.bind_call(name, args)
+    }
+
+    /// Bind a call to the first version of scalar function `name` that
+    /// takes `args`, which are converted to the types it declares.
+    fn bind_call(
+        &self,
+        name: &str,
+        args: Vec<(BoundExpr, DataType)>,
+    ) -> Result<(BoundExpr, DataType), BindError> {
+        let versions = self.functions.scalar(name);
+        if versions.is_empty() {
+            return Err(BindError::UnknownFunction(name.to_string()));
+        }
+        let types: Vec<DataType> = args.iter().map(|(_, ty)| ty.clone()).collect();
+        let (func, (targets, returns)) = versions
+            .iter()
+            .find_map(|f| coerce::match_signature(f.signature(), &types).map(|m| (f, m)))
+            .ok_or_else(|| no_version(name, &types))?;
+
+        let args = args
+            .into_iter()
+            .zip(targets)
+            .map(|((arg, ty), to)| coerce::widen(arg, &ty, &to))
+            .collect();
+        Ok((
+            BoundExpr::Function {
+                func: func.clone(),
+                args,
+            },
+            returns,
+        ))
+    }
+
+    /// Bind a call to one of the aggregate functions. Its argument is evaluated
+    /// per input row, so it may not contain another aggregate.
+    fn bind_aggregate(
+        &self,
+        func: AggregateFunc,
+        name: &str,
+        args: &[SqlExpr],
+        distinct: bool,
+        scope: &ColumnScope,
+    ) -> Result<(BoundExpr, DataType), BindError> {
+        let invalid = |reason: &str| BindError::InvalidFunctionCall {
+            function: name.to_ascii_uppercase(),
+            reason: reason.to_string(),
+        };
+
+        let [arg] = args else {
+            return Err(invalid("expected exactly one argument"));
+        };
+
+        // COUNT(*)
+        if matches!(arg, SqlExpr::Column { table: None, name } if name == "*") {
+            if func != AggregateFunc::Count {
+                return Err(invalid("only COUNT accepts *"));
+            }
+            let expr = BoundExpr::Aggregate {
+                func,
+                arg: None,
+                distinct,
+            };
+            return Ok((expr, DataType::Int64));
+        }
+
+        let (arg, arg_ty) = self.bind_expr(arg, scope)?;
+        if arg.contains_aggregate() {
+            return Err(BindError::MisplacedAggregate("aggregate arguments"));
+        }
+        let result_ty = infer_aggregate_type(&func, &arg_ty)?;
+
+        Ok((
+            BoundExpr::Aggregate {
+                func,
+                arg: Some(Box::new(arg)),
+                distinct,
+            },
+            result_ty,
+        ))
+    }
+
+    /// Bind a call to the first version of registered aggregate `name` that
+    /// takes the type of its one argument, which is converted to the type
+    /// it declares.
+    fn bind_user_aggregate(
+        &self,
+        name: &str,
+        args: &[SqlExpr],
+        distinct: bool,
+        scope: &ColumnScope,
+    ) -> Result<(BoundExpr, DataType), BindError> {
+        let [arg] = args else {
+            return Err(BindError::InvalidFunctionCall {
+                function: name.to_ascii_uppercase(),
+                reason: "expected exactly one argument".to_string(),
+            });
+        };
+        let (arg, arg_ty) = self.bind_expr(arg, scope)?;
+        if arg.contains_aggregate() {
+            return Err(BindError::MisplacedAggregate("aggregate arguments"));
         }
+
+        let types = [arg_ty];
+        let (func, (targets, returns)) = self
+            .functions
+            .aggregate(name)
+            .iter()
+            .find_map(|f| coerce::match_signature(f.signature(), &types).map(|m| (f, m)))
+            .ok_or_else(|| no_version(name, &types))?;
+        let [arg_ty] = types;
+
+        Ok((
+            BoundExpr::Aggregate {
+                func: AggregateFunc::User(func.clone()),
+                arg: Some(Box::new(coerce::widen(arg, &arg_ty, &targets[0]))),
+                distinct,
+            },
+            returns,
+        ))
+    }
+}
+
+fn no_version(name: &str, types: &[DataType]) -> BindError {
+    BindError::InvalidFunctionCall {
+        function: name.to_ascii_uppercase(),
+        reason: format!(
+            "no version takes arguments ({})",
+            types
+                .iter()
+                .map(DataType::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Bind `left op right`, converting the operands to the type the operator
+/// applies to. Arithmetic on dates takes its operands as they are.
+fn bind_binary(
+    op: IrBinaryOp,
+    (mut l, mut l_ty): (BoundExpr, DataType),
+    (mut r, mut r_ty): (BoundExpr, DataType),
+) -> Result<(BoundExpr, DataType), BindError> {
+    let comparison = matches!(
+        op,
+        IrBinaryOp::Eq
+            | IrBinaryOp::Neq
+            | IrBinaryOp::Lt
+            | IrBinaryOp::Lte
+            | IrBinaryOp::Gt
+            | IrBinaryOp::Gte
+            | IrBinaryOp::IsDistinctFrom
+            | IrBinaryOp::IsNotDistinctFrom
+    );
+    if comparison {
+        // A string literal compared with a date or timestamp is read as one.
+        let temporal = |ty: &DataType| matches!(ty, DataType::Date | DataType::Timestamp);
+        if matches!(l, BoundExpr::Literal(Value::String(_))) && temporal(&r_ty) {
+            l = coerce::cast(l, &r_ty);
+            l_ty = r_ty.clone();
+        } else if matches!(r, BoundExpr::Literal(Value::String(_))) && temporal(&l_ty) {
+            r = coerce::cast(r, &l_ty);
+            r_ty = l_ty.clone();
+        }
+    }
+
+    // So does an integer literal combined with a narrower integer, so that
+    // `int_column + 1` stays INT.
+    if !comparison && is_integer(&l_ty) && is_integer(&r_ty) {
+        if let Some(lit) = coerce::fit_literal(&r, &l_ty) {
+            r = lit;
+            r_ty = l_ty.clone();
+        } else if let Some(lit) = coerce::fit_literal(&l, &r_ty) {
+            l = lit;
+            l_ty = r_ty.clone();
+        }
+    }
+
+    let result_ty = infer_binary_type(op, &l_ty, &r_ty)?;
+
+    let operand_ty = if comparison {
+        // A literal the other side's type holds is converted by itself.
+        if let Some(lit) = coerce::fit_literal(&r, &l_ty) {
+            r = lit;
+            r_ty = l_ty.clone();
+        } else if let Some(lit) = coerce::fit_literal(&l, &r_ty) {
+            l = lit;
+            l_ty = r_ty.clone();
+        }
+        common_type(&l_ty, &r_ty)
+    } else if is_numeric(&result_ty) && !matches!(l_ty, DataType::Date) {
+        Some(result_ty.clone())
+    } else {
+        None
+    };
+    if let Some(ty) = operand_ty {
+        l = coerce::widen(l, &l_ty, &ty);
+        r = coerce::widen(r, &r_ty, &ty);
+    }
+
+    Ok((
+        BoundExpr::Binary {
+            left: Box::new(l),
+            op,
+            right: Box::new(r),
+        },
+        result_ty,
+    ))
+}
+
+/// `value` and `items`, converted so that `value` can be compared with each
+/// item as by `=`. Where every item already has the type of `value`, or is
+/// a literal that type holds, `value` keeps its type, so an index on it
+/// still applies.
+fn compare_with(
+    (mut value, value_ty): (BoundExpr, DataType),
+    items: Vec<(BoundExpr, DataType)>,
+) -> Result<(BoundExpr, Vec<BoundExpr>), BindError> {
+    let temporal = matches!(value_ty, DataType::Date | DataType::Timestamp);
+    let items = items
+        .into_iter()
+        .map(|(item, ty)| match item {
+            BoundExpr::Literal(Value::String(_)) if temporal => {
+                (coerce::cast(item, &value_ty), value_ty.clone())
+            }
+            item => (item, ty),
+        })
+        .collect::<Vec<_>>();
+    for (_, ty) in &items {
+        infer_binary_type(IrBinaryOp::Eq, &value_ty, ty)?;
+    }
+
+    let as_is = items
+        .iter()
+        .map(|(item, ty)| match common_type(&value_ty, ty) {
+            Some(common) if common == value_ty => Some(coerce::widen(item.clone(), ty, &value_ty)),
+            _ => coerce::fit_literal(item, &value_ty),
+        })
+        .collect::<Option<Vec<_>>>();
+    if let Some(items) = as_is {
+        return Ok((value, items));
+    }
+
+    let common = items
+        .iter()
+        .try_fold(value_ty.clone(), |common, (_, ty)| common_type(&common, ty))
+        .ok_or_else(|| BindError::TypeMismatchBinary {
+            op: "IN".to_string(),
+            left: value_ty.clone(),
+            right: items.last().map_or(DataType::Null, |(_, ty)| ty.clone()),
+        })?;
+    value = coerce::widen(value, &value_ty, &common);
+    let items = items
+        .into_iter()
+        .map(|(item, ty)| coerce::widen(item, &ty, &common))
+        .collect();
+    Ok((value, items))
+}
+
+/// `expr`, or `NOT expr` if `negated`.
+fn negate(expr: BoundExpr, negated: bool) -> BoundExpr {
+    match negated {
+        true => BoundExpr::Unary {
+            op: IrUnaryOp::Not,
+            expr: Box::new(expr),
+        },
+        false => expr,
     }
 }
+
+/// The escape character of a LIKE: a backslash by default, none for an
+/// empty ESCAPE string.
+fn like_escape(escape: Option<&SqlExpr>) -> Result<Option<char>, BindError> {
+    let escape = match escape {
+        None => return Ok(Some('\\')),
+        Some(SqlExpr::Literal(Value::String(s))) => s,
+        Some(_) => {
+            return Err(BindError::NotImplemented(
+                "LIKE escape other than a string literal".to_string(),
+            ));
+        }
+    };
+    let mut chars = escape.chars();
+    match (chars.next(), chars.next()) {
+        (c, None) => Ok(c),
+        _ => Err(BindError::InvalidEscape(escape.clone())),
+    }
+}
+
+/// Convert the `i`th output column of `select` to the wider type `to`.
+pub(crate) fn coerce_output(select: &mut BoundSelect, i: usize, to: &DataType) {
+    let column = &mut select.output[i];
+    if column.data_type == *to {
+        return;
+    }
+    let expr = select.projection[i].clone();
+    select.projection[i] = coerce::widen(expr, &column.data_type, to);
+    column.data_type = to.clone();
+    column.table = None;
+}
+
+/// The type a column declared as `ty` holds.
+pub(crate) fn data_type(ty: &SqlType) -> DataType {
+    match ty {
+        SqlType::SmallInt => DataType::Int16,
+        SqlType::Int => DataType::Int32,
+        SqlType::BigInt => DataType::Int64,
+        SqlType::Real => DataType::Float32,
+        SqlType::Double => DataType::Float64,
+        SqlType::Bool => DataType::Boolean,
+        SqlType::Text => DataType::Varchar { max_len: None },
+        SqlType::Varchar(max_len) => DataType::Varchar { max_len: *max_len },
+        SqlType::Date => DataType::Date,
+        SqlType::Timestamp => DataType::Timestamp,
+        SqlType::Blob => DataType::Blob,
+    }
+}
+
+fn aggregate_func(name: &str) -> Option<AggregateFunc> {
+    Some(match name.to_ascii_uppercase().as_str() {
+        "COUNT" => AggregateFunc::Count,
+        "SUM" => AggregateFunc::Sum,
+        "AVG" => AggregateFunc::Avg,
+        "MIN" => AggregateFunc::Min,
+        "MAX" => AggregateFunc::Max,
+        _ => return None,
+    })
+}
+
+/// Type of the only column of a subquery used as a value or with IN.
+fn single_column(select: &BoundSelect) -> Result<DataType, BindError> {
+    match select.output.as_slice() {
+        [column] => Ok(column.data_type.clone()),
+        columns => Err(BindError::SubqueryColumnCount(columns.len())),
+    }
+}
+
+fn infer_aggregate_type(func: &AggregateFunc, arg: &DataType) -> Result<DataType, BindError> {
+    let mismatch = || BindError::TypeMismatchUnary {
+        op: format!("{:?}", func).to_ascii_uppercase(),
+        found: arg.clone(),
+    };
+
+    match func {
+        AggregateFunc::Count => Ok(DataType::Int64),
+        AggregateFunc::Min | AggregateFunc::Max => Ok(arg.clone()),
+        AggregateFunc::Sum => match arg {
+            DataType::Int16 | DataType::Int32 | DataType::Int64 | DataType::Null => {
+                Ok(DataType::Int64)
+            }
+            DataType::Float32 | DataType::Float64 => Ok(DataType::Float64),
+            _ => Err(mismatch()),
+        },
+        AggregateFunc::Avg => match arg {
+            DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Null => Ok(DataType::Float64),
+            _ => Err(mismatch()),
+        },
+        AggregateFunc::User(_) => unreachable!("registered aggregates are bound by signature"),
+    }
+}
+
 fn literal_type(v: &Value) -> DataType {
     match v {
+        Value::Int16(_) => DataType::Int16,
         Value::Int32(_) => DataType::Int32,
         Value::Int64(_) => DataType::Int64,
         Value::Float32(_) => DataType::Float32,
@@ -72,6 +727,7 @@ fn literal_type(v: &Value) -> DataType {
         Value::Blob(_) => DataType::Blob,
         Value::Date(_) => DataType::Date,
         Value::Timestamp(_) => DataType::Timestamp,
+        Value::Interval { .. } => DataType::Interval,
         Value::Null => DataType::Null,
     }
 }
@@ -79,7 +735,7 @@ fn literal_type(v: &Value) -> DataType {
 fn infer_unary_type(op: IrUnaryOp, inner: &DataType) -> Result<DataType, BindError> {
     match op {
         IrUnaryOp::Neg => {
-            if *inner == DataType::Int64 || *inner == DataType::Float64 {
+            if is_numeric(inner) {
                 Ok(inner.clone())
             } else {
                 Err(BindError::TypeMismatchUnary {
@@ -89,8 +745,10 @@ fn infer_unary_type(op: IrUnaryOp, inner: &DataType) -> Result<DataType, BindErr
             }
         }
 
+        IrUnaryOp::IsNull | IrUnaryOp::IsNotNull => Ok(DataType::Boolean),
+
         IrUnaryOp::Not => {
-            if *inner == DataType::Boolean {
+            if matches!(inner, DataType::Boolean | DataType::Null) {
                 Ok(DataType::Boolean)
             } else {
                 Err(BindError::TypeMismatchUnary {
@@ -102,15 +760,15 @@ fn infer_unary_type(op: IrUnaryOp, inner: &DataType) -> Result<DataType, BindErr
     }
 }
 
-fn infer_binary_type(
+pub(crate) fn infer_binary_type(
     op: IrBinaryOp,
     left: &DataType,
     right: &DataType,
 ) -> Result<DataType, BindError> {
     match op {
-        IrBinaryOp::Add | IrBinaryOp::Sub | IrBinaryOp::Mul | IrBinaryOp::Div => {
-            if left == right && (*left == DataType::Int64 || *left == DataType::Float64) {
-                Ok(left.clone())
+        IrBinaryOp::Add | IrBinaryOp::Sub | IrBinaryOp::Mul | IrBinaryOp::Div | IrBinaryOp::Mod => {
+            if let Some(ty) = arithmetic_type(op, left, right) {
+                Ok(ty)
             } else {
                 Err(BindError::TypeMismatchBinary {
                     op: format!("{:?}", op),
@@ -125,8 +783,10 @@ fn infer_binary_type(
         | IrBinaryOp::Lt
         | IrBinaryOp::Lte
         | IrBinaryOp::Gt
-        | IrBinaryOp::Gte => {
-            if left == right || *left == DataType::Null || *right == DataType::Null {
+        | IrBinaryOp::Gte
+        | IrBinaryOp::IsDistinctFrom
+        | IrBinaryOp::IsNotDistinctFrom => {
+            if comparable(left, right) {
                 Ok(DataType::Boolean)
             } else {
                 Err(BindError::TypeMismatchBinary {
@@ -138,7 +798,8 @@ fn infer_binary_type(
         }
 
         IrBinaryOp::And | IrBinaryOp::Or => {
-            if *left == DataType::Boolean && *right == DataType::Boolean {
+            let boolean = |ty: &DataType| matches!(ty, DataType::Boolean | DataType::Null);
+            if boolean(left) && boolean(right) {
                 Ok(DataType::Boolean)
             } else {
                 Err(BindError::TypeMismatchBinary {
@@ -151,12 +812,39 @@ fn infer_binary_type(
     }
 }
 
+/// Type of `left op right` for an arithmetic `op`. Numbers of different
+/// types are widened to the type that holds both, days may be added to or
+/// taken from dates, and intervals to or from dates and timestamps.
+fn arithmetic_type(op: IrBinaryOp, left: &DataType, right: &DataType) -> Option<DataType> {
+    use DataType::*;
+    let add_or_sub = matches!(op, IrBinaryOp::Add | IrBinaryOp::Sub);
+    match (left, right) {
+        (Null, ty) | (ty, Null) if is_numeric(ty) => Some(ty.clone()),
+        (l, r) if is_numeric(l) && is_numeric(r) => common_type(l, r),
+        (Date, ty) if is_integer(ty) && add_or_sub => Some(Date),
+        (ty, Date) if is_integer(ty) && op == IrBinaryOp::Add => Some(Date),
+        (Date, Date) if op == IrBinaryOp::Sub => Some(Int64),
+        (Date | Timestamp, Date | Timestamp) if op == IrBinaryOp::Sub => Some(Interval),
+        (Date | Timestamp, Interval) if add_or_sub => Some(Timestamp),
+        (Interval, Date | Timestamp) if op == IrBinaryOp::Add => Some(Timestamp),
+        (Interval, Interval) if add_or_sub => Some(Interval),
+        _ => None,
+    }
+}
+
+/// Whether values of `left` and `right` can be compared: those of types
+/// with a common one they both widen to.
+fn comparable(left: &DataType, right: &DataType) -> bool {
+    common_type(left, right).is_some()
+}
+
 fn lower_binary_op(op: AstBinaryOp) -> IrBinaryOp {
     match op {
         AstBinaryOp::Add => IrBinaryOp::Add,
         AstBinaryOp::Sub => IrBinaryOp::Sub,
         AstBinaryOp::Mul => IrBinaryOp::Mul,
         AstBinaryOp::Div => IrBinaryOp::Div,
+        AstBinaryOp::Mod => IrBinaryOp::Mod,
 
         AstBinaryOp::Eq => IrBinaryOp::Eq,
         AstBinaryOp::Neq => IrBinaryOp::Neq,
@@ -167,6 +855,8 @@ fn lower_binary_op(op: AstBinaryOp) -> IrBinaryOp {
 
         AstBinaryOp::And => IrBinaryOp::And,
         AstBinaryOp::Or => IrBinaryOp::Or,
+
+        AstBinaryOp::Concat => unreachable!("|| is bound as a function call"),
     }
 }
 
@@ -0,0 +1,104 @@
+//! Set operation binding.
+//!
+//! The rows of `left UNION right` and the like are read as if they were a
+//! table, so ORDER BY and LIMIT over them bind like those of any query.
+
+use crate::binder::bind_expr::coerce_output;
+use crate::binder::bind_stmt::Binder;
+use crate::binder::bound::{BoundFrom, BoundSelect};
+use crate::binder::coerce::common_type;
+use crate::binder::errors::BindError;
+use crate::binder::scope::ColumnScope;
+use crate::frontend::sql::ast::{Query, QueryBody, SetOperator};
+use crate::ir::plan::SetOpType;
+use crate::types::schema::OutputColumn;
+
+impl<'a> Binder<'a> {
+    /// Bind `left op right`, with the columns of its rows made visible in
+    /// `scope`. Returns their descriptions too, named after the left side.
+    pub(crate) fn bind_set_op(
+        &self,
+        op: SetOperator,
+        all: bool,
+        left: QueryBody,
+        right: QueryBody,
+        scope: ColumnScope,
+    ) -> Result<(BoundFrom, Vec<OutputColumn>, ColumnScope), BindError> {
+        let (mut left, mut scope) = self.bind_operand(left, scope)?;
+        scope.clear();
+        let (mut right, mut scope) = self.bind_operand(right, scope)?;
+        scope.clear();
+
+        let mut output = left.output.clone();
+        merge_columns(op_name(op), &mut output, &right.output)?;
+        for (i, column) in output.iter().enumerate() {
+            coerce_output(&mut left, i, &column.data_type);
+            coerce_output(&mut right, i, &column.data_type);
+        }
+        let (from, columns) = self.add_derived_columns("", &output, &mut scope);
+        let from = BoundFrom::SetOp {
+            op: match op {
+                SetOperator::Union => SetOpType::Union,
+                SetOperator::Intersect => SetOpType::Intersect,
+                SetOperator::Except => SetOpType::Except,
+            },
+            all,
+            left: Box::new(left),
+            right: Box::new(right),
+            from,
+            columns,
+        };
+        Ok((from, output, scope))
+    }
+
+    /// Bind one of the queries a set operation combines.
+    pub(crate) fn bind_operand(
+        &self,
+        body: QueryBody,
+        scope: ColumnScope,
+    ) -> Result<(BoundSelect, ColumnScope), BindError> {
+        let query = Query {
+            with: None,
+            body,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+        };
+        self.bind_query(query, scope)
+    }
+}
+
+pub(crate) fn op_name(op: SetOperator) -> &'static str {
+    match op {
+        SetOperator::Union => "UNION",
+        SetOperator::Intersect => "INTERSECT",
+        SetOperator::Except => "EXCEPT",
+    }
+}
+
+/// Widen `columns` to also describe `found`, the columns of the other query
+/// of set operation `op`, each to the type that holds values of both. A
+/// column may be NULL if it may be in either.
+pub(crate) fn merge_columns(
+    op: &str,
+    columns: &mut [OutputColumn],
+    found: &[OutputColumn],
+) -> Result<(), BindError> {
+    if columns.len() != found.len() {
+        return Err(BindError::SetOpColumnCount(op.to_string()));
+    }
+    for (column, found) in columns.iter_mut().zip(found) {
+        column.data_type = common_type(&column.data_type, &found.data_type).ok_or_else(|| {
+            BindError::SetOpTypeMismatch {
+                op: op.to_string(),
+                left: column.data_type.clone(),
+                right: found.data_type.clone(),
+            }
+        })?;
+        column.nullable |= found.nullable;
+        if column.table != found.table {
+            column.table = None;
+        }
+    }
+    Ok(())
+}
@@ -3,28 +3,65 @@
 //! Converts SQL AST statements into BoundStatement.
 //! Owns table resolution, scope construction, and statement shape.
 
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 
-use crate::binder::bind_expr::bind_expr;
+use crate::binder::bind_cte::CteBinding;
+use crate::binder::bind_expr::{data_type, infer_binary_type};
 use crate::binder::bound::*;
+use crate::binder::coerce::{self, assignable, common_type};
 use crate::binder::errors::BindError;
 use crate::binder::scope::ColumnScope;
 use crate::catalog::catalog::Catalog;
+use crate::catalog::column::ColumnMeta;
 use crate::frontend::sql::ast::*;
-use crate::ir::plan::JoinType;
+use crate::functions::registry::FunctionRegistry;
+use crate::ir::expr::{BinaryOp as IrBinaryOp, UnaryOp as IrUnaryOp};
+use crate::ir::plan::{AggregateFunc, FromId, JoinType};
 use crate::types::datatype::DataType;
+use crate::types::schema::OutputColumn;
+use crate::types::value::Value;
 
 pub struct Binder<'a> {
     pub catalog: &'a Catalog,
+    /// Functions calls are resolved against.
+    pub(crate) functions: &'a FunctionRegistry,
+    /// CTEs in scope, innermost last.
+    pub(crate) ctes: RefCell<Vec<CteBinding>>,
+    /// Column ids handed out to CTE references so far.
+    pub(crate) cte_columns: Cell<u32>,
+    /// FROM items read so far, after the target of a write.
+    from_items: Cell<u32>,
+    /// Recursive CTEs bound so far.
+    pub(crate) recursive_ctes: Cell<usize>,
 }
 impl<'a> Binder<'a> {
+    /// A binder resolving function calls against the built-in functions.
     pub fn new(catalog: &'a Catalog) -> Self {
-        Self { catalog }
+        Self::with_functions(catalog, FunctionRegistry::builtins())
+    }
+
+    pub fn with_functions(catalog: &'a Catalog, functions: &'a FunctionRegistry) -> Self {
+        Self {
+            catalog,
+            functions,
+            ctes: RefCell::new(Vec::new()),
+            cte_columns: Cell::new(0),
+            from_items: Cell::new(FromId::TARGET.0 + 1),
+            recursive_ctes: Cell::new(0),
+        }
+    }
+
+    /// Fresh id for a table or query read in FROM.
+    pub(crate) fn next_from(&self) -> FromId {
+        let n = self.from_items.get();
+        self.from_items.set(n + 1);
+        FromId(n)
     }
 
     pub fn bind_statement(&self, stmt: Statement) -> Result<BoundStatement, BindError> {
         match stmt {
-            Statement::Select(s) => Ok(BoundStatement::Select(self.bind_select(s)?)),
+            Statement::Select(s) => Ok(BoundStatement::Select(Box::new(self.bind_select(*s)?))),
 
             Statement::Insert(s) => Ok(BoundStatement::Insert(self.bind_insert(s)?)),
 
@@ -50,6 +87,8 @@ impl<'a> Binder<'a> {
                 Ok(BoundStatement::DropIndex(self.bind_drop_index(name)?))
             }
 
+            Statement::Transaction(s) => Ok(BoundStatement::Transaction(s)),
+
             Statement::Explain { analyze, stmt } => {
                 let inner = self.bind_statement(*stmt)?;
                 Ok(BoundStatement::Explain {
@@ -62,21 +101,130 @@ impl<'a> Binder<'a> {
 }
 
 impl<'a> Binder<'a> {
-    fn bind_select(&self, stmt: SelectStmt) -> Result<BoundSelect, BindError> {
+    fn bind_select(&self, query: Query) -> Result<BoundSelect, BindError> {
+        Ok(self.bind_query(query, ColumnScope::new())?.0)
+    }
+
+    /// Bind a query whose FROM columns go into `scope`, which is empty but
+    /// may be nested in the scope of an enclosing query. Returns the scope
+    /// too, which knows what the query read from enclosing ones.
+    pub(crate) fn bind_query(
+        &self,
+        mut query: Query,
+        scope: ColumnScope,
+    ) -> Result<(BoundSelect, ColumnScope), BindError> {
+        let Some(with) = query.with.take() else {
+            return self.bind_query_body(query, scope);
+        };
+
+        // CTEs are only visible to the query they are defined for.
+        let visible = self.ctes.borrow().len();
+        let bound = self
+            .bind_with(with)
+            .and_then(|_| self.bind_query_body(query, scope));
+        self.ctes.borrow_mut().truncate(visible);
+        bound
+    }
+
+    fn bind_query_body(
+        &self,
+        query: Query,
+        scope: ColumnScope,
+    ) -> Result<(BoundSelect, ColumnScope), BindError> {
+        let Query {
+            body,
+            order_by,
+            limit,
+            offset,
+            ..
+        } = query;
+        let (from, output, scope) = match body {
+            QueryBody::Select(select) => {
+                return self.bind_select_body(*select, order_by, limit, offset, scope);
+            }
+            QueryBody::Nested(query)
+                if order_by.is_empty() && limit.is_none() && offset.is_none() =>
+            {
+                return self.bind_query(*query, scope);
+            }
+            QueryBody::SetOp {
+                op,
+                all,
+                left,
+                right,
+            } => self.bind_set_op(op, all, *left, *right, scope)?,
+            // A parenthesized query with ORDER BY or LIMIT of its own, read
+            // like a CTE.
+            QueryBody::Nested(query) => {
+                let (query, mut scope) = self.bind_query(*query, scope)?;
+                scope.clear();
+                let output = query.output.clone();
+                let (from, columns) = self.add_derived_columns("", &output, &mut scope);
+                let cte = BoundCte {
+                    query,
+                    recursive: None,
+                };
+                let from = BoundFrom::Cte {
+                    cte: Box::new(cte),
+                    from,
+                    columns,
+                };
+                (from, output, scope)
+            }
+        };
+
+        // Rows read like a table, of which ORDER BY can read the columns.
+        let (projection, output): (Vec<_>, Vec<_>) = scope
+            .iter_columns()
+            .map(|(from, column_id, _)| BoundExpr::Column { from, column_id })
+            .zip(output)
+            .unzip();
+        let order_by = order_by
+            .into_iter()
+            .map(|o| {
+                let (expr, _) = self.bind_row_expr(&o.expr, &scope, "ORDER BY")?;
+                Ok((expr, o.asc))
+            })
+            .collect::<Result<Vec<_>, BindError>>()?;
+        let select = BoundSelect {
+            distinct: None,
+            projection,
+            output,
+            from,
+            selection: None,
+            group_by: Vec::new(),
+            having: None,
+            order_by,
+            limit: limit.map(|v| v as u64),
+            offset: offset.map(|v| v as u64),
+        };
+        Ok((select, scope))
+    }
+
+    fn bind_select_body(
+        &self,
+        stmt: SelectStmt,
+        order_by: Vec<OrderByExpr>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        mut scope: ColumnScope,
+    ) -> Result<(BoundSelect, ColumnScope), BindError> {
         // 1. Resolve FROM clause
-        let (from, scope) = self.bind_from(stmt.from)?;
+        let from = self.bind_from_inner(stmt.from, &mut scope)?;
 
         // 2. Bind projection
         let mut projection = Vec::new();
+        let mut output = Vec::new();
         for item in stmt.columns {
             match item.expr {
                 // SELECT *
                 Expr::Column { name, table } if name == "*" => {
-                    self.expand_star(&mut projection, table.as_deref(), &scope)?;
+                    self.expand_star(&mut projection, &mut output, table.as_deref(), &scope)?;
                 }
 
                 other => {
-                    let (expr, _) = bind_expr(&other, &scope)?;
+                    let (expr, ty) = self.bind_expr(&other, &scope)?;
+                    output.push(output_column(&expr, ty, item.alias, &scope));
                     projection.push(expr);
                 }
             }
@@ -89,39 +237,113 @@ impl<'a> Binder<'a> {
         // 3. WHERE
         let selection = stmt
             .where_clause
-            .map(|e| bind_expr(&e, &scope).map(|(x, _)| x))
+            .map(|e| self.bind_row_expr(&e, &scope, "WHERE").map(|(x, _)| x))
+            .transpose()?;
+
+        // 4. GROUP BY / HAVING
+        let group_by = stmt
+            .group_by
+            .iter()
+            .map(|e| self.bind_row_expr(e, &scope, "GROUP BY").map(|(x, _)| x))
+            .collect::<Result<Vec<_>, BindError>>()?;
+
+        let having = stmt
+            .having
+            .map(|e| self.bind_expr(&e, &scope).map(|(x, _)| x))
             .transpose()?;
 
-        // 4. ORDER BY
-        let order_by = stmt
-            .order_by
+        // 5. ORDER BY
+        let order_by = order_by
             .into_iter()
             .map(|o| {
-                let (expr, _) = bind_expr(&o.expr, &scope)?;
+                let (expr, _) = self.bind_expr(&o.expr, &scope)?;
                 Ok((expr, o.asc))
             })
             .collect::<Result<Vec<_>, BindError>>()?;
 
-        // 5. LIMIT / OFFSET
-        let limit = stmt.limit.map(|v| v as u64);
-        let offset = stmt.offset.map(|v| v as u64);
+        // 6. DISTINCT
+        let distinct = stmt
+            .distinct
+            .map(|d| self.bind_distinct(d, &projection, &order_by, &scope))
+            .transpose()?;
+
+        // 7. LIMIT / OFFSET
+        let limit = limit.map(|v| v as u64);
+        let offset = offset.map(|v| v as u64);
 
-        Ok(BoundSelect {
+        let select = BoundSelect {
+            distinct,
             projection,
+            output,
             from,
             selection,
+            group_by,
+            having,
             order_by,
             limit,
             offset,
-        })
+        };
+
+        // Once rows are grouped, only group keys and aggregates are left
+        // to read.
+        if select.is_grouped() {
+            let grouped = select
+                .projection
+                .iter()
+                .chain(&select.having)
+                .chain(select.order_by.iter().map(|(e, _)| e))
+                .chain(select.distinct.iter().flat_map(BoundDistinct::exprs));
+            for expr in grouped {
+                check_grouped(expr, &select.group_by, &scope)?;
+            }
+        }
+
+        Ok((select, scope))
     }
 }
 
 impl<'a> Binder<'a> {
-    fn bind_from(&self, from: FromItem) -> Result<(BoundFrom, ColumnScope), BindError> {
-        let mut columns_scope = ColumnScope::new();
-        let from = self.bind_from_inner(from, &mut columns_scope)?;
-        Ok((from, columns_scope))
+    /// Bind DISTINCT of a query with `projection` and sorted by `order_by`.
+    /// Which row DISTINCT ON keeps for each value is up to ORDER BY, so it
+    /// must sort by the DISTINCT ON expressions first. Rows DISTINCT drops
+    /// are only told apart by their projected values, so it can only sort
+    /// by those.
+    fn bind_distinct(
+        &self,
+        distinct: Distinct,
+        projection: &[BoundExpr],
+        order_by: &[(BoundExpr, bool)],
+        scope: &ColumnScope,
+    ) -> Result<BoundDistinct, BindError> {
+        let on = match distinct {
+            Distinct::Rows => {
+                if order_by.iter().any(|(e, _)| !projection.contains(e)) {
+                    return Err(BindError::DistinctOrderBy);
+                }
+                return Ok(BoundDistinct::Rows);
+            }
+            Distinct::On(exprs) => exprs
+                .iter()
+                .map(|e| self.bind_expr(e, scope).map(|(x, _)| x))
+                .collect::<Result<Vec<_>, BindError>>()?,
+        };
+
+        if !order_by.is_empty() {
+            let mut missing: Vec<&BoundExpr> = on.iter().collect();
+            for (key, _) in order_by {
+                if missing.is_empty() {
+                    break;
+                }
+                if !on.contains(key) {
+                    return Err(BindError::DistinctOnOrderBy);
+                }
+                missing.retain(|e| *e != key);
+            }
+            if !missing.is_empty() {
+                return Err(BindError::DistinctOnOrderBy);
+            }
+        }
+        Ok(BoundDistinct::On(on))
     }
 
     fn bind_from_inner(
@@ -130,37 +352,77 @@ impl<'a> Binder<'a> {
         scope: &mut ColumnScope,
     ) -> Result<BoundFrom, BindError> {
         match from {
-            FromItem::Table { name, .. } => {
+            FromItem::Table { name, alias } => {
+                let visible = alias.as_deref().unwrap_or(&name);
+                if let Some(from) = self.bind_cte_ref(&name, visible, scope) {
+                    return Ok(from);
+                }
+
                 let table = self
                     .catalog
                     .get_table_by_name(&name)
                     .ok_or_else(|| BindError::UnknownTable(name.clone()))?;
 
+                let from = self.next_from();
                 for col in &table.schema.columns {
-                    scope.add_column(col.name.clone(), col.id, col.data_type.clone())?;
+                    scope.add_column(visible, table.id, from, col)?;
                 }
 
-                Ok(BoundFrom::Table { table_id: table.id })
+                Ok(BoundFrom::Table {
+                    table_id: table.id,
+                    from,
+                })
             }
 
-            FromItem::Join { left, right, on } => {
+            FromItem::Join {
+                left,
+                right,
+                kind,
+                constraint,
+            } => {
                 let left = self.bind_from_inner(*left, scope)?;
+                let split = scope.len();
                 let right = self.bind_from_inner(*right, scope)?;
 
-                let (on_expr, ty) = bind_expr(&on, scope)?;
-                if ty != DataType::Boolean {
-                    return Err(BindError::TypeMismatchBinary {
-                        op: "JOIN ON".into(),
-                        left: ty,
-                        right: DataType::Boolean,
-                    });
+                let join_type = match kind {
+                    JoinKind::Inner | JoinKind::Cross => JoinType::Inner,
+                    JoinKind::Left => JoinType::Left,
+                    JoinKind::Right => JoinType::Right,
+                    JoinKind::Full => JoinType::Full,
+                };
+
+                let on = match constraint {
+                    JoinConstraint::On(on) => {
+                        let (on_expr, ty) = self.bind_row_expr(&on, scope, "JOIN conditions")?;
+                        if ty != DataType::Boolean {
+                            return Err(BindError::TypeMismatchBinary {
+                                op: "JOIN ON".into(),
+                                left: ty,
+                                right: DataType::Boolean,
+                            });
+                        }
+                        on_expr
+                    }
+                    JoinConstraint::Using(columns) => {
+                        bind_using(&columns, split, join_type, scope)?
+                    }
+                    JoinConstraint::None => BoundExpr::Literal(Value::Boolean(true)),
+                };
+
+                // The side an outer join pads with NULLs
+                let end = scope.len();
+                match join_type {
+                    JoinType::Inner | JoinType::Semi | JoinType::Anti => {}
+                    JoinType::Left => scope.make_nullable(split..end),
+                    JoinType::Right => scope.make_nullable(0..split),
+                    JoinType::Full => scope.make_nullable(0..end),
                 }
 
                 Ok(BoundFrom::Join {
                     left: Box::new(left),
                     right: Box::new(right),
-                    on: on_expr,
-                    join_type: JoinType::Inner,
+                    on,
+                    join_type,
                 })
             }
         }
@@ -177,7 +439,7 @@ impl<'a> Binder<'a> {
         let mut scope = ColumnScope::new();
 
         for col in table.schema.columns.iter() {
-            scope.add_column(col.name.clone(), col.id, col.data_type.clone())?
+            scope.add_column(&table.name, table.id, FromId::TARGET, col)?
         }
         for row in stmt.rows {
             if row.len() != table.schema.columns.len() {
@@ -185,9 +447,9 @@ impl<'a> Binder<'a> {
             }
 
             let mut bound = Vec::new();
-            for expr in row {
-                let (e, _) = bind_expr(&expr, &scope)?;
-                bound.push(e);
+            for (expr, column) in row.iter().zip(&table.schema.columns) {
+                let (e, ty) = self.bind_row_expr(expr, &scope, "VALUES")?;
+                bound.push(assign(column, e, &ty)?);
             }
             rows.push(bound);
         }
@@ -207,24 +469,24 @@ impl<'a> Binder<'a> {
         let mut scope = ColumnScope::new();
 
         for col in table.schema.columns.iter() {
-            scope.add_column(col.name.clone(), col.id, col.data_type.clone())?
+            scope.add_column(&table.name, table.id, FromId::TARGET, col)?
         }
         let assignments = stmt
             .assignments
             .into_iter()
             .map(|(name, expr)| {
-                let (e, _) = bind_expr(&expr, &scope)?;
+                let (e, ty) = self.bind_row_expr(&expr, &scope, "UPDATE")?;
                 let col = table
                     .schema
                     .column_named(&name)
                     .ok_or_else(|| BindError::UnknownColumn(name.clone()))?;
-                Ok((col.id, e))
+                Ok((col.id, assign(col, e, &ty)?))
             })
             .collect::<Result<Vec<_>, BindError>>()?;
 
         let predicate = stmt
             .where_clause
-            .map(|e| bind_expr(&e, &scope).map(|(x, _)| x))
+            .map(|e| self.bind_row_expr(&e, &scope, "WHERE").map(|(x, _)| x))
             .transpose()?;
 
         Ok(BoundUpdate {
@@ -243,12 +505,12 @@ impl<'a> Binder<'a> {
         let mut scope = ColumnScope::new();
 
         for col in table.schema.columns.iter() {
-            scope.add_column(col.name.clone(), col.id, col.data_type.clone())?
+            scope.add_column(&table.name, table.id, FromId::TARGET, col)?
         }
 
         let predicate = stmt
             .where_clause
-            .map(|e| bind_expr(&e, &scope).map(|(x, _)| x))
+            .map(|e| self.bind_row_expr(&e, &scope, "WHERE").map(|(x, _)| x))
             .transpose()?;
 
         Ok(BoundDelete {
@@ -260,6 +522,7 @@ impl<'a> Binder<'a> {
     fn expand_star(
         &self,
         out: &mut Vec<BoundExpr>,
+        output: &mut Vec<OutputColumn>,
         table: Option<&str>,
         scope: &ColumnScope,
     ) -> Result<(), BindError> {
@@ -269,8 +532,9 @@ impl<'a> Binder<'a> {
             }
 
             None => {
-                for (id, _) in scope.iter_columns() {
-                    out.push(BoundExpr::Column { column_id: id });
+                for (from, column_id, column) in scope.iter_columns() {
+                    out.push(BoundExpr::Column { from, column_id });
+                    output.push(column);
                 }
             }
         }
@@ -278,7 +542,6 @@ impl<'a> Binder<'a> {
     }
 
     fn bind_create_table(&self, stmt: CreateTableStmt) -> Result<BoundCreateTable, BindError> {
-        use crate::types::datatype::DataType;
         use crate::types::schema::Schema;
 
         if stmt.columns.is_empty() {
@@ -288,14 +551,9 @@ impl<'a> Binder<'a> {
         let mut schema = Schema::new();
 
         for col_def in stmt.columns {
-            let data_type = match col_def.ty {
-                SqlType::Int => DataType::Int64,
-                SqlType::Bool => DataType::Boolean,
-                SqlType::Text => DataType::Varchar { max_len: None },
-            };
+            let data_type = data_type(&col_def.ty);
 
             // We'll assign column IDs later in the catalog
-            use crate::catalog::column::ColumnMeta;
             use crate::catalog::ids::ColumnId;
 
             schema.push(ColumnMeta {
@@ -344,6 +602,21 @@ impl<'a> Binder<'a> {
         })
     }
 
+    /// Bind an expression evaluated once per input row, where aggregates
+    /// have nothing to aggregate over.
+    fn bind_row_expr(
+        &self,
+        expr: &Expr,
+        scope: &ColumnScope,
+        clause: &'static str,
+    ) -> Result<(BoundExpr, DataType), BindError> {
+        let (bound, ty) = self.bind_expr(expr, scope)?;
+        if bound.contains_aggregate() {
+            return Err(BindError::MisplacedAggregate(clause));
+        }
+        Ok((bound, ty))
+    }
+
     fn bind_drop_index(&self, name: String) -> Result<BoundDropIndex, BindError> {
         let index = self
             .catalog
@@ -354,3 +627,215 @@ impl<'a> Binder<'a> {
         })
     }
 }
+
+/// Bind `USING (columns)` as equalities between the left side of the scope,
+/// before `split`, and the right side. Each pair is then visible unqualified
+/// as one column: the one from the side whose rows are always kept. A FULL
+/// join keeps neither side, so both stay visible there.
+fn bind_using(
+    columns: &[String],
+    split: usize,
+    join_type: JoinType,
+    scope: &mut ColumnScope,
+) -> Result<BoundExpr, BindError> {
+    let mut on: Option<BoundExpr> = None;
+
+    for name in columns {
+        let (left_pos, left_from, left_id, left_ty) = scope.resolve_in(0..split, name)?;
+        let (right_pos, right_from, right_id, right_ty) =
+            scope.resolve_in(split..scope.len(), name)?;
+        infer_binary_type(IrBinaryOp::Eq, &left_ty, &right_ty)?;
+        let common = common_type(&left_ty, &right_ty).unwrap_or(left_ty.clone());
+
+        match join_type {
+            JoinType::Inner | JoinType::Left => scope.merge(right_pos),
+            JoinType::Right => scope.merge(left_pos),
+            JoinType::Full | JoinType::Semi | JoinType::Anti => {}
+        }
+
+        let left = BoundExpr::Column {
+            from: left_from,
+            column_id: left_id,
+        };
+        let right = BoundExpr::Column {
+            from: right_from,
+            column_id: right_id,
+        };
+        let eq = BoundExpr::Binary {
+            left: Box::new(coerce::widen(left, &left_ty, &common)),
+            op: IrBinaryOp::Eq,
+            right: Box::new(coerce::widen(right, &right_ty, &common)),
+        };
+        on = Some(match on {
+            None => eq,
+            Some(prev) => BoundExpr::Binary {
+                left: Box::new(prev),
+                op: IrBinaryOp::And,
+                right: Box::new(eq),
+            },
+        });
+    }
+
+    on.ok_or_else(|| BindError::NotImplemented("USING without columns".into()))
+}
+
+/// Check that `expr` only reads columns through group keys or aggregates.
+fn check_grouped(
+    expr: &BoundExpr,
+    group_by: &[BoundExpr],
+    scope: &ColumnScope,
+) -> Result<(), BindError> {
+    if group_by.contains(expr) {
+        return Ok(());
+    }
+
+    match expr {
+        BoundExpr::Column { from, column_id } => Err(BindError::UngroupedColumn(
+            scope
+                .output_column(*from, *column_id)
+                .map_or_else(|| format!("{}", column_id.0), |c| c.name),
+        )),
+        BoundExpr::Unary { expr, .. } | BoundExpr::Cast { expr, .. } => {
+            check_grouped(expr, group_by, scope)
+        }
+        BoundExpr::Binary { left, right, .. } => {
+            check_grouped(left, group_by, scope)?;
+            check_grouped(right, group_by, scope)
+        }
+        BoundExpr::Function { args, .. } => args
+            .iter()
+            .try_for_each(|arg| check_grouped(arg, group_by, scope)),
+        BoundExpr::Case {
+            operand,
+            branches,
+            else_result,
+        } => operand
+            .iter()
+            .chain(else_result)
+            .map(|e| &**e)
+            .chain(branches.iter().flat_map(|(when, then)| [when, then]))
+            .try_for_each(|e| check_grouped(e, group_by, scope)),
+        BoundExpr::InList { expr, list } => {
+            check_grouped(expr, group_by, scope)?;
+            list.iter()
+                .try_for_each(|e| check_grouped(e, group_by, scope))
+        }
+        BoundExpr::Like { expr, pattern, .. } => {
+            check_grouped(expr, group_by, scope)?;
+            check_grouped(pattern, group_by, scope)
+        }
+        // The subquery itself reads its own rows; only the values passed
+        // into it come from the grouped ones.
+        BoundExpr::Subquery { kind, params, .. } => {
+            if let BoundSubqueryKind::In(expr) = kind {
+                check_grouped(expr, group_by, scope)?;
+            }
+            params
+                .iter()
+                .try_for_each(|p| check_grouped(p, group_by, scope))
+        }
+        BoundExpr::Aggregate { .. }
+        | BoundExpr::OuterColumn { .. }
+        | BoundExpr::Literal(_)
+        | BoundExpr::Null => Ok(()),
+    }
+}
+
+/// `expr`, of type `found`, converted to be stored in `column`. Values of
+/// other types are cast even to wider column types, which checks they fit.
+fn assign(column: &ColumnMeta, expr: BoundExpr, found: &DataType) -> Result<BoundExpr, BindError> {
+    if *found == column.data_type || *found == DataType::Null {
+        Ok(expr)
+    } else if assignable(found, &column.data_type) {
+        Ok(coerce::cast(expr, &column.data_type))
+    } else {
+        Err(BindError::TypeMismatch {
+            column: column.name.clone(),
+            expected: column.data_type.to_string(),
+            found: found.to_string(),
+        })
+    }
+}
+
+/// Describe a projected expression as a result column. Plain column
+/// references keep their name and table; other expressions are named by
+/// their alias, or without one by their aggregate or scalar function or
+/// `?column?`.
+fn output_column(
+    expr: &BoundExpr,
+    ty: DataType,
+    alias: Option<String>,
+    scope: &ColumnScope,
+) -> OutputColumn {
+    let source = match expr {
+        BoundExpr::Column { from, column_id } => scope.output_column(*from, *column_id),
+        _ => None,
+    };
+    let unnamed = match expr {
+        BoundExpr::Aggregate {
+            func: AggregateFunc::User(f),
+            ..
+        } => f.name().to_lowercase(),
+        BoundExpr::Aggregate { func, .. } => format!("{:?}", func).to_lowercase(),
+        // `||` is bound as a function too, but written as an operator.
+        BoundExpr::Function { func, .. } if func.name() != "||" => func.name().to_lowercase(),
+        BoundExpr::Subquery {
+            kind: BoundSubqueryKind::Exists,
+            ..
+        } => "exists".into(),
+        _ => "?column?".into(),
+    };
+
+    OutputColumn {
+        name: alias
+            .or_else(|| source.as_ref().map(|c| c.name.clone()))
+            .unwrap_or(unnamed),
+        data_type: ty,
+        nullable: is_nullable(expr, scope),
+        table: source.and_then(|c| c.table),
+    }
+}
+
+/// Whether `expr` can evaluate to NULL: it is NULL itself, reads a
+/// nullable column, is an aggregate other than COUNT, which is NULL over
+/// no values, a subquery other than EXISTS, or a function call.
+fn is_nullable(expr: &BoundExpr, scope: &ColumnScope) -> bool {
+    match expr {
+        BoundExpr::Column { from, column_id } => scope
+            .output_column(*from, *column_id)
+            .is_none_or(|c| c.nullable),
+        BoundExpr::Literal(v) => v.is_null(),
+        BoundExpr::Null => true,
+        BoundExpr::Unary {
+            op: IrUnaryOp::IsNull | IrUnaryOp::IsNotNull,
+            ..
+        }
+        | BoundExpr::Binary {
+            op: IrBinaryOp::IsDistinctFrom | IrBinaryOp::IsNotDistinctFrom,
+            ..
+        } => false,
+        BoundExpr::Unary { expr, .. } | BoundExpr::Cast { expr, .. } => is_nullable(expr, scope),
+        BoundExpr::Binary { left, right, .. } => {
+            is_nullable(left, scope) || is_nullable(right, scope)
+        }
+        BoundExpr::Function { .. } => true,
+        // NULL when no branch is taken and there is no ELSE.
+        BoundExpr::Case {
+            branches,
+            else_result,
+            ..
+        } => {
+            else_result.as_ref().is_none_or(|e| is_nullable(e, scope))
+                || branches.iter().any(|(_, then)| is_nullable(then, scope))
+        }
+        BoundExpr::InList { expr, list } => {
+            is_nullable(expr, scope) || list.iter().any(|e| is_nullable(e, scope))
+        }
+        BoundExpr::Like { expr, pattern, .. } => {
+            is_nullable(expr, scope) || is_nullable(pattern, scope)
+        }
+        BoundExpr::Aggregate { func, .. } => *func != AggregateFunc::Count,
+        BoundExpr::Subquery { kind, .. } => *kind != BoundSubqueryKind::Exists,
+        BoundExpr::OuterColumn { .. } => true,
+    }
+}
@@ -2,14 +2,22 @@
 //!
 //! Fully resolved, planner-facing representation.
 
+use std::sync::Arc;
+
 use crate::catalog::ids::{ColumnId, IndexId, TableId};
+use crate::frontend::sql::ast::TransactionStmt;
+use crate::functions::scalar::ScalarFunction;
 use crate::ir::expr::{BinaryOp, UnaryOp};
-use crate::ir::plan::JoinType;
+use crate::ir::plan::{AggregateFunc, FromId, JoinType, SetOpType};
+use crate::types::datatype::DataType;
+use crate::types::schema::OutputColumn;
 use crate::types::value::Value;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BoundExpr {
+    /// Column of the FROM item `from`.
     Column {
+        from: FromId,
         column_id: ColumnId,
     },
 
@@ -26,13 +34,120 @@ pub enum BoundExpr {
         right: Box<BoundExpr>,
     },
 
+    /// Conversion of `expr` to type `to`.
+    Cast {
+        expr: Box<BoundExpr>,
+        to: DataType,
+    },
+
+    /// Call of the scalar function `func`.
+    Function {
+        func: Arc<ScalarFunction>,
+        args: Vec<BoundExpr>,
+    },
+
+    /// CASE; a simple CASE has an `operand` its WHEN values are compared
+    /// with.
+    Case {
+        operand: Option<Box<BoundExpr>>,
+        branches: Vec<(BoundExpr, BoundExpr)>,
+        else_result: Option<Box<BoundExpr>>,
+    },
+
+    /// `expr IN (list)`, all of one type.
+    InList {
+        expr: Box<BoundExpr>,
+        list: Vec<BoundExpr>,
+    },
+
+    /// `expr LIKE pattern`, with `escape` making the character after it
+    /// match itself.
+    Like {
+        expr: Box<BoundExpr>,
+        pattern: Box<BoundExpr>,
+        escape: Option<char>,
+        case_insensitive: bool,
+    },
+
+    /// Aggregate call; `arg` is `None` for `COUNT(*)`.
+    Aggregate {
+        func: AggregateFunc,
+        arg: Option<Box<BoundExpr>>,
+        distinct: bool,
+    },
+
+    /// Nested query. `params` are the values of the enclosing query it
+    /// reads, in the order of its `OuterColumn` parameters.
+    Subquery {
+        kind: BoundSubqueryKind,
+        select: Box<BoundSelect>,
+        params: Vec<BoundExpr>,
+    },
+
+    /// Column of an enclosing query, read inside a subquery through its
+    /// `param`th parameter.
+    OuterColumn {
+        from: FromId,
+        column_id: ColumnId,
+        param: usize,
+    },
+
     Null,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoundSubqueryKind {
+    Scalar,
+    Exists,
+    /// The expression is evaluated in the enclosing query.
+    In(Box<BoundExpr>),
+}
+
+impl BoundExpr {
+    pub fn contains_aggregate(&self) -> bool {
+        match self {
+            BoundExpr::Aggregate { .. } => true,
+            BoundExpr::Unary { expr, .. } | BoundExpr::Cast { expr, .. } => {
+                expr.contains_aggregate()
+            }
+            BoundExpr::Binary { left, right, .. } => {
+                left.contains_aggregate() || right.contains_aggregate()
+            }
+            BoundExpr::Function { args, .. } => args.iter().any(BoundExpr::contains_aggregate),
+            BoundExpr::Case {
+                operand,
+                branches,
+                else_result,
+            } => operand
+                .iter()
+                .chain(else_result)
+                .map(|e| &**e)
+                .chain(branches.iter().flat_map(|(when, then)| [when, then]))
+                .any(BoundExpr::contains_aggregate),
+            BoundExpr::InList { expr, list } => {
+                expr.contains_aggregate() || list.iter().any(BoundExpr::contains_aggregate)
+            }
+            BoundExpr::Like { expr, pattern, .. } => {
+                expr.contains_aggregate() || pattern.contains_aggregate()
+            }
+            // Aggregates inside a subquery belong to it.
+            BoundExpr::Subquery { kind, .. } => match kind {
+                BoundSubqueryKind::In(expr) => expr.contains_aggregate(),
+                BoundSubqueryKind::Scalar | BoundSubqueryKind::Exists => false,
+            },
+            BoundExpr::Column { .. }
+            | BoundExpr::OuterColumn { .. }
+            | BoundExpr::Literal(_)
+            | BoundExpr::Null => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum BoundFrom {
     Table {
         table_id: TableId,
+        from: FromId,
     },
 
     Join {
@@ -41,18 +156,105 @@ pub enum BoundFrom {
         on: BoundExpr,
         join_type: JoinType,
     },
+
+    /// A CTE, or a parenthesized query, read in FROM. Its query's columns
+    /// are read as `columns` of `from`.
+    Cte {
+        cte: Box<BoundCte>,
+        from: FromId,
+        columns: Vec<ColumnId>,
+    },
+
+    /// Rows found so far by the recursive CTE `id`, read by its recursive
+    /// term. Their columns are described by `output` and read as `columns`
+    /// of `from`.
+    WorkTable {
+        id: usize,
+        output: Vec<OutputColumn>,
+        from: FromId,
+        columns: Vec<ColumnId>,
+    },
+
+    /// Rows of two queries combined, their columns read as `columns` of
+    /// `from`.
+    SetOp {
+        op: SetOpType,
+        all: bool,
+        left: Box<BoundSelect>,
+        right: Box<BoundSelect>,
+        from: FromId,
+        columns: Vec<ColumnId>,
+    },
 }
 
-#[derive(Debug)]
+/// Query a CTE stands for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundCte {
+    pub query: BoundSelect,
+    pub recursive: Option<BoundRecursiveTerm>,
+}
+
+/// Query run over the rows a recursive CTE found last, until it finds no
+/// new ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundRecursiveTerm {
+    /// Tells the work tables of recursive CTEs apart.
+    pub id: usize,
+    /// Keep duplicate rows, as `UNION ALL`.
+    pub all: bool,
+    pub query: BoundSelect,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct BoundSelect {
+    pub distinct: Option<BoundDistinct>,
     pub projection: Vec<BoundExpr>,
+    /// Result column for each projected expression.
+    pub output: Vec<OutputColumn>,
     pub from: BoundFrom,
     pub selection: Option<BoundExpr>,
+    pub group_by: Vec<BoundExpr>,
+    pub having: Option<BoundExpr>,
     pub order_by: Vec<(BoundExpr, bool)>,
     pub limit: Option<u64>,
     pub offset: Option<u64>,
 }
 
+impl BoundSelect {
+    /// Whether rows are grouped before projection: the query has GROUP BY
+    /// or HAVING, or uses aggregates.
+    pub fn is_grouped(&self) -> bool {
+        !self.group_by.is_empty()
+            || self.having.is_some()
+            || self.projection.iter().any(BoundExpr::contains_aggregate)
+            || self.order_by.iter().any(|(e, _)| e.contains_aggregate())
+            || self
+                .distinct
+                .iter()
+                .flat_map(BoundDistinct::exprs)
+                .any(BoundExpr::contains_aggregate)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoundDistinct {
+    /// Each distinct output row once.
+    Rows,
+    /// The first row, in ORDER BY's order, of each distinct value of these.
+    On(Vec<BoundExpr>),
+}
+
+impl BoundDistinct {
+    /// Expressions read from the query's rows to tell them apart, other
+    /// than the projection.
+    pub fn exprs(&self) -> &[BoundExpr] {
+        match self {
+            BoundDistinct::Rows => &[],
+            BoundDistinct::On(exprs) => exprs,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct BoundInsert {
     pub table_id: TableId,
@@ -97,7 +299,7 @@ pub struct BoundDropIndex {
 
 #[derive(Debug)]
 pub enum BoundStatement {
-    Select(BoundSelect),
+    Select(Box<BoundSelect>),
     Insert(BoundInsert),
     Update(BoundUpdate),
     Delete(BoundDelete),
@@ -107,6 +309,9 @@ pub enum BoundStatement {
     CreateIndex(BoundCreateIndex),
     DropIndex(BoundDropIndex),
 
+    /// Nothing to resolve; passed through to the database as-is.
+    Transaction(TransactionStmt),
+
     Explain {
         analyze: bool,
         stmt: Box<BoundStatement>,
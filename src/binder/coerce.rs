@@ -0,0 +1,218 @@
+//! Implicit type conversions.
+//!
+//! Where values of different types meet, such as the operands of an
+//! operator, the arguments of a function, a value stored in a column, or
+//! the columns of the queries a set operation combines, the binder converts
+//! them to one type with explicit `Cast` nodes, so execution only sees
+//! operands of the types it was told about. Types widen along this lattice:
+//!
+//! ```text
+//! Int16 ──> Int32 ──> Int64 ───────> Float64
+//!   └────────┴─────> Float32 ─────────┘
+//! Date ──> Timestamp
+//! Varchar(n) ──> Varchar
+//! ```
+//!
+//! NULL converts to any type, and `Int64` meets `Float32` at `Float64`.
+//! A literal that the type of the other side holds exactly is converted
+//! instead of that side, so `small_column = 1` still compares the column as
+//! it is stored. For arithmetic this applies to integers only, so
+//! `int_column + 1` stays INT. A string literal compared with a date or
+//! timestamp is read as one.
+
+use crate::binder::bound::BoundExpr;
+use crate::functions::scalar::{ArgType, ReturnType, Signature};
+use crate::ir::expr::UnaryOp;
+use crate::types::datatype::DataType;
+use crate::types::value::Value;
+use crate::util::numeric::float_as_int;
+use crate::util::temporal::MICROS_PER_DAY;
+
+pub(crate) fn is_numeric(ty: &DataType) -> bool {
+    matches!(
+        ty,
+        DataType::Int16 | DataType::Int32 | DataType::Int64 | DataType::Float32 | DataType::Float64
+    )
+}
+
+pub(crate) fn is_integer(ty: &DataType) -> bool {
+    matches!(ty, DataType::Int16 | DataType::Int32 | DataType::Int64)
+}
+
+/// The narrowest type both `a` and `b` widen to, if they have one.
+pub(crate) fn common_type(a: &DataType, b: &DataType) -> Option<DataType> {
+    use DataType::*;
+    match (a, b) {
+        (a, b) if a == b => Some(a.clone()),
+        (Null, ty) | (ty, Null) => Some(ty.clone()),
+        (Varchar { .. }, Varchar { .. }) => Some(Varchar { max_len: None }),
+        (Date | Timestamp, Date | Timestamp) => Some(Timestamp),
+        (Int64, Float32) | (Float32, Int64) => Some(Float64),
+        (a, b) if is_numeric(a) && is_numeric(b) => {
+            let rank = |ty: &DataType| {
+                [Int16, Int32, Int64, Float32, Float64]
+                    .iter()
+                    .position(|t| t == ty)
+            };
+            Some(if rank(a) >= rank(b) { a } else { b }.clone())
+        }
+        _ => None,
+    }
+}
+
+/// `expr`, of type `from`, converted to the wider type `to`.
+pub(crate) fn widen(expr: BoundExpr, from: &DataType, to: &DataType) -> BoundExpr {
+    match (from, to) {
+        (DataType::Null, _) | (DataType::Varchar { .. }, DataType::Varchar { max_len: None }) => {
+            expr
+        }
+        (from, to) if from == to => expr,
+        _ => cast(expr, to),
+    }
+}
+
+/// `expr` converted to `to` when evaluated.
+pub(crate) fn cast(expr: BoundExpr, to: &DataType) -> BoundExpr {
+    BoundExpr::Cast {
+        expr: Box::new(expr),
+        to: to.clone(),
+    }
+}
+
+/// Whether a value of type `found` can be stored in a column of type
+/// `column`: numbers in numeric columns, strings in string columns and
+/// dates in timestamp columns. Whether the value fits is checked as it is
+/// converted.
+pub(crate) fn assignable(found: &DataType, column: &DataType) -> bool {
+    use DataType::*;
+    match (found, column) {
+        (Null, _) => true,
+        (Varchar { .. }, Varchar { .. }) => true,
+        (Date, Timestamp) => true,
+        (f, c) => f == c || (is_numeric(f) && is_numeric(c)),
+    }
+}
+
+/// Whether `CAST` converts values of type `from` to `to`. Any value has a
+/// text form and may be read from one; other conversions are between
+/// numbers, between numbers and booleans, and between dates and timestamps.
+pub(crate) fn castable(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+    match (from, to) {
+        (Null, _) | (_, Varchar { .. }) | (Varchar { .. }, _) => true,
+        (f, t) if f == t => true,
+        (f, t) if is_numeric(f) && is_numeric(t) => true,
+        (Boolean, t) | (t, Boolean) if is_integer(t) => true,
+        (Date | Timestamp, Date | Timestamp) => true,
+        _ => false,
+    }
+}
+
+/// Types to convert arguments of types `found` to in a call to a function
+/// with `signature`, and the type of its result, if it takes them.
+pub(crate) fn match_signature(
+    signature: &Signature,
+    found: &[DataType],
+) -> Option<(Vec<DataType>, DataType)> {
+    let fixed = signature.args.len();
+    if found.len() < fixed || (signature.variadic.is_none() && found.len() > fixed) {
+        return None;
+    }
+    let expected: Vec<&ArgType> = signature
+        .args
+        .iter()
+        .chain(signature.variadic.iter().cycle().take(found.len() - fixed))
+        .collect();
+
+    for (arg, ty) in expected.iter().zip(found) {
+        let takes = match arg {
+            ArgType::Exact(t) => common_type(ty, t).as_ref() == Some(t),
+            ArgType::Numeric => is_numeric(ty) || *ty == DataType::Null,
+            ArgType::Any => true,
+        };
+        if !takes {
+            return None;
+        }
+    }
+
+    // Arguments of no exact type share the type they all widen to.
+    let common = match &signature.returns {
+        ReturnType::Common => Some(
+            expected
+                .iter()
+                .zip(found)
+                .filter(|(arg, _)| !matches!(arg, ArgType::Exact(_)))
+                .try_fold(DataType::Null, |common, (_, ty)| common_type(&common, ty))?,
+        ),
+        ReturnType::Fixed(_) => None,
+    };
+    let targets = expected
+        .iter()
+        .zip(found)
+        .map(|(arg, ty)| match (arg, &common) {
+            (ArgType::Exact(t), _) => t.clone(),
+            (_, Some(common)) => common.clone(),
+            (_, None) => ty.clone(),
+        })
+        .collect();
+    let returns = match &signature.returns {
+        ReturnType::Fixed(ty) => ty.clone(),
+        ReturnType::Common => common.unwrap_or(DataType::Null),
+    };
+    Some((targets, returns))
+}
+
+/// `expr` as a literal of type `to`, if it is a literal whose value that
+/// type holds exactly.
+pub(crate) fn fit_literal(expr: &BoundExpr, to: &DataType) -> Option<BoundExpr> {
+    let value = match expr {
+        BoundExpr::Literal(v) => v.clone(),
+        BoundExpr::Unary {
+            op: UnaryOp::Neg,
+            expr,
+        } => match &**expr {
+            BoundExpr::Literal(Value::Int64(i)) => Value::Int64(i.checked_neg()?),
+            BoundExpr::Literal(Value::Float64(f)) => Value::Float64(-f),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    exactly(&value, to).map(BoundExpr::Literal)
+}
+
+/// `value` as a value of type `to`, if it converts without loss.
+fn exactly(value: &Value, to: &DataType) -> Option<Value> {
+    let int = match value {
+        Value::Int16(i) => Some(*i as i64),
+        Value::Int32(i) => Some(*i as i64),
+        Value::Int64(i) => Some(*i),
+        Value::Float32(f) => float_as_int(*f as f64),
+        Value::Float64(f) => float_as_int(*f),
+        _ => None,
+    };
+    // Past 2^53 not every integer is a float.
+    let float = match value {
+        Value::Int16(i) => Some(*i as f64),
+        Value::Int32(i) => Some(*i as f64),
+        Value::Int64(i) if i.unsigned_abs() <= 1 << 53 => Some(*i as f64),
+        Value::Float32(f) => Some(*f as f64),
+        Value::Float64(f) => Some(*f),
+        _ => None,
+    };
+    match to {
+        DataType::Int16 => i16::try_from(int?).ok().map(Value::Int16),
+        DataType::Int32 => i32::try_from(int?).ok().map(Value::Int32),
+        DataType::Int64 => int.map(Value::Int64),
+        DataType::Float32 => float
+            .filter(|f| (*f as f32) as f64 == *f)
+            .map(|f| Value::Float32(f as f32)),
+        DataType::Float64 => float.map(Value::Float64),
+        DataType::Timestamp => match value {
+            Value::Date(d) => (*d as i64)
+                .checked_mul(MICROS_PER_DAY)
+                .map(Value::Timestamp),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -28,6 +28,43 @@ pub enum BindError {
         left: DataType,
         right: DataType,
     },
+    /// A `CAST` between types with no conversion between them.
+    InvalidCast {
+        from: DataType,
+        to: DataType,
+    },
+
+    UnknownFunction(String),
+    InvalidFunctionCall {
+        function: String,
+        reason: String,
+    },
+    /// An aggregate used where rows are not grouped yet, such as WHERE.
+    MisplacedAggregate(&'static str),
+    /// A column used outside an aggregate in a grouped query without being
+    /// one of its group keys.
+    UngroupedColumn(String),
+    /// A subquery used as a value or with IN that returns other than one
+    /// column.
+    SubqueryColumnCount(usize),
+    /// Two CTEs of one WITH clause with the same name.
+    DuplicateCte(String),
+    /// SELECT DISTINCT sorted by something other than its output.
+    DistinctOrderBy,
+    /// SELECT DISTINCT ON not sorted by its expressions first.
+    DistinctOnOrderBy,
+    /// Queries combined by a set operation with different numbers of
+    /// columns.
+    SetOpColumnCount(String),
+    /// Queries combined by a set operation with columns of different types
+    /// at the same position.
+    SetOpTypeMismatch {
+        op: String,
+        left: DataType,
+        right: DataType,
+    },
+    /// A LIKE escape that is neither empty nor one character.
+    InvalidEscape(String),
 }
 
 impl fmt::Display for BindError {
@@ -55,8 +92,48 @@ impl fmt::Display for BindError {
             BindError::TypeMismatchUnary { op, found } => {
                 write!(f, "type mismatch in {}: found {}", op, found)
             }
+            BindError::InvalidCast { from, to } => {
+                write!(f, "cannot cast type {} to {}", from, to)
+            }
             BindError::EmptyProject => write!(f, "projection list cannot be empty"),
             BindError::NotImplemented(msg) => write!(f, "not implemented: {}", msg),
+            BindError::UnknownFunction(name) => write!(f, "function '{}' does not exist", name),
+            BindError::InvalidFunctionCall { function, reason } => {
+                write!(f, "invalid call to {}: {}", function, reason)
+            }
+            BindError::MisplacedAggregate(clause) => {
+                write!(f, "aggregate functions are not allowed in {}", clause)
+            }
+            BindError::UngroupedColumn(c) => write!(
+                f,
+                "column '{}' must appear in GROUP BY or be used in an aggregate function",
+                c
+            ),
+            BindError::SubqueryColumnCount(n) => {
+                write!(f, "subquery must return one column, not {}", n)
+            }
+            BindError::DuplicateCte(name) => {
+                write!(f, "WITH query name '{}' specified more than once", name)
+            }
+            BindError::DistinctOrderBy => write!(
+                f,
+                "for SELECT DISTINCT, ORDER BY expressions must appear in select list"
+            ),
+            BindError::DistinctOnOrderBy => write!(
+                f,
+                "SELECT DISTINCT ON expressions must match initial ORDER BY expressions"
+            ),
+            BindError::SetOpColumnCount(op) => {
+                write!(f, "each {} query must have the same number of columns", op)
+            }
+            BindError::SetOpTypeMismatch { op, left, right } => {
+                write!(f, "{} types {} and {} cannot be matched", op, left, right)
+            }
+            BindError::InvalidEscape(escape) => write!(
+                f,
+                "invalid escape string '{}': must be empty or one character",
+                escape
+            ),
         }
     }
 }
@@ -1,5 +1,8 @@
+mod bind_cte;
 pub mod bind_expr;
+mod bind_set_op;
 pub mod bind_stmt;
 pub mod bound;
+mod coerce;
 pub mod errors;
 mod scope;
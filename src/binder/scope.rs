@@ -1,10 +1,17 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, ops::Range};
 
 use crate::{
     binder::{bind_stmt::Binder, errors::BindError},
-    catalog::{column::ColumnMeta, ids::ColumnId},
+    catalog::{
+        column::ColumnMeta,
+        ids::{ColumnId, TableId},
+    },
     frontend::sql::ast::FromItem,
-    types::{datatype::DataType, schema::Schema},
+    ir::plan::FromId,
+    types::{
+        datatype::DataType,
+        schema::{OutputColumn, Schema},
+    },
 };
 
 impl Schema {
@@ -22,39 +29,261 @@ impl Schema {
 }
 
 /// Column resolution scope.
-/// Maps visible column names to ColumnId + DataType.
+/// Maps visible column names to the FROM item and ColumnId they are read
+/// as, and their DataType, in FROM order.
 #[derive(Debug)]
 pub struct ColumnScope {
-    columns: HashMap<String, Vec<(ColumnId, DataType)>>,
+    columns: Vec<ScopeColumn>,
+    /// Columns of the enclosing queries of a subquery, nearest first.
+    outer: Vec<ScopeColumn>,
+    /// Outer columns the query reads, with their depth, in the order of
+    /// the parameters they are passed in as.
+    correlated: RefCell<Vec<(FromId, ColumnId, usize)>>,
+}
+
+#[derive(Debug, Clone)]
+struct ScopeColumn {
+    /// Table name, or its alias when it has one.
+    table: String,
+    /// Table the values are read from; `None` for computed values.
+    source: Option<TableId>,
+    /// FROM item the column is read through.
+    from: FromId,
+    meta: ColumnMeta,
+    /// Merged into the other side of a `JOIN ... USING`: only reachable
+    /// through its qualifier, and left out of `*`.
+    merged: bool,
+    /// How many queries out the column comes from; 0 for the query's own.
+    depth: usize,
 }
 
 impl ColumnScope {
     pub fn new() -> Self {
         Self {
-            columns: HashMap::new(),
+            columns: Vec::new(),
+            outer: Vec::new(),
+            correlated: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Scope of a subquery of the query `outer` belongs to. Its columns are
+    /// visible behind the subquery's own.
+    pub fn nested(outer: &ColumnScope) -> Self {
+        let visible = outer.columns.iter().chain(&outer.outer);
+        Self {
+            columns: Vec::new(),
+            outer: visible
+                .map(|c| ScopeColumn {
+                    depth: c.depth + 1,
+                    ..c.clone()
+                })
+                .collect(),
+            correlated: RefCell::new(Vec::new()),
         }
     }
 
+    /// Make `column` of table `table_id`, read through `from`, visible
+    /// qualified by `table`.
     pub fn add_column(
         &mut self,
-        name: String,
-        id: ColumnId,
-        ty: DataType,
+        table: &str,
+        table_id: TableId,
+        from: FromId,
+        column: &ColumnMeta,
     ) -> Result<(), BindError> {
-        self.columns.entry(name).or_default().push((id, ty));
+        self.columns.push(ScopeColumn {
+            table: table.to_string(),
+            source: Some(table_id),
+            from,
+            meta: column.clone(),
+            merged: false,
+            depth: 0,
+        });
         Ok(())
     }
 
-    pub fn resolve(&self, name: &str) -> Result<(ColumnId, DataType), BindError> {
-        match self.columns.get(name) {
-            None => Err(BindError::UnknownColumn(name.to_string())),
-            Some(cols) if cols.len() == 1 => Ok(cols[0].clone()),
-            Some(_) => Err(BindError::AmbiguousColumn(name.to_string())),
+    /// Make a column of a CTE visible as `id` of `from`, qualified by
+    /// `table`.
+    pub fn add_cte_column(
+        &mut self,
+        table: &str,
+        from: FromId,
+        id: ColumnId,
+        column: &OutputColumn,
+    ) {
+        self.columns.push(ScopeColumn {
+            table: table.to_string(),
+            source: column.table,
+            from,
+            meta: ColumnMeta {
+                id,
+                name: column.name.clone(),
+                data_type: column.data_type.clone(),
+                nullable: column.nullable,
+            },
+            merged: false,
+            depth: 0,
+        });
+    }
+
+    /// Drop the query's own columns, keeping what it read from enclosing
+    /// queries. The queries a set operation combines each see their own
+    /// FROM, but share parameters.
+    pub fn clear(&mut self) {
+        self.columns.clear();
+    }
+
+    /// Number of columns in scope. Columns are added in FROM order, so a
+    /// join's left side is everything before the length taken between
+    /// binding its two sides.
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Resolve an unqualified `name` among the columns in `range`, returning
+    /// its position in the scope too.
+    pub fn resolve_in(
+        &self,
+        range: Range<usize>,
+        name: &str,
+    ) -> Result<(usize, FromId, ColumnId, DataType), BindError> {
+        let mut matches = self.columns[range.clone()]
+            .iter()
+            .zip(range)
+            .filter(|(c, _)| c.meta.name == name && !c.merged);
+
+        match (matches.next(), matches.next()) {
+            (None, _) => Err(BindError::UnknownColumn(name.to_string())),
+            (Some((c, i)), None) => Ok((i, c.from, c.meta.id, c.meta.data_type.clone())),
+            (Some(_), Some(_)) => Err(BindError::AmbiguousColumn(name.to_string())),
+        }
+    }
+
+    /// Hide the column at `position` behind the one it was merged with.
+    pub fn merge(&mut self, position: usize) {
+        self.columns[position].merged = true;
+    }
+
+    /// Columns in `range` may be NULL-padded by an outer join.
+    pub fn make_nullable(&mut self, range: Range<usize>) {
+        for c in &mut self.columns[range] {
+            c.meta.nullable = true;
         }
     }
 
-    pub fn iter_columns(&self) -> impl Iterator<Item = (ColumnId, DataType)> + '_ {
-        self.columns.values().flat_map(|v| v.iter().cloned())
+    /// Resolve `name`, or `table.name` when a qualifier is given.
+    pub fn resolve(
+        &self,
+        table: Option<&str>,
+        name: &str,
+    ) -> Result<(FromId, ColumnId, DataType), BindError> {
+        let mut matches =
+            self.columns
+                .iter()
+                .filter(|c| c.meta.name == name)
+                .filter(|c| match table {
+                    Some(t) => c.table == t,
+                    None => !c.merged,
+                });
+
+        let qualified = || match table {
+            Some(t) => format!("{}.{}", t, name),
+            None => name.to_string(),
+        };
+        match (matches.next(), matches.next()) {
+            (None, _) => Err(BindError::UnknownColumn(qualified())),
+            (Some(c), None) => Ok((c.from, c.meta.id, c.meta.data_type.clone())),
+            (Some(_), Some(_)) => Err(BindError::AmbiguousColumn(qualified())),
+        }
+    }
+
+    /// Resolve a column of an enclosing query, from the nearest one that
+    /// has it. Returns the parameter it is read through.
+    pub fn resolve_outer(
+        &self,
+        table: Option<&str>,
+        name: &str,
+    ) -> Result<(usize, FromId, ColumnId, DataType), BindError> {
+        let matches: Vec<_> = self
+            .outer
+            .iter()
+            .filter(|c| c.meta.name == name)
+            .filter(|c| match table {
+                Some(t) => c.table == t,
+                None => !c.merged,
+            })
+            .collect();
+        let qualified = || match table {
+            Some(t) => format!("{}.{}", t, name),
+            None => name.to_string(),
+        };
+
+        let Some(depth) = matches.iter().map(|c| c.depth).min() else {
+            return Err(BindError::UnknownColumn(qualified()));
+        };
+        let mut nearest = matches.iter().filter(|c| c.depth == depth);
+        match (nearest.next(), nearest.next()) {
+            (Some(c), None) => Ok((
+                self.correlate(c.from, c.meta.id, depth),
+                c.from,
+                c.meta.id,
+                c.meta.data_type.clone(),
+            )),
+            _ => Err(BindError::AmbiguousColumn(qualified())),
+        }
+    }
+
+    /// Parameter that column `id` of `from`, in the query `depth` levels
+    /// out, is read through.
+    pub fn correlate(&self, from: FromId, id: ColumnId, depth: usize) -> usize {
+        let mut correlated = self.correlated.borrow_mut();
+        match correlated.iter().position(|c| *c == (from, id, depth)) {
+            Some(param) => param,
+            None => {
+                correlated.push((from, id, depth));
+                correlated.len() - 1
+            }
+        }
+    }
+
+    /// Outer columns read through parameters, in parameter order, with how
+    /// many queries out each comes from.
+    pub fn correlated(&self) -> Vec<(FromId, ColumnId, usize)> {
+        self.correlated.borrow().clone()
+    }
+
+    pub fn has_outer(&self) -> bool {
+        !self.outer.is_empty()
+    }
+
+    /// Describe column `id` of `from` as it appears in a query result.
+    pub fn output_column(&self, from: FromId, id: ColumnId) -> Option<OutputColumn> {
+        self.columns
+            .iter()
+            .find(|c| c.from == from && c.meta.id == id)
+            .map(ScopeColumn::output_column)
+    }
+
+    pub fn iter_columns(&self) -> impl Iterator<Item = (FromId, ColumnId, OutputColumn)> + '_ {
+        self.columns
+            .iter()
+            .filter(|c| !c.merged)
+            .map(|c| (c.from, c.meta.id, c.output_column()))
+    }
+}
+
+impl ScopeColumn {
+    fn output_column(&self) -> OutputColumn {
+        OutputColumn {
+            name: self.meta.name.clone(),
+            data_type: self.meta.data_type.clone(),
+            nullable: self.meta.nullable,
+            table: self.source,
+        }
     }
 }
 
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
 use crate::catalog::column::ColumnMeta;
@@ -7,10 +8,11 @@ use crate::catalog::ids::*;
 use crate::catalog::index::{IndexEntry, IndexMeta};
 use crate::catalog::table::TableMeta;
 use crate::storage::buffer::pool::{BufferPool, BufferPoolHandle};
+use crate::storage::heap::heap_table::HeapTable;
 use crate::storage::index::btree::BTreeIndex;
 use crate::storage::index::btree::disk::BPlusTree;
 use crate::storage::index::index::Index;
-use crate::storage::pagemgr::file::FilePageManager;
+use crate::storage::page::page_id::PageId;
 use crate::types::datatype::DataType;
 use crate::types::schema::Schema;
 
@@ -19,16 +21,20 @@ pub struct TableStats {
     pub row_count: u64,
 }
 
+#[derive(Clone)]
 pub struct Catalog {
-    next_table_id: u32,
-    next_column_id: u32,
-    next_index_id: u32,
+    pub(crate) next_table_id: u32,
+    pub(crate) next_column_id: u32,
+    pub(crate) next_index_id: u32,
 
-    tables_by_id: HashMap<TableId, TableMeta>,
-    tables_by_name: HashMap<String, TableId>,
+    pub(crate) tables_by_id: HashMap<TableId, TableMeta>,
+    pub(crate) tables_by_name: HashMap<String, TableId>,
 
-    indexes_by_id: HashMap<IndexId, IndexEntry>,
-    indexes_by_name: HashMap<String, IndexId>,
+    pub(crate) indexes_by_id: HashMap<IndexId, IndexEntry>,
+    pub(crate) indexes_by_name: HashMap<String, IndexId>,
+
+    /// Pages currently holding the persisted catalog image.
+    pub(crate) catalog_pages: Vec<PageId>,
 }
 
 impl Catalog {
@@ -41,6 +47,7 @@ impl Catalog {
             tables_by_name: HashMap::new(),
             indexes_by_id: HashMap::new(),
             indexes_by_name: HashMap::new(),
+            catalog_pages: Vec::new(),
         }
     }
 
@@ -50,6 +57,7 @@ impl Catalog {
         &mut self,
         name: String,
         columns: Vec<(String, DataType, bool)>,
+        bp: BufferPoolHandle,
     ) -> Result<TableId, CatalogError> {
         if self.tables_by_name.contains_key(&name) {
             return Err(CatalogError::TableExists(name));
@@ -71,12 +79,17 @@ impl Catalog {
             });
         }
 
+        // Allocate the heap's root page up front so the table can always be
+        // reopened from its catalog entry.
+        let heap = HeapTable::create(table_id, bp)?;
+
         let meta = TableMeta {
             id: table_id,
             name: name.clone(),
             schema,
-            root_page: None,       // ADD THIS
+            root_page: Some(heap.root_page()),
             index_ids: Vec::new(), // ADD THIS
+            row_count: Arc::new(AtomicU64::new(0)),
         };
 
         self.tables_by_name.insert(name, table_id);
@@ -85,6 +98,16 @@ impl Catalog {
         Ok(table_id)
     }
 
+    /// Remove a table from the catalog. Its indexes must be dropped first.
+    pub fn drop_table(&mut self, id: TableId) -> Result<TableMeta, CatalogError> {
+        let meta = self
+            .tables_by_id
+            .remove(&id)
+            .ok_or_else(|| CatalogError::TableNotFound(format!("{:?}", id)))?;
+        self.tables_by_name.remove(&meta.name);
+        Ok(meta)
+    }
+
     // Add method to register index with table
     pub fn register_index_with_table(&mut self, table_id: TableId, index_id: IndexId) {
         if let Some(table) = self.tables_by_id.get_mut(&table_id) {
@@ -118,16 +141,18 @@ impl Catalog {
         let index_id = IndexId(self.next_index_id);
         self.next_index_id += 1;
 
+        // Create the actual B+Tree index
+        let tree = BPlusTree::new(100, bp)?; // order = 100
+
         let meta = IndexMeta {
             id: index_id,
             name: name.clone(),
             table_id,
             column_ids,
             unique,
+            root_page: tree.meta_page(),
         };
 
-        // Create the actual B+Tree index
-        let tree = BPlusTree::new(100, bp)?; // order = 100
         let index = Arc::new(Mutex::new(BTreeIndex::new(tree)));
 
         let entry = IndexEntry { meta, index };
@@ -138,6 +163,19 @@ impl Catalog {
         Ok(index_id)
     }
 
+    /// Remove an index from the catalog and from its table's index list.
+    pub fn drop_index(&mut self, id: IndexId) -> Result<IndexEntry, CatalogError> {
+        let entry = self
+            .indexes_by_id
+            .remove(&id)
+            .ok_or_else(|| CatalogError::IndexNotFound(format!("{:?}", id)))?;
+        self.indexes_by_name.remove(&entry.meta.name);
+        if let Some(table) = self.tables_by_id.get_mut(&entry.meta.table_id) {
+            table.index_ids.retain(|i| *i != id);
+        }
+        Ok(entry)
+    }
+
     // Add this method for getting index by name
     pub fn get_index_by_name(&self, name: &str) -> Option<&IndexEntry> {
         self.indexes_by_name
@@ -145,12 +183,13 @@ impl Catalog {
             .and_then(|id| self.indexes_by_id.get(id))
     }
 
-    // Add this method for table statistics (needed by optimizer)
+    /// Statistics the optimizer estimates the size of `table_id` from.
     pub fn table_stats(&self, table_id: TableId) -> TableStats {
-        // For now, return dummy stats. In Phase 2, this will be persisted
-        TableStats {
-            row_count: 1000, // placeholder
-        }
+        let row_count = self
+            .tables_by_id
+            .get(&table_id)
+            .map_or(0, |t| t.row_count.load(Ordering::Relaxed));
+        TableStats { row_count }
     }
     pub fn get_index_by_id(&self, id: IndexId) -> Option<&IndexEntry> {
         self.indexes_by_id.get(&id)
@@ -9,6 +9,12 @@ pub enum CatalogError {
     IndexExists(String),
     IndexNotFound(String),
     Storage(StorageError),
+
+    /// On-disk catalog could not be decoded.
+    Corrupted(String),
+
+    /// On-disk catalog was written by a newer format version.
+    UnsupportedVersion(u32),
 }
 
 impl From<StorageError> for CatalogError {
@@ -19,11 +25,15 @@ impl From<StorageError> for CatalogError {
 impl fmt::Display for CatalogError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            CatalogError::TableExists(_) => todo!(),
-            CatalogError::TableNotFound(_) => todo!(),
-            CatalogError::IndexExists(_) => todo!(),
-            CatalogError::IndexNotFound(_) => todo!(),
-            CatalogError::Storage(storage_error) => todo!(),
+            CatalogError::TableExists(t) => write!(f, "table '{}' already exists", t),
+            CatalogError::TableNotFound(t) => write!(f, "table '{}' does not exist", t),
+            CatalogError::IndexExists(i) => write!(f, "index '{}' already exists", i),
+            CatalogError::IndexNotFound(i) => write!(f, "index '{}' does not exist", i),
+            CatalogError::Storage(e) => write!(f, "catalog storage error: {}", e),
+            CatalogError::Corrupted(reason) => write!(f, "catalog corrupted: {}", reason),
+            CatalogError::UnsupportedVersion(v) => {
+                write!(f, "unsupported catalog format version {}", v)
+            }
         }
     }
 }
@@ -2,19 +2,21 @@ use std::sync::{Arc, Mutex};
 
 use crate::{
     catalog::ids::{ColumnId, IndexId, TableId},
-    storage::index::index::Index,
+    storage::{index::index::Index, page::page_id::PageId},
 };
 
+#[derive(Clone)]
 pub struct IndexEntry {
     pub meta: IndexMeta,
     pub index: Arc<Mutex<dyn Index>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IndexMeta {
     pub id: IndexId,
     pub name: String,
     pub table_id: TableId,
     pub column_ids: Vec<ColumnId>,
     pub unique: bool,
+    pub root_page: PageId, // B+Tree meta page
 }
@@ -0,0 +1,458 @@
+//! On-disk catalog image.
+//!
+//! Page 0 of the database file is the catalog superblock:
+//!
+//! ```text
+//! | magic "HELIUMDB" (8) | version u32 | head page u64 | payload len u32 | payload crc32 u32 | redo lsn u64 |
+//! ```
+//!
+//! `redo lsn` is the durable end of the write-ahead log when the image was
+//! written. Every page was flushed before the superblock flipped, so crash
+//! recovery never needs to replay records at or below it.
+//!
+//! The payload lives in a chain of catalog pages, each laid out as
+//! `| next page u64 | chunk len u16 | chunk bytes |`.
+//!
+//! Writes are shadowed: a new image goes to fresh pages, everything is
+//! flushed, and only then is the superblock rewritten to point at it. The
+//! superblock write is the commit point, so a crash leaves either the old
+//! or the new catalog, never a mix.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::catalog::catalog::Catalog;
+use crate::catalog::column::ColumnMeta;
+use crate::catalog::errors::CatalogError;
+use crate::catalog::ids::{ColumnId, IndexId, TableId};
+use crate::catalog::index::{IndexEntry, IndexMeta};
+use crate::catalog::table::TableMeta;
+use crate::storage::buffer::frame::PAGE_PAYLOAD_SIZE;
+use crate::storage::buffer::pool::{BufferPool, BufferPoolHandle};
+use crate::storage::index::btree::BTreeIndex;
+use crate::storage::index::btree::disk::BPlusTree;
+use crate::storage::page::page_id::PageId;
+use crate::txn::wal::record::Lsn;
+use crate::types::datatype::DataType;
+use crate::types::schema::Schema;
+use crate::util::bytes::{ByteReader, ByteWriter, DecodeResult};
+use crate::util::checksum::crc32;
+
+pub const SUPERBLOCK_PAGE: PageId = PageId(0);
+pub const CATALOG_MAGIC: &[u8; 8] = b"HELIUMDB";
+pub const CATALOG_FORMAT_VERSION: u32 = 1;
+
+const NO_PAGE: u64 = u64::MAX;
+const CHAIN_HEADER_SIZE: usize = 10;
+const CHUNK_CAPACITY: usize = PAGE_PAYLOAD_SIZE - CHAIN_HEADER_SIZE;
+
+struct Superblock {
+    version: u32,
+    head: Option<PageId>,
+    len: u32,
+    crc: u32,
+    redo_lsn: Lsn,
+}
+
+impl Catalog {
+    /// Read the catalog from the superblock, initializing an empty one if
+    /// the file is new.
+    pub fn load(bp: &BufferPoolHandle) -> Result<Catalog, CatalogError> {
+        let (pages, payload) = {
+            let mut pool = bp.lock().unwrap();
+
+            if pool.pm.num_pages() == 0 {
+                let pid = pool.allocate_page()?;
+                debug_assert_eq!(pid, SUPERBLOCK_PAGE);
+                write_superblock(
+                    &mut pool,
+                    &Superblock {
+                        version: CATALOG_FORMAT_VERSION,
+                        head: None,
+                        len: 0,
+                        crc: crc32(&[]),
+                        redo_lsn: Lsn::INVALID,
+                    },
+                )?;
+                pool.sync()?;
+                return Ok(Catalog::new());
+            }
+
+            let sb = read_superblock(&mut pool)?;
+            if sb.version > CATALOG_FORMAT_VERSION {
+                return Err(CatalogError::UnsupportedVersion(sb.version));
+            }
+
+            let (payload, pages) = read_chain(&mut pool, sb.head)?;
+            if payload.len() != sb.len as usize || crc32(&payload) != sb.crc {
+                return Err(CatalogError::Corrupted(
+                    "catalog payload checksum mismatch".into(),
+                ));
+            }
+            (pages, payload)
+        };
+
+        // Index trees lock the pool themselves, so decode without holding it.
+        let (mut catalog, free_pages) = decode(&payload, bp)
+            .map_err(|reason| CatalogError::Corrupted(format!("catalog payload: {}", reason)))?;
+        catalog.catalog_pages = pages;
+
+        bp.lock().unwrap().pm.set_free_pages(free_pages);
+        Ok(catalog)
+    }
+
+    /// Atomically replace the on-disk catalog with the current state.
+    ///
+    /// Also forces the log and flushes every dirty page, so heap and index
+    /// pages referenced by the new image are durable before it becomes
+    /// visible.
+    pub fn persist(&mut self, bp: &BufferPoolHandle) -> Result<(), CatalogError> {
+        self.persist_locked(&mut BufferPool::lock_quiesced(bp))
+    }
+
+    /// `persist` for a caller that already holds the buffer pool through
+    /// [`BufferPool::lock_quiesced`], and with it every page change, still
+    /// until it has logged its checkpoint.
+    pub fn persist_locked(&mut self, pool: &mut BufferPool) -> Result<(), CatalogError> {
+        let old_pages = self.catalog_pages.clone();
+        let redo_lsn = match pool.wal() {
+            Some(wal) => {
+                let mut wal = wal.lock().unwrap();
+                wal.flush()?;
+                wal.durable_lsn()
+            }
+            None => Lsn::INVALID,
+        };
+
+        // Size with the largest free list the image can carry: allocating
+        // the new chain only ever shrinks it.
+        let mut free = pool.pm.free_pages();
+        free.extend(old_pages.iter().copied());
+        let bound = encode(self, &free).len();
+        let needed = bound.div_ceil(CHUNK_CAPACITY).max(1);
+
+        let new_pages = (0..needed)
+            .map(|_| pool.allocate_page())
+            .collect::<Result<Vec<PageId>, _>>()?;
+
+        // The old chain stays live until the superblock flips, but it is
+        // recorded as free in the image that replaces it.
+        let mut free = pool.pm.free_pages();
+        free.extend(old_pages.iter().copied());
+        let payload = encode(self, &free);
+
+        let mut chunks = payload.chunks(CHUNK_CAPACITY);
+        for (i, pid) in new_pages.iter().enumerate() {
+            let chunk = chunks.next().unwrap_or(&[]);
+            let next = new_pages.get(i + 1).map(|p| p.0).unwrap_or(NO_PAGE);
+
+            let frame = pool.fetch_page(*pid)?;
+            frame.data.fill(0);
+            frame.data[0..8].copy_from_slice(&next.to_le_bytes());
+            frame.data[8..10].copy_from_slice(&(chunk.len() as u16).to_le_bytes());
+            frame.data[CHAIN_HEADER_SIZE..CHAIN_HEADER_SIZE + chunk.len()].copy_from_slice(chunk);
+            pool.unpin_page(*pid, true)?;
+        }
+
+        pool.flush_all()?;
+        pool.sync()?;
+
+        write_superblock(
+            pool,
+            &Superblock {
+                version: CATALOG_FORMAT_VERSION,
+                head: new_pages.first().copied(),
+                len: payload.len() as u32,
+                crc: crc32(&payload),
+                redo_lsn,
+            },
+        )?;
+        pool.sync()?;
+
+        for pid in old_pages {
+            pool.free_page(pid)?;
+        }
+        self.catalog_pages = new_pages;
+        Ok(())
+    }
+}
+
+fn write_superblock(pool: &mut BufferPool, sb: &Superblock) -> Result<(), CatalogError> {
+    let frame = pool.fetch_page(SUPERBLOCK_PAGE)?;
+    frame.data.fill(0);
+    frame.data[0..8].copy_from_slice(CATALOG_MAGIC);
+    frame.data[8..12].copy_from_slice(&sb.version.to_le_bytes());
+    frame.data[12..20].copy_from_slice(&sb.head.map(|p| p.0).unwrap_or(NO_PAGE).to_le_bytes());
+    frame.data[20..24].copy_from_slice(&sb.len.to_le_bytes());
+    frame.data[24..28].copy_from_slice(&sb.crc.to_le_bytes());
+    frame.data[28..36].copy_from_slice(&sb.redo_lsn.0.to_le_bytes());
+    pool.unpin_page(SUPERBLOCK_PAGE, true)?;
+    pool.flush_page(SUPERBLOCK_PAGE)?;
+    Ok(())
+}
+
+fn read_superblock(pool: &mut BufferPool) -> Result<Superblock, CatalogError> {
+    let frame = pool.fetch_page(SUPERBLOCK_PAGE)?;
+    let data = frame.data;
+    pool.unpin_page(SUPERBLOCK_PAGE, false)?;
+
+    if &data[0..8] != CATALOG_MAGIC {
+        return Err(CatalogError::Corrupted("missing catalog superblock".into()));
+    }
+
+    let head = u64::from_le_bytes(data[12..20].try_into().unwrap());
+    Ok(Superblock {
+        version: u32::from_le_bytes(data[8..12].try_into().unwrap()),
+        head: (head != NO_PAGE).then_some(PageId(head)),
+        len: u32::from_le_bytes(data[20..24].try_into().unwrap()),
+        crc: u32::from_le_bytes(data[24..28].try_into().unwrap()),
+        redo_lsn: Lsn(u64::from_le_bytes(data[28..36].try_into().unwrap())),
+    })
+}
+
+/// Log position up to which the last persisted catalog image guarantees
+/// all pages are on disk. `Lsn::INVALID` for a new database.
+pub fn redo_lsn(bp: &BufferPoolHandle) -> Result<Lsn, CatalogError> {
+    let mut pool = bp.lock().unwrap();
+    if pool.pm.num_pages() == 0 {
+        return Ok(Lsn::INVALID);
+    }
+    Ok(read_superblock(&mut pool)?.redo_lsn)
+}
+
+fn read_chain(
+    pool: &mut BufferPool,
+    head: Option<PageId>,
+) -> Result<(Vec<u8>, Vec<PageId>), CatalogError> {
+    let mut payload = Vec::new();
+    let mut pages = Vec::new();
+    let mut next = head;
+
+    while let Some(pid) = next {
+        if pages.contains(&pid) {
+            return Err(CatalogError::Corrupted(
+                "cycle in catalog page chain".into(),
+            ));
+        }
+
+        let frame = pool.fetch_page(pid)?;
+        let data = frame.data;
+        pool.unpin_page(pid, false)?;
+
+        let raw_next = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let len = u16::from_le_bytes(data[8..10].try_into().unwrap()) as usize;
+        if len > CHUNK_CAPACITY {
+            return Err(CatalogError::Corrupted(format!(
+                "catalog page {} chunk length {} out of range",
+                pid.0, len
+            )));
+        }
+
+        payload.extend_from_slice(&data[CHAIN_HEADER_SIZE..CHAIN_HEADER_SIZE + len]);
+        pages.push(pid);
+        next = (raw_next != NO_PAGE).then_some(PageId(raw_next));
+    }
+
+    Ok((payload, pages))
+}
+
+// ---------- payload codec (format version 1) ----------
+
+fn encode(catalog: &Catalog, free_pages: &[PageId]) -> Vec<u8> {
+    let mut w = ByteWriter::new();
+
+    w.put_u32(catalog.next_table_id);
+    w.put_u32(catalog.next_column_id);
+    w.put_u32(catalog.next_index_id);
+
+    w.put_u32(free_pages.len() as u32);
+    for pid in free_pages {
+        w.put_u64(pid.0);
+    }
+
+    // Sorted so identical catalogs produce identical images.
+    let mut tables: Vec<&TableMeta> = catalog.tables_by_id.values().collect();
+    tables.sort_by_key(|t| t.id.0);
+
+    w.put_u32(tables.len() as u32);
+    for table in tables {
+        w.put_u32(table.id.0);
+        w.put_str(&table.name);
+        w.put_u64(table.root_page.map(|p| p.0).unwrap_or(NO_PAGE));
+        w.put_u64(table.row_count.load(Ordering::Relaxed));
+
+        w.put_u32(table.schema.columns.len() as u32);
+        for col in &table.schema.columns {
+            w.put_u32(col.id.0);
+            w.put_str(&col.name);
+            encode_type(&mut w, &col.data_type);
+            w.put_bool(col.nullable);
+        }
+
+        w.put_u32(table.index_ids.len() as u32);
+        for id in &table.index_ids {
+            w.put_u32(id.0);
+        }
+    }
+
+    let mut indexes: Vec<&IndexMeta> = catalog.indexes_by_id.values().map(|e| &e.meta).collect();
+    indexes.sort_by_key(|m| m.id.0);
+
+    w.put_u32(indexes.len() as u32);
+    for meta in indexes {
+        w.put_u32(meta.id.0);
+        w.put_str(&meta.name);
+        w.put_u32(meta.table_id.0);
+        w.put_u32(meta.column_ids.len() as u32);
+        for col in &meta.column_ids {
+            w.put_u32(col.0);
+        }
+        w.put_bool(meta.unique);
+        w.put_u64(meta.root_page.0);
+    }
+
+    w.into_inner()
+}
+
+fn decode(payload: &[u8], bp: &BufferPoolHandle) -> DecodeResult<(Catalog, Vec<PageId>)> {
+    let mut catalog = Catalog::new();
+
+    // A freshly initialized file has an empty image.
+    if payload.is_empty() {
+        return Ok((catalog, Vec::new()));
+    }
+
+    let mut r = ByteReader::new(payload);
+
+    catalog.next_table_id = r.get_u32()?;
+    catalog.next_column_id = r.get_u32()?;
+    catalog.next_index_id = r.get_u32()?;
+
+    let free_count = r.get_u32()?;
+    let mut free_pages = Vec::with_capacity(free_count as usize);
+    for _ in 0..free_count {
+        free_pages.push(PageId(r.get_u64()?));
+    }
+
+    let table_count = r.get_u32()?;
+    for _ in 0..table_count {
+        let id = TableId(r.get_u32()?);
+        let name = r.get_str()?;
+        let root = r.get_u64()?;
+        let row_count = r.get_u64()?;
+
+        let mut schema = Schema::new();
+        let col_count = r.get_u32()?;
+        for _ in 0..col_count {
+            schema.push(ColumnMeta {
+                id: ColumnId(r.get_u32()?),
+                name: r.get_str()?,
+                data_type: decode_type(&mut r)?,
+                nullable: r.get_bool()?,
+            });
+        }
+
+        let index_count = r.get_u32()?;
+        let mut index_ids = Vec::with_capacity(index_count as usize);
+        for _ in 0..index_count {
+            index_ids.push(IndexId(r.get_u32()?));
+        }
+
+        catalog.tables_by_name.insert(name.clone(), id);
+        catalog.tables_by_id.insert(
+            id,
+            TableMeta {
+                id,
+                name,
+                schema,
+                root_page: (root != NO_PAGE).then_some(PageId(root)),
+                index_ids,
+                row_count: Arc::new(AtomicU64::new(row_count)),
+            },
+        );
+    }
+
+    let index_count = r.get_u32()?;
+    for _ in 0..index_count {
+        let id = IndexId(r.get_u32()?);
+        let name = r.get_str()?;
+        let table_id = TableId(r.get_u32()?);
+        let col_count = r.get_u32()?;
+        let mut column_ids = Vec::with_capacity(col_count as usize);
+        for _ in 0..col_count {
+            column_ids.push(ColumnId(r.get_u32()?));
+        }
+        let unique = r.get_bool()?;
+        let root_page = PageId(r.get_u64()?);
+
+        let tree =
+            BPlusTree::open(root_page, bp.clone()).map_err(|_| "unreadable index meta page")?;
+
+        catalog.indexes_by_name.insert(name.clone(), id);
+        catalog.indexes_by_id.insert(
+            id,
+            IndexEntry {
+                meta: IndexMeta {
+                    id,
+                    name,
+                    table_id,
+                    column_ids,
+                    unique,
+                    root_page,
+                },
+                index: Arc::new(Mutex::new(BTreeIndex::new(tree))),
+            },
+        );
+    }
+
+    if r.remaining() != 0 {
+        return Err("trailing bytes after catalog image");
+    }
+
+    Ok((catalog, free_pages))
+}
+
+fn encode_type(w: &mut ByteWriter, ty: &DataType) {
+    match ty {
+        DataType::Int32 => w.put_u8(0),
+        DataType::Int64 => w.put_u8(1),
+        DataType::Float32 => w.put_u8(2),
+        DataType::Float64 => w.put_u8(3),
+        DataType::Boolean => w.put_u8(4),
+        DataType::Varchar { max_len } => {
+            w.put_u8(5);
+            w.put_bool(max_len.is_some());
+            w.put_u32(max_len.unwrap_or(0));
+        }
+        DataType::Date => w.put_u8(6),
+        DataType::Timestamp => w.put_u8(7),
+        DataType::Blob => w.put_u8(8),
+        DataType::Null => w.put_u8(9),
+        DataType::Int16 => w.put_u8(10),
+        DataType::Interval => w.put_u8(11),
+    }
+}
+
+fn decode_type(r: &mut ByteReader) -> DecodeResult<DataType> {
+    Ok(match r.get_u8()? {
+        0 => DataType::Int32,
+        1 => DataType::Int64,
+        2 => DataType::Float32,
+        3 => DataType::Float64,
+        4 => DataType::Boolean,
+        5 => {
+            let has_len = r.get_bool()?;
+            let len = r.get_u32()?;
+            DataType::Varchar {
+                max_len: has_len.then_some(len),
+            }
+        }
+        6 => DataType::Date,
+        7 => DataType::Timestamp,
+        8 => DataType::Blob,
+        9 => DataType::Null,
+        10 => DataType::Int16,
+        11 => DataType::Interval,
+        _ => return Err("unknown data type tag"),
+    })
+}
@@ -1,15 +1,20 @@
+use std::sync::{Arc, atomic::AtomicU64};
+
 use crate::catalog::column::ColumnMeta;
 use crate::catalog::ids::{ColumnId, IndexId, TableId};
 use crate::storage::page::page_id::PageId;
 use crate::types::schema::Schema;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TableMeta {
     pub id: TableId,
     pub name: String,
     pub schema: Schema,
     pub root_page: Option<PageId>, // First page of heap
     pub index_ids: Vec<IndexId>,
+    /// Rows in the heap, for the optimizer's estimates. Shared by every
+    /// copy of the catalog and kept by the heap as rows come and go.
+    pub row_count: Arc<AtomicU64>,
 }
 
 impl TableMeta {
@@ -21,4 +26,3 @@ impl TableMeta {
         self.schema.columns.iter().find(|c| c.id == id)
     }
 }
-
@@ -66,7 +66,7 @@ pub fn run() {
                 // ---------- Execute SQL ----------
                 match db.execute(&buffer) {
                     Ok(ExecutionResult::Query(res)) => {
-                        println!("{:?}", res);
+                        print_query(&res);
                     }
                     Ok(ExecutionResult::Mutation(res)) => {
                         println!("{:?}", res);
@@ -74,6 +74,9 @@ pub fn run() {
                     Ok(ExecutionResult::Definition(res)) => {
                         println!("{:?}", res);
                     }
+                    Ok(ExecutionResult::Transaction(res)) => {
+                        println!("{:?}", res);
+                    }
                     Err(err) => {
                         eprintln!("Error: {err}");
                     }
@@ -100,6 +103,42 @@ pub fn run() {
     }
 }
 
+/// Print a query result as a table headed by its column names.
+fn print_query(res: &QueryResult) {
+    let cells: Vec<Vec<String>> = res
+        .rows
+        .iter()
+        .map(|row| row.iter().map(|v| v.to_string()).collect())
+        .collect();
+
+    let mut widths: Vec<usize> = res.schema.names().map(|n| n.len()).collect();
+    for row in &cells {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.len());
+        }
+    }
+
+    let line = |cells: &mut dyn Iterator<Item = &str>| {
+        let padded: Vec<String> = cells
+            .zip(&widths)
+            .map(|(c, w)| format!("{:<w$}", c, w = *w))
+            .collect();
+        println!(" {} ", padded.join(" | "));
+    };
+
+    line(&mut res.schema.names());
+    let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    println!("-{}-", rule.join("-+-"));
+    for row in &cells {
+        line(&mut row.iter().map(String::as_str));
+    }
+    println!(
+        "({} row{})",
+        res.rows.len(),
+        if res.rows.len() == 1 { "" } else { "s" }
+    );
+}
+
 fn handle_meta_command(cmd: &str) -> bool {
     match cmd {
         ".exit" | ".quit" => {
@@ -0,0 +1,136 @@
+//! Conversion of values between types, for `CAST` and the casts the binder
+//! inserts where values of different types meet.
+
+use crate::execution::errors::{ConversionFailure, ExecutionError};
+use crate::execution::executor::ExecResult;
+use crate::types::datatype::DataType;
+use crate::types::value::Value;
+use crate::util::hex;
+use crate::util::numeric::float_as_int;
+use crate::util::temporal::{self, MICROS_PER_DAY};
+
+/// `value` as a value of type `to`. Floats are rounded to the nearest
+/// integer, and numbers and strings must fit the type.
+pub fn cast_value(value: Value, to: &DataType) -> ExecResult<Value> {
+    let fail = |value: Value, reason| ExecutionError::Conversion {
+        value,
+        to: to.clone(),
+        reason,
+    };
+
+    let converted = match (&value, to) {
+        (Value::Null, _) => return Ok(Value::Null),
+
+        (_, DataType::Varchar { max_len }) => {
+            let text = match &value {
+                Value::String(s) => s.clone(),
+                v => v.to_string(),
+            };
+            if max_len.is_some_and(|max| text.chars().count() > max as usize) {
+                return Err(fail(value, ConversionFailure::TooLong));
+            }
+            Ok(Value::String(text))
+        }
+
+        (Value::String(s), ty) => from_text(s.trim(), ty),
+
+        (v, DataType::Int16 | DataType::Int32 | DataType::Int64) => match v {
+            Value::Int16(i) => Ok(*i as i64),
+            Value::Int32(i) => Ok(*i as i64),
+            Value::Int64(i) => Ok(*i),
+            Value::Float32(f) => {
+                float_as_int((*f as f64).round()).ok_or(ConversionFailure::OutOfRange)
+            }
+            Value::Float64(f) => float_as_int(f.round()).ok_or(ConversionFailure::OutOfRange),
+            Value::Boolean(b) => Ok(*b as i64),
+            _ => Err(ConversionFailure::Unsupported),
+        }
+        .and_then(|i| integer_as(i, to)),
+
+        (v, DataType::Float32 | DataType::Float64) => match v {
+            Value::Int16(i) => Ok(*i as f64),
+            Value::Int32(i) => Ok(*i as f64),
+            Value::Int64(i) => Ok(*i as f64),
+            Value::Float32(f) => Ok(*f as f64),
+            Value::Float64(f) => Ok(*f),
+            _ => Err(ConversionFailure::Unsupported),
+        }
+        .and_then(|f| float_as(f, to)),
+
+        (Value::Boolean(b), DataType::Boolean) => Ok(Value::Boolean(*b)),
+        (Value::Int16(i), DataType::Boolean) => Ok(Value::Boolean(*i != 0)),
+        (Value::Int32(i), DataType::Boolean) => Ok(Value::Boolean(*i != 0)),
+        (Value::Int64(i), DataType::Boolean) => Ok(Value::Boolean(*i != 0)),
+
+        (Value::Date(d), DataType::Date) => Ok(Value::Date(*d)),
+        (Value::Timestamp(ts), DataType::Date) => i32::try_from(ts.div_euclid(MICROS_PER_DAY))
+            .map(Value::Date)
+            .map_err(|_| ConversionFailure::OutOfRange),
+
+        (Value::Timestamp(ts), DataType::Timestamp) => Ok(Value::Timestamp(*ts)),
+        (Value::Date(d), DataType::Timestamp) => (*d as i64)
+            .checked_mul(MICROS_PER_DAY)
+            .map(Value::Timestamp)
+            .ok_or(ConversionFailure::OutOfRange),
+
+        (Value::Blob(b), DataType::Blob) => Ok(Value::Blob(b.clone())),
+
+        _ => Err(ConversionFailure::Unsupported),
+    };
+
+    converted.map_err(|reason| fail(value, reason))
+}
+
+/// The value `text` spells in type `ty`.
+fn from_text(text: &str, ty: &DataType) -> Result<Value, ConversionFailure> {
+    let invalid = ConversionFailure::InvalidText;
+    match ty {
+        DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+            let i = text.parse::<i64>().map_err(|e| match e.kind() {
+                std::num::IntErrorKind::PosOverflow | std::num::IntErrorKind::NegOverflow => {
+                    ConversionFailure::OutOfRange
+                }
+                _ => invalid,
+            })?;
+            integer_as(i, ty)
+        }
+        DataType::Float32 | DataType::Float64 => float_as(text.parse().map_err(|_| invalid)?, ty),
+        DataType::Boolean => match text.to_ascii_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "on" | "1" => Ok(Value::Boolean(true)),
+            "false" | "f" | "no" | "n" | "off" | "0" => Ok(Value::Boolean(false)),
+            _ => Err(invalid),
+        },
+        DataType::Date => temporal::parse_date(text).map(Value::Date).ok_or(invalid),
+        DataType::Timestamp => temporal::parse_timestamp(text)
+            .map(Value::Timestamp)
+            .ok_or(invalid),
+        // Hex digits after `\x`, or else the bytes of the text itself.
+        DataType::Blob => match text.strip_prefix("\\x") {
+            Some(digits) => hex::decode(digits).map(Value::Blob).ok_or(invalid),
+            None => Ok(Value::Blob(text.as_bytes().to_vec())),
+        },
+        _ => Err(ConversionFailure::Unsupported),
+    }
+}
+
+fn integer_as(i: i64, ty: &DataType) -> Result<Value, ConversionFailure> {
+    match ty {
+        DataType::Int16 => i16::try_from(i)
+            .map(Value::Int16)
+            .map_err(|_| ConversionFailure::OutOfRange),
+        DataType::Int32 => i32::try_from(i)
+            .map(Value::Int32)
+            .map_err(|_| ConversionFailure::OutOfRange),
+        _ => Ok(Value::Int64(i)),
+    }
+}
+
+fn float_as(f: f64, ty: &DataType) -> Result<Value, ConversionFailure> {
+    match ty {
+        DataType::Float32 if f.is_finite() && !(f as f32).is_finite() => {
+            Err(ConversionFailure::OutOfRange)
+        }
+        DataType::Float32 => Ok(Value::Float32(f as f32)),
+        _ => Ok(Value::Float64(f)),
+    }
+}
@@ -1,24 +1,70 @@
-use std::collections::HashMap;
-
 use crate::{
     catalog::{catalog::Catalog, ids::TableId},
-    execution::errors::ExecutionStats,
-    storage::{buffer::pool::BufferPoolHandle, errors::StorageResult, heap::heap_table::HeapTable},
+    execution::{
+        errors::{ExecutionError, ExecutionStats},
+        eval_expr::eval_expr,
+        executor::ExecResult,
+    },
+    functions::scalar::CallContext,
+    ir::expr::Expr,
+    storage::{
+        errors::{StorageError, StorageResult},
+        heap::heap_table::HeapTable,
+        manager::StorageManager,
+        page::row_id::RowId,
+    },
+    txn::{
+        lock_manager::{LockMode, LockTarget},
+        snapshot::Snapshot,
+        transaction::TxnId,
+    },
+    types::value::Value,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, RwLock},
 };
-use std::sync::Arc;
+
+/// Default for `ExecutionContext::work_mem`.
+pub const DEFAULT_WORK_MEM: usize = 4 << 20;
 
 pub struct ExecutionContext<'a> {
     pub catalog: &'a Catalog,
-    pub buffer_pool: &'a BufferPoolHandle,
+    /// Where schema changes install the newest catalog, if `catalog` may be
+    /// replaced while the statement runs. A table lock is then only taken
+    /// while `catalog` is still the newest one.
+    pub installed: Option<&'a RwLock<Arc<Catalog>>>,
+    pub storage: &'a StorageManager,
+    /// Transaction that heap changes are logged and locks are taken under.
+    pub txn_id: TxnId,
+    /// What scans see; writes always apply to the newest row versions.
+    pub snapshot: Arc<Snapshot>,
     pub stats: ExecutionStats,
-    heap_tables: HashMap<TableId, Arc<HeapTable>>,
+    /// Bytes an operator may buffer before spilling to disk.
+    pub work_mem: usize,
+    /// Parameters of the subqueries being run, innermost last.
+    pub params: Vec<Vec<Value>>,
+    /// Values returned by subqueries without parameters, by subquery id.
+    pub subquery_results: HashMap<usize, Vec<Value>>,
+    /// Rows each running recursive CTE found in its last round.
+    pub work_tables: HashMap<usize, Vec<Vec<Value>>>,
+    /// What the statement's function calls read, taken when it starts.
+    pub call: CallContext,
 }
 
 impl<'a> ExecutionContext<'a> {
-    pub fn new(catalog: &'a Catalog, buffer_pool: &'a BufferPoolHandle) -> Self {
+    pub fn new(
+        catalog: &'a Catalog,
+        storage: &'a StorageManager,
+        txn_id: TxnId,
+        snapshot: Arc<Snapshot>,
+    ) -> Self {
         Self {
             catalog,
-            buffer_pool,
+            installed: None,
+            storage,
+            txn_id,
+            snapshot,
             stats: ExecutionStats {
                 rows_output: 0,
                 rows_scanned: 0,
@@ -26,16 +72,87 @@ impl<'a> ExecutionContext<'a> {
                 index_lookups: 0,
                 storage_ops: 0,
             },
-            heap_tables: HashMap::new(),
+            work_mem: DEFAULT_WORK_MEM,
+            params: Vec::new(),
+            subquery_results: HashMap::new(),
+            work_tables: HashMap::new(),
+            call: CallContext::now(),
         }
     }
 
     pub fn get_heap(&mut self, table_id: TableId) -> StorageResult<Arc<HeapTable>> {
-        if !self.heap_tables.contains_key(&table_id) {
-            let heap = HeapTable::open(table_id, self.buffer_pool.clone())?;
-            self.heap_tables.insert(table_id, Arc::new(heap));
+        let table = self
+            .catalog
+            .get_table_by_id(table_id)
+            .ok_or(StorageError::MissingHeap {
+                table_id: table_id.0,
+            })?;
+        self.storage.get_table(table)
+    }
+
+    /// Lock `target` for the statement's transaction, waiting if needed.
+    /// Fails with `SchemaChanged` if a schema change installed a new catalog
+    /// before the lock was granted: the statement was bound against the
+    /// old one and would miss what changed.
+    pub fn lock(&self, target: LockTarget, mode: LockMode) -> ExecResult<()> {
+        self.storage.locks().lock(self.txn_id, target, mode)?;
+        if let Some(installed) = self.installed
+            && !std::ptr::eq(&**installed.read().unwrap(), self.catalog)
+        {
+            return Err(ExecutionError::SchemaChanged);
         }
-        Ok(self.heap_tables.get(&table_id).unwrap().clone())
+        Ok(())
     }
-}
 
+    /// Exclusively lock the rows of `heap` that `predicate` selects and
+    /// return their newest contents.
+    ///
+    /// A row another transaction is still changing is locked if either the
+    /// version the snapshot sees or the newest one matches, since the
+    /// outcome is only known once that transaction finishes. Every row is
+    /// read and matched again once its lock is held.
+    pub fn lock_rows(
+        &mut self,
+        heap: &HeapTable,
+        predicate: Option<&Expr>,
+    ) -> ExecResult<Vec<(RowId, Vec<Value>)>> {
+        let selects = |values: &[Value], ctx: &mut Self| -> ExecResult<bool> {
+            match predicate {
+                Some(pred) => Ok(matches!(
+                    eval_expr(pred, values, ctx)?,
+                    Value::Boolean(true)
+                )),
+                None => Ok(true),
+            }
+        };
+
+        let mut candidates = BTreeSet::new();
+        for entry in heap.scan() {
+            let (rid, row) = entry?;
+            if selects(&row.values, self)? {
+                candidates.insert(rid);
+            }
+        }
+        for rid in heap.changed_rows(&self.snapshot)? {
+            if let Some(row) = heap.fetch_at(rid, &self.snapshot)?
+                && selects(&row.values, self)?
+            {
+                candidates.insert(rid);
+            }
+        }
+
+        let mut rows = Vec::with_capacity(candidates.len());
+        for rid in candidates {
+            self.lock(LockTarget::Row(rid), LockMode::Exclusive)?;
+            let row = match heap.fetch(rid) {
+                Ok(row) => row,
+                Err(StorageError::InvalidRowId { .. }) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if selects(&row.values, self)? {
+                rows.push((rid, row.values));
+            }
+        }
+        Ok(rows)
+    }
+}
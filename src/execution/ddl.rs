@@ -0,0 +1,154 @@
+//! DDL execution.
+//!
+//! CREATE/DROP statements do not produce a logical plan; they are applied
+//! directly to the catalog and storage here. The caller is responsible for
+//! persisting the catalog afterwards, and only then for freeing the pages
+//! of the objects dropped: until the new catalog is durable the old one may
+//! still be the one recovered, and it refers to them.
+
+use crate::{
+    api::errors::{DefinitionAction, DefinitionResult},
+    binder::bound::{BoundCreateIndex, BoundStatement},
+    catalog::{catalog::Catalog, ids::IndexId},
+    execution::errors::{ExecutionError, ExecutionResult, ExecutionResultType, ExecutionStats},
+    storage::{
+        index::btree::{disk::BPlusTree, key::IndexKey},
+        manager::StorageManager,
+        page::page_id::PageId,
+    },
+};
+
+/// Apply `stmt` to `catalog`. The pages of the objects it drops are added
+/// to `freed` for the caller to free.
+pub fn execute_ddl(
+    stmt: BoundStatement,
+    catalog: &mut Catalog,
+    storage: &StorageManager,
+    freed: &mut Vec<PageId>,
+) -> ExecutionResultType {
+    let bp = storage.buffer_pool().clone();
+
+    let (action, object) = match stmt {
+        BoundStatement::CreateTable(s) => {
+            let columns = s
+                .schema
+                .columns
+                .into_iter()
+                .map(|c| (c.name, c.data_type, c.nullable))
+                .collect();
+            catalog.create_table(s.table_name.clone(), columns, bp)?;
+            (DefinitionAction::CreateTable, s.table_name)
+        }
+
+        BoundStatement::DropTable(s) => {
+            let index_ids = catalog
+                .get_table_by_id(s.table_id)
+                .ok_or(ExecutionError::TableNotFound {
+                    table_id: s.table_id,
+                })?
+                .index_ids
+                .clone();
+            for index_id in index_ids {
+                drop_index(catalog, storage, index_id, freed)?;
+            }
+
+            let meta = catalog.drop_table(s.table_id)?;
+            freed.extend(storage.drop_table(&meta)?);
+            (DefinitionAction::DropTable, meta.name)
+        }
+
+        BoundStatement::CreateIndex(s) => {
+            let name = s.name.clone();
+            create_index(catalog, storage, s)?;
+            (DefinitionAction::CreateIndex, name)
+        }
+
+        BoundStatement::DropIndex(s) => {
+            let name = drop_index(catalog, storage, s.index_id, freed)?;
+            (DefinitionAction::DropIndex, name)
+        }
+
+        other => {
+            return Err(ExecutionError::InvalidPlan {
+                reason: format!("not a DDL statement: {:?}", other),
+            });
+        }
+    };
+
+    Ok(ExecutionResult::Definition(DefinitionResult {
+        action,
+        object,
+        stats: ExecutionStats::default(),
+    }))
+}
+
+/// Create the index and fill it from the rows already in the table. If the
+/// backfill fails the half-built index is dropped again.
+fn create_index(
+    catalog: &mut Catalog,
+    storage: &StorageManager,
+    stmt: BoundCreateIndex,
+) -> Result<(), ExecutionError> {
+    let index_id = catalog.create_index(
+        stmt.name,
+        stmt.table_id,
+        vec![stmt.column_id],
+        false,
+        storage.buffer_pool().clone(),
+    )?;
+    catalog.register_index_with_table(stmt.table_id, index_id);
+
+    if let Err(e) = backfill_index(catalog, storage, index_id) {
+        // No persisted catalog has the index yet, so its pages can go now.
+        let mut pages = Vec::new();
+        drop_index(catalog, storage, index_id, &mut pages)?;
+        storage.free_pages(&pages)?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn backfill_index(
+    catalog: &Catalog,
+    storage: &StorageManager,
+    index_id: IndexId,
+) -> Result<(), ExecutionError> {
+    let entry = catalog
+        .get_index_by_id(index_id)
+        .ok_or(ExecutionError::IndexNotFound { index_id })?;
+    let table =
+        catalog
+            .get_table_by_id(entry.meta.table_id)
+            .ok_or(ExecutionError::TableNotFound {
+                table_id: entry.meta.table_id,
+            })?;
+    let pos = table
+        .schema
+        .position(entry.meta.column_ids[0])
+        .ok_or_else(|| ExecutionError::index_key_error(index_id, "indexed column not in table"))?;
+
+    let heap = storage.get_table(table)?;
+    let mut index = entry.index.lock().unwrap();
+    for entry in heap.scan() {
+        let (rid, row) = entry?;
+        let key = IndexKey::try_from(&row.values[pos])
+            .map_err(|e| ExecutionError::index_key_error(index_id, e))?;
+        index.insert(key, rid)?;
+    }
+    Ok(())
+}
+
+/// Remove an index from the catalog and add its pages to `freed`. Returns
+/// its name.
+fn drop_index(
+    catalog: &mut Catalog,
+    storage: &StorageManager,
+    index_id: IndexId,
+    freed: &mut Vec<PageId>,
+) -> Result<String, ExecutionError> {
+    let entry = catalog.drop_index(index_id)?;
+    // Drop the live handle before reopening the tree from its meta page.
+    drop(entry.index);
+    freed.extend(BPlusTree::open(entry.meta.root_page, storage.buffer_pool().clone())?.pages()?);
+    Ok(entry.meta.name)
+}
@@ -1,18 +1,30 @@
 use crate::api::errors::{MutationKind, MutationResult, QueryResult};
+use crate::catalog::catalog::Catalog;
 use crate::execution::context::ExecutionContext;
 use crate::execution::errors::{ExecutionError, ExecutionResult, ExecutionResultType};
 use crate::execution::executor::{ExecResult, Executor};
+use crate::execution::operators::aggregate::AggregateExecutor;
 use crate::execution::operators::delete::DeleteExecutor;
+use crate::execution::operators::distinct::DistinctExecutor;
 use crate::execution::operators::filter::FilterExecutor;
+use crate::execution::operators::hash_join::HashJoinExecutor;
+use crate::execution::operators::index_join::IndexJoinExecutor;
 use crate::execution::operators::index_scan::IndexScanExecutor;
 use crate::execution::operators::insert::InsertExecutor;
 use crate::execution::operators::join::JoinExecutor;
 use crate::execution::operators::limit::LimitExecutor;
 use crate::execution::operators::project::ProjectExecutor;
+use crate::execution::operators::recursive_cte::RecursiveCteExecutor;
 use crate::execution::operators::scan::ScanExecutor;
+use crate::execution::operators::set_op::SetOpExecutor;
 use crate::execution::operators::sort::SortExecutor;
 use crate::execution::operators::update::UpdateExecutor;
-use crate::ir::plan::LogicalPlan;
+use crate::execution::operators::work_table::WorkTableExecutor;
+use crate::functions::scalar::ReturnType;
+use crate::ir::expr::Expr;
+use crate::ir::plan::{AggregateFunc, JoinSide, JoinType, LogicalPlan};
+use crate::types::datatype::DataType;
+use crate::types::schema::{OutputColumn, OutputSchema};
 
 pub fn execute_plan(plan: LogicalPlan, ctx: &mut ExecutionContext) -> ExecutionResultType {
     match plan {
@@ -22,7 +34,15 @@ pub fn execute_plan(plan: LogicalPlan, ctx: &mut ExecutionContext) -> ExecutionR
         | LogicalPlan::Sort { .. }
         | LogicalPlan::Limit { .. }
         | LogicalPlan::Join { .. }
-        | LogicalPlan::IndexScan { .. } => execute_query(plan, ctx),
+        | LogicalPlan::HashJoin { .. }
+        | LogicalPlan::IndexJoin { .. }
+        | LogicalPlan::Aggregate { .. }
+        | LogicalPlan::IndexScan { .. }
+        | LogicalPlan::Derived { .. }
+        | LogicalPlan::RecursiveCte { .. }
+        | LogicalPlan::WorkTable { .. }
+        | LogicalPlan::SetOp { .. }
+        | LogicalPlan::Distinct { .. } => execute_query(plan, ctx),
 
         LogicalPlan::Insert { .. } | LogicalPlan::Update { .. } | LogicalPlan::Delete { .. } => {
             execute_mutation(plan, ctx)
@@ -31,7 +51,7 @@ pub fn execute_plan(plan: LogicalPlan, ctx: &mut ExecutionContext) -> ExecutionR
 }
 
 pub fn execute_query(plan: LogicalPlan, ctx: &mut ExecutionContext) -> ExecutionResultType {
-    let schema = plan_output_schema(&plan, &ctx.catalog)?;
+    let schema = plan_output_schema(&plan, ctx.catalog)?;
 
     let mut root = build_executor(plan, ctx)?;
     root.open(ctx)?;
@@ -82,14 +102,15 @@ pub fn build_executor(
     ctx: &mut ExecutionContext,
 ) -> ExecResult<Box<dyn Executor>> {
     Ok(match plan {
-        LogicalPlan::Scan { table_id } => Box::new(ScanExecutor::new(table_id)),
+        LogicalPlan::Scan { table_id, .. } => Box::new(ScanExecutor::new(table_id)),
 
         LogicalPlan::IndexScan {
             table_id,
             index_id,
             predicate,
+            ..
         } => {
-            let _table = ctx
+            let table = ctx
                 .catalog
                 .get_table_by_id(table_id)
                 .ok_or(ExecutionError::TableNotFound { table_id })?;
@@ -99,16 +120,28 @@ pub fn build_executor(
                 .get_index_by_id(index_id)
                 .ok_or(ExecutionError::IndexNotFound { index_id })?;
 
+            let key_pos = table
+                .schema
+                .position(index.meta.column_ids[0])
+                .ok_or_else(|| {
+                    ExecutionError::index_key_error(index_id, "indexed column not in table")
+                })?;
+
             let heap = ctx.get_heap(table_id)?;
 
-            Box::new(IndexScanExecutor::new(index.index.clone(), heap, predicate))
+            Box::new(IndexScanExecutor::new(
+                index.index.clone(),
+                heap,
+                predicate,
+                key_pos,
+            ))
         }
 
         LogicalPlan::Filter { input, predicate } => {
             Box::new(FilterExecutor::new(build_executor(*input, ctx)?, predicate))
         }
 
-        LogicalPlan::Project { input, exprs } => {
+        LogicalPlan::Project { input, exprs, .. } => {
             Box::new(ProjectExecutor::new(build_executor(*input, ctx)?, exprs))
         }
 
@@ -116,6 +149,12 @@ pub fn build_executor(
             Box::new(SortExecutor::new(build_executor(*input, ctx)?, keys))
         }
 
+        LogicalPlan::Distinct { input, on, sorted } => Box::new(DistinctExecutor::new(
+            build_executor(*input, ctx)?,
+            on,
+            sorted,
+        )),
+
         LogicalPlan::Limit {
             input,
             limit,
@@ -131,11 +170,125 @@ pub fn build_executor(
             right,
             on,
             join_type,
-        } => Box::new(JoinExecutor::new(
+        } => {
+            let left_width = plan_output_schema(&left, ctx.catalog)?.len();
+            let right_width = plan_output_schema(&right, ctx.catalog)?.len();
+            Box::new(JoinExecutor::new(
+                build_executor(*left, ctx)?,
+                build_executor(*right, ctx)?,
+                on,
+                join_type,
+                left_width,
+                right_width,
+            ))
+        }
+
+        LogicalPlan::HashJoin {
+            left,
+            right,
+            left_keys,
+            right_keys,
+            residual,
+            join_type,
+            build,
+        } => {
+            let left_width = plan_output_schema(&left, ctx.catalog)?.len();
+            let right_width = plan_output_schema(&right, ctx.catalog)?.len();
+            Box::new(HashJoinExecutor::new(
+                build_executor(*left, ctx)?,
+                build_executor(*right, ctx)?,
+                left_keys,
+                right_keys,
+                residual,
+                join_type,
+                build,
+                left_width,
+                right_width,
+            ))
+        }
+
+        LogicalPlan::IndexJoin {
+            outer,
+            inner_table,
+            inner_from: _,
+            index_id,
+            outer_key,
+            residual,
+            join_type,
+            inner_side,
+        } => {
+            let table =
+                ctx.catalog
+                    .get_table_by_id(inner_table)
+                    .ok_or(ExecutionError::TableNotFound {
+                        table_id: inner_table,
+                    })?;
+
+            let index = ctx
+                .catalog
+                .get_index_by_id(index_id)
+                .ok_or(ExecutionError::IndexNotFound { index_id })?;
+
+            let key_pos = table
+                .schema
+                .position(index.meta.column_ids[0])
+                .ok_or_else(|| {
+                    ExecutionError::index_key_error(index_id, "indexed column not in table")
+                })?;
+
+            let inner_width = table.schema.len();
+            let index = index.index.clone();
+            let heap = ctx.get_heap(inner_table)?;
+
+            Box::new(IndexJoinExecutor::new(
+                build_executor(*outer, ctx)?,
+                index,
+                heap,
+                outer_key,
+                key_pos,
+                residual,
+                join_type,
+                inner_side,
+                inner_width,
+            ))
+        }
+
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => Box::new(AggregateExecutor::new(
+            build_executor(*input, ctx)?,
+            group_by,
+            aggregates,
+        )),
+
+        LogicalPlan::Derived { input, .. } => build_executor(*input, ctx)?,
+
+        LogicalPlan::RecursiveCte {
+            id,
+            anchor,
+            recursive,
+            all,
+        } => Box::new(RecursiveCteExecutor::new(
+            id,
+            build_executor(*anchor, ctx)?,
+            *recursive,
+            all,
+        )),
+
+        LogicalPlan::WorkTable { id, .. } => Box::new(WorkTableExecutor::new(id)),
+
+        LogicalPlan::SetOp {
+            op,
+            all,
+            left,
+            right,
+        } => Box::new(SetOpExecutor::new(
+            op,
+            all,
             build_executor(*left, ctx)?,
             build_executor(*right, ctx)?,
-            on,
-            join_type,
         )),
 
         LogicalPlan::Insert { table_id, rows } => Box::new(InsertExecutor::new(table_id, rows)),
@@ -153,48 +306,156 @@ pub fn build_executor(
     })
 }
 
-fn plan_output_schema(
-    plan: &LogicalPlan,
-    catalog: &crate::catalog::catalog::Catalog,
-) -> ExecResult<crate::types::schema::Schema> {
-    use crate::types::schema::Schema;
-
+/// Shape of the rows `plan` produces.
+fn plan_output_schema(plan: &LogicalPlan, catalog: &Catalog) -> ExecResult<OutputSchema> {
     match plan {
-        LogicalPlan::Scan { table_id } => {
+        LogicalPlan::Scan { table_id, .. } | LogicalPlan::IndexScan { table_id, .. } => {
             let table =
                 catalog
                     .get_table_by_id(*table_id)
                     .ok_or(ExecutionError::TableNotFound {
                         table_id: *table_id,
                     })?;
-            Ok(table.schema.clone())
+            Ok(OutputSchema::of_table(*table_id, &table.schema))
         }
 
-        LogicalPlan::Project { input: _, exprs } => {
-            let mut schema = Schema::new();
-            for (idx, _expr) in exprs.iter().enumerate() {
-                use crate::catalog::column::ColumnMeta;
-                use crate::catalog::ids::ColumnId;
-                use crate::types::datatype::DataType;
-
-                schema.push(ColumnMeta {
-                    id: ColumnId(idx as u32),
-                    name: format!("col_{}", idx),
-                    data_type: DataType::Int64,
-                    nullable: true,
-                });
-            }
-            Ok(schema)
-        }
+        LogicalPlan::Project { columns, .. } => Ok(OutputSchema {
+            columns: columns.clone(),
+        }),
 
         LogicalPlan::Filter { input, .. }
         | LogicalPlan::Sort { input, .. }
-        | LogicalPlan::Limit { input, .. } => plan_output_schema(input, catalog),
+        | LogicalPlan::Distinct { input, .. }
+        | LogicalPlan::Limit { input, .. }
+        | LogicalPlan::Derived { input, .. }
+        | LogicalPlan::RecursiveCte { anchor: input, .. }
+        | LogicalPlan::SetOp { left: input, .. } => plan_output_schema(input, catalog),
+
+        LogicalPlan::WorkTable { columns, .. } => Ok(OutputSchema {
+            columns: columns.clone(),
+        }),
+
+        // The left columns followed by the right ones; an outer join pads
+        // the side it keeps unmatched rows of with NULLs on the other.
+        LogicalPlan::Join {
+            left,
+            right,
+            join_type,
+            ..
+        }
+        | LogicalPlan::HashJoin {
+            left,
+            right,
+            join_type,
+            ..
+        } => Ok(join_schema(
+            plan_output_schema(left, catalog)?,
+            plan_output_schema(right, catalog)?,
+            *join_type,
+        )),
 
-        _ => {
-            let schema = Schema::new();
-            Ok(schema)
+        LogicalPlan::IndexJoin {
+            outer,
+            inner_table,
+            inner_from,
+            join_type,
+            inner_side,
+            ..
+        } => {
+            let outer = plan_output_schema(outer, catalog)?;
+            let inner = plan_output_schema(
+                &LogicalPlan::Scan {
+                    table_id: *inner_table,
+                    from: *inner_from,
+                },
+                catalog,
+            )?;
+            Ok(match inner_side {
+                JoinSide::Left => join_schema(inner, outer, *join_type),
+                JoinSide::Right => join_schema(outer, inner, *join_type),
+            })
+        }
+
+        // Group keys that are plain input columns keep their shape; the
+        // rest are computed. Aggregates other than COUNT are NULL over
+        // empty groups.
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => {
+            let input = plan_output_schema(input, catalog)?;
+            let input_column = |e: &Expr| match e {
+                Expr::ColumnSlot { index } => input.columns.get(*index).cloned(),
+                _ => None,
+            };
+
+            let mut columns: Vec<_> = group_by
+                .iter()
+                .map(|e| {
+                    input_column(e).unwrap_or_else(|| computed_column("?column?", DataType::Null))
+                })
+                .collect();
+            columns.extend(aggregates.iter().map(|a| {
+                let arg_type = a.arg.as_ref().and_then(input_column).map(|c| c.data_type);
+                let (name, data_type) = match &a.func {
+                    AggregateFunc::Count => {
+                        return OutputColumn {
+                            nullable: false,
+                            ..computed_column("count", DataType::Int64)
+                        };
+                    }
+                    AggregateFunc::User(f) => {
+                        let data_type = match &f.signature().returns {
+                            ReturnType::Fixed(ty) => Some(ty.clone()),
+                            ReturnType::Common => arg_type,
+                        };
+                        let name = f.name().to_ascii_lowercase();
+                        return computed_column(&name, data_type.unwrap_or(DataType::Null));
+                    }
+                    AggregateFunc::Sum => ("sum", arg_type),
+                    AggregateFunc::Avg => ("avg", Some(DataType::Float64)),
+                    AggregateFunc::Min => ("min", arg_type),
+                    AggregateFunc::Max => ("max", arg_type),
+                };
+                computed_column(name, data_type.unwrap_or(DataType::Null))
+            }));
+            Ok(OutputSchema { columns })
+        }
+
+        LogicalPlan::Insert { .. } | LogicalPlan::Update { .. } | LogicalPlan::Delete { .. } => {
+            Ok(OutputSchema::new())
+        }
+    }
+}
+
+fn join_schema(
+    mut left: OutputSchema,
+    mut right: OutputSchema,
+    join_type: JoinType,
+) -> OutputSchema {
+    let (left_padded, right_padded) = match join_type {
+        // Only the left rows come out.
+        JoinType::Semi | JoinType::Anti => return left,
+        JoinType::Inner => (false, false),
+        JoinType::Left => (false, true),
+        JoinType::Right => (true, false),
+        JoinType::Full => (true, true),
+    };
+    for (schema, padded) in [(&mut left, left_padded), (&mut right, right_padded)] {
+        if padded {
+            schema.columns.iter_mut().for_each(|c| c.nullable = true);
         }
     }
+    left.columns.extend(right.columns);
+    left
 }
 
+fn computed_column(name: &str, data_type: DataType) -> OutputColumn {
+    OutputColumn {
+        name: name.into(),
+        data_type,
+        nullable: true,
+        table: None,
+    }
+}
@@ -1,11 +1,16 @@
 use core::fmt;
 
 use crate::{
-    api::errors::{DefinitionResult, MutationResult, QueryResult},
+    api::errors::{DefinitionResult, MutationResult, QueryResult, TransactionResult},
     binder::bound::BoundExpr,
-    catalog::ids::{IndexId, TableId},
+    catalog::{
+        errors::CatalogError,
+        ids::{IndexId, TableId},
+    },
+    functions::errors::FunctionError,
     storage::errors::StorageError,
-    types::value::Value,
+    txn::errors::LockError,
+    types::{datatype::DataType, value::Value},
 };
 
 pub type ExecutionResultType = Result<ExecutionResult, ExecutionError>;
@@ -20,6 +25,9 @@ pub enum ExecutionResult {
 
     /// CREATE / DROP (future-proof)
     Definition(DefinitionResult),
+
+    /// BEGIN / COMMIT / ROLLBACK / SAVEPOINT
+    Transaction(TransactionResult),
 }
 
 #[derive(Debug, Default, Clone)]
@@ -131,6 +139,10 @@ pub enum ExecutionError {
         column_count: usize,
     },
 
+    /// A schema change replaced the catalog the statement was bound against
+    /// before the statement locked a table it reads or writes.
+    SchemaChanged,
+
     // ----------------------------
     // Plan / Engine errors
     // ----------------------------
@@ -166,6 +178,19 @@ pub enum ExecutionError {
     },
     UnboundColumn,
 
+    /// Function `name` failed on the arguments it was called with.
+    Function {
+        name: String,
+        error: FunctionError,
+    },
+
+    /// `value` could not be converted to type `to`.
+    Conversion {
+        value: Value,
+        to: DataType,
+        reason: ConversionFailure,
+    },
+
     // ----------------------------
     // Index & constraint errors
     // ----------------------------
@@ -178,15 +203,53 @@ pub enum ExecutionError {
     // Storage passthrough (boxed)
     // ----------------------------
     Storage(StorageError),
+    Catalog(CatalogError),
+    Lock(LockError),
     Internal(String),
 }
 
+/// Why a value could not be converted to another type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionFailure {
+    /// Text that does not spell a value of the type.
+    InvalidText,
+    /// A number outside the range of the type.
+    OutOfRange,
+    /// A string longer than the type allows.
+    TooLong,
+    /// No conversion between the two types exists.
+    Unsupported,
+}
+
+impl fmt::Display for ConversionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionFailure::InvalidText => write!(f, "invalid input syntax"),
+            ConversionFailure::OutOfRange => write!(f, "value out of range"),
+            ConversionFailure::TooLong => write!(f, "value too long"),
+            ConversionFailure::Unsupported => write!(f, "no conversion between the types"),
+        }
+    }
+}
+
 impl From<StorageError> for ExecutionError {
     fn from(err: StorageError) -> Self {
         ExecutionError::Storage(err)
     }
 }
 
+impl From<CatalogError> for ExecutionError {
+    fn from(err: CatalogError) -> Self {
+        ExecutionError::Catalog(err)
+    }
+}
+
+impl From<LockError> for ExecutionError {
+    fn from(err: LockError) -> Self {
+        ExecutionError::Lock(err)
+    }
+}
+
 impl ExecutionError {
     pub fn index_key_error(index_id: IndexId, msg: impl Into<String>) -> Self {
         ExecutionError::IndexViolation {
@@ -228,7 +291,12 @@ impl fmt::Display for ExecutionError {
                 "column index {} out of bounds ({} columns)",
                 index, column_count
             ),
+            ExecutionError::SchemaChanged => {
+                write!(f, "the schema changed after the statement was bound")
+            }
             ExecutionError::Storage(err) => write!(f, "storage error: {}", err),
+            ExecutionError::Catalog(err) => write!(f, "catalog error: {}", err),
+            ExecutionError::Lock(err) => write!(f, "lock error: {}", err),
             ExecutionError::InvalidExpression { reason } => {
                 write!(f, "invalid expression: {}", reason)
             }
@@ -237,6 +305,11 @@ impl fmt::Display for ExecutionError {
                 write!(f, "type mismatch in {}: {:?} and {:?}", op, left, right)
             }
             ExecutionError::UnboundColumn => write!(f, "unbound column reference"),
+            ExecutionError::Function { name, error } => write!(f, "{}: {}", name, error),
+            ExecutionError::Conversion { value, to, reason } => match value {
+                Value::String(s) => write!(f, "cannot convert '{}' to {}: {}", s, to, reason),
+                _ => write!(f, "cannot convert {} to {}: {}", value, to, reason),
+            },
             ExecutionError::Internal(msg) => write!(f, "internal error: {}", msg),
         }
     }
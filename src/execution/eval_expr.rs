@@ -1,42 +1,264 @@
-use crate::catalog::ids::ColumnId;
+use std::cmp::Ordering;
+
+use crate::execution::cast::cast_value;
+use crate::execution::context::ExecutionContext;
+use crate::execution::engine::build_executor;
 use crate::execution::errors::ExecutionError;
 use crate::execution::executor::ExecResult;
-use crate::ir::expr::{BinaryOp, Expr, UnaryOp};
+use crate::ir::expr::{BinaryOp, Expr, SubqueryKind, UnaryOp};
+use crate::ir::plan::LogicalPlan;
 use crate::types::value::Value;
+use crate::util::like;
+use crate::util::numeric::{cmp_float, cmp_int_float};
+use crate::util::temporal::{MICROS_PER_DAY, add_months};
 
-pub fn eval_expr(expr: &Expr, row: &[Value]) -> ExecResult<Value> {
+/// Evaluate `expr` on `row`. The context runs subqueries and holds the
+/// parameters passed into the one being run.
+pub fn eval_expr(expr: &Expr, row: &[Value], ctx: &mut ExecutionContext) -> ExecResult<Value> {
     match expr {
         Expr::Literal(v) => Ok(v.clone()),
         Expr::Null => Ok(Value::Null),
 
-        Expr::BoundColumn { column_id } => {
-            let ColumnId(idx) = column_id;
-            row.get(*idx as usize)
+        Expr::ColumnSlot { index } => {
+            row.get(*index)
                 .cloned()
                 .ok_or(ExecutionError::ColumnOutOfBounds {
-                    index: *idx as usize,
+                    index: *index,
                     column_count: row.len(),
                 })
         }
 
+        // Column ids say nothing about where a value sits in the row.
+        Expr::BoundColumn { .. } => Err(ExecutionError::UnboundColumn),
+
         Expr::Unary { op, expr } => {
-            let v = eval_expr(expr, row)?;
+            let v = eval_expr(expr, row, ctx)?;
             eval_unary(*op, v)
         }
 
         Expr::Binary { left, op, right } => {
-            let l = eval_expr(left, row)?;
-            let r = eval_expr(right, row)?;
+            let l = eval_expr(left, row, ctx)?;
+            let r = eval_expr(right, row, ctx)?;
             eval_binary(*op, l, r)
         }
+
+        Expr::Cast { expr, to } => cast_value(eval_expr(expr, row, ctx)?, to),
+
+        // A simple CASE compares its operand with each WHEN value; NULL
+        // equals nothing.
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            let operand = match operand {
+                Some(e) => Some(eval_expr(e, row, ctx)?),
+                None => None,
+            };
+            for (when, then) in branches {
+                let when = eval_expr(when, row, ctx)?;
+                let taken = match &operand {
+                    Some(v) => compare_values(v, &when).is_some_and(Ordering::is_eq),
+                    None => when == Value::Boolean(true),
+                };
+                if taken {
+                    return eval_expr(then, row, ctx);
+                }
+            }
+            match else_result {
+                Some(e) => eval_expr(e, row, ctx),
+                None => Ok(Value::Null),
+            }
+        }
+
+        // NULL, rather than false, when there is no match but a NULL could
+        // have been one.
+        Expr::InList { expr, list } => {
+            let value = eval_expr(expr, row, ctx)?;
+            if value.is_null() {
+                return Ok(Value::Null);
+            }
+            let mut unknown = false;
+            for item in list {
+                let item = eval_expr(item, row, ctx)?;
+                if item.is_null() {
+                    unknown = true;
+                } else if compare_values(&value, &item).is_some_and(Ordering::is_eq) {
+                    return Ok(Value::Boolean(true));
+                }
+            }
+            Ok(if unknown {
+                Value::Null
+            } else {
+                Value::Boolean(false)
+            })
+        }
+
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            case_insensitive,
+        } => {
+            let text = eval_expr(expr, row, ctx)?;
+            let pattern = eval_expr(pattern, row, ctx)?;
+            match (&text, &pattern) {
+                (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+                (Value::String(t), Value::String(p)) => {
+                    like::matches(t, p, *escape, *case_insensitive)
+                        .map(Value::Boolean)
+                        .ok_or_else(|| ExecutionError::InvalidExpression {
+                            reason: "LIKE pattern must not end with the escape character".into(),
+                        })
+                }
+                _ => Err(ExecutionError::TypeMismatch {
+                    op: "LIKE".into(),
+                    left: text,
+                    right: pattern,
+                }),
+            }
+        }
+
+        Expr::Function { func, args } => {
+            let args = args
+                .iter()
+                .map(|arg| eval_expr(arg, row, ctx))
+                .collect::<ExecResult<Vec<_>>>()?;
+            func.call(&ctx.call, &args)
+                .map_err(|error| ExecutionError::Function {
+                    name: func.name().to_string(),
+                    error,
+                })
+        }
+
+        Expr::Parameter { index } => ctx
+            .params
+            .last()
+            .and_then(|params| params.get(*index))
+            .cloned()
+            .ok_or_else(|| ExecutionError::ExecutorInvariantViolation {
+                reason: format!("no parameter {} passed into the subquery", index),
+            }),
+
+        Expr::Subquery {
+            id,
+            kind,
+            plan,
+            params,
+        } => {
+            let args = params
+                .iter()
+                .map(|p| eval_expr(p, row, ctx))
+                .collect::<ExecResult<Vec<_>>>()?;
+            let value = match kind {
+                SubqueryKind::In(expr) => Some(eval_expr(expr, row, ctx)?),
+                SubqueryKind::Scalar | SubqueryKind::Exists => None,
+            };
+            // Only as many rows as the answer needs are read.
+            let limit = match kind {
+                SubqueryKind::Scalar => Some(2),
+                SubqueryKind::Exists => Some(1),
+                SubqueryKind::In(_) => None,
+            };
+
+            // Without parameters the result is the same every time.
+            if !args.is_empty() {
+                let values = run_subquery(plan, args, limit, ctx)?;
+                return subquery_result(kind, value, &values);
+            }
+            if !ctx.subquery_results.contains_key(id) {
+                let values = run_subquery(plan, args, limit, ctx)?;
+                ctx.subquery_results.insert(*id, values);
+            }
+            subquery_result(kind, value, &ctx.subquery_results[id])
+        }
+    }
+}
+
+/// First column of the rows `plan` returns with `args` as its parameters,
+/// up to `limit` of them.
+fn run_subquery(
+    plan: &LogicalPlan,
+    args: Vec<Value>,
+    limit: Option<usize>,
+    ctx: &mut ExecutionContext,
+) -> ExecResult<Vec<Value>> {
+    ctx.params.push(args);
+    let values = read_values(plan, limit, ctx);
+    ctx.params.pop();
+    values
+}
+
+fn read_values(
+    plan: &LogicalPlan,
+    limit: Option<usize>,
+    ctx: &mut ExecutionContext,
+) -> ExecResult<Vec<Value>> {
+    let mut root = build_executor(plan.clone(), ctx)?;
+    root.open(ctx)?;
+
+    let mut values = Vec::new();
+    while limit.is_none_or(|limit| values.len() < limit) {
+        let Some(row) = root.next(ctx)? else {
+            break;
+        };
+        values.push(row.into_iter().next().unwrap_or(Value::Null));
+    }
+
+    root.close(ctx)?;
+    Ok(values)
+}
+
+/// `value` is the left side of IN.
+fn subquery_result(
+    kind: &SubqueryKind,
+    value: Option<Value>,
+    values: &[Value],
+) -> ExecResult<Value> {
+    match kind {
+        SubqueryKind::Scalar => match values {
+            [] => Ok(Value::Null),
+            [v] => Ok(v.clone()),
+            _ => Err(ExecutionError::InvalidExpression {
+                reason: "subquery used as an expression returned more than one row".into(),
+            }),
+        },
+
+        SubqueryKind::Exists => Ok(Value::Boolean(!values.is_empty())),
+
+        // NULL, rather than false, when there is no match but a NULL could
+        // have been one.
+        SubqueryKind::In(_) => {
+            let value = value.unwrap_or(Value::Null);
+            Ok(if values.is_empty() {
+                Value::Boolean(false)
+            } else if value.is_null() {
+                Value::Null
+            } else if values
+                .iter()
+                .any(|v| compare_values(v, &value).is_some_and(Ordering::is_eq))
+            {
+                Value::Boolean(true)
+            } else if values.iter().any(Value::is_null) {
+                Value::Null
+            } else {
+                Value::Boolean(false)
+            })
+        }
     }
 }
 
 fn eval_unary(op: UnaryOp, v: Value) -> ExecResult<Value> {
     match (op, v) {
+        (UnaryOp::IsNull, v) => Ok(Value::Boolean(v.is_null())),
+        (UnaryOp::IsNotNull, v) => Ok(Value::Boolean(!v.is_null())),
         (_, Value::Null) => Ok(Value::Null),
 
-        (UnaryOp::Neg, Value::Int64(x)) => Ok(Value::Int64(-x)),
+        (UnaryOp::Neg, Value::Int16(x)) => x.checked_neg().map(Value::Int16).ok_or_else(overflow),
+        (UnaryOp::Neg, Value::Int32(x)) => x.checked_neg().map(Value::Int32).ok_or_else(overflow),
+        (UnaryOp::Neg, Value::Int64(x)) => x.checked_neg().map(Value::Int64).ok_or_else(overflow),
+        (UnaryOp::Neg, Value::Float32(x)) => Ok(Value::Float32(-x)),
+        (UnaryOp::Neg, Value::Float64(x)) => Ok(Value::Float64(-x)),
         (UnaryOp::Not, Value::Boolean(b)) => Ok(Value::Boolean(!b)),
 
         _ => Err(ExecutionError::InvalidExpression {
@@ -48,34 +270,259 @@ fn eval_unary(op: UnaryOp, v: Value) -> ExecResult<Value> {
 fn eval_binary(op: BinaryOp, l: Value, r: Value) -> ExecResult<Value> {
     use BinaryOp::*;
 
+    match op {
+        And | Or => return logic(op, l, r),
+        IsDistinctFrom | IsNotDistinctFrom => {
+            let same = match (&l, &r) {
+                (Value::Null, Value::Null) => true,
+                (Value::Null, _) | (_, Value::Null) => false,
+                (l, r) => compare_values(l, r).is_some_and(Ordering::is_eq),
+            };
+            return Ok(Value::Boolean(same == (op == IsNotDistinctFrom)));
+        }
+        _ => {}
+    }
     if matches!(l, Value::Null) || matches!(r, Value::Null) {
         return Ok(Value::Null);
     }
 
-    match (op, l, r) {
-        (Add, Value::Int64(a), Value::Int64(b)) => Ok(Value::Int64(a + b)),
-        (Sub, Value::Int64(a), Value::Int64(b)) => Ok(Value::Int64(a - b)),
-        (Mul, Value::Int64(a), Value::Int64(b)) => Ok(Value::Int64(a * b)),
-
-        (Div, _, Value::Int64(0)) => Err(ExecutionError::DivisionByZero),
-        (Div, Value::Int64(a), Value::Int64(b)) => Ok(Value::Int64(a / b)),
+    let result = match op {
+        Add | Sub | Mul | Div | Mod => arithmetic(op, &l, &r)?,
 
-        (Eq, a, b) => Ok(Value::Boolean(a == b)),
-        (Neq, a, b) => Ok(Value::Boolean(a != b)),
+        Eq | Neq | Lt | Lte | Gt | Gte => compare_values(&l, &r).map(|ord| {
+            Value::Boolean(match op {
+                Eq => ord.is_eq(),
+                Neq => ord.is_ne(),
+                Lt => ord.is_lt(),
+                Lte => ord.is_le(),
+                Gt => ord.is_gt(),
+                _ => ord.is_ge(),
+            })
+        }),
 
-        (Lt, Value::Int64(a), Value::Int64(b)) => Ok(Value::Boolean(a < b)),
-        (Lte, Value::Int64(a), Value::Int64(b)) => Ok(Value::Boolean(a <= b)),
-        (Gt, Value::Int64(a), Value::Int64(b)) => Ok(Value::Boolean(a > b)),
-        (Gte, Value::Int64(a), Value::Int64(b)) => Ok(Value::Boolean(a >= b)),
+        And | Or | IsDistinctFrom | IsNotDistinctFrom => unreachable!("handled above"),
+    };
 
-        (And, Value::Boolean(a), Value::Boolean(b)) => Ok(Value::Boolean(a && b)),
-        (Or, Value::Boolean(a), Value::Boolean(b)) => Ok(Value::Boolean(a || b)),
+    result.ok_or_else(|| ExecutionError::TypeMismatch {
+        op: format!("{:?}", op),
+        left: l,
+        right: r,
+    })
+}
 
-        (op, l, r) => Err(ExecutionError::TypeMismatch {
+/// `l AND r` or `l OR r` in three-valued logic: NULL is an unknown truth
+/// value, so the result is NULL only when the known operand does not
+/// decide it.
+fn logic(op: BinaryOp, l: Value, r: Value) -> ExecResult<Value> {
+    let truth = |v: &Value| match v {
+        Value::Boolean(b) => Some(Some(*b)),
+        Value::Null => Some(None),
+        _ => None,
+    };
+    let (Some(a), Some(b)) = (truth(&l), truth(&r)) else {
+        return Err(ExecutionError::TypeMismatch {
             op: format!("{:?}", op),
             left: l,
             right: r,
-        }),
+        });
+    };
+    // The value that decides the result whatever the other operand is.
+    let decisive = op == BinaryOp::Or;
+    Ok(if a == Some(decisive) || b == Some(decisive) {
+        Value::Boolean(decisive)
+    } else if a.is_none() || b.is_none() {
+        Value::Null
+    } else {
+        Value::Boolean(!decisive)
+    })
+}
+
+/// `l op r`, or `None` if the operation does not apply to their types.
+/// Numbers are computed in the wider of their types.
+fn arithmetic(op: BinaryOp, l: &Value, r: &Value) -> ExecResult<Option<Value>> {
+    use BinaryOp::*;
+
+    let value = match (l, r) {
+        (Value::Int16(a), Value::Int16(b)) => Value::Int16(
+            int_op(op, *a as i64, *b as i64)
+                .and_then(|v| i16::try_from(v).map_err(|_| overflow()))?,
+        ),
+        (Value::Int32(a), Value::Int32(b)) => Value::Int32(
+            int_op(op, *a as i64, *b as i64)
+                .and_then(|v| i32::try_from(v).map_err(|_| overflow()))?,
+        ),
+        (Value::Date(d), n) | (n, Value::Date(d)) if integer(n).is_some() => {
+            let n = integer(n).unwrap();
+            let days = match op {
+                Add => (*d as i64).checked_add(n),
+                Sub if matches!(l, Value::Date(_)) => (*d as i64).checked_sub(n),
+                _ => return Ok(None),
+            };
+            Value::Date(
+                days.and_then(|d| i32::try_from(d).ok())
+                    .ok_or_else(overflow)?,
+            )
+        }
+        (Value::Date(a), Value::Date(b)) if op == Sub => Value::Int64(*a as i64 - *b as i64),
+        (a, b) if op == Sub && instant(a).is_some() && instant(b).is_some() => Value::Interval {
+            months: 0,
+            micros: instant(a)
+                .unwrap()
+                .checked_sub(instant(b).unwrap())
+                .ok_or_else(timestamp_overflow)?,
+        },
+        (Value::Interval { months, micros }, t) | (t, Value::Interval { months, micros })
+            if instant(t).is_some() =>
+        {
+            let sign = match op {
+                Add => 1,
+                Sub if matches!(r, Value::Interval { .. }) => -1,
+                _ => return Ok(None),
+            };
+            let moved = add_months(instant(t).unwrap(), sign * *months as i64)
+                .and_then(|ts| ts.checked_add(micros.checked_mul(sign)?))
+                .ok_or_else(timestamp_overflow)?;
+            Value::Timestamp(moved)
+        }
+        (
+            Value::Interval {
+                months: m1,
+                micros: u1,
+            },
+            Value::Interval {
+                months: m2,
+                micros: u2,
+            },
+        ) => {
+            let (months, micros) = match op {
+                Add => (m1.checked_add(*m2), u1.checked_add(*u2)),
+                Sub => (m1.checked_sub(*m2), u1.checked_sub(*u2)),
+                _ => return Ok(None),
+            };
+            match (months, micros) {
+                (Some(months), Some(micros)) => Value::Interval { months, micros },
+                _ => return Err(interval_overflow()),
+            }
+        }
+        _ => match (integer(l), integer(r)) {
+            (Some(a), Some(b)) => Value::Int64(int_op(op, a, b)?),
+            _ => {
+                let (Some(a), Some(b)) = (float(l), float(r)) else {
+                    return Ok(None);
+                };
+                if matches!(op, Div | Mod) && b == 0.0 {
+                    return Err(ExecutionError::DivisionByZero);
+                }
+                let v = match op {
+                    Add => a + b,
+                    Sub => a - b,
+                    Mul => a * b,
+                    Mod => a % b,
+                    _ => a / b,
+                };
+                // REAL stays REAL unless mixed with a 64-bit type.
+                let narrow =
+                    |v: &Value| matches!(v, Value::Int16(_) | Value::Int32(_) | Value::Float32(_));
+                if narrow(l) && narrow(r) {
+                    Value::Float32(v as f32)
+                } else {
+                    Value::Float64(v)
+                }
+            }
+        },
+    };
+    Ok(Some(value))
+}
+
+fn int_op(op: BinaryOp, a: i64, b: i64) -> ExecResult<i64> {
+    let v = match op {
+        BinaryOp::Add => a.checked_add(b),
+        BinaryOp::Sub => a.checked_sub(b),
+        BinaryOp::Mul => a.checked_mul(b),
+        _ if b == 0 => return Err(ExecutionError::DivisionByZero),
+        BinaryOp::Mod => a.checked_rem(b),
+        _ => a.checked_div(b),
+    };
+    v.ok_or_else(overflow)
+}
+
+fn integer(v: &Value) -> Option<i64> {
+    match v {
+        Value::Int16(v) => Some(*v as i64),
+        Value::Int32(v) => Some(*v as i64),
+        Value::Int64(v) => Some(*v),
+        _ => None,
     }
 }
 
+fn float(v: &Value) -> Option<f64> {
+    match v {
+        Value::Float32(v) => Some(*v as f64),
+        Value::Float64(v) => Some(*v),
+        v => integer(v).map(|v| v as f64),
+    }
+}
+
+fn overflow() -> ExecutionError {
+    ExecutionError::ExpressionError {
+        message: "integer out of range".into(),
+    }
+}
+
+fn timestamp_overflow() -> ExecutionError {
+    ExecutionError::ExpressionError {
+        message: "timestamp out of range".into(),
+    }
+}
+
+fn interval_overflow() -> ExecutionError {
+    ExecutionError::ExpressionError {
+        message: "interval out of range".into(),
+    }
+}
+
+/// Microseconds since the epoch of a date or timestamp, a date being the
+/// midnight that starts it.
+fn instant(v: &Value) -> Option<i64> {
+    match v {
+        Value::Date(d) => Some(*d as i64 * MICROS_PER_DAY),
+        Value::Timestamp(ts) => Some(*ts),
+        _ => None,
+    }
+}
+
+/// SQL order of two non-NULL values, or `None` if their types cannot be
+/// compared. Numbers compare by value whatever their types, a date is
+/// the midnight that starts it, and a month of an interval is 30 days.
+pub(crate) fn compare_values(l: &Value, r: &Value) -> Option<Ordering> {
+    Some(match (l, r) {
+        (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Blob(a), Value::Blob(b)) => a.cmp(b),
+        (Value::Date(a), Value::Date(b)) => a.cmp(b),
+        (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
+        (Value::Date(a), Value::Timestamp(b)) => (*a as i64).saturating_mul(MICROS_PER_DAY).cmp(b),
+        (Value::Timestamp(a), Value::Date(b)) => a.cmp(&(*b as i64).saturating_mul(MICROS_PER_DAY)),
+        (
+            Value::Interval {
+                months: m1,
+                micros: u1,
+            },
+            Value::Interval {
+                months: m2,
+                micros: u2,
+            },
+        ) => {
+            let length = |months: i32, micros: i64| {
+                months as i128 * 30 * MICROS_PER_DAY as i128 + micros as i128
+            };
+            length(*m1, *u1).cmp(&length(*m2, *u2))
+        }
+        _ => match (integer(l), integer(r)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(a), None) => cmp_int_float(a, float(r)?),
+            (None, Some(b)) => cmp_int_float(b, float(l)?).reverse(),
+            (None, None) => cmp_float(float(l)?, float(r)?),
+        },
+    })
+}
@@ -0,0 +1,34 @@
+//! Byte keys for values that hash together exactly when they compare equal.
+//!
+//! Values do not hash, so joins, groups and duplicate checks key their maps
+//! by encoded values instead. Serializing a value as is would put `1` and
+//! `1.0`, or `0.0` and `-0.0`, under different keys, so numbers are first
+//! brought to one form shared by every number equal to them.
+
+use crate::types::value::Value;
+use crate::util::numeric::float_as_int;
+
+/// `value` in a form shared by every value equal to it, so that numbers of
+/// different types meet in the same bucket.
+pub(crate) fn canonical(value: Value) -> Value {
+    match value {
+        Value::Int16(v) => Value::Int64(v as i64),
+        Value::Int32(v) => Value::Int64(v as i64),
+        Value::Float32(v) => canonical(Value::Float64(v as f64)),
+        Value::Float64(v) if v.is_nan() => Value::Float64(f64::NAN),
+        Value::Float64(v) => match float_as_int(v) {
+            Some(i) => Value::Int64(i),
+            None => Value::Float64(v),
+        },
+        v => v,
+    }
+}
+
+/// Key of the row of `values`.
+pub(crate) fn encode(values: &[Value]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for v in values {
+        canonical(v.clone()).serialize(&mut buf);
+    }
+    buf
+}
@@ -1,6 +1,9 @@
+pub mod cast;
 pub mod context;
+pub mod ddl;
 pub mod engine;
 pub mod errors;
 mod eval_expr;
 pub mod executor;
+mod key;
 mod operators;
@@ -0,0 +1,310 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::execution::context::ExecutionContext;
+use crate::execution::errors::{ExecutionError, TableMutationStats};
+use crate::execution::eval_expr::{compare_values, eval_expr};
+use crate::execution::executor::{ExecResult, Executor, Row};
+use crate::execution::key::encode;
+use crate::functions::aggregate::{AggregateFunction, AggregateState as UserState};
+use crate::ir::expr::Expr;
+use crate::ir::plan::{AggregateExpr, AggregateFunc};
+use crate::types::value::Value;
+
+/// Hash aggregate. Drains its input on `open`, then emits one row per group,
+/// in the order the groups were first seen: the group keys followed by the
+/// aggregate values.
+pub struct AggregateExecutor {
+    input: Box<dyn Executor>,
+    group_by: Vec<Expr>,
+    aggregates: Vec<AggregateExpr>,
+    buffer: Vec<Row>,
+    pos: usize,
+}
+
+impl AggregateExecutor {
+    pub fn new(
+        input: Box<dyn Executor>,
+        group_by: Vec<Expr>,
+        aggregates: Vec<AggregateExpr>,
+    ) -> Self {
+        Self {
+            input,
+            group_by,
+            aggregates,
+            buffer: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    fn new_group(&self, keys: Row) -> Group {
+        Group {
+            keys,
+            states: self
+                .aggregates
+                .iter()
+                .map(|a| AggregateState::new(&a.func))
+                .collect(),
+            seen: vec![HashSet::new(); self.aggregates.len()],
+        }
+    }
+}
+
+impl Executor for AggregateExecutor {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> ExecResult<()> {
+        self.buffer.clear();
+        self.pos = 0;
+        self.input.open(ctx)?;
+
+        // Values do not hash, so groups are keyed by their encoded keys.
+        let mut groups: Vec<Group> = Vec::new();
+        let mut index: HashMap<Vec<u8>, usize> = HashMap::new();
+
+        while let Some(row) = self.input.next(ctx)? {
+            let keys = self
+                .group_by
+                .iter()
+                .map(|e| eval_expr(e, &row, ctx))
+                .collect::<ExecResult<Row>>()?;
+
+            let slot = *index.entry(encode(&keys)).or_insert_with(|| {
+                groups.push(self.new_group(keys));
+                groups.len() - 1
+            });
+            groups[slot].update(&self.aggregates, &row, ctx)?;
+        }
+
+        // Without GROUP BY the whole input is one group, even when empty.
+        if groups.is_empty() && self.group_by.is_empty() {
+            groups.push(self.new_group(Vec::new()));
+        }
+
+        self.buffer = groups
+            .into_iter()
+            .map(Group::finish)
+            .collect::<ExecResult<_>>()?;
+        Ok(())
+    }
+
+    fn next(&mut self, _ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        if self.pos >= self.buffer.len() {
+            return Ok(None);
+        }
+
+        let row = self.buffer[self.pos].clone();
+        self.pos += 1;
+        Ok(Some(row))
+    }
+
+    fn close(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Vec<TableMutationStats>> {
+        self.buffer.clear();
+        self.pos = 0;
+        self.input.close(ctx)
+    }
+}
+
+struct Group {
+    keys: Row,
+    states: Vec<AggregateState>,
+    /// Arguments already fed to each DISTINCT aggregate.
+    seen: Vec<HashSet<Vec<u8>>>,
+}
+
+impl Group {
+    fn update(
+        &mut self,
+        aggregates: &[AggregateExpr],
+        row: &Row,
+        ctx: &mut ExecutionContext,
+    ) -> ExecResult<()> {
+        for (i, agg) in aggregates.iter().enumerate() {
+            let value = match &agg.arg {
+                // COUNT(*) counts every row.
+                None => Value::Boolean(true),
+                Some(arg) => eval_expr(arg, row, ctx)?,
+            };
+
+            if value.is_null() {
+                continue;
+            }
+            if agg.distinct && !self.seen[i].insert(encode(std::slice::from_ref(&value))) {
+                continue;
+            }
+
+            self.states[i].update(value)?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> ExecResult<Row> {
+        let mut row = self.keys;
+        for state in self.states {
+            row.push(state.finish()?);
+        }
+        Ok(row)
+    }
+}
+
+/// Running state of one aggregate over one group. NULL inputs never reach
+/// it.
+enum AggregateState {
+    Count(i64),
+    Sum(Option<Sum>),
+    Avg {
+        sum: Option<Total>,
+        count: i64,
+    },
+    Min(Option<Value>),
+    Max(Option<Value>),
+    User {
+        func: Arc<AggregateFunction>,
+        state: UserState,
+    },
+}
+
+#[derive(Clone, Copy)]
+enum Sum {
+    Int(i64),
+    Float(f64),
+}
+
+/// Running total of AVG. Integers are added up in 128 bits, which no
+/// number of 64-bit values overflows.
+#[derive(Clone, Copy)]
+enum Total {
+    Int(i128),
+    Float(f64),
+}
+
+impl AggregateState {
+    fn new(func: &AggregateFunc) -> Self {
+        match func {
+            AggregateFunc::Count => AggregateState::Count(0),
+            AggregateFunc::Sum => AggregateState::Sum(None),
+            AggregateFunc::Avg => AggregateState::Avg {
+                sum: None,
+                count: 0,
+            },
+            AggregateFunc::Min => AggregateState::Min(None),
+            AggregateFunc::Max => AggregateState::Max(None),
+            AggregateFunc::User(func) => AggregateState::User {
+                func: func.clone(),
+                state: func.init(),
+            },
+        }
+    }
+
+    fn update(&mut self, value: Value) -> ExecResult<()> {
+        match self {
+            AggregateState::Count(n) => *n += 1,
+
+            AggregateState::Sum(sum) => *sum = Some(add(*sum, &value)?),
+
+            AggregateState::Avg { sum, count } => {
+                *sum = Some(add_to_total(*sum, &value)?);
+                *count += 1;
+            }
+
+            AggregateState::Min(min) => {
+                if min
+                    .as_ref()
+                    .is_none_or(|m| compare_values(&value, m) == Some(Ordering::Less))
+                {
+                    *min = Some(value);
+                }
+            }
+
+            AggregateState::Max(max) => {
+                if max
+                    .as_ref()
+                    .is_none_or(|m| compare_values(&value, m) == Some(Ordering::Greater))
+                {
+                    *max = Some(value);
+                }
+            }
+
+            AggregateState::User { func, state } => {
+                func.update(state, &value)
+                    .map_err(|error| ExecutionError::Function {
+                        name: func.name().to_string(),
+                        error,
+                    })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> ExecResult<Value> {
+        Ok(match self {
+            AggregateState::Count(n) => Value::Int64(n),
+
+            AggregateState::Sum(sum) => match sum {
+                Some(Sum::Int(v)) => Value::Int64(v),
+                Some(Sum::Float(v)) => Value::Float64(v),
+                None => Value::Null,
+            },
+
+            AggregateState::Avg { sum, count } => match sum {
+                Some(Total::Int(v)) => Value::Float64(v as f64 / count as f64),
+                Some(Total::Float(v)) => Value::Float64(v / count as f64),
+                None => Value::Null,
+            },
+
+            AggregateState::Min(v) | AggregateState::Max(v) => v.unwrap_or(Value::Null),
+
+            AggregateState::User { func, state } => {
+                func.finalize(state)
+                    .map_err(|error| ExecutionError::Function {
+                        name: func.name().to_string(),
+                        error,
+                    })?
+            }
+        })
+    }
+}
+
+/// `value` as a term of a sum.
+fn term(value: &Value) -> ExecResult<Sum> {
+    Ok(match value {
+        Value::Int16(v) => Sum::Int(*v as i64),
+        Value::Int32(v) => Sum::Int(*v as i64),
+        Value::Int64(v) => Sum::Int(*v),
+        Value::Float32(v) => Sum::Float(*v as f64),
+        Value::Float64(v) => Sum::Float(*v),
+        other => {
+            return Err(ExecutionError::TypeError {
+                expected: "numeric".into(),
+                found: other.clone(),
+            });
+        }
+    })
+}
+
+fn add_to_total(total: Option<Total>, value: &Value) -> ExecResult<Total> {
+    Ok(match (total, term(value)?) {
+        (None, Sum::Int(b)) => Total::Int(b as i128),
+        (None, Sum::Float(b)) => Total::Float(b),
+        (Some(Total::Int(a)), Sum::Int(b)) => Total::Int(a + b as i128),
+        (Some(Total::Int(a)), Sum::Float(b)) => Total::Float(a as f64 + b),
+        (Some(Total::Float(a)), Sum::Int(b)) => Total::Float(a + b as f64),
+        (Some(Total::Float(a)), Sum::Float(b)) => Total::Float(a + b),
+    })
+}
+
+fn add(sum: Option<Sum>, value: &Value) -> ExecResult<Sum> {
+    Ok(match (sum, term(value)?) {
+        (None, v) => v,
+        (Some(Sum::Int(a)), Sum::Int(b)) => {
+            Sum::Int(
+                a.checked_add(b)
+                    .ok_or_else(|| ExecutionError::ExpressionError {
+                        message: "integer overflow in SUM".into(),
+                    })?,
+            )
+        }
+        (Some(Sum::Int(a)), Sum::Float(b)) => Sum::Float(a as f64 + b),
+        (Some(Sum::Float(a)), Sum::Int(b)) => Sum::Float(a + b as f64),
+        (Some(Sum::Float(a)), Sum::Float(b)) => Sum::Float(a + b),
+    })
+}
@@ -3,12 +3,14 @@ use crate::{
     execution::{
         context::ExecutionContext,
         errors::{ExecutionError, TableMutationStats},
-        eval_expr::eval_expr,
         executor::{ExecResult, Executor, Row},
     },
     ir::expr::Expr,
     storage::index::btree::key::IndexKey,
-    types::value::Value,
+    txn::{
+        lock_manager::{LockMode, LockTarget},
+        wal::record::LogBody,
+    },
 };
 
 pub struct DeleteExecutor {
@@ -42,7 +44,7 @@ impl Executor for DeleteExecutor {
 
         self.done = true;
 
-        let _table_meta =
+        let table_meta =
             ctx.catalog
                 .get_table_by_id(self.table_id)
                 .ok_or(ExecutionError::TableNotFound {
@@ -50,35 +52,38 @@ impl Executor for DeleteExecutor {
                 })?;
 
         let heap = ctx.get_heap(self.table_id)?;
-        let cursor = heap.scan();
-
-        let mut to_delete = Vec::new();
-
-        for (rid, row) in cursor {
-            if let Some(pred) = &self.predicate {
-                match eval_expr(pred, &row.values)? {
-                    Value::Boolean(true) => {}
-                    _ => continue,
-                }
-            }
-            to_delete.push((rid, row.values.clone()));
-        }
+        ctx.lock(
+            LockTarget::Table(self.table_id),
+            LockMode::IntentionExclusive,
+        )?;
+        let to_delete = ctx.lock_rows(&heap, self.predicate.as_ref())?;
 
         for (rid, old_row) in to_delete {
             for idx in ctx.catalog.indexes_for_table(self.table_id) {
-                let col = idx.meta.column_ids[0];
-                let key = IndexKey::try_from(&old_row[col.0 as usize]).map_err(|e| {
+                let pos = table_meta
+                    .schema
+                    .position(idx.meta.column_ids[0])
+                    .ok_or_else(|| {
+                        ExecutionError::index_key_error(idx.meta.id, "indexed column not in table")
+                    })?;
+                let key = IndexKey::try_from(&old_row[pos]).map_err(|e| {
                     ExecutionError::IndexViolation {
                         index_id: idx.meta.id,
                         reason: e.into(),
                     }
                 })?;
 
+                let body = LogBody::IndexDelete {
+                    index_id: idx.meta.id,
+                    key: key.clone(),
+                    rid,
+                };
+                ctx.storage.log(ctx.txn_id, body)?;
                 idx.index.lock().unwrap().delete(&key, rid)?;
                 self.stats.record_index_delete(idx.meta.id);
             }
 
-            heap.delete(rid)?;
+            heap.delete(ctx.txn_id, rid)?;
             self.stats.rows_deleted += 1;
             self.stats.rows_affected += 1;
         }
@@ -90,4 +95,3 @@ impl Executor for DeleteExecutor {
         Ok(vec![self.stats.clone()])
     }
 }
-
@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+
+use crate::execution::context::ExecutionContext;
+use crate::execution::errors::TableMutationStats;
+use crate::execution::eval_expr::eval_expr;
+use crate::execution::executor::{ExecResult, Executor, Row};
+use crate::execution::key::encode;
+use crate::ir::expr::Expr;
+
+/// Outputs the first input row for each distinct value of `on`, or each
+/// distinct row when `on` is empty. NULLs are equal to each other. Sorted
+/// input only needs the last value kept; otherwise every value is hashed.
+pub struct DistinctExecutor {
+    input: Box<dyn Executor>,
+    on: Vec<Expr>,
+    sorted: bool,
+
+    seen: HashSet<Vec<u8>>,
+    last: Option<Vec<u8>>,
+}
+
+impl DistinctExecutor {
+    pub fn new(input: Box<dyn Executor>, on: Vec<Expr>, sorted: bool) -> Self {
+        Self {
+            input,
+            on,
+            sorted,
+            seen: HashSet::new(),
+            last: None,
+        }
+    }
+
+    fn key(&self, row: &Row, ctx: &mut ExecutionContext) -> ExecResult<Vec<u8>> {
+        if self.on.is_empty() {
+            return Ok(encode(row));
+        }
+        let values = self
+            .on
+            .iter()
+            .map(|e| eval_expr(e, row, ctx))
+            .collect::<ExecResult<Vec<_>>>()?;
+        Ok(encode(&values))
+    }
+}
+
+impl Executor for DistinctExecutor {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> ExecResult<()> {
+        self.seen.clear();
+        self.last = None;
+        self.input.open(ctx)
+    }
+
+    fn next(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        while let Some(row) = self.input.next(ctx)? {
+            let key = self.key(&row, ctx)?;
+            let new = if self.sorted {
+                self.last.as_ref() != Some(&key)
+            } else {
+                !self.seen.contains(&key)
+            };
+            if !new {
+                continue;
+            }
+
+            if self.sorted {
+                self.last = Some(key);
+            } else {
+                self.seen.insert(key);
+            }
+            return Ok(Some(row));
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Vec<TableMutationStats>> {
+        self.seen.clear();
+        self.last = None;
+        self.input.close(ctx)
+    }
+}
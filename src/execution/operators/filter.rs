@@ -23,7 +23,7 @@ impl Executor for FilterExecutor {
 
     fn next(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
         while let Some(row) = self.input.next(ctx)? {
-            match eval_expr(&self.predicate, &row)? {
+            match eval_expr(&self.predicate, &row, ctx)? {
                 Value::Boolean(true) => return Ok(Some(row)),
                 Value::Boolean(false) | Value::Null => {
                     ctx.stats.rows_filtered += 1;
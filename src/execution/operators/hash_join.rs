@@ -0,0 +1,572 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::execution::context::ExecutionContext;
+use crate::execution::errors::{ExecutionError, TableMutationStats};
+use crate::execution::eval_expr::eval_expr;
+use crate::execution::executor::{ExecResult, Executor, Row};
+use crate::execution::key::canonical;
+use crate::ir::expr::Expr;
+use crate::ir::plan::{JoinSide, JoinType};
+use crate::storage::errors::StorageError;
+use crate::types::value::Value;
+
+/// Number of partitions each input is split into once the build side does
+/// not fit in memory.
+const SPILL_PARTITIONS: usize = 16;
+
+/// Hash join. The build input is read into a hash table on its keys on
+/// `open`; probe rows are then streamed through it. Outer joins pad probe
+/// rows without a match as they go, and unmatched build rows once the probe
+/// input is exhausted. Semi and anti joins build on the right and output
+/// each probe row once, if it has a match or if it has none.
+///
+/// When the build rows outgrow the context's memory budget, both inputs are
+/// split by key hash into partition files and joined one partition at a
+/// time, since matching rows always land in the same partition. A partition
+/// still too large is split again, hashing with another seed. Once a split
+/// leaves all of its build rows in one partition, as when they share a key,
+/// that partition is joined a memory-sized block of build rows at a time,
+/// reading its probe rows once per block.
+pub struct HashJoinExecutor {
+    build: Box<dyn Executor>,
+    probe: Box<dyn Executor>,
+    build_keys: Vec<Expr>,
+    probe_keys: Vec<Expr>,
+    residual: Option<Expr>,
+    join_type: JoinType,
+    build_side: JoinSide,
+    build_width: usize,
+    probe_width: usize,
+    pad_build: bool,
+    pad_probe: bool,
+
+    table: HashTable,
+    probe_source: ProbeSource,
+    /// Partitions still to join, once spilled.
+    partitions: VecDeque<Partition>,
+    /// The partition being joined block by block, if any.
+    block: Option<Block>,
+    pending: VecDeque<Row>,
+}
+
+/// Build and probe rows whose keys hash alike under `seed`.
+struct Partition {
+    build: SpillFile,
+    probe: SpillFile,
+    seed: u64,
+    /// Whether splitting again may divide the build rows, which it cannot
+    /// once a split kept them all together.
+    splittable: bool,
+}
+
+/// Progress through a partition joined block by block. Its probe rows
+/// learn whether they matched only once the last block has been probed.
+struct Block {
+    /// Build rows of the blocks still to come.
+    rest: SpillReader,
+    /// First row of the next block, if there is one.
+    next: Option<Row>,
+    /// Whether each probe row, by position, matched in an earlier block.
+    probe_found: Vec<bool>,
+    probe_pos: usize,
+}
+
+#[derive(Default)]
+struct HashTable {
+    rows: Vec<Row>,
+    matched: Vec<bool>,
+    /// Encoded key to positions in `rows`. Rows with a NULL key are kept in
+    /// `rows` but never indexed.
+    buckets: HashMap<Vec<u8>, Vec<usize>>,
+}
+
+enum ProbeSource {
+    Input,
+    Spilled(SpillReader),
+    Done,
+}
+
+impl HashJoinExecutor {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        left: Box<dyn Executor>,
+        right: Box<dyn Executor>,
+        left_keys: Vec<Expr>,
+        right_keys: Vec<Expr>,
+        residual: Option<Expr>,
+        join_type: JoinType,
+        build_side: JoinSide,
+        left_width: usize,
+        right_width: usize,
+    ) -> Self {
+        // Semi and anti joins output probe rows alone.
+        debug_assert!(!join_type.left_only() || build_side == JoinSide::Right);
+        let pads_left = matches!(join_type, JoinType::Left | JoinType::Full);
+        let pads_right = matches!(join_type, JoinType::Right | JoinType::Full);
+
+        let (build, probe, build_keys, probe_keys) = match build_side {
+            JoinSide::Left => (left, right, left_keys, right_keys),
+            JoinSide::Right => (right, left, right_keys, left_keys),
+        };
+        let (build_width, probe_width, pad_build, pad_probe) = match build_side {
+            JoinSide::Left => (left_width, right_width, pads_left, pads_right),
+            JoinSide::Right => (right_width, left_width, pads_right, pads_left),
+        };
+
+        Self {
+            build,
+            probe,
+            build_keys,
+            probe_keys,
+            residual,
+            join_type,
+            build_side,
+            build_width,
+            probe_width,
+            pad_build,
+            pad_probe,
+            table: HashTable::default(),
+            probe_source: ProbeSource::Done,
+            partitions: VecDeque::new(),
+            block: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Joined row in left-then-right order.
+    fn joined(&self, build: Option<&Row>, probe: Option<&Row>) -> Row {
+        let build = build
+            .cloned()
+            .unwrap_or_else(|| vec![Value::Null; self.build_width]);
+        let probe = probe
+            .cloned()
+            .unwrap_or_else(|| vec![Value::Null; self.probe_width]);
+        let (mut left, right) = match self.build_side {
+            JoinSide::Left => (build, probe),
+            JoinSide::Right => (probe, build),
+        };
+        left.extend(right);
+        left
+    }
+
+    fn insert_build_row(&mut self, row: Row, key: Option<Vec<u8>>) {
+        let pos = self.table.rows.len();
+        if let Some(key) = key {
+            self.table.buckets.entry(key).or_default().push(pos);
+        }
+        self.table.rows.push(row);
+        self.table.matched.push(false);
+    }
+
+    /// Queue the joined rows `row` produces against the current table.
+    /// Unmatched and semi or anti join rows wait for the last block.
+    fn probe_row(&mut self, row: Row, ctx: &mut ExecutionContext) -> ExecResult<()> {
+        let mut found = false;
+
+        if let Some(key) = encode_key(&self.probe_keys, &row, ctx)? {
+            let candidates = self.table.buckets.get(&key).cloned().unwrap_or_default();
+            for pos in candidates {
+                let joined = self.joined(Some(&self.table.rows[pos]), Some(&row));
+                if self.passes_residual(&joined, ctx)? {
+                    found = true;
+                    if self.join_type.left_only() {
+                        break;
+                    }
+                    self.table.matched[pos] = true;
+                    self.pending.push_back(joined);
+                }
+            }
+        }
+
+        if let Some(block) = &mut self.block {
+            let pos = block.probe_pos;
+            block.probe_pos += 1;
+            if pos == block.probe_found.len() {
+                block.probe_found.push(false);
+            }
+            block.probe_found[pos] |= found;
+            found = block.probe_found[pos];
+            if block.next.is_some() {
+                return Ok(());
+            }
+        }
+
+        if self.join_type.left_only() {
+            if found == (self.join_type == JoinType::Semi) {
+                self.pending.push_back(row);
+            }
+            return Ok(());
+        }
+        if !found && self.pad_probe {
+            let padded = self.joined(None, Some(&row));
+            self.pending.push_back(padded);
+        }
+        Ok(())
+    }
+
+    fn passes_residual(&self, row: &Row, ctx: &mut ExecutionContext) -> ExecResult<bool> {
+        let Some(residual) = &self.residual else {
+            return Ok(true);
+        };
+        match eval_expr(residual, row, ctx)? {
+            Value::Boolean(b) => Ok(b),
+            Value::Null => Ok(false),
+            _ => Err(ExecutionError::InvalidExpression {
+                reason: "join predicate must be boolean".into(),
+            }),
+        }
+    }
+
+    /// Queue the unmatched rows of the current table, if they are kept.
+    fn finish_table(&mut self) {
+        let table = std::mem::take(&mut self.table);
+        if self.pad_build {
+            for (row, matched) in table.rows.iter().zip(table.matched) {
+                if !matched {
+                    let padded = self.joined(Some(row), None);
+                    self.pending.push_back(padded);
+                }
+            }
+        }
+    }
+
+    /// Load the next spilled partition, returning false when none is left.
+    /// A partition too large for memory is split again, or if that cannot
+    /// help, joined block by block.
+    fn next_partition(&mut self, ctx: &mut ExecutionContext) -> ExecResult<bool> {
+        while let Some(part) = self.partitions.pop_front() {
+            let mut build = part.build.reader()?;
+            let mut used = 0;
+            while used <= ctx.work_mem
+                && let Some(row) = build.next_row()?
+            {
+                used += row_size(&row);
+                let key = encode_key(&self.build_keys, &row, ctx)?;
+                self.insert_build_row(row, key);
+            }
+            let next = build.next_row()?;
+
+            if next.is_some() && part.splittable {
+                let buffered = std::mem::take(&mut self.table).rows;
+                let mut build_rows = buffered.into_iter().chain(next);
+                let mut probe = part.probe.reader()?;
+                let parts = partition(
+                    &self.build_keys,
+                    &self.probe_keys,
+                    part.seed + 1,
+                    |_| match build_rows.next() {
+                        Some(row) => Ok(Some(row)),
+                        None => build.next_row(),
+                    },
+                    |_| probe.next_row(),
+                    ctx,
+                )?;
+                for part in parts.into_iter().rev() {
+                    self.partitions.push_front(part);
+                }
+                continue;
+            }
+
+            if next.is_some() {
+                self.block = Some(Block {
+                    rest: build,
+                    next,
+                    probe_found: Vec::new(),
+                    probe_pos: 0,
+                });
+            }
+            self.probe_source = ProbeSource::Spilled(part.probe.reader()?);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Load the next block of the partition joined block by block and
+    /// read its probe rows again, returning false when none is left.
+    fn next_block(&mut self, ctx: &mut ExecutionContext) -> ExecResult<bool> {
+        let Some(mut block) = self.block.take() else {
+            return Ok(false);
+        };
+        let ProbeSource::Spilled(probe) = &mut self.probe_source else {
+            return Ok(false);
+        };
+        let Some(first) = block.next.take() else {
+            return Ok(false);
+        };
+        probe.rewind()?;
+        block.probe_pos = 0;
+
+        let mut used = 0;
+        let mut row = Some(first);
+        while let Some(r) = row {
+            used += row_size(&r);
+            let key = encode_key(&self.build_keys, &r, ctx)?;
+            self.insert_build_row(r, key);
+            row = block.rest.next_row()?;
+            if used > ctx.work_mem {
+                break;
+            }
+        }
+        block.next = row;
+        self.block = Some(block);
+        Ok(true)
+    }
+}
+
+impl Executor for HashJoinExecutor {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> ExecResult<()> {
+        self.table = HashTable::default();
+        self.partitions.clear();
+        self.block = None;
+        self.pending.clear();
+
+        self.build.open(ctx)?;
+        self.probe.open(ctx)?;
+
+        let mut used = 0;
+        while let Some(row) = self.build.next(ctx)? {
+            used += row_size(&row);
+            let key = encode_key(&self.build_keys, &row, ctx)?;
+            self.insert_build_row(row, key);
+
+            // Move the rows read so far and the rest of both inputs into
+            // partition files.
+            if used > ctx.work_mem {
+                let mut buffered = std::mem::take(&mut self.table).rows.into_iter();
+                let (build, probe) = (&mut self.build, &mut self.probe);
+                let parts = partition(
+                    &self.build_keys,
+                    &self.probe_keys,
+                    0,
+                    |ctx| match buffered.next() {
+                        Some(row) => Ok(Some(row)),
+                        None => build.next(ctx),
+                    },
+                    |ctx| probe.next(ctx),
+                    ctx,
+                )?;
+                self.partitions.extend(parts);
+                self.probe_source = ProbeSource::Done;
+                self.next_partition(ctx)?;
+                return Ok(());
+            }
+        }
+
+        self.probe_source = ProbeSource::Input;
+        Ok(())
+    }
+
+    fn next(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Ok(Some(row));
+            }
+
+            let row = match &mut self.probe_source {
+                ProbeSource::Input => self.probe.next(ctx)?,
+                ProbeSource::Spilled(reader) => reader.next_row()?,
+                ProbeSource::Done => return Ok(None),
+            };
+
+            match row {
+                Some(row) => self.probe_row(row, ctx)?,
+                None => {
+                    self.finish_table();
+                    if !self.next_block(ctx)? {
+                        self.probe_source = ProbeSource::Done;
+                        self.next_partition(ctx)?;
+                    }
+                }
+            }
+        }
+    }
+
+    fn close(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Vec<TableMutationStats>> {
+        self.table = HashTable::default();
+        self.partitions.clear();
+        self.block = None;
+        self.pending.clear();
+        self.probe_source = ProbeSource::Done;
+        self.build.close(ctx)?;
+        self.probe.close(ctx)?;
+        Ok(vec![])
+    }
+}
+
+/// Encoded key values, or `None` if any of them is NULL.
+fn encode_key(keys: &[Expr], row: &Row, ctx: &mut ExecutionContext) -> ExecResult<Option<Vec<u8>>> {
+    let mut buf = Vec::new();
+    for key in keys {
+        let value = eval_expr(key, row, ctx)?;
+        if value.is_null() {
+            return Ok(None);
+        }
+        canonical(value).serialize(&mut buf);
+    }
+    Ok(Some(buf))
+}
+
+/// Write the build rows `next_build` yields and the probe rows `next_probe`
+/// yields into partition files by the hash of their keys under `seed`.
+fn partition(
+    build_keys: &[Expr],
+    probe_keys: &[Expr],
+    seed: u64,
+    mut next_build: impl FnMut(&mut ExecutionContext) -> ExecResult<Option<Row>>,
+    mut next_probe: impl FnMut(&mut ExecutionContext) -> ExecResult<Option<Row>>,
+    ctx: &mut ExecutionContext,
+) -> ExecResult<Vec<Partition>> {
+    let mut build_parts = (0..SPILL_PARTITIONS)
+        .map(|_| SpillFile::create("build"))
+        .collect::<ExecResult<Vec<_>>>()?;
+    let mut probe_parts = (0..SPILL_PARTITIONS)
+        .map(|_| SpillFile::create("probe"))
+        .collect::<ExecResult<Vec<_>>>()?;
+
+    let mut rows = 0;
+    while let Some(row) = next_build(ctx)? {
+        let key = encode_key(build_keys, &row, ctx)?;
+        build_parts[partition_of(key.as_deref(), seed)].write_row(&row)?;
+        rows += 1;
+    }
+    while let Some(row) = next_probe(ctx)? {
+        let key = encode_key(probe_keys, &row, ctx)?;
+        probe_parts[partition_of(key.as_deref(), seed)].write_row(&row)?;
+    }
+
+    build_parts
+        .into_iter()
+        .zip(probe_parts)
+        .map(|(build, probe)| {
+            Ok(Partition {
+                splittable: build.rows < rows,
+                build: build.finish()?,
+                probe: probe.finish()?,
+                seed,
+            })
+        })
+        .collect()
+}
+
+fn partition_of(key: Option<&[u8]>, seed: u64) -> usize {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    key.hash(&mut hasher);
+    hasher.finish() as usize % SPILL_PARTITIONS
+}
+
+/// Rough in-memory size of a buffered row.
+fn row_size(row: &Row) -> usize {
+    let mut buf = Vec::new();
+    for v in row {
+        v.serialize(&mut buf);
+    }
+    buf.len() + std::mem::size_of::<Row>()
+}
+
+fn io_error(err: std::io::Error) -> ExecutionError {
+    StorageError::Io {
+        message: err.to_string(),
+    }
+    .into()
+}
+
+/// Temporary file of spilled rows, removed when dropped.
+struct SpillFile {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    /// Rows written.
+    rows: u64,
+}
+
+impl SpillFile {
+    fn create(kind: &str) -> ExecResult<Self> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "helium_hashjoin_{}_{}.{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed),
+            kind
+        ));
+        let file = File::create(&path).map_err(io_error)?;
+        Ok(Self {
+            path,
+            writer: Some(BufWriter::new(file)),
+            rows: 0,
+        })
+    }
+
+    /// Rows are stored as their encoded length followed by their values.
+    fn write_row(&mut self, row: &Row) -> ExecResult<()> {
+        let mut buf = Vec::new();
+        for v in row {
+            v.serialize(&mut buf);
+        }
+        self.rows += 1;
+        let writer = self.writer.as_mut().expect("spill file is still open");
+        writer
+            .write_all(&(buf.len() as u32).to_le_bytes())
+            .and_then(|_| writer.write_all(&buf))
+            .map_err(io_error)
+    }
+
+    fn finish(mut self) -> ExecResult<Self> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush().map_err(io_error)?;
+        }
+        Ok(self)
+    }
+
+    fn reader(self) -> ExecResult<SpillReader> {
+        let file = File::open(&self.path).map_err(io_error)?;
+        Ok(SpillReader {
+            reader: BufReader::new(file),
+            _file: self,
+        })
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        self.writer = None;
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+struct SpillReader {
+    reader: BufReader<File>,
+    /// Keeps the file around until it has been read.
+    _file: SpillFile,
+}
+
+impl SpillReader {
+    /// Start reading from the first row again.
+    fn rewind(&mut self) -> ExecResult<()> {
+        self.reader.rewind().map_err(io_error)
+    }
+
+    fn next_row(&mut self) -> ExecResult<Option<Row>> {
+        let mut len = [0u8; 4];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(io_error(e)),
+        }
+
+        let mut buf = vec![0u8; u32::from_le_bytes(len) as usize];
+        self.reader.read_exact(&mut buf).map_err(io_error)?;
+
+        let mut input = buf.as_slice();
+        let mut row = Vec::new();
+        while !input.is_empty() {
+            row.push(Value::deserialize(&mut input));
+        }
+        Ok(Some(row))
+    }
+}
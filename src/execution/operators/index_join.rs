@@ -0,0 +1,184 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use crate::execution::context::ExecutionContext;
+use crate::execution::errors::{ExecutionError, TableMutationStats};
+use crate::execution::eval_expr::eval_expr;
+use crate::execution::executor::{ExecResult, Executor, Row};
+use crate::ir::expr::Expr;
+use crate::ir::plan::{JoinSide, JoinType};
+use crate::storage::heap::heap_table::HeapTable;
+use crate::storage::index::btree::key::IndexKey;
+use crate::storage::index::index::Index;
+use crate::storage::page::row_id::RowId;
+use crate::txn::lock_manager::{LockMode, LockTarget};
+use crate::types::value::Value;
+
+/// Index nested loop join. Streams the outer input and, for each row, looks
+/// up the matching inner rows through an index on the inner join column
+/// instead of reading the inner table. Semi and anti joins output each outer
+/// row once, if it has a match or if it has none.
+pub struct IndexJoinExecutor {
+    outer: Box<dyn Executor>,
+    index: Arc<Mutex<dyn Index>>,
+    heap: Arc<HeapTable>,
+    outer_key: Expr,
+    /// Position of the indexed column in the inner table's rows.
+    key_pos: usize,
+    residual: Option<Expr>,
+    join_type: JoinType,
+    inner_side: JoinSide,
+    inner_width: usize,
+    pad_outer: bool,
+
+    /// Inner rows whose visible version may differ from what the index
+    /// says; they are checked against every outer key.
+    recheck: HashSet<RowId>,
+    pending: VecDeque<Row>,
+}
+
+impl IndexJoinExecutor {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        outer: Box<dyn Executor>,
+        index: Arc<Mutex<dyn Index>>,
+        heap: Arc<HeapTable>,
+        outer_key: Expr,
+        key_pos: usize,
+        residual: Option<Expr>,
+        join_type: JoinType,
+        inner_side: JoinSide,
+        inner_width: usize,
+    ) -> Self {
+        // Semi and anti joins output outer rows alone.
+        debug_assert!(!join_type.left_only() || inner_side == JoinSide::Right);
+        let pad_outer = match inner_side {
+            JoinSide::Right => matches!(join_type, JoinType::Left | JoinType::Full),
+            JoinSide::Left => matches!(join_type, JoinType::Right | JoinType::Full),
+        };
+
+        Self {
+            outer,
+            index,
+            heap,
+            outer_key,
+            key_pos,
+            residual,
+            join_type,
+            inner_side,
+            inner_width,
+            pad_outer,
+            recheck: HashSet::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Joined row in left-then-right order.
+    fn joined(&self, outer: &Row, inner: Row) -> Row {
+        let (mut left, right) = match self.inner_side {
+            JoinSide::Right => (outer.clone(), inner),
+            JoinSide::Left => (inner, outer.clone()),
+        };
+        left.extend(right);
+        left
+    }
+
+    fn inner_rows(&self, key: &Value, ctx: &mut ExecutionContext) -> ExecResult<Vec<Row>> {
+        // NULL, and keys of types the index cannot hold, match nothing.
+        let Ok(index_key) = IndexKey::try_from(key) else {
+            return Ok(Vec::new());
+        };
+        ctx.stats.index_lookups += 1;
+
+        let mut rids = self.index.lock().unwrap().get(&index_key)?;
+        let listed: HashSet<RowId> = rids.iter().copied().collect();
+        rids.extend(self.recheck.iter().filter(|rid| !listed.contains(rid)));
+
+        let mut rows = Vec::new();
+        for rid in rids {
+            let Some(row) = self.heap.fetch_at(rid, &ctx.snapshot)? else {
+                continue;
+            };
+            if self.recheck.contains(&rid)
+                && IndexKey::try_from(&row.values[self.key_pos]).ok().as_ref() != Some(&index_key)
+            {
+                continue;
+            }
+            rows.push(row.values);
+        }
+        Ok(rows)
+    }
+
+    fn passes_residual(&self, row: &Row, ctx: &mut ExecutionContext) -> ExecResult<bool> {
+        let Some(residual) = &self.residual else {
+            return Ok(true);
+        };
+        match eval_expr(residual, row, ctx)? {
+            Value::Boolean(b) => Ok(b),
+            Value::Null => Ok(false),
+            _ => Err(ExecutionError::InvalidExpression {
+                reason: "join predicate must be boolean".into(),
+            }),
+        }
+    }
+}
+
+impl Executor for IndexJoinExecutor {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> ExecResult<()> {
+        self.pending.clear();
+        self.outer.open(ctx)?;
+        ctx.lock(
+            LockTarget::Table(self.heap.table_id()),
+            LockMode::IntentionShared,
+        )?;
+
+        // The index only knows the newest versions, see `IndexScanExecutor`.
+        self.recheck = self.heap.changed_rows(&ctx.snapshot)?.into_iter().collect();
+        Ok(())
+    }
+
+    fn next(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Ok(Some(row));
+            }
+
+            let Some(outer) = self.outer.next(ctx)? else {
+                return Ok(None);
+            };
+
+            let key = eval_expr(&self.outer_key, &outer, ctx)?;
+            for inner in self.inner_rows(&key, ctx)? {
+                let joined = self.joined(&outer, inner);
+                if self.passes_residual(&joined, ctx)? {
+                    self.pending.push_back(joined);
+                    if self.join_type.left_only() {
+                        break;
+                    }
+                }
+            }
+
+            if self.join_type.left_only() {
+                let found = self.pending.pop_front().is_some();
+                if found == (self.join_type == JoinType::Semi) {
+                    return Ok(Some(outer));
+                }
+                continue;
+            }
+
+            if self.pending.is_empty() && self.pad_outer {
+                return Ok(Some(
+                    self.joined(&outer, vec![Value::Null; self.inner_width]),
+                ));
+            }
+        }
+    }
+
+    fn close(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Vec<TableMutationStats>> {
+        self.pending.clear();
+        self.recheck.clear();
+        self.outer.close(ctx)
+    }
+}
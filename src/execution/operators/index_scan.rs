@@ -1,4 +1,7 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
 
 use crate::execution::context::ExecutionContext;
 use crate::execution::errors::{ExecutionError, TableMutationStats};
@@ -8,12 +11,19 @@ use crate::storage::heap::heap_table::HeapTable;
 use crate::storage::index::btree::key::IndexKey;
 use crate::storage::index::index::Index;
 use crate::storage::page::row_id::RowId;
+use crate::txn::lock_manager::{LockMode, LockTarget};
+use crate::types::value::Value;
 
 pub struct IndexScanExecutor {
     index: Arc<Mutex<dyn Index>>,
     heap: Arc<HeapTable>,
     predicate: IndexPredicate,
+    /// Position of the indexed column in the table's rows.
+    key_pos: usize,
     rids: Vec<RowId>,
+    /// Rows whose visible version may differ from what the index says; their
+    /// key is checked against the predicate again.
+    recheck: HashSet<RowId>,
     pos: usize,
 }
 
@@ -22,21 +32,44 @@ impl IndexScanExecutor {
         index: Arc<Mutex<dyn Index>>,
         heap: Arc<HeapTable>,
         predicate: IndexPredicate,
+        key_pos: usize,
     ) -> Self {
         Self {
             index,
             heap,
             predicate,
+            key_pos,
             rids: Vec::new(),
+            recheck: HashSet::new(),
             pos: 0,
         }
     }
+
+    fn matches(&self, key: &Value) -> bool {
+        let Ok(key) = IndexKey::try_from(key) else {
+            return false;
+        };
+        match &self.predicate {
+            IndexPredicate::Eq(v) => IndexKey::try_from(v).is_ok_and(|v| key == v),
+            IndexPredicate::In(values) => values
+                .iter()
+                .any(|v| IndexKey::try_from(v).is_ok_and(|v| key == v)),
+            IndexPredicate::Range { low, high } => {
+                IndexKey::try_from(low).is_ok_and(|low| key >= low)
+                    && IndexKey::try_from(high).is_ok_and(|high| key <= high)
+            }
+        }
+    }
 }
 
 impl Executor for IndexScanExecutor {
     fn open(&mut self, ctx: &mut ExecutionContext) -> ExecResult<()> {
         self.pos = 0;
         ctx.stats.index_lookups += 1;
+        ctx.lock(
+            LockTarget::Table(self.heap.table_id()),
+            LockMode::IntentionShared,
+        )?;
 
         let idx = self.index.lock().unwrap();
         self.rids = match &self.predicate {
@@ -45,6 +78,16 @@ impl Executor for IndexScanExecutor {
                     .map_err(|e| ExecutionError::InvalidExpression { reason: e.into() })?;
                 idx.get(&k)?
             }
+            IndexPredicate::In(values) => {
+                let mut seen = HashSet::new();
+                let mut rids = Vec::new();
+                for v in values {
+                    let k = IndexKey::try_from(v)
+                        .map_err(|e| ExecutionError::InvalidExpression { reason: e.into() })?;
+                    rids.extend(idx.get(&k)?.into_iter().filter(|rid| seen.insert(*rid)));
+                }
+                rids
+            }
             IndexPredicate::Range { low, high } => {
                 let l = IndexKey::try_from(low)
                     .map_err(|e| ExecutionError::InvalidExpression { reason: e.into() })?;
@@ -53,25 +96,44 @@ impl Executor for IndexScanExecutor {
                 idx.range(&l, &h)?
             }
         };
+        drop(idx);
+
+        // The index only knows the newest versions. Rows changed by
+        // transactions the snapshot does not see may be visible under a
+        // different key, or under a key the index no longer lists.
+        self.recheck = self.heap.changed_rows(&ctx.snapshot)?.into_iter().collect();
+        let listed: HashSet<RowId> = self.rids.iter().copied().collect();
+        let mut extra: Vec<RowId> = self
+            .recheck
+            .iter()
+            .filter(|rid| !listed.contains(rid))
+            .copied()
+            .collect();
+        extra.sort();
+        self.rids.extend(extra);
 
         Ok(())
     }
 
-    fn next(&mut self, _ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
-        if self.pos >= self.rids.len() {
-            return Ok(None);
-        }
-
-        let rid = self.rids[self.pos];
-        self.pos += 1;
+    fn next(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        while self.pos < self.rids.len() {
+            let rid = self.rids[self.pos];
+            self.pos += 1;
 
-        let row = self.heap.fetch(rid)?;
-        Ok(Some(row.values.clone()))
+            let Some(row) = self.heap.fetch_at(rid, &ctx.snapshot)? else {
+                continue;
+            };
+            if self.recheck.contains(&rid) && !self.matches(&row.values[self.key_pos]) {
+                continue;
+            }
+            return Ok(Some(row.values));
+        }
+        Ok(None)
     }
 
     fn close(&mut self, _ctx: &mut ExecutionContext) -> ExecResult<Vec<TableMutationStats>> {
         self.rids.clear();
+        self.recheck.clear();
         Ok(vec![])
     }
 }
-
@@ -5,6 +5,8 @@ use crate::execution::eval_expr::eval_expr;
 use crate::execution::executor::{ExecResult, Executor, Row};
 use crate::ir::expr::Expr;
 use crate::storage::index::btree::key::IndexKey;
+use crate::txn::lock_manager::{LockMode, LockTarget};
+use crate::txn::wal::record::LogBody;
 
 pub struct InsertExecutor {
     table_id: TableId,
@@ -31,11 +33,8 @@ impl Executor for InsertExecutor {
     }
 
     fn next(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
-        if self.pos >= self.rows.len() {
-            return Ok(None);
-        }
-
-        let _table_meta =
+        // INSERT produces no rows; every row is written on the first call.
+        let table_meta =
             ctx.catalog
                 .get_table_by_id(self.table_id)
                 .ok_or(ExecutionError::TableNotFound {
@@ -43,28 +42,46 @@ impl Executor for InsertExecutor {
                 })?;
 
         let heap = ctx.get_heap(self.table_id)?;
-        let exprs = &self.rows[self.pos];
-        self.pos += 1;
+        ctx.lock(
+            LockTarget::Table(self.table_id),
+            LockMode::IntentionExclusive,
+        )?;
 
-        let mut values = Vec::with_capacity(exprs.len());
-        for e in exprs {
-            values.push(eval_expr(e, &[])?);
-        }
+        while self.pos < self.rows.len() {
+            let exprs = &self.rows[self.pos];
+            self.pos += 1;
 
-        let rid = heap.insert(values.clone())?;
-        self.stats.rows_affected += 1;
+            let mut values = Vec::with_capacity(exprs.len());
+            for e in exprs {
+                values.push(eval_expr(e, &[], ctx)?);
+            }
 
-        for idx in ctx.catalog.indexes_for_table(self.table_id) {
-            let col = idx.meta.column_ids[0];
-            let key = IndexKey::try_from(&values[col.0 as usize]).map_err(|e| {
-                ExecutionError::IndexViolation {
-                    index_id: idx.meta.id,
-                    reason: e.into(),
-                }
-            })?;
+            let rid = heap.insert(ctx.txn_id, values.clone())?;
+            self.stats.rows_affected += 1;
 
-            idx.index.lock().unwrap().insert(key, rid)?;
-            self.stats.record_index_insert(idx.meta.id);
+            for idx in ctx.catalog.indexes_for_table(self.table_id) {
+                let pos = table_meta
+                    .schema
+                    .position(idx.meta.column_ids[0])
+                    .ok_or_else(|| {
+                        ExecutionError::index_key_error(idx.meta.id, "indexed column not in table")
+                    })?;
+                let key = IndexKey::try_from(&values[pos]).map_err(|e| {
+                    ExecutionError::IndexViolation {
+                        index_id: idx.meta.id,
+                        reason: e.into(),
+                    }
+                })?;
+
+                let body = LogBody::IndexInsert {
+                    index_id: idx.meta.id,
+                    key: key.clone(),
+                    rid,
+                };
+                ctx.storage.log(ctx.txn_id, body)?;
+                idx.index.lock().unwrap().insert(key, rid)?;
+                self.stats.record_index_insert(idx.meta.id);
+            }
         }
 
         Ok(None)
@@ -74,4 +91,3 @@ impl Executor for InsertExecutor {
         Ok(vec![self.stats.clone()])
     }
 }
-
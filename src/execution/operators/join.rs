@@ -6,34 +6,76 @@ use crate::ir::expr::Expr;
 use crate::ir::plan::JoinType;
 use crate::types::value::Value;
 
+/// Nested loop join. The right input is buffered on `open`; each left row
+/// is then matched against all of it. Outer joins pad the side that found
+/// no match with NULLs: unmatched left rows as soon as their scan ends,
+/// unmatched right rows once the left input is exhausted. Semi and anti
+/// joins output a left row on its first match, or once it has found none.
 pub struct JoinExecutor {
     left: Box<dyn Executor>,
     right: Box<dyn Executor>,
     on: Expr,
     join_type: JoinType,
+    left_width: usize,
+    right_width: usize,
 
     right_buf: Vec<Row>,
+    right_matched: Vec<bool>,
     left_row: Option<Row>,
+    left_matched: bool,
     right_pos: usize,
+    /// Position in `right_buf` while emitting unmatched right rows.
+    unmatched_pos: Option<usize>,
 }
 
 impl JoinExecutor {
+    /// `left_width` and `right_width` are the number of columns each input
+    /// produces, used to NULL-pad rows.
     pub fn new(
         left: Box<dyn Executor>,
         right: Box<dyn Executor>,
         on: Expr,
         join_type: JoinType,
+        left_width: usize,
+        right_width: usize,
     ) -> Self {
         Self {
             left,
             right,
             on,
             join_type,
+            left_width,
+            right_width,
             right_buf: Vec::new(),
+            right_matched: Vec::new(),
             left_row: None,
+            left_matched: false,
             right_pos: 0,
+            unmatched_pos: None,
         }
     }
+
+    fn pads_left_rows(&self) -> bool {
+        matches!(self.join_type, JoinType::Left | JoinType::Full)
+    }
+
+    fn pads_right_rows(&self) -> bool {
+        matches!(self.join_type, JoinType::Right | JoinType::Full)
+    }
+
+    fn next_unmatched_right(&mut self) -> Option<Row> {
+        let pos = self.unmatched_pos.get_or_insert(0);
+        while *pos < self.right_buf.len() {
+            let i = *pos;
+            *pos += 1;
+            if !self.right_matched[i] {
+                let mut row = vec![Value::Null; self.left_width];
+                row.extend_from_slice(&self.right_buf[i]);
+                return Some(row);
+            }
+        }
+        None
+    }
 }
 
 impl Executor for JoinExecutor {
@@ -41,6 +83,7 @@ impl Executor for JoinExecutor {
         self.right_buf.clear();
         self.left_row = None;
         self.right_pos = 0;
+        self.unmatched_pos = None;
 
         self.left.open(ctx)?;
         self.right.open(ctx)?;
@@ -48,17 +91,26 @@ impl Executor for JoinExecutor {
         while let Some(row) = self.right.next(ctx)? {
             self.right_buf.push(row);
         }
+        self.right_matched = vec![false; self.right_buf.len()];
 
         Ok(())
     }
 
     fn next(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        if self.unmatched_pos.is_some() {
+            return Ok(self.next_unmatched_right());
+        }
+
         loop {
             if self.left_row.is_none() {
                 self.left_row = self.left.next(ctx)?;
+                self.left_matched = false;
                 self.right_pos = 0;
 
                 if self.left_row.is_none() {
+                    if self.pads_right_rows() {
+                        return Ok(self.next_unmatched_right());
+                    }
                     return Ok(None);
                 }
             }
@@ -66,15 +118,28 @@ impl Executor for JoinExecutor {
             let left = self.left_row.as_ref().unwrap();
 
             while self.right_pos < self.right_buf.len() {
-                let right = &self.right_buf[self.right_pos];
+                let i = self.right_pos;
+                let right = &self.right_buf[i];
                 self.right_pos += 1;
 
                 let mut joined = Vec::with_capacity(left.len() + right.len());
                 joined.extend_from_slice(left);
                 joined.extend_from_slice(right);
 
-                match eval_expr(&self.on, &joined)? {
-                    Value::Boolean(true) => return Ok(Some(joined)),
+                match eval_expr(&self.on, &joined, ctx)? {
+                    Value::Boolean(true) => match self.join_type {
+                        // The first match settles a semi or anti join.
+                        JoinType::Semi => return Ok(self.left_row.take()),
+                        JoinType::Anti => {
+                            self.left_matched = true;
+                            break;
+                        }
+                        _ => {
+                            self.left_matched = true;
+                            self.right_matched[i] = true;
+                            return Ok(Some(joined));
+                        }
+                    },
                     Value::Boolean(false) | Value::Null => continue,
                     _ => {
                         return Err(ExecutionError::InvalidExpression {
@@ -84,12 +149,21 @@ impl Executor for JoinExecutor {
                 }
             }
 
-            self.left_row = None;
+            let left = self.left_row.take().unwrap();
+            if !self.left_matched && self.join_type == JoinType::Anti {
+                return Ok(Some(left));
+            }
+            if !self.left_matched && self.pads_left_rows() {
+                let mut row = left;
+                row.resize(self.left_width + self.right_width, Value::Null);
+                return Ok(Some(row));
+            }
         }
     }
 
     fn close(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Vec<TableMutationStats>> {
         self.right_buf.clear();
+        self.right_matched.clear();
         self.left.close(ctx)?;
         self.right.close(ctx)?;
         Ok(vec![])
@@ -1,11 +1,17 @@
 pub mod aggregate;
 pub mod delete;
+pub mod distinct;
 pub mod filter;
+pub mod hash_join;
+pub mod index_join;
 pub mod index_scan;
 pub mod insert;
 pub mod join;
 pub mod limit;
 pub mod project;
+pub mod recursive_cte;
 pub mod scan;
+pub mod set_op;
 pub mod sort;
 pub mod update;
+pub mod work_table;
@@ -28,7 +28,7 @@ impl Executor for ProjectExecutor {
 
         let mut out = Vec::with_capacity(self.exprs.len());
         for expr in &self.exprs {
-            out.push(eval_expr(expr, &row)?);
+            out.push(eval_expr(expr, &row, ctx)?);
         }
 
         Ok(Some(out))
@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+
+use crate::execution::context::ExecutionContext;
+use crate::execution::engine::build_executor;
+use crate::execution::errors::TableMutationStats;
+use crate::execution::executor::{ExecResult, Executor, Row};
+use crate::ir::plan::LogicalPlan;
+
+/// Runs a recursive CTE. Outputs the anchor's rows, then runs the recursive
+/// term over the rows found in the round before, which it reads as work
+/// table `id`, until a round finds nothing new. Without `all`, rows seen
+/// before are dropped, which also stops cycles.
+pub struct RecursiveCteExecutor {
+    id: usize,
+    anchor: Box<dyn Executor>,
+    recursive: LogicalPlan,
+    all: bool,
+
+    /// The term being read; the recursive one once the anchor is done.
+    current: Option<Box<dyn Executor>>,
+    in_anchor: bool,
+    found: Vec<Row>,
+    seen: HashSet<Vec<u8>>,
+}
+
+impl RecursiveCteExecutor {
+    pub fn new(id: usize, anchor: Box<dyn Executor>, recursive: LogicalPlan, all: bool) -> Self {
+        Self {
+            id,
+            anchor,
+            recursive,
+            all,
+            current: None,
+            in_anchor: true,
+            found: Vec::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Whether `row` has not been output before.
+    fn is_new(&mut self, row: &Row) -> bool {
+        if self.all {
+            return true;
+        }
+        let mut key = Vec::new();
+        for v in row {
+            v.serialize(&mut key);
+        }
+        self.seen.insert(key)
+    }
+
+    /// Start a round over the rows found in the last one, or return false
+    /// if it found none.
+    fn next_round(&mut self, ctx: &mut ExecutionContext) -> ExecResult<bool> {
+        if let Some(mut done) = self.current.take() {
+            done.close(ctx)?;
+        }
+        if self.found.is_empty() {
+            return Ok(false);
+        }
+
+        ctx.work_tables
+            .insert(self.id, std::mem::take(&mut self.found));
+        let mut term = build_executor(self.recursive.clone(), ctx)?;
+        term.open(ctx)?;
+        self.current = Some(term);
+        Ok(true)
+    }
+}
+
+impl Executor for RecursiveCteExecutor {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> ExecResult<()> {
+        self.in_anchor = true;
+        self.current = None;
+        self.found.clear();
+        self.seen.clear();
+        self.anchor.open(ctx)
+    }
+
+    fn next(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        loop {
+            let row = if self.in_anchor {
+                self.anchor.next(ctx)?
+            } else {
+                match &mut self.current {
+                    Some(term) => term.next(ctx)?,
+                    None => return Ok(None),
+                }
+            };
+
+            match row {
+                Some(row) => {
+                    if self.is_new(&row) {
+                        self.found.push(row.clone());
+                        return Ok(Some(row));
+                    }
+                }
+                None => {
+                    self.in_anchor = false;
+                    if !self.next_round(ctx)? {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    }
+
+    fn close(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Vec<TableMutationStats>> {
+        if let Some(mut term) = self.current.take() {
+            term.close(ctx)?;
+        }
+        self.found.clear();
+        self.seen.clear();
+        ctx.work_tables.remove(&self.id);
+        self.anchor.close(ctx)
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
         executor::{ExecResult, Executor, Row},
     },
     storage::heap::heap_cursor::HeapCursor,
+    txn::lock_manager::{LockMode, LockTarget},
 };
 use std::sync::Arc;
 
@@ -37,10 +38,12 @@ impl Executor for ScanExecutor {
                 })?;
 
         let heap = ctx.get_heap(self.table_id)?;
+        ctx.lock(LockTarget::Table(self.table_id), LockMode::IntentionShared)?;
 
         // Materialize all rows during open
         self.rows.clear();
-        for (_rid, row) in heap.scan() {
+        for entry in heap.scan_at(&ctx.snapshot) {
+            let (_rid, row) = entry?;
             self.rows.push(row.values.clone());
         }
 
@@ -66,4 +69,3 @@ impl Executor for ScanExecutor {
         Ok(vec![])
     }
 }
-
@@ -0,0 +1,134 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::execution::context::ExecutionContext;
+use crate::execution::errors::TableMutationStats;
+use crate::execution::executor::{ExecResult, Executor, Row};
+use crate::execution::key::encode;
+use crate::ir::plan::SetOpType;
+
+/// Hash-based UNION, INTERSECT and EXCEPT. Rows are compared whole, with
+/// NULLs equal to each other. INTERSECT and EXCEPT count the right rows up
+/// front and stream the left ones against the counts; the distinct
+/// variants remember the rows they output.
+pub struct SetOpExecutor {
+    op: SetOpType,
+    all: bool,
+    left: Box<dyn Executor>,
+    right: Box<dyn Executor>,
+
+    /// Right rows not yet matched by a left one.
+    counts: HashMap<Vec<u8>, usize>,
+    /// Rows output so far, when duplicates are dropped.
+    seen: HashSet<Vec<u8>>,
+    left_done: bool,
+}
+
+impl SetOpExecutor {
+    pub fn new(
+        op: SetOpType,
+        all: bool,
+        left: Box<dyn Executor>,
+        right: Box<dyn Executor>,
+    ) -> Self {
+        Self {
+            op,
+            all,
+            left,
+            right,
+            counts: HashMap::new(),
+            seen: HashSet::new(),
+            left_done: false,
+        }
+    }
+
+    fn next_union(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        loop {
+            if !self.left_done {
+                match self.left.next(ctx)? {
+                    Some(row) => {
+                        if self.all || self.seen.insert(encode(&row)) {
+                            return Ok(Some(row));
+                        }
+                        continue;
+                    }
+                    None => self.left_done = true,
+                }
+            }
+
+            let Some(row) = self.right.next(ctx)? else {
+                return Ok(None);
+            };
+            if self.all || self.seen.insert(encode(&row)) {
+                return Ok(Some(row));
+            }
+        }
+    }
+
+    fn next_intersect(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        while let Some(row) = self.left.next(ctx)? {
+            let Some(n) = self.counts.get_mut(&encode(&row)) else {
+                continue;
+            };
+            if *n == 0 {
+                continue;
+            }
+            // Without ALL, a row is output once, however often it matches.
+            *n = if self.all { *n - 1 } else { 0 };
+            return Ok(Some(row));
+        }
+        Ok(None)
+    }
+
+    fn next_except(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        while let Some(row) = self.left.next(ctx)? {
+            let key = encode(&row);
+            match self.counts.get_mut(&key) {
+                // With ALL, each right row cancels one left row.
+                Some(n) if *n > 0 => {
+                    if self.all {
+                        *n -= 1;
+                    }
+                }
+                _ => {
+                    if self.all || self.seen.insert(key) {
+                        return Ok(Some(row));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Executor for SetOpExecutor {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> ExecResult<()> {
+        self.counts.clear();
+        self.seen.clear();
+        self.left_done = false;
+        self.left.open(ctx)?;
+        self.right.open(ctx)?;
+
+        if self.op != SetOpType::Union {
+            while let Some(row) = self.right.next(ctx)? {
+                *self.counts.entry(encode(&row)).or_default() += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn next(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        match self.op {
+            SetOpType::Union => self.next_union(ctx),
+            SetOpType::Intersect => self.next_intersect(ctx),
+            SetOpType::Except => self.next_except(ctx),
+        }
+    }
+
+    fn close(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Vec<TableMutationStats>> {
+        self.counts.clear();
+        self.seen.clear();
+        let mut stats = self.left.close(ctx)?;
+        stats.extend(self.right.close(ctx)?);
+        Ok(stats)
+    }
+}
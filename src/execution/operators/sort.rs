@@ -2,7 +2,7 @@ use std::cmp::Ordering;
 
 use crate::execution::context::ExecutionContext;
 use crate::execution::errors::TableMutationStats;
-use crate::execution::eval_expr::eval_expr;
+use crate::execution::eval_expr::{self, eval_expr};
 use crate::execution::executor::{ExecResult, Executor, Row};
 use crate::ir::plan::SortKey;
 use crate::types::value::Value;
@@ -31,12 +31,19 @@ impl Executor for SortExecutor {
         self.pos = 0;
         self.input.open(ctx)?;
 
+        // Keys are computed once per row, not per comparison.
+        let mut keyed = Vec::new();
         while let Some(row) = self.input.next(ctx)? {
-            self.buffer.push(row);
+            let values = self
+                .keys
+                .iter()
+                .map(|k| eval_expr(&k.expr, &row, ctx))
+                .collect::<ExecResult<Vec<_>>>()?;
+            keyed.push((values, row));
         }
 
-        let keys = self.keys.clone();
-        self.buffer.sort_by(|a, b| compare_rows(a, b, &keys));
+        keyed.sort_by(|(a, _), (b, _)| compare_keys(a, b, &self.keys));
+        self.buffer = keyed.into_iter().map(|(_, row)| row).collect();
 
         Ok(())
     }
@@ -58,12 +65,9 @@ impl Executor for SortExecutor {
     }
 }
 
-fn compare_rows(a: &Row, b: &Row, keys: &[SortKey]) -> Ordering {
-    for key in keys {
-        let va = eval_expr(&key.expr, a).unwrap_or(Value::Null);
-        let vb = eval_expr(&key.expr, b).unwrap_or(Value::Null);
-
-        let ord = compare_values(&va, &vb);
+fn compare_keys(a: &[Value], b: &[Value], keys: &[SortKey]) -> Ordering {
+    for ((key, va), vb) in keys.iter().zip(a).zip(b) {
+        let ord = compare_values(va, vb);
         if ord != Ordering::Equal {
             return if key.asc { ord } else { ord.reverse() };
         }
@@ -77,6 +81,6 @@ fn compare_values(a: &Value, b: &Value) -> Ordering {
         (Value::Null, Value::Null) => Equal,
         (Value::Null, _) => Greater,
         (_, Value::Null) => Less,
-        _ => a.partial_cmp(b).unwrap_or(Equal),
+        _ => eval_expr::compare_values(a, b).unwrap_or(Equal),
     }
 }
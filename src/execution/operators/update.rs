@@ -1,8 +1,12 @@
 use crate::catalog::ids::{ColumnId, TableId};
 use crate::execution::context::ExecutionContext;
-use crate::execution::errors::TableMutationStats;
+use crate::execution::errors::{ExecutionError, TableMutationStats};
+use crate::execution::eval_expr::eval_expr;
 use crate::execution::executor::{ExecResult, Executor, Row};
 use crate::ir::expr::Expr;
+use crate::storage::index::btree::key::IndexKey;
+use crate::txn::lock_manager::{LockMode, LockTarget};
+use crate::txn::wal::record::LogBody;
 
 pub struct UpdateExecutor {
     pub(crate) table_id: TableId,
@@ -11,6 +15,7 @@ pub struct UpdateExecutor {
 
     // runtime
     done: bool,
+    stats: TableMutationStats,
 }
 
 impl UpdateExecutor {
@@ -24,6 +29,7 @@ impl UpdateExecutor {
             assignments,
             predicate,
             done: false,
+            stats: TableMutationStats::new(table_id),
         }
     }
 }
@@ -34,18 +40,93 @@ impl Executor for UpdateExecutor {
         Ok(())
     }
 
-    fn next(&mut self, _ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+    fn next(&mut self, ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
         // UPDATE produces no rows
         if self.done {
-            Ok(None)
-        } else {
-            self.done = true;
-            Ok(None)
+            return Ok(None);
         }
+
+        self.done = true;
+
+        let table_meta =
+            ctx.catalog
+                .get_table_by_id(self.table_id)
+                .ok_or(ExecutionError::TableNotFound {
+                    table_id: self.table_id,
+                })?;
+
+        let mut targets = Vec::with_capacity(self.assignments.len());
+        for (col, expr) in &self.assignments {
+            let pos = table_meta
+                .schema
+                .position(*col)
+                .ok_or(ExecutionError::UnboundColumn)?;
+            targets.push((pos, expr));
+        }
+
+        let heap = ctx.get_heap(self.table_id)?;
+        ctx.lock(
+            LockTarget::Table(self.table_id),
+            LockMode::IntentionExclusive,
+        )?;
+
+        // Collect first so rows moved by the update are not visited twice.
+        let mut to_update = Vec::new();
+        for (rid, old_row) in ctx.lock_rows(&heap, self.predicate.as_ref())? {
+            let mut new_row = old_row.clone();
+            for (pos, expr) in &targets {
+                new_row[*pos] = eval_expr(expr, &old_row, ctx)?;
+            }
+            to_update.push((rid, old_row, new_row));
+        }
+
+        for (rid, old_row, new_row) in to_update {
+            let new_rid = heap.update(ctx.txn_id, rid, new_row.clone())?;
+
+            for idx in ctx.catalog.indexes_for_table(self.table_id) {
+                let pos = table_meta
+                    .schema
+                    .position(idx.meta.column_ids[0])
+                    .ok_or_else(|| {
+                        ExecutionError::index_key_error(idx.meta.id, "indexed column not in table")
+                    })?;
+                if new_rid == rid && old_row[pos] == new_row[pos] {
+                    continue;
+                }
+
+                let old_key = IndexKey::try_from(&old_row[pos])
+                    .map_err(|e| ExecutionError::index_key_error(idx.meta.id, e))?;
+                let new_key = IndexKey::try_from(&new_row[pos])
+                    .map_err(|e| ExecutionError::index_key_error(idx.meta.id, e))?;
+
+                let body = LogBody::IndexDelete {
+                    index_id: idx.meta.id,
+                    key: old_key.clone(),
+                    rid,
+                };
+                ctx.storage.log(ctx.txn_id, body)?;
+                let mut index = idx.index.lock().unwrap();
+                index.delete(&old_key, rid)?;
+                self.stats.record_index_delete(idx.meta.id);
+
+                let body = LogBody::IndexInsert {
+                    index_id: idx.meta.id,
+                    key: new_key.clone(),
+                    rid: new_rid,
+                };
+                ctx.storage.log(ctx.txn_id, body)?;
+                index.insert(new_key, new_rid)?;
+                self.stats.record_index_insert(idx.meta.id);
+            }
+
+            self.stats.rows_written += 1;
+            self.stats.rows_affected += 1;
+        }
+
+        Ok(None)
     }
 
     fn close(&mut self, _ctx: &mut ExecutionContext) -> ExecResult<Vec<TableMutationStats>> {
-        // Actual UPDATE is performed by engine
-        Ok(vec![])
+        Ok(vec![self.stats.clone()])
     }
 }
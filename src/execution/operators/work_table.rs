@@ -0,0 +1,41 @@
+use std::collections::VecDeque;
+
+use crate::execution::context::ExecutionContext;
+use crate::execution::errors::TableMutationStats;
+use crate::execution::executor::{ExecResult, Executor, Row};
+
+/// Reads the rows the recursive CTE `id` found in its last round.
+pub struct WorkTableExecutor {
+    id: usize,
+    rows: VecDeque<Row>,
+}
+
+impl WorkTableExecutor {
+    pub fn new(id: usize) -> Self {
+        Self {
+            id,
+            rows: VecDeque::new(),
+        }
+    }
+}
+
+impl Executor for WorkTableExecutor {
+    fn open(&mut self, ctx: &mut ExecutionContext) -> ExecResult<()> {
+        self.rows = ctx
+            .work_tables
+            .get(&self.id)
+            .cloned()
+            .unwrap_or_default()
+            .into();
+        Ok(())
+    }
+
+    fn next(&mut self, _ctx: &mut ExecutionContext) -> ExecResult<Option<Row>> {
+        Ok(self.rows.pop_front())
+    }
+
+    fn close(&mut self, _ctx: &mut ExecutionContext) -> ExecResult<Vec<TableMutationStats>> {
+        self.rows.clear();
+        Ok(Vec::new())
+    }
+}
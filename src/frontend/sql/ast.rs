@@ -2,7 +2,7 @@ use crate::types::value::Value;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
-    Select(SelectStmt),
+    Select(Box<Query>),
     Insert(InsertStmt),
     Delete(DeleteStmt),
     Update(UpdateStmt),
@@ -21,16 +21,89 @@ pub enum Statement {
     DropIndex {
         name: String,
     },
+    Transaction(TransactionStmt),
+}
+
+/// Transaction control statements.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionStmt {
+    Begin,
+    Commit,
+    Rollback,
+    Savepoint(String),
+    RollbackTo(String),
+}
+
+/// A query: SELECTs, possibly combined by set operations, and the clauses
+/// that apply to the rows they produce.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// CTEs the query is defined under.
+    pub with: Option<WithClause>,
+    pub body: QueryBody,
+    pub order_by: Vec<OrderByExpr>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryBody {
+    Select(Box<SelectStmt>),
+    /// `left UNION|INTERSECT|EXCEPT [ALL] right`
+    SetOp {
+        op: SetOperator,
+        /// Keep duplicate rows.
+        all: bool,
+        left: Box<QueryBody>,
+        right: Box<QueryBody>,
+    },
+    /// A parenthesized query, which may have clauses of its own.
+    Nested(Box<Query>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperator {
+    Union,
+    Intersect,
+    Except,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct SelectStmt {
+    pub distinct: Option<Distinct>,
     pub columns: Vec<SelectItem>,
     pub from: FromItem,
     pub where_clause: Option<Expr>,
-    pub order_by: Vec<OrderByExpr>,
-    pub limit: Option<usize>,
-    pub offset: Option<usize>,
+    pub group_by: Vec<Expr>,
+    pub having: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Distinct {
+    /// `DISTINCT`: each distinct row once.
+    Rows,
+    /// `DISTINCT ON (exprs)`: the first row of each distinct value of
+    /// `exprs`.
+    On(Vec<Expr>),
+}
+
+/// `WITH [RECURSIVE] cte, ...` ahead of a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithClause {
+    /// Whether each CTE may read itself.
+    pub recursive: bool,
+    pub ctes: Vec<Cte>,
+}
+
+/// `name [(columns)] AS (query)`: a named query FROM can read like a table.
+/// In a recursive WITH, a query `anchor UNION [ALL] term` whose last term
+/// reads the CTE runs that term over the rows found so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cte {
+    pub name: String,
+    /// Names for the query's columns; empty to keep the query's own.
+    pub columns: Vec<String>,
+    pub query: Box<Query>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -54,9 +127,18 @@ pub struct ColumnDef {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum SqlType {
+    SmallInt,
     Int,
+    BigInt,
+    Real,
+    Double,
     Bool,
     Text,
+    /// `VARCHAR(n)`, or `VARCHAR` with no limit.
+    Varchar(Option<u32>),
+    Date,
+    Timestamp,
+    Blob,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -99,6 +181,67 @@ pub enum Expr {
         op: UnaryOp,
         expr: Box<Expr>,
     },
+    /// Function call such as `SUM(x)` or `COUNT(DISTINCT x)`. In `COUNT(*)`
+    /// the argument is a `*` column, as in `SELECT *`.
+    Function {
+        name: String,
+        args: Vec<Expr>,
+        distinct: bool,
+    },
+    /// `CAST(expr AS ty)` or `expr::ty`
+    Cast {
+        expr: Box<Expr>,
+        ty: SqlType,
+    },
+    /// `(SELECT ...)` used as a value.
+    Subquery(Box<Query>),
+    /// `EXISTS (SELECT ...)`
+    Exists(Box<Query>),
+    /// `expr [NOT] IN (SELECT ...)`
+    InSubquery {
+        expr: Box<Expr>,
+        subquery: Box<Query>,
+        negated: bool,
+    },
+    /// `expr [NOT] IN (a, b, ...)`
+    InList {
+        expr: Box<Expr>,
+        list: Vec<Expr>,
+        negated: bool,
+    },
+    /// `expr [NOT] BETWEEN low AND high`
+    Between {
+        expr: Box<Expr>,
+        low: Box<Expr>,
+        high: Box<Expr>,
+        negated: bool,
+    },
+    /// `expr [NOT] LIKE pattern [ESCAPE escape]`, or `ILIKE`, which ignores
+    /// case.
+    Like {
+        expr: Box<Expr>,
+        pattern: Box<Expr>,
+        escape: Option<Box<Expr>>,
+        case_insensitive: bool,
+        negated: bool,
+    },
+    /// `expr IS [NOT] NULL`
+    IsNull {
+        expr: Box<Expr>,
+        negated: bool,
+    },
+    /// `left IS [NOT] DISTINCT FROM right`
+    IsDistinctFrom {
+        left: Box<Expr>,
+        right: Box<Expr>,
+        negated: bool,
+    },
+    /// `CASE [operand] WHEN a THEN b ... [ELSE c] END`
+    Case {
+        operand: Option<Box<Expr>>,
+        branches: Vec<(Expr, Expr)>,
+        else_result: Option<Box<Expr>>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -121,6 +264,8 @@ pub enum BinaryOp {
     Sub,
     Mul,
     Div,
+    Mod,
+    Concat,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -138,6 +283,25 @@ pub enum FromItem {
     Join {
         left: Box<FromItem>,
         right: Box<FromItem>,
-        on: Expr,
+        kind: JoinKind,
+        constraint: JoinConstraint,
     },
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+    Cross,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JoinConstraint {
+    On(Expr),
+    /// `USING (a, b)`: equality on columns both sides have.
+    Using(Vec<String>),
+    /// `CROSS JOIN` takes no condition.
+    None,
+}
@@ -8,6 +8,9 @@ pub enum Token {
     Where,
     Order,
     By,
+    Group,
+    Having,
+    Distinct,
     Limit,
     And,
     Or,
@@ -16,7 +19,31 @@ pub enum Token {
     True,
     False,
     Join,
+    Inner,
+    Left,
+    Right,
+    Full,
+    Outer,
+    Cross,
     On,
+    Using,
+    Exists,
+    In,
+    Is,
+    Between,
+    Like,
+    ILike,
+    Case,
+    When,
+    Then,
+    Else,
+    End,
+    With,
+    Recursive,
+    Union,
+    All,
+    Intersect,
+    Except,
     Create,
     Drop,
     Table,
@@ -31,16 +58,28 @@ pub enum Token {
     Analyze,
     Asc,
     Desc,
+    Begin,
+    Commit,
+    Rollback,
+    Savepoint,
+    Transaction,
+    To,
 
     // identifiers
     Ident(String),
 
     // literals
     Int(i64),
+    Float(f64),
     String(String),
+    /// `x'...'`, with the hex digits between the quotes.
+    HexString(String),
 
     // punctuation
     Dot,
+    DoubleColon,
+    /// `||`
+    Concat,
     Comma,
     LParen,
     RParen,
@@ -57,6 +96,7 @@ pub enum Token {
     Minus,
     Star,
     Slash,
+    Percent,
 
     EOF,
 }
@@ -100,6 +140,7 @@ impl<'a> Tokenizer<'a> {
 
         let tok = match c {
             // ---------- punctuation ----------
+            '.' if self.chars.peek().is_some_and(|c| c.is_ascii_digit()) => self.number(c),
             '.' => Token::Dot,
             ',' => Token::Comma,
             '(' => Token::LParen,
@@ -111,8 +152,12 @@ impl<'a> Tokenizer<'a> {
             '-' => Token::Minus,
             '*' => Token::Star,
             '/' => Token::Slash,
+            '%' => Token::Percent,
             '=' => Token::Eq,
 
+            ':' if self.consume(':') => Token::DoubleColon,
+            '|' if self.consume('|') => Token::Concat,
+
             '!' => {
                 if self.consume('=') {
                     Token::NotEq
@@ -138,18 +183,7 @@ impl<'a> Tokenizer<'a> {
             }
 
             // ---------- string literal ----------
-            '\'' => {
-                let mut s = String::new();
-                while let Some(&_ch) = self.chars.peek() {
-                    let ch = self.chars.next().unwrap();
-                    self.advance_position(ch);
-                    if ch == '\'' {
-                        break;
-                    }
-                    s.push(ch);
-                }
-                Token::String(s)
-            }
+            '\'' => Token::String(self.quoted()),
             '"' => {
                 let mut s = String::new();
                 while let Some(&ch) = self.chars.peek() {
@@ -163,22 +197,16 @@ impl<'a> Tokenizer<'a> {
             }
 
             // ---------- number ----------
-            c if c.is_ascii_digit() => {
-                let mut num = c.to_string();
-                while let Some(&ch) = self.chars.peek() {
-                    if ch.is_ascii_digit() {
-                        num.push(ch);
-                        self.chars.next();
-                        self.advance_position(ch);
-                    } else {
-                        break;
-                    }
-                }
-                Token::Int(num.parse().unwrap())
-            }
+            c if c.is_ascii_digit() => self.number(c),
 
             // ---------- identifier ----------
             // ---------- identifier / keyword ----------
+            'x' | 'X' if self.chars.peek() == Some(&'\'') => {
+                let quote = self.chars.next().unwrap();
+                self.advance_position(quote);
+                Token::HexString(self.quoted())
+            }
+
             c if is_ident_start(c) => {
                 let mut ident = c.to_string();
                 while let Some(&ch) = self.chars.peek() {
@@ -197,6 +225,9 @@ impl<'a> Tokenizer<'a> {
                     "WHERE" => Token::Where,
                     "ORDER" => Token::Order,
                     "BY" => Token::By,
+                    "GROUP" => Token::Group,
+                    "HAVING" => Token::Having,
+                    "DISTINCT" => Token::Distinct,
                     "LIMIT" => Token::Limit,
                     "AND" => Token::And,
                     "OR" => Token::Or,
@@ -205,7 +236,31 @@ impl<'a> Tokenizer<'a> {
                     "TRUE" => Token::True,
                     "FALSE" => Token::False,
                     "JOIN" => Token::Join,
+                    "INNER" => Token::Inner,
+                    "LEFT" => Token::Left,
+                    "RIGHT" => Token::Right,
+                    "FULL" => Token::Full,
+                    "OUTER" => Token::Outer,
+                    "CROSS" => Token::Cross,
                     "ON" => Token::On,
+                    "USING" => Token::Using,
+                    "EXISTS" => Token::Exists,
+                    "IN" => Token::In,
+                    "IS" => Token::Is,
+                    "BETWEEN" => Token::Between,
+                    "LIKE" => Token::Like,
+                    "ILIKE" => Token::ILike,
+                    "CASE" => Token::Case,
+                    "WHEN" => Token::When,
+                    "THEN" => Token::Then,
+                    "ELSE" => Token::Else,
+                    "END" => Token::End,
+                    "WITH" => Token::With,
+                    "RECURSIVE" => Token::Recursive,
+                    "UNION" => Token::Union,
+                    "ALL" => Token::All,
+                    "INTERSECT" => Token::Intersect,
+                    "EXCEPT" => Token::Except,
                     "ASC" => Token::Asc,
                     "DESC" => Token::Desc,
                     "CREATE" => Token::Create,
@@ -220,6 +275,12 @@ impl<'a> Tokenizer<'a> {
                     "DELETE" => Token::Delete,
                     "EXPLAIN" => Token::Explain,
                     "ANALYZE" => Token::Analyze,
+                    "BEGIN" => Token::Begin,
+                    "COMMIT" => Token::Commit,
+                    "ROLLBACK" => Token::Rollback,
+                    "SAVEPOINT" => Token::Savepoint,
+                    "TRANSACTION" => Token::Transaction,
+                    "TO" => Token::To,
                     _ => Token::Ident(ident),
                 }
             }
@@ -230,6 +291,85 @@ impl<'a> Tokenizer<'a> {
         (tok, start_pos)
     }
 
+    /// The rest of a quoted string whose opening quote was read.
+    fn quoted(&mut self) -> String {
+        let mut s = String::new();
+        while let Some(&_ch) = self.chars.peek() {
+            let ch = self.chars.next().unwrap();
+            self.advance_position(ch);
+            if ch == '\'' {
+                break;
+            }
+            s.push(ch);
+        }
+        s
+    }
+
+    /// A number starting with `first`, a digit or the point of a fraction
+    /// such as `.5`. A fraction or an exponent makes it a float.
+    fn number(&mut self, first: char) -> Token {
+        let mut num = first.to_string();
+        let mut float = first == '.';
+        self.push_while(&mut num, |ch| ch.is_ascii_digit());
+        if !float
+            && self.chars.peek() == Some(&'.')
+            && self.peek_second().is_some_and(|c| c.is_ascii_digit())
+        {
+            float = true;
+            self.chars.next();
+            self.advance_position('.');
+            num.push('.');
+            self.push_while(&mut num, |ch| ch.is_ascii_digit());
+        }
+        if matches!(self.chars.peek(), Some('e' | 'E')) {
+            let sign = matches!(self.peek_second(), Some('+' | '-'));
+            let mut temp = self.chars.clone();
+            temp.next();
+            if sign {
+                temp.next();
+            }
+            if temp.peek().is_some_and(|c| c.is_ascii_digit()) {
+                float = true;
+                let e = self.chars.next().unwrap();
+                self.advance_position(e);
+                num.push(e);
+                if sign {
+                    let s = self.chars.next().unwrap();
+                    self.advance_position(s);
+                    num.push(s);
+                }
+                self.push_while(&mut num, |ch| ch.is_ascii_digit());
+            }
+        }
+        if float {
+            Token::Float(num.parse().unwrap())
+        } else {
+            match num.parse() {
+                Ok(n) => Token::Int(n),
+                // Too large for an integer.
+                Err(_) => Token::Float(num.parse().unwrap()),
+            }
+        }
+    }
+
+    fn push_while(&mut self, out: &mut String, keep: impl Fn(char) -> bool) {
+        while let Some(&ch) = self.chars.peek() {
+            if !keep(ch) {
+                break;
+            }
+            out.push(ch);
+            self.chars.next();
+            self.advance_position(ch);
+        }
+    }
+
+    /// The character after the next one.
+    fn peek_second(&self) -> Option<char> {
+        let mut temp = self.chars.clone();
+        temp.next();
+        temp.next()
+    }
+
     fn consume(&mut self, expected: char) -> bool {
         matches!(self.chars.peek(), Some(&c) if c == expected) && {
             self.chars.next();
@@ -6,6 +6,7 @@ use crate::{
         lexer::{Token, Tokenizer},
     },
     types::value::Value,
+    util::{hex, temporal},
 };
 
 use super::ast::*;
@@ -79,6 +80,14 @@ impl Parser {
             })
         }
     }
+    /// Error for finding the next token where `expected` should be.
+    fn expected(&self, expected: &str) -> ParseError {
+        ParseError::Expected {
+            expected: expected.into(),
+            found: Some(format!("{:?}", self.peek())),
+            position: self.current_position(),
+        }
+    }
     fn expect_ident(&mut self) -> Result<String, ParseError> {
         let pos = self.current_position();
         match self.next() {
@@ -129,10 +138,9 @@ impl Parser {
             let pos = self.current_position();
 
             let result = match self.peek() {
-                Token::Select => {
-                    self.next();
+                Token::Select | Token::With | Token::LParen => {
                     db_info!(Component::Parser, "Parsing SELECT statement");
-                    Statement::Select(self.parse_select()?)
+                    Statement::Select(Box::new(self.parse_query()?))
                 }
 
                 Token::Explain => {
@@ -207,6 +215,11 @@ impl Parser {
                     Statement::Delete(self.parse_delete()?)
                 }
 
+                Token::Begin | Token::Commit | Token::Rollback | Token::Savepoint => {
+                    db_info!(Component::Parser, "Parsing transaction statement");
+                    Statement::Transaction(self.parse_transaction()?)
+                }
+
                 t => Err(ParseError::UnexpectedToken {
                     token: t.clone(),
                     position: pos,
@@ -219,10 +232,10 @@ impl Parser {
     }
 
     fn parse_select(&mut self) -> Result<SelectStmt, ParseError> {
+        let distinct = self.parse_distinct()?;
         let columns = self.parse_select_list()?;
         self.expect(Token::From)?;
         let from = self.parse_from()?;
-        let pos = self.current_position();
 
         let where_clause = if matches!(self.peek(), Token::Where) {
             self.next();
@@ -231,6 +244,186 @@ impl Parser {
             None
         };
 
+        let group_by = if matches!(self.peek(), Token::Group) {
+            self.next();
+            self.expect(Token::By)?;
+            self.parse_expr_list()?
+        } else {
+            Vec::new()
+        };
+
+        let having = if matches!(self.peek(), Token::Having) {
+            self.next();
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+
+        Ok(SelectStmt {
+            distinct,
+            columns,
+            from,
+            where_clause,
+            group_by,
+            having,
+        })
+    }
+    fn parse_order_by(&mut self) -> Result<Vec<OrderByExpr>, ParseError> {
+        let mut out = Vec::new();
+
+        loop {
+            let expr = self.parse_expr()?;
+            let asc = if matches!(self.peek(), Token::Desc) {
+                self.next();
+                false
+            } else if matches!(self.peek(), Token::Asc) {
+                self.next();
+                true
+            } else {
+                true
+            };
+
+            out.push(OrderByExpr { expr, asc });
+
+            if matches!(self.peek(), Token::Comma) {
+                self.next();
+            } else {
+                break;
+            }
+        }
+
+        Ok(out)
+    }
+    fn parse_expr(&mut self) -> Result<Expr, ParseError> {
+        self.parse_or()
+    }
+
+    /// One or more comma-separated expressions.
+    fn parse_expr_list(&mut self) -> Result<Vec<Expr>, ParseError> {
+        let mut out = vec![self.parse_expr()?];
+        while matches!(self.peek(), Token::Comma) {
+            self.next();
+            out.push(self.parse_expr()?);
+        }
+        Ok(out)
+    }
+
+    /// Arguments of a function call, after its opening parenthesis:
+    /// `[DISTINCT] expr, ...)`, `*)` or `)`.
+    /// `CAST(expr AS type)`, after its opening parenthesis.
+    fn parse_cast(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.parse_expr()?;
+        if !self.peek().is_keyword("AS") {
+            return Err(ParseError::Expected {
+                expected: "AS".into(),
+                found: Some(format!("{:?}", self.peek())),
+                position: self.current_position(),
+            });
+        }
+        self.next();
+        let ty = self.parse_type()?;
+        self.expect(Token::RParen)?;
+        Ok(Expr::Cast {
+            expr: Box::new(expr),
+            ty,
+        })
+    }
+
+    /// `EXTRACT(field FROM expr)`, after its opening parenthesis. It calls
+    /// the function with the field's name as its first argument.
+    fn parse_extract(&mut self, name: String) -> Result<Expr, ParseError> {
+        let field = self.expect_ident()?;
+        self.expect(Token::From)?;
+        let expr = self.parse_expr()?;
+        self.expect(Token::RParen)?;
+        Ok(Expr::Function {
+            name,
+            args: vec![
+                Expr::Literal(Value::String(field.to_ascii_lowercase())),
+                expr,
+            ],
+            distinct: false,
+        })
+    }
+
+    fn parse_call(&mut self, name: String) -> Result<Expr, ParseError> {
+        let distinct = matches!(self.peek(), Token::Distinct);
+        if distinct {
+            self.next();
+        }
+
+        let args = match self.peek() {
+            Token::RParen => Vec::new(),
+            Token::Star if !distinct => {
+                self.next();
+                vec![Expr::Column {
+                    table: None,
+                    name: "*".into(),
+                }]
+            }
+            _ => self.parse_expr_list()?,
+        };
+        self.expect(Token::RParen)?;
+
+        Ok(Expr::Function {
+            name,
+            args,
+            distinct,
+        })
+    }
+
+    /// `CASE [operand] WHEN a THEN b ... [ELSE c] END`, after CASE.
+    fn parse_case(&mut self) -> Result<Expr, ParseError> {
+        let operand = match self.peek() {
+            Token::When => None,
+            _ => Some(Box::new(self.parse_expr()?)),
+        };
+
+        let mut branches = Vec::new();
+        while matches!(self.peek(), Token::When) {
+            self.next();
+            let when = self.parse_expr()?;
+            self.expect(Token::Then)?;
+            branches.push((when, self.parse_expr()?));
+        }
+        if branches.is_empty() {
+            return Err(self.expected("WHEN"));
+        }
+
+        let else_result = match self.peek() {
+            Token::Else => {
+                self.next();
+                Some(Box::new(self.parse_expr()?))
+            }
+            _ => None,
+        };
+        self.expect(Token::End)?;
+
+        Ok(Expr::Case {
+            operand,
+            branches,
+            else_result,
+        })
+    }
+
+    /// A parenthesized query, after its opening parenthesis.
+    fn parse_subquery(&mut self) -> Result<Query, ParseError> {
+        let select = self.parse_query()?;
+        self.expect(Token::RParen)?;
+        Ok(select)
+    }
+
+    /// `[WITH ...] body [ORDER BY ...] [LIMIT n] [OFFSET n]`
+    fn parse_query(&mut self) -> Result<Query, ParseError> {
+        let with = if matches!(self.peek(), Token::With) {
+            self.next();
+            Some(self.parse_with()?)
+        } else {
+            None
+        };
+        let body = self.parse_set_expr()?;
+        let pos = self.current_position();
+
         let order_by = if matches!(self.peek(), Token::Order) {
             self.next();
             self.expect(Token::By)?;
@@ -269,43 +462,123 @@ impl Parser {
             None
         };
 
-        Ok(SelectStmt {
-            columns,
-            from,
-            where_clause,
+        Ok(Query {
+            with,
+            body,
             order_by,
             limit,
             offset,
         })
     }
-    fn parse_order_by(&mut self) -> Result<Vec<OrderByExpr>, ParseError> {
-        let mut out = Vec::new();
 
+    /// Queries combined by UNION and EXCEPT, which bind looser than
+    /// INTERSECT and apply left to right.
+    fn parse_set_expr(&mut self) -> Result<QueryBody, ParseError> {
+        let mut left = self.parse_intersect_expr()?;
         loop {
-            let expr = self.parse_expr()?;
-            let asc = if matches!(self.peek(), Token::Desc) {
-                self.next();
-                false
-            } else if matches!(self.peek(), Token::Asc) {
+            let op = match self.peek() {
+                Token::Union => SetOperator::Union,
+                Token::Except => SetOperator::Except,
+                _ => break,
+            };
+            self.next();
+            let all = self.parse_set_quantifier();
+            let right = self.parse_intersect_expr()?;
+            left = QueryBody::SetOp {
+                op,
+                all,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_intersect_expr(&mut self) -> Result<QueryBody, ParseError> {
+        let mut left = self.parse_query_primary()?;
+        while matches!(self.peek(), Token::Intersect) {
+            self.next();
+            let all = self.parse_set_quantifier();
+            let right = self.parse_query_primary()?;
+            left = QueryBody::SetOp {
+                op: SetOperator::Intersect,
+                all,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    /// `ALL` after a set operator, or `DISTINCT`, which is the default.
+    fn parse_set_quantifier(&mut self) -> bool {
+        match self.peek() {
+            Token::All => {
                 self.next();
                 true
-            } else {
-                true
-            };
+            }
+            Token::Distinct => {
+                self.next();
+                false
+            }
+            _ => false,
+        }
+    }
 
-            out.push(OrderByExpr { expr, asc });
+    /// `SELECT ...` or `(query)`.
+    fn parse_query_primary(&mut self) -> Result<QueryBody, ParseError> {
+        if matches!(self.peek(), Token::LParen) {
+            self.next();
+            return Ok(QueryBody::Nested(Box::new(self.parse_subquery()?)));
+        }
+        self.expect(Token::Select)?;
+        Ok(QueryBody::Select(Box::new(self.parse_select()?)))
+    }
 
-            if matches!(self.peek(), Token::Comma) {
+    /// The CTEs of a WITH clause, after the WITH keyword.
+    fn parse_with(&mut self) -> Result<WithClause, ParseError> {
+        let recursive = matches!(self.peek(), Token::Recursive);
+        if recursive {
+            self.next();
+        }
+
+        let mut ctes = Vec::new();
+        loop {
+            let name = self.expect_ident()?;
+            let mut columns = Vec::new();
+            if matches!(self.peek(), Token::LParen) {
                 self.next();
-            } else {
+                columns.push(self.expect_ident()?);
+                while matches!(self.peek(), Token::Comma) {
+                    self.next();
+                    columns.push(self.expect_ident()?);
+                }
+                self.expect(Token::RParen)?;
+            }
+
+            if !self.peek().is_keyword("AS") {
+                return Err(ParseError::Expected {
+                    expected: "AS".into(),
+                    found: Some(format!("{:?}", self.peek())),
+                    position: self.current_position(),
+                });
+            }
+            self.next();
+            self.expect(Token::LParen)?;
+            let query = Box::new(self.parse_subquery()?);
+
+            ctes.push(Cte {
+                name,
+                columns,
+                query,
+            });
+            if !matches!(self.peek(), Token::Comma) {
                 break;
             }
+            self.next();
         }
 
-        Ok(out)
-    }
-    fn parse_expr(&mut self) -> Result<Expr, ParseError> {
-        self.parse_or()
+        Ok(WithClause { recursive, ctes })
     }
 
     fn parse_or(&mut self) -> Result<Expr, ParseError> {
@@ -340,6 +613,23 @@ impl Parser {
         Ok(left)
     }
 
+    /// `a || b`, which binds looser than arithmetic.
+    fn parse_concat(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_arithmetic()?;
+
+        while matches!(self.peek(), Token::Concat) {
+            self.next();
+            let right = self.parse_arithmetic()?;
+            left = Expr::Binary {
+                left: Box::new(left),
+                op: BinaryOp::Concat,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(left)
+    }
+
     fn parse_arithmetic(&mut self) -> Result<Expr, ParseError> {
         let mut left = self.parse_term()?; // Changed from parse_primary
 
@@ -362,15 +652,16 @@ impl Parser {
 
     // Add this new method:
     fn parse_term(&mut self) -> Result<Expr, ParseError> {
-        let mut left = self.parse_primary()?;
+        let mut left = self.parse_unary()?;
 
-        while matches!(self.peek(), Token::Star | Token::Slash) {
+        while matches!(self.peek(), Token::Star | Token::Slash | Token::Percent) {
             let op = match self.next() {
                 Token::Star => BinaryOp::Mul,
                 Token::Slash => BinaryOp::Div,
+                Token::Percent => BinaryOp::Mod,
                 _ => unreachable!(),
             };
-            let right = self.parse_primary()?;
+            let right = self.parse_unary()?;
             left = Expr::Binary {
                 left: Box::new(left),
                 op,
@@ -381,6 +672,34 @@ impl Parser {
         Ok(left)
     }
 
+    /// A primary expression under any number of signs.
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Token::Minus => {
+                self.next();
+                Ok(Expr::Unary {
+                    op: UnaryOp::Minus,
+                    expr: Box::new(self.parse_unary()?),
+                })
+            }
+            Token::Plus => {
+                self.next();
+                self.parse_unary()
+            }
+            _ => {
+                let mut expr = self.parse_primary()?;
+                while matches!(self.peek(), Token::DoubleColon) {
+                    self.next();
+                    expr = Expr::Cast {
+                        expr: Box::new(expr),
+                        ty: self.parse_type()?,
+                    };
+                }
+                Ok(expr)
+            }
+        }
+    }
+
     fn parse_primary(&mut self) -> Result<Expr, ParseError> {
         let pos = self.current_position();
 
@@ -391,7 +710,21 @@ impl Parser {
 
             Token::Ident(first) => {
                 let first = first.clone();
-                if matches!(self.peek(), Token::Dot) {
+                if let (Some(ty), Token::String(text)) = (literal_type(&first), self.peek()) {
+                    let text = text.clone();
+                    self.next();
+                    return typed_literal(ty, text, pos);
+                }
+                if matches!(self.peek(), Token::LParen) {
+                    self.next();
+                    if first.eq_ignore_ascii_case("CAST") {
+                        return self.parse_cast();
+                    }
+                    if first.eq_ignore_ascii_case("EXTRACT") {
+                        return self.parse_extract(first);
+                    }
+                    self.parse_call(first)
+                } else if matches!(self.peek(), Token::Dot) {
                     self.next();
                     let second = self.expect_ident()?;
                     Ok(Expr::Column {
@@ -407,14 +740,35 @@ impl Parser {
             }
 
             Token::Int(n) => Ok(Expr::Literal(Value::Int64(*n))),
+            Token::Float(n) => Ok(Expr::Literal(Value::Float64(*n))),
             Token::String(s) => Ok(Expr::Literal(Value::String(s.clone()))),
+            Token::HexString(s) => {
+                let s = s.clone();
+                match hex::decode(&s) {
+                    Some(bytes) => Ok(Expr::Literal(Value::Blob(bytes))),
+                    None => Err(ParseError::InvalidLiteral {
+                        literal: format!("x'{}'", s),
+                        position: pos,
+                    }),
+                }
+            }
 
             Token::LParen => {
+                if matches!(self.peek(), Token::Select | Token::With) {
+                    return Ok(Expr::Subquery(Box::new(self.parse_subquery()?)));
+                }
                 let e = self.parse_expr()?;
                 self.expect(Token::RParen)?;
                 Ok(e)
             }
 
+            Token::Exists => {
+                self.expect(Token::LParen)?;
+                Ok(Expr::Exists(Box::new(self.parse_subquery()?)))
+            }
+
+            Token::Case => self.parse_case(),
+
             t => Err(ParseError::UnexpectedToken {
                 token: t.clone(),
                 position: pos,
@@ -436,11 +790,68 @@ impl Parser {
         })
     }
 
+    /// BEGIN [TRANSACTION] | COMMIT [TRANSACTION] | SAVEPOINT name |
+    /// ROLLBACK [TRANSACTION] [TO [SAVEPOINT] name]
+    fn parse_transaction(&mut self) -> Result<TransactionStmt, ParseError> {
+        let pos = self.current_position();
+        let stmt = match self.next().clone() {
+            Token::Begin => TransactionStmt::Begin,
+            Token::Commit => TransactionStmt::Commit,
+            Token::Savepoint => return Ok(TransactionStmt::Savepoint(self.expect_ident()?)),
+            Token::Rollback => {
+                if matches!(self.peek(), Token::Transaction) {
+                    self.next();
+                }
+                if !matches!(self.peek(), Token::To) {
+                    return Ok(TransactionStmt::Rollback);
+                }
+                self.next();
+                if matches!(self.peek(), Token::Savepoint) {
+                    self.next();
+                }
+                return Ok(TransactionStmt::RollbackTo(self.expect_ident()?));
+            }
+            t => {
+                return Err(ParseError::UnexpectedToken {
+                    token: t,
+                    position: pos,
+                });
+            }
+        };
+
+        if matches!(self.peek(), Token::Transaction) {
+            self.next();
+        }
+        Ok(stmt)
+    }
+
     fn parse_drop_index(&mut self) -> Result<Statement, ParseError> {
         let name = self.expect_ident()?;
         Ok(Statement::DropIndex { name })
     }
 
+    /// `[ALL | DISTINCT [ON (exprs)]]` after SELECT.
+    fn parse_distinct(&mut self) -> Result<Option<Distinct>, ParseError> {
+        match self.peek() {
+            Token::All => {
+                self.next();
+                Ok(None)
+            }
+            Token::Distinct => {
+                self.next();
+                if !matches!(self.peek(), Token::On) {
+                    return Ok(Some(Distinct::Rows));
+                }
+                self.next();
+                self.expect(Token::LParen)?;
+                let exprs = self.parse_expr_list()?;
+                self.expect(Token::RParen)?;
+                Ok(Some(Distinct::On(exprs)))
+            }
+            _ => Ok(None),
+        }
+    }
+
     fn parse_select_list(&mut self) -> Result<Vec<SelectItem>, ParseError> {
         let mut cols = Vec::with_capacity(4);
 
@@ -484,26 +895,119 @@ impl Parser {
         let mut left = self.parse_table_ref()?;
 
         // 2. Parse zero or more JOIN clauses
-        loop {
-            if matches!(self.peek(), Token::Join) {
-                self.next(); // consume JOIN
+        while let Some(kind) = self.parse_join_kind()? {
+            let right = self.parse_table_ref()?;
+
+            let constraint = match kind {
+                JoinKind::Cross => JoinConstraint::None,
+                _ if matches!(self.peek(), Token::Using) => {
+                    self.next(); // consume USING
+                    self.expect(Token::LParen)?;
+                    let mut columns = vec![self.expect_ident()?];
+                    while matches!(self.peek(), Token::Comma) {
+                        self.next();
+                        columns.push(self.expect_ident()?);
+                    }
+                    self.expect(Token::RParen)?;
+                    JoinConstraint::Using(columns)
+                }
+                _ => {
+                    self.expect(Token::On)?;
+                    JoinConstraint::On(self.parse_expr()?)
+                }
+            };
 
-                let right = self.parse_table_ref()?;
+            left = FromItem::Join {
+                left: Box::new(left),
+                right: Box::new(right),
+                kind,
+                constraint,
+            };
+        }
 
-                self.expect(Token::On)?;
-                let on = self.parse_expr()?;
+        Ok(left)
+    }
 
-                left = FromItem::Join {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                    on,
+    /// Consume `[INNER] JOIN`, `{LEFT | RIGHT | FULL} [OUTER] JOIN` or
+    /// `CROSS JOIN`, if one comes next.
+    fn parse_join_kind(&mut self) -> Result<Option<JoinKind>, ParseError> {
+        let kind = match self.peek() {
+            Token::Join => {
+                self.next();
+                return Ok(Some(JoinKind::Inner));
+            }
+            Token::Inner => JoinKind::Inner,
+            Token::Cross => JoinKind::Cross,
+            Token::Left | Token::Right | Token::Full => {
+                let kind = match self.next() {
+                    Token::Left => JoinKind::Left,
+                    Token::Right => JoinKind::Right,
+                    _ => JoinKind::Full,
                 };
-            } else {
-                break;
+                if matches!(self.peek(), Token::Outer) {
+                    self.next();
+                }
+                self.expect(Token::Join)?;
+                return Ok(Some(kind));
             }
-        }
+            _ => return Ok(None),
+        };
 
-        Ok(left)
+        self.next();
+        self.expect(Token::Join)?;
+        Ok(Some(kind))
+    }
+
+    /// A column type, such as `INT` or `VARCHAR(20)`.
+    fn parse_type(&mut self) -> Result<SqlType, ParseError> {
+        let pos = self.current_position();
+        let ty = self.expect_ident()?;
+
+        let sql_ty = match ty.to_uppercase().as_str() {
+            "SMALLINT" => SqlType::SmallInt,
+            "INT" | "INTEGER" => SqlType::Int,
+            "BIGINT" => SqlType::BigInt,
+            "REAL" => SqlType::Real,
+            "FLOAT" => SqlType::Double,
+            "DOUBLE" => {
+                if self.peek().is_keyword("PRECISION") {
+                    self.next();
+                }
+                SqlType::Double
+            }
+            "BOOL" | "BOOLEAN" => SqlType::Bool,
+            "TEXT" => SqlType::Text,
+            "VARCHAR" => {
+                let mut max_len = None;
+                if matches!(self.peek(), Token::LParen) {
+                    self.next();
+                    let pos = self.current_position();
+                    match self.next() {
+                        Token::Int(n) if (1..=u32::MAX as i64).contains(n) => {
+                            max_len = Some(*n as u32)
+                        }
+                        t => {
+                            return Err(ParseError::UnexpectedToken {
+                                token: t.clone(),
+                                position: pos,
+                            });
+                        }
+                    }
+                    self.expect(Token::RParen)?;
+                }
+                SqlType::Varchar(max_len)
+            }
+            "DATE" => SqlType::Date,
+            "TIMESTAMP" => SqlType::Timestamp,
+            "BLOB" => SqlType::Blob,
+            _ => {
+                return Err(ParseError::SyntaxError {
+                    message: format!("unknown type '{}'", ty),
+                    position: pos,
+                });
+            }
+        };
+        Ok(sql_ty)
     }
 
     fn parse_create_table(&mut self) -> Result<CreateTableStmt, ParseError> {
@@ -514,20 +1018,7 @@ impl Parser {
 
         loop {
             let name = self.expect_ident()?;
-            let pos = self.current_position();
-            let ty = self.expect_ident()?; // INT, TEXT, BOOL
-
-            let sql_ty = match ty.to_uppercase().as_str() {
-                "INT" => SqlType::Int,
-                "TEXT" => SqlType::Text,
-                "BOOL" => SqlType::Bool,
-                _ => {
-                    return Err(ParseError::SyntaxError {
-                        message: format!("unknown type '{}'", ty),
-                        position: pos,
-                    });
-                }
-            };
+            let sql_ty = self.parse_type()?;
 
             let mut nullable = true;
             if matches!(self.peek(), Token::Not) {
@@ -678,7 +1169,32 @@ impl Parser {
             });
         }
 
-        let left = self.parse_arithmetic()?;
+        let left = self.parse_concat()?;
+
+        let negated = matches!(self.peek(), Token::Not);
+        if negated {
+            self.next();
+        }
+        match self.peek() {
+            Token::In => {
+                self.next();
+                return self.parse_in(left, negated);
+            }
+            Token::Between => {
+                self.next();
+                return self.parse_between(left, negated);
+            }
+            Token::Like | Token::ILike => {
+                let case_insensitive = matches!(self.next(), Token::ILike);
+                return self.parse_like(left, case_insensitive, negated);
+            }
+            _ if negated => return Err(self.expected("IN, BETWEEN, LIKE or ILIKE")),
+            Token::Is => {
+                self.next();
+                return self.parse_is(left);
+            }
+            _ => {}
+        }
 
         let op = match self.peek() {
             Token::Eq => BinaryOp::Eq,
@@ -691,7 +1207,7 @@ impl Parser {
         };
 
         self.next(); // consume operator
-        let right = self.parse_arithmetic()?;
+        let right = self.parse_concat()?;
 
         Ok(Expr::Binary {
             left: Box::new(left),
@@ -699,6 +1215,88 @@ impl Parser {
             right: Box::new(right),
         })
     }
+
+    /// `(SELECT ...)` or `(a, b, ...)`, after `expr [NOT] IN`.
+    fn parse_in(&mut self, expr: Expr, negated: bool) -> Result<Expr, ParseError> {
+        self.expect(Token::LParen)?;
+        if matches!(self.peek(), Token::Select | Token::With) {
+            return Ok(Expr::InSubquery {
+                expr: Box::new(expr),
+                subquery: Box::new(self.parse_subquery()?),
+                negated,
+            });
+        }
+        let list = self.parse_expr_list()?;
+        self.expect(Token::RParen)?;
+        Ok(Expr::InList {
+            expr: Box::new(expr),
+            list,
+            negated,
+        })
+    }
+
+    /// `low AND high`, after `expr [NOT] BETWEEN`.
+    fn parse_between(&mut self, expr: Expr, negated: bool) -> Result<Expr, ParseError> {
+        let low = self.parse_concat()?;
+        self.expect(Token::And)?;
+        let high = self.parse_concat()?;
+        Ok(Expr::Between {
+            expr: Box::new(expr),
+            low: Box::new(low),
+            high: Box::new(high),
+            negated,
+        })
+    }
+
+    /// `pattern [ESCAPE escape]`, after `expr [NOT] LIKE` or `ILIKE`.
+    fn parse_like(
+        &mut self,
+        expr: Expr,
+        case_insensitive: bool,
+        negated: bool,
+    ) -> Result<Expr, ParseError> {
+        let pattern = self.parse_concat()?;
+        let escape = if self.peek().is_keyword("ESCAPE") {
+            self.next();
+            Some(Box::new(self.parse_concat()?))
+        } else {
+            None
+        };
+        Ok(Expr::Like {
+            expr: Box::new(expr),
+            pattern: Box::new(pattern),
+            escape,
+            case_insensitive,
+            negated,
+        })
+    }
+
+    /// `[NOT] NULL` or `[NOT] DISTINCT FROM right`, after `expr IS`.
+    fn parse_is(&mut self, expr: Expr) -> Result<Expr, ParseError> {
+        let negated = matches!(self.peek(), Token::Not);
+        if negated {
+            self.next();
+        }
+        match self.peek() {
+            Token::Null => {
+                self.next();
+                Ok(Expr::IsNull {
+                    expr: Box::new(expr),
+                    negated,
+                })
+            }
+            Token::Distinct => {
+                self.next();
+                self.expect(Token::From)?;
+                Ok(Expr::IsDistinctFrom {
+                    left: Box::new(expr),
+                    right: Box::new(self.parse_concat()?),
+                    negated,
+                })
+            }
+            _ => Err(self.expected("NULL or DISTINCT FROM")),
+        }
+    }
 }
 
 impl Parser {
@@ -712,3 +1310,41 @@ impl Token {
         matches!(self, Token::Ident(s) if s.eq_ignore_ascii_case(kw))
     }
 }
+
+/// Types with literals written `TYPE 'text'`.
+#[derive(Clone, Copy)]
+enum LiteralType {
+    Date,
+    Timestamp,
+    Interval,
+}
+
+fn literal_type(name: &str) -> Option<LiteralType> {
+    match name.to_ascii_uppercase().as_str() {
+        "DATE" => Some(LiteralType::Date),
+        "TIMESTAMP" => Some(LiteralType::Timestamp),
+        "INTERVAL" => Some(LiteralType::Interval),
+        _ => None,
+    }
+}
+
+fn typed_literal(ty: LiteralType, text: String, position: Position) -> Result<Expr, ParseError> {
+    let (name, value) = match ty {
+        LiteralType::Date => ("DATE", temporal::parse_date(&text).map(Value::Date)),
+        LiteralType::Timestamp => (
+            "TIMESTAMP",
+            temporal::parse_timestamp(&text).map(Value::Timestamp),
+        ),
+        LiteralType::Interval => (
+            "INTERVAL",
+            temporal::parse_interval(&text)
+                .map(|(months, micros)| Value::Interval { months, micros }),
+        ),
+    };
+    value
+        .map(Expr::Literal)
+        .ok_or_else(|| ParseError::InvalidLiteral {
+            literal: format!("{} '{}'", name, text),
+            position,
+        })
+}
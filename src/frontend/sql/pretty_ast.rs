@@ -14,7 +14,7 @@ fn pretty_stmt(stmt: &Statement, depth: usize, out: &mut String) {
     match stmt {
         Statement::Select(s) => {
             out.push_str(&format!("{}Select\n", indent(depth)));
-            pretty_select(s, depth + 1, out);
+            pretty_query(s, depth + 1, out);
         }
 
         Statement::Explain { analyze, stmt } => {
@@ -44,7 +44,84 @@ fn pretty_stmt(stmt: &Statement, depth: usize, out: &mut String) {
     }
 }
 
+fn pretty_query(q: &Query, depth: usize, out: &mut String) {
+    if let Some(with) = &q.with {
+        let recursive = if with.recursive { " Recursive" } else { "" };
+        out.push_str(&format!("{}With{}\n", indent(depth), recursive));
+        for cte in &with.ctes {
+            let columns = if cte.columns.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", cte.columns.join(", "))
+            };
+            out.push_str(&format!(
+                "{}Cte {}{}\n",
+                indent(depth + 1),
+                cte.name,
+                columns
+            ));
+            pretty_query(&cte.query, depth + 2, out);
+        }
+    }
+
+    pretty_body(&q.body, depth, out);
+
+    if !q.order_by.is_empty() {
+        out.push_str(&format!("{}OrderBy\n", indent(depth)));
+        for o in &q.order_by {
+            out.push_str(&format!(
+                "{}- {:?} {}\n",
+                indent(depth + 1),
+                o.expr,
+                if o.asc { "ASC" } else { "DESC" }
+            ));
+        }
+    }
+
+    if let Some(l) = q.limit {
+        out.push_str(&format!("{}Limit {}\n", indent(depth), l));
+    }
+}
+
+fn pretty_body(body: &QueryBody, depth: usize, out: &mut String) {
+    match body {
+        QueryBody::Select(s) => pretty_select(s, depth, out),
+        QueryBody::SetOp {
+            op,
+            all,
+            left,
+            right,
+        } => {
+            let all = if *all { "All" } else { "" };
+            out.push_str(&format!("{}{:?}{}\n", indent(depth), op, all));
+            for side in [left, right] {
+                if matches!(**side, QueryBody::Select(_)) {
+                    out.push_str(&format!("{}Select\n", indent(depth + 1)));
+                    pretty_body(side, depth + 2, out);
+                } else {
+                    pretty_body(side, depth + 1, out);
+                }
+            }
+        }
+        QueryBody::Nested(q) => {
+            out.push_str(&format!("{}Nested\n", indent(depth)));
+            pretty_query(q, depth + 1, out);
+        }
+    }
+}
+
 fn pretty_select(s: &SelectStmt, depth: usize, out: &mut String) {
+    match &s.distinct {
+        Some(Distinct::Rows) => out.push_str(&format!("{}Distinct\n", indent(depth))),
+        Some(Distinct::On(exprs)) => {
+            out.push_str(&format!("{}DistinctOn\n", indent(depth)));
+            for e in exprs {
+                pretty_expr(e, depth + 1, out);
+            }
+        }
+        None => {}
+    }
+
     out.push_str(&format!("{}Columns\n", indent(depth)));
     for c in &s.columns {
         out.push_str(&format!("{}- {:?}\n", indent(depth + 1), c));
@@ -58,20 +135,16 @@ fn pretty_select(s: &SelectStmt, depth: usize, out: &mut String) {
         pretty_expr(w, depth + 1, out);
     }
 
-    if !s.order_by.is_empty() {
-        out.push_str(&format!("{}OrderBy\n", indent(depth)));
-        for o in &s.order_by {
-            out.push_str(&format!(
-                "{}- {:?} {}\n",
-                indent(depth + 1),
-                o.expr,
-                if o.asc { "ASC" } else { "DESC" }
-            ));
+    if !s.group_by.is_empty() {
+        out.push_str(&format!("{}GroupBy\n", indent(depth)));
+        for g in &s.group_by {
+            pretty_expr(g, depth + 1, out);
         }
     }
 
-    if let Some(l) = s.limit {
-        out.push_str(&format!("{}Limit {}\n", indent(depth), l));
+    if let Some(h) = &s.having {
+        out.push_str(&format!("{}Having\n", indent(depth)));
+        pretty_expr(h, depth + 1, out);
     }
 }
 
@@ -89,12 +162,29 @@ fn pretty_from(from: &FromItem, depth: usize, out: &mut String) {
             ));
         }
 
-        FromItem::Join { left, right, on } => {
-            out.push_str(&format!("{}Join\n", indent(depth)));
+        FromItem::Join {
+            left,
+            right,
+            kind,
+            constraint,
+        } => {
+            out.push_str(&format!("{}Join {:?}\n", indent(depth), kind));
             pretty_from(left, depth + 1, out);
             pretty_from(right, depth + 1, out);
-            out.push_str(&format!("{}On\n", indent(depth + 1)));
-            pretty_expr(on, depth + 2, out);
+            match constraint {
+                JoinConstraint::On(on) => {
+                    out.push_str(&format!("{}On\n", indent(depth + 1)));
+                    pretty_expr(on, depth + 2, out);
+                }
+                JoinConstraint::Using(columns) => {
+                    out.push_str(&format!(
+                        "{}Using {}\n",
+                        indent(depth + 1),
+                        columns.join(", ")
+                    ));
+                }
+                JoinConstraint::None => {}
+            }
         }
     }
 }
@@ -121,5 +211,124 @@ fn pretty_expr(e: &Expr, depth: usize, out: &mut String) {
             out.push_str(&format!("{}Unary {:?}\n", indent(depth), op));
             pretty_expr(expr, depth + 1, out);
         }
+        Expr::Function {
+            name,
+            args,
+            distinct,
+        } => {
+            out.push_str(&format!(
+                "{}Function {}{}\n",
+                indent(depth),
+                name,
+                if *distinct { " DISTINCT" } else { "" }
+            ));
+            for arg in args {
+                pretty_expr(arg, depth + 1, out);
+            }
+        }
+        Expr::Cast { expr, ty } => {
+            out.push_str(&format!("{}Cast {:?}\n", indent(depth), ty));
+            pretty_expr(expr, depth + 1, out);
+        }
+        Expr::Subquery(select) => {
+            out.push_str(&format!("{}Subquery\n", indent(depth)));
+            pretty_query(select, depth + 1, out);
+        }
+        Expr::Exists(select) => {
+            out.push_str(&format!("{}Exists\n", indent(depth)));
+            pretty_query(select, depth + 1, out);
+        }
+        Expr::InSubquery {
+            expr,
+            subquery,
+            negated,
+        } => {
+            out.push_str(&format!("{}{}In\n", indent(depth), not(*negated)));
+            pretty_expr(expr, depth + 1, out);
+            pretty_query(subquery, depth + 1, out);
+        }
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => {
+            out.push_str(&format!("{}{}InList\n", indent(depth), not(*negated)));
+            pretty_expr(expr, depth + 1, out);
+            for item in list {
+                pretty_expr(item, depth + 1, out);
+            }
+        }
+        Expr::Between {
+            expr,
+            low,
+            high,
+            negated,
+        } => {
+            out.push_str(&format!("{}{}Between\n", indent(depth), not(*negated)));
+            pretty_expr(expr, depth + 1, out);
+            pretty_expr(low, depth + 1, out);
+            pretty_expr(high, depth + 1, out);
+        }
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            case_insensitive,
+            negated,
+        } => {
+            out.push_str(&format!(
+                "{}{}{}\n",
+                indent(depth),
+                not(*negated),
+                if *case_insensitive { "ILike" } else { "Like" }
+            ));
+            pretty_expr(expr, depth + 1, out);
+            pretty_expr(pattern, depth + 1, out);
+            if let Some(escape) = escape {
+                out.push_str(&format!("{}Escape\n", indent(depth + 1)));
+                pretty_expr(escape, depth + 2, out);
+            }
+        }
+        Expr::IsNull { expr, negated } => {
+            out.push_str(&format!("{}Is{}Null\n", indent(depth), not(*negated)));
+            pretty_expr(expr, depth + 1, out);
+        }
+        Expr::IsDistinctFrom {
+            left,
+            right,
+            negated,
+        } => {
+            out.push_str(&format!(
+                "{}Is{}DistinctFrom\n",
+                indent(depth),
+                not(*negated)
+            ));
+            pretty_expr(left, depth + 1, out);
+            pretty_expr(right, depth + 1, out);
+        }
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            out.push_str(&format!("{}Case\n", indent(depth)));
+            if let Some(operand) = operand {
+                pretty_expr(operand, depth + 1, out);
+            }
+            for (when, then) in branches {
+                out.push_str(&format!("{}When\n", indent(depth + 1)));
+                pretty_expr(when, depth + 2, out);
+                out.push_str(&format!("{}Then\n", indent(depth + 1)));
+                pretty_expr(then, depth + 2, out);
+            }
+            if let Some(else_result) = else_result {
+                out.push_str(&format!("{}Else\n", indent(depth + 1)));
+                pretty_expr(else_result, depth + 2, out);
+            }
+        }
     }
 }
+
+fn not(negated: bool) -> &'static str {
+    if negated { "Not" } else { "" }
+}
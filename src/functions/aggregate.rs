@@ -0,0 +1,115 @@
+//! Aggregate functions defined outside the engine, which fold the values of
+//! their argument over a group of rows into one value.
+
+use std::any::Any;
+use std::fmt;
+
+use crate::functions::errors::FunctionError;
+use crate::functions::scalar::{FunctionResult, Signature};
+use crate::types::value::Value;
+
+/// Running state of an aggregate over one group, of the type its `init`
+/// returns.
+pub type AggregateState = Box<dyn Any + Send>;
+
+type InitFn = dyn Fn() -> AggregateState + Send + Sync;
+type UpdateFn = dyn Fn(&mut AggregateState, &Value) -> Result<(), FunctionError> + Send + Sync;
+type MergeFn =
+    dyn Fn(&mut AggregateState, AggregateState) -> Result<(), FunctionError> + Send + Sync;
+type FinalizeFn = dyn Fn(AggregateState) -> FunctionResult + Send + Sync;
+
+/// An aggregate of one argument. As with the built-in aggregates, NULL
+/// values of the argument are skipped, and with DISTINCT each value is fed
+/// to it once.
+pub struct AggregateFunction {
+    name: String,
+    /// Takes a single argument; the result type is that of the aggregate.
+    signature: Signature,
+    init: Box<InitFn>,
+    update: Box<UpdateFn>,
+    merge: Box<MergeFn>,
+    finalize: Box<FinalizeFn>,
+}
+
+impl AggregateFunction {
+    /// An aggregate called `name` whose state over a group starts as `init`
+    /// returns it, takes in each value with `update`, and is turned into
+    /// the result by `finalize`. `merge` adds the state of another part of
+    /// the same group.
+    pub fn new<S: Send + 'static>(
+        name: &str,
+        signature: Signature,
+        init: impl Fn() -> S + Send + Sync + 'static,
+        update: impl Fn(&mut S, &Value) -> Result<(), FunctionError> + Send + Sync + 'static,
+        merge: impl Fn(&mut S, S) -> Result<(), FunctionError> + Send + Sync + 'static,
+        finalize: impl Fn(S) -> FunctionResult + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_ascii_uppercase(),
+            signature,
+            init: Box::new(move || Box::new(init())),
+            update: Box::new(move |state, value| update(state_of(state), value)),
+            merge: Box::new(move |state, other| merge(state_of(state), *owned(other))),
+            finalize: Box::new(move |state| finalize(*owned(state))),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// State over a group without any values yet.
+    pub fn init(&self) -> AggregateState {
+        (self.init)()
+    }
+
+    pub fn update(&self, state: &mut AggregateState, value: &Value) -> Result<(), FunctionError> {
+        (self.update)(state, value)
+    }
+
+    pub fn merge(
+        &self,
+        state: &mut AggregateState,
+        other: AggregateState,
+    ) -> Result<(), FunctionError> {
+        (self.merge)(state, other)
+    }
+
+    pub fn finalize(&self, state: AggregateState) -> FunctionResult {
+        (self.finalize)(state)
+    }
+}
+
+/// States are only ever made by the `init` of the aggregate they are
+/// passed back to.
+fn state_of<S: 'static>(state: &mut AggregateState) -> &mut S {
+    state
+        .downcast_mut()
+        .expect("aggregate state made by another aggregate")
+}
+
+fn owned<S: 'static>(state: AggregateState) -> Box<S> {
+    state
+        .downcast()
+        .expect("aggregate state made by another aggregate")
+}
+
+impl fmt::Debug for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AggregateFunction")
+            .field("name", &self.name)
+            .field("signature", &self.signature)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Like scalar functions, aggregates are equal only to themselves.
+impl PartialEq for AggregateFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
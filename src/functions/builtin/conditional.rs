@@ -0,0 +1,41 @@
+//! Functions that pick among their arguments, which are converted to one
+//! type. They are called on NULL arguments, which they are about.
+
+use crate::functions::registry::FunctionRegistry;
+use crate::functions::scalar::{ArgType, ReturnType, ScalarFunction, Signature};
+use crate::types::value::Value;
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    let pair = || Signature::new(vec![ArgType::Any, ArgType::Any], ReturnType::Common);
+
+    // The first argument that is not NULL.
+    registry.add_scalar(
+        ScalarFunction::new(
+            "COALESCE",
+            Signature::new(vec![ArgType::Any], ReturnType::Common).variadic(ArgType::Any),
+            |args| Ok(first_not_null(args)),
+        )
+        .called_on_null(),
+    );
+    registry.add_scalar(
+        ScalarFunction::new("IFNULL", pair(), |args| Ok(first_not_null(args))).called_on_null(),
+    );
+
+    // NULL if both are equal, otherwise the first.
+    registry.add_scalar(
+        ScalarFunction::new("NULLIF", pair(), |args| {
+            Ok(match (&args[0], &args[1]) {
+                (a, b) if !b.is_null() && a == b => Value::Null,
+                (a, _) => a.clone(),
+            })
+        })
+        .called_on_null(),
+    );
+}
+
+fn first_not_null(args: &[Value]) -> Value {
+    args.iter()
+        .find(|v| !v.is_null())
+        .cloned()
+        .unwrap_or(Value::Null)
+}
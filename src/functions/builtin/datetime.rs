@@ -0,0 +1,178 @@
+//! Date and time functions. Dates are passed as timestamps at their
+//! midnight, and units and fields are named by strings such as `'month'`.
+
+use crate::functions::builtin::{TEXT, add, int, text, timestamp};
+use crate::functions::errors::FunctionError;
+use crate::functions::registry::FunctionRegistry;
+use crate::functions::scalar::{ScalarFunction, Signature, Volatility};
+use crate::types::datatype::DataType;
+use crate::types::value::Value;
+use crate::util::temporal::{MICROS_PER_DAY, add_months, civil_from_days, days_from_civil};
+
+use DataType::{Float64, Int64, Timestamp};
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    // The time the statement started, so every row of it sees one time.
+    let now = ScalarFunction::with_context("NOW", Signature::exact(vec![], Timestamp), |ctx, _| {
+        Ok(Value::Timestamp(ctx.statement_time))
+    });
+    registry.add_scalar(now.with_volatility(Volatility::Volatile));
+    add(
+        registry,
+        &["EXTRACT", "DATE_PART"],
+        &[TEXT, Timestamp],
+        Float64,
+        |args| extract(text(&args[0])?, timestamp(&args[1])?).map(Value::Float64),
+    );
+    add(
+        registry,
+        &["DATE_TRUNC"],
+        &[TEXT, Timestamp],
+        Timestamp,
+        |args| {
+            let unit = Unit::named(text(&args[0])?)?;
+            Ok(Value::Timestamp(unit.truncate(timestamp(&args[1])?)))
+        },
+    );
+    add(
+        registry,
+        &["DATE_ADD"],
+        &[TEXT, Int64, Timestamp],
+        Timestamp,
+        |args| {
+            let unit = Unit::named(text(&args[0])?)?;
+            unit.add(timestamp(&args[2])?, int(&args[1])?)
+                .map(Value::Timestamp)
+                .ok_or_else(|| FunctionError::new("timestamp out of range"))
+        },
+    );
+    add(
+        registry,
+        &["DATE_DIFF"],
+        &[TEXT, Timestamp, Timestamp],
+        Int64,
+        |args| {
+            let unit = Unit::named(text(&args[0])?)?;
+            Ok(Value::Int64(
+                unit.between(timestamp(&args[1])?, timestamp(&args[2])?),
+            ))
+        },
+    );
+}
+
+/// Parts of a timestamp.
+struct Civil {
+    days: i64,
+    year: i64,
+    month: u32,
+    day: u32,
+    /// Microseconds into the day.
+    time: i64,
+}
+
+impl Civil {
+    fn of(ts: i64) -> Self {
+        let days = ts.div_euclid(MICROS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        Civil {
+            days,
+            year,
+            month,
+            day,
+            time: ts.rem_euclid(MICROS_PER_DAY),
+        }
+    }
+}
+
+/// Field `field` of `ts`, as `EXTRACT(field FROM ts)`.
+fn extract(field: &str, ts: i64) -> Result<f64, FunctionError> {
+    let c = Civil::of(ts);
+    let seconds = c.time / MICROS_PER_SECOND;
+    Ok(match field.to_ascii_lowercase().as_str() {
+        "year" => c.year as f64,
+        "quarter" => ((c.month - 1) / 3 + 1) as f64,
+        "month" => c.month as f64,
+        "day" => c.day as f64,
+        // Sunday is 0; 1970-01-01 was a Thursday.
+        "dow" => (c.days + 4).rem_euclid(7) as f64,
+        "doy" => (c.days - days_from_civil(c.year, 1, 1) + 1) as f64,
+        "hour" => (seconds / 3600) as f64,
+        "minute" => (seconds / 60 % 60) as f64,
+        "second" => (c.time % (60 * MICROS_PER_SECOND)) as f64 / MICROS_PER_SECOND as f64,
+        "epoch" => ts as f64 / MICROS_PER_SECOND as f64,
+        _ => return Err(unknown("field", field)),
+    })
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Year,
+    Quarter,
+    Month,
+    Week,
+    /// A fixed number of microseconds.
+    Micros(i64),
+}
+
+impl Unit {
+    fn named(name: &str) -> Result<Self, FunctionError> {
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "year" => Unit::Year,
+            "quarter" => Unit::Quarter,
+            "month" => Unit::Month,
+            "week" => Unit::Week,
+            "day" => Unit::Micros(MICROS_PER_DAY),
+            "hour" => Unit::Micros(3600 * MICROS_PER_SECOND),
+            "minute" => Unit::Micros(60 * MICROS_PER_SECOND),
+            "second" => Unit::Micros(MICROS_PER_SECOND),
+            _ => return Err(unknown("unit", name)),
+        })
+    }
+
+    /// Start of the unit `ts` is in. Weeks start on Monday.
+    fn truncate(self, ts: i64) -> i64 {
+        let c = Civil::of(ts);
+        let days = match self {
+            Unit::Year => days_from_civil(c.year, 1, 1),
+            Unit::Quarter => days_from_civil(c.year, (c.month - 1) / 3 * 3 + 1, 1),
+            Unit::Month => days_from_civil(c.year, c.month, 1),
+            Unit::Week => c.days - (c.days + 3).rem_euclid(7),
+            Unit::Micros(micros) => return ts - ts.rem_euclid(micros),
+        };
+        days * MICROS_PER_DAY
+    }
+
+    /// `ts` moved by `n` units, as by [`add_months`] for units of months.
+    fn add(self, ts: i64, n: i64) -> Option<i64> {
+        let months = match self {
+            Unit::Year => n.checked_mul(12)?,
+            Unit::Quarter => n.checked_mul(3)?,
+            Unit::Month => n,
+            Unit::Week => return ts.checked_add(n.checked_mul(7 * MICROS_PER_DAY)?),
+            Unit::Micros(micros) => return ts.checked_add(n.checked_mul(micros)?),
+        };
+        add_months(ts, months)
+    }
+
+    /// Number of unit boundaries between `from` and `to`, negative if `to`
+    /// is earlier.
+    fn between(self, from: i64, to: i64) -> i64 {
+        let months = |ts: i64| {
+            let c = Civil::of(ts);
+            c.year * 12 + c.month as i64 - 1
+        };
+        match self {
+            Unit::Year => Civil::of(to).year - Civil::of(from).year,
+            Unit::Quarter => months(to).div_euclid(3) - months(from).div_euclid(3),
+            Unit::Month => months(to) - months(from),
+            Unit::Week => (self.truncate(to) - self.truncate(from)) / (7 * MICROS_PER_DAY),
+            Unit::Micros(micros) => to.div_euclid(micros) - from.div_euclid(micros),
+        }
+    }
+}
+
+fn unknown(what: &str, name: &str) -> FunctionError {
+    FunctionError::new(format!("{} '{}' not recognized", what, name))
+}
@@ -0,0 +1,105 @@
+//! Math functions. Those other than ABS and MOD compute in `DOUBLE`.
+
+use crate::functions::builtin::{add, float, int, unexpected};
+use crate::functions::errors::FunctionError;
+use crate::functions::registry::FunctionRegistry;
+use crate::functions::scalar::{ArgType, ReturnType, ScalarFunction, Signature};
+use crate::types::datatype::DataType;
+use crate::types::value::Value;
+
+use DataType::{Float64, Int64};
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.add_scalar(ScalarFunction::new(
+        "ABS",
+        Signature::new(vec![ArgType::Numeric], ReturnType::Common),
+        |args| match &args[0] {
+            Value::Int16(i) => i.checked_abs().map(Value::Int16).ok_or_else(out_of_range),
+            Value::Int32(i) => i.checked_abs().map(Value::Int32).ok_or_else(out_of_range),
+            Value::Int64(i) => i.checked_abs().map(Value::Int64).ok_or_else(out_of_range),
+            Value::Float32(f) => Ok(Value::Float32(f.abs())),
+            Value::Float64(f) => Ok(Value::Float64(f.abs())),
+            v => Err(unexpected(v)),
+        },
+    ));
+
+    add(registry, &["ROUND"], &[Float64], Float64, |args| {
+        Ok(Value::Float64(float(&args[0])?.round()))
+    });
+    // Rounded to `digits` places after the point, or before it if negative.
+    add(registry, &["ROUND"], &[Float64, Int64], Float64, |args| {
+        let (f, digits) = (float(&args[0])?, int(&args[1])?);
+        let scale = 10f64.powi(digits.clamp(-400, 400) as i32);
+        let rounded = (f * scale).round() / scale;
+        Ok(Value::Float64(if rounded.is_finite() {
+            rounded
+        } else {
+            f
+        }))
+    });
+    add(registry, &["FLOOR"], &[Float64], Float64, |args| {
+        Ok(Value::Float64(float(&args[0])?.floor()))
+    });
+    add(
+        registry,
+        &["CEIL", "CEILING"],
+        &[Float64],
+        Float64,
+        |args| Ok(Value::Float64(float(&args[0])?.ceil())),
+    );
+
+    // The remainder has the sign of the dividend.
+    add(registry, &["MOD"], &[Int64, Int64], Int64, |args| {
+        match (int(&args[0])?, int(&args[1])?) {
+            (_, 0) => Err(FunctionError::new("division by zero")),
+            (a, b) => Ok(Value::Int64(a.wrapping_rem(b))),
+        }
+    });
+    add(
+        registry,
+        &["MOD"],
+        &[Float64, Float64],
+        Float64,
+        |args| match (float(&args[0])?, float(&args[1])?) {
+            (_, 0.0) => Err(FunctionError::new("division by zero")),
+            (a, b) => Ok(Value::Float64(a % b)),
+        },
+    );
+
+    add(
+        registry,
+        &["POWER", "POW"],
+        &[Float64, Float64],
+        Float64,
+        |args| {
+            let (base, exp) = (float(&args[0])?, float(&args[1])?);
+            if base == 0.0 && exp < 0.0 {
+                return Err(FunctionError::new(
+                    "zero raised to a negative power is undefined",
+                ));
+            }
+            if base < 0.0 && exp.fract() != 0.0 {
+                return Err(FunctionError::new(
+                    "a negative number raised to a non-integer power yields a complex result",
+                ));
+            }
+            Ok(Value::Float64(base.powf(exp)))
+        },
+    );
+    add(
+        registry,
+        &["SQRT"],
+        &[Float64],
+        Float64,
+        |args| match float(&args[0])? {
+            f if f < 0.0 => Err(FunctionError::new(
+                "cannot take square root of a negative number",
+            )),
+            f => Ok(Value::Float64(f.sqrt())),
+        },
+    );
+}
+
+fn out_of_range() -> FunctionError {
+    FunctionError::new("integer out of range")
+}
@@ -0,0 +1,70 @@
+//! Functions every database has.
+
+mod conditional;
+mod datetime;
+mod math;
+mod string;
+
+use crate::functions::errors::FunctionError;
+use crate::functions::registry::FunctionRegistry;
+use crate::functions::scalar::{FunctionResult, ScalarFunction, Signature};
+use crate::types::datatype::DataType;
+use crate::types::value::Value;
+
+pub(crate) fn register(registry: &mut FunctionRegistry) {
+    string::register(registry);
+    math::register(registry);
+    conditional::register(registry);
+    datetime::register(registry);
+}
+
+/// Add a function taking arguments of exactly the types `args`, under each
+/// of `names`.
+fn add(
+    registry: &mut FunctionRegistry,
+    names: &[&str],
+    args: &[DataType],
+    returns: DataType,
+    body: fn(&[Value]) -> FunctionResult,
+) {
+    for name in names {
+        let signature = Signature::exact(args.to_vec(), returns.clone());
+        registry.add_scalar(ScalarFunction::new(name, signature, body));
+    }
+}
+
+const TEXT: DataType = DataType::Varchar { max_len: None };
+
+fn text(value: &Value) -> Result<&str, FunctionError> {
+    match value {
+        Value::String(s) => Ok(s),
+        v => Err(unexpected(v)),
+    }
+}
+
+fn int(value: &Value) -> Result<i64, FunctionError> {
+    match value {
+        Value::Int64(i) => Ok(*i),
+        v => Err(unexpected(v)),
+    }
+}
+
+fn float(value: &Value) -> Result<f64, FunctionError> {
+    match value {
+        Value::Float64(f) => Ok(*f),
+        v => Err(unexpected(v)),
+    }
+}
+
+fn timestamp(value: &Value) -> Result<i64, FunctionError> {
+    match value {
+        Value::Timestamp(ts) => Ok(*ts),
+        v => Err(unexpected(v)),
+    }
+}
+
+/// An argument of a type the signature does not allow, which the binder
+/// should have converted.
+fn unexpected(value: &Value) -> FunctionError {
+    FunctionError::new(format!("unexpected argument {}", value))
+}
@@ -0,0 +1,81 @@
+//! String functions. Positions and lengths count characters, not bytes.
+
+use crate::functions::builtin::{TEXT, add, int, text};
+use crate::functions::errors::FunctionError;
+use crate::functions::registry::FunctionRegistry;
+use crate::functions::scalar::{ArgType, ReturnType, ScalarFunction, Signature};
+use crate::types::datatype::DataType;
+use crate::types::value::Value;
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    add(registry, &["UPPER"], &[TEXT], TEXT, |args| {
+        Ok(Value::String(text(&args[0])?.to_uppercase()))
+    });
+    add(registry, &["LOWER"], &[TEXT], TEXT, |args| {
+        Ok(Value::String(text(&args[0])?.to_lowercase()))
+    });
+    add(
+        registry,
+        &["LENGTH", "CHAR_LENGTH"],
+        &[TEXT],
+        DataType::Int64,
+        |args| Ok(Value::Int64(text(&args[0])?.chars().count() as i64)),
+    );
+    add(registry, &["TRIM"], &[TEXT], TEXT, |args| {
+        Ok(Value::String(text(&args[0])?.trim().to_string()))
+    });
+    add(registry, &["REPLACE"], &[TEXT, TEXT, TEXT], TEXT, |args| {
+        let (s, from, to) = (text(&args[0])?, text(&args[1])?, text(&args[2])?);
+        Ok(Value::String(match from {
+            "" => s.to_string(),
+            from => s.replace(from, to),
+        }))
+    });
+    for args in [
+        &[TEXT, DataType::Int64][..],
+        &[TEXT, DataType::Int64, DataType::Int64],
+    ] {
+        add(registry, &["SUBSTR", "SUBSTRING"], args, TEXT, |args| {
+            let len = args.get(2).map(int).transpose()?;
+            substr(text(&args[0])?, int(&args[1])?, len).map(Value::String)
+        });
+    }
+
+    // NULL arguments are left out.
+    registry.add_scalar(
+        ScalarFunction::new(
+            "CONCAT",
+            Signature::new(vec![ArgType::Any], ReturnType::Fixed(TEXT)).variadic(ArgType::Any),
+            |args| {
+                let parts = args.iter().filter(|v| !v.is_null());
+                Ok(Value::String(parts.map(Value::to_string).collect()))
+            },
+        )
+        .called_on_null(),
+    );
+    // `a || b`, which is NULL if either is.
+    registry.add_scalar(ScalarFunction::new(
+        "||",
+        Signature::new(vec![ArgType::Any, ArgType::Any], ReturnType::Fixed(TEXT)),
+        |args| Ok(Value::String(format!("{}{}", args[0], args[1]))),
+    ));
+}
+
+/// The `len` characters of `s` from the 1-based position `start`, or the
+/// rest of it without a `len`. Positions before the first character count
+/// towards `len` but select nothing.
+fn substr(s: &str, start: i64, len: Option<i64>) -> Result<String, FunctionError> {
+    let end = match len {
+        Some(len) if len < 0 => {
+            return Err(FunctionError::new("negative substring length not allowed"));
+        }
+        Some(len) => start.saturating_add(len),
+        None => i64::MAX,
+    };
+    let skip = start.max(1) - 1;
+    let take = end.saturating_sub(start.max(1)).max(0);
+    Ok(s.chars()
+        .skip(usize::try_from(skip).unwrap_or(usize::MAX))
+        .take(usize::try_from(take).unwrap_or(usize::MAX))
+        .collect())
+}
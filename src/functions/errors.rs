@@ -0,0 +1,24 @@
+use core::fmt;
+
+/// Failure of a function called on arguments it does not accept, such as
+/// the square root of a negative number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionError {
+    pub message: String,
+}
+
+impl FunctionError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for FunctionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for FunctionError {}
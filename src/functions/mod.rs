@@ -0,0 +1,17 @@
+//! Functions callable from SQL.
+//!
+//! A function is looked up by name in a `FunctionRegistry`, which may hold
+//! several versions of it taking different arguments. The binder picks
+//! the first version whose signature takes the arguments of a call and
+//! converts them to the types it declares, so a function body only sees
+//! values of those types.
+//!
+//! Applications add their own scalar and aggregate functions to the
+//! registry of a database, which are then called just like the built-in
+//! ones.
+
+pub mod aggregate;
+mod builtin;
+pub mod errors;
+pub mod registry;
+pub mod scalar;
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use crate::functions::aggregate::AggregateFunction;
+use crate::functions::builtin;
+use crate::functions::scalar::ScalarFunction;
+
+/// Functions by name. A name may have several versions, which are tried in
+/// the order they were added.
+#[derive(Debug, Clone, Default)]
+pub struct FunctionRegistry {
+    scalars: HashMap<String, Vec<Arc<ScalarFunction>>>,
+    aggregates: HashMap<String, Vec<Arc<AggregateFunction>>>,
+}
+
+impl FunctionRegistry {
+    /// A registry without any functions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in functions.
+    pub fn builtins() -> &'static FunctionRegistry {
+        static BUILTINS: OnceLock<FunctionRegistry> = OnceLock::new();
+        BUILTINS.get_or_init(|| {
+            let mut registry = FunctionRegistry::new();
+            builtin::register(&mut registry);
+            registry
+        })
+    }
+
+    /// Add `function` as another version of those of its name.
+    pub fn add_scalar(&mut self, function: ScalarFunction) {
+        self.scalars
+            .entry(function.name().to_string())
+            .or_default()
+            .push(Arc::new(function));
+    }
+
+    /// Versions of the scalar function called `name`, in any case.
+    pub fn scalar(&self, name: &str) -> &[Arc<ScalarFunction>] {
+        self.scalars
+            .get(&name.to_ascii_uppercase())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Add `function` as another version of the aggregates of its name.
+    /// Built-in aggregates, such as `SUM`, keep their names.
+    pub fn add_aggregate(&mut self, function: AggregateFunction) {
+        self.aggregates
+            .entry(function.name().to_string())
+            .or_default()
+            .push(Arc::new(function));
+    }
+
+    /// Versions of the aggregate function called `name`, in any case.
+    pub fn aggregate(&self, name: &str) -> &[Arc<AggregateFunction>] {
+        self.aggregates
+            .get(&name.to_ascii_uppercase())
+            .map_or(&[], Vec::as_slice)
+    }
+}
@@ -0,0 +1,185 @@
+//! Scalar functions, which compute one value from the values of their
+//! arguments.
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::functions::errors::FunctionError;
+use crate::types::datatype::DataType;
+use crate::types::value::Value;
+
+pub type FunctionResult = Result<Value, FunctionError>;
+
+/// What a function computes, given the context of the call and its
+/// arguments converted to the types its signature declares.
+pub type ScalarBody = dyn Fn(&CallContext, &[Value]) -> FunctionResult + Send + Sync;
+
+/// What a call may read besides its arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallContext {
+    /// When the calling statement started, in microseconds since the Unix
+    /// epoch. Every call a statement makes sees the same time.
+    pub statement_time: i64,
+}
+
+impl CallContext {
+    /// Context of a statement starting now.
+    pub fn now() -> Self {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Self {
+            statement_time: since_epoch.as_micros() as i64,
+        }
+    }
+}
+
+/// Type an argument of a function must have.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgType {
+    /// A value of this type, or of one that widens to it.
+    Exact(DataType),
+    /// Any number.
+    Numeric,
+    /// A value of any type.
+    Any,
+}
+
+/// Type of the value a function returns.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReturnType {
+    Fixed(DataType),
+    /// The type the arguments not of an `Exact` type all widen to, which
+    /// they are converted to before the call.
+    Common,
+}
+
+/// The arguments a function takes and what it returns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub args: Vec<ArgType>,
+    /// Type of any further arguments, for a function that takes any number
+    /// of them.
+    pub variadic: Option<ArgType>,
+    pub returns: ReturnType,
+}
+
+impl Signature {
+    pub fn new(args: Vec<ArgType>, returns: ReturnType) -> Self {
+        Self {
+            args,
+            variadic: None,
+            returns,
+        }
+    }
+
+    /// Arguments of exactly these types, and a result of type `returns`.
+    pub fn exact(args: Vec<DataType>, returns: DataType) -> Self {
+        Self::new(
+            args.into_iter().map(ArgType::Exact).collect(),
+            ReturnType::Fixed(returns),
+        )
+    }
+
+    /// Also take any number of further arguments of type `arg`.
+    pub fn variadic(mut self, arg: ArgType) -> Self {
+        self.variadic = Some(arg);
+        self
+    }
+}
+
+/// Whether a function always returns the same result for the same
+/// arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Volatility {
+    /// The result depends only on the arguments, so a call on constants may
+    /// be made once, while the query is planned.
+    Deterministic,
+    /// The result may differ from call to call, as the time does.
+    Volatile,
+}
+
+pub struct ScalarFunction {
+    name: String,
+    signature: Signature,
+    /// Whether a NULL argument makes the result NULL without calling the
+    /// body.
+    strict: bool,
+    volatility: Volatility,
+    body: Box<ScalarBody>,
+}
+
+impl ScalarFunction {
+    /// A deterministic function called `name` that returns NULL when any
+    /// argument is NULL and otherwise calls `body`.
+    pub fn new(
+        name: &str,
+        signature: Signature,
+        body: impl Fn(&[Value]) -> FunctionResult + Send + Sync + 'static,
+    ) -> Self {
+        Self::with_context(name, signature, move |_, args| body(args))
+    }
+
+    /// Like `new`, for a body that also reads the context of the call.
+    pub fn with_context(
+        name: &str,
+        signature: Signature,
+        body: impl Fn(&CallContext, &[Value]) -> FunctionResult + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_ascii_uppercase(),
+            signature,
+            strict: true,
+            volatility: Volatility::Deterministic,
+            body: Box::new(body),
+        }
+    }
+
+    /// Call the body on NULL arguments too.
+    pub fn called_on_null(mut self) -> Self {
+        self.strict = false;
+        self
+    }
+
+    pub fn with_volatility(mut self, volatility: Volatility) -> Self {
+        self.volatility = volatility;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    pub fn volatility(&self) -> Volatility {
+        self.volatility
+    }
+
+    pub fn call(&self, ctx: &CallContext, args: &[Value]) -> FunctionResult {
+        if self.strict && args.iter().any(Value::is_null) {
+            return Ok(Value::Null);
+        }
+        (self.body)(ctx, args)
+    }
+}
+
+impl fmt::Debug for ScalarFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScalarFunction")
+            .field("name", &self.name)
+            .field("signature", &self.signature)
+            .field("volatility", &self.volatility)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Functions are equal only to themselves: two registered under one name
+/// with the same signature may still compute different things.
+impl PartialEq for ScalarFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
@@ -3,12 +3,22 @@
 //! This module is FROZEN.
 //! Expressions here are fully bound and resolved.
 
-use crate::{catalog::ids::ColumnId, types::value::Value};
+use std::sync::Arc;
+
+use crate::{
+    catalog::ids::ColumnId,
+    functions::scalar::ScalarFunction,
+    ir::plan::{FromId, LogicalPlan},
+    types::{datatype::DataType, value::Value},
+};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum UnaryOp {
     Not,
     Neg,
+    /// `IS NULL`, which is never NULL itself.
+    IsNull,
+    IsNotNull,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -17,6 +27,8 @@ pub enum BinaryOp {
     Sub,
     Mul,
     Div,
+    /// Remainder of the division, with the sign of the dividend.
+    Mod,
 
     Eq,
     Neq,
@@ -27,16 +39,28 @@ pub enum BinaryOp {
 
     And,
     Or,
+
+    /// Inequality that treats NULLs as equal to each other and unequal to
+    /// anything else, so it is never NULL.
+    IsDistinctFrom,
+    IsNotDistinctFrom,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 #[non_exhaustive]
 pub enum Expr {
-    /// Reference to a resolved column
+    /// Reference to a resolved column, as read through `from`
     BoundColumn {
+        from: FromId,
         column_id: ColumnId,
     },
 
+    /// Column at a position in the operator's input row. Column references
+    /// are rewritten into slots once the plan is optimized.
+    ColumnSlot {
+        index: usize,
+    },
+
     /// Literal runtime value
     Literal(Value),
 
@@ -51,6 +75,80 @@ pub enum Expr {
         right: Box<Expr>,
     },
 
+    /// Value of `expr` converted to type `to`, written `CAST(expr AS to)`
+    /// or inserted by the binder where operands of different types meet.
+    Cast {
+        expr: Box<Expr>,
+        to: DataType,
+    },
+
+    /// Call of a scalar function, with its arguments already of the types
+    /// its signature declares.
+    Function {
+        func: Arc<ScalarFunction>,
+        args: Vec<Expr>,
+    },
+
+    /// `CASE [operand] WHEN .. THEN .. ELSE .. END`: the result of the first
+    /// branch whose condition is true, or that equals `operand` when there
+    /// is one, else that of `else_result`, or NULL without one.
+    Case {
+        operand: Option<Box<Expr>>,
+        branches: Vec<(Expr, Expr)>,
+        else_result: Option<Box<Expr>>,
+    },
+
+    /// `expr IN (list)`, with the values of `list` of the type of `expr`.
+    InList {
+        expr: Box<Expr>,
+        list: Vec<Expr>,
+    },
+
+    /// `expr LIKE pattern`, where `%` in the pattern matches any text and
+    /// `_` any one character, unless preceded by `escape`.
+    Like {
+        expr: Box<Expr>,
+        pattern: Box<Expr>,
+        escape: Option<char>,
+        case_insensitive: bool,
+    },
+
     /// Explicit NULL literal
     Null,
+
+    /// Result of a nested query. `params` are evaluated on the row at hand
+    /// and passed into the query, which reads them as `Parameter`s; that is
+    /// how a correlated subquery sees the columns of its enclosing query.
+    /// `id` tells subqueries of one statement apart.
+    Subquery {
+        id: usize,
+        kind: SubqueryKind,
+        plan: Box<LogicalPlan>,
+        params: Vec<Expr>,
+    },
+
+    /// Parameter passed into the subquery this expression belongs to.
+    Parameter {
+        index: usize,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubqueryKind {
+    /// The only value of a one-column query, or NULL if it returns no rows.
+    Scalar,
+    /// Whether the query returns any rows.
+    Exists,
+    /// Whether the value of the expression is among the query's values.
+    In(Box<Expr>),
+}
+
+impl SubqueryKind {
+    /// Left side of IN, which is evaluated in the enclosing query.
+    pub fn operand(&self) -> Option<&Expr> {
+        match self {
+            SubqueryKind::In(expr) => Some(expr),
+            SubqueryKind::Scalar | SubqueryKind::Exists => None,
+        }
+    }
 }
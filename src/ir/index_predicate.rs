@@ -3,5 +3,10 @@ use crate::types::value::Value;
 #[derive(Clone, Debug, PartialEq)]
 pub enum IndexPredicate {
     Eq(Value),
-    Range { low: Value, high: Value },
+    /// Any of several keys.
+    In(Vec<Value>),
+    Range {
+        low: Value,
+        high: Value,
+    },
 }
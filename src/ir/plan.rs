@@ -2,9 +2,13 @@
 //!
 //! This module is FROZEN.
 
+use std::sync::Arc;
+
 use crate::{
     catalog::ids::{ColumnId, IndexId, TableId},
+    functions::aggregate::AggregateFunction,
     ir::{expr::Expr, index_predicate::IndexPredicate},
+    types::schema::OutputColumn,
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -12,6 +16,7 @@ use crate::{
 pub enum LogicalPlan {
     Scan {
         table_id: TableId,
+        from: FromId,
     },
 
     Filter {
@@ -22,6 +27,8 @@ pub enum LogicalPlan {
     Project {
         input: Box<LogicalPlan>,
         exprs: Vec<Expr>,
+        /// Result column for each expression.
+        columns: Vec<OutputColumn>,
     },
 
     Sort {
@@ -42,12 +49,98 @@ pub enum LogicalPlan {
         join_type: JoinType,
     },
 
+    /// Equi-join. Rows of the `build` side are hashed on their keys and
+    /// rows of the other side look up their matches, which must also pass
+    /// `residual`. Output rows are the left row followed by the right one,
+    /// as for `Join`. Rows with a NULL key match nothing.
+    HashJoin {
+        left: Box<LogicalPlan>,
+        right: Box<LogicalPlan>,
+        /// Key expressions over left rows, paired with `right_keys`.
+        left_keys: Vec<Expr>,
+        right_keys: Vec<Expr>,
+        residual: Option<Expr>,
+        join_type: JoinType,
+        build: JoinSide,
+    },
+
+    /// Index nested loop join: looks up the rows of `inner_table` matching
+    /// each `outer` row through an index on the inner join column, keyed by
+    /// `outer_key`.
+    /// Matches must also pass `residual`. Output rows are the left row
+    /// followed by the right one; `inner_side` says which the table is.
+    /// Only the outer side may be kept unmatched.
+    IndexJoin {
+        outer: Box<LogicalPlan>,
+        inner_table: TableId,
+        inner_from: FromId,
+        index_id: IndexId,
+        outer_key: Expr,
+        residual: Option<Expr>,
+        join_type: JoinType,
+        inner_side: JoinSide,
+    },
+
+    /// Groups input rows by `group_by` and computes `aggregates` per group.
+    /// Output rows are the group key values followed by the aggregate
+    /// results. Without group keys the whole input is one group, even when
+    /// it is empty.
+    Aggregate {
+        input: Box<LogicalPlan>,
+        group_by: Vec<Expr>,
+        aggregates: Vec<AggregateExpr>,
+    },
+
     IndexScan {
         table_id: TableId,
+        from: FromId,
         index_id: IndexId,
         predicate: IndexPredicate,
     },
 
+    /// Rows of a query read in FROM, such as a CTE, with their values taken
+    /// as `columns` by position.
+    Derived {
+        input: Box<LogicalPlan>,
+        from: FromId,
+        columns: Vec<ColumnId>,
+    },
+
+    /// Rows of `anchor`, then those `recursive` finds when run over the
+    /// rows found last, which it reads as `WorkTable` `id`, until it finds
+    /// none. Unless `all` is set, rows found before are dropped.
+    RecursiveCte {
+        id: usize,
+        anchor: Box<LogicalPlan>,
+        recursive: Box<LogicalPlan>,
+        all: bool,
+    },
+
+    /// Rows the recursive CTE `id` found in its last round.
+    WorkTable {
+        id: usize,
+        columns: Vec<OutputColumn>,
+    },
+
+    /// The first row of `input` for each distinct value of `on`, or each
+    /// distinct row when `on` is empty. When `sorted`, rows with equal
+    /// values arrive together, so only the last value is remembered;
+    /// otherwise every value seen is hashed.
+    Distinct {
+        input: Box<LogicalPlan>,
+        on: Vec<Expr>,
+        sorted: bool,
+    },
+
+    /// Rows of `left` combined with those of `right`, which have the same
+    /// shape. Unless `all` is set, each distinct row is output once.
+    SetOp {
+        op: SetOpType,
+        all: bool,
+        left: Box<LogicalPlan>,
+        right: Box<LogicalPlan>,
+    },
+
     Insert {
         table_id: TableId,
         rows: Vec<Vec<Expr>>,
@@ -65,16 +158,76 @@ pub enum LogicalPlan {
     },
 }
 
+/// One table or query read in the FROM clauses of a statement. A table
+/// read twice, as in a self-join, is two of them, so its columns are told
+/// apart by the one they are read through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FromId(pub u32);
+
+impl FromId {
+    /// The table an INSERT, UPDATE or DELETE writes, whose rows its
+    /// expressions read.
+    pub const TARGET: FromId = FromId(0);
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct SortKey {
     pub expr: Expr,
     pub asc: bool,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct AggregateExpr {
+    pub func: AggregateFunc,
+    /// `None` for `COUNT(*)`.
+    pub arg: Option<Expr>,
+    pub distinct: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AggregateFunc {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    /// An aggregate added to the function registry.
+    User(Arc<AggregateFunction>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetOpType {
+    /// Rows of either side.
+    Union,
+    /// Rows of the left side the right side has too.
+    Intersect,
+    /// Rows of the left side the right side does not have.
+    Except,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinSide {
+    Left,
+    Right,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum JoinType {
     Inner,
     Left,
     Right,
     Full,
+    /// Left rows with at least one match, each output once. Output rows
+    /// are the left rows alone.
+    Semi,
+    /// Left rows without any match. Output rows are the left rows alone.
+    Anti,
+}
+
+impl JoinType {
+    /// Whether output rows are the left rows alone, without the right row
+    /// they matched.
+    pub fn left_only(self) -> bool {
+        matches!(self, JoinType::Semi | JoinType::Anti)
+    }
 }
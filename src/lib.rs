@@ -5,6 +5,7 @@ pub mod cli;
 pub mod diagnostics;
 pub mod execution;
 pub mod frontend;
+pub mod functions;
 pub mod ir;
 pub mod optimizer;
 pub mod planner;
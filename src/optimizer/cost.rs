@@ -1,4 +1,13 @@
-use crate::{catalog::catalog::Catalog, ir::plan::LogicalPlan};
+use crate::{
+    catalog::catalog::Catalog,
+    ir::{
+        index_predicate::IndexPredicate,
+        plan::{LogicalPlan, SetOpType},
+    },
+};
+
+/// Rounds a recursive CTE is assumed to run for.
+const RECURSIVE_ROUNDS: u64 = 10;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Cost {
@@ -14,7 +23,7 @@ impl Cost {
 
 pub fn estimate_cost(plan: &LogicalPlan, catalog: &Catalog) -> Cost {
     match plan {
-        LogicalPlan::Scan { table_id } => {
+        LogicalPlan::Scan { table_id, .. } => {
             let rows = catalog.table_stats(*table_id).row_count;
             Cost {
                 cpu: rows,
@@ -32,15 +41,126 @@ pub fn estimate_cost(plan: &LogicalPlan, catalog: &Catalog) -> Cost {
             }
         }
 
+        LogicalPlan::Derived { input, .. } => estimate_cost(input, catalog),
+
+        // Assume a few rounds, each about the anchor's size.
+        LogicalPlan::RecursiveCte {
+            anchor, recursive, ..
+        } => {
+            let a = estimate_cost(anchor, catalog);
+            let r = estimate_cost(recursive, catalog);
+            Cost {
+                cpu: a.cpu + RECURSIVE_ROUNDS * r.cpu,
+                io: a.io + RECURSIVE_ROUNDS * r.io,
+            }
+        }
+
+        // Every row of both sides is hashed.
+        LogicalPlan::SetOp { left, right, .. } => {
+            let l = estimate_cost(left, catalog);
+            let r = estimate_cost(right, catalog);
+            Cost {
+                cpu: l.cpu + r.cpu + estimate_rows(left, catalog) + estimate_rows(right, catalog),
+                io: l.io + r.io,
+            }
+        }
+
+        // Every left row is checked against every right row.
         LogicalPlan::Join { left, right, .. } => {
             let l = estimate_cost(left, catalog);
             let r = estimate_cost(right, catalog);
             Cost {
-                cpu: l.cpu * r.cpu,
+                cpu: l.cpu + r.cpu + estimate_rows(left, catalog) * estimate_rows(right, catalog),
                 io: l.io + r.io,
             }
         }
 
+        // Each input row is hashed once.
+        LogicalPlan::HashJoin { left, right, .. } => {
+            let l = estimate_cost(left, catalog);
+            let r = estimate_cost(right, catalog);
+            Cost {
+                cpu: l.cpu + r.cpu + estimate_rows(left, catalog) + estimate_rows(right, catalog),
+                io: l.io + r.io,
+            }
+        }
+
+        // One index lookup per outer row.
+        LogicalPlan::IndexJoin { outer, .. } => {
+            let o = estimate_cost(outer, catalog);
+            let rows = estimate_rows(outer, catalog);
+            Cost {
+                cpu: o.cpu + 2 * rows,
+                io: o.io + rows,
+            }
+        }
+
         _ => Cost { cpu: 1, io: 1 },
     }
 }
+
+/// Number of rows `plan` is expected to produce.
+pub fn estimate_rows(plan: &LogicalPlan, catalog: &Catalog) -> u64 {
+    match plan {
+        LogicalPlan::Scan { table_id, .. } => catalog.table_stats(*table_id).row_count,
+
+        LogicalPlan::IndexScan {
+            table_id,
+            predicate,
+            ..
+        } => match predicate {
+            IndexPredicate::Eq(_) => 1,
+            IndexPredicate::In(values) => values.len() as u64,
+            IndexPredicate::Range { .. } => catalog.table_stats(*table_id).row_count / 3,
+        },
+
+        LogicalPlan::Filter { input, .. } => estimate_rows(input, catalog).div_ceil(3),
+
+        LogicalPlan::Project { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Distinct { input, .. }
+        | LogicalPlan::Derived { input, .. } => estimate_rows(input, catalog),
+
+        LogicalPlan::RecursiveCte { anchor, .. } => {
+            estimate_rows(anchor, catalog) * (RECURSIVE_ROUNDS + 1)
+        }
+
+        LogicalPlan::Limit { input, limit, .. } => estimate_rows(input, catalog).min(*limit),
+
+        LogicalPlan::SetOp {
+            op: SetOpType::Union,
+            left,
+            right,
+            ..
+        } => estimate_rows(left, catalog) + estimate_rows(right, catalog),
+
+        // Some of the left rows.
+        LogicalPlan::SetOp { left, .. } => estimate_rows(left, catalog),
+
+        // Semi and anti joins output some of the left rows.
+        LogicalPlan::Join {
+            left, join_type, ..
+        }
+        | LogicalPlan::HashJoin {
+            left, join_type, ..
+        } if join_type.left_only() => estimate_rows(left, catalog),
+
+        LogicalPlan::Join { left, right, .. } | LogicalPlan::HashJoin { left, right, .. } => {
+            estimate_rows(left, catalog).max(estimate_rows(right, catalog))
+        }
+
+        LogicalPlan::IndexJoin { outer, .. } => estimate_rows(outer, catalog),
+
+        LogicalPlan::Aggregate {
+            input, group_by, ..
+        } => {
+            if group_by.is_empty() {
+                1
+            } else {
+                estimate_rows(input, catalog).div_ceil(10)
+            }
+        }
+
+        _ => 0,
+    }
+}
@@ -8,16 +8,19 @@ use crate::{
     optimizer::{
         errors::OptimizerError,
         rules::{
-            constant_fold::constant_fold, index_selection::index_selection,
+            constant_fold::constant_fold, decorrelate::decorrelate,
+            index_selection::index_selection, join_selection::join_selection,
             predicate_pushdown::predicate_pushdown, projection_prune::projection_prune,
         },
     },
 };
 
 pub fn optimize(plan: &LogicalPlan, catalog: &Catalog) -> Result<LogicalPlan, OptimizerError> {
-    let plan = constant_fold(plan)?;
-    let plan = predicate_pushdown(&plan)?;
+    let plan = decorrelate(plan, catalog)?;
+    let plan = constant_fold(&plan)?;
+    let plan = predicate_pushdown(&plan, catalog)?;
     let plan = index_selection(&plan, catalog)?;
+    let plan = join_selection(&plan, catalog)?;
     let plan = projection_prune(&plan)?;
     Ok(plan)
 }
@@ -1,7 +1,9 @@
 use crate::{
+    execution::cast::cast_value,
+    functions::scalar::{CallContext, Volatility},
     ir::{
         expr::{BinaryOp, Expr, UnaryOp},
-        plan::LogicalPlan,
+        plan::{AggregateExpr, LogicalPlan},
     },
     optimizer::errors::OptimizerError,
     types::value::Value,
@@ -16,9 +18,14 @@ pub fn constant_fold(plan: &LogicalPlan) -> Result<LogicalPlan, OptimizerError>
             predicate: fold_expr(predicate),
         },
 
-        LogicalPlan::Project { input, exprs } => LogicalPlan::Project {
+        LogicalPlan::Project {
+            input,
+            exprs,
+            columns,
+        } => LogicalPlan::Project {
             input: Box::new(constant_fold(input)?),
             exprs: exprs.iter().map(fold_expr).collect(),
+            columns: columns.clone(),
         },
 
         LogicalPlan::Sort { input, keys } => LogicalPlan::Sort {
@@ -26,6 +33,12 @@ pub fn constant_fold(plan: &LogicalPlan) -> Result<LogicalPlan, OptimizerError>
             keys: keys.clone(),
         },
 
+        LogicalPlan::Distinct { input, on, sorted } => LogicalPlan::Distinct {
+            input: Box::new(constant_fold(input)?),
+            on: on.clone(),
+            sorted: *sorted,
+        },
+
         LogicalPlan::Limit {
             input,
             limit,
@@ -48,18 +61,148 @@ pub fn constant_fold(plan: &LogicalPlan) -> Result<LogicalPlan, OptimizerError>
             join_type: *join_type,
         },
 
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => LogicalPlan::Aggregate {
+            input: Box::new(constant_fold(input)?),
+            group_by: group_by.iter().map(fold_expr).collect(),
+            aggregates: aggregates
+                .iter()
+                .map(|a| AggregateExpr {
+                    arg: a.arg.as_ref().map(fold_expr),
+                    ..a.clone()
+                })
+                .collect(),
+        },
+
+        LogicalPlan::Derived {
+            input,
+            from,
+            columns,
+        } => LogicalPlan::Derived {
+            input: Box::new(constant_fold(input)?),
+            from: *from,
+            columns: columns.clone(),
+        },
+
+        LogicalPlan::RecursiveCte {
+            id,
+            anchor,
+            recursive,
+            all,
+        } => LogicalPlan::RecursiveCte {
+            id: *id,
+            anchor: Box::new(constant_fold(anchor)?),
+            recursive: Box::new(constant_fold(recursive)?),
+            all: *all,
+        },
+
+        LogicalPlan::SetOp {
+            op,
+            all,
+            left,
+            right,
+        } => LogicalPlan::SetOp {
+            op: *op,
+            all: *all,
+            left: Box::new(constant_fold(left)?),
+            right: Box::new(constant_fold(right)?),
+        },
+
         _ => plan.clone(),
     })
 }
 
 pub fn fold_expr(expr: &Expr) -> Expr {
     match expr {
-        Expr::Literal(_) | Expr::Null | Expr::BoundColumn { .. } => expr.clone(),
+        Expr::Literal(_)
+        | Expr::Null
+        | Expr::BoundColumn { .. }
+        | Expr::ColumnSlot { .. }
+        | Expr::Subquery { .. }
+        | Expr::Parameter { .. } => expr.clone(),
+
+        // A cast that would fail is left to fail when the query runs.
+        Expr::Cast { expr, to } => match fold_expr(expr) {
+            Expr::Literal(v) => match cast_value(v.clone(), to) {
+                Ok(v) => Expr::Literal(v),
+                Err(_) => Expr::Cast {
+                    expr: Box::new(Expr::Literal(v)),
+                    to: to.clone(),
+                },
+            },
+            e => Expr::Cast {
+                expr: Box::new(e),
+                to: to.clone(),
+            },
+        },
+
+        // A deterministic function called on constants is called now. A
+        // call that fails is left for the query to make, which may never
+        // reach it.
+        Expr::Function { func, args } => {
+            let args: Vec<Expr> = args.iter().map(fold_expr).collect();
+            let constants = args
+                .iter()
+                .map(|a| match a {
+                    Expr::Literal(v) => Some(v.clone()),
+                    Expr::Null => Some(Value::Null),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            let folded = constants
+                .filter(|_| func.volatility() == Volatility::Deterministic)
+                .and_then(|values| func.call(&CallContext::now(), &values).ok());
+            match folded {
+                Some(v) => Expr::Literal(v),
+                None => Expr::Function {
+                    func: func.clone(),
+                    args,
+                },
+            }
+        }
+
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => Expr::Case {
+            operand: operand.as_ref().map(|e| Box::new(fold_expr(e))),
+            branches: branches
+                .iter()
+                .map(|(when, then)| (fold_expr(when), fold_expr(then)))
+                .collect(),
+            else_result: else_result.as_ref().map(|e| Box::new(fold_expr(e))),
+        },
+
+        Expr::InList { expr, list } => Expr::InList {
+            expr: Box::new(fold_expr(expr)),
+            list: list.iter().map(fold_expr).collect(),
+        },
+
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            case_insensitive,
+        } => Expr::Like {
+            expr: Box::new(fold_expr(expr)),
+            pattern: Box::new(fold_expr(pattern)),
+            escape: *escape,
+            case_insensitive: *case_insensitive,
+        },
 
         Expr::Unary { op, expr } => {
             let e = fold_expr(expr);
             match (op, &e) {
-                (UnaryOp::Neg, Expr::Literal(Value::Int64(v))) => Expr::Literal(Value::Int64(-v)),
+                (UnaryOp::Neg, Expr::Literal(Value::Int64(v))) if *v != i64::MIN => {
+                    Expr::Literal(Value::Int64(-v))
+                }
+                (UnaryOp::Neg, Expr::Literal(Value::Float64(v))) => {
+                    Expr::Literal(Value::Float64(-v))
+                }
                 (UnaryOp::Not, Expr::Literal(Value::Boolean(v))) => {
                     Expr::Literal(Value::Boolean(!v))
                 }
@@ -75,7 +218,9 @@ pub fn fold_expr(expr: &Expr) -> Expr {
             let r = fold_expr(right);
 
             match (&l, op, &r) {
-                (Expr::Literal(Value::Int64(a)), BinaryOp::Add, Expr::Literal(Value::Int64(b))) => {
+                (Expr::Literal(Value::Int64(a)), BinaryOp::Add, Expr::Literal(Value::Int64(b)))
+                    if a.checked_add(*b).is_some() =>
+                {
                     Expr::Literal(Value::Int64(a + b))
                 }
 
@@ -98,4 +243,3 @@ pub fn fold_expr(expr: &Expr) -> Expr {
         }
     }
 }
-
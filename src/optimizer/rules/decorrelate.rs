@@ -0,0 +1,540 @@
+use crate::{
+    catalog::catalog::Catalog,
+    ir::{
+        expr::{BinaryOp, Expr, SubqueryKind, UnaryOp},
+        plan::{JoinType, LogicalPlan, SortKey},
+    },
+    optimizer::{
+        errors::OptimizerError,
+        optimize,
+        rules::predicate_pushdown::{conjoin, output_columns, split_conjuncts},
+    },
+};
+
+/// Turn `[NOT] EXISTS` and `IN` subqueries in filters into semi and anti
+/// joins, so the subquery is read once rather than run for every row and
+/// join selection can pick a hash or index join for it. `NOT IN` only
+/// becomes an anti join when neither side can be NULL, since a NULL makes
+/// it NULL rather than true.
+///
+/// A subquery qualifies when it is a filtered read of tables the enclosing
+/// query does not read, correlated only through its filter. The correlated
+/// conjuncts become the join condition, with the values passed in replaced
+/// by the expressions they come from. Other subqueries are left in place
+/// and optimized on their own.
+pub fn decorrelate(plan: &LogicalPlan, catalog: &Catalog) -> Result<LogicalPlan, OptimizerError> {
+    Ok(match plan {
+        LogicalPlan::Filter { input, predicate } => {
+            let mut input = decorrelate(input, catalog)?;
+            let mut kept = Vec::new();
+            for conjunct in split_conjuncts(predicate) {
+                match semi_join(&input, &conjunct, catalog)? {
+                    Some(join) => input = join,
+                    None => kept.push(optimize_subqueries(&conjunct, catalog)?),
+                }
+            }
+            match conjoin(kept) {
+                Some(predicate) => LogicalPlan::Filter {
+                    input: Box::new(input),
+                    predicate,
+                },
+                None => input,
+            }
+        }
+
+        LogicalPlan::Project {
+            input,
+            exprs,
+            columns,
+        } => LogicalPlan::Project {
+            input: Box::new(decorrelate(input, catalog)?),
+            exprs: exprs
+                .iter()
+                .map(|e| optimize_subqueries(e, catalog))
+                .collect::<Result<_, _>>()?,
+            columns: columns.clone(),
+        },
+
+        LogicalPlan::Sort { input, keys } => LogicalPlan::Sort {
+            input: Box::new(decorrelate(input, catalog)?),
+            keys: keys
+                .iter()
+                .map(|k| {
+                    Ok(SortKey {
+                        expr: optimize_subqueries(&k.expr, catalog)?,
+                        asc: k.asc,
+                    })
+                })
+                .collect::<Result<_, OptimizerError>>()?,
+        },
+
+        LogicalPlan::Distinct { input, on, sorted } => LogicalPlan::Distinct {
+            input: Box::new(decorrelate(input, catalog)?),
+            on: on
+                .iter()
+                .map(|e| optimize_subqueries(e, catalog))
+                .collect::<Result<_, _>>()?,
+            sorted: *sorted,
+        },
+
+        LogicalPlan::Limit {
+            input,
+            limit,
+            offset,
+        } => LogicalPlan::Limit {
+            input: Box::new(decorrelate(input, catalog)?),
+            limit: *limit,
+            offset: *offset,
+        },
+
+        LogicalPlan::Join {
+            left,
+            right,
+            on,
+            join_type,
+        } => LogicalPlan::Join {
+            left: Box::new(decorrelate(left, catalog)?),
+            right: Box::new(decorrelate(right, catalog)?),
+            on: optimize_subqueries(on, catalog)?,
+            join_type: *join_type,
+        },
+
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => LogicalPlan::Aggregate {
+            input: Box::new(decorrelate(input, catalog)?),
+            group_by: group_by.clone(),
+            aggregates: aggregates.clone(),
+        },
+
+        LogicalPlan::Delete {
+            table_id,
+            predicate,
+        } => LogicalPlan::Delete {
+            table_id: *table_id,
+            predicate: predicate
+                .as_ref()
+                .map(|p| optimize_subqueries(p, catalog))
+                .transpose()?,
+        },
+
+        LogicalPlan::Update {
+            table_id,
+            assignments,
+            predicate,
+        } => LogicalPlan::Update {
+            table_id: *table_id,
+            assignments: assignments
+                .iter()
+                .map(|(col, e)| Ok((*col, optimize_subqueries(e, catalog)?)))
+                .collect::<Result<_, OptimizerError>>()?,
+            predicate: predicate
+                .as_ref()
+                .map(|p| optimize_subqueries(p, catalog))
+                .transpose()?,
+        },
+
+        LogicalPlan::Derived {
+            input,
+            from,
+            columns,
+        } => LogicalPlan::Derived {
+            input: Box::new(decorrelate(input, catalog)?),
+            from: *from,
+            columns: columns.clone(),
+        },
+
+        LogicalPlan::RecursiveCte {
+            id,
+            anchor,
+            recursive,
+            all,
+        } => LogicalPlan::RecursiveCte {
+            id: *id,
+            anchor: Box::new(decorrelate(anchor, catalog)?),
+            recursive: Box::new(decorrelate(recursive, catalog)?),
+            all: *all,
+        },
+
+        LogicalPlan::SetOp {
+            op,
+            all,
+            left,
+            right,
+        } => LogicalPlan::SetOp {
+            op: *op,
+            all: *all,
+            left: Box::new(decorrelate(left, catalog)?),
+            right: Box::new(decorrelate(right, catalog)?),
+        },
+
+        _ => plan.clone(),
+    })
+}
+
+/// `input` semi or anti joined with the subquery of `conjunct`, if the
+/// filter can be written that way.
+fn semi_join(
+    input: &LogicalPlan,
+    conjunct: &Expr,
+    catalog: &Catalog,
+) -> Result<Option<LogicalPlan>, OptimizerError> {
+    let (subquery, negated) = match conjunct {
+        Expr::Unary {
+            op: UnaryOp::Not,
+            expr,
+        } => (&**expr, true),
+        _ => (conjunct, false),
+    };
+    let Expr::Subquery {
+        kind, plan, params, ..
+    } = subquery
+    else {
+        return Ok(None);
+    };
+    if matches!(kind, SubqueryKind::Scalar) {
+        return Ok(None);
+    }
+
+    let Some((output, body)) = strip_output(plan, kind) else {
+        return Ok(None);
+    };
+    let (source, filter) = match body {
+        LogicalPlan::Filter { input, predicate } => (&**input, split_conjuncts(predicate)),
+        _ => (body, Vec::new()),
+    };
+    if !is_plain_read(source) {
+        return Ok(None);
+    }
+    let outer_columns = output_columns(input, catalog)?;
+    if !output_columns(source, catalog)?.is_disjoint(&outer_columns) {
+        return Ok(None);
+    }
+
+    let (correlated, local): (Vec<Expr>, Vec<Expr>) = filter.into_iter().partition(has_parameter);
+    let mut on = Vec::new();
+    for conjunct in correlated {
+        match bind_params(&conjunct, params) {
+            Some(conjunct) => on.push(conjunct),
+            None => return Ok(None),
+        }
+    }
+
+    if let SubqueryKind::In(lhs) = kind {
+        // A NULL on either side makes NOT IN NULL, which the anti join
+        // would read as no match.
+        if negated && !(never_null(lhs, input, catalog) && never_null(output, source, catalog)) {
+            return Ok(None);
+        }
+        let Some(output) = bind_params(output, params) else {
+            return Ok(None);
+        };
+        on.push(Expr::Binary {
+            left: lhs.clone(),
+            op: BinaryOp::Eq,
+            right: Box::new(output),
+        });
+    }
+    // An uncorrelated EXISTS is cheaper run once.
+    let Some(on) = conjoin(on) else {
+        return Ok(None);
+    };
+    if on_reads_slots(&on) {
+        return Ok(None);
+    }
+
+    let right = match conjoin(local) {
+        Some(predicate) => LogicalPlan::Filter {
+            input: Box::new(source.clone()),
+            predicate,
+        },
+        None => source.clone(),
+    };
+    Ok(Some(LogicalPlan::Join {
+        left: Box::new(input.clone()),
+        right: Box::new(decorrelate(&right, catalog)?),
+        on,
+        join_type: if negated {
+            JoinType::Anti
+        } else {
+            JoinType::Semi
+        },
+    }))
+}
+
+/// The subquery's output expression, which IN compares against, and the
+/// plan below its projection. Ordering does not matter to either kind, and a limit does
+/// not change whether any rows exist.
+fn strip_output<'a>(
+    plan: &'a LogicalPlan,
+    kind: &SubqueryKind,
+) -> Option<(&'a Expr, &'a LogicalPlan)> {
+    let mut plan = plan;
+    let mut output = None;
+    loop {
+        match plan {
+            LogicalPlan::Project { input, exprs, .. } if output.is_none() => {
+                output = exprs.first();
+                plan = input;
+            }
+            LogicalPlan::Sort { input, .. } => plan = input,
+            LogicalPlan::Limit {
+                input,
+                limit,
+                offset: 0,
+            } if *limit > 0 && matches!(kind, SubqueryKind::Exists) => plan = input,
+            _ => break,
+        }
+    }
+    output.map(|output| (output, plan))
+}
+
+/// Whether `plan` only reads and filters tables, without reading values
+/// passed into the subquery.
+fn is_plain_read(plan: &LogicalPlan) -> bool {
+    match plan {
+        LogicalPlan::Scan { .. } => true,
+        LogicalPlan::Filter { input, predicate } => {
+            !has_parameter(predicate) && is_plain_read(input)
+        }
+        LogicalPlan::Join {
+            left, right, on, ..
+        } => !has_parameter(on) && is_plain_read(left) && is_plain_read(right),
+        _ => false,
+    }
+}
+
+/// Whether the rows of `plan` never hold NULL in `expr`: it must be a NOT
+/// NULL column of a table read without outer joins.
+fn never_null(expr: &Expr, plan: &LogicalPlan, catalog: &Catalog) -> bool {
+    let Expr::BoundColumn { from, column_id } = expr else {
+        return false;
+    };
+    match plan {
+        LogicalPlan::Scan { table_id, from: f } if f == from => {
+            catalog.get_table_by_id(*table_id).is_some_and(|t| {
+                t.schema
+                    .columns
+                    .iter()
+                    .any(|c| c.id == *column_id && !c.nullable)
+            })
+        }
+        LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Limit { input, .. } => never_null(expr, input, catalog),
+        LogicalPlan::Join {
+            left,
+            right,
+            join_type: JoinType::Inner,
+            ..
+        } => never_null(expr, left, catalog) || never_null(expr, right, catalog),
+        LogicalPlan::Join {
+            left,
+            join_type: JoinType::Semi | JoinType::Anti,
+            ..
+        } => never_null(expr, left, catalog),
+        _ => false,
+    }
+}
+
+/// Whether `expr` reads values passed into the subquery it belongs to.
+fn has_parameter(expr: &Expr) -> bool {
+    match expr {
+        Expr::Parameter { .. } => true,
+        Expr::Unary { expr, .. } | Expr::Cast { expr, .. } => has_parameter(expr),
+        Expr::Binary { left, right, .. } => has_parameter(left) || has_parameter(right),
+        Expr::Function { args, .. } => args.iter().any(has_parameter),
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => operand
+            .iter()
+            .chain(else_result)
+            .map(|e| &**e)
+            .chain(branches.iter().flat_map(|(when, then)| [when, then]))
+            .any(has_parameter),
+        Expr::InList { expr, list } => has_parameter(expr) || list.iter().any(has_parameter),
+        Expr::Like { expr, pattern, .. } => has_parameter(expr) || has_parameter(pattern),
+        Expr::Subquery { kind, params, .. } => {
+            params.iter().chain(kind.operand()).any(has_parameter)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `expr` reads positional slots, which mean nothing in a join
+/// condition.
+fn on_reads_slots(expr: &Expr) -> bool {
+    match expr {
+        Expr::ColumnSlot { .. } => true,
+        Expr::Unary { expr, .. } | Expr::Cast { expr, .. } => on_reads_slots(expr),
+        Expr::Binary { left, right, .. } => on_reads_slots(left) || on_reads_slots(right),
+        Expr::Function { args, .. } => args.iter().any(on_reads_slots),
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => operand
+            .iter()
+            .chain(else_result)
+            .map(|e| &**e)
+            .chain(branches.iter().flat_map(|(when, then)| [when, then]))
+            .any(on_reads_slots),
+        Expr::InList { expr, list } => on_reads_slots(expr) || list.iter().any(on_reads_slots),
+        Expr::Like { expr, pattern, .. } => on_reads_slots(expr) || on_reads_slots(pattern),
+        _ => false,
+    }
+}
+
+/// `expr` with the values passed into the subquery in place of its
+/// parameters, or `None` if it holds a subquery of its own.
+fn bind_params(expr: &Expr, params: &[Expr]) -> Option<Expr> {
+    Some(match expr {
+        Expr::Parameter { index } => params.get(*index)?.clone(),
+        Expr::Unary { op, expr } => Expr::Unary {
+            op: *op,
+            expr: Box::new(bind_params(expr, params)?),
+        },
+        Expr::Binary { left, op, right } => Expr::Binary {
+            left: Box::new(bind_params(left, params)?),
+            op: *op,
+            right: Box::new(bind_params(right, params)?),
+        },
+        Expr::Cast { expr, to } => Expr::Cast {
+            expr: Box::new(bind_params(expr, params)?),
+            to: to.clone(),
+        },
+        Expr::Function { func, args } => Expr::Function {
+            func: func.clone(),
+            args: args
+                .iter()
+                .map(|arg| bind_params(arg, params))
+                .collect::<Option<_>>()?,
+        },
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => Expr::Case {
+            operand: match operand {
+                Some(e) => Some(Box::new(bind_params(e, params)?)),
+                None => None,
+            },
+            branches: branches
+                .iter()
+                .map(|(when, then)| Some((bind_params(when, params)?, bind_params(then, params)?)))
+                .collect::<Option<_>>()?,
+            else_result: match else_result {
+                Some(e) => Some(Box::new(bind_params(e, params)?)),
+                None => None,
+            },
+        },
+        Expr::InList { expr, list } => Expr::InList {
+            expr: Box::new(bind_params(expr, params)?),
+            list: list
+                .iter()
+                .map(|e| bind_params(e, params))
+                .collect::<Option<_>>()?,
+        },
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            case_insensitive,
+        } => Expr::Like {
+            expr: Box::new(bind_params(expr, params)?),
+            pattern: Box::new(bind_params(pattern, params)?),
+            escape: *escape,
+            case_insensitive: *case_insensitive,
+        },
+        Expr::Subquery { .. } => return None,
+        _ => expr.clone(),
+    })
+}
+
+/// `expr` with the plans of the subqueries in it optimized.
+fn optimize_subqueries(expr: &Expr, catalog: &Catalog) -> Result<Expr, OptimizerError> {
+    Ok(match expr {
+        Expr::Subquery {
+            id,
+            kind,
+            plan,
+            params,
+        } => Expr::Subquery {
+            id: *id,
+            kind: match kind {
+                SubqueryKind::In(expr) => {
+                    SubqueryKind::In(Box::new(optimize_subqueries(expr, catalog)?))
+                }
+                kind => kind.clone(),
+            },
+            plan: Box::new(optimize(plan, catalog)?),
+            params: params
+                .iter()
+                .map(|e| optimize_subqueries(e, catalog))
+                .collect::<Result<_, _>>()?,
+        },
+        Expr::Unary { op, expr } => Expr::Unary {
+            op: *op,
+            expr: Box::new(optimize_subqueries(expr, catalog)?),
+        },
+        Expr::Binary { left, op, right } => Expr::Binary {
+            left: Box::new(optimize_subqueries(left, catalog)?),
+            op: *op,
+            right: Box::new(optimize_subqueries(right, catalog)?),
+        },
+        Expr::Cast { expr, to } => Expr::Cast {
+            expr: Box::new(optimize_subqueries(expr, catalog)?),
+            to: to.clone(),
+        },
+        Expr::Function { func, args } => Expr::Function {
+            func: func.clone(),
+            args: args
+                .iter()
+                .map(|arg| optimize_subqueries(arg, catalog))
+                .collect::<Result<_, _>>()?,
+        },
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            let optimize = |e: &Expr| optimize_subqueries(e, catalog).map(Box::new);
+            Expr::Case {
+                operand: operand.as_deref().map(optimize).transpose()?,
+                branches: branches
+                    .iter()
+                    .map(|(when, then)| {
+                        Ok((
+                            optimize_subqueries(when, catalog)?,
+                            optimize_subqueries(then, catalog)?,
+                        ))
+                    })
+                    .collect::<Result<_, OptimizerError>>()?,
+                else_result: else_result.as_deref().map(optimize).transpose()?,
+            }
+        }
+        Expr::InList { expr, list } => Expr::InList {
+            expr: Box::new(optimize_subqueries(expr, catalog)?),
+            list: list
+                .iter()
+                .map(|e| optimize_subqueries(e, catalog))
+                .collect::<Result<_, _>>()?,
+        },
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            case_insensitive,
+        } => Expr::Like {
+            expr: Box::new(optimize_subqueries(expr, catalog)?),
+            pattern: Box::new(optimize_subqueries(pattern, catalog)?),
+            escape: *escape,
+            case_insensitive: *case_insensitive,
+        },
+        _ => expr.clone(),
+    })
+}
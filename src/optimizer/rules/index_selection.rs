@@ -1,11 +1,19 @@
 use crate::{
-    catalog::catalog::Catalog,
+    catalog::{
+        catalog::Catalog,
+        ids::{ColumnId, TableId},
+    },
     ir::{
         expr::{BinaryOp, Expr},
         index_predicate::IndexPredicate,
-        plan::LogicalPlan,
+        plan::{FromId, LogicalPlan},
+    },
+    optimizer::{
+        errors::OptimizerError,
+        rules::predicate_pushdown::{conjoin, split_conjuncts},
     },
-    optimizer::errors::OptimizerError,
+    types::value::Value,
+    util::like,
 };
 
 pub fn index_selection(
@@ -16,29 +24,15 @@ pub fn index_selection(
         LogicalPlan::Filter { input, predicate } => {
             let input = index_selection(input, catalog)?;
 
-            let LogicalPlan::Scan { table_id } = &input else {
+            let LogicalPlan::Scan { table_id, from } = &input else {
                 return Ok(LogicalPlan::Filter {
                     input: Box::new(input),
                     predicate: predicate.clone(),
                 });
             };
 
-            if let Expr::Binary {
-                left,
-                op: BinaryOp::Eq,
-                right,
-            } = predicate
-            {
-                if let (Expr::BoundColumn { column_id, .. }, Expr::Literal(v)) = (&**left, &**right)
-                {
-                    if let Some(index) = catalog.find_index_on_column(*table_id, *column_id) {
-                        return Ok(LogicalPlan::IndexScan {
-                            table_id: *table_id,
-                            index_id: index.meta.id,
-                            predicate: IndexPredicate::Eq(v.clone()),
-                        });
-                    }
-                }
+            if let Some(scan) = index_scan(*table_id, *from, predicate, catalog) {
+                return Ok(scan);
             }
 
             LogicalPlan::Filter {
@@ -47,11 +41,225 @@ pub fn index_selection(
             }
         }
 
-        LogicalPlan::Project { input, exprs } => LogicalPlan::Project {
+        LogicalPlan::Project {
+            input,
+            exprs,
+            columns,
+        } => LogicalPlan::Project {
             input: Box::new(index_selection(input, catalog)?),
             exprs: exprs.clone(),
+            columns: columns.clone(),
+        },
+
+        LogicalPlan::Sort { input, keys } => LogicalPlan::Sort {
+            input: Box::new(index_selection(input, catalog)?),
+            keys: keys.clone(),
+        },
+
+        LogicalPlan::Distinct { input, on, sorted } => LogicalPlan::Distinct {
+            input: Box::new(index_selection(input, catalog)?),
+            on: on.clone(),
+            sorted: *sorted,
+        },
+
+        LogicalPlan::Limit {
+            input,
+            limit,
+            offset,
+        } => LogicalPlan::Limit {
+            input: Box::new(index_selection(input, catalog)?),
+            limit: *limit,
+            offset: *offset,
+        },
+
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => LogicalPlan::Aggregate {
+            input: Box::new(index_selection(input, catalog)?),
+            group_by: group_by.clone(),
+            aggregates: aggregates.clone(),
+        },
+
+        LogicalPlan::Derived {
+            input,
+            from,
+            columns,
+        } => LogicalPlan::Derived {
+            input: Box::new(index_selection(input, catalog)?),
+            from: *from,
+            columns: columns.clone(),
+        },
+
+        LogicalPlan::RecursiveCte {
+            id,
+            anchor,
+            recursive,
+            all,
+        } => LogicalPlan::RecursiveCte {
+            id: *id,
+            anchor: Box::new(index_selection(anchor, catalog)?),
+            recursive: Box::new(index_selection(recursive, catalog)?),
+            all: *all,
+        },
+
+        LogicalPlan::SetOp {
+            op,
+            all,
+            left,
+            right,
+        } => LogicalPlan::SetOp {
+            op: *op,
+            all: *all,
+            left: Box::new(index_selection(left, catalog)?),
+            right: Box::new(index_selection(right, catalog)?),
         },
 
         _ => plan.clone(),
     })
 }
+
+/// A scan of `table_id` through an index that finds the rows `predicate`
+/// holds for, if one of its conjuncts limits an indexed column to some
+/// keys: `col = v`, `col IN (...)`, `col >= a AND col <= b` as written for
+/// `BETWEEN`, or `col LIKE 'abc%'`. The other conjuncts filter the rows it
+/// finds.
+fn index_scan(
+    table_id: TableId,
+    from: FromId,
+    predicate: &Expr,
+    catalog: &Catalog,
+) -> Option<LogicalPlan> {
+    let conjuncts = split_conjuncts(predicate);
+    let (index_id, predicate, used) = (0..conjuncts.len())
+        .filter_map(|i| key_predicate(&conjuncts, i))
+        .filter_map(|(column_id, predicate, used)| {
+            let index = catalog.find_index_on_column(table_id, column_id)?;
+            Some((index.meta.id, predicate, used))
+        })
+        .min_by_key(|(_, predicate, _)| match predicate {
+            IndexPredicate::Eq(_) => 0,
+            IndexPredicate::In(_) => 1,
+            IndexPredicate::Range { .. } => 2,
+        })?;
+
+    let scan = LogicalPlan::IndexScan {
+        table_id,
+        from,
+        index_id,
+        predicate,
+    };
+    let rest = conjuncts
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !used.contains(i))
+        .map(|(_, conjunct)| conjunct)
+        .collect();
+    Some(match conjoin(rest) {
+        Some(predicate) => LogicalPlan::Filter {
+            input: Box::new(scan),
+            predicate,
+        },
+        None => scan,
+    })
+}
+
+/// The column the `i`th of `conjuncts` limits, the keys it limits it to,
+/// and the conjuncts those keys make redundant.
+fn key_predicate(conjuncts: &[Expr], i: usize) -> Option<(ColumnId, IndexPredicate, Vec<usize>)> {
+    let bound = |expr: &Expr, op: BinaryOp| match expr {
+        Expr::Binary { left, op: o, right } if *o == op => match (&**left, &**right) {
+            (Expr::BoundColumn { column_id, .. }, Expr::Literal(v)) if *v != Value::Null => {
+                Some((*column_id, v.clone()))
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+
+    match &conjuncts[i] {
+        expr @ Expr::Binary {
+            op: BinaryOp::Eq, ..
+        } => {
+            let (column_id, v) = bound(expr, BinaryOp::Eq)?;
+            Some((column_id, IndexPredicate::Eq(v), vec![i]))
+        }
+
+        expr @ Expr::Binary {
+            op: BinaryOp::Gte, ..
+        } => {
+            let (column_id, low) = bound(expr, BinaryOp::Gte)?;
+            conjuncts.iter().enumerate().find_map(|(j, other)| {
+                let (other_id, high) = bound(other, BinaryOp::Lte)?;
+                (other_id == column_id).then(|| {
+                    (
+                        column_id,
+                        IndexPredicate::Range {
+                            low: low.clone(),
+                            high,
+                        },
+                        vec![i, j],
+                    )
+                })
+            })
+        }
+
+        // NULL items match no row, so only the others are looked up.
+        Expr::InList { expr, list } => {
+            let Expr::BoundColumn { column_id, .. } = &**expr else {
+                return None;
+            };
+            let mut values = Vec::new();
+            for item in list {
+                match item {
+                    Expr::Null | Expr::Literal(Value::Null) => {}
+                    Expr::Literal(v) if !values.contains(v) => values.push(v.clone()),
+                    Expr::Literal(_) => {}
+                    _ => return None,
+                }
+            }
+            (!values.is_empty()).then(|| (*column_id, IndexPredicate::In(values), vec![i]))
+        }
+
+        // Only the rows whose key starts with the text before the first
+        // wildcard are read; the LIKE itself still filters them.
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            case_insensitive: false,
+        } => {
+            let (Expr::BoundColumn { column_id, .. }, Expr::Literal(Value::String(pattern))) =
+                (&**expr, &**pattern)
+            else {
+                return None;
+            };
+            let low = like::prefix(pattern, *escape).filter(|prefix| !prefix.is_empty())?;
+            let high = after_prefix(&low)?;
+            Some((
+                *column_id,
+                IndexPredicate::Range {
+                    low: Value::String(low),
+                    high: Value::String(high),
+                },
+                vec![],
+            ))
+        }
+
+        _ => None,
+    }
+}
+
+/// The least string greater than every string that starts with `prefix`,
+/// if there is one.
+fn after_prefix(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        if let Some(next) = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32) {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
@@ -0,0 +1,317 @@
+use crate::{
+    catalog::catalog::Catalog,
+    ir::{
+        expr::{BinaryOp, Expr},
+        plan::{JoinSide, JoinType, LogicalPlan},
+    },
+    optimizer::{
+        cost::{estimate_cost, estimate_rows},
+        errors::OptimizerError,
+        rules::predicate_pushdown::{conjoin, expr_columns, output_columns, split_conjuncts},
+    },
+    types::value::Value,
+};
+
+/// Pick how each join with equalities between its two sides is executed:
+/// as a nested loop, a hash join building on the smaller input, or an index
+/// nested loop join probing an index on the inner join column, whichever
+/// the cost model rates cheapest.
+pub fn join_selection(
+    plan: &LogicalPlan,
+    catalog: &Catalog,
+) -> Result<LogicalPlan, OptimizerError> {
+    Ok(match plan {
+        LogicalPlan::Join {
+            left,
+            right,
+            on,
+            join_type,
+        } => {
+            let join = LogicalPlan::Join {
+                left: Box::new(join_selection(left, catalog)?),
+                right: Box::new(join_selection(right, catalog)?),
+                on: on.clone(),
+                join_type: *join_type,
+            };
+            let mut candidates = equi_join_candidates(&join, catalog)?;
+            candidates.push(join);
+            candidates
+                .into_iter()
+                .min_by_key(|p| estimate_cost(p, catalog).total())
+                .expect("the nested loop join is always a candidate")
+        }
+
+        LogicalPlan::Filter { input, predicate } => LogicalPlan::Filter {
+            input: Box::new(join_selection(input, catalog)?),
+            predicate: predicate.clone(),
+        },
+
+        LogicalPlan::Project {
+            input,
+            exprs,
+            columns,
+        } => LogicalPlan::Project {
+            input: Box::new(join_selection(input, catalog)?),
+            exprs: exprs.clone(),
+            columns: columns.clone(),
+        },
+
+        LogicalPlan::Sort { input, keys } => LogicalPlan::Sort {
+            input: Box::new(join_selection(input, catalog)?),
+            keys: keys.clone(),
+        },
+
+        LogicalPlan::Distinct { input, on, sorted } => LogicalPlan::Distinct {
+            input: Box::new(join_selection(input, catalog)?),
+            on: on.clone(),
+            sorted: *sorted,
+        },
+
+        LogicalPlan::Limit {
+            input,
+            limit,
+            offset,
+        } => LogicalPlan::Limit {
+            input: Box::new(join_selection(input, catalog)?),
+            limit: *limit,
+            offset: *offset,
+        },
+
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => LogicalPlan::Aggregate {
+            input: Box::new(join_selection(input, catalog)?),
+            group_by: group_by.clone(),
+            aggregates: aggregates.clone(),
+        },
+
+        LogicalPlan::Derived {
+            input,
+            from,
+            columns,
+        } => LogicalPlan::Derived {
+            input: Box::new(join_selection(input, catalog)?),
+            from: *from,
+            columns: columns.clone(),
+        },
+
+        LogicalPlan::RecursiveCte {
+            id,
+            anchor,
+            recursive,
+            all,
+        } => LogicalPlan::RecursiveCte {
+            id: *id,
+            anchor: Box::new(join_selection(anchor, catalog)?),
+            recursive: Box::new(join_selection(recursive, catalog)?),
+            all: *all,
+        },
+
+        LogicalPlan::SetOp {
+            op,
+            all,
+            left,
+            right,
+        } => LogicalPlan::SetOp {
+            op: *op,
+            all: *all,
+            left: Box::new(join_selection(left, catalog)?),
+            right: Box::new(join_selection(right, catalog)?),
+        },
+
+        _ => plan.clone(),
+    })
+}
+
+/// Equalities between the two sides of a join condition, and the rest of it.
+struct EquiKeys {
+    left: Vec<Expr>,
+    right: Vec<Expr>,
+    residual: Vec<Expr>,
+}
+
+impl EquiKeys {
+    /// Residual conditions, plus the key equalities other than the `i`th.
+    fn residual_without(&self, i: usize) -> Vec<Expr> {
+        let pairs = self.left.iter().zip(&self.right).enumerate();
+        pairs
+            .filter(|(j, _)| *j != i)
+            .map(|(_, (l, r))| Expr::Binary {
+                left: Box::new(l.clone()),
+                op: BinaryOp::Eq,
+                right: Box::new(r.clone()),
+            })
+            .chain(self.residual.iter().cloned())
+            .collect()
+    }
+}
+
+/// Hash and index joins equivalent to `join`, if its condition has
+/// equi-join keys.
+fn equi_join_candidates(
+    join: &LogicalPlan,
+    catalog: &Catalog,
+) -> Result<Vec<LogicalPlan>, OptimizerError> {
+    let LogicalPlan::Join {
+        left,
+        right,
+        on,
+        join_type,
+    } = join
+    else {
+        return Ok(Vec::new());
+    };
+
+    let keys = equi_keys(on, left, right, catalog)?;
+    if keys.left.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Semi and anti joins output left rows alone, so those are the rows
+    // streamed through the join.
+    let inner_sides: &[JoinSide] = if join_type.left_only() {
+        &[JoinSide::Right]
+    } else {
+        &[JoinSide::Right, JoinSide::Left]
+    };
+    let mut candidates = Vec::new();
+    for &inner_side in inner_sides {
+        for i in 0..keys.left.len() {
+            candidates.extend(index_join_for(join, &keys, i, inner_side, catalog));
+        }
+    }
+
+    let build =
+        if !join_type.left_only() && estimate_rows(left, catalog) < estimate_rows(right, catalog) {
+            JoinSide::Left
+        } else {
+            JoinSide::Right
+        };
+    candidates.push(LogicalPlan::HashJoin {
+        left: left.clone(),
+        right: right.clone(),
+        left_keys: keys.left,
+        right_keys: keys.right,
+        residual: conjoin(keys.residual),
+        join_type: *join_type,
+        build,
+    });
+    Ok(candidates)
+}
+
+fn equi_keys(
+    on: &Expr,
+    left: &LogicalPlan,
+    right: &LogicalPlan,
+    catalog: &Catalog,
+) -> Result<EquiKeys, OptimizerError> {
+    let left_columns = output_columns(left, catalog)?;
+    let right_columns = output_columns(right, catalog)?;
+    let side_of = |e: &Expr| match expr_columns(e) {
+        Some(cols) if !cols.is_empty() && cols.is_subset(&left_columns) => Some(JoinSide::Left),
+        Some(cols) if !cols.is_empty() && cols.is_subset(&right_columns) => Some(JoinSide::Right),
+        _ => None,
+    };
+
+    let mut keys = EquiKeys {
+        left: Vec::new(),
+        right: Vec::new(),
+        residual: Vec::new(),
+    };
+    for conjunct in split_conjuncts(on) {
+        if let Expr::Binary {
+            left: a,
+            op: BinaryOp::Eq,
+            right: b,
+        } = &conjunct
+        {
+            match (side_of(a), side_of(b)) {
+                (Some(JoinSide::Left), Some(JoinSide::Right)) => {
+                    keys.left.push((**a).clone());
+                    keys.right.push((**b).clone());
+                    continue;
+                }
+                (Some(JoinSide::Right), Some(JoinSide::Left)) => {
+                    keys.left.push((**b).clone());
+                    keys.right.push((**a).clone());
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if conjunct != Expr::Literal(Value::Boolean(true)) {
+            keys.residual.push(conjunct);
+        }
+    }
+    Ok(keys)
+}
+
+/// Index nested loop join looking up the `inner_side` input through an
+/// index on its `i`th key. The inner input must be a plain table scan, or a
+/// filtered one for joins that only output matches of it, and its rows must
+/// not be kept unmatched.
+fn index_join_for(
+    join: &LogicalPlan,
+    keys: &EquiKeys,
+    i: usize,
+    inner_side: JoinSide,
+    catalog: &Catalog,
+) -> Option<LogicalPlan> {
+    let LogicalPlan::Join {
+        left,
+        right,
+        join_type,
+        ..
+    } = join
+    else {
+        return None;
+    };
+
+    let (outer, inner, outer_key, inner_key) = match inner_side {
+        JoinSide::Left => (right, left, &keys.right[i], &keys.left[i]),
+        JoinSide::Right => (left, right, &keys.left[i], &keys.right[i]),
+    };
+    let keeps_inner = match inner_side {
+        JoinSide::Left => matches!(join_type, JoinType::Left | JoinType::Full),
+        JoinSide::Right => matches!(join_type, JoinType::Right | JoinType::Full),
+    };
+    if keeps_inner {
+        return None;
+    }
+
+    let mut residual = keys.residual_without(i);
+    let (inner_table, inner_from) = match &**inner {
+        LogicalPlan::Scan { table_id, from } => (*table_id, *from),
+        LogicalPlan::Filter { input, predicate }
+            if matches!(join_type, JoinType::Inner | JoinType::Semi | JoinType::Anti) =>
+        {
+            let LogicalPlan::Scan { table_id, from } = &**input else {
+                return None;
+            };
+            residual.push(predicate.clone());
+            (*table_id, *from)
+        }
+        _ => return None,
+    };
+
+    let Expr::BoundColumn { from, column_id } = inner_key else {
+        return None;
+    };
+    if *from != inner_from {
+        return None;
+    }
+    let index = catalog.find_index_on_column(inner_table, *column_id)?;
+
+    Some(LogicalPlan::IndexJoin {
+        outer: outer.clone(),
+        inner_table,
+        inner_from,
+        index_id: index.meta.id,
+        outer_key: outer_key.clone(),
+        residual: conjoin(residual),
+        join_type: *join_type,
+        inner_side,
+    })
+}
@@ -1,4 +1,6 @@
 pub mod constant_fold;
+pub mod decorrelate;
 pub mod index_selection;
+pub mod join_selection;
 pub mod predicate_pushdown;
 pub mod projection_prune;
@@ -1,18 +1,41 @@
-use crate::{ir::plan::LogicalPlan, optimizer::errors::OptimizerError};
+use std::collections::HashSet;
 
-pub fn predicate_pushdown(plan: &LogicalPlan) -> Result<LogicalPlan, OptimizerError> {
+use crate::{
+    catalog::{catalog::Catalog, ids::ColumnId},
+    ir::{
+        expr::{BinaryOp, Expr},
+        plan::{FromId, JoinType, LogicalPlan},
+    },
+    optimizer::errors::OptimizerError,
+};
+
+pub fn predicate_pushdown(
+    plan: &LogicalPlan,
+    catalog: &Catalog,
+) -> Result<LogicalPlan, OptimizerError> {
     Ok(match plan {
         LogicalPlan::Filter { input, predicate } => {
-            let optimized_input = predicate_pushdown(input)?;
+            let optimized_input = predicate_pushdown(input, catalog)?;
 
             match optimized_input {
-                LogicalPlan::Project { input, exprs } => LogicalPlan::Project {
+                LogicalPlan::Project {
+                    input,
+                    exprs,
+                    columns,
+                } => LogicalPlan::Project {
                     input: Box::new(LogicalPlan::Filter {
                         input,
                         predicate: predicate.clone(),
                     }),
                     exprs,
+                    columns,
                 },
+                LogicalPlan::Join {
+                    left,
+                    right,
+                    on,
+                    join_type,
+                } => push_into_join(predicate, *left, *right, on, join_type, catalog)?,
                 other => LogicalPlan::Filter {
                     input: Box::new(other),
                     predicate: predicate.clone(),
@@ -20,28 +43,309 @@ pub fn predicate_pushdown(plan: &LogicalPlan) -> Result<LogicalPlan, OptimizerEr
             }
         }
 
-        LogicalPlan::Project { input, exprs } => LogicalPlan::Project {
-            input: Box::new(predicate_pushdown(input)?),
+        LogicalPlan::Project {
+            input,
+            exprs,
+            columns,
+        } => LogicalPlan::Project {
+            input: Box::new(predicate_pushdown(input, catalog)?),
             exprs: exprs.clone(),
+            columns: columns.clone(),
         },
 
         LogicalPlan::Sort { input, keys } => LogicalPlan::Sort {
-            input: Box::new(predicate_pushdown(input)?),
+            input: Box::new(predicate_pushdown(input, catalog)?),
             keys: keys.clone(),
         },
 
+        LogicalPlan::Distinct { input, on, sorted } => LogicalPlan::Distinct {
+            input: Box::new(predicate_pushdown(input, catalog)?),
+            on: on.clone(),
+            sorted: *sorted,
+        },
+
+        LogicalPlan::Limit {
+            input,
+            limit,
+            offset,
+        } => LogicalPlan::Limit {
+            input: Box::new(predicate_pushdown(input, catalog)?),
+            limit: *limit,
+            offset: *offset,
+        },
+
         LogicalPlan::Join {
             left,
             right,
             on,
             join_type,
         } => LogicalPlan::Join {
-            left: Box::new(predicate_pushdown(left)?),
-            right: Box::new(predicate_pushdown(right)?),
+            left: Box::new(predicate_pushdown(left, catalog)?),
+            right: Box::new(predicate_pushdown(right, catalog)?),
             on: on.clone(),
-            join_type: join_type.clone(),
+            join_type: *join_type,
+        },
+
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => LogicalPlan::Aggregate {
+            input: Box::new(predicate_pushdown(input, catalog)?),
+            group_by: group_by.clone(),
+            aggregates: aggregates.clone(),
+        },
+
+        LogicalPlan::Derived {
+            input,
+            from,
+            columns,
+        } => LogicalPlan::Derived {
+            input: Box::new(predicate_pushdown(input, catalog)?),
+            from: *from,
+            columns: columns.clone(),
+        },
+
+        LogicalPlan::RecursiveCte {
+            id,
+            anchor,
+            recursive,
+            all,
+        } => LogicalPlan::RecursiveCte {
+            id: *id,
+            anchor: Box::new(predicate_pushdown(anchor, catalog)?),
+            recursive: Box::new(predicate_pushdown(recursive, catalog)?),
+            all: *all,
+        },
+
+        LogicalPlan::SetOp {
+            op,
+            all,
+            left,
+            right,
+        } => LogicalPlan::SetOp {
+            op: *op,
+            all: *all,
+            left: Box::new(predicate_pushdown(left, catalog)?),
+            right: Box::new(predicate_pushdown(right, catalog)?),
         },
 
         _ => plan.clone(),
     })
 }
+
+/// Move the conjuncts of a filter above a join into the side whose columns
+/// they read. A side an outer join pads with NULLs keeps its filters above
+/// the join: below it they would drop rows the join then pads back in.
+/// Conjuncts reading both sides of an inner join become part of its
+/// condition, where join selection can find equi-join keys.
+fn push_into_join(
+    predicate: &Expr,
+    left: LogicalPlan,
+    right: LogicalPlan,
+    on: Expr,
+    join_type: JoinType,
+    catalog: &Catalog,
+) -> Result<LogicalPlan, OptimizerError> {
+    let left_columns = output_columns(&left, catalog)?;
+    let right_columns = output_columns(&right, catalog)?;
+    let into_left = matches!(
+        join_type,
+        JoinType::Inner | JoinType::Left | JoinType::Semi | JoinType::Anti
+    );
+    let into_right = matches!(join_type, JoinType::Inner | JoinType::Right);
+
+    let (mut left_preds, mut right_preds) = (Vec::new(), Vec::new());
+    let (mut join_preds, mut kept) = (Vec::new(), Vec::new());
+    for conjunct in split_conjuncts(predicate) {
+        match expr_columns(&conjunct) {
+            Some(cols) if !cols.is_empty() && into_left && cols.is_subset(&left_columns) => {
+                left_preds.push(conjunct)
+            }
+            Some(cols) if !cols.is_empty() && into_right && cols.is_subset(&right_columns) => {
+                right_preds.push(conjunct)
+            }
+            Some(_) if join_type == JoinType::Inner => join_preds.push(conjunct),
+            _ => kept.push(conjunct),
+        }
+    }
+
+    let on = match conjoin(join_preds) {
+        Some(extra) => conjoin(vec![on, extra]).expect("two conjuncts"),
+        None => on,
+    };
+    let join = LogicalPlan::Join {
+        left: Box::new(filter_side(left, left_preds, catalog)?),
+        right: Box::new(filter_side(right, right_preds, catalog)?),
+        on,
+        join_type,
+    };
+
+    Ok(match conjoin(kept) {
+        Some(predicate) => LogicalPlan::Filter {
+            input: Box::new(join),
+            predicate,
+        },
+        None => join,
+    })
+}
+
+fn filter_side(
+    plan: LogicalPlan,
+    predicates: Vec<Expr>,
+    catalog: &Catalog,
+) -> Result<LogicalPlan, OptimizerError> {
+    match conjoin(predicates) {
+        Some(predicate) => predicate_pushdown(
+            &LogicalPlan::Filter {
+                input: Box::new(plan),
+                predicate,
+            },
+            catalog,
+        ),
+        None => Ok(plan),
+    }
+}
+
+pub(crate) fn split_conjuncts(expr: &Expr) -> Vec<Expr> {
+    match expr {
+        Expr::Binary {
+            left,
+            op: BinaryOp::And,
+            right,
+        } => {
+            let mut out = split_conjuncts(left);
+            out.extend(split_conjuncts(right));
+            out
+        }
+        _ => vec![expr.clone()],
+    }
+}
+
+pub(crate) fn conjoin(exprs: Vec<Expr>) -> Option<Expr> {
+    exprs.into_iter().reduce(|left, right| Expr::Binary {
+        left: Box::new(left),
+        op: BinaryOp::And,
+        right: Box::new(right),
+    })
+}
+
+/// Columns `expr` reads, or `None` if it reads positional slots, which only
+/// make sense against the operator it sits on.
+pub(crate) fn expr_columns(expr: &Expr) -> Option<HashSet<(FromId, ColumnId)>> {
+    fn collect(expr: &Expr, out: &mut HashSet<(FromId, ColumnId)>) -> bool {
+        match expr {
+            Expr::BoundColumn { from, column_id } => {
+                out.insert((*from, *column_id));
+                true
+            }
+            Expr::Unary { expr, .. } | Expr::Cast { expr, .. } => collect(expr, out),
+            Expr::Binary { left, right, .. } => collect(left, out) && collect(right, out),
+            Expr::Function { args, .. } => args.iter().all(|e| collect(e, out)),
+            Expr::Case {
+                operand,
+                branches,
+                else_result,
+            } => operand
+                .iter()
+                .chain(else_result)
+                .map(|e| &**e)
+                .chain(branches.iter().flat_map(|(when, then)| [when, then]))
+                .all(|e| collect(e, out)),
+            Expr::InList { expr, list } => {
+                collect(expr, out) && list.iter().all(|e| collect(e, out))
+            }
+            Expr::Like { expr, pattern, .. } => collect(expr, out) && collect(pattern, out),
+            // A subquery reads the values passed into it from the row.
+            Expr::Subquery { kind, params, .. } => {
+                params.iter().chain(kind.operand()).all(|e| collect(e, out))
+            }
+            Expr::ColumnSlot { .. } => false,
+            Expr::Literal(_) | Expr::Null | Expr::Parameter { .. } => true,
+        }
+    }
+
+    let mut out = HashSet::new();
+    collect(expr, &mut out).then_some(out)
+}
+
+/// Columns whose values the rows of `plan` carry.
+pub(crate) fn output_columns(
+    plan: &LogicalPlan,
+    catalog: &Catalog,
+) -> Result<HashSet<(FromId, ColumnId)>, OptimizerError> {
+    Ok(match plan {
+        LogicalPlan::Scan { table_id, from } | LogicalPlan::IndexScan { table_id, from, .. } => {
+            let table =
+                catalog
+                    .get_table_by_id(*table_id)
+                    .ok_or_else(|| OptimizerError::CatalogError {
+                        message: format!("unknown table {:?}", table_id),
+                    })?;
+            table.schema.columns.iter().map(|c| (*from, c.id)).collect()
+        }
+
+        LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Distinct { input, .. }
+        | LogicalPlan::Limit { input, .. } => output_columns(input, catalog)?,
+
+        LogicalPlan::Join {
+            left,
+            right,
+            join_type,
+            ..
+        }
+        | LogicalPlan::HashJoin {
+            left,
+            right,
+            join_type,
+            ..
+        } => {
+            let mut columns = output_columns(left, catalog)?;
+            if !join_type.left_only() {
+                columns.extend(output_columns(right, catalog)?);
+            }
+            columns
+        }
+
+        LogicalPlan::Project { exprs, .. } => exprs
+            .iter()
+            .filter_map(|e| match e {
+                Expr::BoundColumn { from, column_id } => Some((*from, *column_id)),
+                _ => None,
+            })
+            .collect(),
+
+        LogicalPlan::IndexJoin {
+            outer,
+            inner_table,
+            inner_from,
+            join_type,
+            ..
+        } => {
+            let mut columns = output_columns(outer, catalog)?;
+            if join_type.left_only() {
+                return Ok(columns);
+            }
+            columns.extend(output_columns(
+                &LogicalPlan::Scan {
+                    table_id: *inner_table,
+                    from: *inner_from,
+                },
+                catalog,
+            )?);
+            columns
+        }
+
+        LogicalPlan::Derived { from, columns, .. } => columns.iter().map(|c| (*from, *c)).collect(),
+
+        LogicalPlan::Aggregate { .. }
+        | LogicalPlan::RecursiveCte { .. }
+        | LogicalPlan::SetOp { .. }
+        | LogicalPlan::WorkTable { .. }
+        | LogicalPlan::Insert { .. }
+        | LogicalPlan::Update { .. }
+        | LogicalPlan::Delete { .. } => HashSet::new(),
+    })
+}
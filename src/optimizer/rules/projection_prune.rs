@@ -4,9 +4,10 @@ use crate::{
     catalog::ids::ColumnId,
     ir::{
         expr::Expr,
-        plan::{LogicalPlan, SortKey},
+        plan::{FromId, LogicalPlan, SortKey},
     },
     optimizer::errors::OptimizerError,
+    types::schema::OutputColumn,
 };
 
 pub fn projection_prune(plan: &LogicalPlan) -> Result<LogicalPlan, OptimizerError> {
@@ -15,7 +16,7 @@ pub fn projection_prune(plan: &LogicalPlan) -> Result<LogicalPlan, OptimizerErro
     Ok(rewrite(plan, &required))
 }
 
-pub fn rewrite(plan: &LogicalPlan, required: &HashSet<ColumnId>) -> LogicalPlan {
+pub fn rewrite(plan: &LogicalPlan, required: &HashSet<(FromId, ColumnId)>) -> LogicalPlan {
     match plan {
         // -------------------------
         // LIMIT
@@ -33,13 +34,19 @@ pub fn rewrite(plan: &LogicalPlan, required: &HashSet<ColumnId>) -> LogicalPlan
         // -------------------------
         // PROJECT
         // -------------------------
-        LogicalPlan::Project { input, exprs } => {
-            // Keep only expressions that reference required columns
-            let kept_exprs: Vec<Expr> = exprs
+        LogicalPlan::Project {
+            input,
+            exprs,
+            columns,
+        } => {
+            // Keep only expressions that reference required columns, and
+            // those that reference none, such as literals
+            let (kept_exprs, kept_columns): (Vec<Expr>, Vec<OutputColumn>) = exprs
                 .iter()
-                .filter(|expr| expr_uses_any(expr, required))
-                .cloned()
-                .collect();
+                .zip(columns)
+                .filter(|(expr, _)| expr_uses_any(expr, required) || !expr_uses_columns(expr))
+                .map(|(expr, column)| (expr.clone(), column.clone()))
+                .unzip();
 
             // If projection becomes identity, remove it
             if kept_exprs.is_empty() {
@@ -49,6 +56,7 @@ pub fn rewrite(plan: &LogicalPlan, required: &HashSet<ColumnId>) -> LogicalPlan
             LogicalPlan::Project {
                 input: Box::new(rewrite(input, required)),
                 exprs: kept_exprs,
+                columns: kept_columns,
             }
         }
 
@@ -68,6 +76,12 @@ pub fn rewrite(plan: &LogicalPlan, required: &HashSet<ColumnId>) -> LogicalPlan
             keys: keys.clone(),
         },
 
+        LogicalPlan::Distinct { input, on, sorted } => LogicalPlan::Distinct {
+            input: Box::new(rewrite(input, required)),
+            on: on.clone(),
+            sorted: *sorted,
+        },
+
         // -------------------------
         // JOIN
         // -------------------------
@@ -83,51 +97,205 @@ pub fn rewrite(plan: &LogicalPlan, required: &HashSet<ColumnId>) -> LogicalPlan
             join_type: join_type.clone(),
         },
 
+        LogicalPlan::HashJoin {
+            left,
+            right,
+            left_keys,
+            right_keys,
+            residual,
+            join_type,
+            build,
+        } => LogicalPlan::HashJoin {
+            left: Box::new(rewrite(left, required)),
+            right: Box::new(rewrite(right, required)),
+            left_keys: left_keys.clone(),
+            right_keys: right_keys.clone(),
+            residual: residual.clone(),
+            join_type: *join_type,
+            build: *build,
+        },
+
+        LogicalPlan::IndexJoin {
+            outer,
+            inner_table,
+            inner_from,
+            index_id,
+            outer_key,
+            residual,
+            join_type,
+            inner_side,
+        } => LogicalPlan::IndexJoin {
+            outer: Box::new(rewrite(outer, required)),
+            inner_table: *inner_table,
+            inner_from: *inner_from,
+            index_id: *index_id,
+            outer_key: outer_key.clone(),
+            residual: residual.clone(),
+            join_type: *join_type,
+            inner_side: *inner_side,
+        },
+
+        // -------------------------
+        // AGGREGATE
+        // -------------------------
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => LogicalPlan::Aggregate {
+            input: Box::new(rewrite(input, required)),
+            group_by: group_by.clone(),
+            aggregates: aggregates.clone(),
+        },
+
+        // -------------------------
+        // CTE
+        // -------------------------
+        LogicalPlan::Derived {
+            input,
+            from,
+            columns,
+        } => LogicalPlan::Derived {
+            input: Box::new(rewrite(input, required)),
+            from: *from,
+            columns: columns.clone(),
+        },
+
+        LogicalPlan::RecursiveCte {
+            id,
+            anchor,
+            recursive,
+            all,
+        } => LogicalPlan::RecursiveCte {
+            id: *id,
+            anchor: Box::new(rewrite(anchor, required)),
+            recursive: Box::new(rewrite(recursive, required)),
+            all: *all,
+        },
+
+        LogicalPlan::SetOp {
+            op,
+            all,
+            left,
+            right,
+        } => LogicalPlan::SetOp {
+            op: *op,
+            all: *all,
+            left: Box::new(rewrite(left, required)),
+            right: Box::new(rewrite(right, required)),
+        },
+
         // -------------------------
         // SCAN / INDEXSCAN (terminal)
         // -------------------------
         LogicalPlan::Scan { .. }
         | LogicalPlan::IndexScan { .. }
+        | LogicalPlan::WorkTable { .. }
         | LogicalPlan::Insert { .. }
         | LogicalPlan::Update { .. }
         | LogicalPlan::Delete { .. } => plan.clone(),
     }
 }
-fn expr_uses_any(expr: &Expr, required: &HashSet<ColumnId>) -> bool {
+fn expr_uses_any(expr: &Expr, required: &HashSet<(FromId, ColumnId)>) -> bool {
     match expr {
-        Expr::BoundColumn { column_id, .. } => required.contains(column_id),
+        Expr::BoundColumn { from, column_id } => required.contains(&(*from, *column_id)),
 
-        Expr::Unary { expr, .. } => expr_uses_any(expr, required),
+        Expr::Unary { expr, .. } | Expr::Cast { expr, .. } => expr_uses_any(expr, required),
 
         Expr::Binary { left, right, .. } => {
             expr_uses_any(left, required) || expr_uses_any(right, required)
         }
 
-        Expr::Literal(_) | Expr::Null => false,
+        Expr::Function { args, .. } => args.iter().any(|e| expr_uses_any(e, required)),
+
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => operand
+            .iter()
+            .chain(else_result)
+            .map(|e| &**e)
+            .chain(branches.iter().flat_map(|(when, then)| [when, then]))
+            .any(|e| expr_uses_any(e, required)),
+
+        Expr::InList { expr, list } => {
+            expr_uses_any(expr, required) || list.iter().any(|e| expr_uses_any(e, required))
+        }
+
+        Expr::Like { expr, pattern, .. } => {
+            expr_uses_any(expr, required) || expr_uses_any(pattern, required)
+        }
+
+        Expr::Subquery { kind, params, .. } => params
+            .iter()
+            .chain(kind.operand())
+            .any(|e| expr_uses_any(e, required)),
+
+        Expr::Literal(_) | Expr::Null | Expr::ColumnSlot { .. } | Expr::Parameter { .. } => false,
     }
 }
 
-fn collect_columns(expr: &Expr, out: &mut HashSet<ColumnId>) {
+fn expr_uses_columns(expr: &Expr) -> bool {
+    let mut columns = HashSet::new();
+    collect_columns(expr, &mut columns);
+    !columns.is_empty()
+}
+
+fn collect_columns(expr: &Expr, out: &mut HashSet<(FromId, ColumnId)>) {
     match expr {
-        Expr::BoundColumn { column_id, .. } => {
-            out.insert(*column_id);
+        Expr::BoundColumn { from, column_id } => {
+            out.insert((*from, *column_id));
         }
-        Expr::Unary { expr, .. } => collect_columns(expr, out),
+        Expr::Unary { expr, .. } | Expr::Cast { expr, .. } => collect_columns(expr, out),
         Expr::Binary { left, right, .. } => {
             collect_columns(left, out);
             collect_columns(right, out);
         }
+        Expr::Function { args, .. } => {
+            for e in args {
+                collect_columns(e, out);
+            }
+        }
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            for e in operand.iter().chain(else_result) {
+                collect_columns(e, out);
+            }
+            for (when, then) in branches {
+                collect_columns(when, out);
+                collect_columns(then, out);
+            }
+        }
+        Expr::InList { expr, list } => {
+            collect_columns(expr, out);
+            for e in list {
+                collect_columns(e, out);
+            }
+        }
+        Expr::Like { expr, pattern, .. } => {
+            collect_columns(expr, out);
+            collect_columns(pattern, out);
+        }
+        Expr::Subquery { kind, params, .. } => {
+            for e in params.iter().chain(kind.operand()) {
+                collect_columns(e, out);
+            }
+        }
         _ => {}
     }
 }
 
-fn collect_expr_columns(expr: &Expr, required: &mut HashSet<ColumnId>) {
+fn collect_expr_columns(expr: &Expr, required: &mut HashSet<(FromId, ColumnId)>) {
     match expr {
-        Expr::BoundColumn { column_id, .. } => {
-            required.insert(*column_id);
+        Expr::BoundColumn { from, column_id } => {
+            required.insert((*from, *column_id));
         }
 
-        Expr::Unary { expr, .. } => {
+        Expr::Unary { expr, .. } | Expr::Cast { expr, .. } => {
             collect_expr_columns(expr, required);
         }
 
@@ -136,16 +304,55 @@ fn collect_expr_columns(expr: &Expr, required: &mut HashSet<ColumnId>) {
             collect_expr_columns(right, required);
         }
 
-        Expr::Literal(_) | Expr::Null => {}
+        Expr::Function { args, .. } => {
+            for e in args {
+                collect_expr_columns(e, required);
+            }
+        }
+
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            for e in operand.iter().chain(else_result) {
+                collect_expr_columns(e, required);
+            }
+            for (when, then) in branches {
+                collect_expr_columns(when, required);
+                collect_expr_columns(then, required);
+            }
+        }
+
+        Expr::InList { expr, list } => {
+            collect_expr_columns(expr, required);
+            for e in list {
+                collect_expr_columns(e, required);
+            }
+        }
+
+        Expr::Like { expr, pattern, .. } => {
+            collect_expr_columns(expr, required);
+            collect_expr_columns(pattern, required);
+        }
+
+        // Columns a subquery reads from the row are passed in as values.
+        Expr::Subquery { kind, params, .. } => {
+            for e in params.iter().chain(kind.operand()) {
+                collect_expr_columns(e, required);
+            }
+        }
+
+        Expr::Literal(_) | Expr::Null | Expr::ColumnSlot { .. } | Expr::Parameter { .. } => {}
     }
 }
 
-pub fn collect_required_columns(plan: &LogicalPlan, required: &mut HashSet<ColumnId>) {
+pub fn collect_required_columns(plan: &LogicalPlan, required: &mut HashSet<(FromId, ColumnId)>) {
     match plan {
         // -------------------------
         // PROJECT
         // -------------------------
-        LogicalPlan::Project { input, exprs } => {
+        LogicalPlan::Project { input, exprs, .. } => {
             for expr in exprs {
                 collect_expr_columns(expr, required);
             }
@@ -170,6 +377,13 @@ pub fn collect_required_columns(plan: &LogicalPlan, required: &mut HashSet<Colum
             collect_required_columns(input, required);
         }
 
+        LogicalPlan::Distinct { input, on, .. } => {
+            for expr in on {
+                collect_expr_columns(expr, required);
+            }
+            collect_required_columns(input, required);
+        }
+
         // -------------------------
         // JOIN
         // -------------------------
@@ -184,6 +398,50 @@ pub fn collect_required_columns(plan: &LogicalPlan, required: &mut HashSet<Colum
             collect_required_columns(right, required);
         }
 
+        LogicalPlan::HashJoin {
+            left,
+            right,
+            left_keys,
+            right_keys,
+            residual,
+            ..
+        } => {
+            for expr in left_keys.iter().chain(right_keys).chain(residual) {
+                collect_expr_columns(expr, required);
+            }
+            collect_required_columns(left, required);
+            collect_required_columns(right, required);
+        }
+
+        LogicalPlan::IndexJoin {
+            outer,
+            outer_key,
+            residual,
+            ..
+        } => {
+            for expr in std::iter::once(outer_key).chain(residual) {
+                collect_expr_columns(expr, required);
+            }
+            collect_required_columns(outer, required);
+        }
+
+        // -------------------------
+        // AGGREGATE
+        // -------------------------
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => {
+            for expr in group_by
+                .iter()
+                .chain(aggregates.iter().filter_map(|a| a.arg.as_ref()))
+            {
+                collect_expr_columns(expr, required);
+            }
+            collect_required_columns(input, required);
+        }
+
         // -------------------------
         // LIMIT
         // -------------------------
@@ -202,16 +460,42 @@ pub fn collect_required_columns(plan: &LogicalPlan, required: &mut HashSet<Colum
                     // literal only → no column usage
                     let _ = v;
                 }
+                IndexPredicate::In(values) => {
+                    let _ = values;
+                }
                 IndexPredicate::Range { low, high } => {
                     let _ = (low, high);
                 }
             }
         }
 
+        // -------------------------
+        // CTE
+        // -------------------------
+        // A CTE's columns are read by position, so its projection keeps
+        // all of them. So do the queries of a set operation, whose rows
+        // are compared whole.
+        LogicalPlan::Derived { input, .. } => {
+            collect_required_columns(input, required);
+        }
+
+        LogicalPlan::RecursiveCte {
+            anchor, recursive, ..
+        } => {
+            collect_required_columns(anchor, required);
+            collect_required_columns(recursive, required);
+        }
+
+        LogicalPlan::SetOp { left, right, .. } => {
+            collect_required_columns(left, required);
+            collect_required_columns(right, required);
+        }
+
         // -------------------------
         // TERMINALS
         // -------------------------
         LogicalPlan::Scan { .. }
+        | LogicalPlan::WorkTable { .. }
         | LogicalPlan::Insert { .. }
         | LogicalPlan::Update { .. }
         | LogicalPlan::Delete { .. } => {}
@@ -1,18 +1,36 @@
 use std::fmt;
 
-use crate::ir::plan::LogicalPlan;
+use crate::{catalog::ids::ColumnId, ir::plan::LogicalPlan};
 
 pub type PlanResult = Result<LogicalPlan, PlanError>;
 
 #[derive(Debug)]
 pub enum PlanError {
-    InvalidPlan { reason: &'static str },
+    InvalidPlan {
+        reason: &'static str,
+    },
 
-    UnsupportedFeature { feature: &'static str },
+    UnsupportedFeature {
+        feature: &'static str,
+    },
 
-    InvalidPredicate { message: String },
+    InvalidPredicate {
+        message: String,
+    },
 
-    InvalidJoin { message: String },
+    InvalidJoin {
+        message: String,
+    },
+
+    /// A column reference that the operator's input does not provide.
+    UnresolvedColumn {
+        column_id: ColumnId,
+    },
+
+    /// A column reference that the operator's input provides more than once.
+    AmbiguousColumn {
+        column_id: ColumnId,
+    },
 }
 
 impl fmt::Display for PlanError {
@@ -33,6 +51,14 @@ impl fmt::Display for PlanError {
             PlanError::InvalidJoin { message } => {
                 write!(f, "planner error: invalid join ({})", message)
             }
+
+            PlanError::UnresolvedColumn { column_id } => {
+                write!(f, "planner error: column {} is not in scope", column_id.0)
+            }
+
+            PlanError::AmbiguousColumn { column_id } => {
+                write!(f, "planner error: column {} is ambiguous", column_id.0)
+            }
         }
     }
 }
@@ -2,29 +2,36 @@
 //!
 //! Lowers bound statements into frozen logical IR.
 
+use std::cell::Cell;
+
 use crate::binder::bound::BoundExpr;
 use crate::binder::bound::*;
 use crate::catalog::ids::ColumnId;
-use crate::ir::expr::Expr;
-use crate::ir::plan::{JoinType, LogicalPlan, SortKey};
+use crate::ir::expr::{Expr, SubqueryKind};
+use crate::ir::plan::{AggregateExpr, LogicalPlan, SortKey};
 use crate::planner::errors::{PlanError, PlanResult};
 
-pub struct LogicalPlanner;
+pub struct LogicalPlanner {
+    /// Id of the next subquery planned.
+    next_subquery: Cell<usize>,
+}
 
 impl LogicalPlanner {
     pub fn new() -> Self {
-        Self
+        Self {
+            next_subquery: Cell::new(0),
+        }
     }
 
     pub fn plan(&self, stmt: BoundStatement) -> Result<LogicalPlan, PlanError> {
         match stmt {
-            BoundStatement::Select(s) => self.plan_select(s),
+            BoundStatement::Select(s) => self.plan_select(*s),
 
-            BoundStatement::Insert(s) => Ok(self.plan_insert(s)),
+            BoundStatement::Insert(s) => self.plan_insert(s),
 
-            BoundStatement::Update(s) => Ok(self.plan_update(s)),
+            BoundStatement::Update(s) => self.plan_update(s),
 
-            BoundStatement::Delete(s) => Ok(self.plan_delete(s)),
+            BoundStatement::Delete(s) => self.plan_delete(s),
 
             BoundStatement::Explain { stmt, .. } => self.plan(*stmt),
 
@@ -34,11 +41,27 @@ impl LogicalPlanner {
             | BoundStatement::DropIndex(_) => Err(PlanError::InvalidPlan {
                 reason: "DDL must bypass logical planner",
             }),
+
+            BoundStatement::Transaction(_) => Err(PlanError::InvalidPlan {
+                reason: "transaction control must bypass logical planner",
+            }),
         }
     }
 }
 impl LogicalPlanner {
     fn plan_select(&self, stmt: BoundSelect) -> Result<LogicalPlan, PlanError> {
+        let grouping = stmt.is_grouped().then(|| Grouping::of(&stmt));
+        // Equal rows are adjacent once sorted by everything DISTINCT
+        // compares; DISTINCT ON is sorted by its expressions first.
+        let distinct_sorted = match &stmt.distinct {
+            Some(BoundDistinct::Rows) => stmt
+                .projection
+                .iter()
+                .all(|e| stmt.order_by.iter().any(|(key, _)| key == e)),
+            Some(BoundDistinct::On(_)) => !stmt.order_by.is_empty(),
+            None => false,
+        };
+
         // FROM
         let mut plan = self.plan_from(stmt.from)?;
 
@@ -46,7 +69,66 @@ impl LogicalPlanner {
         if let Some(predicate) = stmt.selection {
             plan = LogicalPlan::Filter {
                 input: Box::new(plan),
-                predicate: self.lower_expr(predicate),
+                predicate: self.lower_expr(predicate)?,
+            };
+        }
+
+        // GROUP BY / aggregates
+        // Everything above reads group keys and aggregate results only.
+        if let Some(grouping) = &grouping {
+            plan = LogicalPlan::Aggregate {
+                input: Box::new(plan),
+                group_by: grouping
+                    .keys
+                    .iter()
+                    .map(|e| self.lower_expr(e.clone()))
+                    .collect::<Result<_, _>>()?,
+                aggregates: grouping
+                    .aggregates
+                    .iter()
+                    .map(|e| self.lower_aggregate(e))
+                    .collect::<Result<_, _>>()?,
+            };
+        }
+
+        // HAVING
+        if let Some(predicate) = stmt.having {
+            plan = LogicalPlan::Filter {
+                input: Box::new(plan),
+                predicate: self.lower_output(predicate, grouping.as_ref())?,
+            };
+        }
+
+        // ORDER BY
+        // Sorts below the projection, since keys may use columns that are
+        // not projected.
+        if !stmt.order_by.is_empty() {
+            plan = LogicalPlan::Sort {
+                input: Box::new(plan),
+                keys: stmt
+                    .order_by
+                    .into_iter()
+                    .map(|(e, asc)| {
+                        Ok(SortKey {
+                            expr: self.lower_output(e, grouping.as_ref())?,
+                            asc,
+                        })
+                    })
+                    .collect::<Result<_, PlanError>>()?,
+            };
+        }
+
+        // DISTINCT ON
+        // Compares rows before projection, since its expressions need not
+        // be projected.
+        if let Some(BoundDistinct::On(exprs)) = stmt.distinct.as_ref() {
+            plan = LogicalPlan::Distinct {
+                input: Box::new(plan),
+                on: exprs
+                    .iter()
+                    .map(|e| self.lower_output(e.clone(), grouping.as_ref()))
+                    .collect::<Result<_, _>>()?,
+                sorted: distinct_sorted,
             };
         }
 
@@ -62,22 +144,17 @@ impl LogicalPlanner {
             exprs: stmt
                 .projection
                 .into_iter()
-                .map(|e| self.lower_expr(e))
-                .collect(),
+                .map(|e| self.lower_output(e, grouping.as_ref()))
+                .collect::<Result<_, _>>()?,
+            columns: stmt.output,
         };
 
-        // ORDER BY
-        if !stmt.order_by.is_empty() {
-            plan = LogicalPlan::Sort {
+        // DISTINCT
+        if let Some(BoundDistinct::Rows) = stmt.distinct {
+            plan = LogicalPlan::Distinct {
                 input: Box::new(plan),
-                keys: stmt
-                    .order_by
-                    .into_iter()
-                    .map(|(e, asc)| SortKey {
-                        expr: self.lower_expr(e),
-                        asc,
-                    })
-                    .collect(),
+                on: Vec::new(),
+                sorted: distinct_sorted,
             };
         }
 
@@ -100,89 +177,395 @@ impl LogicalPlanner {
     }
 }
 
+/// Group keys and distinct aggregate calls of a grouped query, in the order
+/// the `Aggregate` node outputs them.
+struct Grouping {
+    keys: Vec<BoundExpr>,
+    aggregates: Vec<BoundExpr>,
+}
+
+impl Grouping {
+    fn of(stmt: &BoundSelect) -> Self {
+        let mut aggregates = Vec::new();
+        let outputs = stmt
+            .projection
+            .iter()
+            .chain(&stmt.having)
+            .chain(stmt.order_by.iter().map(|(e, _)| e))
+            .chain(stmt.distinct.iter().flat_map(BoundDistinct::exprs));
+        for expr in outputs {
+            collect_aggregates(expr, &mut aggregates);
+        }
+
+        Self {
+            keys: stmt.group_by.clone(),
+            aggregates,
+        }
+    }
+}
+
+fn collect_aggregates(expr: &BoundExpr, out: &mut Vec<BoundExpr>) {
+    match expr {
+        BoundExpr::Aggregate { .. } => {
+            if !out.contains(expr) {
+                out.push(expr.clone());
+            }
+        }
+        BoundExpr::Unary { expr, .. } | BoundExpr::Cast { expr, .. } => {
+            collect_aggregates(expr, out)
+        }
+        BoundExpr::Binary { left, right, .. } => {
+            collect_aggregates(left, out);
+            collect_aggregates(right, out);
+        }
+        BoundExpr::Function { args, .. } => {
+            args.iter().for_each(|arg| collect_aggregates(arg, out));
+        }
+        BoundExpr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            operand.iter().for_each(|e| collect_aggregates(e, out));
+            for (when, then) in branches {
+                collect_aggregates(when, out);
+                collect_aggregates(then, out);
+            }
+            else_result.iter().for_each(|e| collect_aggregates(e, out));
+        }
+        BoundExpr::InList { expr, list } => {
+            collect_aggregates(expr, out);
+            list.iter().for_each(|e| collect_aggregates(e, out));
+        }
+        BoundExpr::Like { expr, pattern, .. } => {
+            collect_aggregates(expr, out);
+            collect_aggregates(pattern, out);
+        }
+        BoundExpr::Subquery {
+            kind: BoundSubqueryKind::In(expr),
+            ..
+        } => collect_aggregates(expr, out),
+        BoundExpr::Subquery { .. }
+        | BoundExpr::Column { .. }
+        | BoundExpr::OuterColumn { .. }
+        | BoundExpr::Literal(_)
+        | BoundExpr::Null => {}
+    }
+}
+
 impl LogicalPlanner {
     fn plan_from(&self, from: BoundFrom) -> Result<LogicalPlan, PlanError> {
         match from {
-            BoundFrom::Table { table_id } => Ok(LogicalPlan::Scan { table_id }),
+            BoundFrom::Table { table_id, from } => Ok(LogicalPlan::Scan { table_id, from }),
 
             BoundFrom::Join {
                 left,
                 right,
                 on,
                 join_type,
-            } => {
-                if matches!(join_type, JoinType::Full) {
-                    return Err(PlanError::UnsupportedFeature {
-                        feature: "FULL OUTER JOIN",
-                    });
-                }
+            } => Ok(LogicalPlan::Join {
+                left: Box::new(self.plan_from(*left)?),
+                right: Box::new(self.plan_from(*right)?),
+                on: self.lower_expr(on)?,
+                join_type,
+            }),
 
-                Ok(LogicalPlan::Join {
-                    left: Box::new(self.plan_from(*left)?),
-                    right: Box::new(self.plan_from(*right)?),
-                    on: self.lower_expr(on),
-                    join_type,
+            BoundFrom::Cte { cte, from, columns } => {
+                let anchor = self.plan_select(cte.query)?;
+                let input = match cte.recursive {
+                    Some(term) => LogicalPlan::RecursiveCte {
+                        id: term.id,
+                        anchor: Box::new(anchor),
+                        recursive: Box::new(self.plan_select(term.query)?),
+                        all: term.all,
+                    },
+                    None => anchor,
+                };
+                Ok(LogicalPlan::Derived {
+                    input: Box::new(input),
+                    from,
+                    columns,
                 })
             }
+
+            BoundFrom::SetOp {
+                op,
+                all,
+                left,
+                right,
+                from,
+                columns,
+            } => Ok(LogicalPlan::Derived {
+                input: Box::new(LogicalPlan::SetOp {
+                    op,
+                    all,
+                    left: Box::new(self.plan_select(*left)?),
+                    right: Box::new(self.plan_select(*right)?),
+                }),
+                from,
+                columns,
+            }),
+
+            BoundFrom::WorkTable {
+                id,
+                output,
+                from,
+                columns,
+            } => Ok(LogicalPlan::Derived {
+                input: Box::new(LogicalPlan::WorkTable {
+                    id,
+                    columns: output,
+                }),
+                from,
+                columns,
+            }),
         }
     }
 }
 
 impl LogicalPlanner {
-    fn plan_insert(&self, stmt: BoundInsert) -> LogicalPlan {
-        LogicalPlan::Insert {
+    fn plan_insert(&self, stmt: BoundInsert) -> PlanResult {
+        Ok(LogicalPlan::Insert {
             table_id: stmt.table_id,
             rows: stmt
                 .rows
                 .into_iter()
                 .map(|row| row.into_iter().map(|e| self.lower_expr(e)).collect())
-                .collect(),
-        }
+                .collect::<Result<_, _>>()?,
+        })
     }
 }
 
 impl LogicalPlanner {
-    fn plan_update(&self, stmt: BoundUpdate) -> LogicalPlan {
-        LogicalPlan::Update {
+    fn plan_update(&self, stmt: BoundUpdate) -> PlanResult {
+        Ok(LogicalPlan::Update {
             table_id: stmt.table_id,
             assignments: stmt
                 .assignments
                 .into_iter()
-                .map(|(col_id, expr)| (col_id, self.lower_expr(expr)))
-                .collect(),
-            predicate: stmt.predicate.map(|p| self.lower_expr(p)),
-        }
+                .map(|(col_id, expr)| Ok((col_id, self.lower_expr(expr)?)))
+                .collect::<Result<_, PlanError>>()?,
+            predicate: stmt.predicate.map(|p| self.lower_expr(p)).transpose()?,
+        })
     }
 }
 
 impl LogicalPlanner {
-    fn plan_delete(&self, stmt: BoundDelete) -> LogicalPlan {
-        LogicalPlan::Delete {
+    fn plan_delete(&self, stmt: BoundDelete) -> PlanResult {
+        Ok(LogicalPlan::Delete {
             table_id: stmt.table_id,
-            predicate: stmt.predicate.map(|p| self.lower_expr(p)),
-        }
+            predicate: stmt.predicate.map(|p| self.lower_expr(p)).transpose()?,
+        })
     }
 }
 
 impl LogicalPlanner {
-    fn lower_expr(&self, expr: BoundExpr) -> Expr {
-        match expr {
-            BoundExpr::Column { column_id } => Expr::BoundColumn { column_id },
+    /// Lower an expression evaluated on input rows.
+    fn lower_expr(&self, expr: BoundExpr) -> Result<Expr, PlanError> {
+        Ok(match expr {
+            BoundExpr::Column { from, column_id } => Expr::BoundColumn { from, column_id },
 
             BoundExpr::Literal(v) => Expr::Literal(v),
 
             BoundExpr::Unary { op, expr } => Expr::Unary {
                 op,
-                expr: Box::new(self.lower_expr(*expr)),
+                expr: Box::new(self.lower_expr(*expr)?),
             },
 
             BoundExpr::Binary { left, op, right } => Expr::Binary {
-                left: Box::new(self.lower_expr(*left)),
+                left: Box::new(self.lower_expr(*left)?),
                 op,
-                right: Box::new(self.lower_expr(*right)),
+                right: Box::new(self.lower_expr(*right)?),
             },
 
+            BoundExpr::Cast { expr, to } => Expr::Cast {
+                expr: Box::new(self.lower_expr(*expr)?),
+                to,
+            },
+
+            BoundExpr::Function { func, args } => Expr::Function {
+                func,
+                args: args
+                    .into_iter()
+                    .map(|arg| self.lower_expr(arg))
+                    .collect::<Result<_, _>>()?,
+            },
+
+            BoundExpr::Aggregate { .. } => {
+                return Err(PlanError::InvalidPlan {
+                    reason: "aggregate outside of a grouped query",
+                });
+            }
+
+            BoundExpr::Subquery {
+                kind,
+                select,
+                params,
+            } => self.lower_subquery(kind, *select, params, |e| self.lower_expr(e))?,
+
+            expr @ (BoundExpr::Case { .. } | BoundExpr::InList { .. } | BoundExpr::Like { .. }) => {
+                lower_conditional(expr, |e| self.lower_expr(e))?
+            }
+
+            BoundExpr::OuterColumn { param, .. } => Expr::Parameter { index: param },
+
             BoundExpr::Null => Expr::Null,
+        })
+    }
+
+    /// Plan a subquery. `lower` lowers the expressions evaluated in the
+    /// enclosing query: the values passed in and the left side of IN.
+    fn lower_subquery(
+        &self,
+        kind: BoundSubqueryKind,
+        select: BoundSelect,
+        params: Vec<BoundExpr>,
+        lower: impl Fn(BoundExpr) -> Result<Expr, PlanError>,
+    ) -> Result<Expr, PlanError> {
+        let id = self.next_subquery.get();
+        self.next_subquery.set(id + 1);
+
+        Ok(Expr::Subquery {
+            id,
+            kind: match kind {
+                BoundSubqueryKind::Scalar => SubqueryKind::Scalar,
+                BoundSubqueryKind::Exists => SubqueryKind::Exists,
+                BoundSubqueryKind::In(expr) => SubqueryKind::In(Box::new(lower(*expr)?)),
+            },
+            plan: Box::new(self.plan_select(select)?),
+            params: params.into_iter().map(lower).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Lower an expression evaluated after grouping, if any. Group keys and
+    /// aggregate calls become the slots the `Aggregate` node outputs them in.
+    fn lower_output(
+        &self,
+        expr: BoundExpr,
+        grouping: Option<&Grouping>,
+    ) -> Result<Expr, PlanError> {
+        let Some(grouping) = grouping else {
+            return self.lower_expr(expr);
+        };
+
+        if let Some(index) = grouping.keys.iter().position(|k| *k == expr) {
+            return Ok(Expr::ColumnSlot { index });
         }
+
+        Ok(match expr {
+            BoundExpr::Aggregate { .. } => {
+                let position = grouping.aggregates.iter().position(|a| *a == expr);
+                Expr::ColumnSlot {
+                    index: grouping.keys.len()
+                        + position.expect("aggregates are collected up front"),
+                }
+            }
+
+            BoundExpr::Column { .. } => {
+                return Err(PlanError::InvalidPlan {
+                    reason: "column is neither grouped nor aggregated",
+                });
+            }
+
+            BoundExpr::Unary { op, expr } => Expr::Unary {
+                op,
+                expr: Box::new(self.lower_output(*expr, Some(grouping))?),
+            },
+
+            BoundExpr::Binary { left, op, right } => Expr::Binary {
+                left: Box::new(self.lower_output(*left, Some(grouping))?),
+                op,
+                right: Box::new(self.lower_output(*right, Some(grouping))?),
+            },
+
+            BoundExpr::Cast { expr, to } => Expr::Cast {
+                expr: Box::new(self.lower_output(*expr, Some(grouping))?),
+                to,
+            },
+
+            BoundExpr::Function { func, args } => Expr::Function {
+                func,
+                args: args
+                    .into_iter()
+                    .map(|arg| self.lower_output(arg, Some(grouping)))
+                    .collect::<Result<_, _>>()?,
+            },
+
+            BoundExpr::Subquery {
+                kind,
+                select,
+                params,
+            } => self.lower_subquery(kind, *select, params, |e| {
+                self.lower_output(e, Some(grouping))
+            })?,
+
+            expr @ (BoundExpr::Case { .. } | BoundExpr::InList { .. } | BoundExpr::Like { .. }) => {
+                lower_conditional(expr, |e| self.lower_output(e, Some(grouping)))?
+            }
+
+            BoundExpr::OuterColumn { .. } | BoundExpr::Literal(_) | BoundExpr::Null => {
+                self.lower_expr(expr)?
+            }
+        })
     }
+
+    fn lower_aggregate(&self, expr: &BoundExpr) -> Result<AggregateExpr, PlanError> {
+        let BoundExpr::Aggregate {
+            func,
+            arg,
+            distinct,
+        } = expr
+        else {
+            return Err(PlanError::InvalidPlan {
+                reason: "expected an aggregate call",
+            });
+        };
+
+        Ok(AggregateExpr {
+            func: func.clone(),
+            arg: arg
+                .as_ref()
+                .map(|a| self.lower_expr((**a).clone()))
+                .transpose()?,
+            distinct: *distinct,
+        })
+    }
+}
+
+/// Lower a CASE, IN list or LIKE, whose parts are lowered with `lower`.
+fn lower_conditional(
+    expr: BoundExpr,
+    lower: impl Fn(BoundExpr) -> Result<Expr, PlanError>,
+) -> Result<Expr, PlanError> {
+    let lower_box = |e: Box<BoundExpr>| lower(*e).map(Box::new);
+    Ok(match expr {
+        BoundExpr::Case {
+            operand,
+            branches,
+            else_result,
+        } => Expr::Case {
+            operand: operand.map(lower_box).transpose()?,
+            branches: branches
+                .into_iter()
+                .map(|(when, then)| Ok((lower(when)?, lower(then)?)))
+                .collect::<Result<_, PlanError>>()?,
+            else_result: else_result.map(lower_box).transpose()?,
+        },
+        BoundExpr::InList { expr, list } => Expr::InList {
+            expr: lower_box(expr)?,
+            list: list.into_iter().map(&lower).collect::<Result<_, _>>()?,
+        },
+        BoundExpr::Like {
+            expr,
+            pattern,
+            escape,
+            case_insensitive,
+        } => Expr::Like {
+            expr: lower_box(expr)?,
+            pattern: lower_box(pattern)?,
+            escape,
+            case_insensitive,
+        },
+        _ => unreachable!("not a conditional expression"),
+    })
 }
@@ -0,0 +1,503 @@
+//! Physical lowering.
+//!
+//! Column ids are handed out across the whole catalog, so they say nothing
+//! about where a value sits in a row. This pass works out the layout of the
+//! rows each operator produces and rewrites every `Expr::BoundColumn` into
+//! an `Expr::ColumnSlot` pointing into its operator's input. It runs on the
+//! optimized plan, right before execution.
+
+use crate::catalog::catalog::Catalog;
+use crate::catalog::ids::{ColumnId, TableId};
+use crate::ir::expr::{Expr, SubqueryKind};
+use crate::ir::plan::{AggregateExpr, FromId, JoinSide, LogicalPlan, SortKey};
+use crate::planner::errors::{PlanError, PlanResult};
+
+/// Column at each position of an operator's output rows, with the FROM item
+/// it is read through, so both sides of a self-join have their own.
+/// Computed values have no column id.
+type Layout = Vec<Option<(FromId, ColumnId)>>;
+
+pub fn resolve_columns(plan: LogicalPlan, catalog: &Catalog) -> PlanResult {
+    Ok(resolve_plan(plan, catalog)?.0)
+}
+
+fn resolve_plan(plan: LogicalPlan, catalog: &Catalog) -> Result<(LogicalPlan, Layout), PlanError> {
+    Ok(match plan {
+        LogicalPlan::Scan { table_id, from } => {
+            let layout = table_layout(table_id, from, catalog)?;
+            (LogicalPlan::Scan { table_id, from }, layout)
+        }
+
+        LogicalPlan::IndexScan {
+            table_id,
+            from,
+            index_id,
+            predicate,
+        } => {
+            let layout = table_layout(table_id, from, catalog)?;
+            let plan = LogicalPlan::IndexScan {
+                table_id,
+                from,
+                index_id,
+                predicate,
+            };
+            (plan, layout)
+        }
+
+        LogicalPlan::Filter { input, predicate } => {
+            let (input, layout) = resolve_plan(*input, catalog)?;
+            let plan = LogicalPlan::Filter {
+                input: Box::new(input),
+                predicate: resolve_expr(predicate, &layout, catalog)?,
+            };
+            (plan, layout)
+        }
+
+        LogicalPlan::Project {
+            input,
+            exprs,
+            columns,
+        } => {
+            let (input, input_layout) = resolve_plan(*input, catalog)?;
+            let layout = exprs
+                .iter()
+                .map(|e| match e {
+                    Expr::BoundColumn { from, column_id } => Some((*from, *column_id)),
+                    _ => None,
+                })
+                .collect();
+            let exprs = exprs
+                .into_iter()
+                .map(|e| resolve_expr(e, &input_layout, catalog))
+                .collect::<Result<_, _>>()?;
+            let plan = LogicalPlan::Project {
+                input: Box::new(input),
+                exprs,
+                columns,
+            };
+            (plan, layout)
+        }
+
+        LogicalPlan::Sort { input, keys } => {
+            let (input, layout) = resolve_plan(*input, catalog)?;
+            let keys = keys
+                .into_iter()
+                .map(|k| {
+                    Ok(SortKey {
+                        expr: resolve_expr(k.expr, &layout, catalog)?,
+                        asc: k.asc,
+                    })
+                })
+                .collect::<Result<_, PlanError>>()?;
+            let plan = LogicalPlan::Sort {
+                input: Box::new(input),
+                keys,
+            };
+            (plan, layout)
+        }
+
+        LogicalPlan::Distinct { input, on, sorted } => {
+            let (input, layout) = resolve_plan(*input, catalog)?;
+            let on = on
+                .into_iter()
+                .map(|e| resolve_expr(e, &layout, catalog))
+                .collect::<Result<_, _>>()?;
+            let plan = LogicalPlan::Distinct {
+                input: Box::new(input),
+                on,
+                sorted,
+            };
+            (plan, layout)
+        }
+
+        LogicalPlan::Limit {
+            input,
+            limit,
+            offset,
+        } => {
+            let (input, layout) = resolve_plan(*input, catalog)?;
+            let plan = LogicalPlan::Limit {
+                input: Box::new(input),
+                limit,
+                offset,
+            };
+            (plan, layout)
+        }
+
+        // Joined rows are the left row followed by the right one.
+        LogicalPlan::Join {
+            left,
+            right,
+            on,
+            join_type,
+        } => {
+            let (left, mut layout) = resolve_plan(*left, catalog)?;
+            let (right, right_layout) = resolve_plan(*right, catalog)?;
+            let left_width = layout.len();
+            layout.extend(right_layout);
+            let plan = LogicalPlan::Join {
+                left: Box::new(left),
+                right: Box::new(right),
+                on: resolve_expr(on, &layout, catalog)?,
+                join_type,
+            };
+            if join_type.left_only() {
+                layout.truncate(left_width);
+            }
+            (plan, layout)
+        }
+
+        // Keys are computed on their own side's rows; the residual sees the
+        // joined row.
+        LogicalPlan::HashJoin {
+            left,
+            right,
+            left_keys,
+            right_keys,
+            residual,
+            join_type,
+            build,
+        } => {
+            let (left, left_layout) = resolve_plan(*left, catalog)?;
+            let (right, right_layout) = resolve_plan(*right, catalog)?;
+            let left_keys = left_keys
+                .into_iter()
+                .map(|e| resolve_expr(e, &left_layout, catalog))
+                .collect::<Result<_, _>>()?;
+            let right_keys = right_keys
+                .into_iter()
+                .map(|e| resolve_expr(e, &right_layout, catalog))
+                .collect::<Result<_, _>>()?;
+
+            let left_width = left_layout.len();
+            let mut layout = left_layout;
+            layout.extend(right_layout);
+            let plan = LogicalPlan::HashJoin {
+                left: Box::new(left),
+                right: Box::new(right),
+                left_keys,
+                right_keys,
+                residual: residual
+                    .map(|e| resolve_expr(e, &layout, catalog))
+                    .transpose()?,
+                join_type,
+                build,
+            };
+            if join_type.left_only() {
+                layout.truncate(left_width);
+            }
+            (plan, layout)
+        }
+
+        // The outer key is computed on outer rows; the residual sees the
+        // joined row.
+        LogicalPlan::IndexJoin {
+            outer,
+            inner_table,
+            inner_from,
+            index_id,
+            outer_key,
+            residual,
+            join_type,
+            inner_side,
+        } => {
+            let (outer, outer_layout) = resolve_plan(*outer, catalog)?;
+            let inner_layout = table_layout(inner_table, inner_from, catalog)?;
+            let outer_key = resolve_expr(outer_key, &outer_layout, catalog)?;
+
+            let left_width = match inner_side {
+                JoinSide::Left => inner_layout.len(),
+                JoinSide::Right => outer_layout.len(),
+            };
+            let mut layout = match inner_side {
+                JoinSide::Left => [inner_layout, outer_layout].concat(),
+                JoinSide::Right => [outer_layout, inner_layout].concat(),
+            };
+            let plan = LogicalPlan::IndexJoin {
+                outer: Box::new(outer),
+                inner_table,
+                inner_from,
+                index_id,
+                outer_key,
+                residual: residual
+                    .map(|e| resolve_expr(e, &layout, catalog))
+                    .transpose()?,
+                join_type,
+                inner_side,
+            };
+            if join_type.left_only() {
+                layout.truncate(left_width);
+            }
+            (plan, layout)
+        }
+
+        // Aggregated rows are the group keys followed by the aggregate
+        // values. Expressions above refer to them by slot already.
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => {
+            let (input, input_layout) = resolve_plan(*input, catalog)?;
+            let mut layout: Layout = group_by
+                .iter()
+                .map(|e| match e {
+                    Expr::BoundColumn { from, column_id } => Some((*from, *column_id)),
+                    _ => None,
+                })
+                .collect();
+            layout.extend(aggregates.iter().map(|_| None));
+
+            let group_by = group_by
+                .into_iter()
+                .map(|e| resolve_expr(e, &input_layout, catalog))
+                .collect::<Result<_, _>>()?;
+            let aggregates = aggregates
+                .into_iter()
+                .map(|a| {
+                    Ok(AggregateExpr {
+                        arg: a
+                            .arg
+                            .map(|e| resolve_expr(e, &input_layout, catalog))
+                            .transpose()?,
+                        ..a
+                    })
+                })
+                .collect::<Result<_, PlanError>>()?;
+            let plan = LogicalPlan::Aggregate {
+                input: Box::new(input),
+                group_by,
+                aggregates,
+            };
+            (plan, layout)
+        }
+
+        // Renaming columns moves no values, so the node goes away once its
+        // columns are placed.
+        LogicalPlan::Derived {
+            input,
+            from,
+            columns,
+        } => {
+            let (input, _) = resolve_plan(*input, catalog)?;
+            (
+                input,
+                columns.into_iter().map(|c| Some((from, c))).collect(),
+            )
+        }
+
+        // Each term is resolved on its own; their rows line up by position,
+        // as do those of a set operation's queries.
+        LogicalPlan::RecursiveCte {
+            id,
+            anchor,
+            recursive,
+            all,
+        } => {
+            let (anchor, layout) = resolve_plan(*anchor, catalog)?;
+            let (recursive, _) = resolve_plan(*recursive, catalog)?;
+            let plan = LogicalPlan::RecursiveCte {
+                id,
+                anchor: Box::new(anchor),
+                recursive: Box::new(recursive),
+                all,
+            };
+            (plan, vec![None; layout.len()])
+        }
+
+        LogicalPlan::SetOp {
+            op,
+            all,
+            left,
+            right,
+        } => {
+            let (left, layout) = resolve_plan(*left, catalog)?;
+            let (right, _) = resolve_plan(*right, catalog)?;
+            let plan = LogicalPlan::SetOp {
+                op,
+                all,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+            (plan, vec![None; layout.len()])
+        }
+
+        LogicalPlan::WorkTable { id, columns } => {
+            let layout = vec![None; columns.len()];
+            (LogicalPlan::WorkTable { id, columns }, layout)
+        }
+
+        // VALUES rows are evaluated without an input row.
+        LogicalPlan::Insert { table_id, rows } => {
+            let rows = rows
+                .into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|e| resolve_expr(e, &[], catalog))
+                        .collect()
+                })
+                .collect::<Result<_, _>>()?;
+            (LogicalPlan::Insert { table_id, rows }, Vec::new())
+        }
+
+        // UPDATE and DELETE evaluate against the stored rows of their table.
+        LogicalPlan::Update {
+            table_id,
+            assignments,
+            predicate,
+        } => {
+            let layout = table_layout(table_id, FromId::TARGET, catalog)?;
+            let assignments = assignments
+                .into_iter()
+                .map(|(col, e)| Ok((col, resolve_expr(e, &layout, catalog)?)))
+                .collect::<Result<_, PlanError>>()?;
+            let plan = LogicalPlan::Update {
+                table_id,
+                assignments,
+                predicate: predicate
+                    .map(|p| resolve_expr(p, &layout, catalog))
+                    .transpose()?,
+            };
+            (plan, Vec::new())
+        }
+
+        LogicalPlan::Delete {
+            table_id,
+            predicate,
+        } => {
+            let layout = table_layout(table_id, FromId::TARGET, catalog)?;
+            let plan = LogicalPlan::Delete {
+                table_id,
+                predicate: predicate
+                    .map(|p| resolve_expr(p, &layout, catalog))
+                    .transpose()?,
+            };
+            (plan, Vec::new())
+        }
+    })
+}
+
+fn table_layout(table_id: TableId, from: FromId, catalog: &Catalog) -> Result<Layout, PlanError> {
+    let table = catalog
+        .get_table_by_id(table_id)
+        .ok_or(PlanError::InvalidPlan {
+            reason: "plan refers to an unknown table",
+        })?;
+    Ok(table
+        .schema
+        .columns
+        .iter()
+        .map(|c| Some((from, c.id)))
+        .collect())
+}
+
+fn resolve_expr(
+    expr: Expr,
+    layout: &[Option<(FromId, ColumnId)>],
+    catalog: &Catalog,
+) -> Result<Expr, PlanError> {
+    Ok(match expr {
+        Expr::BoundColumn { from, column_id } => {
+            let mut positions = layout
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| **c == Some((from, column_id)))
+                .map(|(i, _)| i);
+
+            match (positions.next(), positions.next()) {
+                (Some(index), None) => Expr::ColumnSlot { index },
+                (None, _) => return Err(PlanError::UnresolvedColumn { column_id }),
+                (Some(_), Some(_)) => return Err(PlanError::AmbiguousColumn { column_id }),
+            }
+        }
+
+        Expr::Unary { op, expr } => Expr::Unary {
+            op,
+            expr: Box::new(resolve_expr(*expr, layout, catalog)?),
+        },
+
+        Expr::Binary { left, op, right } => Expr::Binary {
+            left: Box::new(resolve_expr(*left, layout, catalog)?),
+            op,
+            right: Box::new(resolve_expr(*right, layout, catalog)?),
+        },
+
+        Expr::Cast { expr, to } => Expr::Cast {
+            expr: Box::new(resolve_expr(*expr, layout, catalog)?),
+            to,
+        },
+
+        Expr::Function { func, args } => Expr::Function {
+            func,
+            args: args
+                .into_iter()
+                .map(|arg| resolve_expr(arg, layout, catalog))
+                .collect::<Result<_, _>>()?,
+        },
+
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            let resolve = |e: Box<Expr>| resolve_expr(*e, layout, catalog).map(Box::new);
+            Expr::Case {
+                operand: operand.map(resolve).transpose()?,
+                branches: branches
+                    .into_iter()
+                    .map(|(when, then)| {
+                        Ok((
+                            resolve_expr(when, layout, catalog)?,
+                            resolve_expr(then, layout, catalog)?,
+                        ))
+                    })
+                    .collect::<Result<_, PlanError>>()?,
+                else_result: else_result.map(resolve).transpose()?,
+            }
+        }
+
+        Expr::InList { expr, list } => Expr::InList {
+            expr: Box::new(resolve_expr(*expr, layout, catalog)?),
+            list: list
+                .into_iter()
+                .map(|e| resolve_expr(e, layout, catalog))
+                .collect::<Result<_, _>>()?,
+        },
+
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            case_insensitive,
+        } => Expr::Like {
+            expr: Box::new(resolve_expr(*expr, layout, catalog)?),
+            pattern: Box::new(resolve_expr(*pattern, layout, catalog)?),
+            escape,
+            case_insensitive,
+        },
+
+        // The subquery is resolved on its own; what is passed into it is
+        // evaluated on this row.
+        Expr::Subquery {
+            id,
+            kind,
+            plan,
+            params,
+        } => Expr::Subquery {
+            id,
+            kind: match kind {
+                SubqueryKind::In(expr) => {
+                    SubqueryKind::In(Box::new(resolve_expr(*expr, layout, catalog)?))
+                }
+                kind => kind,
+            },
+            plan: Box::new(resolve_plan(*plan, catalog)?.0),
+            params: params
+                .into_iter()
+                .map(|e| resolve_expr(e, layout, catalog))
+                .collect::<Result<_, _>>()?,
+        },
+
+        Expr::ColumnSlot { .. } | Expr::Literal(_) | Expr::Null | Expr::Parameter { .. } => expr,
+    })
+}
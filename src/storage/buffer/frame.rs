@@ -1,16 +1,62 @@
-use crate::storage::page::page_id::PageId;
+use std::cell::UnsafeCell;
 
-#[derive(Debug)]
-pub struct BufferFrame {
-    pub page: PageFrame,
-    pub pin_count: usize,
+use crate::{
+    storage::{buffer::latch::Latch, page::page_id::PageId},
+    txn::wal::record::Lsn,
+};
+
+/// One slot of the buffer pool: the page it holds, behind a reader/writer
+/// latch.
+///
+/// The page is only reached while holding the latch, or by the pool itself
+/// while nobody has the frame pinned; only pinners take the latch.
+#[derive(Default)]
+pub(crate) struct BufferFrame {
+    pub(crate) latch: Latch,
+    page: UnsafeCell<PageFrame>,
+}
+
+// SAFETY: every access to `page` goes through the latch or happens under
+// the pool mutex while the frame is unpinned, as described above.
+unsafe impl Sync for BufferFrame {}
+
+impl BufferFrame {
+    pub(crate) fn page_ptr(&self) -> *mut PageFrame {
+        self.page.get()
+    }
 }
 
 pub const PAGE_SIZE: usize = 4096;
 
+/// Every page ends with the LSN of the last log record applied to it.
+pub const PAGE_LSN_SIZE: usize = 8;
+
+/// Bytes available to page layouts, in front of the LSN trailer.
+pub const PAGE_PAYLOAD_SIZE: usize = PAGE_SIZE - PAGE_LSN_SIZE;
+
 #[derive(Debug, Clone)]
 pub struct PageFrame {
     pub id: PageId,
     pub data: [u8; PAGE_SIZE],
-    pub dirty: bool,
+}
+
+impl Default for PageFrame {
+    fn default() -> Self {
+        Self {
+            id: PageId(0),
+            data: [0; PAGE_SIZE],
+        }
+    }
+}
+
+impl PageFrame {
+    pub fn lsn(&self) -> Lsn {
+        Lsn(u64::from_le_bytes(
+            self.data[PAGE_PAYLOAD_SIZE..].try_into().unwrap(),
+        ))
+    }
+
+    pub fn set_lsn(&mut self, lsn: Lsn) {
+        self.data[PAGE_PAYLOAD_SIZE..].copy_from_slice(&lsn.0.to_le_bytes());
+    }
 }
@@ -0,0 +1,101 @@
+//! RAII handles on pinned pages.
+//!
+//! A guard pins its page and holds the frame's latch for as long as it
+//! lives, and gives both back when dropped, on error paths too. The pool
+//! mutex is only taken to pin and unpin, so threads working on different
+//! pages do not wait for each other.
+
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Arc, PoisonError},
+};
+
+use crate::storage::buffer::{
+    frame::{BufferFrame, PageFrame},
+    pool::BufferPoolHandle,
+    replacer::FrameId,
+};
+
+/// Shared access to a pinned page. Any number of readers may hold the same
+/// page at once.
+pub struct ReadPageGuard {
+    bp: BufferPoolHandle,
+    frame_id: FrameId,
+    frame: Arc<BufferFrame>,
+}
+
+impl ReadPageGuard {
+    /// Latch a frame the caller has just pinned.
+    pub(super) fn new(bp: BufferPoolHandle, frame_id: FrameId, frame: Arc<BufferFrame>) -> Self {
+        frame.latch.lock_shared();
+        Self {
+            bp,
+            frame_id,
+            frame,
+        }
+    }
+}
+
+impl Deref for ReadPageGuard {
+    type Target = PageFrame;
+
+    fn deref(&self) -> &PageFrame {
+        // SAFETY: the shared latch is held until drop.
+        unsafe { &*self.frame.page_ptr() }
+    }
+}
+
+impl Drop for ReadPageGuard {
+    fn drop(&mut self) {
+        self.frame.latch.unlock_shared();
+        let mut pool = self.bp.lock().unwrap_or_else(PoisonError::into_inner);
+        pool.release(self.frame_id, false, false);
+    }
+}
+
+/// Exclusive access to a pinned page. The page is marked dirty the first
+/// time it is borrowed mutably.
+pub struct WritePageGuard {
+    bp: BufferPoolHandle,
+    frame_id: FrameId,
+    frame: Arc<BufferFrame>,
+    dirty: bool,
+}
+
+impl WritePageGuard {
+    /// Latch a frame the caller has just pinned for writing.
+    pub(super) fn new(bp: BufferPoolHandle, frame_id: FrameId, frame: Arc<BufferFrame>) -> Self {
+        frame.latch.lock_exclusive();
+        Self {
+            bp,
+            frame_id,
+            frame,
+            dirty: false,
+        }
+    }
+}
+
+impl Deref for WritePageGuard {
+    type Target = PageFrame;
+
+    fn deref(&self) -> &PageFrame {
+        // SAFETY: the exclusive latch is held until drop.
+        unsafe { &*self.frame.page_ptr() }
+    }
+}
+
+impl DerefMut for WritePageGuard {
+    fn deref_mut(&mut self) -> &mut PageFrame {
+        self.dirty = true;
+        // SAFETY: the exclusive latch is held until drop.
+        unsafe { &mut *self.frame.page_ptr() }
+    }
+}
+
+impl Drop for WritePageGuard {
+    fn drop(&mut self) {
+        self.frame.latch.unlock_exclusive();
+        let mut pool = self.bp.lock().unwrap_or_else(PoisonError::into_inner);
+        pool.release(self.frame_id, true, self.dirty);
+    }
+}
@@ -0,0 +1,68 @@
+//! Reader/writer latch guarding a buffer frame.
+//!
+//! Unlike `std::sync::RwLock` it is not tied to a borrow: a page guard
+//! acquires it when it is created and releases it when it is dropped, and
+//! the pool mutex is free in between.
+
+use std::sync::{Condvar, Mutex};
+
+#[derive(Default)]
+struct LatchState {
+    readers: usize,
+    writer: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct Latch {
+    state: Mutex<LatchState>,
+    /// Signalled whenever the latch is released.
+    released: Condvar,
+}
+
+impl Latch {
+    pub(crate) fn lock_shared(&self) {
+        let state = self.state.lock().unwrap();
+        let mut state = self.released.wait_while(state, |s| s.writer).unwrap();
+        state.readers += 1;
+    }
+
+    pub(crate) fn try_lock_shared(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.writer {
+            return false;
+        }
+        state.readers += 1;
+        true
+    }
+
+    pub(crate) fn unlock_shared(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.readers -= 1;
+        if state.readers == 0 {
+            self.released.notify_all();
+        }
+    }
+
+    pub(crate) fn lock_exclusive(&self) {
+        let state = self.state.lock().unwrap();
+        let mut state = self
+            .released
+            .wait_while(state, |s| s.writer || s.readers > 0)
+            .unwrap();
+        state.writer = true;
+    }
+
+    pub(crate) fn try_lock_exclusive(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.writer || state.readers > 0 {
+            return false;
+        }
+        state.writer = true;
+        true
+    }
+
+    pub(crate) fn unlock_exclusive(&self) {
+        self.state.lock().unwrap().writer = false;
+        self.released.notify_all();
+    }
+}
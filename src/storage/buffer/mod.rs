@@ -1,3 +1,5 @@
 pub mod frame;
+pub mod guard;
+mod latch;
 pub mod pool;
 pub mod replacer;
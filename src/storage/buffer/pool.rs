@@ -1,81 +1,359 @@
 use std::{
     collections::HashMap,
-    sync::{Arc, Mutex},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
 };
 
 use crate::storage::{
-    buffer::frame::{BufferFrame, PageFrame},
+    buffer::{
+        frame::{BufferFrame, PageFrame},
+        guard::{ReadPageGuard, WritePageGuard},
+        replacer::{FrameId, LruReplacer, Replacer},
+    },
     errors::{StorageError, StorageResult},
     page::page_id::PageId,
     pagemgr::manager::PageManager,
 };
+use crate::txn::wal::writer::WalHandle;
 
 pub type BufferPoolHandle = Arc<Mutex<BufferPool>>;
 
+/// Number of frames a pool gets unless asked for another size (4 MiB).
+pub const DEFAULT_POOL_SIZE: usize = 1024;
+
+/// A fixed number of page frames shared by everything that reads or writes
+/// pages.
+///
+/// Pages are reached through [`ReadPageGuard`]s and [`WritePageGuard`]s,
+/// which pin the page and latch its frame until they are dropped. The pool
+/// mutex is only held to pin and unpin. A page stays in its frame while it
+/// is pinned; once it is not, the replacer may pick the frame for another
+/// page, and a dirty victim is written back first, forcing the log ahead of
+/// it if needed.
 pub struct BufferPool {
     pub pm: Box<dyn PageManager>,
-    frames: HashMap<PageId, BufferFrame>,
+    capacity: usize,
+    frames: Vec<FrameState>,
+    page_table: HashMap<PageId, FrameId>,
+    /// Frames that hold no page.
+    free_frames: Vec<FrameId>,
+    replacer: Box<dyn Replacer>,
+    wal: Option<WalHandle>,
+    /// Write guards currently alive.
+    writers: usize,
+    /// Signalled when the last write guard goes away.
+    writers_done: Arc<Condvar>,
+}
+
+/// The pool's bookkeeping for one frame.
+struct FrameState {
+    frame: Arc<BufferFrame>,
+    page_id: PageId,
+    pin_count: usize,
+    dirty: bool,
 }
 
 impl BufferPool {
     pub fn new(pm: Box<dyn PageManager>) -> Self {
+        Self::with_replacer(pm, DEFAULT_POOL_SIZE, Box::new(LruReplacer::new()))
+    }
+
+    /// A pool of `capacity` frames that picks victims with `replacer`.
+    pub fn with_replacer(
+        pm: Box<dyn PageManager>,
+        capacity: usize,
+        replacer: Box<dyn Replacer>,
+    ) -> Self {
         Self {
             pm,
-            frames: HashMap::new(),
+            capacity: capacity.max(1),
+            frames: Vec::new(),
+            page_table: HashMap::new(),
+            free_frames: Vec::new(),
+            replacer,
+            wal: None,
+            writers: 0,
+            writers_done: Arc::new(Condvar::new()),
         }
     }
 
-    pub fn fetch_page(&mut self, pid: PageId) -> StorageResult<&mut PageFrame> {
-        if !self.frames.contains_key(&pid) {
-            let pm_page = self.pm.fetch_page(pid)?;
+    /// Pin `pid` and latch it for reading.
+    pub fn fetch_page_read(bp: &BufferPoolHandle, pid: PageId) -> StorageResult<ReadPageGuard> {
+        let (frame_id, frame) = bp.lock().unwrap().pin(pid)?;
+        Ok(ReadPageGuard::new(bp.clone(), frame_id, frame))
+    }
 
-            let page = PageFrame {
-                id: pm_page.id,
-                data: pm_page.data,
-                dirty: false,
-            };
+    /// Pin `pid` and latch it for writing, waiting for its current readers
+    /// and writer to let go.
+    pub fn fetch_page_write(bp: &BufferPoolHandle, pid: PageId) -> StorageResult<WritePageGuard> {
+        let (frame_id, frame) = {
+            let mut pool = bp.lock().unwrap();
+            let pinned = pool.pin(pid)?;
+            pool.writers += 1;
+            pinned
+        };
+        Ok(WritePageGuard::new(bp.clone(), frame_id, frame))
+    }
 
-            self.frames.insert(pid, BufferFrame { page, pin_count: 0 });
-        }
+    /// Allocate a zeroed page and latch it for writing.
+    pub fn new_page(bp: &BufferPoolHandle) -> StorageResult<WritePageGuard> {
+        let (frame_id, frame) = {
+            let mut pool = bp.lock().unwrap();
+            let pid = pool.allocate_page()?;
+            let pinned = pool.pin(pid)?;
+            pool.writers += 1;
+            pinned
+        };
+        Ok(WritePageGuard::new(bp.clone(), frame_id, frame))
+    }
 
-        let frame = self
-            .frames
-            .get_mut(&pid)
-            .ok_or(StorageError::PageNotFound { page_id: pid.0 })?;
+    /// Lock the pool once no write guard is alive. No page can change until
+    /// the lock is released, so whatever is flushed meanwhile matches the
+    /// log. Must not be called while holding a write guard.
+    pub fn lock_quiesced(bp: &BufferPoolHandle) -> MutexGuard<'_, BufferPool> {
+        let pool = bp.lock().unwrap();
+        let writers_done = pool.writers_done.clone();
+        writers_done
+            .wait_while(pool, |pool| pool.writers > 0)
+            .unwrap()
+    }
 
-        frame.pin_count += 1;
-        Ok(&mut frame.page)
+    /// Enforce write-ahead logging: from now on a dirty page is only written
+    /// back once the log is durable up to the page's LSN.
+    pub fn attach_wal(&mut self, wal: WalHandle) {
+        self.wal = Some(wal);
     }
 
-    pub fn unpin_page(&mut self, pid: PageId, dirty: bool) -> StorageResult<()> {
-        let frame = self
-            .frames
-            .get_mut(&pid)
-            .ok_or(StorageError::PageNotFound { page_id: pid.0 })?;
+    pub fn wal(&self) -> Option<WalHandle> {
+        self.wal.clone()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of pages currently held in frames.
+    pub fn resident_pages(&self) -> usize {
+        self.page_table.len()
+    }
 
-        if dirty {
-            frame.page.dirty = true;
+    /// Pin `pid` and borrow it exclusively through the locked pool. For
+    /// callers that keep the pool locked until the matching
+    /// [`BufferPool::unpin_page`]; everyone else uses page guards.
+    pub(crate) fn fetch_page(&mut self, pid: PageId) -> StorageResult<&mut PageFrame> {
+        let (frame_id, frame) = self.pin(pid)?;
+        if !frame.latch.try_lock_exclusive() {
+            self.release(frame_id, false, false);
+            return Err(StorageError::PageLatched { page_id: pid.0 });
         }
+        // SAFETY: the exclusive latch is held until `unpin_page`, and the
+        // borrow of `self` ends before then.
+        Ok(unsafe { &mut *frame.page_ptr() })
+    }
 
-        if frame.pin_count == 0 {
+    /// Undo a [`BufferPool::fetch_page`].
+    pub(crate) fn unpin_page(&mut self, pid: PageId, dirty: bool) -> StorageResult<()> {
+        let frame_id = *self
+            .page_table
+            .get(&pid)
+            .ok_or(StorageError::PageNotFound { page_id: pid.0 })?;
+        if self.frames[frame_id].pin_count == 0 {
             return Err(StorageError::CorruptedPage {
                 page_id: pid.0,
                 reason: "unbalanced unpin".into(),
             });
         }
-        frame.pin_count -= 1;
+        self.frames[frame_id].frame.latch.unlock_exclusive();
+        self.release(frame_id, false, dirty);
         Ok(())
     }
 
+    /// Allocate a new page. It starts out zeroed, resident and unpinned;
+    /// nothing is written until it is flushed or evicted.
+    pub fn allocate_page(&mut self) -> StorageResult<PageId> {
+        let pid = self.pm.allocate_page();
+        let frame_id = match self.take_frame() {
+            Ok(frame_id) => frame_id,
+            Err(e) => {
+                self.pm.deallocate_page(pid);
+                return Err(e);
+            }
+        };
+
+        let frame = self.frames[frame_id].frame.clone();
+        // SAFETY: the frame is unpinned, and only pinners latch frames, so
+        // holding the pool is enough to have it to ourselves.
+        let page = unsafe { &mut *frame.page_ptr() };
+        page.id = pid;
+        page.data.fill(0);
+        let state = &mut self.frames[frame_id];
+        state.page_id = pid;
+        state.dirty = true;
+        self.page_table.insert(pid, frame_id);
+        self.replacer.record_access(frame_id);
+        self.replacer.set_evictable(frame_id, true);
+        Ok(pid)
+    }
+
+    /// Write `pid` back if it is resident and dirty. Fails if the log is
+    /// not yet durable up to the page's LSN.
+    pub fn flush_page(&mut self, pid: PageId) -> StorageResult<()> {
+        let Some(&frame_id) = self.page_table.get(&pid) else {
+            return Ok(());
+        };
+        self.write_back(frame_id, false)
+    }
+
+    /// Write back every dirty page. A page latched for writing is skipped,
+    /// it is dirty again once its guard goes; [`BufferPool::lock_quiesced`]
+    /// rules that out.
     pub fn flush_all(&mut self) -> StorageResult<()> {
-        for frame in self.frames.values_mut() {
-            if frame.page.dirty {
-                let pm_page = self.pm.fetch_page(frame.page.id)?;
-                pm_page.data.copy_from_slice(&frame.page.data);
-                self.pm.flush_page(frame.page.id);
-                frame.page.dirty = false;
+        let frame_ids: Vec<FrameId> = self.page_table.values().copied().collect();
+        for frame_id in frame_ids {
+            self.write_back(frame_id, false)?;
+        }
+        Ok(())
+    }
+
+    /// Drop a page from the pool and hand it back to the page manager's
+    /// free list.
+    pub fn free_page(&mut self, pid: PageId) -> StorageResult<()> {
+        if let Some(&frame_id) = self.page_table.get(&pid) {
+            if self.frames[frame_id].pin_count > 0 {
+                return Err(StorageError::CorruptedPage {
+                    page_id: pid.0,
+                    reason: "cannot free a pinned page".into(),
+                });
             }
+            self.page_table.remove(&pid);
+            self.replacer.remove(frame_id);
+            self.free_frames.push(frame_id);
         }
+        self.pm.deallocate_page(pid);
         Ok(())
     }
+
+    /// Force everything flushed so far to stable storage.
+    pub fn sync(&mut self) -> StorageResult<()> {
+        self.pm.sync()
+    }
+
+    /// Pin `pid`, reading it into a frame if it is not resident.
+    fn pin(&mut self, pid: PageId) -> StorageResult<(FrameId, Arc<BufferFrame>)> {
+        let frame_id = match self.page_table.get(&pid) {
+            Some(&frame_id) => frame_id,
+            None => {
+                let frame_id = self.take_frame()?;
+                let frame = self.frames[frame_id].frame.clone();
+                // SAFETY: as in `allocate_page`, the frame is unpinned.
+                let page = unsafe { &mut *frame.page_ptr() };
+                if let Err(e) = self.pm.read_page(pid, &mut page.data) {
+                    self.free_frames.push(frame_id);
+                    return Err(e);
+                }
+                page.id = pid;
+                let state = &mut self.frames[frame_id];
+                state.page_id = pid;
+                state.dirty = false;
+                self.page_table.insert(pid, frame_id);
+                frame_id
+            }
+        };
+
+        self.replacer.record_access(frame_id);
+        self.replacer.set_evictable(frame_id, false);
+        let state = &mut self.frames[frame_id];
+        state.pin_count += 1;
+        Ok((frame_id, state.frame.clone()))
+    }
+
+    /// Give back a pin taken by `pin`; called by the page guards once they
+    /// have released the latch.
+    pub(super) fn release(&mut self, frame_id: FrameId, writer: bool, dirty: bool) {
+        let state = &mut self.frames[frame_id];
+        state.dirty |= dirty;
+        state.pin_count -= 1;
+        if state.pin_count == 0 {
+            self.replacer.set_evictable(frame_id, true);
+        }
+        if writer {
+            self.writers -= 1;
+            if self.writers == 0 {
+                self.writers_done.notify_all();
+            }
+        }
+    }
+
+    /// Find a frame for a new resident page: a free one, a frame never used
+    /// so far, or the replacer's victim once its page is written back.
+    fn take_frame(&mut self) -> StorageResult<FrameId> {
+        if let Some(frame_id) = self.free_frames.pop() {
+            return Ok(frame_id);
+        }
+        if self.frames.len() < self.capacity {
+            self.frames.push(FrameState {
+                frame: Arc::new(BufferFrame::default()),
+                page_id: PageId(0),
+                pin_count: 0,
+                dirty: false,
+            });
+            return Ok(self.frames.len() - 1);
+        }
+
+        let frame_id = self.replacer.evict().ok_or(StorageError::PoolExhausted {
+            capacity: self.capacity,
+        })?;
+        if let Err(e) = self.write_back(frame_id, true) {
+            // Keep the page resident; it can be picked again later.
+            self.replacer.record_access(frame_id);
+            self.replacer.set_evictable(frame_id, true);
+            return Err(e);
+        }
+        let pid = self.frames[frame_id].page_id;
+        self.page_table.remove(&pid);
+        Ok(frame_id)
+    }
+
+    /// Write the page in `frame_id` to disk if it is dirty and not being
+    /// written. A page must not reach disk ahead of its log records: with
+    /// `force_log` the log is flushed as far as needed, otherwise that is an
+    /// error.
+    fn write_back(&mut self, frame_id: FrameId, force_log: bool) -> StorageResult<()> {
+        let state = &self.frames[frame_id];
+        if !state.dirty {
+            return Ok(());
+        }
+        let frame = state.frame.clone();
+        let pinned = state.pin_count > 0;
+        if pinned && !frame.latch.try_lock_shared() {
+            return Ok(());
+        }
+
+        // SAFETY: the frame is either unpinned or latched shared just above.
+        let page = unsafe { &*frame.page_ptr() };
+        let written = self.write_page_out(page, force_log);
+        if pinned {
+            frame.latch.unlock_shared();
+        }
+        written?;
+        self.frames[frame_id].dirty = false;
+        Ok(())
+    }
+
+    fn write_page_out(&mut self, page: &PageFrame, force_log: bool) -> StorageResult<()> {
+        if let Some(wal) = &self.wal {
+            let mut wal = wal.lock().unwrap();
+            let page_lsn = page.lsn();
+            if page_lsn > wal.durable_lsn() {
+                if !force_log {
+                    return Err(StorageError::WalNotDurable {
+                        page_id: page.id.0,
+                        page_lsn: page_lsn.0,
+                        durable_lsn: wal.durable_lsn().0,
+                    });
+                }
+                wal.flush()?;
+            }
+        }
+        self.pm.write_page(page.id, &page.data)
+    }
 }
@@ -0,0 +1,202 @@
+//! Page replacement policies for the buffer pool.
+//!
+//! The pool tells its replacer about every access to a frame and whether
+//! the frame may currently be evicted (it may once nobody has it pinned).
+//! When the pool needs a frame and has none free, the replacer picks the
+//! victim among the evictable ones.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Index of a frame in the buffer pool.
+pub type FrameId = usize;
+
+pub trait Replacer: Send + Sync {
+    /// Record that `frame` was just accessed.
+    fn record_access(&mut self, frame: FrameId);
+
+    /// Allow or forbid evicting `frame`. Frames start out not evictable.
+    fn set_evictable(&mut self, frame: FrameId, evictable: bool);
+
+    /// Pick an evictable frame to reuse and forget its history. `None` if
+    /// every tracked frame is pinned.
+    fn evict(&mut self) -> Option<FrameId>;
+
+    /// Forget `frame`, e.g. because its page was freed.
+    fn remove(&mut self, frame: FrameId);
+
+    /// Number of evictable frames.
+    fn size(&self) -> usize;
+}
+
+/// Evicts the frame whose last access is the oldest.
+#[derive(Default)]
+pub struct LruReplacer {
+    clock: u64,
+    last_access: HashMap<FrameId, u64>,
+    evictable: HashSet<FrameId>,
+}
+
+impl LruReplacer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Replacer for LruReplacer {
+    fn record_access(&mut self, frame: FrameId) {
+        self.clock += 1;
+        self.last_access.insert(frame, self.clock);
+    }
+
+    fn set_evictable(&mut self, frame: FrameId, evictable: bool) {
+        if evictable {
+            self.evictable.insert(frame);
+        } else {
+            self.evictable.remove(&frame);
+        }
+    }
+
+    fn evict(&mut self) -> Option<FrameId> {
+        let victim = *self
+            .evictable
+            .iter()
+            .min_by_key(|f| self.last_access.get(f).copied().unwrap_or(0))?;
+        self.remove(victim);
+        Some(victim)
+    }
+
+    fn remove(&mut self, frame: FrameId) {
+        self.evictable.remove(&frame);
+        self.last_access.remove(&frame);
+    }
+
+    fn size(&self) -> usize {
+        self.evictable.len()
+    }
+}
+
+/// Second-chance approximation of LRU: a hand sweeps the frames and
+/// evicts the first evictable one whose reference bit is already clear,
+/// clearing the bits it passes.
+#[derive(Default)]
+pub struct ClockReplacer {
+    /// Frames in the order the hand visits them.
+    ring: VecDeque<FrameId>,
+    referenced: HashMap<FrameId, bool>,
+    evictable: HashSet<FrameId>,
+}
+
+impl ClockReplacer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Replacer for ClockReplacer {
+    fn record_access(&mut self, frame: FrameId) {
+        if self.referenced.insert(frame, true).is_none() {
+            self.ring.push_back(frame);
+        }
+    }
+
+    fn set_evictable(&mut self, frame: FrameId, evictable: bool) {
+        // Only frames on the ring can be swept up.
+        if evictable && self.referenced.contains_key(&frame) {
+            self.evictable.insert(frame);
+        } else {
+            self.evictable.remove(&frame);
+        }
+    }
+
+    fn evict(&mut self) -> Option<FrameId> {
+        if self.evictable.is_empty() {
+            return None;
+        }
+        // Two sweeps clear every bit, so the loop ends within them.
+        loop {
+            let frame = self.ring.pop_front()?;
+            let referenced = self.referenced.get_mut(&frame).unwrap();
+            if self.evictable.contains(&frame) && !*referenced {
+                self.remove(frame);
+                return Some(frame);
+            }
+            *referenced = false;
+            self.ring.push_back(frame);
+        }
+    }
+
+    fn remove(&mut self, frame: FrameId) {
+        self.evictable.remove(&frame);
+        if self.referenced.remove(&frame).is_some() {
+            self.ring.retain(|f| *f != frame);
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.evictable.len()
+    }
+}
+
+/// LRU-K: evicts the frame whose k-th most recent access is the oldest, so
+/// a page touched once by a large scan does not push out pages that are
+/// used over and over. Frames with fewer than `k` accesses go first, the
+/// one first accessed longest ago before the others.
+pub struct LruKReplacer {
+    k: usize,
+    clock: u64,
+    /// Up to `k` most recent access times per frame, oldest first.
+    history: HashMap<FrameId, VecDeque<u64>>,
+    evictable: HashSet<FrameId>,
+}
+
+impl LruKReplacer {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k: k.max(1),
+            clock: 0,
+            history: HashMap::new(),
+            evictable: HashSet::new(),
+        }
+    }
+}
+
+impl Replacer for LruKReplacer {
+    fn record_access(&mut self, frame: FrameId) {
+        self.clock += 1;
+        let history = self.history.entry(frame).or_default();
+        history.push_back(self.clock);
+        if history.len() > self.k {
+            history.pop_front();
+        }
+    }
+
+    fn set_evictable(&mut self, frame: FrameId, evictable: bool) {
+        if evictable {
+            self.evictable.insert(frame);
+        } else {
+            self.evictable.remove(&frame);
+        }
+    }
+
+    fn evict(&mut self) -> Option<FrameId> {
+        // Frames with a full history rank after those without one; within
+        // each group the oldest remembered access loses.
+        let victim = *self.evictable.iter().min_by_key(|f| {
+            let history = self.history.get(f);
+            let full = history.is_some_and(|h| h.len() >= self.k);
+            let oldest = history.and_then(|h| h.front().copied()).unwrap_or(0);
+            (full, oldest)
+        })?;
+        self.remove(victim);
+        Some(victim)
+    }
+
+    fn remove(&mut self, frame: FrameId) {
+        self.evictable.remove(&frame);
+        self.history.remove(&frame);
+    }
+
+    fn size(&self) -> usize {
+        self.evictable.len()
+    }
+}
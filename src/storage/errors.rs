@@ -4,21 +4,72 @@ pub type StorageResult<T> = Result<T, StorageError>;
 
 #[derive(Debug)]
 pub enum StorageError {
-    PageNotFound { page_id: u64 },
+    PageNotFound {
+        page_id: u64,
+    },
 
-    InvalidRowId { page_id: u64, slot_id: u16 },
+    InvalidRowId {
+        page_id: u64,
+        slot_id: u16,
+    },
 
-    PageFull { page_id: u64 },
+    PageFull {
+        page_id: u64,
+    },
 
-    CorruptedPage { page_id: u64, reason: String },
+    RowTooLarge {
+        size: usize,
+    },
 
-    IndexCorrupted { page_id: u64, reason: String },
+    MissingHeap {
+        table_id: u32,
+    },
 
-    IndexViolation { index_name: String, reason: String },
+    CorruptedPage {
+        page_id: u64,
+        reason: String,
+    },
 
-    IndexInvariantViolation { reason: String },
+    IndexCorrupted {
+        page_id: u64,
+        reason: String,
+    },
 
-    Io { message: String },
+    IndexViolation {
+        index_name: String,
+        reason: String,
+    },
+
+    IndexInvariantViolation {
+        reason: String,
+    },
+
+    Io {
+        message: String,
+    },
+
+    LogCorrupted {
+        reason: String,
+    },
+
+    /// Every frame of the buffer pool is pinned, so no page can be brought
+    /// in.
+    PoolExhausted {
+        capacity: usize,
+    },
+
+    /// A page is held by a page guard, so it cannot be borrowed through
+    /// the locked pool.
+    PageLatched {
+        page_id: u64,
+    },
+
+    /// A page was about to be written ahead of its log records.
+    WalNotDurable {
+        page_id: u64,
+        page_lsn: u64,
+        durable_lsn: u64,
+    },
 }
 
 impl fmt::Display for StorageError {
@@ -37,6 +88,16 @@ impl fmt::Display for StorageError {
             StorageError::PageFull { page_id } => {
                 write!(f, "storage error: page {} is full", page_id)
             }
+            StorageError::RowTooLarge { size } => {
+                write!(
+                    f,
+                    "storage error: row of {} bytes does not fit in a page",
+                    size
+                )
+            }
+            StorageError::MissingHeap { table_id } => {
+                write!(f, "storage error: table {} has no heap root page", table_id)
+            }
             StorageError::CorruptedPage { page_id, reason } => {
                 write!(f, "storage error: corrupted page {} ({})", page_id, reason)
             }
@@ -50,6 +111,26 @@ impl fmt::Display for StorageError {
             StorageError::Io { message } => {
                 write!(f, "storage IO error: {}", message)
             }
+            StorageError::LogCorrupted { reason } => {
+                write!(f, "storage error: write-ahead log corrupted ({})", reason)
+            }
+            StorageError::PoolExhausted { capacity } => write!(
+                f,
+                "storage error: all {} buffer pool frames are pinned",
+                capacity
+            ),
+            StorageError::PageLatched { page_id } => {
+                write!(f, "storage error: page {} is latched", page_id)
+            }
+            StorageError::WalNotDurable {
+                page_id,
+                page_lsn,
+                durable_lsn,
+            } => write!(
+                f,
+                "storage error: page {} has LSN {} but the log is only durable up to {}",
+                page_id, page_lsn, durable_lsn
+            ),
             StorageError::IndexCorrupted { page_id, reason } => {
                 write!(f, "index corrupted at page {}: {}", page_id, reason)
             }
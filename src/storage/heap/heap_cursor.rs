@@ -1,29 +1,43 @@
-use crate::storage::{
-    errors::{StorageError, StorageResult},
-    heap::heap_table::HeapTable,
-    page::{page_id::PageId, row::StorageRow, row_id::RowId, row_page::RowPage},
+use crate::{
+    storage::{
+        buffer::pool::BufferPool,
+        errors::StorageResult,
+        heap::heap_table::HeapTable,
+        page::{row::StorageRow, row_id::RowId, row_page::RowPage},
+    },
+    txn::snapshot::Snapshot,
 };
 
+/// Rows of a heap table, page by page. A page that cannot be read ends the
+/// scan with its error.
 pub struct HeapCursor<'a> {
     table: &'a HeapTable,
+    /// Without a snapshot the cursor returns the newest version of each row.
+    snapshot: Option<&'a Snapshot>,
     page_idx: usize,
     slot_idx: u16,
+    failed: bool,
 }
 
 impl<'a> HeapCursor<'a> {
-    pub fn new(table: &'a HeapTable) -> Self {
+    pub fn new(table: &'a HeapTable, snapshot: Option<&'a Snapshot>) -> Self {
         Self {
             table,
+            snapshot,
             page_idx: 0,
             slot_idx: 0,
+            failed: false,
         }
     }
 }
 
 impl<'a> Iterator for HeapCursor<'a> {
-    type Item = (RowId, StorageRow);
+    type Item = StorageResult<(RowId, StorageRow)>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
         loop {
             let pages = self.table.pages.lock().unwrap();
             if self.page_idx >= pages.len() {
@@ -33,30 +47,31 @@ impl<'a> Iterator for HeapCursor<'a> {
             let pid = pages[self.page_idx];
             drop(pages);
 
-            let mut bp = self.table.bp.lock().unwrap();
-            let frame = bp.fetch_page(pid).unwrap();
-            let page = match RowPage::from_bytes(pid, &frame.data) {
+            let page = BufferPool::fetch_page_read(&self.table.bp, pid)
+                .and_then(|frame| RowPage::from_bytes(pid, &frame.data));
+            let page = match page {
                 Ok(p) => p,
-                Err(_) => {
-                    self.page_idx += 1;
-                    self.slot_idx = 0;
-                    continue; // skip corrupted page
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
                 }
             };
-            bp.unpin_page(pid, false);
 
             while (self.slot_idx as usize) < page.slots_len() {
                 let slot_id = self.slot_idx;
                 self.slot_idx += 1;
 
-                if let Ok(row) = page.get(slot_id) {
-                    return Some((
-                        RowId {
-                            page_id: pid,
-                            slot_id,
-                        },
-                        row.clone(),
-                    ));
+                let rid = RowId {
+                    page_id: pid,
+                    slot_id,
+                };
+                // A free slot may still hold a row for an older snapshot.
+                let row = match self.snapshot {
+                    Some(snapshot) => page.visible(slot_id, snapshot, self.table.horizon()),
+                    None => page.get(slot_id).ok().cloned(),
+                };
+                if let Some(row) = row {
+                    return Some(Ok((rid, row)));
                 }
             }
 
@@ -1,113 +1,383 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 
 use crate::{
     catalog::ids::TableId,
     storage::{
-        buffer::pool::BufferPoolHandle,
-        errors::StorageResult,
+        buffer::{
+            frame::PAGE_PAYLOAD_SIZE,
+            pool::{BufferPool, BufferPoolHandle},
+        },
+        errors::{StorageError, StorageResult},
         heap::heap_cursor::HeapCursor,
-        page::{page_id::PageId, row::StorageRow, row_id::RowId, row_page::RowPage}, // keep your existing RowPage
+        page::{
+            page_id::PageId,
+            row::StorageRow,
+            row_id::RowId,
+            row_page::{HEADER_SIZE, RowPage, SLOT_SIZE, encoded_row_len},
+            traits::Page,
+        },
+    },
+    txn::{
+        lock_manager::{LockManager, LockMode, LockTarget},
+        snapshot::Snapshot,
+        transaction::TxnId,
+        wal::{record::LogBody, writer::log_record},
     },
     types::value::Value,
 };
 
+/// Maximum number of rows stored per heap page.
+pub const DEFAULT_PAGE_CAPACITY: usize = 100;
+
+/// A table's rows, stored in a singly linked chain of `RowPage`s.
+///
+/// The first page of the chain is the heap's root and is recorded in
+/// `TableMeta::root_page`; every page points at its successor through the
+/// `next` field of its header, so the heap can be rebuilt from the root alone.
+///
+/// Rows carry the transactions that wrote and deleted them, and a row
+/// rewritten by a transaction keeps its old version on the same page (see
+/// `RowPage`), so readers whose snapshot does not see a change find what
+/// they should see. Versions are written and logged with the pages, and so
+/// survive eviction and restarts alike.
+///
+/// A freed slot is only reused once no running transaction can still need
+/// it back: rolling back a delete puts the row into the very slot it came
+/// from. With a lock manager attached, an insert also takes an exclusive
+/// lock on the new row before anyone else can see it.
 pub struct HeapTable {
     pub(crate) pages: Mutex<Vec<PageId>>,
     page_capacity: usize,
     pub(crate) table_id: TableId,
     pub(crate) bp: BufferPoolHandle,
+    /// Every live snapshot sees the changes of transactions older than this;
+    /// see `prune_versions`.
+    horizon: AtomicU64,
+    /// Newest transaction to change each page, for pages that may hold
+    /// changes some snapshot does not see.
+    changed: Mutex<HashMap<PageId, TxnId>>,
+    locks: Option<Arc<LockManager>>,
+    /// Rows whose newest version is not deleted. Counted when the heap is
+    /// opened and kept by `insert` and `delete`.
+    row_count: Arc<AtomicU64>,
 }
 
 impl HeapTable {
-    pub fn new(page_capacity: usize, bp: BufferPoolHandle) -> StorageResult<Self> {
-        let pid;
-        {
-            let mut bp = bp.lock().unwrap();
-            pid = bp.pm.allocate_page();
-
-            let page = RowPage::new(pid, page_capacity);
-            let frame = bp.fetch_page(pid)?;
-            page.write_bytes(&mut frame.data);
-            bp.unpin_page(pid, true)?;
-        }
+    /// Allocate the root page of a new, empty heap.
+    pub fn create(table_id: TableId, bp: BufferPoolHandle) -> StorageResult<Self> {
+        let pid = {
+            let mut frame = BufferPool::new_page(&bp)?;
+            let pid = frame.id;
+            RowPage::new(pid, DEFAULT_PAGE_CAPACITY).write_bytes(&mut frame.data);
+            pid
+        };
 
         Ok(Self {
-            table_id: TableId(0), // Will be set properly when opened
+            table_id,
             pages: Mutex::new(vec![pid]),
+            page_capacity: DEFAULT_PAGE_CAPACITY,
+            bp,
+            horizon: AtomicU64::new(0),
+            changed: Mutex::new(HashMap::new()),
+            locks: None,
+            row_count: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Reopen an existing heap by walking its page chain from `root`.
+    pub fn open(table_id: TableId, root: PageId, bp: BufferPoolHandle) -> StorageResult<Self> {
+        let mut pages = Vec::new();
+        let mut page_capacity = DEFAULT_PAGE_CAPACITY;
+        let mut row_count = 0;
+        let mut next = Some(root);
+        while let Some(pid) = next {
+            if pages.contains(&pid) {
+                return Err(StorageError::CorruptedPage {
+                    page_id: pid.0,
+                    reason: "cycle in heap page chain".into(),
+                });
+            }
+            let page = RowPage::from_bytes(pid, &BufferPool::fetch_page_read(&bp, pid)?.data)?;
+
+            if pid == root {
+                page_capacity = page.capacity();
+            }
+            pages.push(pid);
+            row_count += page.used_slots() as u64;
+            next = page.next_page();
+        }
+
+        Ok(Self {
+            table_id,
+            pages: Mutex::new(pages),
             page_capacity,
             bp,
+            horizon: AtomicU64::new(0),
+            changed: Mutex::new(HashMap::new()),
+            locks: None,
+            row_count: Arc::new(AtomicU64::new(row_count)),
         })
     }
 
-    // ADD THIS METHOD
-    pub fn open(table_id: TableId, bp: BufferPoolHandle) -> StorageResult<Self> {
-        // For now, create a new heap since we don't have persistence yet
-        // In Phase 2, this will load pages from catalog metadata
-        let mut heap = Self::new(100, bp)?;
-        heap.table_id = table_id;
-        Ok(heap)
+    /// Lock rows for the inserting transaction in `locks`.
+    pub fn with_locks(mut self, locks: Arc<LockManager>) -> Self {
+        self.locks = Some(locks);
+        self
     }
-    /// Insert a single physical row.
-    pub fn insert(&self, values: Vec<Value>) -> StorageResult<RowId> {
-        let last_pid = {
-            let pages = self.pages.lock().unwrap();
-            *pages.last().unwrap()
-        };
 
-        // Try last page
-        {
-            let mut bp = self.bp.lock().unwrap();
-            let frame = bp.fetch_page(last_pid)?;
-            let mut page = RowPage::from_bytes(last_pid, &frame.data)?;
-
-            if let Ok(rid) = page.insert(values.clone()) {
-                page.write_bytes(&mut frame.data);
-                bp.unpin_page(last_pid, true);
-                return Ok(rid);
-            }
+    /// Keep the row count in `row_count`, such as the table's catalog entry,
+    /// starting from the rows found on opening.
+    pub fn with_row_count(mut self, row_count: Arc<AtomicU64>) -> Self {
+        row_count.store(self.row_count.load(Ordering::Relaxed), Ordering::Relaxed);
+        self.row_count = row_count;
+        self
+    }
+
+    pub fn table_id(&self) -> TableId {
+        self.table_id
+    }
 
-            bp.unpin_page(last_pid, false);
+    /// First page of the chain; this is what the catalog persists.
+    pub fn root_page(&self) -> PageId {
+        self.pages.lock().unwrap()[0]
+    }
+
+    /// All pages owned by this heap, in chain order.
+    pub fn page_ids(&self) -> Vec<PageId> {
+        self.pages.lock().unwrap().clone()
+    }
+
+    /// Insert a single physical row on behalf of `txn`.
+    pub fn insert(&self, txn: TxnId, values: Vec<Value>) -> StorageResult<RowId> {
+        let size = encoded_row_len(&values);
+        if HEADER_SIZE + SLOT_SIZE + size > PAGE_PAYLOAD_SIZE {
+            return Err(StorageError::RowTooLarge { size });
         }
 
-        // Allocate new page
-        let mut bp = self.bp.lock().unwrap();
-        let pid = bp.pm.allocate_page();
+        // Held for the whole insert so concurrent inserts cannot both
+        // extend the chain from the same tail page.
+        let mut pages = self.pages.lock().unwrap();
+        let last_pid = *pages.last().unwrap();
+        let horizon = self.horizon();
 
-        let mut page = RowPage::new(pid, self.page_capacity);
-        let rid = page.insert(values)?;
+        let wal = self.bp.lock().unwrap().wal();
 
-        let frame = bp.fetch_page(pid)?;
-        page.write_bytes(&mut frame.data);
-        bp.unpin_page(pid, true);
+        // Try last page
+        let mut last = BufferPool::fetch_page_write(&self.bp, last_pid)?;
+        let mut page = RowPage::from_bytes(last_pid, &last.data)?;
+        let usable = |slot_id| {
+            let rid = RowId {
+                page_id: last_pid,
+                slot_id,
+            };
+            self.claim(txn, rid)
+        };
+        if let Ok((rid, version)) = page.insert_where(values.clone(), txn, horizon, usable) {
+            let body = LogBody::HeapInsert {
+                table_id: self.table_id,
+                rid,
+                values,
+                version,
+            };
+            let lsn = log_record(wal.as_ref(), txn, body)?;
+            self.touch(last_pid, txn);
+            page.write_bytes(&mut last.data);
+            last.set_lsn(lsn);
+            self.row_count.fetch_add(1, Ordering::Relaxed);
+            return Ok(rid);
+        }
 
-        self.pages.lock().unwrap().push(pid);
+        // Allocate a new tail page and link it from the old one.
+        let mut new_page = BufferPool::new_page(&self.bp)?;
+        let pid = new_page.id;
+        let body = LogBody::HeapNewPage {
+            table_id: self.table_id,
+            page_id: pid,
+            prev_page: last_pid,
+            capacity: self.page_capacity as u16,
+        };
+        let lsn = log_record(wal.as_ref(), txn, body)?;
+        RowPage::new(pid, self.page_capacity).write_bytes(&mut new_page.data);
+        new_page.set_lsn(lsn);
+        page.set_next_page(Some(pid));
+        page.write_bytes(&mut last.data);
+        last.set_lsn(lsn);
+        drop(last);
+        pages.push(pid);
+
+        let mut page = RowPage::from_bytes(pid, &new_page.data)?;
+        let usable = |slot_id| {
+            let rid = RowId {
+                page_id: pid,
+                slot_id,
+            };
+            self.claim(txn, rid)
+        };
+        let (rid, version) = page.insert_where(values.clone(), txn, horizon, usable)?;
+        let body = LogBody::HeapInsert {
+            table_id: self.table_id,
+            rid,
+            values,
+            version,
+        };
+        let lsn = log_record(wal.as_ref(), txn, body)?;
+        self.touch(pid, txn);
+        page.write_bytes(&mut new_page.data);
+        new_page.set_lsn(lsn);
+        self.row_count.fetch_add(1, Ordering::Relaxed);
         Ok(rid)
     }
 
-    pub fn delete(&self, rid: RowId) -> StorageResult<()> {
-        let mut bp = self.bp.lock().unwrap();
-        let frame = bp.fetch_page(rid.page_id)?;
-        let mut page = RowPage::from_bytes(rid.page_id, &frame.data)?;
+    /// Whether `txn` may put a new row at `rid`.
+    fn claim(&self, txn: TxnId, rid: RowId) -> bool {
+        self.locks
+            .as_ref()
+            .is_none_or(|locks| locks.try_lock(txn, LockTarget::Row(rid), LockMode::Exclusive))
+    }
 
-        let ok = page.delete(rid.slot_id);
+    /// Note that `txn` is about to change page `pid`. Called before the
+    /// change lands, so no reader can see it without the page being listed.
+    fn touch(&self, pid: PageId, txn: TxnId) {
+        if txn != TxnId::NONE {
+            let mut changed = self.changed.lock().unwrap();
+            let newest = changed.entry(pid).or_insert(txn);
+            *newest = (*newest).max(txn);
+        }
+    }
 
+    pub fn delete(&self, txn: TxnId, rid: RowId) -> StorageResult<()> {
+        let wal = self.bp.lock().unwrap().wal();
+        let mut frame = BufferPool::fetch_page_write(&self.bp, rid.page_id)?;
+        let mut page = RowPage::from_bytes(rid.page_id, &frame.data)?;
+        let values = page.get(rid.slot_id)?.values.clone();
+        page.delete(rid.slot_id, txn)?;
+
+        let body = LogBody::HeapDelete {
+            table_id: self.table_id,
+            rid,
+            values,
+        };
+        let lsn = log_record(wal.as_ref(), txn, body)?;
+        self.touch(rid.page_id, txn);
         page.write_bytes(&mut frame.data);
-        bp.unpin_page(rid.page_id, true);
+        frame.set_lsn(lsn);
+        let _ = self
+            .row_count
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
         Ok(())
     }
 
+    /// Replace the row at `rid`. The row is rewritten in place when it still
+    /// fits on its page, otherwise it moves; the returned id is its new
+    /// location either way.
+    pub fn update(&self, txn: TxnId, rid: RowId, values: Vec<Value>) -> StorageResult<RowId> {
+        let in_place = {
+            let wal = self.bp.lock().unwrap().wal();
+            let mut frame = BufferPool::fetch_page_write(&self.bp, rid.page_id)?;
+            let mut page = RowPage::from_bytes(rid.page_id, &frame.data)?;
+            let before = page.get(rid.slot_id)?.values.clone();
+            match page.update(rid.slot_id, values.clone(), txn, self.horizon()) {
+                Ok(version) => {
+                    let body = LogBody::HeapUpdate {
+                        table_id: self.table_id,
+                        rid,
+                        before,
+                        after: values.clone(),
+                        version,
+                    };
+                    let lsn = log_record(wal.as_ref(), txn, body)?;
+                    self.touch(rid.page_id, txn);
+                    page.write_bytes(&mut frame.data);
+                    frame.set_lsn(lsn);
+                    true
+                }
+                Err(StorageError::PageFull { .. }) => false,
+                Err(e) => return Err(e),
+            }
+        };
+
+        if in_place {
+            return Ok(rid);
+        }
+        self.delete(txn, rid)?;
+        self.insert(txn, values)
+    }
+
+    /// Newest version of the row at `rid`, whoever wrote it.
     pub fn fetch(&self, rid: RowId) -> StorageResult<StorageRow> {
-        let mut bp = self.bp.lock().unwrap();
-        let frame = bp.fetch_page(rid.page_id)?;
+        let frame = BufferPool::fetch_page_read(&self.bp, rid.page_id)?;
+        RowPage::from_bytes(rid.page_id, &frame.data)?
+            .get(rid.slot_id)
+            .cloned()
+    }
+
+    /// Version of the row at `rid` that `snapshot` sees, if any.
+    pub fn fetch_at(&self, rid: RowId, snapshot: &Snapshot) -> StorageResult<Option<StorageRow>> {
+        let frame = BufferPool::fetch_page_read(&self.bp, rid.page_id)?;
         let page = RowPage::from_bytes(rid.page_id, &frame.data)?;
+        Ok(page.visible(rid.slot_id, snapshot, self.horizon()))
+    }
+
+    /// Scan the newest version of every row.
+    pub fn scan(&self) -> HeapCursor<'_> {
+        HeapCursor::new(self, None)
+    }
+
+    /// Scan the rows as `snapshot` sees them.
+    pub fn scan_at<'a>(&'a self, snapshot: &'a Snapshot) -> HeapCursor<'a> {
+        HeapCursor::new(self, Some(snapshot))
+    }
 
-        let row = page.get(rid.slot_id)?.clone();
+    /// Slots with changes `snapshot` does not see. An index reflects the
+    /// newest versions only, so these have to be checked separately.
+    pub fn changed_rows(&self, snapshot: &Snapshot) -> StorageResult<Vec<RowId>> {
+        // Every change on a page older than the snapshot's oldest unseen
+        // writer is visible to it.
+        let mut pids: Vec<PageId> = self
+            .changed
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, newest)| **newest >= snapshot.xmin())
+            .map(|(pid, _)| *pid)
+            .collect();
+        pids.sort();
 
-        bp.unpin_page(rid.page_id, false);
-        Ok(row)
+        let horizon = self.horizon();
+        let mut rids = Vec::new();
+        for pid in pids {
+            let frame = BufferPool::fetch_page_read(&self.bp, pid)?;
+            let page = RowPage::from_bytes(pid, &frame.data)?;
+            rids.extend(
+                (0..page.slots_len() as u16)
+                    .filter(|slot_id| page.changed(*slot_id, snapshot, horizon))
+                    .map(|slot_id| RowId {
+                        page_id: pid,
+                        slot_id,
+                    }),
+            );
+        }
+        Ok(rids)
     }
 
-    pub fn scan(&self) -> HeapCursor<'_> {
-        HeapCursor::new(self)
+    pub(crate) fn horizon(&self) -> TxnId {
+        TxnId(self.horizon.load(Ordering::Acquire))
+    }
+
+    /// Note that every current and future snapshot sees the changes of
+    /// transactions older than `horizon`. Their old row versions are no
+    /// longer read, and the slots holding them can be reused.
+    pub fn prune_versions(&self, horizon: TxnId) {
+        self.horizon.fetch_max(horizon.0, Ordering::AcqRel);
+        self.changed
+            .lock()
+            .unwrap()
+            .retain(|_, newest| *newest >= horizon);
     }
 }
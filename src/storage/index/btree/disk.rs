@@ -1,11 +1,27 @@
 use crate::storage::{
-    buffer::{frame::PageFrame, pool::BufferPoolHandle},
+    buffer::{
+        frame::{PAGE_PAYLOAD_SIZE, PageFrame},
+        pool::{BufferPool, BufferPoolHandle},
+    },
     errors::{StorageError, StorageResult},
     index::btree::{key::IndexKey, node::BTreeNode},
     page::{page_id::PageId, row_id::RowId},
 };
+use crate::txn::{
+    transaction::TxnId,
+    wal::{
+        record::LogBody,
+        writer::{WalHandle, log_record},
+    },
+};
+
+/// Tag byte identifying a B+Tree meta page.
+const META_TAG: u8 = 2;
 
 pub struct BPlusTree {
+    /// Page holding the current root pointer. Stable for the life of the
+    /// index, so the catalog can record it once.
+    meta: PageId,
     root: PageId,
     order: usize,
     bp: BufferPoolHandle,
@@ -19,11 +35,12 @@ impl BPlusTree {
             });
         }
 
-        // Allocate root page
-        let root_pid = {
+        // Allocate meta + root pages
+        let (meta_pid, wal) = {
             let mut pool = bp.lock().unwrap();
-            let pid = pool.pm.allocate_page();
-
+            (pool.allocate_page()?, pool.wal())
+        };
+        let root_pid = {
             // Initialize root as empty leaf
             let root = BTreeNode::Leaf {
                 keys: Vec::new(),
@@ -31,23 +48,98 @@ impl BPlusTree {
                 next: None,
             };
 
-            let page = pool.fetch_page(pid)?;
-            Self::serialize_node(&root, page)?;
-            pool.unpin_page(pid, true);
+            let mut page = BufferPool::new_page(&bp)?;
+            Self::serialize_node(&root, &mut page)?;
+            Self::log_image(wal.as_ref(), &mut page)?;
+            page.id
+        };
+
+        let tree = Self {
+            meta: meta_pid,
+            root: root_pid,
+            order,
+            bp,
+        };
+        tree.write_meta()?;
+        Ok(tree)
+    }
+
+    /// Reopen a tree from the meta page returned by [`BPlusTree::meta_page`].
+    pub fn open(meta: PageId, bp: BufferPoolHandle) -> StorageResult<Self> {
+        let (root, order) = {
+            let page = BufferPool::fetch_page_read(&bp, meta)?;
+            let data = &page.data;
+
+            if data[0] != META_TAG {
+                return Err(StorageError::IndexCorrupted {
+                    page_id: meta.0,
+                    reason: "not a B+Tree meta page".into(),
+                });
+            }
 
-            pid
+            let root = u64::from_le_bytes(data[1..9].try_into().unwrap());
+            let order = u32::from_le_bytes(data[9..13].try_into().unwrap());
+            (PageId(root), order as usize)
         };
 
         Ok(Self {
-            root: root_pid,
+            meta,
+            root,
             order,
             bp,
         })
     }
 
+    pub fn meta_page(&self) -> PageId {
+        self.meta
+    }
+
+    /// Every page owned by the tree, including its meta page.
+    pub fn pages(&self) -> StorageResult<Vec<PageId>> {
+        let mut pages = vec![self.meta];
+        let mut stack = vec![self.root];
+        while let Some(pid) = stack.pop() {
+            if let BTreeNode::Internal { children, .. } = self.load_node(pid)? {
+                stack.extend(children);
+            }
+            pages.push(pid);
+        }
+        Ok(pages)
+    }
+
+    fn write_meta(&self) -> StorageResult<()> {
+        let wal = self.bp.lock().unwrap().wal();
+        let mut page = BufferPool::fetch_page_write(&self.bp, self.meta)?;
+        page.data[..PAGE_PAYLOAD_SIZE].fill(0);
+        page.data[0] = META_TAG;
+        page.data[1..9].copy_from_slice(&self.root.0.to_le_bytes());
+        page.data[9..13].copy_from_slice(&(self.order as u32).to_le_bytes());
+        Self::log_image(wal.as_ref(), &mut page)
+    }
+
+    fn set_root(&mut self, root: PageId) -> StorageResult<()> {
+        self.root = root;
+        self.write_meta()
+    }
+
+    /// Log the page's new payload as a redo image and stamp its LSN.
+    ///
+    /// Tree pages are logged physically under `TxnId::NONE`: a split or
+    /// merge rewrites several nodes, and replaying their images is simpler
+    /// than replaying the structure change.
+    fn log_image(wal: Option<&WalHandle>, page: &mut PageFrame) -> StorageResult<()> {
+        let body = LogBody::PageImage {
+            page_id: page.id,
+            data: page.data[..PAGE_PAYLOAD_SIZE].to_vec(),
+        };
+        let lsn = log_record(wal, TxnId::NONE, body)?;
+        page.set_lsn(lsn);
+        Ok(())
+    }
+
     // Serialize node to page
     fn serialize_node(node: &BTreeNode, page: &mut PageFrame) -> StorageResult<()> {
-        let data = &mut page.data;
+        let data = &mut page.data[..PAGE_PAYLOAD_SIZE];
         data.fill(0);
 
         let mut out = Vec::new();
@@ -125,7 +217,6 @@ impl BPlusTree {
                     input = &input[8..];
                     u64::from_le_bytes(raw.try_into().unwrap())
                 };
-                input = &input[8..];
 
                 let next = if next_raw == u64::MAX {
                     None
@@ -229,13 +320,7 @@ impl BPlusTree {
         let mut node_pid = self.root;
 
         loop {
-            let node = {
-                let mut pool = self.bp.lock().unwrap();
-                let page = pool.fetch_page(node_pid)?;
-                let node = Self::deserialize_node(page)?;
-                pool.unpin_page(node_pid, false);
-                node
-            };
+            let node = self.load_node(node_pid)?;
 
             match node {
                 BTreeNode::Leaf { .. } => return Ok(node_pid),
@@ -254,7 +339,7 @@ impl BPlusTree {
         if let Some((sep, new_child)) = self.insert_recursive(self.root, key, rid)? {
             // Root split
             let mut bp = self.bp.lock().unwrap();
-            let new_root = bp.pm.allocate_page();
+            let new_root = bp.allocate_page()?;
             drop(bp);
 
             let root = BTreeNode::Internal {
@@ -263,7 +348,7 @@ impl BPlusTree {
             };
 
             self.write_node(new_root, &root)?;
-            self.root = new_root;
+            self.set_root(new_root)?;
         }
         Ok(())
     }
@@ -287,6 +372,11 @@ impl BPlusTree {
                                 reason: "keys/values length mismatch".into(),
                             });
                         }
+                        // Already present: undo may re-add an entry whose
+                        // removal never reached the tree.
+                        if values[i].contains(&rid) {
+                            return Ok(None);
+                        }
                         values[i].push(rid)
                     }
                     Err(i) => {
@@ -308,7 +398,7 @@ impl BPlusTree {
                 let sep = right_keys[0].clone();
 
                 let mut bp = self.bp.lock().unwrap();
-                let right_id = bp.pm.allocate_page();
+                let right_id = bp.allocate_page()?;
                 drop(bp);
 
                 let old_next = *next;
@@ -358,7 +448,7 @@ impl BPlusTree {
                 keys.pop(); // remove sep
 
                 let mut bp = self.bp.lock().unwrap();
-                let right_id = bp.pm.allocate_page();
+                let right_id = bp.allocate_page()?;
                 drop(bp);
 
                 let right = BTreeNode::Internal {
@@ -377,12 +467,7 @@ impl BPlusTree {
     pub fn get(&self, key: &IndexKey) -> StorageResult<Vec<RowId>> {
         let leaf_pid = self.find_leaf(key)?;
 
-        let mut pool = self.bp.lock().unwrap();
-        let page = pool.fetch_page(leaf_pid)?;
-        let node = Self::deserialize_node(page)?;
-        pool.unpin_page(leaf_pid, false)?;
-
-        match node {
+        match self.load_node(leaf_pid)? {
             BTreeNode::Leaf { keys, values, .. } => match keys.binary_search(key) {
                 Ok(i) => Ok(values[i].clone()),
                 Err(_) => Ok(Vec::new()),
@@ -401,13 +486,7 @@ impl BPlusTree {
         let mut node_pid = self.find_leaf(from)?;
 
         loop {
-            let mut pool = self.bp.lock().unwrap();
-            let page = pool.fetch_page(node_pid)?;
-            let node = Self::deserialize_node(page)?;
-            pool.unpin_page(node_pid, false);
-            drop(pool);
-
-            let (keys, values, next) = match node {
+            let (keys, values, next) = match self.load_node(node_pid)? {
                 BTreeNode::Leaf { keys, values, next } => (keys, values, next),
                 _ => {
                     return Err(StorageError::IndexCorrupted {
@@ -442,7 +521,7 @@ impl BPlusTree {
             let root_node = self.load_node(self.root)?;
             if let BTreeNode::Internal { children, .. } = root_node {
                 if children.len() == 1 {
-                    self.root = children[0];
+                    self.set_root(children[0])?;
                 }
             }
         }
@@ -744,19 +823,15 @@ impl BPlusTree {
     }
 
     fn load_node(&self, pid: PageId) -> StorageResult<BTreeNode> {
-        let mut bp = self.bp.lock().unwrap();
-        let frame = bp.fetch_page(pid)?;
-        let node = Self::deserialize_node(frame)?;
-        bp.unpin_page(pid, false);
-        Ok(node)
+        let page = BufferPool::fetch_page_read(&self.bp, pid)?;
+        Self::deserialize_node(&page)
     }
 
     fn write_node(&self, pid: PageId, node: &BTreeNode) -> StorageResult<()> {
-        let mut bp = self.bp.lock().unwrap();
-        let frame = bp.fetch_page(pid)?;
-        Self::serialize_node(node, frame)?;
-        bp.unpin_page(pid, true);
-        Ok(())
+        let wal = self.bp.lock().unwrap().wal();
+        let mut frame = BufferPool::fetch_page_write(&self.bp, pid)?;
+        Self::serialize_node(node, &mut frame)?;
+        Self::log_image(wal.as_ref(), &mut frame)
     }
 
     pub fn search(&self, key: &IndexKey) -> StorageResult<Vec<RowId>> {
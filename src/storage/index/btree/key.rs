@@ -1,32 +1,101 @@
+use std::cmp::Ordering;
+
 use crate::{
     storage::errors::{StorageError, StorageResult},
     types::value::Value,
+    util::numeric::{cmp_float, cmp_int_float, float_as_int},
 };
 
 /// A total-orderable key usable by B+Tree.
 /// NO NULLs allowed.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// Numbers of every type share one order. A float that is a whole number
+/// is keyed as an integer, so equal numbers have the same key.
+#[derive(Debug, Clone)]
 pub enum IndexKey {
     Int(i64),
     Bool(bool),
     String(String),
+    Float(f64),
+    Date(i32),
+    Timestamp(i64),
+    Blob(Vec<u8>),
+}
+
+impl IndexKey {
+    /// Keys of different kinds order by kind.
+    fn kind(&self) -> u8 {
+        match self {
+            IndexKey::Int(_) | IndexKey::Float(_) => 0,
+            IndexKey::Bool(_) => 1,
+            IndexKey::String(_) => 2,
+            IndexKey::Date(_) => 3,
+            IndexKey::Timestamp(_) => 4,
+            IndexKey::Blob(_) => 5,
+        }
+    }
+}
+
+impl Ord for IndexKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (IndexKey::Int(a), IndexKey::Int(b)) => a.cmp(b),
+            (IndexKey::Int(a), IndexKey::Float(b)) => cmp_int_float(*a, *b),
+            (IndexKey::Float(a), IndexKey::Int(b)) => cmp_int_float(*b, *a).reverse(),
+            (IndexKey::Float(a), IndexKey::Float(b)) => cmp_float(*a, *b),
+            (IndexKey::Bool(a), IndexKey::Bool(b)) => a.cmp(b),
+            (IndexKey::String(a), IndexKey::String(b)) => a.cmp(b),
+            (IndexKey::Date(a), IndexKey::Date(b)) => a.cmp(b),
+            (IndexKey::Timestamp(a), IndexKey::Timestamp(b)) => a.cmp(b),
+            (IndexKey::Blob(a), IndexKey::Blob(b)) => a.cmp(b),
+            (a, b) => a.kind().cmp(&b.kind()),
+        }
+    }
+}
+
+impl PartialOrd for IndexKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for IndexKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
 }
 
+impl Eq for IndexKey {}
+
 impl TryFrom<&Value> for IndexKey {
     type Error = &'static str;
 
     fn try_from(v: &Value) -> Result<Self, Self::Error> {
         match v {
+            Value::Int16(v) => Ok(IndexKey::Int(*v as i64)),
+            Value::Int32(v) => Ok(IndexKey::Int(*v as i64)),
             Value::Int64(v) => Ok(IndexKey::Int(*v)),
+            Value::Float32(v) => Ok(IndexKey::from_float(*v as f64)),
+            Value::Float64(v) => Ok(IndexKey::from_float(*v)),
             Value::Boolean(v) => Ok(IndexKey::Bool(*v)),
             Value::String(v) => Ok(IndexKey::String(v.clone())),
+            Value::Date(v) => Ok(IndexKey::Date(*v)),
+            Value::Timestamp(v) => Ok(IndexKey::Timestamp(*v)),
+            Value::Blob(v) => Ok(IndexKey::Blob(v.clone())),
+            Value::Interval { .. } => Err("intervals cannot be indexed"),
             Value::Null => Err("NULL cannot be indexed"),
-            _ => Err("unsupported value type for index"),
         }
     }
 }
 
 impl IndexKey {
+    fn from_float(v: f64) -> Self {
+        match float_as_int(v) {
+            Some(i) => IndexKey::Int(i),
+            None => IndexKey::Float(v),
+        }
+    }
+
     pub fn serialize(&self, buf: &mut Vec<u8>) {
         match self {
             IndexKey::Int(v) => {
@@ -43,6 +112,23 @@ impl IndexKey {
                 buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
                 buf.extend_from_slice(bytes);
             }
+            IndexKey::Float(v) => {
+                buf.push(3);
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+            IndexKey::Date(v) => {
+                buf.push(4);
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+            IndexKey::Timestamp(v) => {
+                buf.push(5);
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+            IndexKey::Blob(b) => {
+                buf.push(6);
+                buf.extend_from_slice(&(b.len() as u32).to_le_bytes());
+                buf.extend_from_slice(b);
+            }
         }
     }
 
@@ -105,6 +191,64 @@ impl IndexKey {
                 Ok(IndexKey::String(s))
             }
 
+            // -------- Float64 --------
+            3 => {
+                let bytes = input.get(..8).ok_or(StorageError::IndexCorrupted {
+                    page_id,
+                    reason: "unexpected EOF reading f64 IndexKey".into(),
+                })?;
+                *input = &input[8..];
+
+                Ok(IndexKey::Float(f64::from_le_bytes(
+                    bytes.try_into().unwrap(),
+                )))
+            }
+
+            // -------- Date --------
+            4 => {
+                let bytes = input.get(..4).ok_or(StorageError::IndexCorrupted {
+                    page_id,
+                    reason: "unexpected EOF reading date IndexKey".into(),
+                })?;
+                *input = &input[4..];
+
+                Ok(IndexKey::Date(i32::from_le_bytes(
+                    bytes.try_into().unwrap(),
+                )))
+            }
+
+            // -------- Timestamp --------
+            5 => {
+                let bytes = input.get(..8).ok_or(StorageError::IndexCorrupted {
+                    page_id,
+                    reason: "unexpected EOF reading timestamp IndexKey".into(),
+                })?;
+                *input = &input[8..];
+
+                Ok(IndexKey::Timestamp(i64::from_le_bytes(
+                    bytes.try_into().unwrap(),
+                )))
+            }
+
+            // -------- Blob --------
+            6 => {
+                let len_bytes = input.get(..4).ok_or(StorageError::IndexCorrupted {
+                    page_id,
+                    reason: "unexpected EOF reading blob length".into(),
+                })?;
+                let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+
+                *input = &input[4..];
+
+                let bytes = input.get(..len).ok_or(StorageError::IndexCorrupted {
+                    page_id,
+                    reason: "unexpected EOF reading blob bytes".into(),
+                })?;
+                *input = &input[len..];
+
+                Ok(IndexKey::Blob(bytes.to_vec()))
+            }
+
             // -------- Invalid Tag --------
             _ => Err(StorageError::IndexCorrupted {
                 page_id,
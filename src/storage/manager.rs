@@ -1,47 +1,166 @@
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 
 use crate::{
-    catalog::{catalog::Catalog, ids::IndexId, ids::TableId},
+    catalog::{ids::TableId, table::TableMeta},
     storage::{
-        buffer::pool::BufferPoolHandle, errors::StorageResult, heap::heap_table::HeapTable,
-        index::index::Index,
+        buffer::pool::{BufferPool, BufferPoolHandle},
+        errors::{StorageError, StorageResult},
+        heap::heap_table::HeapTable,
+        page::page_id::PageId,
+    },
+    txn::{
+        lock_manager::LockManager,
+        snapshot::Snapshot,
+        transaction::TxnId,
+        wal::{
+            record::{LogBody, Lsn},
+            writer::log_record,
+        },
     },
 };
 
+/// Owns the buffer pool and the set of open heaps.
+///
+/// Heaps are opened from `TableMeta::root_page` the first time a statement
+/// touches them and stay cached for the lifetime of the database, so every
+/// statement sees the same page list.
+///
+/// It also hands out read snapshots and keeps track of the live ones, which
+/// decide how long the heaps must keep old row versions, and owns the lock
+/// manager shared by every connection.
 pub struct StorageManager {
-    catalog: Arc<Catalog>,
     buffer_pool: BufferPoolHandle,
-    heaps: HashMap<TableId, Arc<HeapTable>>,
-    indexes: HashMap<IndexId, Arc<Mutex<dyn Index>>>,
+    heaps: Mutex<HashMap<TableId, Arc<HeapTable>>>,
+    snapshots: Mutex<Vec<Weak<Snapshot>>>,
+    locks: Arc<LockManager>,
 }
 
 impl StorageManager {
-    pub fn new(catalog: Arc<Catalog>, buffer_pool: BufferPoolHandle) -> Self {
+    pub fn new(buffer_pool: BufferPoolHandle) -> Self {
         Self {
-            catalog,
             buffer_pool,
-            heaps: HashMap::new(),
-            indexes: HashMap::new(),
+            heaps: Mutex::new(HashMap::new()),
+            snapshots: Mutex::new(Vec::new()),
+            locks: Arc::new(LockManager::new()),
         }
     }
 
-    pub fn get_table(&mut self, id: TableId) -> StorageResult<Arc<HeapTable>> {
-        if !self.heaps.contains_key(&id) {
-            let heap = HeapTable::open(id, self.buffer_pool.clone())?;
-            self.heaps.insert(id, Arc::new(heap));
+    pub fn buffer_pool(&self) -> &BufferPoolHandle {
+        &self.buffer_pool
+    }
+
+    pub fn locks(&self) -> &LockManager {
+        &self.locks
+    }
+
+    /// Return the cached heap for `table`, opening it from its root page on
+    /// first use.
+    pub fn get_table(&self, table: &TableMeta) -> StorageResult<Arc<HeapTable>> {
+        let mut heaps = self.heaps.lock().unwrap();
+        if let Some(heap) = heaps.get(&table.id) {
+            return Ok(heap.clone());
         }
-        Ok(self.heaps.get(&id).unwrap().clone())
+
+        let root = table.root_page.ok_or(StorageError::MissingHeap {
+            table_id: table.id.0,
+        })?;
+        let heap = HeapTable::open(table.id, root, self.buffer_pool.clone())?
+            .with_locks(self.locks.clone())
+            .with_row_count(table.row_count.clone());
+        let heap = Arc::new(heap);
+        heaps.insert(table.id, heap.clone());
+        Ok(heap)
     }
 
-    pub fn get_index(&mut self, id: IndexId) -> StorageResult<Arc<Mutex<dyn Index>>> {
-        if !self.indexes.contains_key(&id) {
-            // Load index from catalog
-            if let Some(idx_entry) = self.catalog.get_index_by_id(id) {
-                self.indexes.insert(id, idx_entry.index.clone());
-            }
+    /// Evict `table`'s heap from the cache and return all of its pages. They
+    /// are left for the caller to free once no persisted catalog refers to
+    /// the table.
+    pub fn drop_table(&self, table: &TableMeta) -> StorageResult<Vec<PageId>> {
+        let heap = match self.heaps.lock().unwrap().remove(&table.id) {
+            Some(heap) => heap,
+            None => match table.root_page {
+                Some(root) => Arc::new(HeapTable::open(table.id, root, self.buffer_pool.clone())?),
+                None => return Ok(Vec::new()),
+            },
+        };
+        Ok(heap.page_ids())
+    }
+
+    /// Return `pages` to the free list for reuse.
+    pub fn free_pages(&self, pages: &[PageId]) -> StorageResult<()> {
+        let mut bp = self.buffer_pool.lock().unwrap();
+        for &pid in pages {
+            bp.free_page(pid)?;
         }
-        Ok(self.indexes.get(&id).unwrap().clone())
+        Ok(())
     }
-}
 
+    /// Take a snapshot for `txn`. It stays registered, holding back version
+    /// pruning, until the last reference to it is dropped. Without a log
+    /// every change is visible.
+    pub fn snapshot(&self, txn: TxnId) -> Arc<Snapshot> {
+        let wal = self.buffer_pool.lock().unwrap().wal();
+        let Some(wal) = wal else {
+            return Arc::new(Snapshot::latest());
+        };
+
+        let snapshot = Arc::new(wal.lock().unwrap().snapshot(txn));
+        let mut live = self.snapshots.lock().unwrap();
+        live.retain(|s| s.strong_count() > 0);
+        live.push(Arc::downgrade(&snapshot));
+        snapshot
+    }
+
+    /// Tell every heap which transactions all running transactions and live
+    /// snapshots see, so row versions older than that can be reclaimed.
+    pub fn prune_versions(&self) {
+        let wal = self.buffer_pool.lock().unwrap().wal();
+        let Some(wal) = wal else {
+            return;
+        };
+
+        let mut horizon = wal.lock().unwrap().oldest_active();
+        self.snapshots
+            .lock()
+            .unwrap()
+            .retain(|s| match s.upgrade() {
+                Some(s) => {
+                    horizon = horizon.min(s.xmin());
+                    true
+                }
+                None => false,
+            });
+
+        for heap in self.heaps.lock().unwrap().values() {
+            heap.prune_versions(horizon);
+        }
+    }
+
+    /// Log `body` for `txn`, if a write-ahead log is attached.
+    pub fn log(&self, txn: TxnId, body: LogBody) -> StorageResult<Lsn> {
+        let wal = self.buffer_pool.lock().unwrap().wal();
+        log_record(wal.as_ref(), txn, body)
+    }
+
+    /// Force the write-ahead log, if one is attached.
+    pub fn flush_log(&self) -> StorageResult<()> {
+        let wal = self.buffer_pool.lock().unwrap().wal();
+        match wal {
+            Some(wal) => wal.lock().unwrap().flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Write every dirty page back to disk and fsync. The log is forced
+    /// first so no page overtakes its log records; the pool stays locked
+    /// with no page latched for writing, so no page can change in between.
+    pub fn flush(&self) -> StorageResult<()> {
+        let mut bp = BufferPool::lock_quiesced(&self.buffer_pool);
+        if let Some(wal) = bp.wal() {
+            wal.lock().unwrap().flush()?;
+        }
+        bp.flush_all()?;
+        bp.sync()
+    }
+}
@@ -1,26 +1,84 @@
-use crate::storage::buffer::frame::PAGE_SIZE;
+use crate::storage::buffer::frame::{PAGE_PAYLOAD_SIZE, PAGE_SIZE};
 use crate::storage::errors::{StorageError, StorageResult};
 use crate::storage::page::row_id::RowId;
+use crate::txn::snapshot::Snapshot;
+use crate::txn::transaction::TxnId;
 use crate::types::value::Value;
 
 use super::row::StorageRow;
 use super::{page_id::PageId, traits::Page};
 
-pub const HEADER_SIZE: usize = 8;
+/// `[slot_count u16][row_count u16][capacity u16][reserved u16][next u64]`
+pub const HEADER_SIZE: usize = 16;
 pub const SLOT_SIZE: usize = 4;
+/// `[xmin u64][xmax u64][prev u16]`, stored in front of every row.
+pub const ROW_HEADER_SIZE: usize = 18;
+
+/// Sentinel stored in the header when a page is the last one in its heap.
+const NO_NEXT_PAGE: u64 = u64::MAX;
+/// Sentinel stored in a row header when no older version is kept.
+const NO_PREV_SLOT: u16 = u16::MAX;
+
+/// What a slot holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotState {
+    /// No row. A deleted row stays readable for snapshots that do not see
+    /// its deletion.
+    Free,
+    /// The newest version of a row.
+    Used,
+    /// An older version of a row, kept for snapshots that do not see the
+    /// change that replaced it.
+    Version,
+}
 
 #[derive(Debug, Clone)]
 pub struct Slot {
     pub offset: u32,
-    pub used: bool,
+    pub state: SlotState,
+}
+
+/// Which transactions a row version belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowHeader {
+    /// Transaction that wrote this version.
+    pub xmin: TxnId,
+    /// Transaction that deleted the row, on a freed slot. `NONE` if the row
+    /// is gone for everyone, or was never deleted.
+    pub xmax: TxnId,
+    /// Slot holding the version this one replaced, if it is still kept.
+    pub prev: Option<u16>,
 }
 
+impl RowHeader {
+    fn new(xmin: TxnId) -> Self {
+        Self {
+            xmin,
+            xmax: TxnId::NONE,
+            prev: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Tuple {
+    header: RowHeader,
+    row: StorageRow,
+}
+
+/// A heap page: slots pointing at rows, each stored with a version header.
+///
+/// A row's slot holds its newest version. Rewriting it in place copies the
+/// old version into a slot of its own, linked from the new one's header, so
+/// every version a snapshot may still read stays on the page. Such slots,
+/// and those of deleted rows, are reused once every snapshot sees past them.
 pub struct RowPage {
     id: PageId,
     slots: Vec<Slot>,
-    rows: Vec<StorageRow>,
+    rows: Vec<Tuple>,
     free_slots: Vec<u16>,
     capacity: usize,
+    next: Option<PageId>,
 }
 
 impl RowPage {
@@ -31,25 +89,38 @@ impl RowPage {
             rows: Vec::with_capacity(capacity),
             free_slots: Vec::new(),
             capacity,
+            next: None,
         }
     }
 
+    /// Next page in the owning heap's page chain.
+    pub fn next_page(&self) -> Option<PageId> {
+        self.next
+    }
+
+    pub fn set_next_page(&mut self, next: Option<PageId>) {
+        self.next = next;
+    }
+
     pub fn get(&self, slot_id: u16) -> StorageResult<&StorageRow> {
+        Ok(&self.tuple(slot_id, SlotState::Used)?.row)
+    }
+
+    /// Version header of the row in a used slot.
+    pub fn header(&self, slot_id: u16) -> StorageResult<RowHeader> {
+        Ok(self.tuple(slot_id, SlotState::Used)?.header)
+    }
+
+    fn tuple(&self, slot_id: u16, state: SlotState) -> StorageResult<&Tuple> {
         let slot = self
             .slots
             .get(slot_id as usize)
+            .filter(|s| s.state == state)
             .ok_or(StorageError::InvalidRowId {
                 page_id: self.id.0,
                 slot_id,
             })?;
 
-        if !slot.used {
-            return Err(StorageError::InvalidRowId {
-                page_id: self.id.0,
-                slot_id,
-            });
-        }
-
         self.rows
             .get(slot.offset as usize)
             .ok_or(StorageError::CorruptedPage {
@@ -58,11 +129,60 @@ impl RowPage {
             })
     }
 
+    fn tuple_mut(&mut self, slot_id: u16) -> &mut Tuple {
+        &mut self.rows[self.slots[slot_id as usize].offset as usize]
+    }
+
+    /// Version of the row at `slot_id` that `snapshot` sees, if any. Changes
+    /// by transactions older than `horizon` are seen by every snapshot still
+    /// in use, and so by this one too.
+    pub fn visible(&self, slot_id: u16, snapshot: &Snapshot, horizon: TxnId) -> Option<StorageRow> {
+        let sees = |txn: TxnId| txn < horizon || snapshot.sees(txn);
+        let slot = self.slots.get(slot_id as usize)?;
+        let head = self.rows.get(slot.offset as usize)?;
+        match slot.state {
+            SlotState::Used => {}
+            SlotState::Free if head.header.xmax != TxnId::NONE && !sees(head.header.xmax) => {}
+            SlotState::Free | SlotState::Version => return None,
+        }
+
+        // Each step goes back one change; a chain longer than the page is
+        // corrupt.
+        let mut tuple = head;
+        for _ in 0..self.slots.len() {
+            if sees(tuple.header.xmin) {
+                return Some(tuple.row.clone());
+            }
+            let prev = self.slots.get(tuple.header.prev? as usize)?;
+            tuple = self.rows.get(prev.offset as usize)?;
+        }
+        None
+    }
+
+    /// Whether the row at `slot_id` has changes `snapshot` does not see,
+    /// counting those older than `horizon` as seen.
+    pub fn changed(&self, slot_id: u16, snapshot: &Snapshot, horizon: TxnId) -> bool {
+        let unseen = |txn: TxnId| txn >= horizon && !snapshot.sees(txn);
+        let Some(slot) = self.slots.get(slot_id as usize) else {
+            return false;
+        };
+        let header = self.slot_header(slot_id);
+        match slot.state {
+            SlotState::Used => unseen(header.xmin),
+            SlotState::Free => header.xmax != TxnId::NONE && unseen(header.xmax),
+            SlotState::Version => false,
+        }
+    }
+
     pub fn from_bytes(id: PageId, buf: &[u8]) -> StorageResult<Self> {
         // ---- header ----
         let slot_count = u16::from_le_bytes([buf[0], buf[1]]) as usize;
         let row_count = u16::from_le_bytes([buf[2], buf[3]]) as usize;
         let capacity = u16::from_le_bytes([buf[4], buf[5]]) as usize;
+        let next = match u64::from_le_bytes(buf[8..16].try_into().unwrap()) {
+            NO_NEXT_PAGE => None,
+            pid => Some(PageId(pid)),
+        };
 
         // ---- slots ----
         let mut slots = Vec::with_capacity(slot_count);
@@ -70,11 +190,21 @@ impl RowPage {
 
         for _ in 0..slot_count {
             let off = u16::from_le_bytes([buf[offset], buf[offset + 1]]);
-            let used = buf[offset + 2] != 0;
+            let state = match buf[offset + 2] {
+                0 => SlotState::Free,
+                1 => SlotState::Used,
+                2 => SlotState::Version,
+                tag => {
+                    return Err(StorageError::CorruptedPage {
+                        page_id: id.0,
+                        reason: format!("unknown slot state {}", tag),
+                    });
+                }
+            };
 
             slots.push(Slot {
                 offset: off as u32,
-                used,
+                state,
             });
 
             offset += SLOT_SIZE;
@@ -85,6 +215,17 @@ impl RowPage {
         let mut row_ptr = HEADER_SIZE + SLOT_SIZE * slot_count;
 
         for _ in 0..row_count {
+            let u64_at = |at: usize| u64::from_le_bytes(buf[at..at + 8].try_into().unwrap());
+            let header = RowHeader {
+                xmin: TxnId(u64_at(row_ptr)),
+                xmax: TxnId(u64_at(row_ptr + 8)),
+                prev: match u16::from_le_bytes([buf[row_ptr + 16], buf[row_ptr + 17]]) {
+                    NO_PREV_SLOT => None,
+                    slot => Some(slot),
+                },
+            };
+            row_ptr += ROW_HEADER_SIZE;
+
             let val_count = u16::from_le_bytes([buf[row_ptr], buf[row_ptr + 1]]) as usize;
             row_ptr += 2;
 
@@ -99,14 +240,17 @@ impl RowPage {
             let consumed = buf[row_ptr..].len() - slice.len();
             row_ptr += consumed;
 
-            rows.push(StorageRow { values });
+            rows.push(Tuple {
+                header,
+                row: StorageRow { values },
+            });
         }
 
         // ---- free slots ----
         let free_slots = slots
             .iter()
             .enumerate()
-            .filter_map(|(i, s)| if !s.used { Some(i as u16) } else { None })
+            .filter_map(|(i, s)| (s.state == SlotState::Free).then_some(i as u16))
             .collect();
 
         Ok(Self {
@@ -115,6 +259,7 @@ impl RowPage {
             rows,
             free_slots,
             capacity,
+            next,
         })
     }
 
@@ -122,58 +267,336 @@ impl RowPage {
         self.slots.len()
     }
 
+    /// Number of slots holding the newest version of a row.
+    pub fn used_slots(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|s| s.state == SlotState::Used)
+            .count()
+    }
+
     pub fn insert(&mut self, values: Vec<Value>) -> StorageResult<RowId> {
+        self.insert_where(values, TxnId::NONE, TxnId::NONE, |_| true)
+            .map(|(rid, _)| rid)
+    }
+
+    /// Insert a row written by `xmin` into the most recently freed slot that
+    /// `usable` accepts, or else into a new slot if `usable` accepts that
+    /// instead. Fails with `PageFull` if no slot qualifies and the row fits.
+    ///
+    /// A slot is only reused once every snapshot from `horizon` on is done
+    /// with what it holds, or if `xmin` freed it itself; the row deleted
+    /// from it is then kept as a version, in the slot that is returned.
+    pub fn insert_where(
+        &mut self,
+        values: Vec<Value>,
+        xmin: TxnId,
+        horizon: TxnId,
+        mut usable: impl FnMut(u16) -> bool,
+    ) -> StorageResult<(RowId, Option<u16>)> {
         if self.num_rows() == self.capacity {
             return Err(StorageError::PageFull { page_id: self.id.0 });
         }
-        let slot_id = if let Some(free) = self.free_slots.pop() {
-            let slot = &mut self.slots[free as usize];
-            slot.used = true;
-            slot.offset = self.rows.len() as u32;
-            free
-        } else {
-            let slot_id = self.slots.len() as u16;
+
+        let row_len = encoded_row_len(&values);
+        let candidates: Vec<u16> = self
+            .free_slots
+            .iter()
+            .rev()
+            .copied()
+            .chain(self.version_slots())
+            .chain([self.slots.len() as u16])
+            .collect();
+
+        for slot_id in candidates {
+            let mut writes = vec![(slot_id, row_len)];
+            let version = match self.slots.get(slot_id as usize) {
+                Some(_) if self.reclaimable(slot_id, horizon) => None,
+                Some(_) if xmin != TxnId::NONE && self.slot_header(slot_id).xmax == xmin => {
+                    let Some(version) = self.version_slot(horizon, slot_id) else {
+                        continue;
+                    };
+                    writes.push((version, tuple_len(self.slot_tuple(slot_id))));
+                    Some(version)
+                }
+                Some(_) => continue,
+                None => None,
+            };
+            if !self.fits(&writes) || !usable(slot_id) {
+                continue;
+            }
+
+            self.insert_at(slot_id, values, xmin, version)?;
+            let rid = RowId {
+                page_id: self.id,
+                slot_id,
+            };
+            return Ok((rid, version));
+        }
+        Err(StorageError::PageFull { page_id: self.id.0 })
+    }
+
+    fn version_slots(&self) -> impl Iterator<Item = u16> + use<'_> {
+        (0..self.slots.len() as u16).filter(|s| self.slots[*s as usize].state == SlotState::Version)
+    }
+
+    /// Slot to keep an old version of the row in `slot_id` in: a free or
+    /// version slot no snapshot from `horizon` on reads, or else a new one.
+    fn version_slot(&self, horizon: TxnId, slot_id: u16) -> Option<u16> {
+        self.free_slots
+            .iter()
+            .rev()
+            .copied()
+            .chain(self.version_slots())
+            .find(|s| *s != slot_id && self.reclaimable(*s, horizon))
+            .or_else(|| (self.slots.len() < self.capacity).then_some(self.slots.len() as u16))
+    }
+
+    /// Whether the page still fits once each `(slot, row length)` of
+    /// `writes` is written; a slot past the end is a new one.
+    fn fits(&self, writes: &[(u16, usize)]) -> bool {
+        let mut len = self.encoded_len();
+        let mut slots = self.slots.len();
+        for &(slot_id, row_len) in writes {
+            if (slot_id as usize) < self.slots.len() {
+                len = len + row_len - tuple_len(self.slot_tuple(slot_id));
+            } else {
+                slots += 1;
+                len += SLOT_SIZE + row_len;
+            }
+        }
+        slots <= self.capacity && len <= PAGE_PAYLOAD_SIZE
+    }
+
+    /// Whether every snapshot from `horizon` on is done with what the free
+    /// or version slot `slot_id` holds.
+    fn reclaimable(&self, slot_id: u16, horizon: TxnId) -> bool {
+        let seen_by_all = |txn: TxnId| txn == TxnId::NONE || txn < horizon;
+        match self.slots[slot_id as usize].state {
+            SlotState::Used => false,
+            SlotState::Free => seen_by_all(self.slot_header(slot_id).xmax),
+            // Once everyone sees the version that replaced it, no one reads
+            // this one.
+            SlotState::Version => self
+                .referrer(slot_id)
+                .is_none_or(|r| seen_by_all(self.slot_header(r).xmin)),
+        }
+    }
+
+    /// Slot whose row header links to the version in `slot_id`.
+    fn referrer(&self, slot_id: u16) -> Option<u16> {
+        (0..self.slots.len() as u16).find(|s| self.slot_header(*s).prev == Some(slot_id))
+    }
+
+    fn slot_tuple(&self, slot_id: u16) -> &Tuple {
+        &self.rows[self.slots[slot_id as usize].offset as usize]
+    }
+
+    fn slot_header(&self, slot_id: u16) -> &RowHeader {
+        &self.slot_tuple(slot_id).header
+    }
+
+    /// Put a row written by `xmin` into a specific slot, which must be free,
+    /// hold a version, or be the next new one, first moving the row deleted
+    /// from it to `version` if given. Replaying or undoing a logged insert
+    /// has to land exactly where the original did; the row is known to have
+    /// fit there before.
+    pub fn insert_at(
+        &mut self,
+        slot_id: u16,
+        values: Vec<Value>,
+        xmin: TxnId,
+        version: Option<u16>,
+    ) -> StorageResult<()> {
+        let mut header = RowHeader::new(xmin);
+        if let Some(version) = version {
+            self.tuple(slot_id, SlotState::Free)?;
+            self.keep_version(slot_id, version)?;
+            header.prev = Some(version);
+        }
+        let tuple = Tuple {
+            header,
+            row: StorageRow { values },
+        };
+        self.place(slot_id, tuple, SlotState::Used)
+    }
+
+    /// Fill `slot_id`, free, holding a version or the next new one, with
+    /// `tuple`. A version it held is unlinked from the row it belonged to.
+    fn place(&mut self, slot_id: u16, tuple: Tuple, state: SlotState) -> StorageResult<()> {
+        let idx = slot_id as usize;
+        if idx == self.slots.len() {
             self.slots.push(Slot {
                 offset: self.rows.len() as u32,
-                used: true,
+                state,
             });
-            slot_id
-        };
+            self.rows.push(tuple);
+            return Ok(());
+        }
 
-        self.rows.push(StorageRow { values });
+        match self.slots.get(idx).map(|s| s.state) {
+            Some(SlotState::Free) => self.free_slots.retain(|s| *s != slot_id),
+            Some(SlotState::Version) => {
+                if let Some(referrer) = self.referrer(slot_id) {
+                    self.tuple_mut(referrer).header.prev = None;
+                }
+            }
+            _ => {
+                return Err(StorageError::InvalidRowId {
+                    page_id: self.id.0,
+                    slot_id,
+                });
+            }
+        }
+        self.slots[idx].state = state;
+        *self.tuple_mut(slot_id) = tuple;
+        Ok(())
+    }
 
-        Ok(RowId {
-            page_id: self.id,
-            slot_id,
-        })
+    /// Copy the row in `slot_id` into `version` as an older version of it.
+    fn keep_version(&mut self, slot_id: u16, version: u16) -> StorageResult<()> {
+        // Reusing `version` may cut this row's own chain short, so the row
+        // is copied only once that is done.
+        if let Some(SlotState::Version) = self.slots.get(version as usize).map(|s| s.state)
+            && let Some(referrer) = self.referrer(version)
+        {
+            self.tuple_mut(referrer).header.prev = None;
+        }
+        let old = self.slot_tuple(slot_id).clone();
+        self.place(version, old, SlotState::Version)
     }
 
-    pub fn delete(&mut self, slot_id: u16) -> StorageResult<()> {
-        let slot = self
-            .slots
-            .get_mut(slot_id as usize)
-            .ok_or(StorageError::InvalidRowId {
-                page_id: self.id.0,
-                slot_id,
-            })?;
+    /// Free `slot_id` and put its row back in it. Undoes an `insert_at`.
+    fn release(&mut self, slot_id: u16) {
+        self.slots[slot_id as usize].state = SlotState::Free;
+        self.tuple_mut(slot_id).header = RowHeader::new(TxnId::NONE);
+        self.free_slots.push(slot_id);
+    }
 
-        if !slot.used {
-            return Err(StorageError::InvalidRowId {
-                page_id: self.id.0,
-                slot_id,
-            });
+    /// Undo an insert into the used slot `slot_id`: the slot is freed for
+    /// everyone, or gets back the deleted row the insert kept as a version.
+    pub fn uninsert(&mut self, slot_id: u16) -> StorageResult<()> {
+        let prev = self.tuple(slot_id, SlotState::Used)?.header.prev;
+        match prev {
+            Some(version) => {
+                let deleted = self.tuple(version, SlotState::Version)?.clone();
+                *self.tuple_mut(slot_id) = deleted;
+                self.slots[slot_id as usize].state = SlotState::Free;
+                self.release(version);
+                self.free_slots.push(slot_id);
+            }
+            None => self.release(slot_id),
         }
+        Ok(())
+    }
 
-        slot.used = false;
-        self.free_slots.push(slot_id);
+    /// Put back the row `delete` freed from `slot_id`, undoing the delete.
+    pub fn undelete(&mut self, slot_id: u16) -> StorageResult<()> {
+        self.tuple(slot_id, SlotState::Free)?;
+        self.slots[slot_id as usize].state = SlotState::Used;
+        self.tuple_mut(slot_id).header.xmax = TxnId::NONE;
+        self.free_slots.retain(|s| *s != slot_id);
+        Ok(())
+    }
+
+    /// Overwrite the row in a used slot on behalf of `txn`. Unless `txn`
+    /// wrote the row itself, the old version is first copied into a slot of
+    /// its own, reusing one no snapshot from `horizon` on reads; that slot
+    /// is returned. Fails with `PageFull` if the new row, and the old
+    /// version, do not fit; the page is left unchanged in that case.
+    pub fn update(
+        &mut self,
+        slot_id: u16,
+        values: Vec<Value>,
+        txn: TxnId,
+        horizon: TxnId,
+    ) -> StorageResult<Option<u16>> {
+        let old = self.tuple(slot_id, SlotState::Used)?;
+        let mut writes = vec![(slot_id, encoded_row_len(&values))];
+        let version = if txn == TxnId::NONE || old.header.xmin == txn {
+            None
+        } else {
+            let version = self
+                .version_slot(horizon, slot_id)
+                .ok_or(StorageError::PageFull { page_id: self.id.0 })?;
+            writes.push((version, tuple_len(old)));
+            Some(version)
+        };
+        if !self.fits(&writes) {
+            return Err(StorageError::PageFull { page_id: self.id.0 });
+        }
+
+        self.update_at(slot_id, values, txn, version)?;
+        Ok(version)
+    }
+
+    /// Overwrite the row in a used slot with `values` written by `xmin`,
+    /// first moving the old version to `version` if given. Replays `update`.
+    pub fn update_at(
+        &mut self,
+        slot_id: u16,
+        values: Vec<Value>,
+        xmin: TxnId,
+        version: Option<u16>,
+    ) -> StorageResult<()> {
+        self.tuple(slot_id, SlotState::Used)?;
+        match version {
+            Some(version) => {
+                self.keep_version(slot_id, version)?;
+                *self.tuple_mut(slot_id) = Tuple {
+                    header: RowHeader {
+                        xmin,
+                        xmax: TxnId::NONE,
+                        prev: Some(version),
+                    },
+                    row: StorageRow { values },
+                };
+            }
+            None => self.tuple_mut(slot_id).row = StorageRow { values },
+        }
+        Ok(())
+    }
+
+    /// Undo an `update` of the used slot `slot_id`: bring back the version
+    /// it moved to `version`, or else just the old `values`.
+    pub fn restore(
+        &mut self,
+        slot_id: u16,
+        values: Vec<Value>,
+        version: Option<u16>,
+    ) -> StorageResult<()> {
+        self.tuple(slot_id, SlotState::Used)?;
+        match version {
+            Some(version) => {
+                let old = self.tuple(version, SlotState::Version)?.clone();
+                *self.tuple_mut(slot_id) = old;
+                self.release(version);
+            }
+            None => self.tuple_mut(slot_id).row = StorageRow { values },
+        }
+        Ok(())
+    }
+
+    /// Number of bytes `write_bytes` will produce for this page.
+    pub fn encoded_len(&self) -> usize {
+        HEADER_SIZE + SLOT_SIZE * self.slots.len() + self.rows.iter().map(tuple_len).sum::<usize>()
+    }
 
+    /// Free the used slot `slot_id`, deleted by `xmax`. The row stays for
+    /// the snapshots that do not see `xmax`.
+    pub fn delete(&mut self, slot_id: u16, xmax: TxnId) -> StorageResult<()> {
+        self.tuple(slot_id, SlotState::Used)?;
+        self.slots[slot_id as usize].state = SlotState::Free;
+        self.tuple_mut(slot_id).header.xmax = xmax;
+        self.free_slots.push(slot_id);
         Ok(())
     }
 
     pub fn write_bytes(&self, buf: &mut [u8]) {
         assert!(buf.len() >= PAGE_SIZE);
 
-        buf.fill(0);
+        // The page LSN trailer is owned by the buffer pool / WAL.
+        buf[..PAGE_PAYLOAD_SIZE].fill(0);
 
         let slot_count = self.slots.len() as u16;
         let row_count = self.rows.len() as u16;
@@ -184,13 +607,19 @@ impl RowPage {
         buf[2..4].copy_from_slice(&row_count.to_le_bytes());
         buf[4..6].copy_from_slice(&capacity.to_le_bytes());
         // buf[6..8] reserved
+        let next = self.next.map_or(NO_NEXT_PAGE, |p| p.0);
+        buf[8..16].copy_from_slice(&next.to_le_bytes());
 
         // ---- slots ----
         let mut offset = HEADER_SIZE;
         for slot in &self.slots {
             let off = slot.offset as u16;
             buf[offset..offset + 2].copy_from_slice(&off.to_le_bytes());
-            buf[offset + 2] = if slot.used { 1 } else { 0 };
+            buf[offset + 2] = match slot.state {
+                SlotState::Free => 0,
+                SlotState::Used => 1,
+                SlotState::Version => 2,
+            };
             buf[offset + 3] = 0; // padding
             offset += SLOT_SIZE;
         }
@@ -198,8 +627,15 @@ impl RowPage {
         // ---- rows ----
         let mut row_ptr = HEADER_SIZE + SLOT_SIZE * self.slots.len();
 
-        for row in &self.rows {
-            let values = &row.values;
+        for tuple in &self.rows {
+            let header = &tuple.header;
+            buf[row_ptr..row_ptr + 8].copy_from_slice(&header.xmin.0.to_le_bytes());
+            buf[row_ptr + 8..row_ptr + 16].copy_from_slice(&header.xmax.0.to_le_bytes());
+            let prev = header.prev.unwrap_or(NO_PREV_SLOT);
+            buf[row_ptr + 16..row_ptr + 18].copy_from_slice(&prev.to_le_bytes());
+            row_ptr += ROW_HEADER_SIZE;
+
+            let values = &tuple.row.values;
 
             buf[row_ptr..row_ptr + 2].copy_from_slice(&(values.len() as u16).to_le_bytes());
             row_ptr += 2;
@@ -212,8 +648,22 @@ impl RowPage {
             }
         }
 
-        debug_assert!(row_ptr <= PAGE_SIZE);
+        debug_assert!(row_ptr <= PAGE_PAYLOAD_SIZE);
+    }
+}
+
+/// Serialized size of one row: its version header, then a u16 value count
+/// followed by the values.
+pub fn encoded_row_len(values: &[Value]) -> usize {
+    let mut tmp = Vec::new();
+    for v in values {
+        v.serialize(&mut tmp);
     }
+    ROW_HEADER_SIZE + 2 + tmp.len()
+}
+
+fn tuple_len(tuple: &Tuple) -> usize {
+    encoded_row_len(&tuple.row.values)
 }
 
 impl Page for RowPage {
@@ -226,7 +676,10 @@ impl Page for RowPage {
     }
 
     fn num_rows(&self) -> usize {
-        self.slots.iter().filter(|s| s.used).count()
+        self.slots
+            .iter()
+            .filter(|s| s.state == SlotState::Used)
+            .count()
     }
 
     fn is_full(&self) -> bool {
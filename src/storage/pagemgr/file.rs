@@ -1,5 +1,4 @@
 use std::{
-    collections::HashMap,
     fs::{File, OpenOptions},
     io::{Read, Seek, SeekFrom, Write},
     path::PathBuf,
@@ -9,14 +8,14 @@ use crate::storage::{
     buffer::frame::PAGE_SIZE,
     errors::{StorageError, StorageResult},
     page::page_id::PageId,
-    pagemgr::{frame::PageFrame, manager::PageManager},
+    pagemgr::manager::PageManager,
 };
 
 #[derive(Debug)]
 pub struct FilePageManager {
     file: File,
-    pages: HashMap<PageId, PageFrame>,
     next_page_id: u64,
+    free_list: Vec<PageId>,
 }
 
 impl FilePageManager {
@@ -40,68 +39,75 @@ impl FilePageManager {
 
         Ok(Self {
             file,
-            pages: HashMap::new(),
             next_page_id,
+            free_list: Vec::new(),
         })
     }
 }
 
+impl FilePageManager {
+    fn file_len(&self) -> StorageResult<u64> {
+        Ok(self.file.metadata().map_err(io_error)?.len())
+    }
+}
+
 impl PageManager for FilePageManager {
     fn allocate_page(&mut self) -> PageId {
-        let id = PageId(self.next_page_id);
-        self.next_page_id += 1;
+        match self.free_list.pop() {
+            Some(id) => id,
+            None => {
+                let id = PageId(self.next_page_id);
+                self.next_page_id += 1;
+                id
+            }
+        }
+    }
 
-        let frame = PageFrame {
-            id,
-            data: [0u8; PAGE_SIZE],
-            dirty: true,
-        };
+    fn read_page(&mut self, id: PageId, buf: &mut [u8; PAGE_SIZE]) -> StorageResult<()> {
+        let offset = id.0 * PAGE_SIZE as u64;
+        self.next_page_id = self.next_page_id.max(id.0 + 1);
 
-        self.pages.insert(id, frame);
-        id
+        // A page allocated before a crash may never have been written.
+        // Log replay recreates it, starting from zeroes.
+        if offset + PAGE_SIZE as u64 > self.file_len()? {
+            buf.fill(0);
+            return Ok(());
+        }
+        self.file.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+        self.file.read_exact(buf).map_err(io_error)
     }
 
-    fn fetch_page(&mut self, id: PageId) -> StorageResult<&mut PageFrame> {
-        if !self.pages.contains_key(&id) {
-            let mut frame = PageFrame {
-                id,
-                data: [0u8; PAGE_SIZE],
-                dirty: false,
-            };
-
-            let offset = id.0 * PAGE_SIZE as u64;
-            self.file
-                .seek(SeekFrom::Start(offset))
-                .map_err(|e| StorageError::Io {
-                    message: e.to_string(),
-                })?;
-            self.file
-                .read_exact(&mut frame.data)
-                .map_err(|e| StorageError::Io {
-                    message: e.to_string(),
-                })?;
-
-            self.pages.insert(id, frame);
-        }
+    fn write_page(&mut self, id: PageId, data: &[u8; PAGE_SIZE]) -> StorageResult<()> {
+        let offset = id.0 * PAGE_SIZE as u64;
+        self.file.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+        self.file.write_all(data).map_err(io_error)
+    }
 
-        Ok(self.pages.get_mut(&id).unwrap())
+    fn num_pages(&self) -> u64 {
+        self.next_page_id
     }
 
-    fn flush_page(&mut self, id: PageId) {
-        if let Some(frame) = self.pages.get_mut(&id) {
-            if frame.dirty {
-                let offset = id.0 * PAGE_SIZE as u64;
-                self.file.seek(SeekFrom::Start(offset)).unwrap();
-                self.file.write_all(&frame.data).unwrap();
-                frame.dirty = false;
-            }
+    fn deallocate_page(&mut self, id: PageId) {
+        if !self.free_list.contains(&id) {
+            self.free_list.push(id);
         }
     }
 
-    fn flush_all(&mut self) {
-        let ids: Vec<PageId> = self.pages.keys().cloned().collect();
-        for id in ids {
-            self.flush_page(id);
-        }
+    fn free_pages(&self) -> Vec<PageId> {
+        self.free_list.clone()
+    }
+
+    fn set_free_pages(&mut self, pages: Vec<PageId>) {
+        self.free_list = pages;
+    }
+
+    fn sync(&mut self) -> StorageResult<()> {
+        self.file.sync_all().map_err(io_error)
+    }
+}
+
+fn io_error(e: std::io::Error) -> StorageError {
+    StorageError::Io {
+        message: e.to_string(),
     }
 }
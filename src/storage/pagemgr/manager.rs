@@ -1,13 +1,34 @@
 use std::sync::{Arc, Mutex};
 
-use super::frame::PageFrame;
-use crate::storage::{errors::StorageResult, page::page_id::PageId};
+use crate::storage::{buffer::frame::PAGE_SIZE, errors::StorageResult, page::page_id::PageId};
 
+/// Page-granular access to the database file. It does no caching of its
+/// own: the buffer pool decides which pages stay in memory.
 pub trait PageManager: Send + Sync {
+    /// Reserve a page id. The page reads as zeroes until it is written.
     fn allocate_page(&mut self) -> PageId;
-    fn fetch_page(&mut self, id: PageId) -> StorageResult<&mut PageFrame>;
-    fn flush_page(&mut self, id: PageId);
-    fn flush_all(&mut self);
+
+    /// Read page `id` into `buf`. A page that was never written reads as
+    /// zeroes.
+    fn read_page(&mut self, id: PageId, buf: &mut [u8; PAGE_SIZE]) -> StorageResult<()>;
+
+    /// Write `data` as page `id`.
+    fn write_page(&mut self, id: PageId, data: &[u8; PAGE_SIZE]) -> StorageResult<()>;
+
+    /// Number of pages ever allocated in this file (including freed ones).
+    fn num_pages(&self) -> u64;
+
+    /// Return a page to the free list so `allocate_page` can reuse it.
+    fn deallocate_page(&mut self, id: PageId);
+
+    /// Current free list. Persisted by the catalog.
+    fn free_pages(&self) -> Vec<PageId>;
+
+    /// Restore a free list previously obtained from `free_pages`.
+    fn set_free_pages(&mut self, pages: Vec<PageId>);
+
+    /// Force written pages to stable storage.
+    fn sync(&mut self) -> StorageResult<()>;
 }
 
 pub type PageManagerHandle = Arc<Mutex<dyn PageManager>>;
@@ -1,3 +1,2 @@
 pub mod file;
-pub mod frame;
 pub mod manager;
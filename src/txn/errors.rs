@@ -0,0 +1,52 @@
+use core::fmt;
+
+use crate::txn::transaction::TxnId;
+
+#[derive(Debug)]
+pub enum TransactionError {
+    /// BEGIN while an explicit transaction is already open.
+    AlreadyActive(TxnId),
+    /// COMMIT, ROLLBACK or SAVEPOINT outside an explicit transaction.
+    NotActive,
+    UnknownSavepoint(String),
+    /// Schema changes are not logged and cannot be rolled back.
+    DdlInTransaction,
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::AlreadyActive(txn) => {
+                write!(f, "transaction {} is already in progress", txn.0)
+            }
+            TransactionError::NotActive => write!(f, "no transaction is in progress"),
+            TransactionError::UnknownSavepoint(name) => {
+                write!(f, "savepoint '{}' does not exist", name)
+            }
+            TransactionError::DdlInTransaction => {
+                write!(f, "CREATE and DROP cannot run inside a transaction")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+#[derive(Debug)]
+pub enum LockError {
+    /// Waiting for the lock would close a cycle in the wait-for graph; the
+    /// requesting transaction was chosen as the victim.
+    Deadlock(TxnId),
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockError::Deadlock(txn) => {
+                write!(f, "transaction {} was aborted to break a deadlock", txn.0)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LockError {}
@@ -0,0 +1,202 @@
+//! Two-phase locking for concurrent writers.
+//!
+//! Readers work from MVCC snapshots and never wait for a row. Writers take
+//! intention locks on a table and exclusive locks on each row they change,
+//! and hold them until their transaction commits or rolls back. Schema
+//! changes lock whole tables, which keeps them out of the way of running
+//! statements.
+//!
+//! A request that has to wait adds edges to a wait-for graph. If that closes
+//! a cycle, the requester is chosen as the deadlock victim: its request
+//! fails with `LockError::Deadlock` and the caller is expected to roll its
+//! transaction back, which releases its locks and lets the others go on.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Condvar, Mutex},
+};
+
+use crate::{
+    catalog::ids::TableId,
+    storage::page::row_id::RowId,
+    txn::{errors::LockError, transaction::TxnId},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    IntentionShared,
+    IntentionExclusive,
+    Shared,
+    SharedIntentionExclusive,
+    Exclusive,
+}
+
+impl LockMode {
+    /// Whether two transactions may hold `self` and `other` at once.
+    pub fn compatible(self, other: LockMode) -> bool {
+        use LockMode::*;
+        match (self, other) {
+            (Exclusive, _) | (_, Exclusive) => false,
+            (IntentionShared, _) | (_, IntentionShared) => true,
+            (IntentionExclusive, IntentionExclusive) | (Shared, Shared) => true,
+            _ => false,
+        }
+    }
+
+    /// The weakest mode that covers both `self` and `other`; what a
+    /// transaction holds after upgrading.
+    pub fn combine(self, other: LockMode) -> LockMode {
+        use LockMode::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Exclusive, _) | (_, Exclusive) => Exclusive,
+            (IntentionShared, m) | (m, IntentionShared) => m,
+            // Any remaining pair mixes shared and intention-exclusive.
+            _ => SharedIntentionExclusive,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LockTarget {
+    Table(TableId),
+    Row(RowId),
+}
+
+#[derive(Default)]
+struct LockState {
+    /// Granted modes per target.
+    granted: HashMap<LockTarget, HashMap<TxnId, LockMode>>,
+    /// Targets each transaction holds, for releasing them in one go.
+    held: HashMap<TxnId, Vec<LockTarget>>,
+    /// Wait-for graph: a waiting transaction and the holders it waits on.
+    waits_for: HashMap<TxnId, HashSet<TxnId>>,
+}
+
+pub struct LockManager {
+    state: Mutex<LockState>,
+    /// Signalled whenever locks are released.
+    released: Condvar,
+}
+
+impl Default for LockManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LockManager {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(LockState::default()),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Acquire `mode` on `target` for `txn`, upgrading a weaker lock it
+    /// already holds, and block until the lock is granted. Fails without
+    /// waiting if that would deadlock. Work outside any transaction
+    /// (`TxnId::NONE`) does not lock.
+    pub fn lock(&self, txn: TxnId, target: LockTarget, mode: LockMode) -> Result<(), LockError> {
+        if txn == TxnId::NONE {
+            return Ok(());
+        }
+
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let blockers = state.blockers(txn, target, mode);
+            if blockers.is_empty() {
+                state.waits_for.remove(&txn);
+                state.grant(txn, target, mode);
+                return Ok(());
+            }
+
+            state.waits_for.insert(txn, blockers);
+            if state.in_cycle(txn) {
+                state.waits_for.remove(&txn);
+                return Err(LockError::Deadlock(txn));
+            }
+            state = self.released.wait(state).unwrap();
+        }
+    }
+
+    /// Acquire `mode` on `target` only if that is possible without waiting.
+    pub fn try_lock(&self, txn: TxnId, target: LockTarget, mode: LockMode) -> bool {
+        if txn == TxnId::NONE {
+            return true;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        if !state.blockers(txn, target, mode).is_empty() {
+            return false;
+        }
+        state.grant(txn, target, mode);
+        true
+    }
+
+    /// Release every lock `txn` holds and wake up the waiters.
+    pub fn unlock_all(&self, txn: TxnId) {
+        let mut state = self.state.lock().unwrap();
+        for target in state.held.remove(&txn).unwrap_or_default() {
+            if let Some(holders) = state.granted.get_mut(&target) {
+                holders.remove(&txn);
+                if holders.is_empty() {
+                    state.granted.remove(&target);
+                }
+            }
+        }
+        state.waits_for.remove(&txn);
+        drop(state);
+        self.released.notify_all();
+    }
+
+    /// Mode `txn` currently holds on `target`, if any.
+    pub fn mode(&self, txn: TxnId, target: LockTarget) -> Option<LockMode> {
+        let state = self.state.lock().unwrap();
+        state.granted.get(&target)?.get(&txn).copied()
+    }
+}
+
+impl LockState {
+    /// Other transactions whose locks on `target` conflict with `txn`
+    /// holding `mode` there.
+    fn blockers(&self, txn: TxnId, target: LockTarget, mode: LockMode) -> HashSet<TxnId> {
+        let Some(holders) = self.granted.get(&target) else {
+            return HashSet::new();
+        };
+        let wanted = holders.get(&txn).map_or(mode, |held| held.combine(mode));
+        holders
+            .iter()
+            .filter(|(holder, held)| **holder != txn && !held.compatible(wanted))
+            .map(|(holder, _)| *holder)
+            .collect()
+    }
+
+    fn grant(&mut self, txn: TxnId, target: LockTarget, mode: LockMode) {
+        let holders = self.granted.entry(target).or_default();
+        match holders.get_mut(&txn) {
+            Some(held) => *held = held.combine(mode),
+            None => {
+                holders.insert(txn, mode);
+                self.held.entry(txn).or_default().push(target);
+            }
+        }
+    }
+
+    /// Whether following wait-for edges from `start` leads back to it.
+    fn in_cycle(&self, start: TxnId) -> bool {
+        let mut stack: Vec<TxnId> = self.waits_for[&start].iter().copied().collect();
+        let mut seen = HashSet::new();
+        while let Some(txn) = stack.pop() {
+            if txn == start {
+                return true;
+            }
+            if seen.insert(txn)
+                && let Some(next) = self.waits_for.get(&txn)
+            {
+                stack.extend(next.iter().copied());
+            }
+        }
+        false
+    }
+}
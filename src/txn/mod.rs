@@ -1,2 +1,6 @@
+pub mod errors;
+pub mod lock_manager;
+pub mod recovery;
 pub mod snapshot;
 pub mod transaction;
+pub mod wal;
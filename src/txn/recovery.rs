@@ -0,0 +1,455 @@
+//! Crash recovery and transaction rollback.
+//!
+//! Recovery follows ARIES and runs in three passes when a database opens:
+//!
+//! 1. **Analysis** reads the log, finds where redo has to start and which
+//!    transactions never committed or aborted (the losers).
+//! 2. **Redo** repeats history: every page change after the start point is
+//!    reapplied unless the page's LSN shows it already reached disk.
+//! 3. **Undo** rolls the losers back, newest record first. Each undone
+//!    change is logged as a compensation record (`Clr`), so a crash during
+//!    undo never undoes the same change twice.
+//!
+//! The catalog is loaded between redo and undo: index undo is logical and
+//! needs the index handles, and those can only be opened once their pages
+//! have been redone.
+//!
+//! Rolling back a live transaction, in full or to a savepoint, uses the same
+//! undo logic, driven by the records the log writer keeps for it in memory.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{
+    catalog::catalog::Catalog,
+    storage::{
+        buffer::{
+            frame::PageFrame,
+            pool::{BufferPool, BufferPoolHandle},
+        },
+        errors::{StorageError, StorageResult},
+        page::{page_id::PageId, row_page::RowPage},
+    },
+    txn::{
+        transaction::TxnId,
+        wal::{
+            reader::WalReader,
+            record::{LogBody, LogRecord, Lsn},
+            writer::WalHandle,
+        },
+    },
+};
+
+/// Result of the analysis pass, consumed by redo and undo.
+pub struct Recovery {
+    records: Vec<LogRecord>,
+    /// First record whose page change may be missing from disk.
+    redo_start: Lsn,
+    /// Log position covered by the persisted catalog image; pages allocated
+    /// after it may still be on the image's free list.
+    catalog_lsn: Lsn,
+    /// Unfinished transactions and their last record.
+    losers: HashMap<TxnId, Lsn>,
+}
+
+impl Recovery {
+    /// Analysis pass over the log in `dir`. `catalog_lsn` is the redo LSN
+    /// recorded in the catalog superblock.
+    pub fn analyze(dir: &Path, catalog_lsn: Lsn) -> StorageResult<Self> {
+        let records = WalReader::open(dir)?.records()?;
+
+        // A checkpoint is only written once every page not listed in it has
+        // been flushed.
+        let mut redo_start = records.first().map_or(Lsn::INVALID, |r| r.lsn);
+        for rec in &records {
+            if let LogBody::Checkpoint { dirty_pages, .. } = &rec.body {
+                redo_start = dirty_pages
+                    .iter()
+                    .map(|(_, lsn)| *lsn)
+                    .fold(rec.lsn, Lsn::min);
+            }
+        }
+        redo_start = redo_start.max(catalog_lsn.next());
+
+        let mut losers = HashMap::new();
+        for rec in &records {
+            if rec.txn_id == TxnId::NONE {
+                continue;
+            }
+            match rec.body {
+                LogBody::Commit | LogBody::Abort => {
+                    losers.remove(&rec.txn_id);
+                }
+                _ => {
+                    losers.insert(rec.txn_id, rec.lsn);
+                }
+            }
+        }
+
+        Ok(Self {
+            records,
+            redo_start,
+            catalog_lsn,
+            losers,
+        })
+    }
+
+    /// Redo pass. Returns the number of records that were reapplied.
+    pub fn redo(&self, bp: &BufferPoolHandle) -> StorageResult<usize> {
+        let mut redone = 0;
+        for rec in self.records.iter().filter(|r| r.lsn >= self.redo_start) {
+            if apply(bp, rec.lsn, rec.txn_id, &rec.body, false)? {
+                redone += 1;
+            }
+        }
+        Ok(redone)
+    }
+
+    /// Complete recovery once the catalog is loaded: take pages allocated
+    /// since the catalog image off its free list, then undo the losers and
+    /// force their abort records.
+    pub fn finish(
+        self,
+        catalog: &Catalog,
+        bp: &BufferPoolHandle,
+        wal: &WalHandle,
+    ) -> StorageResult<()> {
+        let allocated: HashSet<PageId> = self
+            .records
+            .iter()
+            .filter(|r| r.lsn > self.catalog_lsn)
+            .filter_map(|r| match &r.body {
+                LogBody::HeapNewPage { page_id, .. } | LogBody::PageImage { page_id, .. } => {
+                    Some(*page_id)
+                }
+                _ => None,
+            })
+            .collect();
+        {
+            let mut pool = bp.lock().unwrap();
+            let mut free = pool.pm.free_pages();
+            free.retain(|pid| !allocated.contains(pid));
+            pool.pm.set_free_pages(free);
+        }
+
+        let mut wal_guard = wal.lock().unwrap();
+        for (txn, last) in &self.losers {
+            wal_guard.resume(*txn, *last);
+        }
+        drop(wal_guard);
+
+        let by_lsn = self.records.iter().map(|r| (r.lsn, r)).collect();
+        undo(self.losers, &by_lsn, catalog, bp, wal)?;
+        wal.lock().unwrap().flush()
+    }
+}
+
+/// Roll back the live transaction `txn` and log its `Abort`.
+pub fn rollback(
+    txn: TxnId,
+    catalog: &Catalog,
+    bp: &BufferPoolHandle,
+    wal: &WalHandle,
+) -> StorageResult<()> {
+    rollback_to(txn, Lsn::INVALID, catalog, bp, wal)?;
+    wal.lock().unwrap().abort(txn)?;
+    Ok(())
+}
+
+/// Undo every change the live transaction `txn` made after its record
+/// `savepoint`. The transaction stays active.
+pub fn rollback_to(
+    txn: TxnId,
+    savepoint: Lsn,
+    catalog: &Catalog,
+    bp: &BufferPoolHandle,
+    wal: &WalHandle,
+) -> StorageResult<()> {
+    let (records, mut next) = {
+        let wal = wal.lock().unwrap();
+        (wal.txn_records(txn), wal.txn_last_lsn(txn))
+    };
+
+    let by_lsn = records.iter().map(|r| (r.lsn, r)).collect();
+    while next > savepoint {
+        next = undo_record(txn, next, &by_lsn, catalog, bp, wal)?;
+    }
+    Ok(())
+}
+
+/// Undo pass: repeatedly take the newest record still to be undone across
+/// all `pending` transactions. A transaction is finished with `Abort` once
+/// its chain reaches the start.
+fn undo(
+    mut pending: HashMap<TxnId, Lsn>,
+    by_lsn: &HashMap<Lsn, &LogRecord>,
+    catalog: &Catalog,
+    bp: &BufferPoolHandle,
+    wal: &WalHandle,
+) -> StorageResult<()> {
+    while let Some((txn, lsn)) = pending
+        .iter()
+        .map(|(t, l)| (*t, *l))
+        .max_by_key(|(_, l)| *l)
+    {
+        let next = undo_record(txn, lsn, by_lsn, catalog, bp, wal)?;
+        if next == Lsn::INVALID {
+            pending.remove(&txn);
+            wal.lock().unwrap().abort(txn)?;
+        } else {
+            pending.insert(txn, next);
+        }
+    }
+    Ok(())
+}
+
+/// Undo record `lsn` of `txn`, if it is undoable, and return the next
+/// record of the transaction that still needs undoing.
+fn undo_record(
+    txn: TxnId,
+    lsn: Lsn,
+    by_lsn: &HashMap<Lsn, &LogRecord>,
+    catalog: &Catalog,
+    bp: &BufferPoolHandle,
+    wal: &WalHandle,
+) -> StorageResult<Lsn> {
+    let rec = by_lsn.get(&lsn).ok_or_else(|| StorageError::LogCorrupted {
+        reason: format!("record {} of transaction {} is missing", lsn, txn.0),
+    })?;
+
+    Ok(match &rec.body {
+        LogBody::Clr { undo_next, .. } => *undo_next,
+        body => {
+            if let Some(inverse) = inverse(body) {
+                compensate(txn, rec.prev_lsn, inverse, catalog, bp, wal)?;
+            }
+            rec.prev_lsn
+        }
+    })
+}
+
+/// Log a CLR for `action` and carry it out.
+fn compensate(
+    txn: TxnId,
+    undo_next: Lsn,
+    action: LogBody,
+    catalog: &Catalog,
+    bp: &BufferPoolHandle,
+    wal: &WalHandle,
+) -> StorageResult<()> {
+    let clr = LogBody::Clr {
+        undo_next,
+        action: Box::new(action.clone()),
+    };
+
+    match action {
+        // Index changes are undone logically; the tree logs the pages it
+        // touches. An index dropped since has nothing left to undo.
+        LogBody::IndexInsert { index_id, key, rid } => {
+            wal.lock().unwrap().append(txn, clr)?;
+            if let Some(entry) = catalog.get_index_by_id(index_id) {
+                entry.index.lock().unwrap().insert(key, rid)?;
+            }
+        }
+        LogBody::IndexDelete { index_id, key, rid } => {
+            wal.lock().unwrap().append(txn, clr)?;
+            if let Some(entry) = catalog.get_index_by_id(index_id) {
+                entry.index.lock().unwrap().delete(&key, rid)?;
+            }
+        }
+        // Latch the page before logging. Otherwise a change logged after
+        // the CLR could reach the page first, and the page LSN would claim
+        // the CLR is already applied.
+        LogBody::HeapInsert { rid, table_id, .. }
+        | LogBody::HeapDelete { rid, table_id, .. }
+        | LogBody::HeapUpdate { rid, table_id, .. } => {
+            let mut frame = BufferPool::fetch_page_write(bp, rid.page_id)?;
+            let lsn = wal.lock().unwrap().append(txn, clr)?;
+            apply_row_change(&mut frame, lsn, txn, &action, true)?;
+            if let Some(table) = catalog.get_table_by_id(table_id) {
+                count_row_change(&table.row_count, &action);
+            }
+        }
+        action => {
+            let lsn = wal.lock().unwrap().append(txn, clr)?;
+            apply(bp, lsn, txn, &action, true)?;
+        }
+    }
+    Ok(())
+}
+
+/// Keep `row_count` in step with the undoing `action`.
+fn count_row_change(row_count: &AtomicU64, action: &LogBody) {
+    match action {
+        LogBody::HeapInsert { .. } => {
+            row_count.fetch_add(1, Ordering::Relaxed);
+        }
+        LogBody::HeapDelete { .. } => {
+            let _ =
+                row_count.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+        }
+        _ => {}
+    }
+}
+
+/// The change that reverses `body`, if it is undoable.
+fn inverse(body: &LogBody) -> Option<LogBody> {
+    Some(match body.clone() {
+        LogBody::HeapInsert {
+            table_id,
+            rid,
+            values,
+            ..
+        } => LogBody::HeapDelete {
+            table_id,
+            rid,
+            values,
+        },
+        LogBody::HeapDelete {
+            table_id,
+            rid,
+            values,
+        } => LogBody::HeapInsert {
+            table_id,
+            rid,
+            values,
+            version: None,
+        },
+        LogBody::HeapUpdate {
+            table_id,
+            rid,
+            before,
+            after,
+            version,
+        } => LogBody::HeapUpdate {
+            table_id,
+            rid,
+            before: after,
+            after: before,
+            version,
+        },
+        LogBody::IndexInsert { index_id, key, rid } => LogBody::IndexDelete { index_id, key, rid },
+        LogBody::IndexDelete { index_id, key, rid } => LogBody::IndexInsert { index_id, key, rid },
+        // A new heap page stays linked in; it is simply empty.
+        _ => return None,
+    })
+}
+
+/// Reapply the physical change `txn` made as described by `body` to every
+/// page whose LSN is older than `lsn`; `undo` as for `apply_row_change`.
+/// Returns whether any page changed.
+fn apply(
+    bp: &BufferPoolHandle,
+    lsn: Lsn,
+    txn: TxnId,
+    body: &LogBody,
+    undo: bool,
+) -> StorageResult<bool> {
+    match body {
+        LogBody::HeapInsert { rid, .. }
+        | LogBody::HeapDelete { rid, .. }
+        | LogBody::HeapUpdate { rid, .. } => {
+            let mut frame = BufferPool::fetch_page_write(bp, rid.page_id)?;
+            apply_row_change(&mut frame, lsn, txn, body, undo)
+        }
+        LogBody::HeapNewPage {
+            page_id,
+            prev_page,
+            capacity,
+            ..
+        } => {
+            let created = apply_frame(bp, *page_id, lsn, |frame| {
+                RowPage::new(*page_id, *capacity as usize).write_bytes(&mut frame.data);
+                Ok(())
+            })?;
+            let mut prev = BufferPool::fetch_page_write(bp, *prev_page)?;
+            let linked = apply_row(&mut prev, lsn, |page| {
+                page.set_next_page(Some(*page_id));
+                Ok(())
+            })?;
+            Ok(created || linked)
+        }
+        LogBody::PageImage { page_id, data } => apply_frame(bp, *page_id, lsn, |frame| {
+            frame.data[..data.len()].copy_from_slice(data);
+            Ok(())
+        }),
+        LogBody::Clr { action, .. } => apply(bp, lsn, txn, action, true),
+        _ => Ok(false),
+    }
+}
+
+/// Reapply the heap row change `txn` made as described by `body` to its
+/// page, which the caller has latched. With `undo`, `body` is the inverse of
+/// a change being undone: the row versions that change created are dropped
+/// rather than new ones made.
+fn apply_row_change(
+    frame: &mut PageFrame,
+    lsn: Lsn,
+    txn: TxnId,
+    body: &LogBody,
+    undo: bool,
+) -> StorageResult<bool> {
+    apply_row(frame, lsn, |page| match body {
+        LogBody::HeapInsert { rid, .. } if undo => page.undelete(rid.slot_id),
+        LogBody::HeapInsert {
+            rid,
+            values,
+            version,
+            ..
+        } => page.insert_at(rid.slot_id, values.clone(), txn, *version),
+        LogBody::HeapDelete { rid, .. } if undo => page.uninsert(rid.slot_id),
+        LogBody::HeapDelete { rid, .. } => page.delete(rid.slot_id, txn),
+        LogBody::HeapUpdate {
+            rid,
+            after,
+            version,
+            ..
+        } if undo => page.restore(rid.slot_id, after.clone(), *version),
+        LogBody::HeapUpdate {
+            rid,
+            after,
+            version,
+            ..
+        } => page.update_at(rid.slot_id, after.clone(), txn, *version),
+        _ => Ok(()),
+    })
+}
+
+/// Run `f` on the row page in `frame` and stamp it with `lsn`, unless the
+/// page already reflects that record.
+fn apply_row(
+    frame: &mut PageFrame,
+    lsn: Lsn,
+    f: impl FnOnce(&mut RowPage) -> StorageResult<()>,
+) -> StorageResult<bool> {
+    if frame.lsn() >= lsn {
+        return Ok(false);
+    }
+
+    let mut page = RowPage::from_bytes(frame.id, &frame.data)?;
+    f(&mut page)?;
+    page.write_bytes(&mut frame.data);
+    frame.set_lsn(lsn);
+    Ok(true)
+}
+
+/// Run `f` on page `pid` and stamp it with `lsn`, unless the page already
+/// reflects that record.
+fn apply_frame(
+    bp: &BufferPoolHandle,
+    pid: PageId,
+    lsn: Lsn,
+    f: impl FnOnce(&mut PageFrame) -> StorageResult<()>,
+) -> StorageResult<bool> {
+    let mut frame = BufferPool::fetch_page_write(bp, pid)?;
+    if frame.lsn() >= lsn {
+        return Ok(false);
+    }
+
+    f(&mut frame)?;
+    frame.set_lsn(lsn);
+    Ok(true)
+}
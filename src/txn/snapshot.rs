@@ -0,0 +1,67 @@
+//! Snapshots for MVCC reads.
+//!
+//! A snapshot fixes which transactions' changes a reader sees: everything
+//! committed before it was taken, plus the reader's own changes. Each row on
+//! a heap page records which transactions wrote and deleted it, and older
+//! versions stay on the page (see `RowPage`) for as long as a live snapshot
+//! may need them.
+//!
+//! Rolled-back transactions undo their changes before they leave the active
+//! set, so any finished transaction a snapshot sees can be treated as
+//! committed.
+
+use std::collections::HashSet;
+
+use crate::txn::transaction::TxnId;
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Transaction the snapshot belongs to; its own changes are visible.
+    txn: TxnId,
+    /// Oldest transaction whose changes may be invisible.
+    xmin: TxnId,
+    /// First transaction id not yet handed out when the snapshot was taken.
+    xmax: TxnId,
+    /// Transactions that were running when the snapshot was taken.
+    active: HashSet<TxnId>,
+}
+
+impl Snapshot {
+    pub fn new(txn: TxnId, xmax: TxnId, active: impl IntoIterator<Item = TxnId>) -> Self {
+        let active: HashSet<TxnId> = active.into_iter().filter(|t| *t != txn).collect();
+        let xmin = active.iter().copied().fold(xmax, TxnId::min);
+        Self {
+            txn,
+            xmin,
+            xmax,
+            active,
+        }
+    }
+
+    /// A snapshot that sees every change, including uncommitted ones. Used
+    /// where there is no log to say which transactions are running.
+    pub fn latest() -> Self {
+        Self {
+            txn: TxnId::NONE,
+            xmin: TxnId(u64::MAX),
+            xmax: TxnId(u64::MAX),
+            active: HashSet::new(),
+        }
+    }
+
+    pub fn txn(&self) -> TxnId {
+        self.txn
+    }
+
+    /// Every transaction older than this is visible to the snapshot.
+    pub fn xmin(&self) -> TxnId {
+        self.xmin
+    }
+
+    /// Whether changes made by `writer` are visible to the snapshot.
+    pub fn sees(&self, writer: TxnId) -> bool {
+        writer == TxnId::NONE
+            || writer == self.txn
+            || (writer < self.xmax && !self.active.contains(&writer))
+    }
+}
@@ -0,0 +1,59 @@
+//! Transaction identifiers and per-connection transaction state.
+
+use std::sync::Arc;
+
+use crate::txn::{snapshot::Snapshot, wal::record::Lsn};
+
+/// Transaction id. Ids are handed out by the write-ahead log and increase
+/// monotonically across restarts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TxnId(pub u64);
+
+impl TxnId {
+    /// Work done outside any user transaction (e.g. B+Tree structure
+    /// changes). Such log records are redone but never undone.
+    pub const NONE: TxnId = TxnId(0);
+}
+
+/// An explicit transaction opened with `BEGIN`. Outside of one, every
+/// statement runs in its own transaction and commits on success.
+#[derive(Debug)]
+pub struct Transaction {
+    id: TxnId,
+    /// Taken at `BEGIN`; every query in the transaction reads from it.
+    snapshot: Arc<Snapshot>,
+    /// Savepoints in creation order, each with the transaction's last log
+    /// record at the time it was taken.
+    savepoints: Vec<(String, Lsn)>,
+}
+
+impl Transaction {
+    pub fn new(id: TxnId, snapshot: Arc<Snapshot>) -> Self {
+        Self {
+            id,
+            snapshot,
+            savepoints: Vec::new(),
+        }
+    }
+
+    pub fn id(&self) -> TxnId {
+        self.id
+    }
+
+    pub fn snapshot(&self) -> &Arc<Snapshot> {
+        &self.snapshot
+    }
+
+    pub fn add_savepoint(&mut self, name: &str, lsn: Lsn) {
+        self.savepoints.push((name.to_string(), lsn));
+    }
+
+    /// Find the most recent savepoint called `name` and forget every
+    /// savepoint taken after it. Returns the LSN to roll back to; the
+    /// savepoint itself stays usable.
+    pub fn rewind_to(&mut self, name: &str) -> Option<Lsn> {
+        let pos = self.savepoints.iter().rposition(|(n, _)| n == name)?;
+        self.savepoints.truncate(pos + 1);
+        Some(self.savepoints[pos].1)
+    }
+}
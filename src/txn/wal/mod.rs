@@ -0,0 +1,10 @@
+//! Write-ahead log.
+//!
+//! Every change to a heap or index page is described by a `LogRecord`
+//! appended here before the page may reach disk. Records are framed as
+//! `[len u32][crc32 u32][payload]` and written to numbered segment files
+//! inside the log directory; see `docs/file_format.md`.
+
+pub mod reader;
+pub mod record;
+pub mod writer;
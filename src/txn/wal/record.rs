@@ -0,0 +1,392 @@
+//! Log record types and their binary encoding.
+
+use std::fmt;
+
+use crate::{
+    catalog::ids::{IndexId, TableId},
+    storage::{
+        index::btree::key::IndexKey,
+        page::{page_id::PageId, row_id::RowId},
+    },
+    txn::transaction::TxnId,
+    types::value::Value,
+    util::bytes::{ByteReader, ByteWriter, DecodeResult},
+};
+
+/// Log sequence number. Assigned in append order, starting at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Lsn(pub u64);
+
+impl Lsn {
+    /// "No record": the start of a transaction's chain, or a page that has
+    /// never been logged.
+    pub const INVALID: Lsn = Lsn(0);
+
+    pub fn next(self) -> Lsn {
+        Lsn(self.0 + 1)
+    }
+}
+
+impl fmt::Display for Lsn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub lsn: Lsn,
+    /// Previous record written by the same transaction.
+    pub prev_lsn: Lsn,
+    pub txn_id: TxnId,
+    pub body: LogBody,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogBody {
+    Begin,
+    Commit,
+    Abort,
+
+    /// Row `values` was placed in slot `rid`. The row its own transaction
+    /// had deleted from the slot was kept in slot `version`, if given.
+    HeapInsert {
+        table_id: TableId,
+        rid: RowId,
+        values: Vec<Value>,
+        version: Option<u16>,
+    },
+
+    /// Slot `rid`, which held `values`, was freed.
+    HeapDelete {
+        table_id: TableId,
+        rid: RowId,
+        values: Vec<Value>,
+    },
+
+    /// Slot `rid` was overwritten in place. Its old version was kept in
+    /// slot `version`, if given.
+    HeapUpdate {
+        table_id: TableId,
+        rid: RowId,
+        before: Vec<Value>,
+        after: Vec<Value>,
+        version: Option<u16>,
+    },
+
+    /// An empty row page was appended to a heap chain after `prev_page`.
+    HeapNewPage {
+        table_id: TableId,
+        page_id: PageId,
+        prev_page: PageId,
+        capacity: u16,
+    },
+
+    /// Full after-image of a page's payload. Used for B+Tree nodes, whose
+    /// splits and merges touch several pages at once.
+    PageImage {
+        page_id: PageId,
+        data: Vec<u8>,
+    },
+
+    /// Written after all dirty pages have been flushed.
+    Checkpoint {
+        next_txn_id: TxnId,
+        active_txns: Vec<(TxnId, Lsn)>,
+        dirty_pages: Vec<(PageId, Lsn)>,
+    },
+
+    /// `(key, rid)` was added to an index. Logical and undo-only: the tree
+    /// pages it touched are covered by their own `PageImage` records.
+    IndexInsert {
+        index_id: IndexId,
+        key: IndexKey,
+        rid: RowId,
+    },
+
+    /// `(key, rid)` was removed from an index. Undo-only, like `IndexInsert`.
+    IndexDelete {
+        index_id: IndexId,
+        key: IndexKey,
+        rid: RowId,
+    },
+
+    /// Compensation record: `action` undid one earlier record of the same
+    /// transaction. Redone but never undone; rollback resumes at
+    /// `undo_next`.
+    Clr {
+        undo_next: Lsn,
+        action: Box<LogBody>,
+    },
+}
+
+impl LogBody {
+    fn tag(&self) -> u8 {
+        match self {
+            LogBody::Begin => 0,
+            LogBody::Commit => 1,
+            LogBody::Abort => 2,
+            LogBody::HeapInsert { .. } => 3,
+            LogBody::HeapDelete { .. } => 4,
+            LogBody::HeapUpdate { .. } => 5,
+            LogBody::HeapNewPage { .. } => 6,
+            LogBody::PageImage { .. } => 7,
+            LogBody::Checkpoint { .. } => 8,
+            LogBody::IndexInsert { .. } => 9,
+            LogBody::IndexDelete { .. } => 10,
+            LogBody::Clr { .. } => 11,
+        }
+    }
+
+    /// Page touched by this record, if it describes a page change.
+    pub fn page_id(&self) -> Option<PageId> {
+        match self {
+            LogBody::HeapInsert { rid, .. }
+            | LogBody::HeapDelete { rid, .. }
+            | LogBody::HeapUpdate { rid, .. } => Some(rid.page_id),
+            LogBody::HeapNewPage { page_id, .. } | LogBody::PageImage { page_id, .. } => {
+                Some(*page_id)
+            }
+            LogBody::Clr { action, .. } => action.page_id(),
+            _ => None,
+        }
+    }
+}
+
+impl LogRecord {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = ByteWriter::new();
+        w.put_u64(self.lsn.0);
+        w.put_u64(self.prev_lsn.0);
+        w.put_u64(self.txn_id.0);
+        encode_body(&mut w, &self.body);
+        w.into_inner()
+    }
+
+    pub fn decode(buf: &[u8]) -> DecodeResult<Self> {
+        let mut r = ByteReader::new(buf);
+        let lsn = Lsn(r.get_u64()?);
+        let prev_lsn = Lsn(r.get_u64()?);
+        let txn_id = TxnId(r.get_u64()?);
+
+        let body = decode_body(&mut r)?;
+
+        if r.remaining() != 0 {
+            return Err("trailing bytes after log record");
+        }
+
+        Ok(Self {
+            lsn,
+            prev_lsn,
+            txn_id,
+            body,
+        })
+    }
+}
+
+fn encode_body(w: &mut ByteWriter, body: &LogBody) {
+    w.put_u8(body.tag());
+    match body {
+        LogBody::Begin | LogBody::Commit | LogBody::Abort => {}
+        LogBody::HeapInsert {
+            table_id,
+            rid,
+            values,
+            version,
+        } => {
+            w.put_u32(table_id.0);
+            put_rid(w, *rid);
+            put_values(w, values);
+            put_slot(w, *version);
+        }
+        LogBody::HeapDelete {
+            table_id,
+            rid,
+            values,
+        } => {
+            w.put_u32(table_id.0);
+            put_rid(w, *rid);
+            put_values(w, values);
+        }
+        LogBody::HeapUpdate {
+            table_id,
+            rid,
+            before,
+            after,
+            version,
+        } => {
+            w.put_u32(table_id.0);
+            put_rid(w, *rid);
+            put_values(w, before);
+            put_values(w, after);
+            put_slot(w, *version);
+        }
+        LogBody::HeapNewPage {
+            table_id,
+            page_id,
+            prev_page,
+            capacity,
+        } => {
+            w.put_u32(table_id.0);
+            w.put_u64(page_id.0);
+            w.put_u64(prev_page.0);
+            w.put_u16(*capacity);
+        }
+        LogBody::PageImage { page_id, data } => {
+            w.put_u64(page_id.0);
+            w.put_bytes(data);
+        }
+        LogBody::IndexInsert { index_id, key, rid }
+        | LogBody::IndexDelete { index_id, key, rid } => {
+            w.put_u32(index_id.0);
+            let mut buf = Vec::new();
+            key.serialize(&mut buf);
+            w.put_bytes(&buf);
+            put_rid(w, *rid);
+        }
+        LogBody::Clr { undo_next, action } => {
+            w.put_u64(undo_next.0);
+            encode_body(w, action);
+        }
+        LogBody::Checkpoint {
+            next_txn_id,
+            active_txns,
+            dirty_pages,
+        } => {
+            w.put_u64(next_txn_id.0);
+            w.put_u32(active_txns.len() as u32);
+            for (txn, lsn) in active_txns {
+                w.put_u64(txn.0);
+                w.put_u64(lsn.0);
+            }
+            w.put_u32(dirty_pages.len() as u32);
+            for (pid, lsn) in dirty_pages {
+                w.put_u64(pid.0);
+                w.put_u64(lsn.0);
+            }
+        }
+    }
+}
+
+fn decode_body(r: &mut ByteReader) -> DecodeResult<LogBody> {
+    let body = match r.get_u8()? {
+        0 => LogBody::Begin,
+        1 => LogBody::Commit,
+        2 => LogBody::Abort,
+        3 => LogBody::HeapInsert {
+            table_id: TableId(r.get_u32()?),
+            rid: get_rid(r)?,
+            values: get_values(r)?,
+            version: get_slot(r)?,
+        },
+        4 => LogBody::HeapDelete {
+            table_id: TableId(r.get_u32()?),
+            rid: get_rid(r)?,
+            values: get_values(r)?,
+        },
+        5 => LogBody::HeapUpdate {
+            table_id: TableId(r.get_u32()?),
+            rid: get_rid(r)?,
+            before: get_values(r)?,
+            after: get_values(r)?,
+            version: get_slot(r)?,
+        },
+        6 => LogBody::HeapNewPage {
+            table_id: TableId(r.get_u32()?),
+            page_id: PageId(r.get_u64()?),
+            prev_page: PageId(r.get_u64()?),
+            capacity: r.get_u16()?,
+        },
+        7 => LogBody::PageImage {
+            page_id: PageId(r.get_u64()?),
+            data: r.get_bytes()?.to_vec(),
+        },
+        8 => {
+            let next_txn_id = TxnId(r.get_u64()?);
+            let mut active_txns = Vec::new();
+            for _ in 0..r.get_u32()? {
+                active_txns.push((TxnId(r.get_u64()?), Lsn(r.get_u64()?)));
+            }
+            let mut dirty_pages = Vec::new();
+            for _ in 0..r.get_u32()? {
+                dirty_pages.push((PageId(r.get_u64()?), Lsn(r.get_u64()?)));
+            }
+            LogBody::Checkpoint {
+                next_txn_id,
+                active_txns,
+                dirty_pages,
+            }
+        }
+        9 => LogBody::IndexInsert {
+            index_id: IndexId(r.get_u32()?),
+            key: get_key(r)?,
+            rid: get_rid(r)?,
+        },
+        10 => LogBody::IndexDelete {
+            index_id: IndexId(r.get_u32()?),
+            key: get_key(r)?,
+            rid: get_rid(r)?,
+        },
+        11 => LogBody::Clr {
+            undo_next: Lsn(r.get_u64()?),
+            action: Box::new(decode_body(r)?),
+        },
+        _ => return Err("unknown log record tag"),
+    };
+    Ok(body)
+}
+
+fn put_rid(w: &mut ByteWriter, rid: RowId) {
+    w.put_u64(rid.page_id.0);
+    w.put_u16(rid.slot_id);
+}
+
+fn get_rid(r: &mut ByteReader) -> DecodeResult<RowId> {
+    Ok(RowId {
+        page_id: PageId(r.get_u64()?),
+        slot_id: r.get_u16()?,
+    })
+}
+
+/// An optional slot, `u16::MAX` standing for none.
+fn put_slot(w: &mut ByteWriter, slot: Option<u16>) {
+    w.put_u16(slot.unwrap_or(u16::MAX));
+}
+
+fn get_slot(r: &mut ByteReader) -> DecodeResult<Option<u16>> {
+    Ok(match r.get_u16()? {
+        u16::MAX => None,
+        slot => Some(slot),
+    })
+}
+
+fn put_values(w: &mut ByteWriter, values: &[Value]) {
+    let mut buf = Vec::new();
+    for v in values {
+        v.serialize(&mut buf);
+    }
+    w.put_u16(values.len() as u16);
+    w.put_bytes(&buf);
+}
+
+fn get_values(r: &mut ByteReader) -> DecodeResult<Vec<Value>> {
+    let count = r.get_u16()? as usize;
+    let mut input = r.get_bytes()?;
+    let mut values = Vec::with_capacity(count);
+    for _ in 0..count {
+        if input.is_empty() {
+            return Err("truncated value list");
+        }
+        values.push(Value::deserialize(&mut input));
+    }
+    Ok(values)
+}
+
+fn get_key(r: &mut ByteReader) -> DecodeResult<IndexKey> {
+    let mut input = r.get_bytes()?;
+    let key = IndexKey::deserialize(&mut input, 0).map_err(|_| "malformed index key")?;
+    if !input.is_empty() {
+        return Err("trailing bytes after index key");
+    }
+    Ok(key)
+}
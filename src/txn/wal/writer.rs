@@ -0,0 +1,324 @@
+//! Appends log records to the current segment and makes them durable.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    storage::{errors::StorageResult, page::page_id::PageId},
+    txn::{
+        snapshot::Snapshot,
+        transaction::TxnId,
+        wal::{
+            reader::{FRAME_HEADER_SIZE, io_err, list_segments, read_segment, segment_path},
+            record::{LogBody, LogRecord, Lsn},
+        },
+    },
+    util::checksum::crc32,
+};
+
+pub type WalHandle = Arc<Mutex<WalWriter>>;
+
+/// Segments are rolled over once they grow past this size.
+pub const DEFAULT_SEGMENT_SIZE: u64 = 16 * 1024 * 1024;
+
+pub struct WalWriter {
+    dir: PathBuf,
+    segment_size: u64,
+
+    /// Segment currently being appended to.
+    file: File,
+    segment_len: u64,
+
+    next_lsn: Lsn,
+    /// Every record up to and including this LSN has been fsynced.
+    durable_lsn: Lsn,
+
+    next_txn_id: u64,
+    /// Last record of each transaction that has not yet committed or
+    /// aborted, used to chain `prev_lsn`.
+    active: HashMap<TxnId, Lsn>,
+    /// Records of those transactions, kept so they can be rolled back
+    /// without re-reading the log.
+    undo: HashMap<TxnId, Vec<LogRecord>>,
+}
+
+impl WalWriter {
+    pub fn open(dir: &Path) -> StorageResult<Self> {
+        Self::open_with_segment_size(dir, DEFAULT_SEGMENT_SIZE)
+    }
+
+    /// Open (or create) the log in `dir`, continuing after the last intact
+    /// record. A torn tail left by a crash is cut off.
+    pub fn open_with_segment_size(dir: &Path, segment_size: u64) -> StorageResult<Self> {
+        fs::create_dir_all(dir).map_err(io_err)?;
+
+        let mut last_lsn = Lsn::INVALID;
+        let mut max_txn = 0;
+        let segments = list_segments(dir)?;
+
+        let (path, segment_len) = match segments.last() {
+            Some((first, path)) => {
+                // Earlier segments only matter for the txn id high-water mark;
+                // recovery reads them in full.
+                for (_, p) in &segments[..segments.len() - 1] {
+                    for rec in read_segment(p)?.0 {
+                        max_txn = max_txn.max(txn_high_water(&rec));
+                        last_lsn = rec.lsn;
+                    }
+                }
+
+                let (records, valid_len) = read_segment(path)?;
+                for rec in &records {
+                    max_txn = max_txn.max(txn_high_water(rec));
+                    last_lsn = rec.lsn;
+                }
+                if records.is_empty() && last_lsn == Lsn::INVALID {
+                    last_lsn = Lsn(first.0 - 1);
+                }
+                (path.clone(), valid_len)
+            }
+            None => (segment_path(dir, Lsn(1)), 0),
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(io_err)?;
+        file.set_len(segment_len).map_err(io_err)?;
+        file.sync_all().map_err(io_err)?;
+        sync_dir(dir)?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            segment_size,
+            file,
+            segment_len,
+            next_lsn: last_lsn.next(),
+            durable_lsn: last_lsn,
+            next_txn_id: max_txn + 1,
+            active: HashMap::new(),
+            undo: HashMap::new(),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn durable_lsn(&self) -> Lsn {
+        self.durable_lsn
+    }
+
+    /// LSN of the most recently appended record.
+    pub fn last_lsn(&self) -> Lsn {
+        Lsn(self.next_lsn.0 - 1)
+    }
+
+    pub fn next_txn_id(&self) -> TxnId {
+        TxnId(self.next_txn_id)
+    }
+
+    /// Append a record for `txn` and return its LSN. The record is written
+    /// to the segment file but not yet fsynced.
+    pub fn append(&mut self, txn: TxnId, body: LogBody) -> StorageResult<Lsn> {
+        let lsn = self.next_lsn;
+        let prev_lsn = self.active.get(&txn).copied().unwrap_or(Lsn::INVALID);
+
+        let ends_txn = matches!(body, LogBody::Commit | LogBody::Abort);
+        let record = LogRecord {
+            lsn,
+            prev_lsn,
+            txn_id: txn,
+            body,
+        };
+        let payload = record.encode();
+        let body = record.body;
+
+        let frame_len = (FRAME_HEADER_SIZE + payload.len()) as u64;
+        if self.segment_len > 0 && self.segment_len + frame_len > self.segment_size {
+            self.roll_segment(lsn)?;
+        }
+
+        let mut frame = Vec::with_capacity(frame_len as usize);
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&crc32(&payload).to_le_bytes());
+        frame.extend_from_slice(&payload);
+        self.file.write_all(&frame).map_err(io_err)?;
+        self.segment_len += frame_len;
+
+        self.next_lsn = lsn.next();
+        if txn != TxnId::NONE {
+            if ends_txn {
+                self.active.remove(&txn);
+                self.undo.remove(&txn);
+            } else {
+                self.active.insert(txn, lsn);
+                self.undo.entry(txn).or_default().push(LogRecord {
+                    lsn,
+                    prev_lsn,
+                    txn_id: txn,
+                    body,
+                });
+            }
+        }
+        Ok(lsn)
+    }
+
+    /// Start a transaction and log its `Begin` record.
+    pub fn begin(&mut self) -> StorageResult<TxnId> {
+        let txn = TxnId(self.next_txn_id);
+        self.next_txn_id += 1;
+        self.append(txn, LogBody::Begin)?;
+        Ok(txn)
+    }
+
+    /// Hand out a transaction id without starting a transaction. Read-only
+    /// statements use one to own their locks.
+    pub fn allocate_txn_id(&mut self) -> TxnId {
+        let txn = TxnId(self.next_txn_id);
+        self.next_txn_id += 1;
+        txn
+    }
+
+    /// Log `Commit` for `txn` and force the log; the transaction is durable
+    /// once this returns.
+    pub fn commit(&mut self, txn: TxnId) -> StorageResult<Lsn> {
+        let lsn = self.append(txn, LogBody::Commit)?;
+        self.flush()?;
+        Ok(lsn)
+    }
+
+    pub fn abort(&mut self, txn: TxnId) -> StorageResult<Lsn> {
+        self.append(txn, LogBody::Abort)
+    }
+
+    /// Transactions that have begun but not yet committed or aborted, with
+    /// the LSN of their last record.
+    pub fn active_txns(&self) -> Vec<(TxnId, Lsn)> {
+        let mut txns: Vec<_> = self.active.iter().map(|(t, l)| (*t, *l)).collect();
+        txns.sort();
+        txns
+    }
+
+    /// Re-register a transaction recovery found unfinished in the log, so
+    /// the records written to roll it back chain onto its history.
+    pub fn resume(&mut self, txn: TxnId, last: Lsn) {
+        self.active.insert(txn, last);
+    }
+
+    /// Take a snapshot for `txn` (`TxnId::NONE` for a read outside any
+    /// transaction): it sees what has committed so far and `txn`'s own work.
+    pub fn snapshot(&self, txn: TxnId) -> Snapshot {
+        Snapshot::new(txn, TxnId(self.next_txn_id), self.active.keys().copied())
+    }
+
+    /// Oldest transaction still running, or the next id to be handed out if
+    /// none is.
+    pub fn oldest_active(&self) -> TxnId {
+        self.active
+            .keys()
+            .copied()
+            .fold(TxnId(self.next_txn_id), TxnId::min)
+    }
+
+    /// LSN of the last record `txn` has written, or `Lsn::INVALID` if it
+    /// is not active.
+    pub fn txn_last_lsn(&self, txn: TxnId) -> Lsn {
+        self.active.get(&txn).copied().unwrap_or(Lsn::INVALID)
+    }
+
+    /// Records `txn` has written so far, oldest first. Empty once it has
+    /// committed or aborted.
+    pub fn txn_records(&self, txn: TxnId) -> Vec<LogRecord> {
+        self.undo.get(&txn).cloned().unwrap_or_default()
+    }
+
+    /// Log a checkpoint and force it. The caller must have flushed every page
+    /// that is not listed in `dirty_pages`.
+    pub fn checkpoint(&mut self, dirty_pages: Vec<(PageId, Lsn)>) -> StorageResult<Lsn> {
+        let body = LogBody::Checkpoint {
+            next_txn_id: TxnId(self.next_txn_id),
+            active_txns: self.active_txns(),
+            dirty_pages,
+        };
+        let lsn = self.append(TxnId::NONE, body)?;
+        self.flush()?;
+        Ok(lsn)
+    }
+
+    /// Make every appended record durable.
+    pub fn flush(&mut self) -> StorageResult<()> {
+        if self.durable_lsn == self.last_lsn() {
+            return Ok(());
+        }
+        self.file.sync_data().map_err(io_err)?;
+        self.durable_lsn = self.last_lsn();
+        Ok(())
+    }
+
+    /// Delete segments that contain only records older than `lsn`. The
+    /// current segment is never removed. Returns the number of segments
+    /// deleted.
+    pub fn truncate_before(&mut self, lsn: Lsn) -> StorageResult<usize> {
+        let segments = list_segments(&self.dir)?;
+        let mut removed = 0;
+        for pair in segments.windows(2) {
+            let (_, path) = &pair[0];
+            let (next_first, _) = pair[1];
+            if next_first > lsn {
+                break;
+            }
+            fs::remove_file(path).map_err(io_err)?;
+            removed += 1;
+        }
+        if removed > 0 {
+            sync_dir(&self.dir)?;
+        }
+        Ok(removed)
+    }
+
+    fn roll_segment(&mut self, first: Lsn) -> StorageResult<()> {
+        // Records in the old segment must not be lost behind newer ones.
+        self.file.sync_data().map_err(io_err)?;
+        self.durable_lsn = self.last_lsn();
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(segment_path(&self.dir, first))
+            .map_err(io_err)?;
+        self.segment_len = 0;
+        sync_dir(&self.dir)
+    }
+}
+
+/// Append `body` to the log if one is attached. Without a log (e.g. a bare
+/// buffer pool in tests) nothing is recorded and `Lsn::INVALID` is returned.
+pub fn log_record(wal: Option<&WalHandle>, txn: TxnId, body: LogBody) -> StorageResult<Lsn> {
+    match wal {
+        Some(wal) => wal.lock().unwrap().append(txn, body),
+        None => Ok(Lsn::INVALID),
+    }
+}
+
+fn txn_high_water(rec: &LogRecord) -> u64 {
+    match &rec.body {
+        LogBody::Checkpoint { next_txn_id, .. } => {
+            rec.txn_id.0.max(next_txn_id.0.saturating_sub(1))
+        }
+        _ => rec.txn_id.0,
+    }
+}
+
+fn sync_dir(dir: &Path) -> StorageResult<()> {
+    // Persist directory entries for newly created or removed segments.
+    let d = File::open(dir).map_err(io_err)?;
+    let _ = d.sync_all();
+    Ok(())
+}
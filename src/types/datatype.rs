@@ -6,6 +6,7 @@
 #[non_exhaustive]
 pub enum DataType {
     // Integer types
+    Int16,
     Int32,
     Int64,
 
@@ -22,6 +23,7 @@ pub enum DataType {
     // Temporal
     Date,      // days since epoch
     Timestamp, // microseconds since epoch
+    Interval,  // months and microseconds
 
     // Binary
     Blob,
@@ -33,6 +35,7 @@ pub enum DataType {
 impl std::fmt::Display for DataType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            DataType::Int16 => write!(f, "SMALLINT"),
             DataType::Int32 => write!(f, "INT"),
             DataType::Int64 => write!(f, "BIGINT"),
             DataType::Float32 => write!(f, "FLOAT"),
@@ -41,6 +44,7 @@ impl std::fmt::Display for DataType {
             DataType::Varchar { .. } => write!(f, "VARCHAR"),
             DataType::Date => write!(f, "DATE"),
             DataType::Timestamp => write!(f, "TIMESTAMP"),
+            DataType::Interval => write!(f, "INTERVAL"),
             DataType::Blob => write!(f, "BLOB"),
             DataType::Null => write!(f, "NULL"),
         }
@@ -2,7 +2,13 @@
 //!
 //! Schemas describe *shape*, not storage or ownership.
 
-use crate::catalog::{column::ColumnMeta, ids::ColumnId};
+use crate::{
+    catalog::{
+        column::ColumnMeta,
+        ids::{ColumnId, TableId},
+    },
+    types::datatype::DataType,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Schema {
@@ -31,7 +37,76 @@ impl Schema {
         self.columns.iter().find(|c| c.id == id)
     }
 
+    /// Position of a column within a row of this schema.
+    pub fn position(&self, id: ColumnId) -> Option<usize> {
+        self.columns.iter().position(|c| c.id == id)
+    }
+
     pub fn push(&mut self, column: ColumnMeta) {
         self.columns.push(column);
     }
 }
+
+/// Shape of the rows a query returns.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OutputSchema {
+    pub columns: Vec<OutputColumn>,
+}
+
+/// One column of a query result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputColumn {
+    /// Column name, or its alias when the query gives one.
+    pub name: String,
+    pub data_type: DataType,
+    pub nullable: bool,
+    /// Table the values are read from; `None` for computed values.
+    pub table: Option<TableId>,
+}
+
+impl OutputSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Columns of `schema`, read straight from `table`.
+    pub fn of_table(table: TableId, schema: &Schema) -> Self {
+        Self {
+            columns: schema
+                .columns
+                .iter()
+                .map(|c| OutputColumn::of_table(table, c))
+                .collect(),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Position of the first column called `name`.
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.name == name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.columns.iter().map(|c| c.name.as_str())
+    }
+}
+
+impl OutputColumn {
+    pub fn of_table(table: TableId, column: &ColumnMeta) -> Self {
+        Self {
+            name: column.name.clone(),
+            data_type: column.data_type.clone(),
+            nullable: column.nullable,
+            table: Some(table),
+        }
+    }
+}
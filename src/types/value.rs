@@ -4,10 +4,12 @@
 //! This is the canonical runtime value model.
 
 use crate::types::datatype::DataType;
+use crate::util::temporal;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 #[non_exhaustive]
 pub enum Value {
+    Int16(i16),
     Int32(i32),
     Int64(i64),
 
@@ -22,6 +24,11 @@ pub enum Value {
     // Temporal (encoded, not formatted)
     Date(i32),
     Timestamp(i64),
+    /// Months apart from microseconds, as a month has no fixed length.
+    Interval {
+        months: i32,
+        micros: i64,
+    },
 
     // Explicit NULL
     Null,
@@ -33,6 +40,7 @@ impl Value {
     /// NOTE: This must stay trivial.
     pub fn data_type(&self) -> DataType {
         match self {
+            Value::Int16(_) => DataType::Int16,
             Value::Int32(_) => DataType::Int32,
             Value::Int64(_) => DataType::Int64,
             Value::Float32(_) => DataType::Float32,
@@ -42,6 +50,7 @@ impl Value {
             Value::Blob(_) => DataType::Blob,
             Value::Date(_) => DataType::Date,
             Value::Timestamp(_) => DataType::Timestamp,
+            Value::Interval { .. } => DataType::Interval,
             Value::Null => DataType::Null,
         }
     }
@@ -57,10 +66,22 @@ impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Null => write!(f, "NULL"),
+            Value::Int16(v) => write!(f, "{v}"),
+            Value::Int32(v) => write!(f, "{v}"),
             Value::Int64(v) => write!(f, "{v}"),
+            Value::Float32(v) => write!(f, "{v}"),
             Value::Float64(v) => write!(f, "{v}"),
+            Value::Boolean(v) => write!(f, "{v}"),
             Value::String(v) => write!(f, "{v}"),
-            _ => write!(f, "<value>"),
+            Value::Blob(b) => {
+                write!(f, "\\x")?;
+                b.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+            }
+            Value::Date(d) => write!(f, "{}", temporal::format_date(*d)),
+            Value::Timestamp(ts) => write!(f, "{}", temporal::format_timestamp(*ts)),
+            Value::Interval { months, micros } => {
+                write!(f, "{}", temporal::format_interval(*months, *micros))
+            }
         }
     }
 }
@@ -68,6 +89,11 @@ impl std::fmt::Display for Value {
 impl Value {
     pub fn serialize(&self, buf: &mut Vec<u8>) {
         match self {
+            Value::Int16(v) => {
+                buf.push(9);
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+
             Value::Int32(v) => {
                 buf.push(0);
                 buf.extend_from_slice(&v.to_le_bytes());
@@ -116,6 +142,12 @@ impl Value {
                 buf.extend_from_slice(&ts.to_le_bytes());
             }
 
+            Value::Interval { months, micros } => {
+                buf.push(10);
+                buf.extend_from_slice(&months.to_le_bytes());
+                buf.extend_from_slice(&micros.to_le_bytes());
+            }
+
             Value::Null => {
                 buf.push(255);
             }
@@ -140,6 +172,12 @@ impl Value {
                 Value::Int32(i32::from_le_bytes(b.try_into().unwrap()))
             }
 
+            9 => {
+                let (b, rest) = input.split_at(2);
+                *input = rest;
+                Value::Int16(i16::from_le_bytes(b.try_into().unwrap()))
+            }
+
             1 => {
                 let (b, rest) = input.split_at(8);
                 *input = rest;
@@ -192,6 +230,16 @@ impl Value {
                 Value::Timestamp(i64::from_le_bytes(b.try_into().unwrap()))
             }
 
+            10 => {
+                let (m, rest) = input.split_at(4);
+                let (b, rest) = rest.split_at(8);
+                *input = rest;
+                Value::Interval {
+                    months: i32::from_le_bytes(m.try_into().unwrap()),
+                    micros: i64::from_le_bytes(b.try_into().unwrap()),
+                }
+            }
+
             255 => Value::Null,
 
             _ => panic!("unknown Value tag {}", tag),
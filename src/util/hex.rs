@@ -0,0 +1,13 @@
+//! Hexadecimal text of binary values, as in `x'00ff'`.
+
+/// Bytes spelled by pairs of hex digits, or `None` if `digits` is not
+/// made of such pairs.
+pub fn decode(digits: &str) -> Option<Vec<u8>> {
+    if !digits.len().is_multiple_of(2) || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
@@ -0,0 +1,75 @@
+//! `LIKE` patterns, in which `%` matches any text, `_` any one character,
+//! and any other character itself. The escape character makes the one
+//! after it match itself, even if it is `%` or `_`.
+
+enum Piece {
+    Any,
+    One,
+    Char(char),
+}
+
+/// The pieces of `pattern`, or `None` if it ends in the escape character.
+fn pieces(pattern: &str, escape: Option<char>) -> Option<Vec<Piece>> {
+    let mut pieces = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        pieces.push(match c {
+            c if Some(c) == escape => Piece::Char(chars.next()?),
+            '%' => Piece::Any,
+            '_' => Piece::One,
+            c => Piece::Char(c),
+        });
+    }
+    Some(pieces)
+}
+
+/// Whether `text` matches `pattern`, or `None` if the pattern ends in the
+/// escape character.
+pub fn matches(
+    text: &str,
+    pattern: &str,
+    escape: Option<char>,
+    case_insensitive: bool,
+) -> Option<bool> {
+    let pieces = pieces(pattern, escape)?;
+    let text: Vec<char> = text.chars().collect();
+    let same =
+        |a: char, b: char| a == b || (case_insensitive && a.to_lowercase().eq(b.to_lowercase()));
+
+    // Where the last `%` was seen, in the pattern and in the text; when the
+    // pieces after it fail to match, it takes in one more character.
+    let mut retry = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pieces.get(p) {
+            Some(Piece::Any) => {
+                retry = Some((p, t));
+                p += 1;
+            }
+            Some(Piece::One) => (p, t) = (p + 1, t + 1),
+            Some(Piece::Char(c)) if same(*c, text[t]) => (p, t) = (p + 1, t + 1),
+            _ => match retry {
+                Some((any, from)) => {
+                    retry = Some((any, from + 1));
+                    (p, t) = (any + 1, from + 1);
+                }
+                None => return Some(false),
+            },
+        }
+    }
+    Some(pieces[p..].iter().all(|piece| matches!(piece, Piece::Any)))
+}
+
+/// Text every match of `pattern` starts with, or `None` if the pattern ends
+/// in the escape character.
+pub fn prefix(pattern: &str, escape: Option<char>) -> Option<String> {
+    Some(
+        pieces(pattern, escape)?
+            .iter()
+            .map_while(|piece| match piece {
+                Piece::Char(c) => Some(*c),
+                Piece::Any | Piece::One => None,
+            })
+            .collect(),
+    )
+}
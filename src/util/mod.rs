@@ -0,0 +1,6 @@
+pub mod bytes;
+pub mod checksum;
+pub mod hex;
+pub mod like;
+pub mod numeric;
+pub mod temporal;
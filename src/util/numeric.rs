@@ -0,0 +1,36 @@
+//! Exact comparison of numbers of different kinds.
+//!
+//! Converting an `i64` to `f64` loses precision past 2^53, so integers and
+//! floats are compared by their integral and fractional parts instead.
+
+use std::cmp::Ordering;
+
+/// Order of two floats, NaN above every other value and -0 equal to 0.
+pub fn cmp_float(a: f64, b: f64) -> Ordering {
+    if a == b {
+        Ordering::Equal
+    } else {
+        a.total_cmp(&b)
+    }
+}
+
+/// Order of the integer `i` relative to the float `f`.
+pub fn cmp_int_float(i: i64, f: f64) -> Ordering {
+    if f.is_nan() || f >= 9_223_372_036_854_775_808.0 {
+        return Ordering::Less;
+    }
+    if f < -9_223_372_036_854_775_808.0 {
+        return Ordering::Greater;
+    }
+    let whole = f.trunc();
+    match i.cmp(&(whole as i64)) {
+        Ordering::Equal => cmp_float(0.0, f - whole),
+        ord => ord,
+    }
+}
+
+/// `f` as an integer, if it is one that fits.
+pub fn float_as_int(f: f64) -> Option<i64> {
+    (f.fract() == 0.0 && cmp_int_float(i64::MIN, f).is_le() && cmp_int_float(i64::MAX, f).is_ge())
+        .then_some(f as i64)
+}
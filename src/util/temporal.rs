@@ -0,0 +1,219 @@
+//! Text forms of the encoded temporal values: dates are days since
+//! 1970-01-01, timestamps microseconds since its midnight, and intervals
+//! months and microseconds.
+//!
+//! Conversions use the proleptic Gregorian calendar, following
+//! <https://howardhinnant.github.io/date_algorithms.html>.
+
+pub const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+
+/// Days since the epoch of `year-month-day`. The date must be valid.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// `(year, month, day)` of the date `days` after the epoch.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+/// Parse `YYYY-MM-DD`.
+pub fn parse_date(s: &str) -> Option<i32> {
+    let days = parse_days(s.trim())?;
+    i32::try_from(days).ok()
+}
+
+/// Parse `YYYY-MM-DD[ HH:MM[:SS[.ffffff]]]`, with `T` also allowed
+/// between the date and the time.
+pub fn parse_timestamp(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (date, time) = match s.find([' ', 'T']) {
+        Some(at) => (&s[..at], Some(s[at + 1..].trim_start())),
+        None => (s, None),
+    };
+    let days = parse_days(date)?;
+    let micros = match time {
+        Some(time) => parse_time(time)?,
+        None => 0,
+    };
+    days.checked_mul(MICROS_PER_DAY)?.checked_add(micros)
+}
+
+pub fn format_date(days: i32) -> String {
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Fractional seconds are shown only when there are any.
+pub fn format_timestamp(micros: i64) -> String {
+    let days = micros.div_euclid(MICROS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    let mut out = format!("{:04}-{:02}-{:02} ", year, month, day);
+    push_time(&mut out, micros.rem_euclid(MICROS_PER_DAY));
+    out
+}
+
+/// `ts` moved by `months` months. The day of the month is kept where the
+/// target month has it, and its last day is taken otherwise.
+pub fn add_months(ts: i64, months: i64) -> Option<i64> {
+    let days = ts.div_euclid(MICROS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    let month0 = (year.checked_mul(12)? + month as i64 - 1).checked_add(months)?;
+    let (year, month) = (month0.div_euclid(12), month0.rem_euclid(12) as u32 + 1);
+    // Far outside the range of timestamps; the date algorithm would
+    // overflow first.
+    if year.abs() > 1_000_000 {
+        return None;
+    }
+    let day = day.min(days_in_month(year, month));
+    days_from_civil(year, month, day)
+        .checked_mul(MICROS_PER_DAY)?
+        .checked_add(ts.rem_euclid(MICROS_PER_DAY))
+}
+
+/// Parse an interval as `(months, microseconds)`: a sequence of signed
+/// quantities with units, such as `1 year -2 days`, and times of day,
+/// such as `-01:30`.
+pub fn parse_interval(s: &str) -> Option<(i32, i64)> {
+    let (mut months, mut micros) = (0_i64, 0_i64);
+    let mut words = s.split_whitespace().peekable();
+    words.peek()?;
+    while let Some(word) = words.next() {
+        let (negative, digits) = match word.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, word.strip_prefix('+').unwrap_or(word)),
+        };
+        let sign = if negative { -1 } else { 1 };
+        if digits.contains(':') {
+            micros = micros.checked_add(sign * parse_time(digits)?)?;
+            continue;
+        }
+        let n = sign * parse_digits(digits, 1, 18)?;
+        let unit = words.next()?.to_ascii_lowercase();
+        let (month_len, micro_len) = match unit.trim_end_matches('s') {
+            "year" => (12, 0),
+            "month" | "mon" => (1, 0),
+            "week" => (0, 7 * MICROS_PER_DAY),
+            "day" => (0, MICROS_PER_DAY),
+            "hour" => (0, 3600 * MICROS_PER_SECOND),
+            "minute" | "min" => (0, 60 * MICROS_PER_SECOND),
+            "second" | "sec" => (0, MICROS_PER_SECOND),
+            "millisecond" => (0, 1000),
+            "microsecond" => (0, 1),
+            _ => return None,
+        };
+        months = months.checked_add(n.checked_mul(month_len)?)?;
+        micros = micros.checked_add(n.checked_mul(micro_len)?)?;
+    }
+    Some((i32::try_from(months).ok()?, micros))
+}
+
+/// Years, months and days where there are any, then the time of day
+/// where there is one, as in `1 year 2 months 3 days 04:05:06`.
+pub fn format_interval(months: i32, micros: i64) -> String {
+    let mut parts = Vec::new();
+    let days = micros / MICROS_PER_DAY;
+    let units = [
+        (months as i64 / 12, "year"),
+        (months as i64 % 12, "month"),
+        (days, "day"),
+    ];
+    for (n, unit) in units {
+        if n != 0 {
+            let plural = if n.abs() == 1 { "" } else { "s" };
+            parts.push(format!("{} {}{}", n, unit, plural));
+        }
+    }
+    let time = micros % MICROS_PER_DAY;
+    if time != 0 || parts.is_empty() {
+        let mut out = String::from(if time < 0 { "-" } else { "" });
+        push_time(&mut out, time.abs());
+        parts.push(out);
+    }
+    parts.join(" ")
+}
+
+/// `HH:MM:SS` of the time `micros` into a day, with fractional seconds
+/// only when there are any.
+fn push_time(out: &mut String, micros: i64) {
+    let secs = micros / MICROS_PER_SECOND;
+    out.push_str(&format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    ));
+    let frac = micros % MICROS_PER_SECOND;
+    if frac != 0 {
+        let digits = format!("{:06}", frac);
+        out.push('.');
+        out.push_str(digits.trim_end_matches('0'));
+    }
+}
+
+fn parse_days(s: &str) -> Option<i64> {
+    let mut parts = s.splitn(3, '-');
+    let year = parse_digits(parts.next()?, 4, 6)?;
+    let month = parse_digits(parts.next()?, 1, 2)? as u32;
+    let day = parse_digits(parts.next()?, 1, 2)? as u32;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+/// Microseconds into the day of `HH:MM[:SS[.ffffff]]`.
+fn parse_time(s: &str) -> Option<i64> {
+    let mut parts = s.splitn(3, ':');
+    let hour = parse_digits(parts.next()?, 1, 2)?;
+    let minute = parse_digits(parts.next()?, 2, 2)?;
+    let (second, frac) = match parts.next() {
+        Some(rest) => match rest.split_once('.') {
+            Some((second, frac)) => (parse_digits(second, 2, 2)?, Some(frac)),
+            None => (parse_digits(rest, 2, 2)?, None),
+        },
+        None => (0, None),
+    };
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let micros = match frac {
+        Some(frac) => parse_digits(frac, 1, 6)? * 10_i64.pow(6 - frac.len() as u32),
+        None => 0,
+    };
+    Some(((hour * 60 + minute) * 60 + second) * MICROS_PER_SECOND + micros)
+}
+
+/// `s` as a number of between `min` and `max` decimal digits.
+fn parse_digits(s: &str, min: usize, max: usize) -> Option<i64> {
+    if s.len() < min || s.len() > max || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
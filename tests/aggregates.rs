@@ -0,0 +1,193 @@
+mod common;
+
+use helium::{
+    api::{
+        db::Database,
+        errors::{DbError, QueryResult},
+    },
+    binder::errors::BindError,
+    execution::errors::ExecutionResult,
+    types::value::Value,
+};
+
+use common::{int, temp_db, text};
+
+fn sales(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE sales (id BIGINT, region TEXT, amount BIGINT, discount BIGINT)",
+        "INSERT INTO sales VALUES (1, 'north', 10, 1), (2, 'south', 20, NULL), \
+         (3, 'north', 30, 1), (4, 'east', 5, 2), (5, 'south', 20, NULL)",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+fn query(db: &mut Database, sql: &str) -> QueryResult {
+    match db.execute(sql).unwrap() {
+        ExecutionResult::Query(q) => q,
+        _ => panic!("expected a query result"),
+    }
+}
+
+#[test]
+fn aggregates_over_the_whole_table() {
+    let mut db = sales("whole");
+
+    let q = query(
+        &mut db,
+        "SELECT COUNT(*), COUNT(discount), COUNT(DISTINCT amount), SUM(amount), \
+         AVG(amount), MIN(region), MAX(amount) FROM sales",
+    );
+    assert_eq!(
+        q.rows,
+        vec![vec![
+            int(5),
+            int(3),
+            int(4),
+            int(85),
+            Value::Float64(17.0),
+            text("east"),
+            int(30),
+        ]]
+    );
+    assert_eq!(
+        q.schema.names().collect::<Vec<_>>(),
+        vec!["count", "count", "count", "sum", "avg", "min", "max"]
+    );
+    assert!(!q.schema.columns[0].nullable);
+    assert!(q.schema.columns[3].nullable);
+}
+
+#[test]
+fn groups_are_filtered_by_having_and_sorted_by_aggregates() {
+    let mut db = sales("grouped");
+
+    let q = query(
+        &mut db,
+        "SELECT region, SUM(amount) AS total, COUNT(*) FROM sales \
+         WHERE id > 1 GROUP BY region HAVING COUNT(*) > 1 OR MIN(amount) < 10 \
+         ORDER BY SUM(amount) DESC",
+    );
+    assert_eq!(
+        q.rows,
+        vec![
+            vec![text("south"), int(40), int(2)],
+            vec![text("east"), int(5), int(1)],
+        ]
+    );
+    assert_eq!(q.schema.position("total"), Some(1));
+
+    // Keys may be expressions; the same expression may be projected.
+    assert_eq!(
+        query(
+            &mut db,
+            "SELECT amount / 10, COUNT(*) FROM sales GROUP BY amount / 10 ORDER BY amount / 10"
+        )
+        .rows,
+        vec![
+            vec![int(0), int(1)],
+            vec![int(1), int(1)],
+            vec![int(2), int(2)],
+            vec![int(3), int(1)],
+        ]
+    );
+}
+
+#[test]
+fn empty_input_gives_one_row_only_without_group_by() {
+    let mut db = sales("empty");
+
+    assert_eq!(
+        query(
+            &mut db,
+            "SELECT COUNT(*), SUM(amount), MAX(region) FROM sales WHERE id > 100"
+        )
+        .rows,
+        vec![vec![int(0), Value::Null, Value::Null]]
+    );
+    assert!(
+        query(
+            &mut db,
+            "SELECT region, COUNT(*) FROM sales WHERE id > 100 GROUP BY region"
+        )
+        .rows
+        .is_empty()
+    );
+}
+
+#[test]
+fn numbers_that_compare_equal_share_a_group() {
+    let path = temp_db("equal_keys");
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    db.execute("CREATE TABLE readings (level DOUBLE PRECISION, weight BIGINT)")
+        .unwrap();
+    db.execute("INSERT INTO readings VALUES (0.0, 1), (-0.0, 2), (2.5, 3), (2.0, 4), (0.0, 5)")
+        .unwrap();
+
+    // -0.0 equals 0.0, so both fall in the first group seen.
+    assert_eq!(
+        query(
+            &mut db,
+            "SELECT level, SUM(weight) FROM readings GROUP BY level"
+        )
+        .rows,
+        vec![
+            vec![Value::Float64(0.0), int(8)],
+            vec![Value::Float64(2.5), int(3)],
+            vec![Value::Float64(2.0), int(4)],
+        ]
+    );
+    // The weight 2 and the level 2.0 count as one value.
+    assert_eq!(
+        query(
+            &mut db,
+            "SELECT COUNT(DISTINCT level), \
+             COUNT(DISTINCT CASE WHEN weight = 4 THEN level ELSE weight END) FROM readings"
+        )
+        .rows,
+        vec![vec![int(3), int(4)]]
+    );
+}
+
+#[test]
+fn misplaced_aggregates_and_ungrouped_columns_are_rejected() {
+    let mut db = sales("errors");
+
+    assert!(matches!(
+        db.execute("SELECT region, amount FROM sales GROUP BY region"),
+        Err(DbError::Bind(BindError::UngroupedColumn(_)))
+    ));
+    assert!(matches!(
+        db.execute("SELECT id FROM sales WHERE COUNT(*) > 1"),
+        Err(DbError::Bind(BindError::MisplacedAggregate(_)))
+    ));
+    assert!(matches!(
+        db.execute("SELECT SUM(region) FROM sales"),
+        Err(DbError::Bind(_))
+    ));
+    assert!(matches!(
+        db.execute("SELECT COUNT(MAX(amount)) FROM sales"),
+        Err(DbError::Bind(BindError::MisplacedAggregate(_)))
+    ));
+}
+
+#[test]
+fn avg_does_not_overflow_where_sum_does() {
+    let path = temp_db("overflow");
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    db.execute("CREATE TABLE big (n BIGINT)").unwrap();
+    db.execute("INSERT INTO big VALUES (9223372036854775807), (1), (9223372036854775807)")
+        .unwrap();
+
+    assert_eq!(
+        query(&mut db, "SELECT AVG(n) FROM big").rows,
+        vec![vec![Value::Float64((2.0 * i64::MAX as f64 + 1.0) / 3.0)]]
+    );
+    assert!(matches!(
+        db.execute("SELECT SUM(n) FROM big"),
+        Err(DbError::Execution(_))
+    ));
+}
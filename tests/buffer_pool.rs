@@ -0,0 +1,183 @@
+mod common;
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use helium::{
+    api::db::Database,
+    catalog::ids::TableId,
+    storage::{
+        buffer::{
+            pool::{BufferPool, BufferPoolHandle},
+            replacer::{ClockReplacer, LruKReplacer, LruReplacer, Replacer},
+        },
+        errors::StorageError,
+        heap::heap_table::HeapTable,
+        index::btree::{disk::BPlusTree, key::IndexKey},
+        pagemgr::file::FilePageManager,
+    },
+    txn::{transaction::TxnId, wal::writer::WalWriter},
+    types::value::Value,
+};
+
+use common::temp_db;
+
+fn small_pool(path: &PathBuf, capacity: usize, replacer: Box<dyn Replacer>) -> BufferPool {
+    let pm = FilePageManager::open(path).unwrap();
+    BufferPool::with_replacer(Box::new(pm), capacity, replacer)
+}
+
+/// Touch frames `0..n` in order, then the frames in `again`, leave them all
+/// evictable and return the order in which the replacer gives them up.
+fn eviction_order(mut replacer: impl Replacer, n: usize, again: &[usize]) -> Vec<usize> {
+    for frame in 0..n {
+        replacer.record_access(frame);
+    }
+    for frame in again {
+        replacer.record_access(*frame);
+    }
+    for frame in 0..n {
+        replacer.set_evictable(frame, true);
+    }
+    std::iter::from_fn(|| replacer.evict()).collect()
+}
+
+#[test]
+fn replacers_choose_victims_by_their_policy() {
+    assert_eq!(
+        eviction_order(LruReplacer::new(), 4, &[0, 2]),
+        vec![1, 3, 0, 2]
+    );
+    // The hand clears every bit on its first sweep, then goes round in
+    // ring order.
+    assert_eq!(
+        eviction_order(ClockReplacer::new(), 4, &[0, 2]),
+        vec![0, 1, 2, 3]
+    );
+    // Frames accessed only once go first, oldest first; among the others
+    // the oldest second-to-last access loses.
+    assert_eq!(
+        eviction_order(LruKReplacer::new(2), 4, &[2, 0, 2]),
+        vec![1, 3, 0, 2]
+    );
+
+    // Pinned frames are never chosen.
+    let mut lru = LruReplacer::new();
+    for frame in 0..3 {
+        lru.record_access(frame);
+        lru.set_evictable(frame, true);
+    }
+    lru.set_evictable(0, false);
+    assert_eq!(lru.size(), 2);
+    assert_eq!(lru.evict(), Some(1));
+    assert_eq!(lru.evict(), Some(2));
+    assert_eq!(lru.evict(), None);
+}
+
+#[test]
+fn evicted_dirty_pages_are_written_back() {
+    let path = temp_db("writeback");
+    let bp: BufferPoolHandle = Arc::new(Mutex::new(small_pool(
+        &path,
+        4,
+        Box::new(ClockReplacer::new()),
+    )));
+
+    let pids: Vec<_> = (0..20u8)
+        .map(|i| {
+            let mut page = BufferPool::new_page(&bp).unwrap();
+            page.data[..4].copy_from_slice(&[i; 4]);
+            page.id
+        })
+        .collect();
+    assert_eq!(bp.lock().unwrap().resident_pages(), 4);
+
+    for (i, pid) in pids.iter().enumerate() {
+        let page = BufferPool::fetch_page_read(&bp, *pid).unwrap();
+        assert_eq!(page.data[..4], [i as u8; 4]);
+    }
+    let pool = bp.lock().unwrap();
+    assert!(pool.resident_pages() <= pool.capacity());
+}
+
+#[test]
+fn fetch_fails_cleanly_when_every_frame_is_pinned() {
+    let path = temp_db("pinned");
+    let bp: BufferPoolHandle = Arc::new(Mutex::new(small_pool(
+        &path,
+        2,
+        Box::new(LruReplacer::new()),
+    )));
+    let (a, b, c) = {
+        let mut pool = bp.lock().unwrap();
+        (
+            pool.allocate_page().unwrap(),
+            pool.allocate_page().unwrap(),
+            pool.allocate_page().unwrap(),
+        )
+    };
+
+    let held_b = BufferPool::fetch_page_write(&bp, b).unwrap();
+    let _held_c = BufferPool::fetch_page_read(&bp, c).unwrap();
+    assert!(matches!(
+        BufferPool::fetch_page_read(&bp, a),
+        Err(StorageError::PoolExhausted { capacity: 2 })
+    ));
+    assert!(matches!(
+        bp.lock().unwrap().allocate_page(),
+        Err(StorageError::PoolExhausted { .. })
+    ));
+
+    // Releasing a pin makes room again.
+    drop(held_b);
+    BufferPool::fetch_page_read(&bp, a).unwrap();
+
+    // A scan that cannot bring a page in ends with the error.
+    let heap = HeapTable::create(TableId(1), bp.clone()).unwrap();
+    heap.insert(TxnId::NONE, vec![Value::Int64(1)]).unwrap();
+    let _held_a = BufferPool::fetch_page_read(&bp, a).unwrap();
+    let mut rows = heap.scan();
+    assert!(matches!(
+        rows.next(),
+        Some(Err(StorageError::PoolExhausted { capacity: 2 }))
+    ));
+    assert!(rows.next().is_none());
+}
+
+#[test]
+fn tables_and_indexes_larger_than_the_pool_stay_readable() {
+    let path = temp_db("large");
+    let wal = Arc::new(Mutex::new(
+        WalWriter::open(&Database::wal_dir(&path)).unwrap(),
+    ));
+    let mut pool = small_pool(&path, 16, Box::new(LruKReplacer::new(2)));
+    pool.attach_wal(wal.clone());
+    let bp: BufferPoolHandle = Arc::new(Mutex::new(pool));
+
+    let heap = HeapTable::create(TableId(1), bp.clone()).unwrap();
+    let mut index = BPlusTree::new(16, bp.clone()).unwrap();
+
+    // Uncommitted changes are evicted too; the pool forces the log first.
+    let txn = wal.lock().unwrap().begin().unwrap();
+    for i in 0..3000 {
+        let rid = heap
+            .insert(
+                txn,
+                vec![Value::Int64(i), Value::String(format!("row {i}"))],
+            )
+            .unwrap();
+        index.insert(IndexKey::Int(i), rid).unwrap();
+    }
+    wal.lock().unwrap().commit(txn).unwrap();
+    assert!(heap.page_ids().len() > 16);
+
+    assert_eq!(heap.scan().count(), 3000);
+    for i in (0..3000).step_by(97) {
+        let rids = index.get(&IndexKey::Int(i)).unwrap();
+        assert_eq!(rids.len(), 1);
+        assert_eq!(heap.fetch(rids[0]).unwrap().values[0], Value::Int64(i));
+    }
+    assert!(bp.lock().unwrap().resident_pages() <= 16);
+}
@@ -0,0 +1,219 @@
+mod common;
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    binder::{bind_stmt::Binder, errors::BindError},
+    execution::errors::{ConversionFailure, ExecutionError, ExecutionResult},
+    frontend::sql::parser::Parser,
+    ir::plan::LogicalPlan,
+    optimizer::optimize,
+    planner::logical::LogicalPlanner,
+    types::{datatype::DataType, value::Value},
+};
+
+use common::{rows, temp_db};
+
+/// Events with a small and a large count each.
+fn events(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE events (id SMALLINT, hits BIGINT, score DOUBLE, note TEXT, \
+         day DATE, seen TIMESTAMP)",
+        "INSERT INTO events VALUES \
+         (1, 5, 0.5, '42', DATE '2026-01-01', TIMESTAMP '2026-01-01 09:00:00'), \
+         (2, 1, 2.5, 'yes', DATE '2026-01-02', TIMESTAMP '2026-01-02 18:30:00'), \
+         (3, 3, 3.0, '12x', DATE '2026-01-03', TIMESTAMP '2026-01-03 00:00:00')",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+fn conversion_failure(db: &mut Database, sql: &str) -> ConversionFailure {
+    match db.execute(sql) {
+        Err(DbError::Execution(ExecutionError::Conversion { reason, .. })) => reason,
+        other => panic!("expected a conversion error, got {:?}", other),
+    }
+}
+
+/// Whether `sql`'s optimized plan looks rows up through an index.
+fn uses_index(db: &Database, sql: &str) -> bool {
+    fn walk(plan: &LogicalPlan) -> bool {
+        match plan {
+            LogicalPlan::IndexScan { .. } => true,
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Project { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Limit { input, .. } => walk(input),
+            _ => false,
+        }
+    }
+    let catalog = db.catalog();
+    let stmt = Parser::new(sql).parse_statements().unwrap().remove(0);
+    let bound = Binder::new(&catalog).bind_statement(stmt).unwrap();
+    let plan = LogicalPlanner::new().plan(bound).unwrap();
+    walk(&optimize(&plan, &catalog).unwrap())
+}
+
+#[test]
+fn explicit_casts_convert_values() {
+    let mut db = events("explicit");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT CAST(note AS INT), score::int, -hits::real, id::text, CAST(seen AS DATE) \
+             FROM events WHERE id = 1"
+        ),
+        vec![vec![
+            Value::Int32(42),
+            Value::Int32(1),
+            Value::Float32(-5.0),
+            Value::String("1".into()),
+            Value::Date(20454),
+        ]]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT note::boolean, CAST(hits AS BOOLEAN), \
+             CAST('2026-01-02 18:30' AS TIMESTAMP) = seen FROM events WHERE id = 2"
+        ),
+        vec![vec![
+            Value::Boolean(true),
+            Value::Boolean(true),
+            Value::Boolean(true),
+        ]]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id FROM events WHERE score::int = 3 ORDER BY id"
+        ),
+        vec![vec![Value::Int16(2)], vec![Value::Int16(3)]]
+    );
+}
+
+#[test]
+fn failed_conversions_are_reported() {
+    let mut db = events("failures");
+
+    assert_eq!(
+        conversion_failure(&mut db, "SELECT note::int FROM events"),
+        ConversionFailure::InvalidText
+    );
+    assert_eq!(
+        conversion_failure(
+            &mut db,
+            "SELECT CAST(hits * 1000000000 AS SMALLINT) FROM events"
+        ),
+        ConversionFailure::OutOfRange
+    );
+    assert_eq!(
+        conversion_failure(&mut db, "SELECT note::varchar(2) FROM events"),
+        ConversionFailure::TooLong
+    );
+    assert_eq!(
+        conversion_failure(&mut db, "SELECT id FROM events WHERE day = 'soon'"),
+        ConversionFailure::InvalidText
+    );
+    assert!(matches!(
+        db.execute("SELECT CAST(day AS BLOB) FROM events"),
+        Err(DbError::Bind(BindError::InvalidCast {
+            from: DataType::Date,
+            to: DataType::Blob,
+        }))
+    ));
+}
+
+#[test]
+fn operands_of_different_types_are_converted() {
+    let mut db = events("implicit");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id, id + hits, id * score FROM events WHERE id < hits ORDER BY id"
+        ),
+        vec![vec![Value::Int16(1), Value::Int64(6), Value::Float64(0.5)]]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id FROM events WHERE day >= '2026-01-02' AND seen < '2026-01-03' \
+             AND day < seen"
+        ),
+        vec![vec![Value::Int16(2)]]
+    );
+
+    // Set operations widen each column to the type that holds both sides.
+    let result = match db
+        .execute(
+            "SELECT id, day FROM events WHERE id = 1 \
+             UNION ALL SELECT score, seen FROM events WHERE id = 3",
+        )
+        .unwrap()
+    {
+        ExecutionResult::Query(q) => q,
+        _ => panic!("expected a query result"),
+    };
+    let types: Vec<DataType> = result
+        .schema
+        .columns
+        .iter()
+        .map(|c| c.data_type.clone())
+        .collect();
+    assert_eq!(types, vec![DataType::Float64, DataType::Timestamp]);
+    assert_eq!(
+        result.rows,
+        vec![
+            vec![
+                Value::Float64(1.0),
+                Value::Timestamp(20454 * 86_400_000_000)
+            ],
+            vec![
+                Value::Float64(3.0),
+                Value::Timestamp(20456 * 86_400_000_000)
+            ],
+        ]
+    );
+
+    db.execute("INSERT INTO events VALUES (4, 2.6, 1, 'n', DATE '2026-01-04', DATE '2026-01-04')")
+        .unwrap();
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT hits, score, day, seen FROM events WHERE id = 4"
+        ),
+        vec![vec![
+            Value::Int64(3),
+            Value::Float64(1.0),
+            Value::Date(20457),
+            Value::Timestamp(20457 * 86_400_000_000),
+        ]]
+    );
+}
+
+#[test]
+fn comparison_literals_keep_index_lookups() {
+    let mut db = events("indexes");
+    db.execute("CREATE INDEX events_id ON events (id)").unwrap();
+    db.execute("CREATE INDEX events_day ON events (day)")
+        .unwrap();
+
+    assert!(uses_index(&db, "SELECT hits FROM events WHERE id = 2"));
+    assert!(uses_index(&db, "SELECT hits FROM events WHERE id = 2.0"));
+    assert!(uses_index(
+        &db,
+        "SELECT hits FROM events WHERE day = '2026-01-03'"
+    ));
+    assert_eq!(
+        rows(&mut db, "SELECT hits FROM events WHERE day = '2026-01-03'"),
+        vec![vec![Value::Int64(3)]]
+    );
+
+    // No SMALLINT equals 2.5, so the column is compared as a float.
+    assert!(!uses_index(&db, "SELECT hits FROM events WHERE id = 2.5"));
+    assert!(rows(&mut db, "SELECT hits FROM events WHERE id = 2.5").is_empty());
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use helium::{
+    api::db::Database,
+    catalog::{catalog::Catalog, ids::ColumnId},
+    storage::{
+        buffer::pool::{BufferPool, BufferPoolHandle},
+        pagemgr::file::FilePageManager,
+    },
+    types::datatype::DataType,
+};
+
+use common::temp_db;
+
+fn open_pool(path: &PathBuf) -> BufferPoolHandle {
+    let pm = FilePageManager::open(path).unwrap();
+    Arc::new(Mutex::new(BufferPool::new(Box::new(pm))))
+}
+
+fn user_columns() -> Vec<(String, DataType, bool)> {
+    vec![
+        ("id".into(), DataType::Int64, false),
+        ("name".into(), DataType::Varchar { max_len: Some(64) }, true),
+    ]
+}
+
+#[test]
+fn fresh_database_starts_with_empty_catalog() {
+    let path = temp_db("fresh");
+
+    let db = Database::new(path.to_string_lossy().into()).unwrap();
+    assert!(db.catalog().get_table_by_name("users").is_none());
+    drop(db);
+
+    // Reopening the initialized file must not fail.
+    let db = Database::new(path.to_string_lossy().into()).unwrap();
+    assert!(db.catalog().get_table_by_name("users").is_none());
+}
+
+#[test]
+fn tables_and_indexes_survive_reopen() {
+    let path = temp_db("reopen");
+
+    {
+        let bp = open_pool(&path);
+        let mut catalog = Catalog::load(&bp).unwrap();
+        let users = catalog
+            .create_table("users".into(), user_columns(), bp.clone())
+            .unwrap();
+        let id_col = catalog.get_table_by_name("users").unwrap().schema.columns[0].id;
+        let idx = catalog
+            .create_index("users_id".into(), users, vec![id_col], true, bp.clone())
+            .unwrap();
+        catalog.register_index_with_table(users, idx);
+        catalog.persist(&bp).unwrap();
+    }
+
+    let bp = open_pool(&path);
+    let mut catalog = Catalog::load(&bp).unwrap();
+
+    let users = catalog.get_table_by_name("users").unwrap();
+    assert_eq!(users.schema.columns.len(), 2);
+    assert_eq!(users.schema.columns[0].id, ColumnId(1));
+    assert_eq!(users.schema.columns[1].name, "name");
+    assert_eq!(
+        users.schema.columns[1].data_type,
+        DataType::Varchar { max_len: Some(64) }
+    );
+    assert!(!users.schema.columns[0].nullable);
+    assert_eq!(users.index_ids.len(), 1);
+    let users_id = users.id;
+
+    let idx = catalog.get_index_by_name("users_id").unwrap();
+    assert!(idx.meta.unique);
+    assert_eq!(idx.meta.table_id, users_id);
+
+    // Id counters continue where they left off.
+    let orders = catalog
+        .create_table(
+            "orders".into(),
+            vec![("id".into(), DataType::Int64, true)],
+            bp.clone(),
+        )
+        .unwrap();
+    assert_ne!(orders, users_id);
+    let orders = catalog.get_table_by_id(orders).unwrap();
+    assert_eq!(orders.schema.columns[0].id, ColumnId(3));
+}
+
+#[test]
+fn large_catalog_spans_pages_and_rewrites_reuse_space() {
+    let path = temp_db("large");
+    let bp = open_pool(&path);
+    let mut catalog = Catalog::load(&bp).unwrap();
+
+    for i in 0..200 {
+        catalog
+            .create_table(
+                format!("table_with_a_long_name_{i}"),
+                user_columns(),
+                bp.clone(),
+            )
+            .unwrap();
+    }
+    catalog.persist(&bp).unwrap();
+    let pages_after_first = bp.lock().unwrap().pm.num_pages();
+
+    for _ in 0..5 {
+        catalog.persist(&bp).unwrap();
+    }
+    // Superseded images are recycled instead of growing the file.
+    assert!(bp.lock().unwrap().pm.num_pages() <= pages_after_first * 2);
+    drop(catalog);
+    drop(bp);
+
+    let bp = open_pool(&path);
+    let catalog = Catalog::load(&bp).unwrap();
+    for i in 0..200 {
+        assert!(
+            catalog
+                .get_table_by_name(&format!("table_with_a_long_name_{i}"))
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    binder::errors::BindError,
+};
+
+use common::{int, rows, temp_db, text};
+
+/// Two tables, so that the columns of `orders` do not have the ids of its
+/// row positions.
+fn shop(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE customers (id BIGINT, name TEXT)",
+        "CREATE TABLE orders (order_id BIGINT, customer BIGINT, total BIGINT)",
+        "INSERT INTO customers VALUES (1, 'ann'), (2, 'bob'), (3, 'cid')",
+        "INSERT INTO orders VALUES (10, 1, 50), (11, 2, 20), (12, 1, 30), (13, 3, 70)",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+#[test]
+fn columns_of_later_tables_read_their_own_values() {
+    let mut db = shop("later");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT total, order_id FROM orders WHERE customer = 1 ORDER BY total"
+        ),
+        vec![vec![int(30), int(12)], vec![int(50), int(10)]]
+    );
+    // Sort keys need not be projected, and computed columns stay in place.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT order_id, total + 1, 7 FROM orders ORDER BY customer, total DESC LIMIT 2"
+        ),
+        vec![
+            vec![int(10), int(51), int(7)],
+            vec![int(12), int(31), int(7)],
+        ]
+    );
+}
+
+#[test]
+fn joined_rows_take_values_from_both_sides() {
+    let mut db = shop("join");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT c.name, o.total FROM orders o JOIN customers c ON o.customer = c.id \
+             WHERE o.total > 25 ORDER BY o.total"
+        ),
+        vec![
+            vec![text("ann"), int(30)],
+            vec![text("ann"), int(50)],
+            vec![text("cid"), int(70)],
+        ]
+    );
+    // Unqualified names resolve when only one side has them.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT order_id, name FROM customers JOIN orders ON id = customer WHERE id = 2"
+        ),
+        vec![vec![int(11), text("bob")]]
+    );
+}
+
+#[test]
+fn updates_and_deletes_evaluate_against_their_table() {
+    let mut db = shop("mutate");
+
+    db.execute("UPDATE orders SET total = total * 2 WHERE customer = 1")
+        .unwrap();
+    db.execute("DELETE FROM orders WHERE order_id = 11")
+        .unwrap();
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT order_id, total FROM orders ORDER BY order_id"
+        ),
+        vec![
+            vec![int(10), int(100)],
+            vec![int(12), int(60)],
+            vec![int(13), int(70)],
+        ]
+    );
+}
+
+#[test]
+fn qualifiers_pick_between_columns_with_the_same_name() {
+    let mut db = shop("qualified");
+    db.execute("CREATE TABLE refunds (id BIGINT, amount BIGINT)")
+        .unwrap();
+    db.execute("INSERT INTO refunds VALUES (2, 5)").unwrap();
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT customers.name, r.amount FROM customers JOIN refunds r ON customers.id = r.id"
+        ),
+        vec![vec![text("bob"), int(5)]]
+    );
+    assert!(matches!(
+        db.execute("SELECT id FROM customers JOIN refunds ON name = 'bob'"),
+        Err(DbError::Bind(BindError::AmbiguousColumn(_)))
+    ));
+}
+
+#[test]
+fn self_joins_read_each_instance_of_the_table() {
+    let path = temp_db("self_join");
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE emp (id BIGINT, name TEXT, boss BIGINT)",
+        "INSERT INTO emp VALUES (1, 'ann', NULL), (2, 'bob', 1), (3, 'cid', 2)",
+    ] {
+        db.execute(sql).unwrap();
+    }
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT e.name, m.name FROM emp e JOIN emp m ON e.boss = m.id ORDER BY e.id"
+        ),
+        vec![
+            vec![text("bob"), text("ann")],
+            vec![text("cid"), text("bob")],
+        ]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT a.id, b.id FROM emp a CROSS JOIN emp b WHERE a.id < b.id ORDER BY a.id, b.id"
+        ),
+        vec![
+            vec![int(1), int(2)],
+            vec![int(1), int(3)],
+            vec![int(2), int(3)],
+        ]
+    );
+
+    // A correlated subquery over the same table reads the outer instance.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT name FROM emp e WHERE EXISTS (SELECT 1 FROM emp r WHERE r.boss = e.id) ORDER BY id"
+        ),
+        vec![vec![text("ann")], vec![text("bob")]]
+    );
+
+    // With an index on the joined key, the inner side is looked up instead.
+    db.execute("CREATE INDEX emp_id ON emp (id)").unwrap();
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT e.name, m.name FROM emp e JOIN emp m ON e.boss = m.id ORDER BY e.id"
+        ),
+        vec![
+            vec![text("bob"), text("ann")],
+            vec![text("cid"), text("bob")],
+        ]
+    );
+}
@@ -0,0 +1,36 @@
+//! Fixtures shared by the integration tests. Each test file is its own
+//! crate and uses only some of them.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use helium::{api::db::Database, execution::errors::ExecutionResult, types::value::Value};
+
+/// Path for a fresh database named after the test file and `name`, with
+/// anything left over from an earlier run removed.
+pub fn temp_db(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "helium_{}_{}_{}.db",
+        env!("CARGO_CRATE_NAME"),
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_dir_all(Database::wal_dir(&path));
+    path
+}
+
+pub fn rows(db: &mut Database, sql: &str) -> Vec<Vec<Value>> {
+    match db.execute(sql).unwrap() {
+        ExecutionResult::Query(q) => q.rows,
+        _ => panic!("expected a query result"),
+    }
+}
+
+pub fn int(v: i64) -> Value {
+    Value::Int64(v)
+}
+
+pub fn text(s: &str) -> Value {
+    Value::String(s.into())
+}
@@ -0,0 +1,241 @@
+mod common;
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    binder::{bind_stmt::Binder, errors::BindError},
+    execution::errors::{ExecutionError, ExecutionResult},
+    frontend::sql::parser::Parser,
+    ir::{index_predicate::IndexPredicate, plan::LogicalPlan},
+    optimizer::optimize,
+    planner::logical::LogicalPlanner,
+    types::{datatype::DataType, value::Value},
+};
+
+use common::{rows, temp_db, text};
+
+/// Five people, one of unknown age and one of unknown city.
+fn people(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE people (id BIGINT, name TEXT, age BIGINT, city TEXT)",
+        "INSERT INTO people VALUES \
+         (1, 'alice', 34, 'Paris'), \
+         (2, 'albert', NULL, 'Lyon'), \
+         (3, 'bob', 17, NULL), \
+         (4, 'Alma', 52, 'paris'), \
+         (5, '50%_off', 25, 'Nice')",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+/// Ids of the people `condition` holds for, in order.
+fn ids(db: &mut Database, condition: &str) -> Vec<i64> {
+    let sql = format!("SELECT id FROM people WHERE {} ORDER BY id", condition);
+    rows(db, &sql)
+        .into_iter()
+        .map(|row| match row[0] {
+            Value::Int64(id) => id,
+            ref v => panic!("unexpected id {:?}", v),
+        })
+        .collect()
+}
+
+/// The keys `sql`'s optimized plan looks up through an index, if any.
+fn index_lookup(db: &Database, sql: &str) -> Option<IndexPredicate> {
+    fn walk(plan: &LogicalPlan) -> Option<IndexPredicate> {
+        match plan {
+            LogicalPlan::IndexScan { predicate, .. } => Some(predicate.clone()),
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Project { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Limit { input, .. } => walk(input),
+            _ => None,
+        }
+    }
+    let catalog = db.catalog();
+    let stmt = Parser::new(sql).parse_statements().unwrap().remove(0);
+    let bound = Binder::new(&catalog).bind_statement(stmt).unwrap();
+    let plan = LogicalPlanner::new().plan(bound).unwrap();
+    walk(&optimize(&plan, &catalog).unwrap())
+}
+
+#[test]
+fn case_expressions_take_the_first_matching_branch() {
+    let mut db = people("case");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id, \
+             CASE WHEN age < 18 THEN 'minor' WHEN age < 50 THEN 'adult' ELSE 'senior' END, \
+             CASE city WHEN 'Paris' THEN 1 WHEN 'Lyon' THEN 2.5 END \
+             FROM people ORDER BY id"
+        ),
+        vec![
+            vec![Value::Int64(1), text("adult"), Value::Float64(1.0)],
+            // NULL takes no WHEN branch.
+            vec![Value::Int64(2), text("senior"), Value::Float64(2.5)],
+            vec![Value::Int64(3), text("minor"), Value::Null],
+            vec![Value::Int64(4), text("senior"), Value::Null],
+            vec![Value::Int64(5), text("adult"), Value::Null],
+        ]
+    );
+
+    // Grouped queries may branch on aggregates.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT CASE WHEN COUNT(*) > 3 THEN 'many' ELSE 'few' END FROM people"
+        ),
+        vec![vec![text("many")]]
+    );
+
+    match db.execute("SELECT CASE WHEN age > 18 THEN 1 ELSE 'no' END FROM people") {
+        Err(DbError::Bind(BindError::TypeMismatchBinary { op, .. })) => assert_eq!(op, "CASE"),
+        other => panic!("expected a type mismatch, got {:?}", other),
+    }
+    assert!(matches!(
+        db.execute("SELECT CASE WHEN age THEN 1 END FROM people"),
+        Err(DbError::Bind(BindError::TypeMismatchUnary { .. }))
+    ));
+}
+
+#[test]
+fn comparisons_with_null_follow_three_valued_logic() {
+    let mut db = people("nulls");
+
+    assert_eq!(ids(&mut db, "age IN (17, 25, NULL)"), vec![3, 5]);
+    // With a NULL in the list, NOT IN holds for no row.
+    assert_eq!(ids(&mut db, "age NOT IN (17, NULL)"), Vec::<i64>::new());
+    assert_eq!(ids(&mut db, "age NOT IN (17, 25)"), vec![1, 4]);
+    assert_eq!(ids(&mut db, "age BETWEEN 18 AND 40"), vec![1, 5]);
+    assert_eq!(ids(&mut db, "age NOT BETWEEN 18 AND 40"), vec![3, 4]);
+    assert_eq!(ids(&mut db, "age IS NULL OR city IS NULL"), vec![2, 3]);
+    assert_eq!(ids(&mut db, "NOT (age IS NOT NULL)"), vec![2]);
+
+    // Unlike `!=`, IS DISTINCT FROM treats NULL as a value.
+    assert_eq!(ids(&mut db, "city != 'Paris'"), vec![2, 4, 5]);
+    assert_eq!(
+        ids(&mut db, "city IS DISTINCT FROM 'Paris'"),
+        vec![2, 3, 4, 5]
+    );
+    assert_eq!(ids(&mut db, "age IS NOT DISTINCT FROM NULL"), vec![2]);
+
+    // An unknown operand decides AND or OR only when the other one does not.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT NULL AND FALSE, NULL AND TRUE, NULL OR TRUE, NULL OR FALSE, NOT NULL, \
+             NULL IS NULL FROM people WHERE id = 1"
+        ),
+        vec![vec![
+            Value::Boolean(false),
+            Value::Null,
+            Value::Boolean(true),
+            Value::Null,
+            Value::Null,
+            Value::Boolean(true),
+        ]]
+    );
+    assert_eq!(ids(&mut db, "age > 30 OR city = 'Lyon'"), vec![1, 2, 4]);
+}
+
+#[test]
+fn like_patterns_match_wildcards_and_escapes() {
+    let mut db = people("like");
+
+    assert_eq!(ids(&mut db, "name LIKE 'al%'"), vec![1, 2]);
+    assert_eq!(ids(&mut db, "name ILIKE 'al%'"), vec![1, 2, 4]);
+    assert_eq!(ids(&mut db, "name NOT LIKE '%l%'"), vec![3, 5]);
+    assert_eq!(ids(&mut db, "name LIKE '_o_'"), vec![3]);
+    assert_eq!(ids(&mut db, "city ILIKE 'PARIS'"), vec![1, 4]);
+
+    // The escape character, a backslash unless given, makes `%` and `_`
+    // match themselves.
+    assert_eq!(ids(&mut db, "name LIKE '%\\%\\_%'"), vec![5]);
+    assert_eq!(ids(&mut db, "name LIKE '50!%!_off' ESCAPE '!'"), vec![5]);
+    assert_eq!(
+        ids(&mut db, "name LIKE '5%\\_%' ESCAPE ''"),
+        Vec::<i64>::new()
+    );
+
+    assert!(matches!(
+        db.execute("SELECT id FROM people WHERE name LIKE 'a%' ESCAPE 'ab'"),
+        Err(DbError::Bind(BindError::InvalidEscape(_)))
+    ));
+    assert!(matches!(
+        db.execute("SELECT id FROM people WHERE age LIKE '1%'"),
+        Err(DbError::Bind(BindError::TypeMismatchBinary { .. }))
+    ));
+    assert!(matches!(
+        db.execute("SELECT id FROM people WHERE name LIKE 'al\\'"),
+        Err(DbError::Execution(ExecutionError::InvalidExpression { .. }))
+    ));
+}
+
+#[test]
+fn indexes_serve_between_in_and_prefix_like() {
+    let mut db = people("indexes");
+    db.execute("CREATE INDEX people_id ON people (id)").unwrap();
+    db.execute("CREATE INDEX people_name ON people (name)")
+        .unwrap();
+
+    let query = |condition: &str| format!("SELECT id FROM people WHERE {}", condition);
+
+    assert_eq!(
+        index_lookup(&db, &query("id BETWEEN 2 AND 4")),
+        Some(IndexPredicate::Range {
+            low: Value::Int64(2),
+            high: Value::Int64(4),
+        })
+    );
+    assert_eq!(
+        index_lookup(&db, &query("id IN (4, 3, NULL, 4) AND city IS NOT NULL")),
+        Some(IndexPredicate::In(vec![Value::Int64(4), Value::Int64(3)]))
+    );
+    assert_eq!(
+        index_lookup(&db, &query("name LIKE 'al%e%'")),
+        Some(IndexPredicate::Range {
+            low: text("al"),
+            high: text("am"),
+        })
+    );
+    for condition in [
+        "id NOT BETWEEN 2 AND 4",
+        "id > 2 AND id <= 4",
+        "name ILIKE 'al%'",
+        "name LIKE '%al'",
+        "id IN (3, age)",
+    ] {
+        assert_eq!(index_lookup(&db, &query(condition)), None, "{}", condition);
+    }
+
+    // The rows found through the index are those the predicate holds for.
+    assert_eq!(ids(&mut db, "id BETWEEN 2 AND 4"), vec![2, 3, 4]);
+    assert_eq!(ids(&mut db, "id IN (4, 3, NULL, 4)"), vec![3, 4]);
+    assert_eq!(ids(&mut db, "id IN (4, 3) AND city IS NOT NULL"), vec![4]);
+    assert_eq!(ids(&mut db, "name LIKE 'al%e%'"), vec![1, 2]);
+    assert_eq!(ids(&mut db, "name LIKE 'al%t'"), vec![2]);
+
+    let result = match db
+        .execute("SELECT age BETWEEN 20 AND 40, name LIKE 'b%' FROM people WHERE id = 1")
+        .unwrap()
+    {
+        ExecutionResult::Query(q) => q,
+        _ => panic!("expected a query result"),
+    };
+    assert!(
+        result
+            .schema
+            .columns
+            .iter()
+            .all(|c| c.data_type == DataType::Boolean)
+    );
+    assert_eq!(
+        result.rows,
+        vec![vec![Value::Boolean(true), Value::Boolean(false)]]
+    );
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    binder::errors::BindError,
+};
+
+use common::{int, rows, temp_db, text};
+
+/// Ann runs the company; bob and cid report to her, dan to bob.
+fn company(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE staff (id BIGINT NOT NULL, name TEXT, boss BIGINT, pay BIGINT)",
+        "INSERT INTO staff VALUES (1, 'ann', NULL, 90), (2, 'bob', 1, 60), (3, 'cid', 1, 50), \
+         (4, 'dan', 2, 40)",
+        "CREATE TABLE edges (src BIGINT, dst BIGINT)",
+        "INSERT INTO edges VALUES (1, 2), (2, 3), (3, 1), (3, 4)",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+#[test]
+fn ctes_read_like_tables() {
+    let mut db = company("plain");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "WITH rich (who, salary) AS (SELECT name, pay FROM staff WHERE pay > 55) \
+             SELECT who FROM rich ORDER BY salary"
+        ),
+        vec![vec![text("bob")], vec![text("ann")]]
+    );
+    // A CTE can read the ones before it, and be joined with itself.
+    assert_eq!(
+        rows(
+            &mut db,
+            "WITH people AS (SELECT id, name, boss FROM staff), \
+             bosses AS (SELECT p.id, p.name FROM people p WHERE id < 3) \
+             SELECT p.name, b.name FROM people p JOIN bosses b ON p.boss = b.id \
+             ORDER BY p.name"
+        ),
+        vec![
+            vec![text("bob"), text("ann")],
+            vec![text("cid"), text("ann")],
+            vec![text("dan"), text("bob")],
+        ]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "WITH s AS (SELECT id, name, boss FROM staff) \
+             SELECT a.name, b.name FROM s a JOIN s b ON a.boss = b.id WHERE b.id = 1 \
+             ORDER BY a.name"
+        ),
+        vec![
+            vec![text("bob"), text("ann")],
+            vec![text("cid"), text("ann")]
+        ]
+    );
+}
+
+#[test]
+fn recursive_ctes_run_until_nothing_new_is_found() {
+    let mut db = company("recursive");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "WITH RECURSIVE chain (id, name, depth) AS (\
+             SELECT id, name, 0 FROM staff WHERE id = 1 \
+             UNION ALL SELECT s.id, s.name, c.depth + 1 FROM staff s \
+             JOIN chain c ON s.boss = c.id) \
+             SELECT name, depth FROM chain ORDER BY depth, name"
+        ),
+        vec![
+            vec![text("ann"), int(0)],
+            vec![text("bob"), int(1)],
+            vec![text("cid"), int(1)],
+            vec![text("dan"), int(2)],
+        ]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "WITH RECURSIVE n (x) AS (SELECT id FROM staff WHERE id = 1 \
+             UNION ALL SELECT x + 1 FROM n WHERE x < 5) \
+             SELECT SUM(x) FROM n"
+        ),
+        vec![vec![int(15)]]
+    );
+}
+
+#[test]
+fn recursive_union_stops_on_cycles() {
+    let mut db = company("cycle");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "WITH RECURSIVE reach (node) AS (SELECT src FROM edges WHERE src = 1 \
+             UNION SELECT e.dst FROM edges e JOIN reach r ON e.src = r.node) \
+             SELECT node FROM reach ORDER BY node"
+        ),
+        vec![vec![int(1)], vec![int(2)], vec![int(3)], vec![int(4)]]
+    );
+}
+
+#[test]
+fn malformed_ctes_are_rejected() {
+    let mut db = company("errors");
+
+    assert!(matches!(
+        db.execute("WITH a AS (SELECT id FROM staff), a AS (SELECT id FROM staff) SELECT id FROM a"),
+        Err(DbError::Bind(BindError::DuplicateCte(name))) if name == "a"
+    ));
+    assert!(matches!(
+        db.execute("WITH a (x, y) AS (SELECT id FROM staff) SELECT x FROM a"),
+        Err(DbError::Bind(BindError::ColumnCountMismatch))
+    ));
+    // A CTE is only visible in the statement that defines it.
+    assert!(db.execute("SELECT * FROM a").is_err());
+}
@@ -0,0 +1,312 @@
+mod common;
+
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use helium::{
+    api::{db::Database, errors::DefinitionAction},
+    execution::errors::ExecutionResult,
+    storage::{
+        buffer::frame::PAGE_SIZE,
+        errors::{StorageError, StorageResult},
+        index::btree::key::IndexKey,
+        page::page_id::PageId,
+        pagemgr::{file::FilePageManager, manager::PageManager},
+    },
+};
+
+use common::temp_db;
+
+fn open(path: &PathBuf) -> Database {
+    Database::new(path.to_string_lossy().into()).unwrap()
+}
+
+fn row_count(db: &mut Database, sql: &str) -> usize {
+    match db.execute(sql).unwrap() {
+        ExecutionResult::Query(q) => q.rows.len(),
+        _ => panic!("expected a query result"),
+    }
+}
+
+fn insert_rows(db: &mut Database, table: &str, range: std::ops::Range<i64>) {
+    for i in range {
+        db.execute(&format!("INSERT INTO {table} VALUES ({i}, 'name {i}')"))
+            .unwrap();
+    }
+}
+
+/// A file page manager whose writes fail while `failing` is set.
+struct FailingPageManager {
+    inner: FilePageManager,
+    failing: Arc<AtomicBool>,
+}
+
+impl PageManager for FailingPageManager {
+    fn allocate_page(&mut self) -> PageId {
+        self.inner.allocate_page()
+    }
+
+    fn read_page(&mut self, id: PageId, buf: &mut [u8; PAGE_SIZE]) -> StorageResult<()> {
+        self.inner.read_page(id, buf)
+    }
+
+    fn write_page(&mut self, id: PageId, data: &[u8; PAGE_SIZE]) -> StorageResult<()> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(StorageError::Io {
+                message: format!("cannot write page {}", id.0),
+            });
+        }
+        self.inner.write_page(id, data)
+    }
+
+    fn num_pages(&self) -> u64 {
+        self.inner.num_pages()
+    }
+
+    fn deallocate_page(&mut self, id: PageId) {
+        self.inner.deallocate_page(id)
+    }
+
+    fn free_pages(&self) -> Vec<PageId> {
+        self.inner.free_pages()
+    }
+
+    fn set_free_pages(&mut self, pages: Vec<PageId>) {
+        self.inner.set_free_pages(pages)
+    }
+
+    fn sync(&mut self) -> StorageResult<()> {
+        self.inner.sync()
+    }
+}
+
+/// A file page manager whose writes wait while `held` is set.
+struct HeldPageManager {
+    inner: FilePageManager,
+    held: Arc<AtomicBool>,
+}
+
+impl PageManager for HeldPageManager {
+    fn allocate_page(&mut self) -> PageId {
+        self.inner.allocate_page()
+    }
+
+    fn read_page(&mut self, id: PageId, buf: &mut [u8; PAGE_SIZE]) -> StorageResult<()> {
+        self.inner.read_page(id, buf)
+    }
+
+    fn write_page(&mut self, id: PageId, data: &[u8; PAGE_SIZE]) -> StorageResult<()> {
+        while self.held.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        self.inner.write_page(id, data)
+    }
+
+    fn num_pages(&self) -> u64 {
+        self.inner.num_pages()
+    }
+
+    fn deallocate_page(&mut self, id: PageId) {
+        self.inner.deallocate_page(id)
+    }
+
+    fn free_pages(&self) -> Vec<PageId> {
+        self.inner.free_pages()
+    }
+
+    fn set_free_pages(&mut self, pages: Vec<PageId>) {
+        self.inner.set_free_pages(pages)
+    }
+
+    fn sync(&mut self) -> StorageResult<()> {
+        self.inner.sync()
+    }
+}
+
+#[test]
+fn create_table_returns_definition_and_survives_reopen() {
+    let path = temp_db("create");
+    {
+        let mut db = open(&path);
+        match db
+            .execute("CREATE TABLE users (id INT, name TEXT)")
+            .unwrap()
+        {
+            ExecutionResult::Definition(d) => {
+                assert!(matches!(d.action, DefinitionAction::CreateTable));
+                assert_eq!(d.object, "users");
+            }
+            _ => panic!("expected a definition result"),
+        }
+        insert_rows(&mut db, "users", 0..3);
+        assert!(db.execute("CREATE TABLE users (id INT)").is_err());
+    }
+
+    let mut db = open(&path);
+    assert!(db.catalog().get_table_by_name("users").is_some());
+    assert_eq!(row_count(&mut db, "SELECT id FROM users"), 3);
+}
+
+#[test]
+fn create_index_backfills_existing_rows() {
+    let path = temp_db("backfill");
+    let mut db = open(&path);
+    db.execute("CREATE TABLE users (id INT, name TEXT)")
+        .unwrap();
+    insert_rows(&mut db, "users", 0..50);
+
+    db.execute("CREATE INDEX users_id ON users(id)").unwrap();
+    insert_rows(&mut db, "users", 50..60);
+
+    let catalog = db.catalog();
+    let idx = catalog.get_index_by_name("users_id").unwrap();
+    let index = idx.index.lock().unwrap();
+    for i in 0..60 {
+        assert_eq!(index.get(&IndexKey::Int(i)).unwrap().len(), 1, "key {i}");
+    }
+}
+
+#[test]
+fn drop_index_and_table_remove_them_from_the_catalog() {
+    let path = temp_db("drop");
+    {
+        let mut db = open(&path);
+        db.execute("CREATE TABLE users (id INT, name TEXT)")
+            .unwrap();
+        db.execute("CREATE INDEX users_id ON users(id)").unwrap();
+        db.execute("CREATE INDEX users_name ON users(name)")
+            .unwrap();
+
+        db.execute("DROP INDEX users_name").unwrap();
+        assert!(db.catalog().get_index_by_name("users_name").is_none());
+        let catalog = db.catalog();
+        let users = catalog.get_table_by_name("users").unwrap();
+        assert_eq!(users.index_ids.len(), 1);
+
+        // Dropping the table takes its remaining indexes with it.
+        db.execute("DROP TABLE users").unwrap();
+        assert!(db.catalog().get_table_by_name("users").is_none());
+        assert!(db.catalog().get_index_by_name("users_id").is_none());
+        assert!(db.execute("SELECT id FROM users").is_err());
+    }
+
+    let db = open(&path);
+    assert!(db.catalog().get_table_by_name("users").is_none());
+    assert!(db.catalog().get_index_by_name("users_id").is_none());
+}
+
+#[test]
+fn dropped_pages_are_reused() {
+    let path = temp_db("reuse");
+    {
+        let mut db = open(&path);
+        db.execute("CREATE TABLE t (id INT, name TEXT)").unwrap();
+        db.execute("CREATE INDEX t_id ON t(id)").unwrap();
+        insert_rows(&mut db, "t", 0..1000);
+    }
+    let size_before = std::fs::metadata(&path).unwrap().len();
+
+    {
+        let mut db = open(&path);
+        db.execute("DROP TABLE t").unwrap();
+        db.execute("CREATE TABLE t (id INT, name TEXT)").unwrap();
+        db.execute("CREATE INDEX t_id ON t(id)").unwrap();
+        insert_rows(&mut db, "t", 0..1000);
+    }
+    let size_after = std::fs::metadata(&path).unwrap().len();
+
+    // Only the shadowed catalog image may need a few extra pages.
+    assert!(
+        size_after <= size_before + 4 * 4096,
+        "{size_before} -> {size_after}"
+    );
+}
+
+#[test]
+fn a_drop_that_is_not_persisted_keeps_its_pages() {
+    let path = temp_db("unpersisted");
+    {
+        let mut db = open(&path);
+        db.execute("CREATE TABLE t (id INT, name TEXT)").unwrap();
+        db.execute("CREATE INDEX t_id ON t(id)").unwrap();
+        db.execute("CREATE TABLE u (id INT, name TEXT)").unwrap();
+        insert_rows(&mut db, "t", 0..300);
+    }
+
+    let failing = Arc::new(AtomicBool::new(false));
+    let pm = FailingPageManager {
+        inner: FilePageManager::open(&path).unwrap(),
+        failing: failing.clone(),
+    };
+    let mut db = Database::with_page_manager(&path, Box::new(pm)).unwrap();
+
+    // The new catalog cannot be written, so the drop does not happen and
+    // the pages of `t` and its index must not be handed out again.
+    failing.store(true, Ordering::SeqCst);
+    assert!(db.execute("DROP TABLE t").is_err());
+    failing.store(false, Ordering::SeqCst);
+    assert!(db.catalog().get_table_by_name("t").is_some());
+
+    insert_rows(&mut db, "u", 0..300);
+    db.execute("CREATE INDEX u_id ON u(id)").unwrap();
+    assert_eq!(row_count(&mut db, "SELECT id FROM t"), 300);
+    assert_eq!(row_count(&mut db, "SELECT name FROM t WHERE id = 150"), 1);
+    drop(db);
+
+    let mut db = open(&path);
+    assert_eq!(row_count(&mut db, "SELECT id FROM t WHERE id >= 0"), 300);
+    assert_eq!(row_count(&mut db, "SELECT id FROM u"), 300);
+}
+
+#[test]
+fn insert_bound_before_create_index_maintains_the_new_index() {
+    let path = temp_db("race");
+    {
+        let mut db = open(&path);
+        db.execute("CREATE TABLE t (id INT, name TEXT)").unwrap();
+        insert_rows(&mut db, "t", 0..10);
+    }
+
+    let held = Arc::new(AtomicBool::new(false));
+    let pm = HeldPageManager {
+        inner: FilePageManager::open(&path).unwrap(),
+        held: held.clone(),
+    };
+    let mut db = Database::with_page_manager(&path, Box::new(pm)).unwrap();
+    let mut other = db.connect();
+
+    // The index is built under a shared table lock, then waits to persist
+    // the catalog that has it. The insert is bound against the catalog
+    // without it and waits for the lock meanwhile.
+    held.store(true, Ordering::SeqCst);
+    let create = thread::spawn(move || other.execute("CREATE INDEX t_id ON t(id)").map(|_| ()));
+    thread::sleep(Duration::from_millis(100));
+    let insert = thread::spawn(move || {
+        db.execute("INSERT INTO t VALUES (10, 'name 10')")
+            .map(|_| db)
+    });
+    thread::sleep(Duration::from_millis(100));
+    held.store(false, Ordering::SeqCst);
+    create.join().unwrap().unwrap();
+    let mut db = insert.join().unwrap().unwrap();
+
+    let catalog = db.catalog();
+    let index = catalog
+        .get_index_by_name("t_id")
+        .unwrap()
+        .index
+        .lock()
+        .unwrap();
+    for i in 0..11 {
+        assert_eq!(index.get(&IndexKey::Int(i)).unwrap().len(), 1, "key {i}");
+    }
+    drop(index);
+    assert_eq!(row_count(&mut db, "SELECT name FROM t WHERE id = 10"), 1);
+}
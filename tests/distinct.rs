@@ -0,0 +1,174 @@
+mod common;
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    binder::{bind_stmt::Binder, errors::BindError},
+    frontend::sql::parser::Parser,
+    ir::plan::LogicalPlan,
+    optimizer::optimize,
+    planner::logical::LogicalPlanner,
+    types::value::Value,
+};
+
+use common::{int, rows, temp_db, text};
+
+/// Logins per user over time; one has no device.
+fn logins(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE logins (uid BIGINT NOT NULL, ts BIGINT NOT NULL, device TEXT)",
+        "INSERT INTO logins VALUES (1, 10, 'phone'), (2, 11, 'laptop'), (1, 12, 'laptop'), \
+         (3, 13, NULL), (2, 14, 'laptop'), (1, 15, 'phone'), (3, 16, NULL)",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+/// Whether each DISTINCT in `sql`'s optimized plan reads sorted rows.
+fn distinct_sorted(db: &Database, sql: &str) -> Vec<bool> {
+    fn walk(plan: &LogicalPlan, out: &mut Vec<bool>) {
+        match plan {
+            LogicalPlan::Distinct { input, sorted, .. } => {
+                out.push(*sorted);
+                walk(input, out);
+            }
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Project { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Limit { input, .. } => walk(input, out),
+            _ => {}
+        }
+    }
+    let catalog = db.catalog();
+    let stmt = Parser::new(sql).parse_statements().unwrap().remove(0);
+    let bound = Binder::new(&catalog).bind_statement(stmt).unwrap();
+    let plan = LogicalPlanner::new().plan(bound).unwrap();
+    let mut out = Vec::new();
+    walk(&optimize(&plan, &catalog).unwrap(), &mut out);
+    out
+}
+
+#[test]
+fn select_distinct_drops_duplicate_rows() {
+    let mut db = logins("rows");
+
+    assert_eq!(
+        rows(&mut db, "SELECT DISTINCT uid FROM logins ORDER BY uid"),
+        vec![vec![int(1)], vec![int(2)], vec![int(3)]]
+    );
+    // NULLs count as equal, and whole rows are compared.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT DISTINCT uid, device FROM logins ORDER BY uid, device"
+        ),
+        vec![
+            vec![int(1), text("laptop")],
+            vec![int(1), text("phone")],
+            vec![int(2), text("laptop")],
+            vec![int(3), Value::Null],
+        ]
+    );
+    assert_eq!(rows(&mut db, "SELECT DISTINCT device FROM logins").len(), 3);
+    // LIMIT counts distinct rows.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT DISTINCT uid FROM logins ORDER BY uid DESC LIMIT 2"
+        ),
+        vec![vec![int(3)], vec![int(2)]]
+    );
+    assert_eq!(
+        rows(&mut db, "SELECT ALL uid FROM logins WHERE uid = 3"),
+        vec![vec![int(3)], vec![int(3)]]
+    );
+
+    // Numbers that compare equal are one value, -0.0 and 0.0 included.
+    db.execute("CREATE TABLE levels (level DOUBLE PRECISION)")
+        .unwrap();
+    db.execute("INSERT INTO levels VALUES (0.0), (-0.0), (1.5), (0.0)")
+        .unwrap();
+    assert_eq!(
+        rows(&mut db, "SELECT DISTINCT level FROM levels"),
+        vec![vec![Value::Float64(0.0)], vec![Value::Float64(1.5)]]
+    );
+}
+
+#[test]
+fn distinct_on_keeps_the_first_row_of_each_key() {
+    let mut db = logins("on");
+
+    // The latest login of each user.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT DISTINCT ON (uid) uid, ts, device FROM logins ORDER BY uid, ts DESC"
+        ),
+        vec![
+            vec![int(1), int(15), text("phone")],
+            vec![int(2), int(14), text("laptop")],
+            vec![int(3), int(16), Value::Null],
+        ]
+    );
+    // The expressions need not be projected.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT DISTINCT ON (device) ts FROM logins ORDER BY device, ts"
+        ),
+        vec![vec![int(11)], vec![int(10)], vec![int(13)]]
+    );
+    assert_eq!(
+        rows(&mut db, "SELECT DISTINCT ON (uid) uid FROM logins").len(),
+        3
+    );
+}
+
+#[test]
+fn sorted_input_is_deduplicated_without_hashing() {
+    let db = logins("plan");
+
+    assert_eq!(
+        distinct_sorted(&db, "SELECT DISTINCT uid FROM logins"),
+        vec![false]
+    );
+    assert_eq!(
+        distinct_sorted(
+            &db,
+            "SELECT DISTINCT uid, device FROM logins ORDER BY device, uid"
+        ),
+        vec![true]
+    );
+    // Sorting by only some of the columns leaves equal rows apart.
+    assert_eq!(
+        distinct_sorted(&db, "SELECT DISTINCT uid, device FROM logins ORDER BY uid"),
+        vec![false]
+    );
+    assert_eq!(
+        distinct_sorted(
+            &db,
+            "SELECT DISTINCT ON (uid) uid, ts FROM logins ORDER BY uid, ts DESC"
+        ),
+        vec![true]
+    );
+}
+
+#[test]
+fn distinct_must_agree_with_order_by() {
+    let mut db = logins("errors");
+
+    assert!(matches!(
+        db.execute("SELECT DISTINCT uid FROM logins ORDER BY ts"),
+        Err(DbError::Bind(BindError::DistinctOrderBy))
+    ));
+    assert!(matches!(
+        db.execute("SELECT DISTINCT ON (uid) uid, ts FROM logins ORDER BY ts, uid"),
+        Err(DbError::Bind(BindError::DistinctOnOrderBy))
+    ));
+    assert!(matches!(
+        db.execute("SELECT DISTINCT ON (uid, device) uid FROM logins ORDER BY uid"),
+        Err(DbError::Bind(BindError::DistinctOnOrderBy))
+    ));
+}
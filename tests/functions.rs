@@ -0,0 +1,326 @@
+mod common;
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    binder::errors::BindError,
+    execution::errors::{ExecutionError, ExecutionResult},
+    types::{datatype::DataType, value::Value},
+};
+
+use common::{rows, temp_db, text};
+
+/// People with a name, a count of visits and when they last came, some of
+/// it unknown.
+fn people(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE people (id SMALLINT, name TEXT, nick TEXT, visits BIGINT, \
+         balance DOUBLE, joined DATE, seen TIMESTAMP)",
+        "INSERT INTO people VALUES \
+         (1, '  Ada Lovelace ', 'ada', 12, -20.25, DATE '2024-01-31', \
+          TIMESTAMP '2026-03-15 14:45:30.5'), \
+         (2, 'Grace', NULL, -7, 2.5, DATE '2025-06-01', NULL)",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+#[test]
+fn string_functions() {
+    let mut db = people("strings");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT UPPER(nick), lower(TRIM(name)), LENGTH(name), SUBSTR(TRIM(name), 5), \
+             SUBSTRING(nick, 0, 3), REPLACE(name, 'a', '4') FROM people WHERE id = 1"
+        ),
+        vec![vec![
+            text("ADA"),
+            text("ada lovelace"),
+            Value::Int64(15),
+            text("Lovelace"),
+            text("ad"),
+            text("  Ad4 Lovel4ce "),
+        ]]
+    );
+
+    // CONCAT leaves NULLs out; || is NULL with either side NULL.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT CONCAT(nick, '#', id, '/', visits), nick || '#' || id, 'n' || 1 + 2 \
+             FROM people ORDER BY id"
+        ),
+        vec![
+            vec![text("ada#1/12"), text("ada#1"), text("n3")],
+            vec![text("#2/-7"), Value::Null, text("n3")],
+        ]
+    );
+}
+
+#[test]
+fn math_functions() {
+    let mut db = people("math");
+
+    let result = match db
+        .execute(
+            "SELECT ABS(id - 3), ABS(visits), ROUND(balance), ROUND(balance, 1), \
+             FLOOR(balance), CEIL(balance), MOD(visits, 5), MOD(balance, 3), \
+             POWER(2, 10), SQRT(16) FROM people WHERE id = 2",
+        )
+        .unwrap()
+    {
+        ExecutionResult::Query(q) => q,
+        _ => panic!("expected a query result"),
+    };
+    assert_eq!(
+        result.rows,
+        vec![vec![
+            Value::Int16(1),
+            Value::Int64(7),
+            Value::Float64(3.0),
+            Value::Float64(2.5),
+            Value::Float64(2.0),
+            Value::Float64(3.0),
+            Value::Int64(-2),
+            Value::Float64(2.5),
+            Value::Float64(1024.0),
+            Value::Float64(4.0),
+        ]]
+    );
+    assert_eq!(result.schema.columns[0].data_type, DataType::Int16);
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT ABS(id), ROUND(balance, 1) FROM people WHERE id = 1"
+        ),
+        vec![vec![Value::Int16(1), Value::Float64(-20.3)]]
+    );
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT visits % 5, MOD(visits, 5), balance % 3 FROM people ORDER BY id"
+        ),
+        vec![
+            vec![Value::Int64(2), Value::Int64(2), Value::Float64(-2.25)],
+            vec![Value::Int64(-2), Value::Int64(-2), Value::Float64(2.5)],
+        ]
+    );
+    assert!(matches!(
+        db.execute("SELECT visits % (id - 1) FROM people"),
+        Err(DbError::Execution(ExecutionError::DivisionByZero))
+    ));
+
+    assert!(matches!(
+        db.execute("SELECT SQRT(balance) FROM people"),
+        Err(DbError::Execution(ExecutionError::Function { name, .. })) if name == "SQRT"
+    ));
+    assert!(matches!(
+        db.execute("SELECT MOD(visits, id - 1) FROM people"),
+        Err(DbError::Execution(ExecutionError::Function { name, .. })) if name == "MOD"
+    ));
+}
+
+#[test]
+fn null_handling_functions() {
+    let mut db = people("nulls");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT COALESCE(nick, name), COALESCE(NULL, id, visits), IFNULL(seen, joined), \
+             NULLIF(visits, 12), NULLIF(nick, NULL) FROM people ORDER BY id"
+        ),
+        vec![
+            vec![
+                text("ada"),
+                Value::Int64(1),
+                Value::Timestamp(20527 * 86_400_000_000 + 53_130_500_000),
+                Value::Null,
+                text("ada"),
+            ],
+            vec![
+                text("Grace"),
+                Value::Int64(2),
+                Value::Timestamp(20240 * 86_400_000_000),
+                Value::Int64(-7),
+                Value::Null,
+            ],
+        ]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id FROM people WHERE COALESCE(nick, 'none') = 'none'"
+        ),
+        vec![vec![Value::Int16(2)]]
+    );
+
+    assert!(matches!(
+        db.execute("SELECT COALESCE(nick, visits) FROM people"),
+        Err(DbError::Bind(BindError::InvalidFunctionCall { .. }))
+    ));
+    assert!(matches!(
+        db.execute("SELECT LENGTH(visits) FROM people"),
+        Err(DbError::Bind(BindError::InvalidFunctionCall { .. }))
+    ));
+    assert!(matches!(
+        db.execute("SELECT SOUNDEX(name) FROM people"),
+        Err(DbError::Bind(BindError::UnknownFunction(_)))
+    ));
+}
+
+#[test]
+fn date_and_time_functions() {
+    let mut db = people("dates");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT EXTRACT(YEAR FROM joined), EXTRACT(second FROM seen), \
+             DATE_PART('dow', joined), DATE_PART('doy', seen) FROM people WHERE id = 1"
+        ),
+        vec![vec![
+            Value::Float64(2024.0),
+            Value::Float64(30.5),
+            Value::Float64(3.0),
+            Value::Float64(74.0),
+        ]]
+    );
+
+    let shown: Vec<Vec<String>> = rows(
+        &mut db,
+        "SELECT DATE_TRUNC('month', seen), DATE_TRUNC('week', seen), \
+         DATE_TRUNC('hour', seen), DATE_ADD('month', 1, joined), \
+         DATE_ADD('day', -31, joined), DATE_DIFF('month', joined, seen), \
+         DATE_DIFF('day', joined, seen) FROM people WHERE id = 1",
+    )
+    .iter()
+    .map(|row| row.iter().map(Value::to_string).collect())
+    .collect();
+    assert_eq!(
+        shown,
+        vec![vec![
+            "2026-03-01 00:00:00",
+            "2026-03-09 00:00:00",
+            "2026-03-15 14:00:00",
+            "2024-02-29 00:00:00",
+            "2023-12-31 00:00:00",
+            "26",
+            "774",
+        ]]
+    );
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id FROM people WHERE NOW() > seen AND EXTRACT(month FROM joined) = 1"
+        ),
+        vec![vec![Value::Int16(1)]]
+    );
+    assert!(matches!(
+        db.execute("SELECT DATE_TRUNC('fortnight', seen) FROM people"),
+        Err(DbError::Execution(ExecutionError::Function { .. }))
+    ));
+}
+
+#[test]
+fn timestamps_subtract_to_intervals_that_move_them() {
+    let mut db = people("intervals");
+
+    let result = db
+        .execute(
+            "SELECT seen - TIMESTAMP '2026-03-14 12:00:00', \
+             TIMESTAMP '2026-03-14 12:00:00' - seen, joined + INTERVAL '1 month 2 hours', \
+             seen - INTERVAL '1 year -1 day', INTERVAL '1:30' + seen, \
+             INTERVAL '1 year 14 months' - INTERVAL '3 days' FROM people WHERE id = 1",
+        )
+        .unwrap();
+    let ExecutionResult::Query(result) = result else {
+        panic!("expected a query result");
+    };
+    let types: Vec<_> = result
+        .schema
+        .columns
+        .iter()
+        .map(|c| c.data_type.clone())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            DataType::Interval,
+            DataType::Interval,
+            DataType::Timestamp,
+            DataType::Timestamp,
+            DataType::Timestamp,
+            DataType::Interval,
+        ]
+    );
+    let shown: Vec<String> = result.rows[0].iter().map(Value::to_string).collect();
+    assert_eq!(
+        shown,
+        [
+            "1 day 02:45:30.5",
+            "-1 day -02:45:30.5",
+            "2024-02-29 02:00:00",
+            "2025-03-16 14:45:30.5",
+            "2026-03-15 16:15:30.5",
+            "2 years 2 months -3 days",
+        ]
+    );
+
+    // A date is the midnight that starts it.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id FROM people WHERE seen - joined > INTERVAL '774 days' \
+             AND seen - joined < INTERVAL '26 months'"
+        ),
+        vec![vec![Value::Int16(1)]]
+    );
+    assert!(matches!(
+        db.execute("SELECT seen + seen FROM people"),
+        Err(DbError::Bind(BindError::TypeMismatchBinary { .. }))
+    ));
+    assert!(matches!(
+        db.execute("SELECT INTERVAL '2 fortnights' FROM people"),
+        Err(DbError::Parse(_))
+    ));
+}
+
+#[test]
+fn now_is_read_once_per_statement() {
+    let path = temp_db("now");
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    db.execute("CREATE TABLE ticks (id BIGINT, at TIMESTAMP)")
+        .unwrap();
+    let values: Vec<String> = (0..2000).map(|i| format!("({i}, NULL)")).collect();
+    db.execute(&format!("INSERT INTO ticks VALUES {}", values.join(", ")))
+        .unwrap();
+
+    // Every row of a statement sees the time the statement started.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT COUNT(*), COUNT(DISTINCT NOW()) FROM ticks t1 CROSS JOIN ticks t2 \
+             WHERE t2.id < 10"
+        ),
+        vec![vec![Value::Int64(20000), Value::Int64(1)]]
+    );
+    db.execute("UPDATE ticks SET at = NOW()").unwrap();
+    assert_eq!(
+        rows(&mut db, "SELECT COUNT(DISTINCT at) FROM ticks"),
+        vec![vec![Value::Int64(1)]]
+    );
+
+    // A later statement reads a later time.
+    std::thread::sleep(std::time::Duration::from_millis(2));
+    assert_eq!(
+        rows(&mut db, "SELECT COUNT(*) FROM ticks WHERE at < NOW()"),
+        vec![vec![Value::Int64(2000)]]
+    );
+}
@@ -0,0 +1,239 @@
+mod common;
+
+use helium::{
+    api::db::Database,
+    binder::bind_stmt::Binder,
+    execution::errors::ExecutionResult,
+    frontend::sql::parser::Parser,
+    ir::plan::{JoinSide, LogicalPlan},
+    optimizer::optimize,
+    planner::logical::LogicalPlanner,
+    types::value::Value,
+};
+
+use common::{temp_db, text};
+
+/// Customers 0..n and two orders per even customer, plus orders of unknown
+/// customers and with NULL customers.
+fn shop(name: &str, n: i64) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    db.execute("CREATE TABLE customers (id INT, name TEXT)")
+        .unwrap();
+    db.execute("CREATE TABLE orders (order_id INT, customer INT, total INT)")
+        .unwrap();
+
+    let customers: Vec<_> = (0..n).map(|i| format!("({i}, 'c{i}')")).collect();
+    db.execute(&format!(
+        "INSERT INTO customers VALUES {}",
+        customers.join(", ")
+    ))
+    .unwrap();
+
+    let mut orders: Vec<_> = (0..n)
+        .filter(|i| i % 2 == 0)
+        .flat_map(|i| {
+            [
+                format!("({}, {i}, {})", 2 * i, i % 7),
+                format!("({}, {i}, 5)", 2 * i + 1),
+            ]
+        })
+        .collect();
+    orders.push(format!("({}, {}, 1)", 2 * n, n + 100));
+    orders.push(format!("({}, NULL, 1)", 2 * n + 1));
+    db.execute(&format!("INSERT INTO orders VALUES {}", orders.join(", ")))
+        .unwrap();
+    db
+}
+
+fn sorted_rows(db: &mut Database, sql: &str) -> Vec<Vec<Value>> {
+    let mut rows = match db.execute(sql).unwrap() {
+        ExecutionResult::Query(q) => q.rows,
+        _ => panic!("expected a query result"),
+    };
+    rows.sort_by_key(|r| format!("{:?}", r));
+    rows
+}
+
+fn optimized(db: &Database, sql: &str) -> LogicalPlan {
+    let catalog = db.catalog();
+    let stmt = Parser::new(sql).parse_statements().unwrap().remove(0);
+    let bound = Binder::new(&catalog).bind_statement(stmt).unwrap();
+    let plan = LogicalPlanner::new().plan(bound).unwrap();
+    optimize(&plan, &catalog).unwrap()
+}
+
+fn find_hash_join(plan: &LogicalPlan) -> Option<&LogicalPlan> {
+    match plan {
+        LogicalPlan::HashJoin { .. } => Some(plan),
+        LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Project { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Limit { input, .. }
+        | LogicalPlan::Aggregate { input, .. } => find_hash_join(input),
+        LogicalPlan::Join { left, right, .. } => {
+            find_hash_join(left).or_else(|| find_hash_join(right))
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn equi_joins_are_planned_as_hash_joins() {
+    let db = shop("plan", 4);
+
+    let plan = optimized(
+        &db,
+        "SELECT name, total FROM customers c JOIN orders o ON o.customer = c.id AND o.total > 2",
+    );
+    let Some(LogicalPlan::HashJoin {
+        left_keys,
+        residual,
+        ..
+    }) = find_hash_join(&plan)
+    else {
+        panic!("expected a hash join in {plan:?}");
+    };
+    assert_eq!(left_keys.len(), 1);
+    assert!(residual.is_some());
+
+    // The smaller, filtered side is built.
+    let plan = optimized(
+        &db,
+        "SELECT name FROM customers c JOIN orders o ON c.id = o.customer WHERE c.name = 'c2'",
+    );
+    assert!(matches!(
+        find_hash_join(&plan),
+        Some(LogicalPlan::HashJoin {
+            build: JoinSide::Left,
+            ..
+        })
+    ));
+
+    // Without an equality between the sides there is nothing to hash on.
+    let plan = optimized(
+        &db,
+        "SELECT name FROM customers c JOIN orders o ON c.id < o.customer",
+    );
+    assert!(find_hash_join(&plan).is_none());
+}
+
+#[test]
+fn hash_joins_match_nested_loop_results_for_every_join_type() {
+    let mut db = shop("types", 30);
+
+    for join in ["JOIN", "LEFT JOIN", "RIGHT JOIN", "FULL JOIN"] {
+        // `<=` and `>=` together say the same as `=`, but only `=` is
+        // hashed on.
+        let select =
+            format!("SELECT c.id, o.order_id, o.total FROM customers c {join} orders o ON ");
+        let hashed = sorted_rows(
+            &mut db,
+            &format!("{select} c.id = o.customer AND o.total < 5"),
+        );
+        let nested = sorted_rows(
+            &mut db,
+            &format!("{select} c.id <= o.customer AND c.id >= o.customer AND o.total < 5"),
+        );
+        assert_eq!(hashed, nested, "{join}");
+        assert!(!hashed.is_empty(), "{join}");
+    }
+}
+
+#[test]
+fn build_sides_larger_than_work_mem_spill_to_disk() {
+    let mut db = shop("spill", 600);
+    let sql = "SELECT c.name, o.order_id FROM customers c FULL JOIN orders o ON c.id = o.customer";
+
+    let in_memory = sorted_rows(&mut db, sql);
+    db.set_work_mem(1024);
+    let spilled = sorted_rows(&mut db, sql);
+
+    // 300 customers with two orders, 300 without, and two orders without
+    // a customer.
+    assert_eq!(in_memory.len(), 600 + 300 + 2);
+    assert_eq!(spilled, in_memory);
+}
+
+#[test]
+fn the_smaller_table_is_built_whichever_side_it_is_written_on() {
+    let mut db = shop("sides", 10);
+    let orders: Vec<_> = (100..300)
+        .map(|i| format!("({i}, {}, 1)", i % 10))
+        .collect();
+    db.execute(&format!("INSERT INTO orders VALUES {}", orders.join(", ")))
+        .unwrap();
+
+    let build = |db: &Database, sql: &str| match find_hash_join(&optimized(db, sql)) {
+        Some(LogicalPlan::HashJoin { build, .. }) => *build,
+        plan => panic!("expected a hash join, got {plan:?}"),
+    };
+    assert_eq!(
+        build(
+            &db,
+            "SELECT name FROM orders o JOIN customers c ON o.customer = c.id"
+        ),
+        JoinSide::Right
+    );
+    assert_eq!(
+        build(
+            &db,
+            "SELECT name FROM customers c JOIN orders o ON o.customer = c.id"
+        ),
+        JoinSide::Left
+    );
+
+    // Deleted rows no longer count.
+    db.execute("DELETE FROM orders WHERE order_id >= 10")
+        .unwrap();
+    db.execute("INSERT INTO customers VALUES (10, 'c10'), (11, 'c11')")
+        .unwrap();
+    assert_eq!(
+        build(
+            &db,
+            "SELECT name FROM customers c JOIN orders o ON o.customer = c.id"
+        ),
+        JoinSide::Right
+    );
+}
+
+#[test]
+fn a_key_repeated_past_work_mem_is_joined_block_by_block() {
+    let path = temp_db("skew");
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    db.execute("CREATE TABLE hot (k INT, n INT)").unwrap();
+    db.execute("CREATE TABLE keys (k INT, tag TEXT)").unwrap();
+    let hot: Vec<_> = (0..300).map(|i| format!("(1, {i})")).collect();
+    db.execute(&format!("INSERT INTO hot VALUES {}", hot.join(", ")))
+        .unwrap();
+    let keys: Vec<_> = (0..400).map(|i| format!("({i}, 'k{i}')")).collect();
+    db.execute(&format!(
+        "INSERT INTO keys VALUES {}, (1, 'again')",
+        keys.join(", ")
+    ))
+    .unwrap();
+
+    for join in ["JOIN", "LEFT JOIN", "RIGHT JOIN", "FULL JOIN"] {
+        let sql = format!("SELECT h.n, k.tag FROM hot h {join} keys k ON h.k = k.k");
+        db.set_work_mem(1 << 20);
+        let in_memory = sorted_rows(&mut db, &sql);
+        db.set_work_mem(1024);
+        let spilled = sorted_rows(&mut db, &sql);
+        assert_eq!(spilled, in_memory, "{join}");
+    }
+    assert_eq!(
+        sorted_rows(
+            &mut db,
+            "SELECT h.n FROM hot h FULL JOIN keys k ON h.k = k.k"
+        )
+        .len(),
+        2 * 300 + 399
+    );
+    assert_eq!(
+        sorted_rows(
+            &mut db,
+            "SELECT k.tag FROM keys k WHERE EXISTS (SELECT 1 FROM hot h WHERE h.k = k.k)"
+        ),
+        vec![vec![text("again")], vec![text("k1")]]
+    );
+}
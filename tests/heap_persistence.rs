@@ -0,0 +1,122 @@
+mod common;
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use helium::{
+    api::db::Database,
+    catalog::{catalog::Catalog, ids::TableId},
+    execution::errors::ExecutionResult,
+    storage::{
+        buffer::pool::{BufferPool, BufferPoolHandle},
+        heap::heap_table::HeapTable,
+        pagemgr::file::FilePageManager,
+    },
+    txn::transaction::TxnId,
+    types::{datatype::DataType, value::Value},
+};
+
+use common::temp_db;
+
+fn open_pool(path: &PathBuf) -> BufferPoolHandle {
+    let pm = FilePageManager::open(path).unwrap();
+    Arc::new(Mutex::new(BufferPool::new(Box::new(pm))))
+}
+
+fn row_count(db: &mut Database, sql: &str) -> usize {
+    match db.execute(sql).unwrap() {
+        ExecutionResult::Query(q) => q.rows.len(),
+        _ => panic!("expected a query result"),
+    }
+}
+
+#[test]
+fn heap_page_chain_survives_reopen() {
+    let path = temp_db("chain");
+
+    let root = {
+        let bp = open_pool(&path);
+        let heap = HeapTable::create(TableId(1), bp.clone()).unwrap();
+        for i in 0..1000 {
+            heap.insert(
+                TxnId::NONE,
+                vec![Value::Int64(i), Value::String(format!("row {i}"))],
+            )
+            .unwrap();
+        }
+        assert!(heap.page_ids().len() > 1);
+        bp.lock().unwrap().flush_all().unwrap();
+        heap.root_page()
+    };
+
+    let bp = open_pool(&path);
+    let heap = HeapTable::open(TableId(1), root, bp).unwrap();
+    let mut ids: Vec<i64> = heap
+        .scan()
+        .map(|entry| match entry.unwrap().1.values[0] {
+            Value::Int64(v) => v,
+            ref v => panic!("unexpected value {v:?}"),
+        })
+        .collect();
+    ids.sort();
+    assert_eq!(ids, (0..1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn deleted_slots_are_reused_and_large_rows_spill_to_new_pages() {
+    let path = temp_db("reuse");
+    let bp = open_pool(&path);
+    let heap = HeapTable::create(TableId(1), bp).unwrap();
+
+    let rid = heap.insert(TxnId::NONE, vec![Value::Int64(1)]).unwrap();
+    heap.delete(TxnId::NONE, rid).unwrap();
+    let reused = heap.insert(TxnId::NONE, vec![Value::Int64(2)]).unwrap();
+    assert_eq!(reused, rid);
+    assert_eq!(heap.fetch(reused).unwrap().values, vec![Value::Int64(2)]);
+
+    // Each row is ~1KB, so a page fills up by size long before its row cap.
+    let big = "x".repeat(1000);
+    for _ in 0..20 {
+        heap.insert(TxnId::NONE, vec![Value::String(big.clone())])
+            .unwrap();
+    }
+    assert!(heap.page_ids().len() > 1);
+    assert_eq!(heap.scan().count(), 21);
+
+    let huge = "x".repeat(64 * 1024);
+    assert!(heap.insert(TxnId::NONE, vec![Value::String(huge)]).is_err());
+}
+
+#[test]
+fn rows_are_visible_across_statements_and_reopen() {
+    let path = temp_db("statements");
+    {
+        let bp = open_pool(&path);
+        let mut catalog = Catalog::load(&bp).unwrap();
+        catalog
+            .create_table(
+                "users".into(),
+                vec![
+                    ("id".into(), DataType::Int64, false),
+                    ("name".into(), DataType::Varchar { max_len: None }, true),
+                ],
+                bp.clone(),
+            )
+            .unwrap();
+        catalog.persist(&bp).unwrap();
+    }
+
+    {
+        let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+        db.execute("INSERT INTO users VALUES (1, 'alice')").unwrap();
+        db.execute("INSERT INTO users VALUES (2, 'bob')").unwrap();
+        assert_eq!(row_count(&mut db, "SELECT id FROM users"), 2);
+    }
+
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    assert_eq!(row_count(&mut db, "SELECT id FROM users"), 2);
+    db.execute("INSERT INTO users VALUES (3, 'carol')").unwrap();
+    assert_eq!(row_count(&mut db, "SELECT id FROM users"), 3);
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use helium::{
+    api::db::Database,
+    binder::bind_stmt::Binder,
+    execution::errors::ExecutionResult,
+    frontend::sql::parser::Parser,
+    ir::plan::{JoinSide, LogicalPlan},
+    optimizer::optimize,
+    planner::logical::LogicalPlanner,
+    types::value::Value,
+};
+
+use common::temp_db;
+
+/// `n` customers indexed on `id`, a handful of orders for some of them, an
+/// order of an unknown customer and one without a customer.
+fn shop(name: &str, n: i64) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    db.execute("CREATE TABLE customers (id BIGINT, name TEXT)")
+        .unwrap();
+    db.execute("CREATE TABLE orders (order_id BIGINT, customer BIGINT, total BIGINT)")
+        .unwrap();
+
+    let customers: Vec<_> = (0..n).map(|i| format!("({i}, 'c{i}')")).collect();
+    db.execute(&format!(
+        "INSERT INTO customers VALUES {}",
+        customers.join(", ")
+    ))
+    .unwrap();
+    db.execute("CREATE INDEX customers_id ON customers (id)")
+        .unwrap();
+
+    db.execute(
+        "INSERT INTO orders VALUES (1, 3, 10), (2, 3, 20), (3, 7, 30), \
+         (4, 9999, 40), (5, NULL, 50)",
+    )
+    .unwrap();
+    db
+}
+
+fn sorted_rows(db: &mut Database, sql: &str) -> Vec<Vec<Value>> {
+    let mut rows = match db.execute(sql).unwrap() {
+        ExecutionResult::Query(q) => q.rows,
+        _ => panic!("expected a query result"),
+    };
+    rows.sort_by_key(|r| format!("{:?}", r));
+    rows
+}
+
+fn optimized(db: &Database, sql: &str) -> LogicalPlan {
+    let catalog = db.catalog();
+    let stmt = Parser::new(sql).parse_statements().unwrap().remove(0);
+    let bound = Binder::new(&catalog).bind_statement(stmt).unwrap();
+    let plan = LogicalPlanner::new().plan(bound).unwrap();
+    optimize(&plan, &catalog).unwrap()
+}
+
+fn find_index_join(plan: &LogicalPlan) -> Option<&LogicalPlan> {
+    match plan {
+        LogicalPlan::IndexJoin { .. } => Some(plan),
+        LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Project { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Limit { input, .. }
+        | LogicalPlan::Aggregate { input, .. } => find_index_join(input),
+        _ => None,
+    }
+}
+
+#[test]
+fn joins_on_an_indexed_column_probe_the_index() {
+    let db = shop("plan", 2000);
+
+    // The indexed table is the inner side wherever it appears in the query.
+    for (sql, side) in [
+        (
+            "SELECT name FROM orders o JOIN customers c ON o.customer = c.id",
+            JoinSide::Right,
+        ),
+        (
+            "SELECT name FROM customers c JOIN orders o ON o.customer = c.id",
+            JoinSide::Left,
+        ),
+        (
+            "SELECT name FROM orders o LEFT JOIN customers c ON o.customer = c.id",
+            JoinSide::Right,
+        ),
+    ] {
+        let plan = optimized(&db, sql);
+        match find_index_join(&plan) {
+            Some(LogicalPlan::IndexJoin { inner_side, .. }) => {
+                assert_eq!(*inner_side, side, "{sql}")
+            }
+            _ => panic!("expected an index join for {sql}: {plan:?}"),
+        }
+    }
+
+    // Unmatched customers must be kept, which a lookup per order cannot do.
+    let plan = optimized(
+        &db,
+        "SELECT name FROM orders o RIGHT JOIN customers c ON o.customer = c.id",
+    );
+    assert!(find_index_join(&plan).is_none());
+}
+
+#[test]
+fn index_joins_match_nested_loop_results() {
+    let mut db = shop("parity", 2000);
+
+    for join in ["JOIN", "LEFT JOIN"] {
+        let select = format!("SELECT o.order_id, c.name FROM orders o {join} customers c ON ");
+        let indexed = sorted_rows(
+            &mut db,
+            &format!("{select} o.customer = c.id AND o.total < 30"),
+        );
+        let nested = sorted_rows(
+            &mut db,
+            &format!("{select} o.customer <= c.id AND o.customer >= c.id AND o.total < 30"),
+        );
+        assert_eq!(indexed, nested, "{join}");
+    }
+
+    let s = |v: &str| Value::String(v.into());
+    assert_eq!(
+        sorted_rows(
+            &mut db,
+            "SELECT o.order_id, c.name FROM orders o LEFT JOIN customers c \
+             ON o.customer = c.id WHERE o.total < 50"
+        ),
+        vec![
+            vec![Value::Int64(1), s("c3")],
+            vec![Value::Int64(2), s("c3")],
+            vec![Value::Int64(3), s("c7")],
+            vec![Value::Int64(4), Value::Null],
+        ]
+    );
+}
+
+#[test]
+fn index_joins_see_the_transactions_own_changes() {
+    let mut db = shop("mvcc", 2000);
+    let sql = "SELECT o.order_id, c.name FROM orders o JOIN customers c ON o.customer = c.id";
+
+    db.begin().unwrap();
+    db.execute("UPDATE customers SET id = 5000 WHERE id = 3")
+        .unwrap();
+    db.execute("UPDATE customers SET id = 7 WHERE id = 8")
+        .unwrap();
+    let mut other = db.connect();
+
+    let s = |v: &str| Value::String(v.into());
+    assert_eq!(
+        sorted_rows(&mut db, sql),
+        vec![
+            vec![Value::Int64(3), s("c7")],
+            vec![Value::Int64(3), s("c8")],
+        ]
+    );
+    // Other connections still see the committed rows.
+    assert_eq!(
+        sorted_rows(&mut other, sql),
+        vec![
+            vec![Value::Int64(1), s("c3")],
+            vec![Value::Int64(2), s("c3")],
+            vec![Value::Int64(3), s("c7")],
+        ]
+    );
+    db.rollback().unwrap();
+}
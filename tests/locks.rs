@@ -0,0 +1,251 @@
+mod common;
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, mpsc},
+    thread,
+    time::Duration,
+};
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    catalog::{catalog::Catalog, ids::TableId},
+    execution::errors::ExecutionResult,
+    storage::{
+        buffer::pool::{BufferPool, BufferPoolHandle},
+        heap::heap_table::HeapTable,
+        index::btree::key::IndexKey,
+        page::{page_id::PageId, row_id::RowId},
+        pagemgr::file::FilePageManager,
+    },
+    txn::{
+        lock_manager::{LockManager, LockMode, LockTarget},
+        transaction::TxnId,
+    },
+    types::value::Value,
+};
+
+use common::temp_db;
+
+fn open_db(path: &PathBuf, tables: &[&str]) -> Database {
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for table in tables {
+        db.execute(&format!("CREATE TABLE {table} (id BIGINT, v TEXT)"))
+            .unwrap();
+        db.execute(&format!("CREATE INDEX {table}_id ON {table} (id)"))
+            .unwrap();
+    }
+    db
+}
+
+fn row_count(db: &mut Database, table: &str) -> usize {
+    match db.execute(&format!("SELECT id FROM {table}")).unwrap() {
+        ExecutionResult::Query(q) => q.rows.len(),
+        _ => panic!("expected a query result"),
+    }
+}
+
+/// Read `table` directly, checking that its index on `id` matches the heap.
+/// Returns the rows as `id -> v`.
+fn contents(path: &PathBuf, table: &str) -> BTreeMap<i64, String> {
+    let pm = FilePageManager::open(path).unwrap();
+    let bp: BufferPoolHandle = Arc::new(Mutex::new(BufferPool::new(Box::new(pm))));
+    let catalog = Catalog::load(&bp).unwrap();
+    let meta = catalog.get_table_by_name(table).unwrap();
+    let heap = HeapTable::open(meta.id, meta.root_page.unwrap(), bp.clone()).unwrap();
+    let index = catalog
+        .get_index_by_name(&format!("{table}_id"))
+        .unwrap()
+        .index
+        .lock()
+        .unwrap();
+
+    let mut rows = BTreeMap::new();
+    for entry in heap.scan() {
+        let (rid, row) = entry.unwrap();
+        let (Value::Int64(id), Value::String(v)) = (&row.values[0], &row.values[1]) else {
+            panic!("unexpected row {:?}", row.values);
+        };
+        rows.insert(*id, v.clone());
+        assert_eq!(index.get(&IndexKey::Int(*id)).unwrap(), vec![rid]);
+    }
+    let indexed = index
+        .range(&IndexKey::Int(i64::MIN), &IndexKey::Int(i64::MAX))
+        .unwrap();
+    assert_eq!(indexed.len(), rows.len());
+    rows
+}
+
+/// Run a statement on its own, retrying if it is picked as a deadlock
+/// victim.
+fn autocommit(db: &mut Database, sql: &str) {
+    loop {
+        match db.execute(sql) {
+            Ok(_) => return,
+            Err(DbError::Deadlock(_)) => continue,
+            Err(e) => panic!("{sql}: {e}"),
+        }
+    }
+}
+
+#[test]
+fn lock_modes_conflict_and_upgrade() {
+    let locks = LockManager::new();
+    let (t1, t2) = (TxnId(1), TxnId(2));
+    let table = LockTarget::Table(TableId(1));
+    let row = LockTarget::Row(RowId {
+        page_id: PageId(1),
+        slot_id: 0,
+    });
+
+    // Intention locks coexist; a shared table lock does not fit next to
+    // another transaction's intention to write.
+    assert!(locks.try_lock(t1, table, LockMode::IntentionShared));
+    assert!(locks.try_lock(t2, table, LockMode::IntentionExclusive));
+    assert!(!locks.try_lock(t1, table, LockMode::Shared));
+    assert!(locks.try_lock(t2, row, LockMode::Exclusive));
+    assert!(!locks.try_lock(t1, row, LockMode::Shared));
+
+    // Releasing lets the upgrade through; upgrading twice combines modes.
+    locks.unlock_all(t2);
+    assert_eq!(locks.mode(t2, row), None);
+    assert!(locks.try_lock(t1, table, LockMode::Shared));
+    assert!(locks.try_lock(t1, table, LockMode::IntentionExclusive));
+    assert_eq!(
+        locks.mode(t1, table),
+        Some(LockMode::SharedIntentionExclusive)
+    );
+    assert!(!locks.try_lock(t2, table, LockMode::IntentionExclusive));
+    assert!(locks.try_lock(t2, table, LockMode::IntentionShared));
+
+    // Work outside a transaction never locks.
+    assert!(locks.try_lock(TxnId::NONE, table, LockMode::Exclusive));
+    assert_eq!(locks.mode(TxnId::NONE, table), None);
+}
+
+#[test]
+fn writer_waits_for_conflicting_transaction_but_readers_do_not() {
+    let path = temp_db("wait");
+    {
+        let mut a = open_db(&path, &["t"]);
+        a.execute("INSERT INTO t VALUES (1, 'one'), (2, 'two')")
+            .unwrap();
+        let mut b = a.connect();
+        let mut reader = a.connect();
+
+        a.execute("BEGIN").unwrap();
+        a.execute("DELETE FROM t").unwrap();
+
+        let (done, finished) = mpsc::channel();
+        let writer = thread::spawn(move || {
+            let result = b.execute("UPDATE t SET v = 'b'");
+            done.send(()).unwrap();
+            result.map(|r| match r {
+                ExecutionResult::Mutation(m) => m.rows_affected,
+                _ => panic!("expected a mutation result"),
+            })
+        });
+
+        // The update waits for the deleting transaction; a plain read does
+        // not, and still sees both rows.
+        assert!(finished.recv_timeout(Duration::from_millis(200)).is_err());
+        assert_eq!(row_count(&mut reader, "t"), 2);
+
+        // Once the delete is rolled back the update finds the rows again.
+        a.execute("ROLLBACK").unwrap();
+        finished.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(writer.join().unwrap().unwrap(), 2);
+    }
+
+    let expected = BTreeMap::from([(1, "b".to_string()), (2, "b".to_string())]);
+    assert_eq!(contents(&path, "t"), expected);
+}
+
+#[test]
+fn deadlock_victim_is_rolled_back_and_the_other_transaction_commits() {
+    let path = temp_db("deadlock");
+    let value = {
+        let mut a = open_db(&path, &["x", "y"]);
+        a.execute("INSERT INTO x VALUES (1, 'x')").unwrap();
+        a.execute("INSERT INTO y VALUES (1, 'y')").unwrap();
+        let mut b = a.connect();
+
+        a.execute("BEGIN").unwrap();
+        a.execute("UPDATE x SET v = 'a'").unwrap();
+        b.execute("BEGIN").unwrap();
+        b.execute("UPDATE y SET v = 'b'").unwrap();
+
+        let first = thread::spawn(move || {
+            let result = a.execute("UPDATE y SET v = 'a'");
+            (a, result)
+        });
+        // Give the first update time to start waiting on `b`.
+        thread::sleep(Duration::from_millis(100));
+        let second = b.execute("UPDATE x SET v = 'b'");
+        let (mut a, first) = first.join().unwrap();
+
+        // Exactly one of them is the victim and loses its transaction; the
+        // other one goes on and commits.
+        let (winner, value) = match (first, second) {
+            (Ok(_), Err(DbError::Deadlock(_))) => (&mut a, "a"),
+            (Err(DbError::Deadlock(_)), Ok(_)) => (&mut b, "b"),
+            (first, second) => panic!("expected one deadlock, got {first:?} and {second:?}"),
+        };
+        winner.execute("COMMIT").unwrap();
+        assert_eq!(a.transaction_id(), None);
+        assert_eq!(b.transaction_id(), None);
+        value
+    };
+
+    assert_eq!(
+        contents(&path, "x"),
+        BTreeMap::from([(1, value.to_string())])
+    );
+    assert_eq!(
+        contents(&path, "y"),
+        BTreeMap::from([(1, value.to_string())])
+    );
+}
+
+#[test]
+fn concurrent_writers_keep_heap_and_index_consistent() {
+    let path = temp_db("concurrent");
+    {
+        let db = open_db(&path, &["t"]);
+        let workers: Vec<_> = (0..4i64)
+            .map(|w| {
+                let mut conn = db.connect();
+                thread::spawn(move || {
+                    for i in 0..25 {
+                        let id = w * 100 + i;
+                        autocommit(&mut conn, &format!("INSERT INTO t VALUES ({id}, 'new')"));
+                        autocommit(&mut conn, &format!("UPDATE t SET v = 'w{w}'"));
+                        if i % 5 == 4 {
+                            // Rolled back either way; a deadlock just ends
+                            // it early.
+                            conn.execute("BEGIN").unwrap();
+                            let undone = conn
+                                .execute(&format!("INSERT INTO t VALUES ({}, 'gone')", id + 50))
+                                .and_then(|_| conn.execute("UPDATE t SET v = 'gone'"));
+                            match undone {
+                                Ok(_) => conn.execute("ROLLBACK").map(|_| ()).unwrap(),
+                                Err(DbError::Deadlock(_)) => {}
+                                Err(e) => panic!("{e}"),
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+    }
+
+    let rows = contents(&path, "t");
+    assert_eq!(rows.len(), 100);
+    // Every row carries the value of whichever update committed last, and
+    // that update covered all rows present when it ran.
+    assert!(rows.values().all(|v| v.starts_with('w')));
+}
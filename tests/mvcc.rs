@@ -0,0 +1,290 @@
+mod common;
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use helium::{
+    api::db::Database,
+    catalog::{catalog::Catalog, ids::TableId},
+    execution::{context::ExecutionContext, engine::execute_query, errors::ExecutionResult},
+    ir::{
+        index_predicate::IndexPredicate,
+        plan::{FromId, LogicalPlan},
+    },
+    storage::{
+        buffer::pool::{BufferPool, BufferPoolHandle},
+        heap::heap_table::HeapTable,
+        index::btree::key::IndexKey,
+        manager::StorageManager,
+        pagemgr::file::FilePageManager,
+    },
+    txn::{
+        snapshot::Snapshot,
+        transaction::TxnId,
+        wal::writer::{WalHandle, WalWriter},
+    },
+    types::value::Value,
+};
+
+use common::temp_db;
+
+fn open_logged(path: &PathBuf) -> (WalHandle, BufferPoolHandle) {
+    let wal = Arc::new(Mutex::new(
+        WalWriter::open(&Database::wal_dir(path)).unwrap(),
+    ));
+    let mut pool = BufferPool::new(Box::new(FilePageManager::open(path).unwrap()));
+    pool.attach_wal(wal.clone());
+    (wal, Arc::new(Mutex::new(pool)))
+}
+
+fn ints(heap: &HeapTable, snapshot: &Snapshot) -> Vec<i64> {
+    let mut ids: Vec<i64> = heap
+        .scan_at(snapshot)
+        .map(|entry| match entry.unwrap().1.values[0] {
+            Value::Int64(v) => v,
+            ref v => panic!("unexpected value {v:?}"),
+        })
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn snapshot_ignores_changes_committed_after_it_was_taken() {
+    let path = temp_db("visibility");
+    let (wal, bp) = open_logged(&path);
+    let heap = HeapTable::create(TableId(1), bp).unwrap();
+
+    let setup = wal.lock().unwrap().begin().unwrap();
+    let a = heap.insert(setup, vec![Value::Int64(1)]).unwrap();
+    let b = heap.insert(setup, vec![Value::Int64(2)]).unwrap();
+    wal.lock().unwrap().commit(setup).unwrap();
+
+    let before = wal.lock().unwrap().snapshot(TxnId::NONE);
+    let writer = wal.lock().unwrap().begin().unwrap();
+    heap.update(writer, a, vec![Value::Int64(10)]).unwrap();
+    heap.delete(writer, b).unwrap();
+    heap.insert(writer, vec![Value::Int64(3)]).unwrap();
+
+    // The writer sees its own changes, nobody else does yet.
+    let own = wal.lock().unwrap().snapshot(writer);
+    assert_eq!(ints(&heap, &own), vec![3, 10]);
+    let during = wal.lock().unwrap().snapshot(TxnId::NONE);
+    assert_eq!(ints(&heap, &during), vec![1, 2]);
+
+    wal.lock().unwrap().commit(writer).unwrap();
+    assert_eq!(ints(&heap, &before), vec![1, 2]);
+    assert_eq!(ints(&heap, &during), vec![1, 2]);
+    assert_eq!(
+        heap.fetch_at(b, &before).unwrap().unwrap().values,
+        vec![Value::Int64(2)]
+    );
+    // The new row took over the freed slot; older snapshots still find the
+    // deleted one there.
+    assert_eq!(
+        heap.fetch_at(b, &own).unwrap().unwrap().values,
+        vec![Value::Int64(3)]
+    );
+
+    let after = wal.lock().unwrap().snapshot(TxnId::NONE);
+    assert_eq!(ints(&heap, &after), vec![3, 10]);
+
+    // Once no running transaction is older than the writer, its versions
+    // are dropped and even a stale snapshot reads the newest rows.
+    assert_eq!(heap.changed_rows(&before).unwrap(), vec![a, b]);
+    heap.prune_versions(wal.lock().unwrap().oldest_active());
+    assert!(heap.changed_rows(&before).unwrap().is_empty());
+    assert_eq!(ints(&heap, &before), vec![3, 10]);
+}
+
+#[test]
+fn readers_see_consistent_totals_while_a_writer_commits() {
+    let path = temp_db("concurrent");
+    {
+        let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+        db.execute("CREATE TABLE accounts (balance INT)").unwrap();
+    }
+
+    let (wal, bp) = open_logged(&path);
+    let catalog = Catalog::load(&bp).unwrap();
+    let storage = Arc::new(StorageManager::new(bp));
+    let heap = storage
+        .get_table(catalog.get_table_by_name("accounts").unwrap())
+        .unwrap();
+
+    let setup = wal.lock().unwrap().begin().unwrap();
+    let accounts: Vec<_> = (0..10)
+        .map(|_| heap.insert(setup, vec![Value::Int64(100)]).unwrap())
+        .collect();
+    wal.lock().unwrap().commit(setup).unwrap();
+
+    let writer = {
+        let (wal, storage, heap) = (wal.clone(), storage.clone(), heap.clone());
+        thread::spawn(move || {
+            let mut balances = vec![100i64; accounts.len()];
+            for i in 0..300 {
+                let (from, to) = (i % accounts.len(), (i * 7 + 3) % accounts.len());
+                if from == to {
+                    continue;
+                }
+                balances[from] -= 5;
+                balances[to] += 5;
+
+                let txn = wal.lock().unwrap().begin().unwrap();
+                heap.update(txn, accounts[from], vec![Value::Int64(balances[from])])
+                    .unwrap();
+                heap.update(txn, accounts[to], vec![Value::Int64(balances[to])])
+                    .unwrap();
+                wal.lock().unwrap().commit(txn).unwrap();
+                storage.prune_versions();
+            }
+        })
+    };
+
+    let readers: Vec<_> = (0..3)
+        .map(|_| {
+            let (storage, heap) = (storage.clone(), heap.clone());
+            thread::spawn(move || {
+                for _ in 0..200 {
+                    let snapshot = storage.snapshot(TxnId::NONE);
+                    assert_eq!(ints(&heap, &snapshot).iter().sum::<i64>(), 1000);
+                }
+            })
+        })
+        .collect();
+
+    writer.join().unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+}
+
+#[test]
+fn index_scan_returns_rows_as_of_its_snapshot() {
+    let path = temp_db("index");
+    {
+        let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+        db.execute("CREATE TABLE t (id BIGINT, v TEXT)").unwrap();
+        db.execute("CREATE INDEX t_id ON t (id)").unwrap();
+        db.execute("INSERT INTO t VALUES (1, 'a'), (2, 'b')")
+            .unwrap();
+    }
+
+    let (wal, bp) = open_logged(&path);
+    let catalog = Catalog::load(&bp).unwrap();
+    let storage = StorageManager::new(bp);
+    let table = catalog.get_table_by_name("t").unwrap();
+    let index = catalog.get_index_by_name("t_id").unwrap();
+    let heap = storage.get_table(table).unwrap();
+
+    let reader = storage.snapshot(TxnId::NONE);
+
+    // Change the key of row 1, delete row 2 and add row 3, keeping the
+    // index in step the way the executors do.
+    let writer = wal.lock().unwrap().begin().unwrap();
+    {
+        let mut idx = index.index.lock().unwrap();
+        let one = idx.get(&IndexKey::Int(1)).unwrap()[0];
+        let moved = heap
+            .update(
+                writer,
+                one,
+                vec![Value::Int64(10), Value::String("a".into())],
+            )
+            .unwrap();
+        idx.delete(&IndexKey::Int(1), one).unwrap();
+        idx.insert(IndexKey::Int(10), moved).unwrap();
+
+        let two = idx.get(&IndexKey::Int(2)).unwrap()[0];
+        heap.delete(writer, two).unwrap();
+        idx.delete(&IndexKey::Int(2), two).unwrap();
+
+        let three = heap
+            .insert(writer, vec![Value::Int64(3), Value::String("c".into())])
+            .unwrap();
+        idx.insert(IndexKey::Int(3), three).unwrap();
+    }
+    wal.lock().unwrap().commit(writer).unwrap();
+
+    // Rows are compared as a set: versions rebuilt from the heap come after
+    // those the index lists.
+    let lookup = |snapshot: &Arc<Snapshot>, predicate: IndexPredicate| -> Vec<i64> {
+        let plan = LogicalPlan::IndexScan {
+            table_id: table.id,
+            from: FromId(1),
+            index_id: index.meta.id,
+            predicate,
+        };
+        let mut ctx = ExecutionContext::new(&catalog, &storage, TxnId::NONE, snapshot.clone());
+        match execute_query(plan, &mut ctx).unwrap() {
+            ExecutionResult::Query(q) => {
+                let mut ids: Vec<i64> = q
+                    .rows
+                    .iter()
+                    .map(|r| match r[0] {
+                        Value::Int64(v) => v,
+                        ref v => panic!("unexpected value {v:?}"),
+                    })
+                    .collect();
+                ids.sort();
+                ids
+            }
+            _ => panic!("expected a query result"),
+        }
+    };
+    let eq = |v| IndexPredicate::Eq(Value::Int64(v));
+    let all = IndexPredicate::Range {
+        low: Value::Int64(0),
+        high: Value::Int64(100),
+    };
+
+    assert_eq!(lookup(&reader, eq(1)), vec![1]);
+    assert_eq!(lookup(&reader, eq(2)), vec![2]);
+    assert_eq!(lookup(&reader, eq(10)), Vec::<i64>::new());
+    assert_eq!(lookup(&reader, eq(3)), Vec::<i64>::new());
+    assert_eq!(lookup(&reader, all.clone()), vec![1, 2]);
+
+    let latest = storage.snapshot(TxnId::NONE);
+    assert_eq!(lookup(&latest, eq(1)), Vec::<i64>::new());
+    assert_eq!(lookup(&latest, all), vec![3, 10]);
+
+    // The old reader still holds the versions back.
+    storage.prune_versions();
+    assert_eq!(lookup(&reader, eq(2)), vec![2]);
+    let stale = Snapshot::clone(&reader);
+    drop(reader);
+    storage.prune_versions();
+    assert!(heap.changed_rows(&stale).unwrap().is_empty());
+}
+
+#[test]
+fn old_row_versions_are_read_back_from_disk() {
+    let path = temp_db("on_page");
+    let (wal, bp) = open_logged(&path);
+    let heap = HeapTable::create(TableId(1), bp.clone()).unwrap();
+
+    let setup = wal.lock().unwrap().begin().unwrap();
+    let a = heap.insert(setup, vec![Value::Int64(1)]).unwrap();
+    let b = heap.insert(setup, vec![Value::Int64(2)]).unwrap();
+    wal.lock().unwrap().commit(setup).unwrap();
+
+    let before = wal.lock().unwrap().snapshot(TxnId::NONE);
+    let writer = wal.lock().unwrap().begin().unwrap();
+    heap.update(writer, a, vec![Value::Int64(10)]).unwrap();
+    heap.delete(writer, b).unwrap();
+    wal.lock().unwrap().commit(writer).unwrap();
+
+    // A heap read through a fresh pool only has the pages to go by.
+    bp.lock().unwrap().flush_all().unwrap();
+    let reopened_bp = Arc::new(Mutex::new(BufferPool::new(Box::new(
+        FilePageManager::open(&path).unwrap(),
+    ))));
+    let reopened = HeapTable::open(TableId(1), heap.root_page(), reopened_bp).unwrap();
+
+    assert_eq!(ints(&reopened, &before), vec![1, 2]);
+    let after = wal.lock().unwrap().snapshot(TxnId::NONE);
+    assert_eq!(ints(&reopened, &after), vec![10]);
+}
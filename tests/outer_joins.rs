@@ -0,0 +1,155 @@
+mod common;
+
+use helium::{
+    api::{
+        db::Database,
+        errors::{DbError, QueryResult},
+    },
+    binder::errors::BindError,
+    execution::errors::ExecutionResult,
+    types::value::Value,
+};
+
+use common::{int, rows, temp_db, text};
+
+/// `cid` has no orders and order 14 has no customer.
+fn shop(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE customers (id BIGINT NOT NULL, name TEXT)",
+        "CREATE TABLE orders (order_id BIGINT NOT NULL, id BIGINT, total BIGINT)",
+        "INSERT INTO customers VALUES (1, 'ann'), (2, 'bob'), (3, 'cid')",
+        "INSERT INTO orders VALUES (10, 1, 50), (11, 2, 20), (12, 1, 30), (14, 9, 5)",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+fn query(db: &mut Database, sql: &str) -> QueryResult {
+    match db.execute(sql).unwrap() {
+        ExecutionResult::Query(q) => q,
+        _ => panic!("expected a query result"),
+    }
+}
+
+#[test]
+fn outer_joins_pad_unmatched_rows_with_nulls() {
+    let mut db = shop("pad");
+    let null = Value::Null;
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT c.name, o.order_id FROM customers c LEFT JOIN orders o ON c.id = o.id \
+             ORDER BY c.name, o.order_id"
+        ),
+        vec![
+            vec![text("ann"), int(10)],
+            vec![text("ann"), int(12)],
+            vec![text("bob"), int(11)],
+            vec![text("cid"), null.clone()],
+        ]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT c.name, o.order_id FROM customers c RIGHT OUTER JOIN orders o \
+             ON c.id = o.id ORDER BY o.order_id"
+        ),
+        vec![
+            vec![text("ann"), int(10)],
+            vec![text("bob"), int(11)],
+            vec![text("ann"), int(12)],
+            vec![null.clone(), int(14)],
+        ]
+    );
+    // Columns of the padded side become nullable in the result.
+    let q = query(
+        &mut db,
+        "SELECT c.id, o.order_id FROM customers c LEFT JOIN orders o ON c.id = o.id",
+    );
+    assert!(!q.schema.columns[0].nullable);
+    assert!(q.schema.columns[1].nullable);
+}
+
+#[test]
+fn full_joins_keep_unmatched_rows_from_both_sides() {
+    let mut db = shop("full");
+
+    let mut got = rows(
+        &mut db,
+        "SELECT c.name, o.order_id FROM customers c FULL OUTER JOIN orders o ON c.id = o.id",
+    );
+    got.sort_by(|a, b| format!("{:?}", a).cmp(&format!("{:?}", b)));
+    assert_eq!(
+        got,
+        vec![
+            vec![Value::Null, int(14)],
+            vec![text("ann"), int(10)],
+            vec![text("ann"), int(12)],
+            vec![text("bob"), int(11)],
+            vec![text("cid"), Value::Null],
+        ]
+    );
+}
+
+#[test]
+fn where_filters_apply_after_padding() {
+    let mut db = shop("filters");
+
+    // A filter on the padded side must not run below the join, or it would
+    // remove the order rows and pad every customer instead.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT c.name, o.total FROM customers c LEFT JOIN orders o ON c.id = o.id \
+             WHERE o.total > 25 ORDER BY o.total"
+        ),
+        vec![vec![text("ann"), int(30)], vec![text("ann"), int(50)]]
+    );
+    // Filters on the preserved side still narrow the result.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT c.name, o.order_id FROM customers c LEFT JOIN orders o ON c.id = o.id \
+             WHERE c.id > 1 AND c.name = 'cid'"
+        ),
+        vec![vec![text("cid"), Value::Null]]
+    );
+}
+
+#[test]
+fn using_and_cross_joins() {
+    let mut db = shop("using");
+
+    let q = query(
+        &mut db,
+        "SELECT * FROM customers JOIN orders USING (id) WHERE id = 2",
+    );
+    assert_eq!(
+        q.schema.names().collect::<Vec<_>>(),
+        vec!["id", "name", "order_id", "total"]
+    );
+    assert_eq!(q.rows, vec![vec![int(2), text("bob"), int(11), int(20)]]);
+
+    // The merged column comes from the side whose rows are kept.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id, order_id FROM customers RIGHT JOIN orders USING (id) \
+             WHERE order_id = 14"
+        ),
+        vec![vec![int(9), int(14)]]
+    );
+    assert!(matches!(
+        db.execute("SELECT * FROM customers JOIN orders USING (name)"),
+        Err(DbError::Bind(BindError::UnknownColumn(_)))
+    ));
+
+    assert_eq!(
+        rows(&mut db, "SELECT COUNT(*) FROM customers CROSS JOIN orders"),
+        vec![vec![int(12)]]
+    );
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use helium::{
+    api::{db::Database, errors::QueryResult},
+    execution::errors::ExecutionResult,
+    types::{datatype::DataType, schema::OutputColumn},
+};
+
+use common::temp_db;
+
+fn open_db(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE users (id INT NOT NULL, name TEXT)",
+        "CREATE TABLE posts (post_id INT NOT NULL, author INT NOT NULL, title TEXT)",
+        "INSERT INTO users VALUES (1, 'ann')",
+        "INSERT INTO posts VALUES (7, 1, 'hello')",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+fn query(db: &mut Database, sql: &str) -> QueryResult {
+    match db.execute(sql).unwrap() {
+        ExecutionResult::Query(q) => q,
+        _ => panic!("expected a query result"),
+    }
+}
+
+/// Name, type, nullability and whether the column has a source table.
+fn shape(c: &OutputColumn) -> (&str, DataType, bool, bool) {
+    (
+        c.name.as_str(),
+        c.data_type.clone(),
+        c.nullable,
+        c.table.is_some(),
+    )
+}
+
+#[test]
+fn projected_columns_keep_names_types_and_sources() {
+    let mut db = open_db("project");
+    let users = db.catalog().get_table_by_name("users").unwrap().id;
+
+    let q = query(
+        &mut db,
+        "SELECT name, id AS user_id, id + 1, id + 1 AS next, 'x' FROM users",
+    );
+    let text = DataType::Varchar { max_len: None };
+    assert_eq!(
+        q.schema.columns.iter().map(shape).collect::<Vec<_>>(),
+        vec![
+            ("name", text.clone(), true, true),
+            ("user_id", DataType::Int32, false, true),
+            ("?column?", DataType::Int32, false, false),
+            ("next", DataType::Int32, false, false),
+            ("?column?", text, false, false),
+        ]
+    );
+    assert_eq!(q.schema.columns[0].table, Some(users));
+    assert_eq!(q.schema.position("next"), Some(3));
+    assert_eq!(q.rows[0].len(), q.schema.len());
+}
+
+#[test]
+fn function_calls_are_named_after_their_function() {
+    let mut db = open_db("functions");
+
+    let q = query(
+        &mut db,
+        "SELECT UPPER(name), now(), name || '!', COALESCE(name, 'x') AS shown FROM users",
+    );
+    assert_eq!(
+        q.schema.names().collect::<Vec<_>>(),
+        vec!["upper", "now", "?column?", "shown"]
+    );
+}
+
+#[test]
+fn star_lists_columns_of_every_table_in_from_order() {
+    let mut db = open_db("star");
+    let catalog = db.catalog();
+    let posts = catalog.get_table_by_name("posts").unwrap().id;
+    let users = catalog.get_table_by_name("users").unwrap().id;
+
+    let q = query(&mut db, "SELECT * FROM posts JOIN users ON author = id");
+    assert_eq!(
+        q.schema.names().collect::<Vec<_>>(),
+        vec!["post_id", "author", "title", "id", "name"]
+    );
+    let tables: Vec<_> = q.schema.columns.iter().map(|c| c.table.unwrap()).collect();
+    assert_eq!(tables, vec![posts, posts, posts, users, users]);
+    assert_eq!(q.rows[0].len(), 5);
+}
+
+#[test]
+fn filtered_sorted_and_indexed_queries_report_their_projection() {
+    let mut db = open_db("index");
+    db.execute("CREATE INDEX users_id ON users (id)").unwrap();
+
+    for sql in [
+        "SELECT name AS who FROM users WHERE id = 1",
+        "SELECT name AS who FROM users WHERE id > 0 ORDER BY id LIMIT 1",
+    ] {
+        let q = query(&mut db, sql);
+        assert_eq!(q.schema.names().collect::<Vec<_>>(), vec!["who"], "{sql}");
+        assert_eq!(q.rows.len(), 1, "{sql}");
+    }
+}
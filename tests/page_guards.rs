@@ -0,0 +1,214 @@
+mod common;
+
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Duration,
+};
+
+use helium::{
+    catalog::ids::TableId,
+    storage::{
+        buffer::{
+            frame::PAGE_SIZE,
+            pool::{BufferPool, BufferPoolHandle},
+            replacer::LruReplacer,
+        },
+        errors::{StorageError, StorageResult},
+        heap::heap_table::HeapTable,
+        index::btree::disk::BPlusTree,
+        page::{page_id::PageId, row_id::RowId},
+        pagemgr::{file::FilePageManager, manager::PageManager},
+    },
+    txn::transaction::TxnId,
+    types::value::Value,
+};
+
+use common::temp_db;
+
+/// A file page manager that counts the pages written through it.
+struct CountingPageManager {
+    inner: FilePageManager,
+    writes: Arc<AtomicUsize>,
+}
+
+impl PageManager for CountingPageManager {
+    fn allocate_page(&mut self) -> PageId {
+        self.inner.allocate_page()
+    }
+
+    fn read_page(&mut self, id: PageId, buf: &mut [u8; PAGE_SIZE]) -> StorageResult<()> {
+        self.inner.read_page(id, buf)
+    }
+
+    fn write_page(&mut self, id: PageId, data: &[u8; PAGE_SIZE]) -> StorageResult<()> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        self.inner.write_page(id, data)
+    }
+
+    fn num_pages(&self) -> u64 {
+        self.inner.num_pages()
+    }
+
+    fn deallocate_page(&mut self, id: PageId) {
+        self.inner.deallocate_page(id)
+    }
+
+    fn free_pages(&self) -> Vec<PageId> {
+        self.inner.free_pages()
+    }
+
+    fn set_free_pages(&mut self, pages: Vec<PageId>) {
+        self.inner.set_free_pages(pages)
+    }
+
+    fn sync(&mut self) -> StorageResult<()> {
+        self.inner.sync()
+    }
+}
+
+fn pool(path: &PathBuf, capacity: usize) -> BufferPoolHandle {
+    let pm = FilePageManager::open(path).unwrap();
+    Arc::new(Mutex::new(BufferPool::with_replacer(
+        Box::new(pm),
+        capacity,
+        Box::new(LruReplacer::new()),
+    )))
+}
+
+#[test]
+fn failed_reads_leave_no_pins_behind() {
+    let path = temp_db("errors");
+    let bp = pool(&path, 2);
+    let heap = HeapTable::create(TableId(1), bp.clone()).unwrap();
+    heap.insert(TxnId::NONE, vec![Value::Int64(1)]).unwrap();
+    let not_a_tree = heap.root_page();
+
+    // Each of these pins a page and fails while reading it.
+    for slot_id in 1..50 {
+        let rid = RowId {
+            page_id: not_a_tree,
+            slot_id,
+        };
+        assert!(matches!(
+            heap.fetch(rid),
+            Err(StorageError::InvalidRowId { .. })
+        ));
+        assert!(matches!(
+            BPlusTree::open(not_a_tree, bp.clone()),
+            Err(StorageError::IndexCorrupted { .. })
+        ));
+    }
+
+    // Both frames are still free to be pinned at once.
+    let other = bp.lock().unwrap().allocate_page().unwrap();
+    let _a = BufferPool::fetch_page_write(&bp, not_a_tree).unwrap();
+    let _b = BufferPool::fetch_page_write(&bp, other).unwrap();
+}
+
+#[test]
+fn only_mutable_access_dirties_a_page() {
+    let path = temp_db("dirty");
+    let writes = Arc::new(AtomicUsize::new(0));
+    let pm = CountingPageManager {
+        inner: FilePageManager::open(&path).unwrap(),
+        writes: writes.clone(),
+    };
+    let bp: BufferPoolHandle = Arc::new(Mutex::new(BufferPool::new(Box::new(pm))));
+    let pid = BufferPool::new_page(&bp).unwrap().id;
+    bp.lock().unwrap().flush_all().unwrap();
+    assert_eq!(writes.load(Ordering::SeqCst), 1);
+
+    {
+        let page = BufferPool::fetch_page_write(&bp, pid).unwrap();
+        assert_eq!(page.data[0], 0);
+    }
+    bp.lock().unwrap().flush_all().unwrap();
+    assert_eq!(writes.load(Ordering::SeqCst), 1);
+
+    BufferPool::fetch_page_write(&bp, pid).unwrap().data[0] = 7;
+    bp.lock().unwrap().flush_all().unwrap();
+    assert_eq!(writes.load(Ordering::SeqCst), 2);
+
+    drop(bp);
+    let mut buf = [0; PAGE_SIZE];
+    FilePageManager::open(&path)
+        .unwrap()
+        .read_page(pid, &mut buf)
+        .unwrap();
+    assert_eq!(buf[0], 7);
+}
+
+#[test]
+fn a_page_being_written_only_holds_up_its_own_readers() {
+    let path = temp_db("latches");
+    let bp = pool(&path, 8);
+    let (a, b) = {
+        let mut pool = bp.lock().unwrap();
+        (pool.allocate_page().unwrap(), pool.allocate_page().unwrap())
+    };
+
+    let mut writer = BufferPool::fetch_page_write(&bp, a).unwrap();
+    writer.data[0] = 1;
+
+    // Holding a page does not hold the pool.
+    let reader_b = BufferPool::fetch_page_read(&bp, b).unwrap();
+    assert_eq!(bp.lock().unwrap().resident_pages(), 2);
+
+    // Other threads read `b` alongside us, but wait for `a`.
+    let (done, finished) = mpsc::channel();
+    let readers: Vec<_> = [b, a]
+        .into_iter()
+        .map(|pid| {
+            let bp = bp.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                let page = BufferPool::fetch_page_read(&bp, pid).unwrap();
+                done.send((pid, page.data[0])).unwrap();
+            })
+        })
+        .collect();
+    assert_eq!(
+        finished.recv_timeout(Duration::from_secs(10)).unwrap(),
+        (b, 0)
+    );
+    assert!(finished.recv_timeout(Duration::from_millis(200)).is_err());
+
+    // Once the writer lets go, the waiting reader sees its change.
+    drop(writer);
+    assert_eq!(
+        finished.recv_timeout(Duration::from_secs(10)).unwrap(),
+        (a, 1)
+    );
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    drop(reader_b);
+}
+
+#[test]
+fn quiesced_pool_waits_for_writers_to_finish() {
+    let path = temp_db("quiesce");
+    let bp = pool(&path, 8);
+    let pid = bp.lock().unwrap().allocate_page().unwrap();
+
+    let writer = BufferPool::fetch_page_write(&bp, pid).unwrap();
+    let (done, finished) = mpsc::channel();
+    let flusher = {
+        let bp = bp.clone();
+        thread::spawn(move || {
+            BufferPool::lock_quiesced(&bp).flush_all().unwrap();
+            done.send(()).unwrap();
+        })
+    };
+
+    assert!(finished.recv_timeout(Duration::from_millis(200)).is_err());
+    drop(writer);
+    finished.recv_timeout(Duration::from_secs(10)).unwrap();
+    flusher.join().unwrap();
+}
@@ -0,0 +1,72 @@
+use helium::{
+    frontend::sql::{
+        ast::{BinaryOp, Expr, QueryBody, Statement},
+        parser::Parser,
+    },
+    types::value::Value,
+};
+
+/// Expressions the single SELECT of `sql` outputs.
+fn select_list(sql: &str) -> Vec<Expr> {
+    let Statement::Select(query) = Parser::new(sql).parse_statements().unwrap().remove(0) else {
+        panic!("expected a SELECT");
+    };
+    let QueryBody::Select(select) = query.body else {
+        panic!("expected a plain SELECT");
+    };
+    select.columns.into_iter().map(|item| item.expr).collect()
+}
+
+fn column(name: &str) -> Expr {
+    Expr::Column {
+        table: None,
+        name: name.into(),
+    }
+}
+
+fn binary(left: Expr, op: BinaryOp, right: Expr) -> Expr {
+    Expr::Binary {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    }
+}
+
+#[test]
+fn percent_binds_as_tightly_as_multiplication() {
+    assert_eq!(
+        select_list("SELECT a + b % 3 * 2, a%b FROM t"),
+        vec![
+            binary(
+                column("a"),
+                BinaryOp::Add,
+                binary(
+                    binary(column("b"), BinaryOp::Mod, Expr::Literal(Value::Int64(3))),
+                    BinaryOp::Mul,
+                    Expr::Literal(Value::Int64(2)),
+                ),
+            ),
+            binary(column("a"), BinaryOp::Mod, column("b")),
+        ]
+    );
+}
+
+#[test]
+fn numbers_may_have_exponents_and_start_with_a_point() {
+    let float = |v: f64| Expr::Literal(Value::Float64(v));
+    assert_eq!(
+        select_list("SELECT 1e3, 2.5E-2, 4e+1, .5, .25e2, 7, t.x FROM t"),
+        vec![
+            float(1000.0),
+            float(0.025),
+            float(40.0),
+            float(0.5),
+            float(25.0),
+            Expr::Literal(Value::Int64(7)),
+            Expr::Column {
+                table: Some("t".into()),
+                name: "x".into(),
+            },
+        ]
+    );
+}
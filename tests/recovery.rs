@@ -0,0 +1,295 @@
+mod common;
+
+use std::{
+    collections::BTreeMap,
+    panic::{AssertUnwindSafe, catch_unwind},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use helium::{
+    api::db::Database,
+    catalog::catalog::Catalog,
+    execution::errors::ExecutionResult,
+    storage::{
+        buffer::{
+            frame::PAGE_SIZE,
+            pool::{BufferPool, BufferPoolHandle},
+        },
+        errors::{StorageError, StorageResult},
+        heap::heap_table::HeapTable,
+        index::btree::key::IndexKey,
+        manager::StorageManager,
+        page::page_id::PageId,
+        pagemgr::{file::FilePageManager, manager::PageManager},
+    },
+    txn::wal::{reader::WalReader, record::LogBody, writer::WalWriter},
+    types::value::Value,
+};
+
+use common::temp_db;
+
+fn open_db(path: &PathBuf) -> Database {
+    Database::new(path.to_string_lossy().into()).unwrap()
+}
+
+/// Serves pages from the file until `writes` page writes have been made,
+/// then fails every write after them, so the file looks as if the process
+/// died right there.
+struct CrashingPageManager {
+    inner: FilePageManager,
+    writes: usize,
+    crashed: Arc<AtomicBool>,
+}
+
+impl PageManager for CrashingPageManager {
+    fn allocate_page(&mut self) -> PageId {
+        self.inner.allocate_page()
+    }
+
+    fn read_page(&mut self, id: PageId, buf: &mut [u8; PAGE_SIZE]) -> StorageResult<()> {
+        self.inner.read_page(id, buf)
+    }
+
+    fn write_page(&mut self, id: PageId, data: &[u8; PAGE_SIZE]) -> StorageResult<()> {
+        if self.writes == 0 {
+            self.crashed.store(true, Ordering::SeqCst);
+            return Err(StorageError::Io {
+                message: format!("simulated crash before writing page {}", id.0),
+            });
+        }
+        self.writes -= 1;
+        self.inner.write_page(id, data)
+    }
+
+    fn num_pages(&self) -> u64 {
+        self.inner.num_pages()
+    }
+
+    fn deallocate_page(&mut self, id: PageId) {
+        self.inner.deallocate_page(id)
+    }
+
+    fn free_pages(&self) -> Vec<PageId> {
+        self.inner.free_pages()
+    }
+
+    fn set_free_pages(&mut self, pages: Vec<PageId>) {
+        self.inner.set_free_pages(pages)
+    }
+
+    fn sync(&mut self) -> StorageResult<()> {
+        self.inner.sync()
+    }
+}
+
+fn create_schema(path: &PathBuf) {
+    let mut db = open_db(path);
+    db.execute("CREATE TABLE t (id BIGINT, v TEXT)").unwrap();
+    db.execute("CREATE INDEX t_id ON t (id)").unwrap();
+}
+
+fn row_count(db: &mut Database) -> usize {
+    match db.execute("SELECT id FROM t").unwrap() {
+        ExecutionResult::Query(q) => q.rows.len(),
+        _ => panic!("expected a query result"),
+    }
+}
+
+/// Open the file without a log and check that the heap and the index on
+/// `id` describe the same rows. Returns the rows as `id -> v`.
+fn check_invariants(path: &PathBuf) -> BTreeMap<i64, String> {
+    let pm = FilePageManager::open(path).unwrap();
+    let bp: BufferPoolHandle = Arc::new(Mutex::new(BufferPool::new(Box::new(pm))));
+    let catalog = Catalog::load(&bp).unwrap();
+    let table = catalog.get_table_by_name("t").unwrap();
+    let heap = HeapTable::open(table.id, table.root_page.unwrap(), bp.clone()).unwrap();
+    let index = catalog
+        .get_index_by_name("t_id")
+        .unwrap()
+        .index
+        .lock()
+        .unwrap();
+
+    let mut rows = BTreeMap::new();
+    for entry in heap.scan() {
+        let (rid, row) = entry.unwrap();
+        let (Value::Int64(id), Value::String(v)) = (&row.values[0], &row.values[1]) else {
+            panic!("unexpected row {:?}", row.values);
+        };
+        assert!(rows.insert(*id, v.clone()).is_none(), "duplicate id {id}");
+        assert_eq!(index.get(&IndexKey::Int(*id)).unwrap(), vec![rid]);
+    }
+
+    let indexed = index
+        .range(&IndexKey::Int(i64::MIN), &IndexKey::Int(i64::MAX))
+        .unwrap();
+    assert_eq!(indexed.len(), rows.len(), "index has entries without rows");
+    rows
+}
+
+/// Runs a mix of statements with periodic flushes and a final checkpoint,
+/// recording in `model` every statement that committed.
+fn workload(db: &mut Database, model: &mut BTreeMap<i64, String>) {
+    for i in 0..40i64 {
+        let v = format!("row {i}");
+        db.execute(&format!("INSERT INTO t VALUES ({i}, '{v}')"))
+            .unwrap();
+        model.insert(i, v);
+
+        if i % 7 == 6 {
+            // Longer values move some rows to other pages.
+            let v = "y".repeat(i as usize * 4);
+            db.execute(&format!("UPDATE t SET v = '{v}'")).unwrap();
+            model.values_mut().for_each(|old| *old = v.clone());
+        }
+        if i == 25 {
+            db.execute("DELETE FROM t").unwrap();
+            model.clear();
+        }
+        if i % 4 == 3 {
+            db.flush().unwrap();
+        }
+    }
+    db.checkpoint().unwrap();
+}
+
+#[test]
+fn crash_at_every_page_write_recovers_committed_state() {
+    for budget in 0.. {
+        let path = temp_db("crash");
+        create_schema(&path);
+
+        let crashed = Arc::new(AtomicBool::new(false));
+        let pm = CrashingPageManager {
+            inner: FilePageManager::open(&path).unwrap(),
+            writes: budget,
+            crashed: crashed.clone(),
+        };
+        let mut db = Database::with_page_manager(&path, Box::new(pm)).unwrap();
+
+        // A statement that hits the failed write stops the workload.
+        let mut model = BTreeMap::new();
+        let failed = catch_unwind(AssertUnwindSafe(|| workload(&mut db, &mut model))).is_err();
+        // The process "dies": no destructor gets to flush anything.
+        std::mem::forget(db);
+
+        let mut db = open_db(&path);
+        assert_eq!(
+            row_count(&mut db),
+            model.len(),
+            "crash after {budget} page writes"
+        );
+        drop(db);
+        assert_eq!(
+            check_invariants(&path),
+            model,
+            "crash after {budget} page writes"
+        );
+
+        if !failed && !crashed.load(Ordering::SeqCst) {
+            assert!(budget > 0);
+            break;
+        }
+    }
+}
+
+#[test]
+fn uncommitted_changes_flushed_before_a_crash_are_undone() {
+    let path = temp_db("loser");
+    create_schema(&path);
+    {
+        let mut db = open_db(&path);
+        for i in 0..5 {
+            db.execute(&format!("INSERT INTO t VALUES ({i}, 'committed')"))
+                .unwrap();
+        }
+    }
+
+    // A transaction that writes enough to grow the heap and split index
+    // nodes, has its pages stolen to disk, and then never commits.
+    let loser = {
+        let wal = Arc::new(Mutex::new(
+            WalWriter::open(&Database::wal_dir(&path)).unwrap(),
+        ));
+        let mut pool = BufferPool::new(Box::new(FilePageManager::open(&path).unwrap()));
+        pool.attach_wal(wal.clone());
+        let bp: BufferPoolHandle = Arc::new(Mutex::new(pool));
+        let catalog = Catalog::load(&bp).unwrap();
+        let storage = StorageManager::new(bp);
+
+        let table = catalog.get_table_by_name("t").unwrap();
+        let index = catalog.get_index_by_name("t_id").unwrap();
+        let heap = storage.get_table(table).unwrap();
+        let pages_before = heap.page_ids().len();
+
+        let txn = wal.lock().unwrap().begin().unwrap();
+        for i in 100..400 {
+            let rid = heap
+                .insert(txn, vec![Value::Int64(i), Value::String("lost".into())])
+                .unwrap();
+            let key = IndexKey::Int(i);
+            let body = LogBody::IndexInsert {
+                index_id: index.meta.id,
+                key: key.clone(),
+                rid,
+            };
+            storage.log(txn, body).unwrap();
+            index.index.lock().unwrap().insert(key, rid).unwrap();
+        }
+        assert!(heap.page_ids().len() > pages_before);
+
+        storage.flush().unwrap();
+        txn
+    };
+
+    let mut db = open_db(&path);
+    assert_eq!(row_count(&mut db), 5);
+    db.execute("INSERT INTO t VALUES (5, 'after')").unwrap();
+    drop(db);
+
+    let rows = check_invariants(&path);
+    assert_eq!(
+        rows.keys().copied().collect::<Vec<_>>(),
+        vec![0, 1, 2, 3, 4, 5]
+    );
+
+    let records = WalReader::open(&Database::wal_dir(&path))
+        .unwrap()
+        .records()
+        .unwrap();
+    let clrs = records
+        .iter()
+        .filter(|r| r.txn_id == loser && matches!(r.body, LogBody::Clr { .. }))
+        .count();
+    assert_eq!(clrs, 600);
+    assert!(
+        records
+            .iter()
+            .any(|r| r.txn_id == loser && r.body == LogBody::Abort)
+    );
+}
+
+#[test]
+fn failed_statement_is_rolled_back() {
+    let path = temp_db("rollback");
+    create_schema(&path);
+    let mut db = open_db(&path);
+    db.execute("INSERT INTO t VALUES (1, 'a')").unwrap();
+
+    // The second row cannot be indexed, after the first one already was.
+    assert!(
+        db.execute("INSERT INTO t VALUES (2, 'b'), (NULL, 'c')")
+            .is_err()
+    );
+    assert_eq!(row_count(&mut db), 1);
+
+    db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+    drop(db);
+
+    let rows = check_invariants(&path);
+    assert_eq!(rows.keys().copied().collect::<Vec<_>>(), vec![1, 2]);
+}
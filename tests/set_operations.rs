@@ -0,0 +1,175 @@
+mod common;
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    binder::errors::BindError,
+    types::{datatype::DataType, value::Value},
+};
+
+use common::{int, rows, temp_db};
+
+/// Two overlapping lists of numbers, each with a duplicate and a NULL.
+fn lists(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE a (n BIGINT, tag TEXT)",
+        "CREATE TABLE b (n BIGINT, tag TEXT)",
+        "INSERT INTO a VALUES (1, 'x'), (2, 'x'), (2, 'x'), (3, 'x'), (NULL, 'x')",
+        "INSERT INTO b VALUES (2, 'y'), (3, 'y'), (3, 'y'), (4, 'y'), (NULL, 'y')",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+/// The single column of `sql`'s rows, sorted.
+fn sorted(db: &mut Database, sql: &str) -> Vec<Value> {
+    let mut values: Vec<Value> = rows(db, sql).into_iter().map(|r| r[0].clone()).collect();
+    values.sort_by_key(|v| match v {
+        Value::Int64(n) => *n,
+        _ => i64::MIN,
+    });
+    values
+}
+
+#[test]
+fn set_operations_with_and_without_all() {
+    let mut db = lists("kinds");
+    let null = Value::Null;
+
+    assert_eq!(
+        sorted(&mut db, "SELECT n FROM a UNION SELECT n FROM b"),
+        vec![null.clone(), int(1), int(2), int(3), int(4)]
+    );
+    assert_eq!(
+        rows(&mut db, "SELECT n FROM a UNION ALL SELECT n FROM b").len(),
+        10
+    );
+    // NULLs match each other.
+    assert_eq!(
+        sorted(&mut db, "SELECT n FROM a INTERSECT SELECT n FROM b"),
+        vec![null.clone(), int(2), int(3)]
+    );
+    assert_eq!(
+        sorted(&mut db, "SELECT n FROM b INTERSECT ALL SELECT n FROM a"),
+        vec![null, int(2), int(3)]
+    );
+    assert_eq!(
+        sorted(&mut db, "SELECT n FROM a EXCEPT SELECT n FROM b"),
+        vec![int(1)]
+    );
+    // Each right row cancels one left row.
+    assert_eq!(
+        sorted(
+            &mut db,
+            "SELECT n FROM a EXCEPT ALL SELECT n FROM b WHERE n = 2"
+        ),
+        vec![Value::Null, int(1), int(2), int(3)]
+    );
+    // Numbers that compare equal match, whatever their type or sign.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT n * 0.0 FROM a WHERE n = 1 INTERSECT SELECT -n * 0.0 FROM b WHERE n = 4"
+        ),
+        vec![vec![Value::Float64(0.0)]]
+    );
+    assert!(
+        rows(
+            &mut db,
+            "SELECT n FROM a WHERE n = 2 EXCEPT SELECT n / 2.0 FROM b WHERE n = 4"
+        )
+        .is_empty()
+    );
+}
+
+#[test]
+fn order_by_and_limit_apply_to_the_combined_rows() {
+    let mut db = lists("clauses");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT n FROM a WHERE n > 1 UNION SELECT n FROM b WHERE n > 1 ORDER BY n DESC LIMIT 2"
+        ),
+        vec![vec![int(4)], vec![int(3)]]
+    );
+    // INTERSECT binds tighter than UNION, and parentheses override both.
+    assert_eq!(
+        sorted(
+            &mut db,
+            "SELECT n FROM a WHERE n = 1 UNION SELECT n FROM a INTERSECT SELECT n FROM b \
+             WHERE n = 4"
+        ),
+        vec![int(1)]
+    );
+    assert_eq!(
+        sorted(
+            &mut db,
+            "(SELECT n FROM a WHERE n = 1 UNION SELECT n FROM b WHERE n = 4) INTERSECT \
+             SELECT n FROM b"
+        ),
+        vec![int(4)]
+    );
+    // A parenthesized query keeps its own ORDER BY and LIMIT.
+    assert_eq!(
+        rows(
+            &mut db,
+            "(SELECT n FROM a WHERE n > 0 ORDER BY n LIMIT 1) UNION ALL \
+             (SELECT n FROM b WHERE n > 0 ORDER BY n DESC LIMIT 1) ORDER BY n"
+        ),
+        vec![vec![int(1)], vec![int(4)]]
+    );
+}
+
+#[test]
+fn set_operations_nest_in_other_queries() {
+    let mut db = lists("nested");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT tag FROM a WHERE n IN (SELECT n FROM b EXCEPT SELECT n FROM b WHERE n = 2)"
+        ),
+        vec![vec![Value::String("x".into())]]
+    );
+    // Columns are named after the first query.
+    assert_eq!(
+        rows(
+            &mut db,
+            "WITH both (v) AS (SELECT n FROM a INTERSECT SELECT n FROM b) \
+             SELECT COUNT(v) FROM both"
+        ),
+        vec![vec![int(2)]]
+    );
+    // A recursive WITH whose UNION never reads the CTE is a plain UNION.
+    assert_eq!(
+        rows(
+            &mut db,
+            "WITH RECURSIVE u AS (SELECT n FROM a UNION ALL SELECT n FROM b) \
+             SELECT COUNT(*) FROM u"
+        ),
+        vec![vec![int(10)]]
+    );
+}
+
+#[test]
+fn mismatched_queries_are_rejected() {
+    let mut db = lists("errors");
+
+    assert!(matches!(
+        db.execute("SELECT n FROM a UNION SELECT n, tag FROM b"),
+        Err(DbError::Bind(BindError::SetOpColumnCount(op))) if op == "UNION"
+    ));
+    assert!(matches!(
+        db.execute("SELECT n FROM a EXCEPT SELECT tag FROM b"),
+        Err(DbError::Bind(BindError::SetOpTypeMismatch { op, left: DataType::Int64, right: DataType::Varchar { .. } }))
+            if op == "EXCEPT"
+    ));
+    // ORDER BY only sees the combined columns.
+    assert!(matches!(
+        db.execute("SELECT n FROM a UNION SELECT n FROM b ORDER BY tag"),
+        Err(DbError::Bind(BindError::UnknownColumn(_)))
+    ));
+}
@@ -0,0 +1,224 @@
+mod common;
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    binder::bind_stmt::Binder,
+    frontend::sql::parser::Parser,
+    ir::plan::{JoinType, LogicalPlan},
+    optimizer::optimize,
+    planner::logical::LogicalPlanner,
+    types::value::Value,
+};
+
+use common::{int, rows, temp_db, text};
+
+/// `cid` has no orders, order 14 has an unknown customer and order 15 none.
+fn shop(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE customers (id BIGINT NOT NULL, name TEXT)",
+        "CREATE TABLE orders (order_id BIGINT NOT NULL, customer BIGINT, total BIGINT)",
+        "INSERT INTO customers VALUES (1, 'ann'), (2, 'bob'), (3, 'cid')",
+        "INSERT INTO orders VALUES (10, 1, 50), (11, 2, 20), (12, 1, 30), (14, 9, 5), \
+         (15, NULL, 7)",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+fn optimized(db: &Database, sql: &str) -> LogicalPlan {
+    let catalog = db.catalog();
+    let stmt = Parser::new(sql).parse_statements().unwrap().remove(0);
+    let bound = Binder::new(&catalog).bind_statement(stmt).unwrap();
+    let plan = LogicalPlanner::new().plan(bound).unwrap();
+    optimize(&plan, &catalog).unwrap()
+}
+
+fn join_types(plan: &LogicalPlan, out: &mut Vec<JoinType>) {
+    match plan {
+        LogicalPlan::Join {
+            left,
+            right,
+            join_type,
+            ..
+        }
+        | LogicalPlan::HashJoin {
+            left,
+            right,
+            join_type,
+            ..
+        } => {
+            out.push(*join_type);
+            join_types(left, out);
+            join_types(right, out);
+        }
+        LogicalPlan::IndexJoin {
+            outer, join_type, ..
+        } => {
+            out.push(*join_type);
+            join_types(outer, out);
+        }
+        LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Project { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Limit { input, .. }
+        | LogicalPlan::Aggregate { input, .. } => join_types(input, out),
+        _ => {}
+    }
+}
+
+#[test]
+fn scalar_in_and_exists_subqueries() {
+    let mut db = shop("kinds");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT order_id FROM orders WHERE total > (SELECT total FROM orders \
+             WHERE order_id = 12) ORDER BY order_id"
+        ),
+        vec![vec![int(10)]]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT name FROM customers WHERE id IN (SELECT customer FROM orders \
+             WHERE total > 25) ORDER BY name"
+        ),
+        vec![vec![text("ann")]]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT name FROM customers c WHERE EXISTS (SELECT * FROM orders o \
+             WHERE o.customer = c.id) ORDER BY name"
+        ),
+        vec![vec![text("ann")], vec![text("bob")]]
+    );
+    // An empty scalar subquery is NULL, and one with several rows an error.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT name, (SELECT total FROM orders WHERE order_id = 99) FROM customers \
+             WHERE id = 1"
+        ),
+        vec![vec![text("ann"), Value::Null]]
+    );
+    assert!(matches!(
+        db.execute("SELECT name, (SELECT total FROM orders) FROM customers"),
+        Err(DbError::Execution(_))
+    ));
+}
+
+#[test]
+fn correlated_subqueries_see_the_current_row() {
+    let mut db = shop("correlated");
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT name, (SELECT SUM(total) FROM orders WHERE customer = c.id) \
+             FROM customers c ORDER BY name"
+        ),
+        vec![
+            vec![text("ann"), int(80)],
+            vec![text("bob"), int(20)],
+            vec![text("cid"), Value::Null],
+        ]
+    );
+    // Nested two levels deep, reading the outermost query.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT name FROM customers c WHERE EXISTS (SELECT * FROM orders o \
+             WHERE o.customer = c.id AND o.total > (SELECT MIN(total) FROM orders \
+             WHERE customer = c.id)) ORDER BY name"
+        ),
+        vec![vec![text("ann")]]
+    );
+}
+
+#[test]
+fn not_in_and_not_exists_follow_null_semantics() {
+    let mut db = shop("negated");
+
+    // Order 15 has a NULL customer, so no id is provably NOT IN the list.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT name FROM customers WHERE id NOT IN (SELECT customer FROM orders)"
+        ),
+        Vec::<Vec<Value>>::new()
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT name FROM customers WHERE id NOT IN (SELECT customer FROM orders \
+             WHERE customer < 100)"
+        ),
+        vec![vec![text("cid")]]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT name FROM customers c WHERE NOT EXISTS (SELECT * FROM orders o \
+             WHERE o.customer = c.id)"
+        ),
+        vec![vec![text("cid")]]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT order_id FROM orders WHERE order_id NOT IN (SELECT id FROM customers) \
+             ORDER BY order_id"
+        ),
+        vec![
+            vec![int(10)],
+            vec![int(11)],
+            vec![int(12)],
+            vec![int(14)],
+            vec![int(15)],
+        ]
+    );
+}
+
+#[test]
+fn in_and_exists_subqueries_become_semi_and_anti_joins() {
+    let db = shop("plan");
+    let plan_of = |sql: &str| {
+        let mut types = Vec::new();
+        join_types(&optimized(&db, sql), &mut types);
+        types
+    };
+
+    assert_eq!(
+        plan_of(
+            "SELECT name FROM customers c WHERE EXISTS (SELECT * FROM orders o \
+             WHERE o.customer = c.id AND o.total > 10)"
+        ),
+        vec![JoinType::Semi]
+    );
+    assert_eq!(
+        plan_of("SELECT name FROM customers WHERE id IN (SELECT customer FROM orders)"),
+        vec![JoinType::Semi]
+    );
+    assert_eq!(
+        plan_of(
+            "SELECT name FROM customers c WHERE NOT EXISTS (SELECT * FROM orders o \
+             WHERE o.customer = c.id)"
+        ),
+        vec![JoinType::Anti]
+    );
+    // Both sides NOT NULL, so NOT IN is an anti join too.
+    assert_eq!(
+        plan_of("SELECT total FROM orders WHERE order_id NOT IN (SELECT id FROM customers)"),
+        vec![JoinType::Anti]
+    );
+    // A NULL customer could make NOT IN NULL; it stays a subquery.
+    assert_eq!(
+        plan_of("SELECT name FROM customers WHERE id NOT IN (SELECT customer FROM orders)"),
+        Vec::<JoinType>::new()
+    );
+}
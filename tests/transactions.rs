@@ -0,0 +1,188 @@
+mod common;
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    catalog::catalog::Catalog,
+    execution::errors::ExecutionResult,
+    storage::{
+        buffer::pool::{BufferPool, BufferPoolHandle},
+        heap::heap_table::HeapTable,
+        index::btree::key::IndexKey,
+        pagemgr::file::FilePageManager,
+    },
+    txn::errors::TransactionError,
+    types::value::Value,
+};
+
+use common::temp_db;
+
+fn open_db(path: &PathBuf) -> Database {
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    if db.catalog().get_table_by_name("t").is_none() {
+        db.execute("CREATE TABLE t (id BIGINT, v TEXT)").unwrap();
+        db.execute("CREATE INDEX t_id ON t (id)").unwrap();
+    }
+    db
+}
+
+fn row_count(db: &mut Database) -> usize {
+    match db.execute("SELECT id FROM t").unwrap() {
+        ExecutionResult::Query(q) => q.rows.len(),
+        _ => panic!("expected a query result"),
+    }
+}
+
+/// Read the table directly and check that the index on `id` matches the
+/// heap. Returns the rows as `id -> v`.
+fn contents(path: &PathBuf) -> BTreeMap<i64, String> {
+    let pm = FilePageManager::open(path).unwrap();
+    let bp: BufferPoolHandle = Arc::new(Mutex::new(BufferPool::new(Box::new(pm))));
+    let catalog = Catalog::load(&bp).unwrap();
+    let table = catalog.get_table_by_name("t").unwrap();
+    let heap = HeapTable::open(table.id, table.root_page.unwrap(), bp.clone()).unwrap();
+    let index = catalog
+        .get_index_by_name("t_id")
+        .unwrap()
+        .index
+        .lock()
+        .unwrap();
+
+    let mut rows = BTreeMap::new();
+    for entry in heap.scan() {
+        let (rid, row) = entry.unwrap();
+        let (Value::Int64(id), Value::String(v)) = (&row.values[0], &row.values[1]) else {
+            panic!("unexpected row {:?}", row.values);
+        };
+        rows.insert(*id, v.clone());
+        assert_eq!(index.get(&IndexKey::Int(*id)).unwrap(), vec![rid]);
+    }
+    let indexed = index
+        .range(&IndexKey::Int(i64::MIN), &IndexKey::Int(i64::MAX))
+        .unwrap();
+    assert_eq!(indexed.len(), rows.len());
+    rows
+}
+
+#[test]
+fn rollback_undoes_every_statement_in_the_transaction() {
+    let path = temp_db("rollback");
+    {
+        let mut db = open_db(&path);
+        db.execute("INSERT INTO t VALUES (1, 'a'), (2, 'b')")
+            .unwrap();
+
+        db.execute("BEGIN").unwrap();
+        db.execute("INSERT INTO t VALUES (3, 'c')").unwrap();
+        db.execute("UPDATE t SET v = 'changed'").unwrap();
+        db.execute("DELETE FROM t").unwrap();
+        assert_eq!(row_count(&mut db), 0);
+        db.execute("ROLLBACK").unwrap();
+
+        assert_eq!(row_count(&mut db), 2);
+        assert_eq!(db.transaction_id(), None);
+    }
+
+    let expected = BTreeMap::from([(1, "a".to_string()), (2, "b".to_string())]);
+    assert_eq!(contents(&path), expected);
+}
+
+#[test]
+fn committed_transaction_survives_reopen() {
+    let path = temp_db("commit");
+    {
+        let mut db = open_db(&path);
+        db.execute("BEGIN TRANSACTION").unwrap();
+        db.execute("INSERT INTO t VALUES (1, 'a')").unwrap();
+        db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+        db.execute("COMMIT").unwrap();
+
+        // Left open: the handle below is dropped with the database.
+        db.execute("BEGIN").unwrap();
+        db.execute("INSERT INTO t VALUES (3, 'c')").unwrap();
+    }
+
+    let mut db = open_db(&path);
+    assert_eq!(row_count(&mut db), 2);
+    drop(db);
+    assert_eq!(
+        contents(&path).keys().copied().collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+}
+
+#[test]
+fn rollback_to_savepoint_keeps_earlier_work() {
+    let path = temp_db("savepoint");
+    {
+        let mut db = open_db(&path);
+        db.execute("BEGIN").unwrap();
+        db.execute("INSERT INTO t VALUES (1, 'a')").unwrap();
+        db.execute("SAVEPOINT s1").unwrap();
+        db.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+        db.execute("SAVEPOINT s2").unwrap();
+        db.execute("UPDATE t SET v = 'x'").unwrap();
+
+        db.execute("ROLLBACK TO SAVEPOINT s1").unwrap();
+        assert_eq!(row_count(&mut db), 1);
+
+        // s2 was discarded by rolling back past it; s1 is still usable.
+        assert!(matches!(
+            db.execute("ROLLBACK TO s2"),
+            Err(DbError::Transaction(TransactionError::UnknownSavepoint(_)))
+        ));
+        db.execute("INSERT INTO t VALUES (3, 'c')").unwrap();
+        db.execute("ROLLBACK TO s1").unwrap();
+        db.execute("INSERT INTO t VALUES (4, 'd')").unwrap();
+
+        // A failing statement only undoes itself.
+        assert!(
+            db.execute("INSERT INTO t VALUES (5, 'e'), (NULL, 'f')")
+                .is_err()
+        );
+        db.execute("COMMIT").unwrap();
+    }
+
+    let expected = BTreeMap::from([(1, "a".to_string()), (4, "d".to_string())]);
+    assert_eq!(contents(&path), expected);
+}
+
+#[test]
+fn transaction_handle_rolls_back_unless_committed() {
+    let path = temp_db("handle");
+    let mut db = open_db(&path);
+
+    let mut txn = db.transaction().unwrap();
+    txn.execute("INSERT INTO t VALUES (1, 'a')").unwrap();
+    txn.commit().unwrap();
+
+    {
+        let mut txn = db.transaction().unwrap();
+        txn.execute("INSERT INTO t VALUES (2, 'b')").unwrap();
+        txn.execute("DELETE FROM t").unwrap();
+    }
+    assert_eq!(row_count(&mut db), 1);
+
+    assert!(matches!(
+        db.execute("COMMIT"),
+        Err(DbError::Transaction(TransactionError::NotActive))
+    ));
+    let txn = db.begin().unwrap();
+    assert!(matches!(
+        db.execute("BEGIN"),
+        Err(DbError::Transaction(TransactionError::AlreadyActive(t))) if t == txn
+    ));
+    assert!(matches!(
+        db.execute("CREATE TABLE u (id BIGINT)"),
+        Err(DbError::Transaction(TransactionError::DdlInTransaction))
+    ));
+    db.rollback().unwrap();
+    drop(db);
+
+    assert_eq!(contents(&path), BTreeMap::from([(1, "a".to_string())]));
+}
@@ -0,0 +1,278 @@
+mod common;
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    binder::errors::BindError,
+    execution::errors::ExecutionResult,
+    frontend::sql::errors::ParseError,
+    types::{datatype::DataType, value::Value},
+};
+
+use common::{rows, temp_db};
+
+/// A reading of each sensor, one of them without a payload.
+fn readings(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE readings (id SMALLINT NOT NULL, total BIGINT, level REAL, \
+         ratio DOUBLE PRECISION, code VARCHAR(4), day DATE, seen TIMESTAMP, payload BLOB)",
+        "INSERT INTO readings VALUES \
+         (1, 10, 1.5, 0.25, 'ab', DATE '2026-01-01', TIMESTAMP '2026-01-01 08:30:00', x'00FF'), \
+         (2, 20, 2, 1e-1, 'cd', DATE '2026-02-28', TIMESTAMP '2026-03-01 12:00:00.5', NULL), \
+         (3, 30, -0.5, 2.5, 'ef', DATE '2025-12-31', TIMESTAMP '2025-12-31 23:59:59', x'')",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+fn shown(db: &mut Database, sql: &str) -> Vec<Vec<String>> {
+    rows(db, sql)
+        .iter()
+        .map(|row| row.iter().map(Value::to_string).collect())
+        .collect()
+}
+
+#[test]
+fn columns_store_values_of_their_declared_types() {
+    let mut db = readings("stored");
+
+    let result = match db.execute("SELECT * FROM readings WHERE id = 1").unwrap() {
+        ExecutionResult::Query(q) => q,
+        _ => panic!("expected a query result"),
+    };
+    let types: Vec<DataType> = result
+        .schema
+        .columns
+        .iter()
+        .map(|c| c.data_type.clone())
+        .collect();
+    assert_eq!(
+        types,
+        vec![
+            DataType::Int16,
+            DataType::Int64,
+            DataType::Float32,
+            DataType::Float64,
+            DataType::Varchar { max_len: Some(4) },
+            DataType::Date,
+            DataType::Timestamp,
+            DataType::Blob,
+        ]
+    );
+    assert_eq!(
+        result.rows,
+        vec![vec![
+            Value::Int16(1),
+            Value::Int64(10),
+            Value::Float32(1.5),
+            Value::Float64(0.25),
+            Value::String("ab".into()),
+            Value::Date(20454),
+            Value::Timestamp(20454 * 86_400_000_000 + 30_600_000_000),
+            Value::Blob(vec![0x00, 0xff]),
+        ]]
+    );
+
+    assert_eq!(
+        shown(
+            &mut db,
+            "SELECT id, level, ratio, day, seen, payload FROM readings ORDER BY id"
+        ),
+        vec![
+            vec![
+                "1",
+                "1.5",
+                "0.25",
+                "2026-01-01",
+                "2026-01-01 08:30:00",
+                "\\x00ff"
+            ],
+            vec![
+                "2",
+                "2",
+                "0.1",
+                "2026-02-28",
+                "2026-03-01 12:00:00.5",
+                "NULL"
+            ],
+            vec![
+                "3",
+                "-0.5",
+                "2.5",
+                "2025-12-31",
+                "2025-12-31 23:59:59",
+                "\\x"
+            ],
+        ]
+    );
+}
+
+#[test]
+fn arithmetic_and_comparison_across_types() {
+    let mut db = readings("arithmetic");
+
+    // Mixed numbers are computed in the wider type.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id + id, id + total, level * 2, ratio + level, total / 4 FROM readings \
+             WHERE id = 2"
+        ),
+        vec![vec![
+            Value::Int16(4),
+            Value::Int64(22),
+            Value::Float64(4.0),
+            Value::Float64(2.1),
+            Value::Int64(5),
+        ]]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id FROM readings WHERE level > 1 AND ratio < 0.5 ORDER BY id"
+        ),
+        vec![vec![Value::Int16(1)], vec![Value::Int16(2)]]
+    );
+
+    // Dates move by days, and differ by a number of days.
+    assert_eq!(
+        shown(
+            &mut db,
+            "SELECT day + 1, day - 365, DATE '2026-03-01' - day FROM readings WHERE id = 2"
+        ),
+        vec![vec!["2026-03-01", "2025-02-28", "1"]]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id FROM readings WHERE day < DATE '2026-01-01' \
+             OR seen >= TIMESTAMP '2026-03-01' ORDER BY id"
+        ),
+        vec![vec![Value::Int16(2)], vec![Value::Int16(3)]]
+    );
+    assert_eq!(
+        shown(&mut db, "SELECT code FROM readings ORDER BY level"),
+        vec![vec!["ef"], vec!["ab"], vec!["cd"]]
+    );
+}
+
+#[test]
+fn values_must_fit_their_columns() {
+    let mut db = readings("fit");
+
+    assert!(matches!(
+        db.execute("INSERT INTO readings VALUES (3000000000, 1, 1, 1, 'a', NULL, NULL, NULL)"),
+        Err(DbError::Execution(_))
+    ));
+    assert!(matches!(
+        db.execute("INSERT INTO readings VALUES (4, 1, 1, 1, 'abcde', NULL, NULL, NULL)"),
+        Err(DbError::Execution(_))
+    ));
+    assert!(matches!(
+        db.execute("UPDATE readings SET day = 'tomorrow'"),
+        Err(DbError::Bind(BindError::TypeMismatch { .. }))
+    ));
+    assert!(matches!(
+        db.execute("SELECT id FROM readings WHERE day = 1"),
+        Err(DbError::Bind(BindError::TypeMismatchBinary { .. }))
+    ));
+    assert!(matches!(
+        db.execute("SELECT DATE '2026-02-30' FROM readings"),
+        Err(DbError::Parse(ParseError::InvalidLiteral { .. }))
+    ));
+    assert!(matches!(
+        db.execute("SELECT x'ABC' FROM readings"),
+        Err(DbError::Parse(ParseError::InvalidLiteral { .. }))
+    ));
+    assert!(matches!(
+        db.execute("SELECT total * 9223372036854775807 FROM readings"),
+        Err(DbError::Execution(_))
+    ));
+
+    // A whole float may be stored in an integer column.
+    db.execute("UPDATE readings SET id = id * 1.0 + 3.0, level = 7 WHERE id = 1")
+        .unwrap();
+    assert_eq!(
+        rows(&mut db, "SELECT id, level FROM readings WHERE total = 10"),
+        vec![vec![Value::Int16(4), Value::Float32(7.0)]]
+    );
+}
+
+#[test]
+fn indexes_on_numeric_and_temporal_columns() {
+    let mut db = readings("indexes");
+    db.execute("CREATE INDEX readings_level ON readings (level)")
+        .unwrap();
+    db.execute("CREATE INDEX readings_day ON readings (day)")
+        .unwrap();
+
+    assert_eq!(
+        rows(&mut db, "SELECT id FROM readings WHERE level = 2"),
+        vec![vec![Value::Int16(2)]]
+    );
+    assert_eq!(
+        rows(&mut db, "SELECT id FROM readings WHERE level = -0.5"),
+        vec![vec![Value::Int16(3)]]
+    );
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id FROM readings WHERE day = DATE '2026-01-01'"
+        ),
+        vec![vec![Value::Int16(1)]]
+    );
+
+    // Keys of equal numbers of different types join.
+    db.execute("CREATE TABLE levels (level INT, name TEXT)")
+        .unwrap();
+    db.execute("INSERT INTO levels VALUES (2, 'two'), (3, 'three')")
+        .unwrap();
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT r.id, l.name FROM readings r JOIN levels l ON r.level = l.level"
+        ),
+        vec![vec![Value::Int16(2), Value::String("two".into())]]
+    );
+}
+
+#[test]
+fn integer_columns_hold_only_their_own_range() {
+    let mut db = readings("ranges");
+    db.execute("CREATE TABLE widths (s SMALLINT, i INT)")
+        .unwrap();
+
+    db.execute("INSERT INTO widths VALUES (32767, 2147483647), (-32768, -2147483648)")
+        .unwrap();
+    assert_eq!(
+        rows(&mut db, "SELECT s, i FROM widths ORDER BY s"),
+        vec![
+            vec![Value::Int16(-32768), Value::Int32(-2147483648)],
+            vec![Value::Int16(32767), Value::Int32(2147483647)],
+        ]
+    );
+    for sql in [
+        "INSERT INTO widths VALUES (32768, 0)",
+        "INSERT INTO widths VALUES (-32769, 0)",
+        "INSERT INTO widths VALUES (0, 2147483648)",
+        "UPDATE widths SET s = 70000",
+        "UPDATE widths SET s = s + 1",
+        "UPDATE widths SET i = i + 1",
+        "SELECT CAST(40000 AS SMALLINT) FROM widths",
+        "SELECT CAST(-32769 AS SMALLINT) FROM widths",
+    ] {
+        assert!(
+            matches!(db.execute(sql), Err(DbError::Execution(_))),
+            "{sql} should be out of range"
+        );
+    }
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT CAST(-32768 AS SMALLINT), CAST(32767 AS SMALLINT) FROM readings WHERE id = 1"
+        ),
+        vec![vec![Value::Int16(-32768), Value::Int16(32767)]]
+    );
+}
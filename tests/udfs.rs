@@ -0,0 +1,346 @@
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use helium::{
+    api::{db::Database, errors::DbError},
+    binder::errors::BindError,
+    execution::errors::{ExecutionError, ExecutionResult},
+    functions::{
+        aggregate::AggregateFunction,
+        errors::FunctionError,
+        scalar::{ArgType, ReturnType, Signature, Volatility},
+    },
+    types::{datatype::DataType, value::Value},
+};
+
+use common::{rows, temp_db, text};
+
+const TEXT: DataType = DataType::Varchar { max_len: None };
+
+/// Orders of two customers, one without a note.
+fn orders(name: &str) -> Database {
+    let path = temp_db(name);
+    let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+    for sql in [
+        "CREATE TABLE orders (id BIGINT, customer TEXT, qty BIGINT, price DOUBLE, note TEXT)",
+        "INSERT INTO orders VALUES \
+         (1, 'ann', 2, 10.0, 'gift'), \
+         (2, 'bob', 3, 4.0, NULL), \
+         (3, 'ann', 2, 1.5, 'rush order')",
+    ] {
+        db.execute(sql).unwrap();
+    }
+    db
+}
+
+/// Product of the values of its argument, NULL over no values.
+fn register_product(db: &Database) {
+    db.register_aggregate_fn(
+        "product",
+        Signature::exact(vec![DataType::Float64], DataType::Float64),
+        || None::<f64>,
+        |product, value| {
+            let Value::Float64(v) = value else {
+                return Err(FunctionError::new("expected a float"));
+            };
+            *product = Some(product.unwrap_or(1.0) * v);
+            Ok(())
+        },
+        |product, other| {
+            if let Some(v) = other {
+                *product = Some(product.unwrap_or(1.0) * v);
+            }
+            Ok(())
+        },
+        |product| Ok(product.map_or(Value::Null, Value::Float64)),
+    );
+}
+
+#[test]
+fn registered_scalar_functions_are_called_from_sql() {
+    let mut db = orders("scalar");
+
+    assert!(matches!(
+        db.execute("SELECT twice(qty) FROM orders"),
+        Err(DbError::Bind(BindError::UnknownFunction(_)))
+    ));
+
+    db.register_scalar_fn(
+        "twice",
+        Signature::exact(vec![DataType::Int64], DataType::Int64),
+        Volatility::Deterministic,
+        |args| match &args[0] {
+            Value::Int64(i) => Ok(Value::Int64(i * 2)),
+            v => Err(FunctionError::new(format!("unexpected {}", v))),
+        },
+    );
+    db.register_scalar_fn(
+        "twice",
+        Signature::exact(vec![TEXT], TEXT),
+        Volatility::Deterministic,
+        |args| Ok(text(&args[0].to_string().repeat(2))),
+    );
+    db.register_scalar_fn(
+        "total",
+        Signature::new(vec![ArgType::Numeric, ArgType::Numeric], ReturnType::Common),
+        Volatility::Deterministic,
+        |args| match (&args[0], &args[1]) {
+            (Value::Float64(a), Value::Float64(b)) => Ok(Value::Float64(a * b)),
+            (Value::Int64(a), Value::Int64(b)) => Ok(Value::Int64(a * b)),
+            _ => Err(FunctionError::new("unexpected arguments")),
+        },
+    );
+
+    // Arguments are converted to the types of the version that takes them.
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id, twice(id), TWICE(customer), total(qty, price), twice(note) \
+             FROM orders WHERE twice(qty) > 4 OR id = 1 ORDER BY id"
+        ),
+        vec![
+            vec![
+                Value::Int64(1),
+                Value::Int64(2),
+                text("annann"),
+                Value::Float64(20.0),
+                text("giftgift"),
+            ],
+            vec![
+                Value::Int64(2),
+                Value::Int64(4),
+                text("bobbob"),
+                Value::Float64(12.0),
+                Value::Null,
+            ],
+        ]
+    );
+    assert!(matches!(
+        db.execute("SELECT twice(price) FROM orders"),
+        Err(DbError::Bind(BindError::InvalidFunctionCall { .. }))
+    ));
+
+    // Functions belong to the database, not to the connection.
+    let mut other = db.connect();
+    assert_eq!(
+        rows(&mut other, "SELECT twice(qty) FROM orders WHERE id = 3"),
+        vec![vec![Value::Int64(4)]]
+    );
+}
+
+#[test]
+fn only_deterministic_calls_on_constants_are_made_while_planning() {
+    let mut db = orders("volatility");
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    for (name, volatility) in [
+        ("next_id", Volatility::Deterministic),
+        ("next_id_volatile", Volatility::Volatile),
+    ] {
+        let calls = calls.clone();
+        db.register_scalar_fn(
+            name,
+            Signature::exact(vec![DataType::Int64], DataType::Int64),
+            volatility,
+            move |args| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                match &args[0] {
+                    Value::Int64(i) => Ok(Value::Int64(i + 1)),
+                    v => Err(FunctionError::new(format!("unexpected {}", v))),
+                }
+            },
+        );
+    }
+    let mut count_calls = |sql: &str| {
+        let before = calls.load(Ordering::SeqCst);
+        let result = rows(&mut db, sql);
+        (calls.load(Ordering::SeqCst) - before, result)
+    };
+
+    let (made, result) = count_calls("SELECT next_id(1 + 1) FROM orders");
+    assert_eq!(made, 1);
+    assert_eq!(result, vec![vec![Value::Int64(3)]; 3]);
+
+    let (made, result) = count_calls("SELECT next_id_volatile(1) FROM orders");
+    assert_eq!(made, 3);
+    assert_eq!(result, vec![vec![Value::Int64(2)]; 3]);
+
+    let (made, _) = count_calls("SELECT next_id(qty) FROM orders");
+    assert_eq!(made, 3);
+
+    // NULL arguments make the result NULL without a call.
+    let (made, result) = count_calls("SELECT next_id_volatile(NULL) FROM orders WHERE id = 1");
+    assert_eq!(made, 0);
+    assert_eq!(result, vec![vec![Value::Null]]);
+}
+
+#[test]
+fn registered_aggregates_fold_groups() {
+    let mut db = orders("aggregates");
+    register_product(&db);
+    db.register_aggregate_fn(
+        "longest",
+        Signature::exact(vec![TEXT], TEXT),
+        String::new,
+        |longest, value| {
+            let Value::String(s) = value else {
+                return Err(FunctionError::new("expected text"));
+            };
+            if s.len() > longest.len() {
+                *longest = s.clone();
+            }
+            Ok(())
+        },
+        |longest, other| {
+            if other.len() > longest.len() {
+                *longest = other;
+            }
+            Ok(())
+        },
+        |longest| Ok(Value::String(longest)),
+    );
+
+    let result = match db
+        .execute(
+            "SELECT customer, product(qty), PRODUCT(DISTINCT qty), longest(note) \
+             FROM orders GROUP BY customer HAVING product(price) > 1 ORDER BY customer",
+        )
+        .unwrap()
+    {
+        ExecutionResult::Query(q) => q,
+        _ => panic!("expected a query result"),
+    };
+    let columns: Vec<(String, DataType)> = result
+        .schema
+        .columns
+        .iter()
+        .map(|c| (c.name.clone(), c.data_type.clone()))
+        .collect();
+    assert_eq!(
+        columns[1..],
+        [
+            ("product".to_string(), DataType::Float64),
+            ("product".to_string(), DataType::Float64),
+            ("longest".to_string(), TEXT),
+        ]
+    );
+    assert_eq!(
+        result.rows,
+        vec![
+            vec![
+                text("ann"),
+                Value::Float64(4.0),
+                Value::Float64(2.0),
+                text("rush order"),
+            ],
+            vec![
+                text("bob"),
+                Value::Float64(3.0),
+                Value::Float64(3.0),
+                text("")
+            ],
+        ]
+    );
+
+    // Over no rows the state is finalized as it started.
+    assert_eq!(
+        rows(&mut db, "SELECT product(price) FROM orders WHERE id > 3"),
+        vec![vec![Value::Null]]
+    );
+    assert!(matches!(
+        db.execute("SELECT product(price, qty) FROM orders"),
+        Err(DbError::Bind(BindError::InvalidFunctionCall { .. }))
+    ));
+    assert!(matches!(
+        db.execute("SELECT product(customer) FROM orders"),
+        Err(DbError::Bind(BindError::InvalidFunctionCall { .. }))
+    ));
+
+    // States of two parts of a group combine into that of the whole group.
+    let sum = AggregateFunction::new(
+        "total",
+        Signature::exact(vec![DataType::Int64], DataType::Int64),
+        || 0i64,
+        |sum, value| {
+            *sum += if let Value::Int64(i) = value { *i } else { 0 };
+            Ok(())
+        },
+        |sum, other| {
+            *sum += other;
+            Ok(())
+        },
+        |sum| Ok(Value::Int64(sum)),
+    );
+    let (mut left, mut right) = (sum.init(), sum.init());
+    sum.update(&mut left, &Value::Int64(2)).unwrap();
+    sum.update(&mut right, &Value::Int64(5)).unwrap();
+    sum.merge(&mut left, right).unwrap();
+    assert_eq!(sum.finalize(left), Ok(Value::Int64(7)));
+}
+
+#[test]
+fn failing_functions_report_their_errors() {
+    let mut db = orders("failures");
+    db.register_scalar_fn(
+        "checked",
+        Signature::exact(vec![DataType::Int64], DataType::Int64),
+        Volatility::Deterministic,
+        |args| match &args[0] {
+            Value::Int64(i) if *i >= 0 => Ok(Value::Int64(*i)),
+            _ => Err(FunctionError::new("negative value")),
+        },
+    );
+    db.register_aggregate_fn(
+        "single",
+        Signature::new(vec![ArgType::Any], ReturnType::Common),
+        Vec::new,
+        |values: &mut Vec<Value>, value| {
+            if !values.contains(value) {
+                values.push(value.clone());
+            }
+            Ok(())
+        },
+        |values, other| {
+            values.extend(other);
+            Ok(())
+        },
+        |mut values| match values.len() {
+            0 | 1 => Ok(values.pop().unwrap_or(Value::Null)),
+            _ => Err(FunctionError::new("more than one value")),
+        },
+    );
+
+    match db.execute("SELECT checked(qty - 3) FROM orders") {
+        Err(DbError::Execution(ExecutionError::Function { name, error })) => {
+            assert_eq!(name, "CHECKED");
+            assert_eq!(error.message, "negative value");
+        }
+        other => panic!("expected a function error, got {:?}", other),
+    }
+    // A failing call on constants is only made when a row needs it.
+    assert!(rows(&mut db, "SELECT checked(-1) FROM orders WHERE id > 3").is_empty());
+    assert!(matches!(
+        db.execute("SELECT checked(-1) FROM orders"),
+        Err(DbError::Execution(ExecutionError::Function { .. }))
+    ));
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT customer, single(qty) FROM orders GROUP BY customer ORDER BY customer"
+        ),
+        vec![
+            vec![text("ann"), Value::Int64(2)],
+            vec![text("bob"), Value::Int64(3)],
+        ]
+    );
+    match db.execute("SELECT single(price) FROM orders") {
+        Err(DbError::Execution(ExecutionError::Function { name, error })) => {
+            assert_eq!(name, "SINGLE");
+            assert_eq!(error.message, "more than one value");
+        }
+        other => panic!("expected a function error, got {:?}", other),
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use helium::{
+    api::db::Database,
+    catalog::ids::TableId,
+    storage::{
+        buffer::pool::{BufferPool, BufferPoolHandle},
+        errors::StorageError,
+        heap::heap_table::HeapTable,
+        page::{page_id::PageId, row_id::RowId},
+        pagemgr::file::FilePageManager,
+    },
+    txn::{
+        transaction::TxnId,
+        wal::{
+            reader::{WalReader, list_segments},
+            record::{LogBody, LogRecord, Lsn},
+            writer::WalWriter,
+        },
+    },
+    types::value::Value,
+};
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("helium_wal_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_dir_all(&path);
+    let _ = std::fs::remove_dir_all(Database::wal_dir(&path));
+    path
+}
+
+fn read_all(dir: &PathBuf) -> Vec<LogRecord> {
+    WalReader::open(dir).unwrap().records().unwrap()
+}
+
+#[test]
+fn records_round_trip_across_segments_and_reopen() {
+    let dir = temp_path("segments");
+
+    let (t1, t2) = {
+        let mut wal = WalWriter::open_with_segment_size(&dir, 512).unwrap();
+        let t1 = wal.begin().unwrap();
+        let t2 = wal.begin().unwrap();
+        for i in 0..40 {
+            let txn = if i % 2 == 0 { t1 } else { t2 };
+            wal.append(
+                txn,
+                LogBody::HeapInsert {
+                    table_id: TableId(1),
+                    rid: RowId {
+                        page_id: PageId(3),
+                        slot_id: i,
+                    },
+                    values: vec![Value::Int64(i as i64), Value::String("x".repeat(20))],
+                    version: None,
+                },
+            )
+            .unwrap();
+        }
+        wal.commit(t1).unwrap();
+        wal.abort(t2).unwrap();
+        wal.flush().unwrap();
+        (t1, t2)
+    };
+
+    assert!(list_segments(&dir).unwrap().len() > 1);
+
+    let records = read_all(&dir);
+    assert_eq!(records.len(), 44);
+    for (i, rec) in records.iter().enumerate() {
+        assert_eq!(rec.lsn, Lsn(i as u64 + 1));
+    }
+
+    // prev_lsn links each transaction's records back to its Begin.
+    let commit = records.iter().find(|r| r.body == LogBody::Commit).unwrap();
+    assert_eq!(commit.txn_id, t1);
+    let mut lsn = commit.prev_lsn;
+    let mut chain = 0;
+    while lsn != Lsn::INVALID {
+        let rec = &records[lsn.0 as usize - 1];
+        assert_eq!(rec.txn_id, t1);
+        lsn = rec.prev_lsn;
+        chain += 1;
+    }
+    assert_eq!(chain, 21);
+
+    // LSNs and transaction ids continue after reopening.
+    let mut wal = WalWriter::open_with_segment_size(&dir, 512).unwrap();
+    assert_eq!(wal.durable_lsn(), Lsn(44));
+    let t3 = wal.begin().unwrap();
+    assert!(t3 > t2);
+    assert_eq!(wal.last_lsn(), Lsn(45));
+}
+
+#[test]
+fn torn_tail_is_discarded_on_open() {
+    let dir = temp_path("torn");
+    {
+        let mut wal = WalWriter::open(&dir).unwrap();
+        let txn = wal.begin().unwrap();
+        wal.commit(txn).unwrap();
+    }
+
+    // Simulate a crash in the middle of writing the next record.
+    let (_, segment) = list_segments(&dir).unwrap().pop().unwrap();
+    let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+    file.write_all(&[40, 0, 0, 0, 1, 2, 3, 4, 9, 9]).unwrap();
+    drop(file);
+
+    assert_eq!(read_all(&dir).len(), 2);
+
+    let mut wal = WalWriter::open(&dir).unwrap();
+    assert_eq!(wal.last_lsn(), Lsn(2));
+    let txn = wal.begin().unwrap();
+    wal.commit(txn).unwrap();
+    drop(wal);
+
+    let records = read_all(&dir);
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[3].body, LogBody::Commit);
+}
+
+#[test]
+fn pool_refuses_to_flush_pages_ahead_of_the_log() {
+    let path = temp_path("protocol");
+    let wal = Arc::new(Mutex::new(
+        WalWriter::open(&Database::wal_dir(&path)).unwrap(),
+    ));
+    let mut pool = BufferPool::new(Box::new(FilePageManager::open(&path).unwrap()));
+    pool.attach_wal(wal.clone());
+    let bp: BufferPoolHandle = Arc::new(Mutex::new(pool));
+
+    let heap = HeapTable::create(TableId(1), bp.clone()).unwrap();
+    let txn = wal.lock().unwrap().begin().unwrap();
+    heap.insert(txn, vec![Value::Int64(7)]).unwrap();
+
+    let err = bp.lock().unwrap().flush_all().unwrap_err();
+    assert!(matches!(err, StorageError::WalNotDurable { .. }));
+
+    wal.lock().unwrap().commit(txn).unwrap();
+    bp.lock().unwrap().flush_all().unwrap();
+}
+
+#[test]
+fn statements_are_logged_as_committed_transactions() {
+    let path = temp_path("statements");
+    {
+        let mut db = Database::new(path.to_string_lossy().into()).unwrap();
+        db.execute("CREATE TABLE users (id INT, name TEXT)")
+            .unwrap();
+        db.execute("INSERT INTO users VALUES (1, 'alice')").unwrap();
+        db.execute("UPDATE users SET name = 'bob'").unwrap();
+        db.execute("DELETE FROM users").unwrap();
+
+        let records = read_all(&Database::wal_dir(&path));
+        let bodies: Vec<_> = records
+            .iter()
+            .filter(|r| r.txn_id != TxnId::NONE)
+            .map(|r| &r.body)
+            .collect();
+
+        assert!(matches!(bodies[0], LogBody::Begin));
+        assert!(matches!(bodies[1], LogBody::HeapInsert { .. }));
+        assert!(matches!(bodies[2], LogBody::Commit));
+        assert!(matches!(bodies[3], LogBody::Begin));
+        match bodies[4] {
+            LogBody::HeapUpdate {
+                before,
+                after,
+                version,
+                ..
+            } => {
+                assert_eq!(before[1], Value::String("alice".into()));
+                assert_eq!(after[1], Value::String("bob".into()));
+                // Written by an earlier transaction, the old row is kept.
+                assert!(version.is_some());
+            }
+            other => panic!("expected an update record, got {other:?}"),
+        }
+        assert!(matches!(bodies[5], LogBody::Commit));
+        match bodies[7] {
+            LogBody::HeapDelete { values, .. } => {
+                assert_eq!(values[1], Value::String("bob".into()));
+            }
+            other => panic!("expected a delete record, got {other:?}"),
+        }
+        assert!(matches!(bodies[8], LogBody::Commit));
+    }
+
+    // A clean shutdown ends the log with a checkpoint.
+    let records = read_all(&Database::wal_dir(&path));
+    assert!(matches!(
+        records.last().unwrap().body,
+        LogBody::Checkpoint { .. }
+    ));
+}